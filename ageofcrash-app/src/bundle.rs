@@ -0,0 +1,562 @@
+//! Profile bundles: the effective config plus the sound files it plays, in a
+//! single file to pass on. `ageofcrash export` writes one and
+//! `ageofcrash import` unpacks it next to config.ron.
+//!
+//! A bundle is one `.ron` text file with the sounds embedded as base64.
+
+use crate::config::{write_atomically, AudioOption, Config};
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Folder the sounds go in, inside a bundle and next to an imported config
+const SOUND_DIR: &str = "sounds";
+
+/// A config and the sound files it plays, keyed by their path relative to the
+/// config (`sounds/hit.wav`), which is how the config refers to them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bundle {
+    pub config: Config,
+    pub sounds: BTreeMap<String, SoundData>,
+}
+
+/// A sound file's contents; written to RON as base64 rather than a list of
+/// numbers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoundData(pub Vec<u8>);
+
+impl Serialize for SoundData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SoundData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl Visitor<'_> for BytesVisitor {
+            type Value = SoundData;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("the bytes of a sound file")
+            }
+
+            fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<SoundData, E> {
+                Ok(SoundData(bytes.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<SoundData, E> {
+                Ok(SoundData(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+/// Checks that `path` names a bundle, i.e. ends in `.ron`
+fn check_bundle_path(path: &Path) -> Result<(), String> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    if extension.as_deref() == Some("ron") {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} must end in .ron to be a bundle",
+            path.display()
+        ))
+    }
+}
+
+impl Bundle {
+    /// The bundle for `config`, whose sound paths are already resolved: each
+    /// sound file is read in and its path rewritten to where the bundle keeps
+    /// it. A sound that can't be read is left out with a warning, and its cue
+    /// plays nothing.
+    pub fn from_config(config: &Config) -> (Self, Vec<String>) {
+        let mut config = config.clone();
+        let mut sounds = BTreeMap::new();
+        let mut warnings = Vec::new();
+        // Source path to bundled name, so a file both cues play is bundled once
+        let mut bundled: BTreeMap<String, String> = BTreeMap::new();
+
+        let audio = &mut config.barrier.audio_feedback;
+        for (cue, option) in [
+            ("on_barrier_hit", &mut audio.on_barrier_hit),
+            ("on_barrier_entry", &mut audio.on_barrier_entry),
+        ] {
+            let AudioOption::File(path) = option else {
+                continue;
+            };
+            if let Some(name) = bundled.get(path.as_str()) {
+                *option = AudioOption::File(name.clone());
+                continue;
+            }
+            match std::fs::read(&*path) {
+                Ok(bytes) => {
+                    let name = bundled_sound_name(Path::new(path.as_str()), &sounds);
+                    sounds.insert(name.clone(), SoundData(bytes));
+                    bundled.insert(path.clone(), name.clone());
+                    *option = AudioOption::File(name);
+                }
+                Err(e) => {
+                    warnings.push(format!(
+                        "Left out {} for {} ({}); it plays nothing in the bundle",
+                        path, cue, e
+                    ));
+                    *option = AudioOption::None;
+                }
+            }
+        }
+
+        (Self { config, sounds }, warnings)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let pretty = ron::ser::PrettyConfig::default();
+        Ok(ron::ser::to_string_pretty(self, pretty)?.into_bytes())
+    }
+
+    /// Reads a bundle written with [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(ron::from_str(std::str::from_utf8(bytes)?)?)
+    }
+
+    /// Drops sounds whose name would put them anywhere but directly in the
+    /// sounds folder, and makes every cue whose sound the bundle lacks play
+    /// nothing, with a warning for each
+    fn check_sounds(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        self.sounds.retain(|name, _| {
+            let safe = is_safe_sound_name(name);
+            if !safe {
+                warnings.push(format!(
+                    "Ignored {:?} in the bundle; sounds must sit directly in {}/",
+                    name, SOUND_DIR
+                ));
+            }
+            safe
+        });
+
+        let sounds = &self.sounds;
+        let audio = &mut self.config.barrier.audio_feedback;
+        for (cue, option) in [
+            ("on_barrier_hit", &mut audio.on_barrier_hit),
+            ("on_barrier_entry", &mut audio.on_barrier_entry),
+        ] {
+            if let AudioOption::File(path) = option {
+                if !sounds.contains_key(path.as_str()) {
+                    warnings.push(format!(
+                        "The bundle doesn't include {} for {}; it plays nothing",
+                        path, cue
+                    ));
+                    *option = AudioOption::None;
+                }
+            }
+        }
+        warnings
+    }
+}
+
+/// Where a bundle keeps the sound file at `path`: in the sounds folder under
+/// its own file name, numbered if a sound already `taken` has that name
+fn bundled_sound_name(path: &Path, taken: &BTreeMap<String, SoundData>) -> String {
+    let stem = path
+        .file_stem()
+        .map_or("sound".into(), |stem| stem.to_string_lossy());
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut name = format!("{}/{}{}", SOUND_DIR, stem, extension);
+    let mut number = 2;
+    while taken.contains_key(&name) {
+        name = format!("{}/{}-{}{}", SOUND_DIR, stem, number, extension);
+        number += 1;
+    }
+    name
+}
+
+/// Whether `name` is a file directly in the sounds folder, so unpacking it
+/// can't write anywhere else
+fn is_safe_sound_name(name: &str) -> bool {
+    name.strip_prefix(SOUND_DIR)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .is_some_and(|file| {
+            !file.is_empty() && file != "." && file != ".." && !file.contains(['/', '\\', ':'])
+        })
+}
+
+/// Writes the bundle for `config` to `path`, which must end in `.ron`.
+/// Returns a warning for each sound left out.
+pub fn export(config: &Config, path: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    check_bundle_path(path)?;
+    let (bundle, warnings) = Bundle::from_config(config);
+    write_atomically(path, bundle.to_bytes()?)?;
+    Ok(warnings)
+}
+
+/// Unpacks the bundle at `path` next to the config at `config_path`: its
+/// config replaces that one and its sounds go in a sounds folder beside it.
+///
+/// The config is validated before anything is written. `confirm` is asked
+/// before existing files are overwritten, and declining leaves everything as
+/// it was. Everything is unpacked into a staging folder beside the config
+/// first, and only renamed into place once the staged config loads, so a
+/// failure replaces nothing. Returns a warning for each part of the bundle
+/// left out; a missing sound makes its cue play nothing rather than failing
+/// the import.
+pub fn import(
+    path: &Path,
+    config_path: &Path,
+    confirm: impl FnOnce(&[PathBuf]) -> bool,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    check_bundle_path(path)?;
+    let mut bundle = Bundle::from_bytes(&std::fs::read(path)?)?;
+    let warnings = bundle.check_sounds();
+    bundle.config.validate()?;
+
+    let dir = config_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let config_name = config_path
+        .file_name()
+        .ok_or("The config path has no file name")?;
+    let config = ron::ser::to_string_pretty(&bundle.config, ron::ser::PrettyConfig::default())?;
+    // Paths relative to the config's folder; the config goes in last, so it
+    // never names a sound that isn't in place yet
+    let mut files: Vec<(PathBuf, Vec<u8>)> = bundle
+        .sounds
+        .into_iter()
+        .map(|(name, sound)| (PathBuf::from(name), sound.0))
+        .collect();
+    files.push((PathBuf::from(config_name), config.into_bytes()));
+
+    let existing: Vec<PathBuf> = files
+        .iter()
+        .map(|(name, _)| dir.join(name))
+        .filter(|target| target.exists())
+        .collect();
+    if !existing.is_empty() && !confirm(&existing) {
+        return Err("Import cancelled; nothing was changed".into());
+    }
+
+    // Beside the config, so the renames stay on one volume
+    let staging = dir.join(format!(".ageofcrash-import-{}", std::process::id()));
+    let result = stage(&staging, &files)
+        .and_then(|()| {
+            Config::load_from_file(staging.join(config_name))
+                .map_err(|e| format!("The imported config doesn't load: {}", e).into())
+        })
+        .and_then(|_| {
+            for (name, _) in &files {
+                let target = dir.join(name);
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::rename(staging.join(name), target)?;
+            }
+            Ok(())
+        });
+    let _ = std::fs::remove_dir_all(&staging);
+    result.map(|()| warnings)
+}
+
+/// Writes `files`, named relative to `staging`, into a fresh `staging` folder
+fn stage(staging: &Path, files: &[(PathBuf, Vec<u8>)]) -> Result<(), Box<dyn std::error::Error>> {
+    // Left over from an import that was killed partway
+    if staging.exists() {
+        std::fs::remove_dir_all(staging)?;
+    }
+    for (name, contents) in files {
+        let path = staging.join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+/// `ageofcrash export <file>`
+pub fn run_export(config: &Config, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    for warning in export(config, path)? {
+        println!("Warning: {}", warning);
+    }
+    println!("Exported the config and its sounds to {}", path.display());
+    Ok(())
+}
+
+/// `ageofcrash import <file>`
+pub fn run_import(path: &Path, config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    for warning in import(path, config_path, ask_to_overwrite)? {
+        println!("Warning: {}", warning);
+    }
+    println!("Imported {} into {}", path.display(), config_path.display());
+    Ok(())
+}
+
+/// Lists the files an import would overwrite and asks whether to go ahead
+fn ask_to_overwrite(existing: &[PathBuf]) -> bool {
+    println!("Importing overwrites:");
+    for path in existing {
+        println!("  {}", path.display());
+    }
+    print!("Overwrite them? [y/N] ");
+    let _ = io::stdout().flush();
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// The default config playing `hit` on hits and `entry` on entries
+    fn config_with_sounds(hit: AudioOption, entry: AudioOption) -> Config {
+        let mut config = Config::default();
+        config.barrier.audio_feedback.on_barrier_hit = hit;
+        config.barrier.audio_feedback.on_barrier_entry = entry;
+        config
+    }
+
+    fn file(path: &Path) -> AudioOption {
+        AudioOption::File(path.to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn test_bundles_must_end_in_ron() {
+        assert_eq!(check_bundle_path(Path::new("clan.ron")), Ok(()));
+        assert_eq!(check_bundle_path(Path::new(r"C:\setups\Clan.RON")), Ok(()));
+        assert!(check_bundle_path(Path::new("clan.zip")).is_err());
+        assert!(check_bundle_path(Path::new("clan")).is_err());
+    }
+
+    #[test]
+    fn test_bundled_sound_names_are_relative_and_unique() {
+        let mut taken = BTreeMap::new();
+        let name = bundled_sound_name(Path::new("hit.wav"), &taken);
+        assert_eq!(name, "sounds/hit.wav");
+
+        taken.insert(name, SoundData(Vec::new()));
+        assert_eq!(
+            bundled_sound_name(Path::new("other/hit.wav"), &taken),
+            "sounds/hit-2.wav"
+        );
+        assert_eq!(bundled_sound_name(Path::new("beep"), &taken), "sounds/beep");
+    }
+
+    #[test]
+    fn test_only_names_inside_the_sound_folder_are_safe() {
+        assert!(is_safe_sound_name("sounds/hit.wav"));
+        for name in [
+            "sounds/",
+            "sounds/..",
+            "sounds/../config.ron",
+            "sounds/nested/hit.wav",
+            r"sounds/..\hit.wav",
+            "sounds/C:hit.wav",
+            "soundshit.wav",
+            "hit.wav",
+        ] {
+            assert!(!is_safe_sound_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_export_rewrites_sound_paths() {
+        let dir = TempDir::new().unwrap();
+        let hit = dir.path().join("hit.wav");
+        std::fs::write(&hit, b"RIFF hit").unwrap();
+
+        // Both cues play the same file, which is bundled once
+        let (bundle, warnings) = Bundle::from_config(&config_with_sounds(file(&hit), file(&hit)));
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let audio = &bundle.config.barrier.audio_feedback;
+        assert_eq!(
+            audio.on_barrier_hit,
+            AudioOption::File("sounds/hit.wav".to_string())
+        );
+        assert_eq!(audio.on_barrier_entry, audio.on_barrier_hit);
+        assert_eq!(
+            bundle.sounds,
+            BTreeMap::from([(
+                "sounds/hit.wav".to_string(),
+                SoundData(b"RIFF hit".to_vec())
+            )])
+        );
+    }
+
+    #[test]
+    fn test_export_leaves_out_missing_sounds() {
+        let dir = TempDir::new().unwrap();
+        let missing = dir.path().join("missing.wav");
+        let (bundle, warnings) = Bundle::from_config(&config_with_sounds(
+            file(&missing),
+            AudioOption::Builtin("chime".to_string()),
+        ));
+
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("on_barrier_hit"), "{}", warnings[0]);
+        let audio = &bundle.config.barrier.audio_feedback;
+        assert_eq!(audio.on_barrier_hit, AudioOption::None);
+        assert_eq!(
+            audio.on_barrier_entry,
+            AudioOption::Builtin("chime".to_string())
+        );
+        assert!(bundle.sounds.is_empty());
+    }
+
+    #[test]
+    fn test_bundles_round_trip() {
+        let mut config = config_with_sounds(
+            AudioOption::File("sounds/hit.wav".to_string()),
+            AudioOption::File("sounds/enter.wav".to_string()),
+        );
+        config.barrier.x = 321;
+        let bundle = Bundle {
+            config,
+            sounds: BTreeMap::from([
+                ("sounds/hit.wav".to_string(), SoundData(vec![0, 1, 2, 255])),
+                ("sounds/enter.wav".to_string(), SoundData(Vec::new())),
+            ]),
+        };
+
+        let restored = Bundle::from_bytes(&bundle.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.sounds, bundle.sounds);
+        assert_eq!(restored.config.barrier.x, 321);
+        assert_eq!(
+            restored.config.barrier.audio_feedback.on_barrier_hit,
+            bundle.config.barrier.audio_feedback.on_barrier_hit
+        );
+    }
+
+    #[test]
+    fn test_import_unpacks_next_to_the_config() {
+        let source = TempDir::new().unwrap();
+        let hit = source.path().join("hit.wav");
+        std::fs::write(&hit, b"RIFF hit").unwrap();
+        let bundle_path = source.path().join("clan.ron");
+        export(
+            &config_with_sounds(file(&hit), AudioOption::None),
+            &bundle_path,
+        )
+        .unwrap();
+
+        let target = TempDir::new().unwrap();
+        let config_path = target.path().join("config.ron");
+        let warnings = import(&bundle_path, &config_path, |_| {
+            panic!("nothing to overwrite yet")
+        })
+        .unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(
+            std::fs::read(target.path().join("sounds").join("hit.wav")).unwrap(),
+            b"RIFF hit"
+        );
+
+        // The sound resolves against the imported config's folder
+        let imported = Config::load_from_file(&config_path).unwrap();
+        let AudioOption::File(path) = imported.barrier.audio_feedback.on_barrier_hit else {
+            panic!("the hit sound was dropped");
+        };
+        assert!(Path::new(&path).ends_with(Path::new("sounds").join("hit.wav")));
+
+        // Importing again asks first, and declining changes nothing
+        std::fs::write(&config_path, "edited").unwrap();
+        let mut asked = Vec::new();
+        assert!(import(&bundle_path, &config_path, |existing| {
+            asked = existing.to_vec();
+            false
+        })
+        .is_err());
+        assert_eq!(asked.len(), 2);
+        assert_eq!(std::fs::read_to_string(&config_path).unwrap(), "edited");
+
+        assert!(import(&bundle_path, &config_path, |_| true).is_ok());
+        assert!(Config::load_from_file(&config_path).is_ok());
+        // The staging folder is gone
+        assert_eq!(std::fs::read_dir(target.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_failed_import_replaces_nothing() {
+        let source = TempDir::new().unwrap();
+        let hit = source.path().join("hit.wav");
+        std::fs::write(&hit, b"RIFF hit").unwrap();
+        let bundle_path = source.path().join("clan.ron");
+        export(
+            &config_with_sounds(file(&hit), AudioOption::None),
+            &bundle_path,
+        )
+        .unwrap();
+
+        // A folder where the sound should go can't be replaced by a file
+        let target = TempDir::new().unwrap();
+        let config_path = target.path().join("config.ron");
+        std::fs::write(&config_path, "edited").unwrap();
+        std::fs::create_dir_all(target.path().join("sounds").join("hit.wav")).unwrap();
+
+        assert!(import(&bundle_path, &config_path, |_| true).is_err());
+        assert_eq!(std::fs::read_to_string(&config_path).unwrap(), "edited");
+        let left: Vec<_> = std::fs::read_dir(target.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(left.len(), 2, "staging folder left behind: {:?}", left);
+    }
+
+    #[test]
+    fn test_export_replaces_the_file_whole() {
+        let dir = TempDir::new().unwrap();
+        let bundle_path = dir.path().join("clan.ron");
+        std::fs::write(&bundle_path, "an older bundle").unwrap();
+
+        export(&Config::default(), &bundle_path).unwrap();
+        let bundle = Bundle::from_bytes(&std::fs::read(&bundle_path).unwrap()).unwrap();
+        assert_eq!(bundle.config.barrier.x, Config::default().barrier.x);
+        // No temporary file is left beside it
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_import_without_its_sounds_plays_nothing() {
+        let source = TempDir::new().unwrap();
+        let bundle = Bundle {
+            config: config_with_sounds(
+                AudioOption::File("sounds/hit.wav".to_string()),
+                AudioOption::File("sounds/enter.wav".to_string()),
+            ),
+            sounds: BTreeMap::from([
+                ("sounds/enter.wav".to_string(), SoundData(b"RIFF".to_vec())),
+                (
+                    "sounds/../evil.wav".to_string(),
+                    SoundData(b"RIFF".to_vec()),
+                ),
+            ]),
+        };
+        let bundle_path = source.path().join("clan.ron");
+        std::fs::write(&bundle_path, bundle.to_bytes().unwrap()).unwrap();
+
+        let target = TempDir::new().unwrap();
+        let config_path = target.path().join("config.ron");
+        let warnings = import(&bundle_path, &config_path, |_| true).unwrap();
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(!target.path().join("evil.wav").exists());
+
+        let imported = Config::load_from_file(&config_path).unwrap();
+        let audio = imported.barrier.audio_feedback;
+        assert_eq!(audio.on_barrier_hit, AudioOption::None);
+        assert!(matches!(audio.on_barrier_entry, AudioOption::File(_)));
+    }
+}
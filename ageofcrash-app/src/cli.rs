@@ -0,0 +1,156 @@
+use std::path::PathBuf;
+
+const DEBUG_DUMP_FLAG: &str = "--debug-dump";
+const SIMULATE_FLAG: &str = "--simulate";
+const DOCTOR_COMMAND: &str = "doctor";
+const MEASURE_COMMAND: &str = "measure";
+const EXPORT_COMMAND: &str = "export";
+const IMPORT_COMMAND: &str = "import";
+const CONFIG_CACHE_FLAG: &str = "--config-cache";
+pub const RELAUNCH_ELEVATED_FLAG: &str = "--relaunch-elevated";
+
+/// Command line options
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Args {
+    /// `--debug-dump <file>`: CSV file to mirror every barrier decision to
+    pub debug_dump: Option<PathBuf>,
+    /// `--simulate <script>`: replay a script against the barrier without
+    /// installing hooks, then exit
+    pub simulate: Option<PathBuf>,
+    /// `doctor`: check the environment for common problems, then exit
+    pub doctor: bool,
+    /// `measure`: print the cursor position at each click and propose a
+    /// barrier from two of them, then exit
+    pub measure: bool,
+    /// `export <file>`: bundle the config and its sound files into a `.ron`
+    /// file, then exit
+    pub export: Option<PathBuf>,
+    /// `import <file>`: unpack a bundle next to the config, replacing it, then
+    /// exit
+    pub import: Option<PathBuf>,
+    /// `--relaunch-elevated`: restart as administrator unless already running as one
+    pub relaunch_elevated: bool,
+    /// `--config-cache`: load the config through a binary cache kept next to
+    /// it, for faster startup
+    pub config_cache: bool,
+}
+
+impl Args {
+    /// Parses the command line arguments (without the program name)
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let mut parsed = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                DOCTOR_COMMAND => {
+                    parsed.doctor = true;
+                    continue;
+                }
+                MEASURE_COMMAND => {
+                    parsed.measure = true;
+                    continue;
+                }
+                RELAUNCH_ELEVATED_FLAG => {
+                    parsed.relaunch_elevated = true;
+                    continue;
+                }
+                CONFIG_CACHE_FLAG => {
+                    parsed.config_cache = true;
+                    continue;
+                }
+                _ => {}
+            }
+            let slot = match arg.as_str() {
+                DEBUG_DUMP_FLAG => &mut parsed.debug_dump,
+                SIMULATE_FLAG => &mut parsed.simulate,
+                EXPORT_COMMAND => &mut parsed.export,
+                IMPORT_COMMAND => &mut parsed.import,
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            };
+            let file = args
+                .next()
+                .ok_or_else(|| format!("{} needs a file path", arg))?;
+            *slot = Some(PathBuf::from(file));
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Args::parse(args(&[])), Ok(Args::default()));
+        assert_eq!(
+            Args::parse(args(&[
+                "--debug-dump",
+                "decisions.csv",
+                "--simulate",
+                "trace.csv"
+            ])),
+            Ok(Args {
+                debug_dump: Some(PathBuf::from("decisions.csv")),
+                simulate: Some(PathBuf::from("trace.csv")),
+                doctor: false,
+                measure: false,
+                export: None,
+                import: None,
+                relaunch_elevated: false,
+                config_cache: false,
+            })
+        );
+        assert_eq!(
+            Args::parse(args(&["doctor"])),
+            Ok(Args {
+                doctor: true,
+                ..Args::default()
+            })
+        );
+        assert_eq!(
+            Args::parse(args(&["measure"])),
+            Ok(Args {
+                measure: true,
+                ..Args::default()
+            })
+        );
+        assert_eq!(
+            Args::parse(args(&["--relaunch-elevated", "--simulate", "trace.csv"])),
+            Ok(Args {
+                simulate: Some(PathBuf::from("trace.csv")),
+                relaunch_elevated: true,
+                ..Args::default()
+            })
+        );
+        assert_eq!(
+            Args::parse(args(&["--config-cache"])),
+            Ok(Args {
+                config_cache: true,
+                ..Args::default()
+            })
+        );
+        assert_eq!(
+            Args::parse(args(&["export", "clan.ron"])),
+            Ok(Args {
+                export: Some(PathBuf::from("clan.ron")),
+                ..Args::default()
+            })
+        );
+        assert_eq!(
+            Args::parse(args(&["import", "clan.ron"])),
+            Ok(Args {
+                import: Some(PathBuf::from("clan.ron")),
+                ..Args::default()
+            })
+        );
+        assert!(Args::parse(args(&["--debug-dump"])).is_err());
+        assert!(Args::parse(args(&["--simulate"])).is_err());
+        assert!(Args::parse(args(&["export"])).is_err());
+        assert!(Args::parse(args(&["--verbose"])).is_err());
+    }
+}
@@ -1,14 +1,41 @@
 use figment::{providers::Serialized, Figment, Profile};
+use mouse_barrier::{BuiltinSound, SnapGrid, Sound};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
 use std::sync::OnceLock;
-use tracing::info;
+use tracing::{info, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub hotkey: HotkeyConfig,
+    #[serde(default)]
+    pub action_hotkeys: Vec<ActionHotkeyConfig>, // Further hotkeys, each bound to one action
+    #[serde(default)]
+    pub chords: Option<ChordConfig>, // A leader hotkey followed by a single key, e.g. Ctrl+M then B
+    #[serde(default)]
+    pub gamepad: Option<GamepadConfig>, // Controller buttons bound to actions and the bypass
     pub barrier: BarrierConfig,
     pub hud: HudConfig,
     pub debug: bool,
+    #[serde(default)]
+    pub debug_trail: bool, // With debug, draw the recent cursor trail and pushes on screen
+    #[serde(default)]
+    pub debug_overlay: bool, // With debug, draw the barrier, buffer zone and last move as the hook sees them
+    #[serde(default = "default_startup_grace_ms")]
+    pub startup_grace_ms: u32, // Config edits this soon after startup wait until it has passed; 0 disables
+    #[serde(default)]
+    pub colorblind_safe: bool, // Orange and blue instead of the default red and green
+    #[serde(default)]
+    pub push_marker: PushMarkerConfig, // Flash a marker where the barrier pushed the cursor
+    #[serde(default)]
+    pub notify_on_toggle: bool, // Show a brief notification when the barrier is enabled or disabled
+    #[serde(skip)]
+    pub missing_sounds: Vec<String>, // Sound files named in the file but not found, as written; they play nothing
+}
+
+fn default_startup_grace_ms() -> u32 {
+    2000
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -17,67 +44,919 @@ pub struct HotkeyConfig {
     pub alt: bool,
     pub shift: bool,
     pub key: String,
+    #[serde(default = "default_toggle_cooldown_ms")]
+    pub toggle_cooldown_ms: u32, // Presses this soon after a toggle are ignored; 0 disables
+}
+
+fn default_toggle_cooldown_ms() -> u32 {
+    150
+}
+
+/// What an action hotkey does to the barrier
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HotkeyAction {
+    ToggleBarrier,     // Same as the main hotkey, sharing its cooldown
+    EnableBarrier,     // Turns the barrier on; does nothing if it already is
+    DisableBarrier,    // Turns the barrier off; does nothing if it already is
+    ToggleEnforcement, // Switches enforcement_mode between Enforce and WarnOnly
+    ToggleHud,         // Shows or hides the HUD
+    Pause,             // Suspends the barrier until pressed again, leaving it enabled
+    EmergencyDisable,  // Removes the barrier and keyboard hook until restart, at once
+}
+
+/// A hotkey bound to an action, e.g.
+/// `(action: EnableBarrier, hotkey: (ctrl: true, alt: false, shift: false, key: "F11"))`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ActionHotkeyConfig {
+    pub action: HotkeyAction,
+    pub hotkey: HotkeyConfig,
+}
+
+/// Two-key sequences: the leader hotkey, then within `timeout_ms` one of the
+/// keys in `bindings`, e.g.
+/// `(leader: (ctrl: true, alt: false, shift: false, key: "M"), bindings: {"B": ToggleBarrier})`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChordConfig {
+    pub leader: HotkeyConfig,
+    #[serde(default = "default_chord_timeout_ms")]
+    pub timeout_ms: u32, // How long after the leader the second key is waited for
+    #[serde(default = "default_suppress_leader")]
+    pub suppress_leader: bool, // Keep the leader press from reaching the game
+    pub bindings: BTreeMap<String, HotkeyAction>, // Second key (as in hotkey.key) to action
+}
+
+fn default_chord_timeout_ms() -> u32 {
+    1000
+}
+
+fn default_suppress_leader() -> bool {
+    true
+}
+
+/// Buttons on an XInput controller, by name (`"A"`, `"Back"`,
+/// `"RightShoulder"`, `"DPadUp"`, ...), e.g.
+/// `(bypass: Some("RightShoulder"), actions: {"Back": ToggleBarrier})`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct GamepadConfig {
+    #[serde(default)]
+    pub bypass: Option<String>, // Button that lifts the barrier while held
+    #[serde(default)]
+    pub actions: BTreeMap<String, HotkeyAction>, // Button to the action a press runs
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BarrierConfig {
+    // Left out when the barrier is given by corners
+    #[serde(default)]
     pub x: i32,
+    #[serde(default)]
     pub y: i32,
+    #[serde(default)]
     pub width: i32,
+    #[serde(default)]
     pub height: i32,
-    pub buffer_zone: i32,
+    #[serde(default)]
+    pub corners: Option<((i32, i32), (i32, i32))>, // Two opposite corners, instead of x/y/width/height
+    #[serde(with = "uniform_or_per_edge")]
+    pub buffer_zone: BufferZoneConfig, // A width for every edge, or (top:, right:, bottom:, left:)
+    #[serde(default = "default_buffer_blocks")]
+    pub buffer_blocks: bool, // Keep the cursor out of the buffer zone; false only warns there
     pub push_factor: i32,
+    #[serde(default = "default_dynamic_push")]
+    pub dynamic_push: bool, // Scale pushes 1x-3x with cursor speed; false always pushes push_factor
+    #[serde(default)]
+    pub offscreen_push: OffscreenPush,
+    #[serde(default)]
+    pub screen_margin: i32, // Pixels pushes keep the cursor clear of every screen edge
+    #[serde(default)]
+    pub approach_zone: Option<ApproachZoneConfig>, // Slow the cursor down in front of the buffer zone
+    #[serde(default = "default_overlay_enabled")]
+    pub overlay_enabled: bool, // Draw the overlay windows; false enforces the barrier with nothing on screen
     pub overlay_color: OverlayColor,
+    #[serde(default)]
+    pub proximity_coloring: bool, // Fade the overlay from proximity_far_color to overlay_color as the cursor nears
+    #[serde(default = "default_proximity_far_color")]
+    pub proximity_far_color: OverlayColor,
+    #[serde(default = "default_proximity_range")]
+    pub proximity_range: i32, // Pixels from the barrier at which the overlay shows proximity_far_color
     pub overlay_alpha: u8, // 0-255, where 255 is opaque, 0 is transparent
+    #[serde(default)]
+    pub overlay_coverage: OverlayCoverage,
+    #[serde(default)]
+    pub overlay_failure: OverlayFailurePolicy,
+    #[serde(default = "default_interior_alpha")]
+    pub interior_alpha: u8, // Alpha of the interior tint when overlay_coverage includes it
+    #[serde(default)]
+    pub buffer_alpha: Option<u8>, // Alpha of the buffer frame; overlay_alpha when unset
+    #[serde(default)]
+    pub flash_alpha: Option<u8>, // Alpha the buffer frame flashes to on a hit; overlay_alpha when unset
+    #[serde(default)]
+    pub overlay_animation: OverlayAnimation,
+    #[serde(default = "default_overlay_tick_ms")]
+    pub overlay_tick_ms: u32, // Overlay timer interval; 0 disables it
     pub audio_feedback: AudioFeedbackConfig,
+    #[serde(default)]
+    pub coordinate_origin: CoordinateOrigin,
+    #[serde(default)]
+    pub snap_grid: Option<i32>, // Round barrier edges to multiples of this many pixels
+    #[serde(default)]
+    pub snap_fraction: Option<i32>, // Round barrier edges to the lines splitting the screen into this many parts
+    #[serde(default = "default_consume_blocked_events")]
+    pub consume_blocked_events: bool, // Swallow slowed mouse moves; pushes are always swallowed
+    #[serde(default)]
+    pub resync_after_block: bool, // Send a zero-length move after a push so raw-input games catch up
+    #[serde(default)]
+    pub enforcement_mode: EnforcementMode,
+    #[serde(default)]
+    pub bypass_hold_ms: u32, // Hold the middle button this long before it bypasses the barrier (0 = on press)
+    #[serde(default)]
+    pub bypass_modifier: Option<BypassModifier>, // Let the cursor through while this modifier is held
+    #[serde(default)]
+    pub clamp_to_screen: bool, // Move a barrier lying entirely off-screen onto the screen
+    #[serde(default)]
+    pub anchor: BarrierAnchor,
+    #[serde(default)]
+    pub edit_mode: Option<EditModeConfig>, // Modifiers that let the mouse move and resize the barrier
+    #[serde(default)]
+    pub high_contrast: HighContrastConfig,
 }
 
 impl BarrierConfig {
-    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
-        if self.width <= 0 {
-            return Err(format!("barrier width must be > 0, got {}", self.width).into());
-        }
-        if self.height <= 0 {
-            return Err(format!("barrier height must be > 0, got {}", self.height).into());
+    /// The alpha of the buffer zone frame
+    pub fn buffer_alpha(&self) -> u8 {
+        self.buffer_alpha.unwrap_or(self.overlay_alpha)
+    }
+
+    /// The alpha the buffer zone frame flashes to when the cursor hits it
+    pub fn flash_alpha(&self) -> u8 {
+        self.flash_alpha.unwrap_or(self.overlay_alpha)
+    }
+
+    /// The grid barrier edges snap to, if any
+    pub fn snap_grid(&self) -> Option<SnapGrid> {
+        self.snap_grid
+            .map(SnapGrid::Pixels)
+            .or(self.snap_fraction.map(SnapGrid::ScreenFraction))
+    }
+
+    /// The overlay color and proximity far color to draw with. `colorblind_safe`
+    /// swaps the shipped red and green for orange and blue; colors the user
+    /// picked are kept.
+    pub fn overlay_colors(&self, colorblind_safe: bool) -> (OverlayColor, OverlayColor) {
+        let defaults = &get_default_config().barrier;
+        let swap = |color: &OverlayColor, default: &OverlayColor, safe: OverlayColor| {
+            if colorblind_safe && color == default {
+                safe
+            } else {
+                color.clone()
+            }
+        };
+        (
+            swap(
+                &self.overlay_color,
+                &defaults.overlay_color,
+                COLORBLIND_SAFE_NEAR,
+            ),
+            swap(
+                &self.proximity_far_color,
+                &defaults.proximity_far_color,
+                COLORBLIND_SAFE_FAR,
+            ),
+        )
+    }
+
+    /// Where the barrier is, offset by `origin`
+    pub fn bounds(&self, origin: (i32, i32)) -> mouse_barrier::BarrierBounds {
+        let offset = |(x, y): (i32, i32)| mouse_barrier::Point {
+            x: origin.0 + x,
+            y: origin.1 + y,
+        };
+        match self.corners {
+            Some((a, b)) => mouse_barrier::BarrierBounds::Corners(offset(a), offset(b)),
+            None => mouse_barrier::BarrierBounds::CornerAndSize {
+                x: origin.0 + self.x,
+                y: origin.1 + self.y,
+                width: self.width,
+                height: self.height,
+            },
         }
-        if self.buffer_zone < 0 {
-            return Err(
-                format!("barrier buffer_zone must be >= 0, got {}", self.buffer_zone).into(),
-            );
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match self.corners {
+            // Left at zero, the default, when corners are given
+            Some(_) if (self.x, self.y, self.width, self.height) != (0, 0, 0, 0) => {
+                return Err(
+                    "barrier takes either corners or x, y, width and height, not both".into(),
+                );
+            }
+            Some(((x1, y1), (x2, y2))) => {
+                if x1 == x2 || y1 == y2 {
+                    return Err(format!(
+                        "barrier corners must differ in both x and y, got ({}, {}) and ({}, {})",
+                        x1, y1, x2, y2
+                    )
+                    .into());
+                }
+            }
+            None => {
+                if self.width <= 0 {
+                    return Err(format!("barrier width must be > 0, got {}", self.width).into());
+                }
+                if self.height <= 0 {
+                    return Err(format!("barrier height must be > 0, got {}", self.height).into());
+                }
+            }
         }
+        self.buffer_zone.validate()?;
         if self.push_factor < 0 {
             return Err(
                 format!("barrier push_factor must be >= 0, got {}", self.push_factor).into(),
             );
         }
+        if self.screen_margin < 0 {
+            return Err(format!(
+                "barrier screen_margin must be >= 0, got {}",
+                self.screen_margin
+            )
+            .into());
+        }
+        if self.proximity_range <= 0 {
+            return Err(format!(
+                "barrier proximity_range must be > 0, got {}",
+                self.proximity_range
+            )
+            .into());
+        }
+        if let Some(grid) = self.snap_grid {
+            if grid <= 0 {
+                return Err(format!("barrier snap_grid must be > 0, got {}", grid).into());
+            }
+        }
+        if let Some(parts) = self.snap_fraction {
+            if parts <= 0 {
+                return Err(format!("barrier snap_fraction must be > 0, got {}", parts).into());
+            }
+            if self.snap_grid.is_some() {
+                return Err("barrier snap_grid and snap_fraction can't both be set".into());
+            }
+        }
+        if let OverlayAnimation::Pulse { period_ms: 0, .. } = self.overlay_animation {
+            return Err("barrier overlay_animation Pulse period_ms must be > 0".into());
+        }
+        // An opaque interior would hide whatever the barrier sits on
+        if self.interior_alpha == 255 && self.overlay_coverage != OverlayCoverage::BufferOnly {
+            return Err(format!(
+                "barrier interior_alpha must be < 255 with overlay_coverage {:?}",
+                self.overlay_coverage
+            )
+            .into());
+        }
+        if let Some(approach_zone) = &self.approach_zone {
+            approach_zone.validate()?;
+        }
+        if let Some(edit_mode) = &self.edit_mode {
+            edit_mode.validate()?;
+        }
+        self.audio_feedback.validate()?;
         Ok(())
     }
 }
 
+/// How far the buffer zone reaches out from each edge of the barrier, in pixels
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BufferZoneConfig {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
+impl BufferZoneConfig {
+    pub const fn uniform(width: i32) -> Self {
+        Self {
+            top: width,
+            right: width,
+            bottom: width,
+            left: width,
+        }
+    }
+
+    /// Each edge's name and width
+    pub fn edges(&self) -> [(&'static str, i32); 4] {
+        [
+            ("top", self.top),
+            ("right", self.right),
+            ("bottom", self.bottom),
+            ("left", self.left),
+        ]
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(width) = self.as_uniform() {
+            if width < 0 {
+                return Err(format!("barrier buffer_zone must be >= 0, got {}", width).into());
+            }
+        }
+        for (edge, width) in self.edges() {
+            if width < 0 {
+                return Err(
+                    format!("barrier buffer_zone {} must be >= 0, got {}", edge, width).into(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn as_uniform(&self) -> Option<i32> {
+        mouse_barrier::BufferZone::from(self).as_uniform()
+    }
+}
+
+impl From<&BufferZoneConfig> for mouse_barrier::BufferZone {
+    fn from(config: &BufferZoneConfig) -> Self {
+        Self {
+            top: config.top,
+            right: config.right,
+            bottom: config.bottom,
+            left: config.left,
+        }
+    }
+}
+
+/// Reads `buffer_zone` as either a plain width for every edge or a per-edge
+/// struct, and writes a uniform one back as a plain width. The binary config
+/// cache can't tell the two forms apart, so it always gets the struct.
+mod uniform_or_per_edge {
+    use super::BufferZoneConfig;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(
+        untagged,
+        expecting = "a width in pixels or (top: _, right: _, bottom: _, left: _)"
+    )]
+    enum Form {
+        Uniform(i32),
+        PerEdge(BufferZoneConfig),
+    }
+
+    pub fn serialize<S: Serializer>(
+        buffer_zone: &BufferZoneConfig,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match buffer_zone.as_uniform() {
+            Some(width) if serializer.is_human_readable() => serializer.serialize_i32(width),
+            _ => buffer_zone.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BufferZoneConfig, D::Error> {
+        if !deserializer.is_human_readable() {
+            return BufferZoneConfig::deserialize(deserializer);
+        }
+        Ok(match Form::deserialize(deserializer)? {
+            Form::Uniform(width) => BufferZoneConfig::uniform(width),
+            Form::PerEdge(buffer_zone) => buffer_zone,
+        })
+    }
+}
+
+/// Progressive resistance in front of the buffer zone: moves towards it are
+/// scaled down from full speed `distance` pixels out to `min_scale` at its edge
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApproachZoneConfig {
+    pub distance: i32,  // Pixels out from the buffer zone where the slowdown starts
+    pub min_scale: f32, // Fraction of a move kept at the buffer zone, in (0, 1]
+}
+
+impl ApproachZoneConfig {
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.distance <= 0 {
+            return Err(format!(
+                "barrier approach_zone distance must be > 0, got {}",
+                self.distance
+            )
+            .into());
+        }
+        // At 0 the cursor could never reach the buffer zone
+        if !(self.min_scale > 0.0 && self.min_scale <= 1.0) {
+            return Err(format!(
+                "barrier approach_zone min_scale must be in (0, 1], got {}",
+                self.min_scale
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl From<&ApproachZoneConfig> for mouse_barrier::ApproachZone {
+    fn from(config: &ApproachZoneConfig) -> Self {
+        Self {
+            distance: config.distance,
+            min_scale: config.min_scale,
+        }
+    }
+}
+
+/// Modifiers that, while held, let the mouse move and resize the barrier
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EditModeConfig {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    #[serde(default = "default_resize_step")]
+    pub resize_step: i32, // Pixels one wheel notch grows or shrinks the barrier's height by
+}
+
+impl EditModeConfig {
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        // Without a modifier the mouse would edit the barrier all the time
+        if !(self.ctrl || self.alt || self.shift) {
+            return Err("barrier edit_mode needs at least one of ctrl, alt or shift".into());
+        }
+        if self.resize_step <= 0 {
+            return Err(format!(
+                "barrier edit_mode resize_step must be > 0, got {}",
+                self.resize_step
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// How the overlay is drawn while Windows high contrast is on or transparency
+/// effects are off
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HighContrastConfig {
+    #[serde(default = "default_follow_system")]
+    pub follow_system: bool, // Switch to the solid, patterned overlay when the system asks for it
+    #[serde(default)]
+    pub color: Option<OverlayColor>, // Border and pattern color; the theme's highlight color when unset
+    #[serde(default)]
+    pub pattern: HatchPattern,
+}
+
+impl Default for HighContrastConfig {
+    fn default() -> Self {
+        Self {
+            follow_system: default_follow_system(),
+            color: None,
+            pattern: HatchPattern::default(),
+        }
+    }
+}
+
+impl From<&HighContrastConfig> for mouse_barrier::HighContrastStyle {
+    fn from(config: &HighContrastConfig) -> Self {
+        Self {
+            follow_system: config.follow_system,
+            color: config
+                .color
+                .as_ref()
+                .map(|color| (color.r, color.g, color.b)),
+            pattern: config.pattern.into(),
+        }
+    }
+}
+
+fn default_follow_system() -> bool {
+    true
+}
+
+/// Fill pattern of the high-contrast overlay
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum HatchPattern {
+    Horizontal,
+    Vertical,
+    Diagonal,
+    Cross,
+    #[default]
+    DiagonalCross,
+}
+
+impl From<HatchPattern> for mouse_barrier::HatchPattern {
+    fn from(pattern: HatchPattern) -> Self {
+        match pattern {
+            HatchPattern::Horizontal => mouse_barrier::HatchPattern::Horizontal,
+            HatchPattern::Vertical => mouse_barrier::HatchPattern::Vertical,
+            HatchPattern::Diagonal => mouse_barrier::HatchPattern::Diagonal,
+            HatchPattern::Cross => mouse_barrier::HatchPattern::Cross,
+            HatchPattern::DiagonalCross => mouse_barrier::HatchPattern::DiagonalCross,
+        }
+    }
+}
+
+fn default_resize_step() -> i32 {
+    10
+}
+
+fn default_buffer_blocks() -> bool {
+    true
+}
+
+fn default_overlay_enabled() -> bool {
+    true
+}
+
+fn default_dynamic_push() -> bool {
+    true
+}
+
+fn default_proximity_far_color() -> OverlayColor {
+    OverlayColor { r: 0, g: 255, b: 0 }
+}
+
+fn default_proximity_range() -> i32 {
+    200
+}
+
+fn default_interior_alpha() -> u8 {
+    100
+}
+
+fn default_consume_blocked_events() -> bool {
+    true
+}
+
+fn default_overlay_tick_ms() -> u32 {
+    50
+}
+
+/// Where the cursor goes when the nearest way out of the barrier is off-screen
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum OffscreenPush {
+    FlipDirection, // Push out the opposite side instead
+    #[default]
+    ClampToEdge, // Pin the push to the screen edge
+    SlideAlongEdge, // Push out the nearest side that runs along the screen edge
+}
+
+impl From<OffscreenPush> for mouse_barrier::OffscreenPush {
+    fn from(push: OffscreenPush) -> Self {
+        match push {
+            OffscreenPush::FlipDirection => mouse_barrier::OffscreenPush::FlipDirection,
+            OffscreenPush::ClampToEdge => mouse_barrier::OffscreenPush::ClampToEdge,
+            OffscreenPush::SlideAlongEdge => mouse_barrier::OffscreenPush::SlideAlongEdge,
+        }
+    }
+}
+
+/// Which parts of the barrier the overlay tints
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum OverlayCoverage {
+    #[default]
+    BufferOnly, // Frame around the barrier covering the buffer zone
+    BarrierOnly, // The barrier interior only
+    Both,        // Buffer frame and interior
+}
+
+impl From<OverlayCoverage> for mouse_barrier::OverlayCoverage {
+    fn from(coverage: OverlayCoverage) -> Self {
+        match coverage {
+            OverlayCoverage::BufferOnly => mouse_barrier::OverlayCoverage::BufferOnly,
+            OverlayCoverage::BarrierOnly => mouse_barrier::OverlayCoverage::BarrierOnly,
+            OverlayCoverage::Both => mouse_barrier::OverlayCoverage::Both,
+        }
+    }
+}
+
+/// How the overlay's opacity changes over time
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum OverlayAnimation {
+    #[default]
+    Steady, // Hold overlay_alpha
+    Pulse {
+        period_ms: u32, // Length of one fade out and back in
+        min_alpha: u8,  // Alpha at the dimmest point
+    },
+}
+
+impl From<OverlayAnimation> for mouse_barrier::OverlayAnimation {
+    fn from(animation: OverlayAnimation) -> Self {
+        match animation {
+            OverlayAnimation::Steady => mouse_barrier::OverlayAnimation::Steady,
+            OverlayAnimation::Pulse {
+                period_ms,
+                min_alpha,
+            } => mouse_barrier::OverlayAnimation::Pulse {
+                period_ms,
+                min_alpha,
+            },
+        }
+    }
+}
+
+/// What happens when the overlay windows can't be created
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum OverlayFailurePolicy {
+    #[default]
+    ContinueInvisible, // Enforce the barrier anyway and warn on the HUD
+    FailEnable, // Refuse to enable the barrier
+}
+
+impl From<OverlayFailurePolicy> for mouse_barrier::OverlayFailurePolicy {
+    fn from(policy: OverlayFailurePolicy) -> Self {
+        match policy {
+            OverlayFailurePolicy::ContinueInvisible => {
+                mouse_barrier::OverlayFailurePolicy::ContinueInvisible
+            }
+            OverlayFailurePolicy::FailEnable => mouse_barrier::OverlayFailurePolicy::FailEnable,
+        }
+    }
+}
+
+/// Whether the barrier stops the cursor or only reports where it would have
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum EnforcementMode {
+    #[default]
+    Enforce, // Push the cursor back out of the barrier
+    WarnOnly, // Play the sounds and count the pushes, but let the cursor through
+}
+
+impl From<EnforcementMode> for mouse_barrier::EnforcementMode {
+    fn from(mode: EnforcementMode) -> Self {
+        match mode {
+            EnforcementMode::Enforce => mouse_barrier::EnforcementMode::Enforce,
+            EnforcementMode::WarnOnly => mouse_barrier::EnforcementMode::WarnOnly,
+        }
+    }
+}
+
+/// A modifier key that lets the cursor through the barrier while held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BypassModifier {
+    Ctrl,
+    Alt,
+    Shift,
+    Win,
+}
+
+impl From<BypassModifier> for mouse_barrier::BypassModifier {
+    fn from(modifier: BypassModifier) -> Self {
+        match modifier {
+            BypassModifier::Ctrl => mouse_barrier::BypassModifier::Ctrl,
+            BypassModifier::Alt => mouse_barrier::BypassModifier::Alt,
+            BypassModifier::Shift => mouse_barrier::BypassModifier::Shift,
+            BypassModifier::Win => mouse_barrier::BypassModifier::Win,
+        }
+    }
+}
+
+/// Which corner of the barrier `x` and `y` refer to
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum CoordinateOrigin {
+    #[default]
+    BottomLeft, // y is the bottom edge, height extends upward
+    TopLeft, // y is the top edge, height extends downward
+}
+
+impl From<CoordinateOrigin> for mouse_barrier::CoordinateOrigin {
+    fn from(origin: CoordinateOrigin) -> Self {
+        match origin {
+            CoordinateOrigin::BottomLeft => mouse_barrier::CoordinateOrigin::BottomLeft,
+            CoordinateOrigin::TopLeft => mouse_barrier::CoordinateOrigin::TopLeft,
+        }
+    }
+}
+
+/// What the barrier's `x` and `y` are measured from
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum BarrierAnchor {
+    #[default]
+    Screen, // Absolute screen coordinates
+    WindowTitle(String), // Top-left of the first window whose title contains this text
+    Process(String),     // Top-left of the main window of this executable, e.g. "AoE2DE_s.exe"
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioFeedbackConfig {
     pub on_barrier_hit: AudioOption,
     pub on_barrier_entry: AudioOption,
+    #[serde(default = "default_interrupt_previous")]
+    pub interrupt_previous: bool, // Stop a playing sound when a new one starts
+    #[serde(default)]
+    pub entry_cue: EntryCue,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Which crossing plays the on_barrier_entry sound
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum EntryCue {
+    #[default]
+    Barrier, // Reaching the barrier itself; rare, since pushes keep the cursor out
+    BufferZone, // Crossing into the buffer zone from outside, like on_barrier_hit
+}
+
+impl From<EntryCue> for mouse_barrier::EntryCue {
+    fn from(cue: EntryCue) -> Self {
+        match cue {
+            EntryCue::Barrier => mouse_barrier::EntryCue::Barrier,
+            EntryCue::BufferZone => mouse_barrier::EntryCue::BufferZone,
+        }
+    }
+}
+
+impl AudioFeedbackConfig {
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        for option in [&self.on_barrier_hit, &self.on_barrier_entry] {
+            if let AudioOption::Builtin(name) = option {
+                if BuiltinSound::from_name(name).is_none() {
+                    let known: Vec<_> = BuiltinSound::ALL.iter().map(|s| s.name()).collect();
+                    return Err(format!(
+                        "unknown builtin sound {:?}, expected one of: {}",
+                        name,
+                        known.join(", ")
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Turns every sound file path into an absolute one, resolving relative
+    /// paths against `dir` rather than the working directory, which depends
+    /// on how the app was launched. A file that doesn't exist is warned about
+    /// and dropped, so its cue plays nothing; returns the paths dropped.
+    pub fn resolve_sound_paths(&mut self, dir: &Path) -> Vec<String> {
+        self.check_sound_files(|path| {
+            resolve_sound_path(path, dir, |name| std::env::var(name).ok())
+        })
+    }
+
+    /// Drops sound files that have gone since their paths were resolved, the
+    /// way [`Self::resolve_sound_paths`] drops ones that never existed
+    pub fn drop_missing_sounds(&mut self) -> Vec<String> {
+        self.check_sound_files(|path| {
+            if Path::new(path).is_file() {
+                Ok(path.to_string())
+            } else {
+                Err(format!("sound file {:?} not found", path))
+            }
+        })
+    }
+
+    /// Replaces every sound file path with what `resolve` makes of it, or
+    /// with [`AudioOption::None`] if it fails; returns the paths that failed
+    fn check_sound_files(
+        &mut self,
+        resolve: impl Fn(&str) -> Result<String, String>,
+    ) -> Vec<String> {
+        let mut missing = Vec::new();
+        for option in [&mut self.on_barrier_hit, &mut self.on_barrier_entry] {
+            let AudioOption::File(path) = option else {
+                continue;
+            };
+            match resolve(path) {
+                Ok(resolved) => *path = resolved,
+                Err(e) => {
+                    warn!("{}; that cue will play no sound", e);
+                    missing.push(std::mem::take(path));
+                    *option = AudioOption::None;
+                }
+            }
+        }
+        missing
+    }
+}
+
+fn default_interrupt_previous() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum AudioOption {
     None,
-    File(String), // Path to audio file
+    File(String),    // Path to a WAV file, relative to the config file's directory
+    Builtin(String), // Sound built into the app: "tick", "thunk", "click" or "chime"
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl AudioOption {
+    /// The sound the barrier plays for this option; `None` for an unknown
+    /// builtin, which validation rejects
+    pub fn sound(&self) -> Option<Sound> {
+        match self {
+            AudioOption::None => None,
+            AudioOption::File(path) => Some(Sound::File(path.clone())),
+            AudioOption::Builtin(name) => BuiltinSound::from_name(name).map(Sound::Builtin),
+        }
+    }
+}
+
+/// `path` with environment variables expanded through `env_var`, made
+/// absolute against `dir` and canonicalized
+fn resolve_sound_path(
+    path: &str,
+    dir: &Path,
+    env_var: impl Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    // Joining an absolute path replaces dir, so those are used as given
+    let full_path = dir.join(expand_env_vars(path, env_var));
+    let resolved = full_path
+        .canonicalize()
+        .ok()
+        .filter(|resolved| resolved.is_file())
+        .ok_or_else(|| {
+            format!(
+                "sound file {:?} not found (looked for {})",
+                path,
+                full_path.display()
+            )
+        })?;
+    Ok(strip_verbatim_prefix(&resolved.to_string_lossy()).to_string())
+}
+
+/// Expands `%NAME%` environment variables, as in `%USERPROFILE%\Music\hit.wav`,
+/// looking each up with `env_var`. Unset variables are left as written.
+fn expand_env_vars(path: &str, env_var: impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = path;
+    while let Some(start) = rest.find('%') {
+        let Some(len) = rest[start + 1..].find('%') else {
+            break;
+        };
+        let name = &rest[start + 1..start + 1 + len];
+        expanded.push_str(&rest[..start]);
+        match env_var(name) {
+            Some(value) if !name.is_empty() => expanded.push_str(&value),
+            _ => expanded.push_str(&rest[start..start + len + 2]),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Canonical Windows paths come back as `\\?\C:\...`; winmm can't open
+/// those, so drop the prefix where the plain form means the same
+fn strip_verbatim_prefix(path: &str) -> std::borrow::Cow<'_, str> {
+    if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        return format!(r"\\{}", unc).into();
+    }
+    match path.strip_prefix(r"\\?\") {
+        Some(drive_path) if drive_path.as_bytes().get(1) == Some(&b':') => drive_path.into(),
+        _ => path.into(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct OverlayColor {
     pub r: u8, // Red component (0-255)
     pub g: u8, // Green component (0-255)
     pub b: u8, // Blue component (0-255)
 }
 
+// Orange and blue from the Okabe-Ito palette, which stay distinct under the
+// common color vision deficiencies
+pub const COLORBLIND_SAFE_NEAR: OverlayColor = OverlayColor {
+    r: 230,
+    g: 159,
+    b: 0,
+};
+pub const COLORBLIND_SAFE_FAR: OverlayColor = OverlayColor {
+    r: 0,
+    g: 114,
+    b: 178,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HudConfig {
     pub enabled: bool,
     pub position: HudPosition,
     pub background_alpha: u8,
+    #[serde(default = "default_position_update_hz")]
+    pub position_update_hz: u32, // Max cursor position updates per second; 0 for every move
+    #[serde(default)]
+    pub coordinate_space: HudCoordinateSpace, // Where the barrier position and cursor are measured from
+    #[serde(default)]
+    pub reset_uptime_on_reload: bool, // Count the barrier's enabled time from zero after a config reload
+    #[serde(default = "default_hud_above_overlay")]
+    pub hud_above_overlay: bool, // Where the HUD and a barrier overlay overlap, draw the HUD on top
+}
+
+/// Which coordinates the HUD shows the barrier position and the cursor in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HudCoordinateSpace {
+    /// The barrier as written in config.ron, the cursor in screen pixels
+    #[default]
+    Mixed,
+    /// Both in screen pixels
+    Screen,
+    /// Both as config.ron measures them: from the game window when the
+    /// barrier follows one
+    Config,
+}
+
+fn default_hud_above_overlay() -> bool {
+    true
+}
+
+fn default_position_update_hz() -> u32 {
+    mouse_barrier::DEFAULT_POSITION_CALLBACK_HZ
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -88,6 +967,43 @@ pub enum HudPosition {
     BottomRight,
 }
 
+/// A crosshair flashed where the barrier pushed the cursor, so it isn't lost
+/// in a fight
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PushMarkerConfig {
+    pub enabled: bool,
+    pub color: OverlayColor,
+    pub size: i32,        // Width and height of the marker in pixels
+    pub duration_ms: u32, // How long the marker takes to fade out after a push
+}
+
+impl Default for PushMarkerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: OverlayColor {
+                r: 255,
+                g: 255,
+                b: 0,
+            },
+            size: 24,
+            duration_ms: 200,
+        }
+    }
+}
+
+impl PushMarkerConfig {
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if !(8..=256).contains(&self.size) {
+            return Err(format!("push_marker size must be 8 to 256, got {}", self.size).into());
+        }
+        if self.duration_ms == 0 {
+            return Err("push_marker duration_ms must be > 0".into());
+        }
+        Ok(())
+    }
+}
+
 // Parse the default config from config.ron at compile time (embedded) and runtime (parsed)
 static DEFAULT_CONFIG: OnceLock<Config> = OnceLock::new();
 
@@ -108,6 +1024,7 @@ impl Default for Config {
 impl Config {
     pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
         self.barrier.validate()?;
+        self.push_marker.validate()?;
         Ok(())
     }
 
@@ -116,17 +1033,28 @@ impl Config {
     ) -> Result<Self, Box<dyn std::error::Error>> {
         // Use Figment to layer defaults with user config
         let defaults = Config::default();
-        let config: Config = Figment::new()
+        let mut config: Config = Figment::new()
             .merge(Serialized::defaults(&defaults))
             .merge(Serialized::from(
-                Self::load_ron_file(path)?,
+                Self::load_ron_file(&path)?,
                 Profile::Default,
             ))
             .extract()?;
         config.validate()?;
+        config.resolve_paths(path.as_ref());
         Ok(config)
     }
 
+    /// Resolves paths in the config loaded from `config_path` against the
+    /// directory containing it, noting the sound files that weren't found
+    fn resolve_paths(&mut self, config_path: &Path) {
+        let dir = config_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        self.missing_sounds = self.barrier.audio_feedback.resolve_sound_paths(dir);
+    }
+
     fn load_ron_file<P: AsRef<std::path::Path>>(
         path: P,
     ) -> Result<Self, Box<dyn std::error::Error>> {
@@ -150,7 +1078,7 @@ impl Config {
         }
 
         // Extract the configuration
-        let config: Config = figment.extract()?;
+        let mut config: Config = figment.extract()?;
         config.validate()?;
 
         // Create default config file if it doesn't exist
@@ -159,6 +1087,8 @@ impl Config {
             config.save(path)?;
         }
 
+        // After saving, so the file keeps the paths as written
+        config.resolve_paths(Path::new(path));
         Ok(config)
     }
 
@@ -169,6 +1099,68 @@ impl Config {
     }
 }
 
+/// Writes `contents` to `path` through a temporary file beside it, renamed
+/// into place once complete, so a crash or full disk partway through leaves
+/// the old file rather than a truncated one
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".tmp");
+    let temp = path.with_file_name(name);
+
+    let written = std::fs::File::create(&temp).and_then(|mut file| {
+        std::io::Write::write_all(&mut file, contents.as_ref())?;
+        file.sync_all()
+    });
+    let result = written.and_then(|()| std::fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+/// `text`, the contents of a config file, with the barrier's `x`, `y`,
+/// `width` and `height` set to `bounds`, leaving everything else as written,
+/// comments included.
+///
+/// Each field is looked for at the start of a line after `barrier:`, the way
+/// config.ron lays them out; a file written otherwise is an error.
+pub fn with_barrier_bounds(text: &str, bounds: (i32, i32, i32, i32)) -> Result<String, String> {
+    let mut lines: Vec<String> = text.split_inclusive('\n').map(String::from).collect();
+    let barrier_line = lines
+        .iter()
+        .position(|line| line.trim_start().starts_with("barrier:"))
+        .ok_or("no barrier section")?;
+
+    if lines[barrier_line + 1..]
+        .iter()
+        .any(|line| line.trim_start().starts_with("corners: Some"))
+    {
+        return Err("the barrier is given by corners, not x, y, width and height".to_string());
+    }
+
+    let (x, y, width, height) = bounds;
+    for (field, value) in [("x", x), ("y", y), ("width", width), ("height", height)] {
+        let key = format!("{}:", field);
+        let line = lines[barrier_line + 1..]
+            .iter_mut()
+            .find(|line| line.trim_start().starts_with(&key))
+            .ok_or_else(|| format!("no barrier {} on a line of its own", field))?;
+
+        let indent = line.len() - line.trim_start().len();
+        let after_key = indent + key.len();
+        let rest = &line[after_key..];
+        let number_start = after_key + (rest.len() - rest.trim_start().len());
+        let number_len = line[number_start..]
+            .find(|c: char| c != '-' && !c.is_ascii_digit())
+            .unwrap_or(line.len() - number_start);
+        if number_len == 0 {
+            return Err(format!("barrier {} isn't a plain number", field));
+        }
+        line.replace_range(number_start..number_start + number_len, &value.to_string());
+    }
+    Ok(lines.concat())
+}
+
 pub fn vk_code_from_string(key: &str) -> Option<u32> {
     use winapi::um::winuser::*;
 
@@ -246,6 +1238,8 @@ mod tests {
                 audio_feedback: AudioFeedbackConfig {
                     on_barrier_hit: AudioOption::None,
                     on_barrier_entry: AudioOption::File("test.wav".to_string()),
+                    interrupt_previous: true,
+                    entry_cue: EntryCue::Barrier,
                 },
                 ..Config::default().barrier
             },
@@ -267,6 +1261,123 @@ mod tests {
         }
     }
 
+    /// Writes `audio_feedback` into a config.ron in a fresh directory
+    fn write_config_with_audio(audio_feedback: AudioFeedbackConfig) -> tempfile::TempDir {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = Config {
+            barrier: BarrierConfig {
+                audio_feedback,
+                ..Config::default().barrier
+            },
+            ..Config::default()
+        };
+        std::fs::write(
+            dir.path().join("config.ron"),
+            ron::to_string(&config).unwrap(),
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_relative_sound_paths_resolve_against_config_dir() {
+        let dir = write_config_with_audio(AudioFeedbackConfig {
+            on_barrier_hit: AudioOption::File("sounds/hit.wav".to_string()),
+            on_barrier_entry: AudioOption::Builtin("chime".to_string()),
+            interrupt_previous: true,
+            entry_cue: EntryCue::Barrier,
+        });
+        std::fs::create_dir(dir.path().join("sounds")).unwrap();
+        std::fs::write(dir.path().join("sounds").join("hit.wav"), b"RIFF").unwrap();
+
+        // The working directory is wherever the tests run, not the config dir
+        let config = Config::load_from_file(dir.path().join("config.ron")).unwrap();
+
+        let AudioOption::File(path) = &config.barrier.audio_feedback.on_barrier_hit else {
+            panic!("Expected File");
+        };
+        let path = std::path::Path::new(path);
+        assert!(path.is_absolute(), "{}", path.display());
+        assert!(!path.to_string_lossy().starts_with(r"\\?\"));
+        assert!(path.ends_with("sounds/hit.wav"));
+        assert_eq!(
+            config.barrier.audio_feedback.on_barrier_entry.sound(),
+            Some(Sound::Builtin(BuiltinSound::Chime))
+        );
+    }
+
+    #[test]
+    fn test_missing_sound_file_plays_nothing() {
+        let dir = write_config_with_audio(AudioFeedbackConfig {
+            on_barrier_hit: AudioOption::Builtin("thunk".to_string()),
+            on_barrier_entry: AudioOption::File("missing.wav".to_string()),
+            interrupt_previous: true,
+            entry_cue: EntryCue::Barrier,
+        });
+
+        let config = Config::load_from_file(dir.path().join("config.ron")).unwrap();
+        assert_eq!(
+            config.barrier.audio_feedback.on_barrier_entry,
+            AudioOption::None
+        );
+        assert_eq!(config.missing_sounds, ["missing.wav"]);
+        // The other cue is untouched
+        assert_eq!(
+            config.barrier.audio_feedback.on_barrier_hit,
+            AudioOption::Builtin("thunk".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sound_deleted_after_resolving_is_dropped() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let sound = dir.path().join("hit.wav");
+        std::fs::write(&sound, b"RIFF").unwrap();
+        let mut audio = AudioFeedbackConfig {
+            on_barrier_hit: AudioOption::File("hit.wav".to_string()),
+            on_barrier_entry: AudioOption::None,
+            interrupt_previous: true,
+            entry_cue: EntryCue::Barrier,
+        };
+        assert!(audio.resolve_sound_paths(dir.path()).is_empty());
+        assert!(audio.drop_missing_sounds().is_empty());
+
+        std::fs::remove_file(&sound).unwrap();
+        assert_eq!(audio.drop_missing_sounds().len(), 1);
+        assert_eq!(audio.on_barrier_hit, AudioOption::None);
+    }
+
+    #[test]
+    fn test_unknown_builtin_sound_fails_validation() {
+        let mut config = Config::default();
+        config.barrier.audio_feedback.on_barrier_hit = AudioOption::Builtin("klaxon".to_string());
+
+        let error = config.validate().unwrap_err();
+        assert!(error.to_string().contains("klaxon"), "{}", error);
+    }
+
+    #[test]
+    fn test_expand_env_vars() {
+        let env_var = |name: &str| (name == "SOUNDS").then(|| r"D:\sounds".to_string());
+        assert_eq!(
+            expand_env_vars(r"%SOUNDS%\hit.wav", env_var),
+            r"D:\sounds\hit.wav"
+        );
+        assert_eq!(expand_env_vars("100%.wav", env_var), "100%.wav");
+        assert_eq!(expand_env_vars("%UNSET%.wav", env_var), "%UNSET%.wav");
+        assert_eq!(expand_env_vars("%%.wav", env_var), "%%.wav");
+    }
+
+    #[test]
+    fn test_strip_verbatim_prefix() {
+        assert_eq!(strip_verbatim_prefix(r"\\?\C:\hit.wav"), r"C:\hit.wav");
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\UNC\server\share\hit.wav"),
+            r"\\server\share\hit.wav"
+        );
+        assert_eq!(strip_verbatim_prefix(r"C:\hit.wav"), r"C:\hit.wav");
+    }
+
     #[test]
     fn test_figment_preserves_file_syntax() {
         let file_option = AudioOption::File("test.wav".to_string());
@@ -289,6 +1400,8 @@ mod tests {
                 audio_feedback: AudioFeedbackConfig {
                     on_barrier_hit: none_option.clone(),
                     on_barrier_entry: file_option.clone(),
+                    interrupt_previous: true,
+                    entry_cue: EntryCue::Barrier,
                 },
                 ..Config::default().barrier
             },
@@ -315,6 +1428,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_builtin_sounds_survive_serialization_and_layering() {
+        for builtin in BuiltinSound::ALL {
+            let option = AudioOption::Builtin(builtin.name().to_string());
+            let ron_string = ron::to_string(&option).unwrap();
+            assert_eq!(ron_string, format!("Builtin(\"{}\")", builtin.name()));
+            assert_eq!(ron::from_str::<AudioOption>(&ron_string).unwrap(), option);
+        }
+
+        // The defaults play builtins; a user config choosing other sounds, or
+        // silence, must replace them rather than merge with them
+        let defaults = Config::default();
+        assert!(matches!(
+            defaults.barrier.audio_feedback.on_barrier_hit,
+            AudioOption::Builtin(_)
+        ));
+        let user = Config {
+            barrier: BarrierConfig {
+                audio_feedback: AudioFeedbackConfig {
+                    on_barrier_hit: AudioOption::None,
+                    on_barrier_entry: AudioOption::Builtin("chime".to_string()),
+                    ..defaults.barrier.audio_feedback.clone()
+                },
+                ..defaults.barrier.clone()
+            },
+            ..defaults.clone()
+        };
+        let layered: Config = Figment::new()
+            .merge(Serialized::defaults(&defaults))
+            .merge(Serialized::from(&user, Profile::Default))
+            .extract()
+            .unwrap();
+        assert_eq!(
+            layered.barrier.audio_feedback.on_barrier_hit,
+            AudioOption::None
+        );
+        assert_eq!(
+            layered.barrier.audio_feedback.on_barrier_entry.sound(),
+            Some(Sound::Builtin(BuiltinSound::Chime))
+        );
+
+        // And a file written with builtins loads back through the layering
+        let dir = write_config_with_audio(AudioFeedbackConfig {
+            on_barrier_hit: AudioOption::Builtin("thunk".to_string()),
+            on_barrier_entry: AudioOption::None,
+            ..defaults.barrier.audio_feedback.clone()
+        });
+        let loaded = Config::load_from_file(dir.path().join("config.ron")).unwrap();
+        assert_eq!(
+            loaded.barrier.audio_feedback.on_barrier_hit.sound(),
+            Some(Sound::Builtin(BuiltinSound::Thunk))
+        );
+        assert_eq!(
+            loaded.barrier.audio_feedback.on_barrier_entry,
+            AudioOption::None
+        );
+    }
+
     #[test]
     fn test_hud_position_serialization() {
         let positions = vec![
@@ -341,6 +1512,404 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_coordinate_origin_defaults_when_missing() {
+        // Barrier section written before coordinate_origin existed
+        let barrier: BarrierConfig = ron::from_str(
+            "(x: 0, y: 1080, width: 200, height: 40, buffer_zone: 20, push_factor: 50, \
+             overlay_color: (r: 255, g: 0, b: 0), overlay_alpha: 200, \
+             audio_feedback: (on_barrier_hit: None, on_barrier_entry: None))",
+        )
+        .unwrap();
+
+        assert_eq!(barrier.coordinate_origin, CoordinateOrigin::BottomLeft);
+        assert_eq!(barrier.snap_grid, None);
+        assert_eq!(barrier.snap_fraction, None);
+        assert_eq!(barrier.offscreen_push, OffscreenPush::ClampToEdge);
+        assert_eq!(barrier.screen_margin, 0);
+        assert_eq!(barrier.overlay_coverage, OverlayCoverage::BufferOnly);
+        assert_eq!(
+            barrier.overlay_failure,
+            OverlayFailurePolicy::ContinueInvisible
+        );
+        assert_eq!(barrier.interior_alpha, 100);
+        assert_eq!(barrier.overlay_animation, OverlayAnimation::Steady);
+        assert_eq!(barrier.overlay_tick_ms, 50);
+        assert!(barrier.consume_blocked_events);
+        assert!(!barrier.resync_after_block);
+        assert_eq!(barrier.enforcement_mode, EnforcementMode::Enforce);
+        assert_eq!(barrier.bypass_hold_ms, 0);
+        assert_eq!(barrier.bypass_modifier, None);
+        assert_eq!(barrier.corners, None);
+        assert!(!barrier.clamp_to_screen);
+        assert_eq!(barrier.anchor, BarrierAnchor::Screen);
+        assert_eq!(barrier.edit_mode, None);
+        assert!(barrier.buffer_blocks);
+        assert!(barrier.overlay_enabled);
+        assert!(barrier.dynamic_push);
+        assert_eq!(barrier.high_contrast, HighContrastConfig::default());
+    }
+
+    #[test]
+    fn test_corners_and_corner_and_size_give_the_same_rect() {
+        let parse = |bounds: &str| -> BarrierConfig {
+            ron::from_str(&format!(
+                "({}, buffer_zone: 20, push_factor: 50, \
+                 overlay_color: (r: 255, g: 0, b: 0), overlay_alpha: 200, \
+                 audio_feedback: (on_barrier_hit: None, on_barrier_entry: None))",
+                bounds
+            ))
+            .unwrap()
+        };
+        let sized = parse("x: 100, y: 500, width: 200, height: 100");
+        let corners = parse("corners: Some(((300, 400), (100, 500)))");
+        assert_eq!(corners.corners, Some(((300, 400), (100, 500))));
+        assert!(sized.validate().is_ok());
+        assert!(corners.validate().is_ok());
+
+        // Anchored to a window at (50, 20) as well as to the screen
+        for origin in [(0, 0), (50, 20)] {
+            let rect = sized.bounds(origin).rect(sized.coordinate_origin.into());
+            assert_eq!(
+                corners
+                    .bounds(origin)
+                    .rect(corners.coordinate_origin.into()),
+                rect
+            );
+            // coordinate_origin doesn't apply to corners
+            assert_eq!(
+                corners
+                    .bounds(origin)
+                    .rect(CoordinateOrigin::TopLeft.into()),
+                rect
+            );
+        }
+    }
+
+    #[test]
+    fn test_corners_are_validated() {
+        let mut barrier = Config::default().barrier;
+        barrier.corners = Some(((0, 1040), (400, 1080)));
+        // The default x, y, width and height are still set
+        let error = barrier.validate().unwrap_err().to_string();
+        assert!(error.contains("not both"), "{}", error);
+
+        (barrier.x, barrier.y, barrier.width, barrier.height) = (0, 0, 0, 0);
+        assert!(barrier.validate().is_ok());
+
+        barrier.corners = Some(((0, 1040), (400, 1040)));
+        assert!(barrier.validate().is_err());
+        barrier.corners = Some(((400, 1040), (400, 1080)));
+        assert!(barrier.validate().is_err());
+    }
+
+    #[test]
+    fn test_pulse_animation_parses_and_needs_a_period() {
+        let mut barrier = Config::default().barrier;
+        barrier.overlay_animation = ron::from_str("Pulse(period_ms: 1500, min_alpha: 60)").unwrap();
+        assert_eq!(
+            barrier.overlay_animation,
+            OverlayAnimation::Pulse {
+                period_ms: 1500,
+                min_alpha: 60
+            }
+        );
+        assert!(barrier.validate().is_ok());
+
+        barrier.overlay_animation = OverlayAnimation::Pulse {
+            period_ms: 0,
+            min_alpha: 60,
+        };
+        assert!(barrier.validate().is_err());
+    }
+
+    #[test]
+    fn test_push_marker_parses_and_is_validated() {
+        let mut config = Config {
+            push_marker: ron::from_str(
+                "(enabled: true, color: (r: 0, g: 255, b: 255), size: 32, duration_ms: 300)",
+            )
+            .unwrap(),
+            ..Config::default()
+        };
+        assert_eq!(config.push_marker.size, 32);
+        assert!(config.validate().is_ok());
+
+        config.push_marker.size = 4;
+        assert!(config.validate().is_err());
+        config.push_marker.size = 32;
+        config.push_marker.duration_ms = 0;
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("duration_ms"), "{}", error);
+    }
+
+    #[test]
+    fn test_snap_grid_parses_and_must_be_positive() {
+        let mut barrier = Config::default().barrier;
+        barrier.snap_grid = ron::from_str("Some(10)").unwrap();
+        assert_eq!(barrier.snap_grid, Some(10));
+        assert!(barrier.validate().is_ok());
+
+        barrier.snap_grid = Some(0);
+        assert!(barrier.validate().is_err());
+        barrier.snap_grid = Some(-10);
+        assert!(barrier.validate().is_err());
+    }
+
+    #[test]
+    fn test_snap_fraction_parses_and_excludes_snap_grid() {
+        let mut barrier = Config::default().barrier;
+        barrier.snap_fraction = ron::from_str("Some(8)").unwrap();
+        assert!(barrier.validate().is_ok());
+        assert_eq!(barrier.snap_grid(), Some(SnapGrid::ScreenFraction(8)));
+
+        barrier.snap_fraction = Some(0);
+        assert!(barrier.validate().is_err());
+
+        barrier.snap_fraction = Some(8);
+        barrier.snap_grid = Some(10);
+        assert_eq!(
+            barrier.validate().unwrap_err().to_string(),
+            "barrier snap_grid and snap_fraction can't both be set"
+        );
+
+        barrier.snap_fraction = None;
+        assert_eq!(barrier.snap_grid(), Some(SnapGrid::Pixels(10)));
+    }
+
+    #[test]
+    fn test_approach_zone_parses_and_is_validated() {
+        let mut barrier = Config::default().barrier;
+        assert_eq!(barrier.approach_zone, None);
+        barrier.approach_zone = ron::from_str("Some((distance: 60, min_scale: 0.3))").unwrap();
+        assert_eq!(
+            barrier.approach_zone,
+            Some(ApproachZoneConfig {
+                distance: 60,
+                min_scale: 0.3,
+            })
+        );
+        assert!(barrier.validate().is_ok());
+
+        for (distance, min_scale) in [(0, 0.3), (-5, 0.3), (60, 0.0), (60, 1.5), (60, f32::NAN)] {
+            barrier.approach_zone = Some(ApproachZoneConfig {
+                distance,
+                min_scale,
+            });
+            assert!(barrier.validate().is_err(), "{} {}", distance, min_scale);
+        }
+    }
+
+    #[test]
+    fn test_edit_mode_parses_and_needs_a_modifier() {
+        let mut barrier = Config::default().barrier;
+        barrier.edit_mode = ron::from_str("Some((ctrl: true, alt: true, shift: false))").unwrap();
+        assert_eq!(
+            barrier.edit_mode,
+            Some(EditModeConfig {
+                ctrl: true,
+                alt: true,
+                shift: false,
+                resize_step: 10,
+            })
+        );
+        assert!(barrier.validate().is_ok());
+
+        barrier.edit_mode = Some(EditModeConfig {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            resize_step: 10,
+        });
+        assert!(barrier.validate().is_err());
+        barrier.edit_mode = Some(EditModeConfig {
+            ctrl: true,
+            alt: false,
+            shift: false,
+            resize_step: 0,
+        });
+        assert!(barrier.validate().is_err());
+    }
+
+    #[test]
+    fn test_high_contrast_parses_with_defaults() {
+        let style: HighContrastConfig =
+            ron::from_str("(color: Some((r: 255, g: 255, b: 0)), pattern: Cross)").unwrap();
+        assert!(style.follow_system);
+        assert_eq!(style.pattern, HatchPattern::Cross);
+
+        let style = mouse_barrier::HighContrastStyle::from(&style);
+        assert_eq!(style.color, Some((255, 255, 0)));
+        assert_eq!(style.pattern, mouse_barrier::HatchPattern::Cross);
+    }
+
+    #[test]
+    fn test_colorblind_safe_swaps_only_the_default_colors() {
+        let mut barrier = Config::default().barrier;
+        assert_eq!(
+            barrier.overlay_colors(false),
+            (
+                barrier.overlay_color.clone(),
+                barrier.proximity_far_color.clone()
+            )
+        );
+        assert_eq!(
+            barrier.overlay_colors(true),
+            (COLORBLIND_SAFE_NEAR, COLORBLIND_SAFE_FAR)
+        );
+
+        // A color the user picked is theirs to keep
+        barrier.overlay_color = OverlayColor {
+            r: 255,
+            g: 0,
+            b: 255,
+        };
+        assert_eq!(
+            barrier.overlay_colors(true),
+            (barrier.overlay_color.clone(), COLORBLIND_SAFE_FAR)
+        );
+    }
+
+    #[test]
+    fn test_with_barrier_bounds_keeps_the_rest_of_the_file() {
+        let text = include_str!("../../config.ron");
+        let edited = with_barrier_bounds(text, (-40, 900, 320, 64)).unwrap();
+
+        let config: Config = ron::from_str(&edited).unwrap();
+        assert_eq!(config.barrier.x, -40);
+        assert_eq!(config.barrier.y, 900);
+        assert_eq!(config.barrier.width, 320);
+        assert_eq!(config.barrier.height, 64);
+        // Only the four numbers changed
+        assert_eq!(edited.lines().count(), text.lines().count());
+        let changed = text
+            .lines()
+            .zip(edited.lines())
+            .filter(|(before, after)| before != after)
+            .count();
+        assert_eq!(changed, 4);
+
+        assert!(with_barrier_bounds("(barrier: (x: 0, y: 0))", (1, 2, 3, 4)).is_err());
+        assert!(with_barrier_bounds(
+            "(barrier: (\n    corners: Some(((0, 0), (9, 9))),\n    x: 0,\n))",
+            (1, 2, 3, 4)
+        )
+        .is_err());
+        assert!(with_barrier_bounds("(hotkey: ())", (1, 2, 3, 4)).is_err());
+    }
+
+    #[test]
+    fn test_proximity_coloring_defaults_when_missing() {
+        let barrier: BarrierConfig = ron::from_str(
+            "(x: 0, y: 0, width: 10, height: 10, buffer_zone: 5, push_factor: 10, \
+             overlay_color: (r: 255, g: 0, b: 0), overlay_alpha: 200, \
+             audio_feedback: (on_barrier_hit: None, on_barrier_entry: None))",
+        )
+        .unwrap();
+
+        assert!(!barrier.proximity_coloring);
+        assert_eq!(barrier.proximity_far_color.g, 255);
+        assert_eq!(barrier.proximity_range, 200);
+
+        let mut barrier = barrier;
+        barrier.proximity_range = 0;
+        assert!(barrier.validate().is_err());
+    }
+
+    #[test]
+    fn test_buffer_zone_accepts_a_width_or_one_per_edge() {
+        let parse = |buffer_zone: &str| {
+            ron::from_str::<BarrierConfig>(&format!(
+                "(x: 0, y: 0, width: 10, height: 10, buffer_zone: {}, push_factor: 10, \
+                 overlay_color: (r: 255, g: 0, b: 0), overlay_alpha: 200, \
+                 audio_feedback: (on_barrier_hit: None, on_barrier_entry: None))",
+                buffer_zone
+            ))
+        };
+
+        let uniform = parse("20").unwrap();
+        assert_eq!(uniform.buffer_zone, BufferZoneConfig::uniform(20));
+        assert!(ron::to_string(&uniform)
+            .unwrap()
+            .contains("buffer_zone:20,"));
+
+        let mut per_edge = parse("(top: 0, right: 5, bottom: 30, left: 0)").unwrap();
+        let expected = BufferZoneConfig {
+            top: 0,
+            right: 5,
+            bottom: 30,
+            left: 0,
+        };
+        assert_eq!(per_edge.buffer_zone, expected);
+        let restored: BarrierConfig = ron::from_str(&ron::to_string(&per_edge).unwrap()).unwrap();
+        assert_eq!(restored.buffer_zone, expected);
+        let restored: BarrierConfig =
+            bincode::deserialize(&bincode::serialize(&per_edge).unwrap()).unwrap();
+        assert_eq!(restored.buffer_zone, expected);
+
+        assert!(parse("(top: 0, bottom: 30)").is_err());
+        per_edge.buffer_zone.left = -1;
+        let error = per_edge.validate().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "barrier buffer_zone left must be >= 0, got -1"
+        );
+    }
+
+    #[test]
+    fn test_opaque_interior_fails_validation_only_when_drawn() {
+        let mut barrier = Config::default().barrier;
+        barrier.interior_alpha = 255;
+        barrier.overlay_coverage = OverlayCoverage::BufferOnly;
+        assert!(barrier.validate().is_ok());
+
+        for coverage in [OverlayCoverage::BarrierOnly, OverlayCoverage::Both] {
+            barrier.overlay_coverage = coverage;
+            assert!(barrier.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_interrupt_previous_defaults_when_missing() {
+        // Audio section written before interrupt_previous existed
+        let audio: AudioFeedbackConfig =
+            ron::from_str("(on_barrier_hit: None, on_barrier_entry: None)").unwrap();
+
+        assert!(audio.interrupt_previous);
+        assert_eq!(audio.entry_cue, EntryCue::Barrier);
+    }
+
+    #[test]
+    fn test_entry_cue_parses() {
+        let audio: AudioFeedbackConfig =
+            ron::from_str("(on_barrier_hit: None, on_barrier_entry: None, entry_cue: BufferZone)")
+                .unwrap();
+
+        assert_eq!(audio.entry_cue, EntryCue::BufferZone);
+        assert_eq!(
+            mouse_barrier::EntryCue::from(audio.entry_cue),
+            mouse_barrier::EntryCue::BufferZone
+        );
+    }
+
+    #[test]
+    fn test_coordinate_origin_serialization() {
+        for origin in [CoordinateOrigin::BottomLeft, CoordinateOrigin::TopLeft] {
+            let config = Config {
+                barrier: BarrierConfig {
+                    coordinate_origin: origin,
+                    ..Config::default().barrier
+                },
+                ..Config::default()
+            };
+
+            let ron_string = ron::to_string(&config).unwrap();
+            let restored: Config = ron::from_str(&ron_string).unwrap();
+
+            assert_eq!(restored.barrier.coordinate_origin, origin);
+        }
+    }
+
     #[test]
     fn test_hotkey_config_creation() {
         let config = HotkeyConfig {
@@ -348,12 +1917,19 @@ mod tests {
             alt: false,
             shift: true,
             key: "F12".to_string(),
+            toggle_cooldown_ms: 200,
         };
 
         assert!(config.ctrl);
         assert!(!config.alt);
         assert!(config.shift);
         assert_eq!(config.key, "F12");
+        assert_eq!(config.toggle_cooldown_ms, 200);
+
+        // Hotkey section written before toggle_cooldown_ms existed
+        let config: HotkeyConfig =
+            ron::from_str("(ctrl: true, alt: false, shift: false, key: \"F12\")").unwrap();
+        assert_eq!(config.toggle_cooldown_ms, 150);
     }
 
     #[test]
@@ -363,13 +1939,63 @@ mod tests {
             y: 200,
             width: 300,
             height: 150,
-            buffer_zone: 25,
+            corners: None,
+            buffer_zone: BufferZoneConfig::uniform(25),
+            buffer_blocks: false,
             push_factor: 50,
+            dynamic_push: false,
+            offscreen_push: OffscreenPush::SlideAlongEdge,
+            screen_margin: 8,
+            approach_zone: Some(ApproachZoneConfig {
+                distance: 60,
+                min_scale: 0.3,
+            }),
+            overlay_enabled: false,
             overlay_color: OverlayColor { r: 255, g: 0, b: 0 },
+            proximity_coloring: true,
+            proximity_far_color: OverlayColor { r: 0, g: 0, b: 255 },
+            proximity_range: 300,
             overlay_alpha: 128,
+            overlay_coverage: OverlayCoverage::Both,
+            overlay_failure: OverlayFailurePolicy::FailEnable,
+            interior_alpha: 64,
+            buffer_alpha: Some(150),
+            flash_alpha: None,
+            overlay_animation: OverlayAnimation::Pulse {
+                period_ms: 1500,
+                min_alpha: 40,
+            },
+            overlay_tick_ms: 30,
             audio_feedback: AudioFeedbackConfig {
                 on_barrier_hit: AudioOption::None,
                 on_barrier_entry: AudioOption::File("sound.wav".to_string()),
+                interrupt_previous: false,
+                entry_cue: EntryCue::Barrier,
+            },
+            coordinate_origin: CoordinateOrigin::TopLeft,
+            snap_grid: Some(10),
+            snap_fraction: None,
+            consume_blocked_events: false,
+            resync_after_block: true,
+            enforcement_mode: EnforcementMode::WarnOnly,
+            bypass_hold_ms: 250,
+            bypass_modifier: Some(BypassModifier::Alt),
+            clamp_to_screen: true,
+            anchor: BarrierAnchor::Process("AoE2DE_s.exe".to_string()),
+            edit_mode: Some(EditModeConfig {
+                ctrl: true,
+                alt: true,
+                shift: false,
+                resize_step: 5,
+            }),
+            high_contrast: HighContrastConfig {
+                follow_system: false,
+                color: Some(OverlayColor {
+                    r: 255,
+                    g: 255,
+                    b: 0,
+                }),
+                pattern: HatchPattern::Vertical,
             },
         };
 
@@ -377,12 +2003,51 @@ mod tests {
         assert_eq!(config.y, 200);
         assert_eq!(config.width, 300);
         assert_eq!(config.height, 150);
-        assert_eq!(config.buffer_zone, 25);
+        assert_eq!(config.buffer_zone, BufferZoneConfig::uniform(25));
+        assert!(!config.buffer_blocks);
+        assert!(!config.overlay_enabled);
         assert_eq!(config.push_factor, 50);
+        assert!(!config.dynamic_push);
+        assert_eq!(config.offscreen_push, OffscreenPush::SlideAlongEdge);
+        assert_eq!(config.screen_margin, 8);
+        assert_eq!(
+            config.approach_zone.as_ref().map(|zone| zone.distance),
+            Some(60)
+        );
         assert_eq!(config.overlay_color.r, 255);
         assert_eq!(config.overlay_color.g, 0);
         assert_eq!(config.overlay_color.b, 0);
+        assert!(config.proximity_coloring);
+        assert_eq!(config.proximity_far_color.b, 255);
+        assert_eq!(config.proximity_range, 300);
         assert_eq!(config.overlay_alpha, 128);
+        assert_eq!(config.overlay_coverage, OverlayCoverage::Both);
+        assert_eq!(config.overlay_failure, OverlayFailurePolicy::FailEnable);
+        assert_eq!(config.interior_alpha, 64);
+        assert_eq!(config.buffer_alpha(), 150);
+        assert_eq!(config.flash_alpha(), 128); // Falls back to overlay_alpha
+        assert_eq!(
+            config.overlay_animation,
+            OverlayAnimation::Pulse {
+                period_ms: 1500,
+                min_alpha: 40
+            }
+        );
+        assert_eq!(config.overlay_tick_ms, 30);
+        assert_eq!(config.coordinate_origin, CoordinateOrigin::TopLeft);
+        assert!(!config.consume_blocked_events);
+        assert!(config.resync_after_block);
+        assert_eq!(config.enforcement_mode, EnforcementMode::WarnOnly);
+        assert_eq!(config.bypass_hold_ms, 250);
+        assert_eq!(config.bypass_modifier, Some(BypassModifier::Alt));
+        assert!(config.clamp_to_screen);
+        assert_eq!(
+            config.anchor,
+            BarrierAnchor::Process("AoE2DE_s.exe".to_string())
+        );
+        assert_eq!(config.edit_mode.map(|edit| edit.resize_step), Some(5));
+        assert!(!config.high_contrast.follow_system);
+        assert_eq!(config.high_contrast.pattern, HatchPattern::Vertical);
 
         match config.audio_feedback.on_barrier_hit {
             AudioOption::None => {}
@@ -401,11 +2066,19 @@ mod tests {
             enabled: true,
             position: HudPosition::BottomRight,
             background_alpha: 200,
+            position_update_hz: 120,
+            coordinate_space: HudCoordinateSpace::Screen,
+            reset_uptime_on_reload: true,
+            hud_above_overlay: false,
         };
 
         assert!(config.enabled);
         assert_eq!(config.position, HudPosition::BottomRight);
         assert_eq!(config.background_alpha, 200);
+        assert_eq!(config.position_update_hz, 120);
+        assert_eq!(config.coordinate_space, HudCoordinateSpace::Screen);
+        assert!(config.reset_uptime_on_reload);
+        assert!(!config.hud_above_overlay);
     }
 
     #[test]
@@ -413,8 +2086,13 @@ mod tests {
         let config = AudioFeedbackConfig {
             on_barrier_hit: AudioOption::File("hit.wav".to_string()),
             on_barrier_entry: AudioOption::None,
+            interrupt_previous: true,
+            entry_cue: EntryCue::BufferZone,
         };
 
+        assert!(config.interrupt_previous);
+        assert_eq!(config.entry_cue, EntryCue::BufferZone);
+
         match config.on_barrier_hit {
             AudioOption::File(path) => assert_eq!(path, "hit.wav"),
             _ => panic!("Expected File"),
@@ -447,27 +2125,105 @@ mod tests {
                 alt: true,
                 shift: false,
                 key: "F1".to_string(),
+                toggle_cooldown_ms: 0,
             },
+            action_hotkeys: vec![ActionHotkeyConfig {
+                action: HotkeyAction::DisableBarrier,
+                hotkey: HotkeyConfig {
+                    ctrl: true,
+                    alt: false,
+                    shift: false,
+                    key: "F11".to_string(),
+                    toggle_cooldown_ms: 150,
+                },
+            }],
+            chords: Some(ChordConfig {
+                leader: HotkeyConfig {
+                    ctrl: true,
+                    alt: false,
+                    shift: false,
+                    key: "M".to_string(),
+                    toggle_cooldown_ms: 150,
+                },
+                timeout_ms: 800,
+                suppress_leader: false,
+                bindings: BTreeMap::from([("H".to_string(), HotkeyAction::ToggleHud)]),
+            }),
+            gamepad: Some(GamepadConfig {
+                bypass: Some("RightShoulder".to_string()),
+                actions: [("Back".to_string(), HotkeyAction::ToggleBarrier)].into(),
+            }),
             barrier: BarrierConfig {
                 x: 50,
                 y: 1080,
                 width: 150,
                 height: 75,
-                buffer_zone: 20,
+                corners: None,
+                buffer_zone: BufferZoneConfig::uniform(20),
+                buffer_blocks: true,
                 push_factor: 30,
+                dynamic_push: true,
+                offscreen_push: OffscreenPush::FlipDirection,
+                screen_margin: 0,
+                approach_zone: None,
+                overlay_enabled: true,
                 overlay_color: OverlayColor { r: 0, g: 255, b: 0 },
+                proximity_coloring: false,
+                proximity_far_color: OverlayColor { r: 0, g: 255, b: 0 },
+                proximity_range: 200,
                 overlay_alpha: 100,
+                overlay_coverage: OverlayCoverage::BufferOnly,
+                overlay_failure: OverlayFailurePolicy::ContinueInvisible,
+                interior_alpha: 100,
+                buffer_alpha: None,
+                flash_alpha: Some(255),
+                overlay_animation: OverlayAnimation::Steady,
+                overlay_tick_ms: 50,
                 audio_feedback: AudioFeedbackConfig {
                     on_barrier_hit: AudioOption::File("beep.wav".to_string()),
                     on_barrier_entry: AudioOption::File("enter.wav".to_string()),
+                    interrupt_previous: false,
+                    entry_cue: EntryCue::Barrier,
                 },
+                coordinate_origin: CoordinateOrigin::BottomLeft,
+                snap_grid: None,
+                snap_fraction: None,
+                consume_blocked_events: true,
+                resync_after_block: false,
+                enforcement_mode: EnforcementMode::Enforce,
+                bypass_hold_ms: 0,
+                bypass_modifier: None,
+                clamp_to_screen: false,
+                anchor: BarrierAnchor::Screen,
+                edit_mode: None,
+                high_contrast: HighContrastConfig::default(),
             },
             hud: HudConfig {
                 enabled: false,
                 position: HudPosition::TopLeft,
                 background_alpha: 180,
+                position_update_hz: 0,
+                coordinate_space: HudCoordinateSpace::Config,
+                reset_uptime_on_reload: false,
+                hud_above_overlay: true,
             },
             debug: true,
+            debug_trail: true,
+            debug_overlay: true,
+            startup_grace_ms: 0,
+            colorblind_safe: true,
+            push_marker: PushMarkerConfig {
+                enabled: true,
+                color: OverlayColor {
+                    r: 0,
+                    g: 255,
+                    b: 255,
+                },
+                size: 32,
+                duration_ms: 300,
+            },
+            notify_on_toggle: true,
+            missing_sounds: Vec::new(),
         };
 
         // Verify hotkey config
@@ -475,14 +2231,29 @@ mod tests {
         assert!(config.hotkey.alt);
         assert!(!config.hotkey.shift);
         assert_eq!(config.hotkey.key, "F1");
+        assert_eq!(config.hotkey.toggle_cooldown_ms, 0);
+        assert_eq!(config.action_hotkeys.len(), 1);
+        assert_eq!(
+            config.action_hotkeys[0].action,
+            HotkeyAction::DisableBarrier
+        );
+        let chords = config.chords.as_ref().unwrap();
+        assert_eq!(chords.leader.key, "M");
+        assert_eq!(chords.timeout_ms, 800);
+        assert!(!chords.suppress_leader);
+        assert_eq!(chords.bindings["H"], HotkeyAction::ToggleHud);
+        let gamepad = config.gamepad.as_ref().unwrap();
+        assert_eq!(gamepad.bypass.as_deref(), Some("RightShoulder"));
+        assert_eq!(gamepad.actions["Back"], HotkeyAction::ToggleBarrier);
 
         // Verify barrier config
         assert_eq!(config.barrier.x, 50);
         assert_eq!(config.barrier.y, 1080);
         assert_eq!(config.barrier.width, 150);
         assert_eq!(config.barrier.height, 75);
-        assert_eq!(config.barrier.buffer_zone, 20);
+        assert_eq!(config.barrier.buffer_zone, BufferZoneConfig::uniform(20));
         assert_eq!(config.barrier.push_factor, 30);
+        assert_eq!(config.barrier.offscreen_push, OffscreenPush::FlipDirection);
         assert_eq!(config.barrier.overlay_color.r, 0);
         assert_eq!(config.barrier.overlay_color.g, 255);
         assert_eq!(config.barrier.overlay_color.b, 0);
@@ -492,9 +2263,21 @@ mod tests {
         assert!(!config.hud.enabled);
         assert_eq!(config.hud.position, HudPosition::TopLeft);
         assert_eq!(config.hud.background_alpha, 180);
+        assert_eq!(config.hud.position_update_hz, 0);
+        assert_eq!(config.hud.coordinate_space, HudCoordinateSpace::Config);
+        assert!(!config.hud.reset_uptime_on_reload);
+        assert!(config.hud.hud_above_overlay);
 
-        // Verify debug flag
+        // Verify debug flags
         assert!(config.debug);
+        assert!(config.debug_trail);
+        assert!(config.debug_overlay);
+        assert_eq!(config.startup_grace_ms, 0);
+        assert!(config.colorblind_safe);
+        assert!(config.push_marker.enabled);
+        assert_eq!(config.push_marker.size, 32);
+        assert_eq!(config.push_marker.duration_ms, 300);
+        assert!(config.notify_on_toggle);
     }
 
     #[test]
@@ -625,15 +2408,64 @@ mod tests {
         // Test that default values are reasonable
         assert!(config.hotkey.ctrl); // Default should require Ctrl
         assert_eq!(config.hotkey.key, "F12"); // Default key should be F12
+        assert_eq!(config.hotkey.toggle_cooldown_ms, 150); // Ignore nervous double-presses
         assert_eq!(config.barrier.x, 0); // Default barrier at bottom-left corner
         assert!(config.barrier.y > 0); // Should have a positive Y (screen height)
         assert!(config.barrier.width > 0); // Should have positive width
         assert!(config.barrier.height > 0); // Should have positive height
-        assert!(config.barrier.buffer_zone >= 0); // Buffer zone should be non-negative
+        assert_eq!(config.barrier.corners, None); // Given by x/y/width/height
+        assert_eq!(config.barrier.buffer_zone, BufferZoneConfig::uniform(20)); // Default from config.ron
+        assert!(config.barrier.buffer_blocks); // The buffer zone pushes the cursor
+        assert!(config.barrier.overlay_enabled); // The barrier is drawn
         assert!(config.barrier.push_factor > 0); // Push factor should be positive
+        assert!(config.barrier.dynamic_push); // Faster moves are pushed further
         assert_eq!(config.barrier.overlay_alpha, 200); // Default from config.ron
+        assert!(!config.barrier.proximity_coloring); // Static color unless asked for
+        assert_eq!(config.barrier.buffer_alpha(), 200); // Follows overlay_alpha
+        assert_eq!(config.barrier.flash_alpha(), 255); // Hits stand out
+        assert_eq!(
+            config.barrier.coordinate_origin,
+            CoordinateOrigin::BottomLeft
+        ); // Compatible with configs written before the option existed
+        assert_eq!(config.barrier.offscreen_push, OffscreenPush::ClampToEdge); // Stay on screen
+        assert_eq!(config.barrier.screen_margin, 0); // Pushes may reach the screen edge
+        assert!(config.barrier.audio_feedback.interrupt_previous); // Only the latest cue plays
+        assert!(matches!(
+            &config.barrier.audio_feedback.on_barrier_hit,
+            AudioOption::Builtin(name) if name == "tick"
+        )); // Audible without shipping sound files
+        assert!(matches!(
+            &config.barrier.audio_feedback.on_barrier_entry,
+            AudioOption::Builtin(name) if name == "thunk"
+        ));
+        assert!(config.barrier.consume_blocked_events); // Redirected moves are swallowed
+        assert!(!config.barrier.resync_after_block); // No synthetic input after a push
+        assert_eq!(config.barrier.enforcement_mode, EnforcementMode::Enforce); // Pushes are carried out
+        assert_eq!(config.barrier.bypass_hold_ms, 0); // Middle button bypasses on press
+        assert_eq!(config.barrier.bypass_modifier, None); // No modifier lifts the barrier
+        assert!(!config.barrier.clamp_to_screen); // Off-screen barriers are only reported
+        assert_eq!(config.barrier.anchor, BarrierAnchor::Screen); // Absolute coordinates
+        assert_eq!(config.barrier.edit_mode, None); // The mouse never edits the barrier
+        assert_eq!(config.barrier.snap_grid, None); // Coordinates used as given
+        assert_eq!(config.barrier.snap_fraction, None);
+        assert_eq!(config.barrier.approach_zone, None); // Full speed up to the buffer zone
+        assert_eq!(config.barrier.overlay_animation, OverlayAnimation::Steady); // No pulsing
+        assert_eq!(config.barrier.overlay_tick_ms, 50); // Overlay timer runs at 20 Hz
         assert!(config.hud.enabled); // HUD enabled by default
+        assert_eq!(config.hud.position_update_hz, 60); // Cursor position at 60 Hz
+        assert_eq!(config.hud.coordinate_space, HudCoordinateSpace::Mixed);
+        assert!(!config.hud.reset_uptime_on_reload); // Uptime spans the whole session
+        assert!(config.hud.hud_above_overlay); // HUD drawn over the barrier overlay
         assert!(!config.debug); // Debug disabled by default
+        assert!(!config.debug_trail); // No trail window by default
+        assert!(!config.debug_overlay); // No debug overlay window by default
+        assert_eq!(config.startup_grace_ms, 2000); // Hold back deployment writes at startup
+        assert!(!config.colorblind_safe); // The red and green palette
+        assert!(config.action_hotkeys.is_empty()); // Only the toggle hotkey
+        assert_eq!(config.chords, None); // No key sequences
+        assert_eq!(config.gamepad, None); // No controller polling
+        assert_eq!(config.push_marker, PushMarkerConfig::default()); // No marker
+        assert!(!config.notify_on_toggle); // Toggles only show on the HUD
     }
 
     // Property test generators
@@ -646,9 +2478,16 @@ mod tests {
             Just("test_audio.wav".to_string()),
         ];
 
+        let builtins = prop::sample::select(
+            BuiltinSound::ALL
+                .map(|sound| sound.name().to_string())
+                .to_vec(),
+        );
+
         prop_oneof![
             Just(AudioOption::None),
             safe_paths.prop_map(AudioOption::File),
+            builtins.prop_map(AudioOption::Builtin),
         ]
     }
 
@@ -657,25 +2496,215 @@ mod tests {
     }
 
     fn arb_audio_feedback_config() -> impl Strategy<Value = AudioFeedbackConfig> {
-        (arb_audio_option(), arb_audio_option()).prop_map(|(on_barrier_hit, on_barrier_entry)| {
-            AudioFeedbackConfig {
-                on_barrier_hit,
-                on_barrier_entry,
+        (
+            arb_audio_option(),
+            arb_audio_option(),
+            any::<bool>(),
+            prop_oneof![Just(EntryCue::Barrier), Just(EntryCue::BufferZone)],
+        )
+            .prop_map(
+                |(on_barrier_hit, on_barrier_entry, interrupt_previous, entry_cue)| {
+                    AudioFeedbackConfig {
+                        on_barrier_hit,
+                        on_barrier_entry,
+                        interrupt_previous,
+                        entry_cue,
+                    }
+                },
+            )
+    }
+
+    fn arb_overlay_failure_policy() -> impl Strategy<Value = OverlayFailurePolicy> {
+        prop_oneof![
+            Just(OverlayFailurePolicy::ContinueInvisible),
+            Just(OverlayFailurePolicy::FailEnable),
+        ]
+    }
+
+    fn arb_enforcement_mode() -> impl Strategy<Value = EnforcementMode> {
+        prop_oneof![
+            Just(EnforcementMode::Enforce),
+            Just(EnforcementMode::WarnOnly),
+        ]
+    }
+
+    fn arb_bypass_modifier() -> impl Strategy<Value = BypassModifier> {
+        prop_oneof![
+            Just(BypassModifier::Ctrl),
+            Just(BypassModifier::Alt),
+            Just(BypassModifier::Shift),
+            Just(BypassModifier::Win),
+        ]
+    }
+
+    fn arb_overlay_animation() -> impl Strategy<Value = OverlayAnimation> {
+        prop_oneof![
+            Just(OverlayAnimation::Steady),
+            (1..=10_000u32, any::<u8>()).prop_map(|(period_ms, min_alpha)| {
+                OverlayAnimation::Pulse {
+                    period_ms,
+                    min_alpha,
+                }
+            }),
+        ]
+    }
+
+    fn arb_overlay_coverage() -> impl Strategy<Value = OverlayCoverage> {
+        prop_oneof![
+            Just(OverlayCoverage::BufferOnly),
+            Just(OverlayCoverage::BarrierOnly),
+            Just(OverlayCoverage::Both),
+        ]
+    }
+
+    fn arb_offscreen_push() -> impl Strategy<Value = OffscreenPush> {
+        prop_oneof![
+            Just(OffscreenPush::FlipDirection),
+            Just(OffscreenPush::ClampToEdge),
+            Just(OffscreenPush::SlideAlongEdge),
+        ]
+    }
+
+    fn arb_coordinate_origin() -> impl Strategy<Value = CoordinateOrigin> {
+        prop_oneof![
+            Just(CoordinateOrigin::BottomLeft),
+            Just(CoordinateOrigin::TopLeft),
+        ]
+    }
+
+    fn arb_barrier_anchor() -> impl Strategy<Value = BarrierAnchor> {
+        // Use safe names that won't break RON parsing
+        prop_oneof![
+            Just(BarrierAnchor::Screen),
+            Just(BarrierAnchor::WindowTitle("Age of Empires II".to_string())),
+            Just(BarrierAnchor::Process("AoE2DE_s.exe".to_string())),
+        ]
+    }
+
+    fn arb_snap() -> impl Strategy<Value = (Option<i32>, Option<i32>)> {
+        // At most one grid, since validation rejects both
+        prop_oneof![
+            Just((None, None)),
+            (1..=240i32).prop_map(|grid| (Some(grid), None)),
+            (1..=16i32).prop_map(|parts| (None, Some(parts))),
+        ]
+    }
+
+    fn arb_edit_mode_config() -> impl Strategy<Value = EditModeConfig> {
+        // Ctrl is always held, since validation rejects an edit mode without modifiers
+        (any::<bool>(), any::<bool>(), 1..=100i32).prop_map(|(alt, shift, resize_step)| {
+            EditModeConfig {
+                ctrl: true,
+                alt,
+                shift,
+                resize_step,
             }
         })
     }
 
+    fn arb_approach_zone_config() -> impl Strategy<Value = ApproachZoneConfig> {
+        (1..=500i32, 0.01f32..=1.0).prop_map(|(distance, min_scale)| ApproachZoneConfig {
+            distance,
+            min_scale,
+        })
+    }
+
+    fn arb_buffer_zone_config(
+        widths: impl Strategy<Value = i32> + Clone,
+    ) -> impl Strategy<Value = BufferZoneConfig> {
+        prop_oneof![
+            widths.clone().prop_map(BufferZoneConfig::uniform),
+            (widths.clone(), widths.clone(), widths.clone(), widths).prop_map(
+                |(top, right, bottom, left)| BufferZoneConfig {
+                    top,
+                    right,
+                    bottom,
+                    left,
+                }
+            ),
+        ]
+    }
+
+    fn arb_hatch_pattern() -> impl Strategy<Value = HatchPattern> {
+        prop_oneof![
+            Just(HatchPattern::Horizontal),
+            Just(HatchPattern::Vertical),
+            Just(HatchPattern::Diagonal),
+            Just(HatchPattern::Cross),
+            Just(HatchPattern::DiagonalCross),
+        ]
+    }
+
+    fn arb_high_contrast_config() -> impl Strategy<Value = HighContrastConfig> {
+        (
+            any::<bool>(),
+            prop::option::of(arb_overlay_color()),
+            arb_hatch_pattern(),
+        )
+            .prop_map(|(follow_system, color, pattern)| HighContrastConfig {
+                follow_system,
+                color,
+                pattern,
+            })
+    }
+
     fn arb_barrier_config() -> impl Strategy<Value = BarrierConfig> {
         (
-            any::<i32>(), // x: any position is valid
-            any::<i32>(), // y: any position is valid
-            1..i32::MAX,  // width: must be > 0
-            1..i32::MAX,  // height: must be > 0
-            0..i32::MAX,  // buffer_zone: must be >= 0
-            0..i32::MAX,  // push_factor: must be >= 0
-            arb_overlay_color(),
+            any::<i32>(),                        // x: any position is valid
+            any::<i32>(),                        // y: any position is valid
+            1..i32::MAX,                         // width: must be > 0
+            1..i32::MAX,                         // height: must be > 0
+            arb_buffer_zone_config(0..i32::MAX), // buffer_zone: must be >= 0
+            0..i32::MAX,                         // push_factor: must be >= 0
+            // overlay_color, offscreen_push, proximity_coloring,
+            // proximity_far_color, proximity_range, enforcement_mode,
+            // bypass_hold_ms and bypass_modifier
+            (
+                arb_overlay_color(),
+                arb_offscreen_push(),
+                any::<bool>(),
+                arb_overlay_color(),
+                1..=2000i32,
+                arb_enforcement_mode(),
+                0..=1000u32,
+                prop::option::of(arb_bypass_modifier()),
+            ),
             any::<u8>(), // overlay_alpha: u8 is automatically valid
+            // overlay_enabled, overlay_coverage, overlay_failure,
+            // overlay_animation and overlay_tick_ms
+            (
+                any::<bool>(),
+                arb_overlay_coverage(),
+                arb_overlay_failure_policy(),
+                arb_overlay_animation(),
+                0..=1000u32,
+            ),
+            // interior_alpha (opaque is rejected when the interior is drawn),
+            // buffer_alpha and flash_alpha
+            (
+                0..=254u8,
+                prop::option::of(any::<u8>()),
+                prop::option::of(any::<u8>()),
+            ),
             arb_audio_feedback_config(),
+            // coordinate_origin, consume_blocked_events, resync_after_block,
+            // anchor, snap_grid and snap_fraction, clamp_to_screen, edit_mode, buffer_blocks,
+            // high_contrast, approach_zone, dynamic_push and screen_margin,
+            // nested to stay within proptest's 12-element tuple limit
+            (
+                arb_coordinate_origin(),
+                any::<bool>(),
+                any::<bool>(),
+                arb_barrier_anchor(),
+                arb_snap(),
+                any::<bool>(),
+                prop::option::of(arb_edit_mode_config()),
+                any::<bool>(),
+                arb_high_contrast_config(),
+                prop::option::of(arb_approach_zone_config()),
+                any::<bool>(),
+                0..=100i32,
+            ),
         )
             .prop_map(
                 |(
@@ -685,19 +2714,79 @@ mod tests {
                     height,
                     buffer_zone,
                     push_factor,
-                    overlay_color,
+                    (
+                        overlay_color,
+                        offscreen_push,
+                        proximity_coloring,
+                        proximity_far_color,
+                        proximity_range,
+                        enforcement_mode,
+                        bypass_hold_ms,
+                        bypass_modifier,
+                    ),
                     overlay_alpha,
+                    (
+                        overlay_enabled,
+                        overlay_coverage,
+                        overlay_failure,
+                        overlay_animation,
+                        overlay_tick_ms,
+                    ),
+                    (interior_alpha, buffer_alpha, flash_alpha),
                     audio_feedback,
+                    (
+                        coordinate_origin,
+                        consume_blocked_events,
+                        resync_after_block,
+                        anchor,
+                        (snap_grid, snap_fraction),
+                        clamp_to_screen,
+                        edit_mode,
+                        buffer_blocks,
+                        high_contrast,
+                        approach_zone,
+                        dynamic_push,
+                        screen_margin,
+                    ),
                 )| BarrierConfig {
                     x,
                     y,
                     width,
                     height,
+                    corners: None,
                     buffer_zone,
+                    buffer_blocks,
                     push_factor,
+                    dynamic_push,
+                    offscreen_push,
+                    screen_margin,
+                    approach_zone,
+                    overlay_enabled,
                     overlay_color,
+                    proximity_coloring,
+                    proximity_far_color,
+                    proximity_range,
                     overlay_alpha,
+                    overlay_coverage,
+                    overlay_failure,
+                    interior_alpha,
+                    buffer_alpha,
+                    flash_alpha,
+                    overlay_animation,
+                    overlay_tick_ms,
                     audio_feedback,
+                    coordinate_origin,
+                    snap_grid,
+                    snap_fraction,
+                    consume_blocked_events,
+                    resync_after_block,
+                    enforcement_mode,
+                    bypass_hold_ms,
+                    bypass_modifier,
+                    clamp_to_screen,
+                    anchor,
+                    edit_mode,
+                    high_contrast,
                 },
             )
     }
@@ -711,14 +2800,43 @@ mod tests {
         ]
     }
 
+    fn arb_hud_coordinate_space() -> impl Strategy<Value = HudCoordinateSpace> {
+        prop_oneof![
+            Just(HudCoordinateSpace::Mixed),
+            Just(HudCoordinateSpace::Screen),
+            Just(HudCoordinateSpace::Config),
+        ]
+    }
+
     fn arb_hud_config() -> impl Strategy<Value = HudConfig> {
-        (any::<bool>(), arb_hud_position(), any::<u8>()).prop_map(
-            |(enabled, position, background_alpha)| HudConfig {
-                enabled,
-                position,
-                background_alpha,
-            },
+        (
+            any::<bool>(),
+            arb_hud_position(),
+            any::<u8>(),
+            0..=1000u32,
+            arb_hud_coordinate_space(),
+            any::<bool>(),
+            any::<bool>(),
         )
+            .prop_map(
+                |(
+                    enabled,
+                    position,
+                    background_alpha,
+                    position_update_hz,
+                    coordinate_space,
+                    reset_uptime_on_reload,
+                    hud_above_overlay,
+                )| HudConfig {
+                    enabled,
+                    position,
+                    background_alpha,
+                    position_update_hz,
+                    coordinate_space,
+                    reset_uptime_on_reload,
+                    hud_above_overlay,
+                },
+            )
     }
 
     fn arb_hotkey_config() -> impl Strategy<Value = HotkeyConfig> {
@@ -734,12 +2852,89 @@ mod tests {
             Just("9".to_string()),
         ];
 
-        (any::<bool>(), any::<bool>(), any::<bool>(), valid_keys).prop_map(
-            |(ctrl, alt, shift, key)| HotkeyConfig {
+        (
+            any::<bool>(),
+            any::<bool>(),
+            any::<bool>(),
+            valid_keys,
+            0u32..1000,
+        )
+            .prop_map(|(ctrl, alt, shift, key, toggle_cooldown_ms)| HotkeyConfig {
                 ctrl,
                 alt,
                 shift,
                 key,
+                toggle_cooldown_ms,
+            })
+    }
+
+    fn arb_action_hotkey_config() -> impl Strategy<Value = ActionHotkeyConfig> {
+        (
+            prop_oneof![
+                Just(HotkeyAction::ToggleBarrier),
+                Just(HotkeyAction::EnableBarrier),
+                Just(HotkeyAction::DisableBarrier),
+                Just(HotkeyAction::ToggleEnforcement),
+                Just(HotkeyAction::ToggleHud),
+                Just(HotkeyAction::Pause),
+                Just(HotkeyAction::EmergencyDisable),
+            ],
+            arb_hotkey_config(),
+        )
+            .prop_map(|(action, hotkey)| ActionHotkeyConfig { action, hotkey })
+    }
+
+    fn arb_chord_config() -> impl Strategy<Value = ChordConfig> {
+        (
+            arb_hotkey_config(),
+            0..=5000u32,
+            any::<bool>(),
+            prop::collection::btree_map(
+                prop_oneof![
+                    Just("B".to_string()),
+                    Just("H".to_string()),
+                    Just("1".to_string())
+                ],
+                arb_action_hotkey_config().prop_map(|binding| binding.action),
+                0..3,
+            ),
+        )
+            .prop_map(
+                |(leader, timeout_ms, suppress_leader, bindings)| ChordConfig {
+                    leader,
+                    timeout_ms,
+                    suppress_leader,
+                    bindings,
+                },
+            )
+    }
+
+    fn arb_gamepad_config() -> impl Strategy<Value = GamepadConfig> {
+        let button = || {
+            prop_oneof![
+                Just("A".to_string()),
+                Just("Back".to_string()),
+                Just("RightShoulder".to_string())
+            ]
+        };
+        (
+            prop::option::of(button()),
+            prop::collection::btree_map(
+                button(),
+                arb_action_hotkey_config().prop_map(|binding| binding.action),
+                0..3,
+            ),
+        )
+            .prop_map(|(bypass, actions)| GamepadConfig { bypass, actions })
+    }
+
+    fn arb_push_marker_config() -> impl Strategy<Value = PushMarkerConfig> {
+        (any::<bool>(), arb_overlay_color(), 8..=256i32, 1..=2000u32).prop_map(
+            |(enabled, color, size, duration_ms)| PushMarkerConfig {
+                enabled,
+                color,
+                size,
+                duration_ms,
             },
         )
     }
@@ -747,16 +2942,55 @@ mod tests {
     fn arb_config() -> impl Strategy<Value = Config> {
         (
             arb_hotkey_config(),
+            prop::collection::vec(arb_action_hotkey_config(), 0..3),
+            // Paired to stay within proptest's 12-element tuples
+            (
+                prop::option::of(arb_chord_config()),
+                prop::option::of(arb_gamepad_config()),
+            ),
             arb_barrier_config(),
             arb_hud_config(),
             any::<bool>(),
+            any::<bool>(),
+            any::<bool>(),
+            0u32..10_000,
+            any::<bool>(),
+            arb_push_marker_config(),
+            any::<bool>(),
         )
-            .prop_map(|(hotkey, barrier, hud, debug)| Config {
-                hotkey,
-                barrier,
-                hud,
-                debug,
-            })
+            .prop_map(
+                |(
+                    hotkey,
+                    action_hotkeys,
+                    (chords, gamepad),
+                    barrier,
+                    hud,
+                    debug,
+                    debug_trail,
+                    debug_overlay,
+                    startup_grace_ms,
+                    colorblind_safe,
+                    push_marker,
+                    notify_on_toggle,
+                )| {
+                    Config {
+                        hotkey,
+                        action_hotkeys,
+                        chords,
+                        gamepad,
+                        barrier,
+                        hud,
+                        debug,
+                        debug_trail,
+                        debug_overlay,
+                        startup_grace_ms,
+                        colorblind_safe,
+                        push_marker,
+                        notify_on_toggle,
+                        missing_sounds: Vec::new(),
+                    }
+                },
+            )
     }
 
     // Generators for invalid values (for testing validation failures)
@@ -772,17 +3006,63 @@ mod tests {
                 ..=0i32,     // invalid height: <= 0
                 1..i32::MAX, // valid height (some configs should still be valid)
             ],
-            prop_oneof![
-                i32::MIN..-1, // invalid buffer_zone: < 0
-                0..i32::MAX,  // valid buffer_zone (some configs should still be valid)
-            ],
+            arb_buffer_zone_config(prop_oneof![
+                i32::MIN..-1, // invalid buffer_zone edge: < 0
+                0..i32::MAX,  // valid buffer_zone edge (some configs should still be valid)
+            ]),
             prop_oneof![
                 i32::MIN..-1, // invalid push_factor: < 0
                 0..i32::MAX,  // valid push_factor (some configs should still be valid)
             ],
-            arb_overlay_color(),
+            // overlay_color, offscreen_push, proximity_coloring,
+            // proximity_far_color, proximity_range, enforcement_mode,
+            // bypass_hold_ms and bypass_modifier
+            (
+                arb_overlay_color(),
+                arb_offscreen_push(),
+                any::<bool>(),
+                arb_overlay_color(),
+                1..=2000i32,
+                arb_enforcement_mode(),
+                0..=1000u32,
+                prop::option::of(arb_bypass_modifier()),
+            ),
             any::<u8>(), // overlay_alpha: u8 is automatically valid
+            // overlay_enabled, overlay_coverage, overlay_failure,
+            // overlay_animation and overlay_tick_ms
+            (
+                any::<bool>(),
+                arb_overlay_coverage(),
+                arb_overlay_failure_policy(),
+                arb_overlay_animation(),
+                0..=1000u32,
+            ),
+            // interior_alpha (opaque is rejected when the interior is drawn),
+            // buffer_alpha and flash_alpha
+            (
+                0..=254u8,
+                prop::option::of(any::<u8>()),
+                prop::option::of(any::<u8>()),
+            ),
             arb_audio_feedback_config(),
+            // coordinate_origin, consume_blocked_events, resync_after_block,
+            // anchor, snap_grid and snap_fraction, clamp_to_screen, edit_mode, buffer_blocks,
+            // high_contrast, approach_zone, dynamic_push and screen_margin,
+            // nested to stay within proptest's 12-element tuple limit
+            (
+                arb_coordinate_origin(),
+                any::<bool>(),
+                any::<bool>(),
+                arb_barrier_anchor(),
+                arb_snap(),
+                any::<bool>(),
+                prop::option::of(arb_edit_mode_config()),
+                any::<bool>(),
+                arb_high_contrast_config(),
+                prop::option::of(arb_approach_zone_config()),
+                any::<bool>(),
+                0..=100i32,
+            ),
         )
             .prop_map(
                 |(
@@ -792,36 +3072,134 @@ mod tests {
                     height,
                     buffer_zone,
                     push_factor,
-                    overlay_color,
+                    (
+                        overlay_color,
+                        offscreen_push,
+                        proximity_coloring,
+                        proximity_far_color,
+                        proximity_range,
+                        enforcement_mode,
+                        bypass_hold_ms,
+                        bypass_modifier,
+                    ),
                     overlay_alpha,
+                    (
+                        overlay_enabled,
+                        overlay_coverage,
+                        overlay_failure,
+                        overlay_animation,
+                        overlay_tick_ms,
+                    ),
+                    (interior_alpha, buffer_alpha, flash_alpha),
                     audio_feedback,
+                    (
+                        coordinate_origin,
+                        consume_blocked_events,
+                        resync_after_block,
+                        anchor,
+                        (snap_grid, snap_fraction),
+                        clamp_to_screen,
+                        edit_mode,
+                        buffer_blocks,
+                        high_contrast,
+                        approach_zone,
+                        dynamic_push,
+                        screen_margin,
+                    ),
                 )| BarrierConfig {
                     x,
                     y,
                     width,
                     height,
+                    corners: None,
                     buffer_zone,
+                    buffer_blocks,
                     push_factor,
+                    dynamic_push,
+                    offscreen_push,
+                    screen_margin,
+                    approach_zone,
+                    overlay_enabled,
                     overlay_color,
+                    proximity_coloring,
+                    proximity_far_color,
+                    proximity_range,
                     overlay_alpha,
+                    overlay_coverage,
+                    overlay_failure,
+                    interior_alpha,
+                    buffer_alpha,
+                    flash_alpha,
+                    overlay_animation,
+                    overlay_tick_ms,
                     audio_feedback,
+                    coordinate_origin,
+                    snap_grid,
+                    snap_fraction,
+                    consume_blocked_events,
+                    resync_after_block,
+                    enforcement_mode,
+                    bypass_hold_ms,
+                    bypass_modifier,
+                    clamp_to_screen,
+                    anchor,
+                    edit_mode,
+                    high_contrast,
                 },
             )
     }
 
     fn arb_invalid_config() -> impl Strategy<Value = Config> {
         (
-            arb_hotkey_config(),          // hotkey: always valid (no validation needed)
+            arb_hotkey_config(), // hotkey: always valid (no validation needed)
+            prop::collection::vec(arb_action_hotkey_config(), 0..3), // action_hotkeys: likewise
+            (
+                prop::option::of(arb_chord_config()),
+                prop::option::of(arb_gamepad_config()),
+            ), // chords and gamepad: likewise, paired to stay within 12 elements
             arb_invalid_barrier_config(), // barrier: may have invalid values
-            arb_hud_config(),             // hud: always valid (no validation needed)
-            any::<bool>(),                // debug: always valid
+            arb_hud_config(),    // hud: always valid (no validation needed)
+            any::<bool>(),       // debug: always valid
+            any::<bool>(),       // debug_trail: always valid
+            any::<bool>(),       // debug_overlay: always valid
+            0u32..10_000,        // startup_grace_ms: always valid
+            any::<bool>(),       // colorblind_safe: always valid
+            arb_push_marker_config(), // push_marker: valid here; tested on its own
+            any::<bool>(),       // notify_on_toggle: always valid
         )
-            .prop_map(|(hotkey, barrier, hud, debug)| Config {
-                hotkey,
-                barrier,
-                hud,
-                debug,
-            })
+            .prop_map(
+                |(
+                    hotkey,
+                    action_hotkeys,
+                    (chords, gamepad),
+                    barrier,
+                    hud,
+                    debug,
+                    debug_trail,
+                    debug_overlay,
+                    startup_grace_ms,
+                    colorblind_safe,
+                    push_marker,
+                    notify_on_toggle,
+                )| {
+                    Config {
+                        hotkey,
+                        action_hotkeys,
+                        chords,
+                        gamepad,
+                        barrier,
+                        hud,
+                        debug,
+                        debug_trail,
+                        debug_overlay,
+                        startup_grace_ms,
+                        colorblind_safe,
+                        push_marker,
+                        notify_on_toggle,
+                        missing_sounds: Vec::new(),
+                    }
+                },
+            )
     }
 
     proptest! {
@@ -838,34 +3216,87 @@ mod tests {
             prop_assert_eq!(restored.hotkey.alt, config.hotkey.alt);
             prop_assert_eq!(restored.hotkey.shift, config.hotkey.shift);
             prop_assert_eq!(restored.hotkey.key, config.hotkey.key);
+            prop_assert_eq!(restored.hotkey.toggle_cooldown_ms, config.hotkey.toggle_cooldown_ms);
 
             prop_assert_eq!(restored.barrier.x, config.barrier.x);
             prop_assert_eq!(restored.barrier.y, config.barrier.y);
             prop_assert_eq!(restored.barrier.width, config.barrier.width);
             prop_assert_eq!(restored.barrier.height, config.barrier.height);
+            prop_assert_eq!(restored.barrier.corners, config.barrier.corners);
             prop_assert_eq!(restored.barrier.buffer_zone, config.barrier.buffer_zone);
+            prop_assert_eq!(restored.barrier.buffer_blocks, config.barrier.buffer_blocks);
+            prop_assert_eq!(restored.barrier.overlay_enabled, config.barrier.overlay_enabled);
             prop_assert_eq!(restored.barrier.push_factor, config.barrier.push_factor);
+            prop_assert_eq!(restored.barrier.dynamic_push, config.barrier.dynamic_push);
+            prop_assert_eq!(restored.barrier.offscreen_push, config.barrier.offscreen_push);
+            prop_assert_eq!(restored.barrier.screen_margin, config.barrier.screen_margin);
             prop_assert_eq!(restored.barrier.overlay_color.r, config.barrier.overlay_color.r);
             prop_assert_eq!(restored.barrier.overlay_color.g, config.barrier.overlay_color.g);
             prop_assert_eq!(restored.barrier.overlay_color.b, config.barrier.overlay_color.b);
+            prop_assert_eq!(restored.barrier.proximity_coloring, config.barrier.proximity_coloring);
+            prop_assert_eq!(restored.barrier.proximity_far_color.g, config.barrier.proximity_far_color.g);
+            prop_assert_eq!(restored.barrier.proximity_range, config.barrier.proximity_range);
             prop_assert_eq!(restored.barrier.overlay_alpha, config.barrier.overlay_alpha);
+            prop_assert_eq!(restored.barrier.overlay_coverage, config.barrier.overlay_coverage);
+            prop_assert_eq!(restored.barrier.overlay_failure, config.barrier.overlay_failure);
+            prop_assert_eq!(restored.barrier.interior_alpha, config.barrier.interior_alpha);
+            prop_assert_eq!(restored.barrier.buffer_alpha, config.barrier.buffer_alpha);
+            prop_assert_eq!(restored.barrier.flash_alpha, config.barrier.flash_alpha);
+            prop_assert_eq!(restored.barrier.overlay_animation, config.barrier.overlay_animation);
+            prop_assert_eq!(restored.barrier.overlay_tick_ms, config.barrier.overlay_tick_ms);
+            prop_assert_eq!(restored.barrier.coordinate_origin, config.barrier.coordinate_origin);
+            prop_assert_eq!(restored.barrier.snap_grid, config.barrier.snap_grid);
+            prop_assert_eq!(restored.barrier.snap_fraction, config.barrier.snap_fraction);
+            prop_assert_eq!(&restored.barrier.approach_zone, &config.barrier.approach_zone);
+            prop_assert_eq!(restored.barrier.consume_blocked_events, config.barrier.consume_blocked_events);
+            prop_assert_eq!(restored.barrier.resync_after_block, config.barrier.resync_after_block);
+            prop_assert_eq!(restored.barrier.enforcement_mode, config.barrier.enforcement_mode);
+            prop_assert_eq!(restored.barrier.bypass_hold_ms, config.barrier.bypass_hold_ms);
+            prop_assert_eq!(restored.barrier.bypass_modifier, config.barrier.bypass_modifier);
+            prop_assert_eq!(restored.barrier.clamp_to_screen, config.barrier.clamp_to_screen);
+            prop_assert_eq!(&restored.barrier.anchor, &config.barrier.anchor);
+            prop_assert_eq!(&restored.barrier.edit_mode, &config.barrier.edit_mode);
+            prop_assert_eq!(&restored.barrier.high_contrast, &config.barrier.high_contrast);
+            prop_assert_eq!(
+                restored.barrier.audio_feedback.interrupt_previous,
+                config.barrier.audio_feedback.interrupt_previous
+            );
+            prop_assert_eq!(
+                restored.barrier.audio_feedback.entry_cue,
+                config.barrier.audio_feedback.entry_cue
+            );
 
             prop_assert_eq!(restored.hud.enabled, config.hud.enabled);
             prop_assert_eq!(restored.hud.position, config.hud.position);
             prop_assert_eq!(restored.hud.background_alpha, config.hud.background_alpha);
+            prop_assert_eq!(restored.hud.position_update_hz, config.hud.position_update_hz);
+            prop_assert_eq!(restored.hud.coordinate_space, config.hud.coordinate_space);
+            prop_assert_eq!(restored.hud.reset_uptime_on_reload, config.hud.reset_uptime_on_reload);
+            prop_assert_eq!(restored.hud.hud_above_overlay, config.hud.hud_above_overlay);
 
             prop_assert_eq!(restored.debug, config.debug);
+            prop_assert_eq!(restored.debug_trail, config.debug_trail);
+            prop_assert_eq!(restored.startup_grace_ms, config.startup_grace_ms);
+            prop_assert_eq!(restored.debug_overlay, config.debug_overlay);
+            prop_assert_eq!(restored.colorblind_safe, config.colorblind_safe);
+            prop_assert_eq!(&restored.action_hotkeys, &config.action_hotkeys);
+            prop_assert_eq!(&restored.chords, &config.chords);
+            prop_assert_eq!(&restored.gamepad, &config.gamepad);
+            prop_assert_eq!(&restored.push_marker, &config.push_marker);
+            prop_assert_eq!(restored.notify_on_toggle, config.notify_on_toggle);
 
             // Verify audio feedback options
             match (&config.barrier.audio_feedback.on_barrier_hit, &restored.barrier.audio_feedback.on_barrier_hit) {
                 (AudioOption::None, AudioOption::None) => {},
                 (AudioOption::File(orig), AudioOption::File(rest)) => prop_assert_eq!(orig, rest),
+                (AudioOption::Builtin(orig), AudioOption::Builtin(rest)) => prop_assert_eq!(orig, rest),
                 _ => prop_assert!(false, "Audio option mismatch for on_barrier_hit"),
             }
 
             match (&config.barrier.audio_feedback.on_barrier_entry, &restored.barrier.audio_feedback.on_barrier_entry) {
                 (AudioOption::None, AudioOption::None) => {},
                 (AudioOption::File(orig), AudioOption::File(rest)) => prop_assert_eq!(orig, rest),
+                (AudioOption::Builtin(orig), AudioOption::Builtin(rest)) => prop_assert_eq!(orig, rest),
                 _ => prop_assert!(false, "Audio option mismatch for on_barrier_entry"),
             }
         }
@@ -926,12 +3357,14 @@ mod tests {
                 prop_assert_eq!(layered_config.hotkey.alt, default_config.hotkey.alt);
                 prop_assert_eq!(layered_config.hotkey.shift, default_config.hotkey.shift);
                 prop_assert_eq!(layered_config.hotkey.key, default_config.hotkey.key);
+                prop_assert_eq!(layered_config.hotkey.toggle_cooldown_ms, default_config.hotkey.toggle_cooldown_ms);
             } else {
                 // All hotkey fields should be defaults
                 prop_assert_eq!(layered_config.hotkey.ctrl, default_config.hotkey.ctrl);
                 prop_assert_eq!(layered_config.hotkey.alt, default_config.hotkey.alt);
                 prop_assert_eq!(layered_config.hotkey.shift, default_config.hotkey.shift);
                 prop_assert_eq!(layered_config.hotkey.key, default_config.hotkey.key);
+                prop_assert_eq!(layered_config.hotkey.toggle_cooldown_ms, default_config.hotkey.toggle_cooldown_ms);
             }
 
             if include_barrier_x {
@@ -953,11 +3386,16 @@ mod tests {
                 // Other hud fields should be defaults since we only set enabled
                 prop_assert_eq!(layered_config.hud.position, default_config.hud.position);
                 prop_assert_eq!(layered_config.hud.background_alpha, default_config.hud.background_alpha);
+                prop_assert_eq!(layered_config.hud.position_update_hz, default_config.hud.position_update_hz);
+                prop_assert_eq!(layered_config.hud.coordinate_space, default_config.hud.coordinate_space);
+                prop_assert_eq!(layered_config.hud.reset_uptime_on_reload, default_config.hud.reset_uptime_on_reload);
+                prop_assert_eq!(layered_config.hud.hud_above_overlay, default_config.hud.hud_above_overlay);
             } else {
                 // All hud fields should be defaults
                 prop_assert_eq!(layered_config.hud.enabled, default_config.hud.enabled);
                 prop_assert_eq!(layered_config.hud.position, default_config.hud.position);
                 prop_assert_eq!(layered_config.hud.background_alpha, default_config.hud.background_alpha);
+                prop_assert_eq!(layered_config.hud.position_update_hz, default_config.hud.position_update_hz);
             }
 
             if include_debug {
@@ -968,11 +3406,44 @@ mod tests {
 
             // All other fields should always be defaults since we never override them
             prop_assert_eq!(layered_config.barrier.buffer_zone, default_config.barrier.buffer_zone);
+            prop_assert_eq!(layered_config.barrier.buffer_blocks, default_config.barrier.buffer_blocks);
+            prop_assert_eq!(layered_config.barrier.overlay_enabled, default_config.barrier.overlay_enabled);
             prop_assert_eq!(layered_config.barrier.push_factor, default_config.barrier.push_factor);
+            prop_assert_eq!(layered_config.barrier.dynamic_push, default_config.barrier.dynamic_push);
+            prop_assert_eq!(layered_config.barrier.offscreen_push, default_config.barrier.offscreen_push);
+            prop_assert_eq!(layered_config.barrier.screen_margin, default_config.barrier.screen_margin);
             prop_assert_eq!(layered_config.barrier.overlay_alpha, default_config.barrier.overlay_alpha);
+            prop_assert_eq!(layered_config.barrier.proximity_coloring, default_config.barrier.proximity_coloring);
+            prop_assert_eq!(layered_config.barrier.proximity_range, default_config.barrier.proximity_range);
+            prop_assert_eq!(layered_config.barrier.buffer_alpha, default_config.barrier.buffer_alpha);
+            prop_assert_eq!(layered_config.barrier.flash_alpha, default_config.barrier.flash_alpha);
             prop_assert_eq!(layered_config.barrier.overlay_color.r, default_config.barrier.overlay_color.r);
             prop_assert_eq!(layered_config.barrier.overlay_color.g, default_config.barrier.overlay_color.g);
             prop_assert_eq!(layered_config.barrier.overlay_color.b, default_config.barrier.overlay_color.b);
+            prop_assert_eq!(layered_config.barrier.coordinate_origin, default_config.barrier.coordinate_origin);
+            prop_assert_eq!(layered_config.barrier.snap_grid, default_config.barrier.snap_grid);
+            prop_assert_eq!(layered_config.barrier.snap_fraction, default_config.barrier.snap_fraction);
+            prop_assert_eq!(&layered_config.barrier.approach_zone, &default_config.barrier.approach_zone);
+            prop_assert_eq!(layered_config.barrier.consume_blocked_events, default_config.barrier.consume_blocked_events);
+            prop_assert_eq!(layered_config.barrier.resync_after_block, default_config.barrier.resync_after_block);
+            prop_assert_eq!(layered_config.barrier.enforcement_mode, default_config.barrier.enforcement_mode);
+            prop_assert_eq!(layered_config.barrier.bypass_hold_ms, default_config.barrier.bypass_hold_ms);
+            prop_assert_eq!(layered_config.barrier.bypass_modifier, default_config.barrier.bypass_modifier);
+            prop_assert_eq!(layered_config.barrier.clamp_to_screen, default_config.barrier.clamp_to_screen);
+            prop_assert_eq!(&layered_config.barrier.anchor, &default_config.barrier.anchor);
+            prop_assert_eq!(&layered_config.barrier.edit_mode, &default_config.barrier.edit_mode);
+            prop_assert_eq!(&layered_config.barrier.high_contrast, &default_config.barrier.high_contrast);
+            prop_assert_eq!(layered_config.barrier.overlay_failure, default_config.barrier.overlay_failure);
+            prop_assert_eq!(layered_config.barrier.overlay_animation, default_config.barrier.overlay_animation);
+            prop_assert_eq!(layered_config.barrier.overlay_tick_ms, default_config.barrier.overlay_tick_ms);
+            prop_assert_eq!(layered_config.startup_grace_ms, default_config.startup_grace_ms);
+            prop_assert_eq!(layered_config.debug_overlay, default_config.debug_overlay);
+            prop_assert_eq!(layered_config.colorblind_safe, default_config.colorblind_safe);
+            prop_assert_eq!(&layered_config.action_hotkeys, &default_config.action_hotkeys);
+            prop_assert_eq!(&layered_config.chords, &default_config.chords);
+            prop_assert_eq!(&layered_config.gamepad, &default_config.gamepad);
+            prop_assert_eq!(&layered_config.push_marker, &default_config.push_marker);
+            prop_assert_eq!(layered_config.notify_on_toggle, default_config.notify_on_toggle);
         }
 
         #[test]
@@ -986,7 +3457,7 @@ mod tests {
             // Check if this config has any invalid values that should cause validation to fail
             let has_invalid_width = config.barrier.width <= 0;
             let has_invalid_height = config.barrier.height <= 0;
-            let has_invalid_buffer_zone = config.barrier.buffer_zone < 0;
+            let has_invalid_buffer_zone = config.barrier.buffer_zone.edges().iter().any(|&(_, width)| width < 0);
             let has_invalid_push_factor = config.barrier.push_factor < 0;
 
             let should_fail = has_invalid_width || has_invalid_height || has_invalid_buffer_zone || has_invalid_push_factor;
@@ -1005,7 +3476,7 @@ mod tests {
             // Check if this config should fail validation
             let has_invalid_width = config.barrier.width <= 0;
             let has_invalid_height = config.barrier.height <= 0;
-            let has_invalid_buffer_zone = config.barrier.buffer_zone < 0;
+            let has_invalid_buffer_zone = config.barrier.buffer_zone.edges().iter().any(|&(_, width)| width < 0);
             let has_invalid_push_factor = config.barrier.push_factor < 0;
 
             let should_fail = has_invalid_width || has_invalid_height || has_invalid_buffer_zone || has_invalid_push_factor;
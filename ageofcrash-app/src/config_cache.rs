@@ -0,0 +1,200 @@
+use crate::config::Config;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+/// Written ahead of the cached config; a cache from another version is ignored
+const CACHE_TAG: &str = concat!("ageofcrash-config-cache ", env!("CARGO_PKG_VERSION"));
+
+/// Loads the config at `path` like [`Config::load_or_create`], going through a
+/// binary copy of the resolved config kept next to it.
+///
+/// The cache is used while it is newer than both `path` and this executable,
+/// skipping RON parsing and layering; otherwise the config is loaded in full
+/// and the cache written again. `path` stays the source of truth, so the cache
+/// can be deleted at any time.
+pub fn load_or_create_cached(path: &str) -> Result<Config, Box<dyn std::error::Error>> {
+    let cache = cache_path(Path::new(path));
+    if let Some(mut config) = load_cache(Path::new(path), &cache) {
+        info!("Loaded config from cache {}", cache.display());
+        // The cache keeps resolved paths, which say nothing of whether the
+        // files are still there
+        config.missing_sounds = config.barrier.audio_feedback.drop_missing_sounds();
+        return Ok(config);
+    }
+
+    let config = Config::load_or_create(path)?;
+    // A cached config would keep playing nothing for a missing sound after
+    // the file turns up, so only a complete one is cached
+    if !config.missing_sounds.is_empty() {
+        return Ok(config);
+    }
+    // A cache that can't be written only costs the next startup some time
+    if let Err(e) = write_cache(&cache, &config) {
+        warn!("Failed to write config cache {}: {}", cache.display(), e);
+    }
+    Ok(config)
+}
+
+/// Where the cache for the config at `path` lives: `config.ron.cache` for
+/// `config.ron`
+pub fn cache_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".cache");
+    PathBuf::from(name)
+}
+
+/// The cached config, if the cache is fresh and readable
+fn load_cache(path: &Path, cache: &Path) -> Option<Config> {
+    let cache_modified = modified(cache)?;
+    let sources = [Some(path.to_path_buf()), std::env::current_exe().ok()];
+    let sources_modified = sources
+        .iter()
+        .flatten()
+        .map(|source| modified(source))
+        .collect::<Option<Vec<_>>>()?;
+    if !is_fresh(cache_modified, &sources_modified) {
+        return None;
+    }
+
+    let bytes = std::fs::read(cache).ok()?;
+    let (tag, config): (String, Config) = bincode::deserialize(&bytes).ok()?;
+    // Cheap next to parsing, and keeps a tampered cache from skipping validation
+    (tag == CACHE_TAG && config.validate().is_ok()).then_some(config)
+}
+
+fn write_cache(cache: &Path, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = bincode::serialize(&(CACHE_TAG, config))?;
+    std::fs::write(cache, bytes)?;
+    Ok(())
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+/// Whether a cache written at `cache` is newer than every one of `sources`
+fn is_fresh(cache: SystemTime, sources: &[SystemTime]) -> bool {
+    sources.iter().all(|&source| cache > source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AudioOption;
+    use std::fs::File;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn set_modified(path: &Path, time: SystemTime) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_cache_path_sits_next_to_the_config() {
+        assert_eq!(
+            cache_path(Path::new(r"C:\ageofcrash\config.ron")),
+            PathBuf::from(r"C:\ageofcrash\config.ron.cache")
+        );
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let now = SystemTime::now();
+        let earlier = now - Duration::from_secs(5);
+        assert!(is_fresh(now, &[earlier, earlier]));
+        assert!(!is_fresh(now, &[earlier, now]));
+        assert!(!is_fresh(earlier, &[now]));
+    }
+
+    #[test]
+    fn test_cache_is_used_until_the_config_changes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.ron");
+        let path_str = path.to_str().unwrap();
+        let cache = cache_path(&path);
+
+        // Creates the config and the cache
+        let config = load_or_create_cached(path_str).unwrap();
+        assert!(cache.exists());
+
+        // A cache newer than the config wins over it
+        let mut cached = config.clone();
+        cached.barrier.x = 1234;
+        write_cache(&cache, &cached).unwrap();
+        let now = SystemTime::now();
+        set_modified(&path, now - Duration::from_secs(60));
+        set_modified(&cache, now);
+        assert_eq!(load_or_create_cached(path_str).unwrap().barrier.x, 1234);
+
+        // An edited config makes it stale, and it is regenerated
+        set_modified(&path, now + Duration::from_secs(60));
+        assert_eq!(
+            load_or_create_cached(path_str).unwrap().barrier.x,
+            config.barrier.x
+        );
+        let bytes = std::fs::read(&cache).unwrap();
+        let (_, regenerated): (String, Config) = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(regenerated.barrier.x, config.barrier.x);
+    }
+
+    #[test]
+    fn test_cached_sounds_are_checked_again() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.ron");
+        let path_str = path.to_str().unwrap();
+        let sound = dir.path().join("hit.wav");
+        let mut config = Config::default();
+        config.barrier.audio_feedback.on_barrier_hit = AudioOption::File("hit.wav".to_string());
+        config.save(path_str).unwrap();
+
+        // Not cached while the sound is missing, so it's found once it turns up
+        let loaded = load_or_create_cached(path_str).unwrap();
+        assert_eq!(loaded.missing_sounds, ["hit.wav"]);
+        assert!(!cache_path(&path).exists());
+
+        std::fs::write(&sound, b"RIFF").unwrap();
+        let loaded = load_or_create_cached(path_str).unwrap();
+        assert!(loaded.missing_sounds.is_empty());
+        assert!(cache_path(&path).exists());
+
+        // Deleted while cached: the cache hit still drops it
+        std::fs::remove_file(&sound).unwrap();
+        let now = SystemTime::now();
+        set_modified(&path, now - Duration::from_secs(60));
+        set_modified(&cache_path(&path), now);
+        let loaded = load_or_create_cached(path_str).unwrap();
+        assert_eq!(
+            loaded.barrier.audio_feedback.on_barrier_hit,
+            AudioOption::None
+        );
+        assert_eq!(loaded.missing_sounds.len(), 1);
+    }
+
+    #[test]
+    fn test_unreadable_cache_falls_back_to_the_config() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.ron");
+        let config = Config::load_or_create(path.to_str().unwrap()).unwrap();
+
+        let cache = cache_path(&path);
+        std::fs::write(&cache, b"not a config").unwrap();
+        set_modified(&path, SystemTime::now() - Duration::from_secs(60));
+
+        assert_eq!(load_cache(&path, &cache).map(|c| c.barrier.x), None);
+        assert_eq!(
+            load_or_create_cached(path.to_str().unwrap())
+                .unwrap()
+                .barrier
+                .x,
+            config.barrier.x
+        );
+    }
+}
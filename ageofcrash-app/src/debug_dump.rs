@@ -0,0 +1,163 @@
+use mouse_barrier::{BarrierDecision, DecisionKind};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+pub const CSV_HEADER: &str = "elapsed_us,kind,input_x,input_y,last_x,last_y,output_x,output_y";
+
+/// Mirrors every barrier decision to a CSV file for offline analysis.
+///
+/// One row per decision, timed in microseconds from when the dump started.
+/// `last_x`/`last_y` are empty when the hook had no previous position.
+pub struct DebugDump<W: Write = BufWriter<File>> {
+    writer: W,
+    start: Instant,
+    failed: bool,
+}
+
+impl DebugDump {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> DebugDump<W> {
+    fn new(mut writer: W) -> io::Result<Self> {
+        writeln!(writer, "{}", CSV_HEADER)?;
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+            failed: false,
+        })
+    }
+
+    /// Appends a row. After the first write error the dump stops writing, so
+    /// a full disk is reported once rather than on every cursor move.
+    pub fn record(&mut self, decision: &BarrierDecision) -> io::Result<()> {
+        if self.failed {
+            return Ok(());
+        }
+
+        let result = self.write_row(decision);
+        self.failed = result.is_err();
+        result
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn write_row(&mut self, decision: &BarrierDecision) -> io::Result<()> {
+        let elapsed = decision.timestamp.saturating_duration_since(self.start);
+        let (last_x, last_y) = match decision.last {
+            Some(last) => (last.x.to_string(), last.y.to_string()),
+            None => (String::new(), String::new()),
+        };
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{},{},{}",
+            elapsed.as_micros(),
+            kind_name(decision.kind),
+            decision.input.x,
+            decision.input.y,
+            last_x,
+            last_y,
+            decision.output.x,
+            decision.output.y
+        )
+    }
+}
+
+/// The `kind` column for `kind`
+pub fn kind_name(kind: DecisionKind) -> &'static str {
+    match kind {
+        DecisionKind::PassThrough => "pass",
+        DecisionKind::TrajectoryBlock => "trajectory_block",
+        DecisionKind::BufferPush => "buffer_push",
+        DecisionKind::ApproachSlowdown => "approach_slowdown",
+        DecisionKind::CorrectivePush => "corrective_push",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mouse_barrier::Point;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rows() {
+        let mut dump = DebugDump::new(Vec::new()).unwrap();
+        let start = dump.start;
+
+        dump.record(&BarrierDecision {
+            input: Point { x: 10, y: 20 },
+            last: None,
+            kind: DecisionKind::PassThrough,
+            output: Point { x: 10, y: 20 },
+            timestamp: start + Duration::from_micros(1500),
+        })
+        .unwrap();
+        dump.record(&BarrierDecision {
+            input: Point { x: 150, y: 105 },
+            last: Some(Point { x: -5, y: 105 }),
+            kind: DecisionKind::TrajectoryBlock,
+            output: Point { x: 89, y: 105 },
+            timestamp: start + Duration::from_millis(2),
+        })
+        .unwrap();
+
+        let csv = String::from_utf8(dump.writer).unwrap();
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            [
+                CSV_HEADER,
+                "1500,pass,10,20,,,10,20",
+                "2000,trajectory_block,150,105,-5,105,89,105",
+            ]
+        );
+    }
+
+    /// Accepts writes until `full` is set
+    struct Disk {
+        full: bool,
+        attempts: usize,
+    }
+
+    impl Write for Disk {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if !self.full {
+                return Ok(buf.len());
+            }
+            self.attempts += 1;
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_stops_after_the_first_write_error() {
+        let disk = Disk {
+            full: false,
+            attempts: 0,
+        };
+        let mut dump = DebugDump::new(disk).unwrap();
+        dump.writer.full = true;
+        let decision = BarrierDecision {
+            input: Point { x: 0, y: 0 },
+            last: None,
+            kind: DecisionKind::BufferPush,
+            output: Point { x: 0, y: 0 },
+            timestamp: dump.start,
+        };
+
+        assert!(dump.record(&decision).is_err());
+        let attempts = dump.writer.attempts;
+        assert!(dump.record(&decision).is_ok());
+        assert_eq!(dump.writer.attempts, attempts);
+    }
+}
@@ -0,0 +1,451 @@
+use crate::debug_dump::kind_name;
+use crate::trail::ScreenScale;
+use crate::ui_thread::{register_class, wide, UiThread};
+use mouse_barrier::{trajectory_samples, DecisionKind, HookView, LastPush, Point, Rect, Zone};
+use std::cell::Cell;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+use winapi::shared::minwindef::*;
+use winapi::shared::windef::*;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::wingdi::*;
+use winapi::um::winuser::*;
+
+/// Window class of the debug overlay, for finding ones left behind by a
+/// previous run
+pub const DEBUG_OVERLAY_CLASS_NAME: &str = "AgeOfCrashDebugOverlay";
+
+const DEBUG_OVERLAY_REPAINT_TIMER_ID: usize = 1;
+const DEBUG_OVERLAY_REPAINT_INTERVAL_MS: u32 = 33; // ~30 Hz
+const SAMPLE_RADIUS: i32 = 2;
+const LABEL_OFFSET: i32 = 12;
+
+// Painted black is see-through: the window uses it as its color key
+const COLOR_TRANSPARENT: u32 = 0x00000000;
+const COLOR_BARRIER: u32 = 0x000000FF; // Red
+const COLOR_BUFFER: u32 = 0x0000A5FF; // Orange
+const COLOR_PATH: u32 = 0x0000FFFF; // Yellow
+const COLOR_HIT_SAMPLE: u32 = 0x00FF00FF; // Magenta
+const COLOR_REDIRECT: u32 = 0x0000FF00; // Green
+const COLOR_PUSH: u32 = 0x00FFFF00; // Cyan
+
+/// One thing the overlay draws, in window coordinates
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    Outline {
+        rect: Rect,
+        color: u32,
+        dashed: bool,
+    },
+    Line {
+        from: Point,
+        to: Point,
+        color: u32,
+    },
+    Dot {
+        at: Point,
+        color: u32,
+    },
+    Label {
+        at: Point,
+        text: String,
+    },
+}
+
+/// What the hook works with, as shapes: the barrier and buffer rectangles, the
+/// last move with the points the trajectory check tested along it, where the
+/// hook sent the cursor instead, and the last push.
+///
+/// Hook positions are scaled to the window; where the hook moved the cursor
+/// is already in cursor coordinates, like the trail draws it.
+fn scene(view: &HookView, push: Option<&LastPush>, scale: ScreenScale) -> Vec<Shape> {
+    let to_window = |rect: Rect| {
+        let top_left = scale.to_window(Point {
+            x: rect.left,
+            y: rect.top,
+        });
+        let bottom_right = scale.to_window(Point {
+            x: rect.right,
+            y: rect.bottom,
+        });
+        Rect {
+            left: top_left.x,
+            top: top_left.y,
+            right: bottom_right.x,
+            bottom: bottom_right.y,
+        }
+    };
+
+    let snapshot = &view.snapshot;
+    let mut shapes = vec![
+        Shape::Outline {
+            rect: to_window(snapshot.buffer_rect()),
+            color: COLOR_BUFFER,
+            dashed: true,
+        },
+        Shape::Outline {
+            rect: to_window(snapshot.rect),
+            color: COLOR_BARRIER,
+            dashed: false,
+        },
+    ];
+
+    if let Some(decision) = &view.decision {
+        let input = scale.to_window(decision.input);
+        if let Some(last) = decision.last {
+            shapes.push(Shape::Line {
+                from: scale.to_window(last),
+                to: input,
+                color: COLOR_PATH,
+            });
+            for sample in trajectory_samples(last, decision.input) {
+                let color = if snapshot.zone_at(sample.x, sample.y) == Zone::Barrier {
+                    COLOR_HIT_SAMPLE
+                } else {
+                    COLOR_PATH
+                };
+                shapes.push(Shape::Dot {
+                    at: scale.to_window(sample),
+                    color,
+                });
+            }
+        }
+        if decision.kind != DecisionKind::PassThrough {
+            shapes.push(Shape::Line {
+                from: input,
+                to: decision.output,
+                color: COLOR_REDIRECT,
+            });
+        }
+        shapes.push(Shape::Label {
+            at: Point {
+                x: input.x + LABEL_OFFSET,
+                y: input.y + LABEL_OFFSET,
+            },
+            text: kind_name(decision.kind).to_string(),
+        });
+    }
+
+    if let Some(push) = push {
+        shapes.push(Shape::Line {
+            from: scale.to_window(push.from),
+            to: push.to,
+            color: COLOR_PUSH,
+        });
+    }
+    shapes
+}
+
+thread_local! {
+    // Measured once when the overlay window is created on its UI thread
+    static WINDOW_SCALE: Cell<Option<ScreenScale>> = const { Cell::new(None) };
+}
+
+/// A click-through, full-screen window drawing the barrier, the buffer zone and
+/// the last cursor move the way the mouse hook sees them, for tuning the
+/// trajectory check
+///
+/// Like the trail, the window lives on its own UI thread, and polls
+/// [`mouse_barrier::hook_view`] and [`mouse_barrier::last_push`] on a timer,
+/// so nothing here runs on the hook thread.
+pub struct DebugOverlay {
+    _ui: UiThread,
+}
+
+impl DebugOverlay {
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            _ui: UiThread::spawn("debug-overlay-ui", create_debug_overlay_window)?,
+        })
+    }
+}
+
+fn create_debug_overlay_window() -> Result<HWND, Box<dyn std::error::Error>> {
+    let class_name = register_class(DEBUG_OVERLAY_CLASS_NAME, debug_overlay_window_proc);
+    let window_title = wide("Mouse Barrier Debug Overlay");
+
+    let scale = ScreenScale::current();
+    WINDOW_SCALE.with(|cell| cell.set(Some(scale)));
+    let (width, height) = scale.logical;
+    let hwnd = unsafe {
+        CreateWindowExW(
+            WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_NOACTIVATE | WS_EX_TOOLWINDOW,
+            class_name.as_ptr(),
+            window_title.as_ptr(),
+            WS_POPUP,
+            0,
+            0,
+            width,
+            height,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            GetModuleHandleW(ptr::null()),
+            ptr::null_mut(),
+        )
+    };
+
+    if hwnd.is_null() {
+        return Err("Failed to create debug overlay window".into());
+    }
+
+    unsafe {
+        SetLayeredWindowAttributes(hwnd, COLOR_TRANSPARENT, 0, LWA_COLORKEY);
+
+        ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+        SetTimer(
+            hwnd,
+            DEBUG_OVERLAY_REPAINT_TIMER_ID,
+            DEBUG_OVERLAY_REPAINT_INTERVAL_MS,
+            None,
+        );
+    }
+
+    Ok(hwnd)
+}
+
+unsafe extern "system" fn debug_overlay_window_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            let mut ps: PAINTSTRUCT = std::mem::zeroed();
+            let hdc = BeginPaint(hwnd, &mut ps);
+
+            let mut rect: RECT = std::mem::zeroed();
+            GetClientRect(hwnd, &mut rect);
+
+            // Draw off-screen so the full-screen clear never flickers
+            let mem_dc = CreateCompatibleDC(hdc);
+            let bitmap = CreateCompatibleBitmap(hdc, rect.right, rect.bottom);
+            let old_bitmap = SelectObject(mem_dc, bitmap as *mut _);
+
+            let background = CreateSolidBrush(COLOR_TRANSPARENT);
+            FillRect(mem_dc, &rect, background);
+            DeleteObject(background as *mut _);
+
+            if let (Some(scale), Some(view)) =
+                (WINDOW_SCALE.with(Cell::get), mouse_barrier::hook_view())
+            {
+                let push = mouse_barrier::last_push();
+                for shape in scene(&view, push.as_ref(), scale) {
+                    draw_shape(mem_dc, &shape);
+                }
+            }
+
+            BitBlt(hdc, 0, 0, rect.right, rect.bottom, mem_dc, 0, 0, SRCCOPY);
+
+            SelectObject(mem_dc, old_bitmap);
+            DeleteObject(bitmap as *mut _);
+            DeleteDC(mem_dc);
+
+            EndPaint(hwnd, &ps);
+            0
+        }
+        WM_TIMER if wparam == DEBUG_OVERLAY_REPAINT_TIMER_ID => {
+            InvalidateRect(hwnd, ptr::null(), FALSE);
+            0
+        }
+        WM_MOUSEACTIVATE => MA_NOACTIVATE as LRESULT,
+        WM_DESTROY => {
+            KillTimer(hwnd, DEBUG_OVERLAY_REPAINT_TIMER_ID);
+            // Ends the overlay thread's message loop
+            PostQuitMessage(0);
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+unsafe fn draw_shape(hdc: HDC, shape: &Shape) {
+    match shape {
+        Shape::Outline {
+            rect,
+            color,
+            dashed,
+        } => {
+            // GDI only dashes one pixel wide pens
+            let pen = if *dashed {
+                CreatePen(PS_DASH as i32, 1, *color)
+            } else {
+                CreatePen(PS_SOLID as i32, 2, *color)
+            };
+            let old_pen = SelectObject(hdc, pen as *mut _);
+            let old_brush = SelectObject(hdc, GetStockObject(NULL_BRUSH as i32));
+            Rectangle(hdc, rect.left, rect.top, rect.right, rect.bottom);
+            SelectObject(hdc, old_brush);
+            SelectObject(hdc, old_pen);
+            DeleteObject(pen as *mut _);
+        }
+        Shape::Line { from, to, color } => {
+            let pen = CreatePen(PS_SOLID as i32, 1, *color);
+            let old_pen = SelectObject(hdc, pen as *mut _);
+            MoveToEx(hdc, from.x, from.y, ptr::null_mut());
+            LineTo(hdc, to.x, to.y);
+            SelectObject(hdc, old_pen);
+            DeleteObject(pen as *mut _);
+        }
+        Shape::Dot { at, color } => {
+            let brush = CreateSolidBrush(*color);
+            let dot = RECT {
+                left: at.x - SAMPLE_RADIUS,
+                top: at.y - SAMPLE_RADIUS,
+                right: at.x + SAMPLE_RADIUS + 1,
+                bottom: at.y + SAMPLE_RADIUS + 1,
+            };
+            FillRect(hdc, &dot, brush);
+            DeleteObject(brush as *mut _);
+        }
+        Shape::Label { at, text } => {
+            let wide: Vec<u16> = OsStr::new(text).encode_wide().collect();
+            SetBkMode(hdc, TRANSPARENT as i32);
+            SetTextColor(hdc, COLOR_PATH);
+            TextOutW(hdc, at.x, at.y, wide.as_ptr(), wide.len() as i32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mouse_barrier::{BarrierDecision, BarrierSnapshot, BufferZone, EnforcementMode};
+    use std::time::Instant;
+
+    const UNSCALED: ScreenScale = ScreenScale {
+        logical: (1920, 1080),
+        physical: (1920, 1080),
+    };
+
+    fn view(decision: Option<BarrierDecision>) -> HookView {
+        HookView {
+            snapshot: BarrierSnapshot {
+                rect: Rect {
+                    left: 100,
+                    top: 100,
+                    right: 200,
+                    bottom: 200,
+                },
+                buffer: BufferZone::uniform(10),
+                enabled: true,
+                push_factor: 20,
+                enforcement_mode: EnforcementMode::Enforce,
+            },
+            decision,
+        }
+    }
+
+    fn decision(
+        last: Option<Point>,
+        input: Point,
+        kind: DecisionKind,
+        output: Point,
+    ) -> BarrierDecision {
+        BarrierDecision {
+            input,
+            last,
+            kind,
+            output,
+            timestamp: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_scene_without_a_move_is_just_the_rectangles() {
+        let shapes = scene(&view(None), None, UNSCALED);
+        assert_eq!(
+            shapes,
+            [
+                Shape::Outline {
+                    rect: Rect {
+                        left: 90,
+                        top: 90,
+                        right: 210,
+                        bottom: 210,
+                    },
+                    color: COLOR_BUFFER,
+                    dashed: true,
+                },
+                Shape::Outline {
+                    rect: view(None).snapshot.rect,
+                    color: COLOR_BARRIER,
+                    dashed: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scene_shows_the_samples_that_hit_the_barrier() {
+        // A flick straight through, stopped before the buffer zone
+        let flick = decision(
+            Some(Point { x: 50, y: 150 }),
+            Point { x: 250, y: 150 },
+            DecisionKind::TrajectoryBlock,
+            Point { x: 70, y: 150 },
+        );
+        let shapes = scene(&view(Some(flick)), None, UNSCALED);
+
+        let hits: Vec<_> = shapes
+            .iter()
+            .filter_map(|shape| match shape {
+                Shape::Dot {
+                    at,
+                    color: COLOR_HIT_SAMPLE,
+                } => Some(at.x),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hits, [110, 130, 150, 170, 190]);
+        assert!(shapes.contains(&Shape::Line {
+            from: Point { x: 250, y: 150 },
+            to: Point { x: 70, y: 150 },
+            color: COLOR_REDIRECT,
+        }));
+        assert!(shapes.contains(&Shape::Label {
+            at: Point { x: 262, y: 162 },
+            text: "trajectory_block".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_scene_scales_hook_positions_but_not_cursor_moves() {
+        let scaled = ScreenScale {
+            logical: (1920, 1080),
+            physical: (3840, 2160),
+        };
+        let push = LastPush {
+            from: Point { x: 400, y: 300 },
+            to: Point { x: 180, y: 150 },
+            edge: None,
+            speed: 5.0,
+            multiplier: 1.0,
+            timestamp: Instant::now(),
+        };
+        let shapes = scene(&view(None), Some(&push), scaled);
+
+        assert_eq!(
+            shapes[1],
+            Shape::Outline {
+                rect: Rect {
+                    left: 50,
+                    top: 50,
+                    right: 100,
+                    bottom: 100,
+                },
+                color: COLOR_BARRIER,
+                dashed: false,
+            }
+        );
+        assert_eq!(
+            shapes.last(),
+            Some(&Shape::Line {
+                from: Point { x: 200, y: 150 },
+                to: Point { x: 180, y: 150 },
+                color: COLOR_PUSH,
+            })
+        );
+    }
+}
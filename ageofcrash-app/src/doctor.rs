@@ -0,0 +1,805 @@
+use crate::config::{AudioFeedbackConfig, AudioOption, BarrierAnchor, Config};
+use crate::elevation::process_elevated;
+use crate::recovery::Win32Desktop;
+use crate::shutdown::SESSION_CLASS_NAME;
+use crate::window_anchor::find_anchor_process;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::ptr;
+use winapi::shared::minwindef::{BOOL, DWORD, LPARAM, TRUE, UINT};
+use winapi::shared::windef::{
+    DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE, DPI_AWARENESS_PER_MONITOR_AWARE,
+    DPI_AWARENESS_SYSTEM_AWARE, HDC, HMONITOR, LPRECT,
+};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::shellscalingapi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+use winapi::um::winuser::*;
+
+/// DPI of a monitor at 100% scaling
+const DEFAULT_DPI: u32 = 96;
+
+/// How a check came out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    /// Works, but likely to cause trouble
+    Warn,
+    /// The barrier won't work properly until this is fixed
+    Fail,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::Pass => "PASS",
+            Status::Warn => "WARN",
+            Status::Fail => "FAIL",
+        }
+    }
+}
+
+/// The outcome of one check, with a suggested fix unless it passed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    pub fix: Option<&'static str>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Pass,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: &'static str) -> Self {
+        Self {
+            name,
+            status: Status::Warn,
+            detail: detail.into(),
+            fix: Some(fix),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: &'static str) -> Self {
+        Self {
+            name,
+            status: Status::Fail,
+            detail: detail.into(),
+            fix: Some(fix),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookKind {
+    Mouse,
+    Keyboard,
+}
+
+/// How Windows scales coordinates for this process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DpiAwareness {
+    /// Everything is scaled as if every monitor ran at 96 DPI
+    Unaware,
+    /// Scaled to the primary monitor's DPI
+    System,
+    PerMonitor,
+}
+
+/// One display, in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Monitor {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub dpi: u32,
+}
+
+/// The Win32 calls the checks make, kept behind a trait so the checks can be
+/// tested without a desktop
+pub trait System {
+    /// Installs a low-level hook of `kind` and removes it again at once
+    fn try_hook(&self, kind: HookKind) -> Result<(), String>;
+    /// Whether another copy of the app is running in this session
+    fn other_instance_running(&self) -> bool;
+    fn dpi_awareness(&self) -> DpiAwareness;
+    fn monitors(&self) -> Vec<Monitor>;
+    /// Whether this is a Remote Desktop session rather than the console
+    fn remote_session(&self) -> bool;
+    /// Id of the process owning the window `anchor` matches, if any
+    fn anchor_process(&self, anchor: &BarrierAnchor) -> Option<u32>;
+    /// Whether process `pid`, or this process for `None`, runs elevated;
+    /// `None` if that can't be told
+    fn elevated(&self, pid: Option<u32>) -> Option<bool>;
+    fn read_file(&self, path: &Path) -> io::Result<Vec<u8>>;
+}
+
+pub fn check_hook(system: &impl System, kind: HookKind) -> Check {
+    let (name, what) = match kind {
+        HookKind::Mouse => ("Mouse hook", "mouse"),
+        HookKind::Keyboard => ("Keyboard hook", "keyboard"),
+    };
+    match system.try_hook(kind) {
+        Ok(()) => Check::pass(name, "Installed and removed a test hook"),
+        Err(e) => Check::fail(
+            name,
+            format!("Couldn't install a low-level {} hook: {}", what, e),
+            "Security software may be blocking global hooks; allow ageofcrash.exe in it",
+        ),
+    }
+}
+
+pub fn check_other_instance(system: &impl System) -> Check {
+    const NAME: &str = "Single instance";
+    if system.other_instance_running() {
+        Check::warn(
+            NAME,
+            "Another copy of ageofcrash is running",
+            "Two barriers fight over the cursor; close the other copy before starting this one",
+        )
+    } else {
+        Check::pass(NAME, "No other copy running")
+    }
+}
+
+pub fn check_dpi(system: &impl System) -> Check {
+    const NAME: &str = "Screen and DPI";
+    let monitors = system.monitors();
+    if monitors.is_empty() {
+        return Check::fail(
+            NAME,
+            "Windows reported no monitors",
+            "Make sure a display is connected and its driver is working",
+        );
+    }
+
+    let awareness = system.dpi_awareness();
+    let mut detail = format!("{:?} DPI awareness;", awareness);
+    for monitor in &monitors {
+        let _ = write!(
+            detail,
+            " {}x{} at ({},{}) {} DPI ({}%),",
+            monitor.right - monitor.left,
+            monitor.bottom - monitor.top,
+            monitor.left,
+            monitor.top,
+            monitor.dpi,
+            monitor.dpi * 100 / DEFAULT_DPI
+        );
+    }
+    detail.pop();
+
+    // Only a per-monitor aware process sees true coordinates on every monitor
+    let scales: BTreeSet<u32> = monitors.iter().map(|monitor| monitor.dpi).collect();
+    if awareness != DpiAwareness::PerMonitor && scales.len() > 1 {
+        Check::warn(
+            NAME,
+            detail,
+            "Monitors with different scaling can put the barrier in the wrong place; \
+             give every monitor the same scaling in Display settings",
+        )
+    } else {
+        Check::pass(NAME, detail)
+    }
+}
+
+/// What to tell the user at startup about how Windows scales coordinates for
+/// this process. Running DPI-unaware is behind most reports of an overlay that
+/// doesn't line up with where the barrier stops the cursor.
+pub fn dpi_awareness_warning(awareness: DpiAwareness) -> Option<&'static str> {
+    match awareness {
+        DpiAwareness::Unaware => Some(
+            "Running DPI-unaware: on a monitor with display scaling above 100% Windows \
+             scales the coordinates the barrier works with, so the overlay may not line up \
+             with where the cursor is stopped. Run `ageofcrash-app.exe doctor` for details",
+        ),
+        DpiAwareness::System | DpiAwareness::PerMonitor => None,
+    }
+}
+
+pub fn check_session(system: &impl System) -> Check {
+    const NAME: &str = "Session";
+    if system.remote_session() {
+        Check::warn(
+            NAME,
+            "Running in a Remote Desktop session",
+            "Low-level hooks see remote input differently; run on the local console for \
+             reliable blocking",
+        )
+    } else {
+        Check::pass(NAME, "Local console session")
+    }
+}
+
+pub fn check_game(system: &impl System, anchor: &BarrierAnchor) -> Check {
+    const NAME: &str = "Game process";
+    if *anchor == BarrierAnchor::Screen {
+        return Check::pass(
+            NAME,
+            "No anchor configured; the barrier uses screen coordinates",
+        );
+    }
+
+    let Some(pid) = system.anchor_process(anchor) else {
+        return Check::warn(
+            NAME,
+            format!("No window matches {:?}", anchor),
+            "Start the game and run doctor again; if it is running, check barrier.anchor \
+             in config.ron",
+        );
+    };
+
+    match (system.elevated(Some(pid)), system.elevated(None)) {
+        (Some(true), Some(false)) => Check::fail(
+            NAME,
+            format!(
+                "The game (pid {}) runs as administrator and ageofcrash doesn't",
+                pid
+            ),
+            "Windows keeps the hooks of a normal process away from elevated windows; run \
+             ageofcrash with --relaunch-elevated",
+        ),
+        (Some(_), Some(_)) => Check::pass(
+            NAME,
+            format!(
+                "Running (pid {}) without more privileges than ageofcrash",
+                pid
+            ),
+        ),
+        _ => Check::warn(
+            NAME,
+            format!(
+                "Couldn't tell whether the game (pid {}) runs as administrator",
+                pid
+            ),
+            "If the barrier doesn't hold inside the game, run ageofcrash as administrator",
+        ),
+    }
+}
+
+/// One check per configured sound file; builtin sounds always pass
+pub fn check_sounds(system: &impl System, audio: &AudioFeedbackConfig) -> Vec<Check> {
+    [
+        ("Hit sound", &audio.on_barrier_hit),
+        ("Entry sound", &audio.on_barrier_entry),
+    ]
+    .into_iter()
+    .filter_map(|(name, option)| match option {
+        AudioOption::None => None,
+        AudioOption::Builtin(builtin) => Some(Check::pass(name, format!("Builtin {:?}", builtin))),
+        AudioOption::File(path) => Some(match system.read_file(Path::new(path)) {
+            Err(e) => Check::fail(
+                name,
+                format!("Can't read {}: {}", path, e),
+                "Fix the path in config.ron; relative paths start from the config file's folder",
+            ),
+            Ok(bytes) => match check_wav(&bytes) {
+                Ok(()) => Check::pass(name, path.clone()),
+                Err(e) => Check::fail(
+                    name,
+                    format!("{} isn't a playable WAV file: {}", path, e),
+                    "Convert the sound to a PCM .wav file",
+                ),
+            },
+        }),
+    })
+    .collect()
+}
+
+/// One failed check per sound file the config names but loading didn't
+/// find, and so dropped
+pub fn check_missing_sounds(missing: &[String]) -> Vec<Check> {
+    missing
+        .iter()
+        .map(|path| {
+            Check::fail(
+                "Sound file",
+                format!("{} not found; its cue plays nothing", path),
+                "Fix the path in config.ron; relative paths start from the config file's folder",
+            )
+        })
+        .collect()
+}
+
+/// Checks that `bytes` are a RIFF WAVE file with the format and data chunks
+/// PlaySound needs
+pub fn check_wav(bytes: &[u8]) -> Result<(), String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("missing the RIFF/WAVE header".to_string());
+    }
+
+    let (mut format, mut data) = (false, false);
+    let mut chunks = &bytes[12..];
+    while chunks.len() >= 8 {
+        let size = u32::from_le_bytes([chunks[4], chunks[5], chunks[6], chunks[7]]) as usize;
+        match &chunks[0..4] {
+            b"fmt " => format = true,
+            b"data" => data = true,
+            _ => {}
+        }
+        // Chunks are padded to an even length
+        let next = 8usize.saturating_add(size).saturating_add(size % 2);
+        chunks = chunks.get(next..).unwrap_or_default();
+    }
+
+    match (format, data) {
+        (false, _) => Err("no fmt chunk".to_string()),
+        (_, false) => Err("no data chunk".to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Whether config.ron loaded; `config` is the result of loading it
+pub fn check_config(config: Result<&Config, &str>) -> Check {
+    const NAME: &str = "Configuration";
+    match config {
+        Ok(_) => Check::pass(NAME, "config.ron loaded"),
+        Err(e) => Check::fail(
+            NAME,
+            format!("config.ron didn't load: {}", e),
+            "Fix the error in config.ron, or move it aside to have the defaults written again",
+        ),
+    }
+}
+
+/// Every check; the game and sound checks need the config, so they are left
+/// out when it didn't load
+pub fn run_checks(system: &impl System, config: Result<&Config, &str>) -> Vec<Check> {
+    let mut checks = vec![
+        check_config(config),
+        check_hook(system, HookKind::Mouse),
+        check_hook(system, HookKind::Keyboard),
+        check_other_instance(system),
+        check_dpi(system),
+        check_session(system),
+    ];
+    if let Ok(config) = config {
+        checks.push(check_game(system, &config.barrier.anchor));
+        checks.extend(check_missing_sounds(&config.missing_sounds));
+        checks.extend(check_sounds(system, &config.barrier.audio_feedback));
+    }
+    checks
+}
+
+/// The printed report: one line per check, its fix below it, then a summary
+pub fn report(checks: &[Check]) -> String {
+    let mut report = String::new();
+    for check in checks {
+        let _ = writeln!(
+            report,
+            "[{}] {}: {}",
+            check.status.label(),
+            check.name,
+            check.detail
+        );
+        if let Some(fix) = check.fix {
+            let _ = writeln!(report, "       fix: {}", fix);
+        }
+    }
+
+    let count = |status| checks.iter().filter(|check| check.status == status).count();
+    let _ = writeln!(
+        report,
+        "{} passed, {} warnings, {} failed",
+        count(Status::Pass),
+        count(Status::Warn),
+        count(Status::Fail)
+    );
+    report
+}
+
+/// Runs every check against this machine and prints the report; fails if
+/// any check did. `config` is the result of loading config.ron, so a config
+/// that doesn't load is reported rather than keeping doctor from running.
+pub fn run(
+    config: Result<Config, Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config.map_err(|e| e.to_string());
+    let checks = run_checks(&Win32System, config.as_ref().map_err(String::as_str));
+    print!("{}", report(&checks));
+
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == Status::Fail)
+        .count();
+    if failed > 0 {
+        return Err(format!("{} doctor check(s) failed", failed).into());
+    }
+    Ok(())
+}
+
+/// The real machine
+pub struct Win32System;
+
+impl System for Win32System {
+    fn try_hook(&self, kind: HookKind) -> Result<(), String> {
+        let id = match kind {
+            HookKind::Mouse => WH_MOUSE_LL,
+            HookKind::Keyboard => WH_KEYBOARD_LL,
+        };
+        unsafe {
+            let hook = SetWindowsHookExW(id, Some(pass_through), GetModuleHandleW(ptr::null()), 0);
+            if hook.is_null() {
+                return Err(format!("error {}", GetLastError()));
+            }
+            UnhookWindowsHookEx(hook);
+        }
+        Ok(())
+    }
+
+    fn other_instance_running(&self) -> bool {
+        !Win32Desktop.foreign_windows(SESSION_CLASS_NAME).is_empty()
+    }
+
+    fn dpi_awareness(&self) -> DpiAwareness {
+        let awareness =
+            unsafe { GetAwarenessFromDpiAwarenessContext(GetThreadDpiAwarenessContext()) };
+        match awareness {
+            DPI_AWARENESS_PER_MONITOR_AWARE => DpiAwareness::PerMonitor,
+            DPI_AWARENESS_SYSTEM_AWARE => DpiAwareness::System,
+            _ => DpiAwareness::Unaware,
+        }
+    }
+
+    fn monitors(&self) -> Vec<Monitor> {
+        let mut monitors = Vec::new();
+        unsafe {
+            // Monitors report 96 DPI to processes that aren't per-monitor aware
+            let previous = SetThreadDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE);
+            EnumDisplayMonitors(
+                ptr::null_mut(),
+                ptr::null(),
+                Some(collect_monitor),
+                &mut monitors as *mut Vec<Monitor> as LPARAM,
+            );
+            if !previous.is_null() {
+                SetThreadDpiAwarenessContext(previous);
+            }
+        }
+        monitors
+    }
+
+    fn remote_session(&self) -> bool {
+        unsafe { GetSystemMetrics(SM_REMOTESESSION) != 0 }
+    }
+
+    fn anchor_process(&self, anchor: &BarrierAnchor) -> Option<u32> {
+        find_anchor_process(anchor)
+    }
+
+    fn elevated(&self, pid: Option<u32>) -> Option<bool> {
+        process_elevated(pid)
+    }
+
+    fn read_file(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+}
+
+unsafe extern "system" fn pass_through(
+    code: i32,
+    wparam: winapi::shared::minwindef::WPARAM,
+    lparam: LPARAM,
+) -> winapi::shared::minwindef::LRESULT {
+    CallNextHookEx(ptr::null_mut(), code, wparam, lparam)
+}
+
+unsafe extern "system" fn collect_monitor(
+    hmonitor: HMONITOR,
+    _hdc: HDC,
+    _rect: LPRECT,
+    lparam: LPARAM,
+) -> BOOL {
+    let monitors = &mut *(lparam as *mut Vec<Monitor>);
+
+    let mut info: MONITORINFO = std::mem::zeroed();
+    info.cbSize = std::mem::size_of::<MONITORINFO>() as DWORD;
+    if GetMonitorInfoW(hmonitor, &mut info) == 0 {
+        return TRUE;
+    }
+
+    let (mut dpi_x, mut dpi_y): (UINT, UINT) = (0, 0);
+    let dpi = if GetDpiForMonitor(hmonitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) == 0 {
+        dpi_x
+    } else {
+        DEFAULT_DPI
+    };
+
+    monitors.push(Monitor {
+        left: info.rcMonitor.left,
+        top: info.rcMonitor.top,
+        right: info.rcMonitor.right,
+        bottom: info.rcMonitor.bottom,
+        dpi,
+    });
+    TRUE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EntryCue;
+
+    /// A machine where everything is fine unless a test says otherwise
+    struct FakeSystem {
+        hook_error: Option<String>,
+        other_instance: bool,
+        awareness: DpiAwareness,
+        monitors: Vec<Monitor>,
+        remote: bool,
+        game_pid: Option<u32>,
+        game_elevated: Option<bool>,
+        self_elevated: Option<bool>,
+        files: Vec<(&'static str, Vec<u8>)>,
+    }
+
+    impl Default for FakeSystem {
+        fn default() -> Self {
+            Self {
+                hook_error: None,
+                other_instance: false,
+                awareness: DpiAwareness::Unaware,
+                monitors: vec![monitor(0, 96)],
+                remote: false,
+                game_pid: Some(4242),
+                game_elevated: Some(false),
+                self_elevated: Some(false),
+                files: Vec::new(),
+            }
+        }
+    }
+
+    impl System for FakeSystem {
+        fn try_hook(&self, _kind: HookKind) -> Result<(), String> {
+            self.hook_error.clone().map_or(Ok(()), Err)
+        }
+
+        fn other_instance_running(&self) -> bool {
+            self.other_instance
+        }
+
+        fn dpi_awareness(&self) -> DpiAwareness {
+            self.awareness
+        }
+
+        fn monitors(&self) -> Vec<Monitor> {
+            self.monitors.clone()
+        }
+
+        fn remote_session(&self) -> bool {
+            self.remote
+        }
+
+        fn anchor_process(&self, _anchor: &BarrierAnchor) -> Option<u32> {
+            self.game_pid
+        }
+
+        fn elevated(&self, pid: Option<u32>) -> Option<bool> {
+            match pid {
+                Some(_) => self.game_elevated,
+                None => self.self_elevated,
+            }
+        }
+
+        fn read_file(&self, path: &Path) -> io::Result<Vec<u8>> {
+            self.files
+                .iter()
+                .find(|(name, _)| Path::new(name) == path)
+                .map(|(_, bytes)| bytes.clone())
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+        }
+    }
+
+    /// A 1920x1080 monitor `index` screens to the right
+    fn monitor(index: i32, dpi: u32) -> Monitor {
+        Monitor {
+            left: index * 1920,
+            top: 0,
+            right: (index + 1) * 1920,
+            bottom: 1080,
+            dpi,
+        }
+    }
+
+    fn wav() -> Vec<u8> {
+        let mut bytes = b"RIFF\x24\x00\x00\x00WAVEfmt \x10\x00\x00\x00".to_vec();
+        bytes.extend([0; 16]);
+        bytes.extend(b"data\x00\x00\x00\x00");
+        bytes
+    }
+
+    fn game() -> BarrierAnchor {
+        BarrierAnchor::Process("AoE2DE_s.exe".to_string())
+    }
+
+    #[test]
+    fn test_healthy_machine_passes_everything() {
+        let system = FakeSystem {
+            files: vec![("hit.wav", wav())],
+            ..FakeSystem::default()
+        };
+        let mut config = Config::default();
+        config.barrier.anchor = game();
+        config.barrier.audio_feedback.on_barrier_hit = AudioOption::File("hit.wav".to_string());
+
+        let checks = run_checks(&system, Ok(&config));
+        assert!(
+            checks.iter().all(|check| check.status == Status::Pass),
+            "{}",
+            report(&checks)
+        );
+        assert!(report(&checks).ends_with("0 warnings, 0 failed\n"));
+    }
+
+    #[test]
+    fn test_config_that_does_not_load_fails_but_the_rest_still_run() {
+        let system = FakeSystem::default();
+
+        let checks = run_checks(&system, Err("expected `)` at 12:3"));
+        assert_eq!(checks[0].name, "Configuration");
+        assert_eq!(checks[0].status, Status::Fail);
+        assert!(checks[0].detail.contains("at 12:3"), "{}", checks[0].detail);
+        assert!(checks.iter().any(|check| check.name == "Mouse hook"));
+        // Nothing to say about the game or sounds without a config
+        assert!(!checks.iter().any(|check| check.name == "Game process"));
+        assert!(
+            report(&checks).ends_with("1 failed\n"),
+            "{}",
+            report(&checks)
+        );
+    }
+
+    #[test]
+    fn test_blocked_hook_fails() {
+        let system = FakeSystem {
+            hook_error: Some("error 5".to_string()),
+            ..FakeSystem::default()
+        };
+
+        let check = check_hook(&system, HookKind::Keyboard);
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.detail.contains("keyboard"), "{}", check.detail);
+        assert!(check.fix.is_some());
+    }
+
+    #[test]
+    fn test_other_instance_and_remote_session_warn() {
+        let system = FakeSystem {
+            other_instance: true,
+            remote: true,
+            ..FakeSystem::default()
+        };
+
+        assert_eq!(check_other_instance(&system).status, Status::Warn);
+        assert_eq!(check_session(&system).status, Status::Warn);
+    }
+
+    #[test]
+    fn test_mixed_scaling_warns_unless_per_monitor_aware() {
+        let mut system = FakeSystem {
+            monitors: vec![monitor(0, 144), monitor(1, 96)],
+            ..FakeSystem::default()
+        };
+        let check = check_dpi(&system);
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.detail.contains("144 DPI (150%)"), "{}", check.detail);
+
+        system.awareness = DpiAwareness::PerMonitor;
+        assert_eq!(check_dpi(&system).status, Status::Pass);
+
+        system.monitors.clear();
+        assert_eq!(check_dpi(&system).status, Status::Fail);
+    }
+
+    #[test]
+    fn test_only_dpi_unaware_warns_at_startup() {
+        assert!(dpi_awareness_warning(DpiAwareness::Unaware).is_some());
+        assert_eq!(dpi_awareness_warning(DpiAwareness::System), None);
+        assert_eq!(dpi_awareness_warning(DpiAwareness::PerMonitor), None);
+    }
+
+    #[test]
+    fn test_elevated_game_fails_for_unelevated_app() {
+        let mut system = FakeSystem {
+            game_elevated: Some(true),
+            ..FakeSystem::default()
+        };
+        assert_eq!(check_game(&system, &game()).status, Status::Fail);
+
+        system.self_elevated = Some(true);
+        assert_eq!(check_game(&system, &game()).status, Status::Pass);
+
+        system.game_elevated = None;
+        assert_eq!(check_game(&system, &game()).status, Status::Warn);
+    }
+
+    #[test]
+    fn test_missing_game_warns_and_no_anchor_passes() {
+        let system = FakeSystem {
+            game_pid: None,
+            ..FakeSystem::default()
+        };
+
+        assert_eq!(check_game(&system, &game()).status, Status::Warn);
+        assert_eq!(
+            check_game(&system, &BarrierAnchor::Screen).status,
+            Status::Pass
+        );
+    }
+
+    #[test]
+    fn test_sound_files_must_exist_and_be_wav() {
+        let system = FakeSystem {
+            files: vec![("notes.txt", b"hello".to_vec())],
+            ..FakeSystem::default()
+        };
+        let audio = AudioFeedbackConfig {
+            on_barrier_hit: AudioOption::File("missing.wav".to_string()),
+            on_barrier_entry: AudioOption::File("notes.txt".to_string()),
+            interrupt_previous: true,
+            entry_cue: EntryCue::Barrier,
+        };
+
+        let checks = check_sounds(&system, &audio);
+        assert_eq!(checks.len(), 2);
+        assert!(checks.iter().all(|check| check.status == Status::Fail));
+        assert!(checks[0].detail.contains("missing.wav"));
+
+        let unset = AudioFeedbackConfig {
+            on_barrier_hit: AudioOption::None,
+            on_barrier_entry: AudioOption::Builtin("click".to_string()),
+            ..audio
+        };
+        let checks = check_sounds(&system, &unset);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].status, Status::Pass);
+    }
+
+    #[test]
+    fn test_sounds_dropped_while_loading_fail() {
+        let config = Config {
+            missing_sounds: vec!["hit.wav".to_string()],
+            ..Config::default()
+        };
+
+        let checks = run_checks(&FakeSystem::default(), Ok(&config));
+        let missing: Vec<_> = checks
+            .iter()
+            .filter(|check| check.name == "Sound file")
+            .collect();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].status, Status::Fail);
+        assert!(
+            missing[0].detail.contains("hit.wav"),
+            "{}",
+            missing[0].detail
+        );
+    }
+
+    #[test]
+    fn test_check_wav() {
+        assert_eq!(check_wav(&wav()), Ok(()));
+        assert!(check_wav(b"RIFF").is_err());
+        assert!(check_wav(b"RIFF\x04\x00\x00\x00AVI ").is_err());
+
+        let mut no_data = wav();
+        no_data.truncate(no_data.len() - 8);
+        assert_eq!(check_wav(&no_data), Err("no data chunk".to_string()));
+
+        // A chunk claiming to run past the end of the file
+        let mut truncated = b"RIFF\x00\x00\x00\x00WAVEfmt \xff\xff\xff\xff".to_vec();
+        truncated.extend([0; 4]);
+        assert!(check_wav(&truncated).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info};
+use winapi::shared::minwindef::{DWORD, FALSE, LPVOID};
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::processthreadsapi::{
+    GetCurrentProcess, GetCurrentProcessId, OpenProcess, OpenProcessToken,
+};
+use winapi::um::securitybaseapi::GetTokenInformation;
+use winapi::um::shellapi::ShellExecuteW;
+use winapi::um::winnt::{
+    TokenElevation, PROCESS_QUERY_LIMITED_INFORMATION, TOKEN_ELEVATION, TOKEN_QUERY,
+};
+use winapi::um::winuser::{GetForegroundWindow, GetWindowThreadProcessId, SW_SHOWNORMAL};
+
+// How often the foreground window is checked
+const FOREGROUND_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Whether process `pid`, or this process for `None`, runs elevated; `None`
+/// if that can't be told
+pub fn process_elevated(pid: Option<u32>) -> Option<bool> {
+    unsafe {
+        let process = match pid {
+            // A pseudo handle; it needs no closing
+            None => GetCurrentProcess(),
+            Some(pid) => OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid),
+        };
+        if process.is_null() {
+            return None;
+        }
+
+        let mut token = ptr::null_mut();
+        let opened = OpenProcessToken(process, TOKEN_QUERY, &mut token) != 0;
+        if pid.is_some() {
+            CloseHandle(process);
+        }
+        // Being denied the token doesn't say why: the process may be elevated,
+        // but it may as well be protected or belong to another user
+        if !opened {
+            return None;
+        }
+
+        let mut elevation = TOKEN_ELEVATION { TokenIsElevated: 0 };
+        let mut len: DWORD = 0;
+        let ok = GetTokenInformation(
+            token,
+            TokenElevation,
+            &mut elevation as *mut TOKEN_ELEVATION as LPVOID,
+            std::mem::size_of::<TOKEN_ELEVATION>() as DWORD,
+            &mut len,
+        ) != 0;
+        CloseHandle(token);
+        ok.then_some(elevation.TokenIsElevated != 0)
+    }
+}
+
+/// Whether a process with elevation `theirs` keeps our hooks from seeing
+/// input while it has focus: only when it is elevated and we are known not
+/// to be
+pub fn outranks(theirs: Option<bool>, ours: Option<bool>) -> bool {
+    theirs == Some(true) && ours == Some(false)
+}
+
+/// Watches for the foreground window belonging to a process more elevated
+/// than this one.
+///
+/// Windows doesn't pass input meant for an elevated window through the
+/// low-level hooks of a normal process, so the barrier does nothing while
+/// such a window has focus.
+pub struct ForegroundWatcher {
+    should_stop: Arc<AtomicBool>,
+    watcher_thread: Option<thread::JoinHandle<()>>,
+}
+
+impl ForegroundWatcher {
+    /// Calls `on_change` from a background thread whenever the foreground
+    /// window starts or stops outranking us, starting from "doesn't".
+    /// Returns `None` when we run elevated ourselves and nothing can outrank us.
+    pub fn start<F>(on_change: F) -> Result<Option<Self>, Box<dyn std::error::Error>>
+    where
+        F: Fn(bool) + Send + 'static,
+    {
+        let ours = process_elevated(None);
+        if ours == Some(true) {
+            return Ok(None);
+        }
+
+        let should_stop = Arc::new(AtomicBool::new(false));
+        let stop = should_stop.clone();
+        let handle = thread::Builder::new()
+            .name("foreground-watcher".to_string())
+            .spawn(move || {
+                let own_process = unsafe { GetCurrentProcessId() };
+                let mut last_process = None;
+                let mut outranked = false;
+
+                while !stop.load(Ordering::Relaxed) {
+                    let process = foreground_process();
+                    // Elevation only changes with the process, so only look it up then
+                    if process != last_process {
+                        last_process = process;
+                        let now_outranked = process.is_some_and(|pid| {
+                            pid != own_process && outranks(process_elevated(Some(pid)), ours)
+                        });
+                        if now_outranked != outranked {
+                            outranked = now_outranked;
+                            on_change(outranked);
+                        }
+                    }
+
+                    thread::sleep(FOREGROUND_POLL_INTERVAL);
+                }
+
+                info!("Foreground watcher thread stopping");
+            })?;
+
+        Ok(Some(Self {
+            should_stop,
+            watcher_thread: Some(handle),
+        }))
+    }
+}
+
+impl Drop for ForegroundWatcher {
+    fn drop(&mut self) {
+        if let Some(handle) = self.watcher_thread.take() {
+            self.should_stop.store(true, Ordering::Relaxed);
+            if let Err(e) = handle.join() {
+                error!("Failed to join foreground watcher thread: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Id of the process owning the foreground window
+fn foreground_process() -> Option<u32> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            return None;
+        }
+        let mut process_id: DWORD = 0;
+        GetWindowThreadProcessId(hwnd, &mut process_id);
+        (process_id != 0).then_some(process_id)
+    }
+}
+
+/// Starts this executable again as administrator with `args`, after the
+/// user accepts the UAC prompt
+pub fn relaunch_elevated(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let exe = std::env::current_exe()?;
+    let wide =
+        |text: &OsStr| -> Vec<u16> { text.encode_wide().chain(std::iter::once(0)).collect() };
+    let verb = wide(OsStr::new("runas"));
+    let file = wide(exe.as_os_str());
+    let parameters = wide(OsStr::new(&join_args(args)));
+    // runas starts in System32 otherwise, where relative paths like the
+    // config file would no longer resolve
+    let directory = wide(std::env::current_dir()?.as_os_str());
+
+    let result = unsafe {
+        ShellExecuteW(
+            ptr::null_mut(),
+            verb.as_ptr(),
+            file.as_ptr(),
+            parameters.as_ptr(),
+            directory.as_ptr(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Values above 32 mean success; declining the UAC prompt is an error
+    if result as usize <= 32 {
+        return Err(format!(
+            "Failed to restart as administrator (error {})",
+            result as usize
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// `args` as one command line that `CommandLineToArgvW` splits back into
+/// the same arguments
+fn join_args(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| quote_arg(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `arg` quoted for a Windows command line if it needs to be.
+///
+/// Inside quotes, backslashes are only special right before a `"`: a run of
+/// them followed by a quote has to be doubled, and the quote escaped with one
+/// more. That includes the closing quote, so trailing backslashes are doubled.
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '\u{0b}', '"']) {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.extend(std::iter::repeat_n('\\', backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outranks() {
+        assert!(outranks(Some(true), Some(false)));
+        assert!(!outranks(Some(true), Some(true)));
+        assert!(!outranks(Some(false), Some(false)));
+        // Don't warn on a guess
+        assert!(!outranks(None, Some(false)));
+        assert!(!outranks(Some(true), None));
+    }
+
+    #[test]
+    fn test_join_args() {
+        let args = ["--debug-dump", r"C:\My Logs\dump.csv", "doctor"].map(String::from);
+        assert_eq!(
+            join_args(&args),
+            r#"--debug-dump "C:\My Logs\dump.csv" doctor"#
+        );
+        assert_eq!(join_args(&[]), "");
+    }
+
+    #[test]
+    fn test_join_args_escapes_quotes_and_backslashes() {
+        // Backslashes not followed by a quote are taken literally
+        assert_eq!(quote_arg(r"C:\Logs\dump.csv"), r"C:\Logs\dump.csv");
+        assert_eq!(quote_arg(""), r#""""#);
+        assert_eq!(quote_arg(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(quote_arg(r#"a\"b"#), r#""a\\\"b""#);
+        // A trailing backslash would otherwise escape the closing quote
+        assert_eq!(quote_arg(r"C:\My Logs\"), r#""C:\My Logs\\""#);
+        assert_eq!(
+            join_args(&[r"C:\My Logs\".to_string(), "doctor".to_string()]),
+            r#""C:\My Logs\\" doctor"#
+        );
+    }
+}
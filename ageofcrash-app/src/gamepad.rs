@@ -0,0 +1,364 @@
+//! Gamepad buttons as hotkeys and as a bypass, read by polling XInput
+
+use crate::config::{GamepadConfig, HotkeyAction};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+use winapi::shared::minwindef::{DWORD, WORD};
+use winapi::shared::winerror::ERROR_SUCCESS;
+use winapi::um::xinput::*;
+
+// How often connected controllers are read, about 60 Hz
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
+// Reading an empty controller slot is slow, so those are only checked this often
+const EMPTY_SLOT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// XInput's name for every button, as written in config.ron
+const BUTTONS: [(&str, WORD); 14] = [
+    ("A", XINPUT_GAMEPAD_A),
+    ("B", XINPUT_GAMEPAD_B),
+    ("X", XINPUT_GAMEPAD_X),
+    ("Y", XINPUT_GAMEPAD_Y),
+    ("LeftShoulder", XINPUT_GAMEPAD_LEFT_SHOULDER),
+    ("RightShoulder", XINPUT_GAMEPAD_RIGHT_SHOULDER),
+    ("LeftThumb", XINPUT_GAMEPAD_LEFT_THUMB),
+    ("RightThumb", XINPUT_GAMEPAD_RIGHT_THUMB),
+    ("Start", XINPUT_GAMEPAD_START),
+    ("Back", XINPUT_GAMEPAD_BACK),
+    ("DPadUp", XINPUT_GAMEPAD_DPAD_UP),
+    ("DPadDown", XINPUT_GAMEPAD_DPAD_DOWN),
+    ("DPadLeft", XINPUT_GAMEPAD_DPAD_LEFT),
+    ("DPadRight", XINPUT_GAMEPAD_DPAD_RIGHT),
+];
+
+/// The `wButtons` bit of the button called `name`, e.g. `"RightShoulder"`
+pub fn button_mask(name: &str) -> Option<WORD> {
+    BUTTONS
+        .iter()
+        .find(|(button, _)| button.eq_ignore_ascii_case(name))
+        .map(|&(_, mask)| mask)
+}
+
+/// What a gamepad button asks of the main loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadEvent {
+    Action(HotkeyAction),
+    /// The bypass button went down (`true`) or came back up
+    Bypass(bool),
+}
+
+/// The configured buttons, turning changes in which buttons are held into
+/// events: actions on presses, the bypass on presses and releases
+pub struct GamepadBindings {
+    actions: Vec<(WORD, HotkeyAction)>,
+    bypass: Option<WORD>,
+    held: WORD,
+}
+
+impl GamepadBindings {
+    /// The bindings in `config`, plus a warning for each button name that
+    /// isn't recognized; those bindings are skipped
+    pub fn new(config: &GamepadConfig) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut mask_of = |name: &str, what: &dyn std::fmt::Debug| {
+            let mask = button_mask(name);
+            if mask.is_none() {
+                warnings.push(format!(
+                    "Unknown gamepad button {:?} for {:?}; binding skipped",
+                    name, what
+                ));
+            }
+            mask
+        };
+
+        let bypass = config
+            .bypass
+            .as_deref()
+            .and_then(|name| mask_of(name, &"bypass"));
+        let actions = config
+            .actions
+            .iter()
+            .filter_map(|(name, &action)| Some((mask_of(name, &action)?, action)))
+            .collect();
+
+        let bindings = Self {
+            actions,
+            bypass,
+            held: 0,
+        };
+        (bindings, warnings)
+    }
+
+    /// Whether no button does anything, so there's nothing to poll for
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty() && self.bypass.is_none()
+    }
+
+    /// The events for going from the buttons held last time to `held`
+    pub fn update(&mut self, held: WORD) -> Vec<GamepadEvent> {
+        let pressed = held & !self.held;
+        let released = self.held & !held;
+        self.held = held;
+
+        let mut events = Vec::new();
+        if let Some(bypass) = self.bypass {
+            if pressed & bypass != 0 {
+                events.push(GamepadEvent::Bypass(true));
+            } else if released & bypass != 0 {
+                events.push(GamepadEvent::Bypass(false));
+            }
+        }
+        events.extend(
+            self.actions
+                .iter()
+                .filter(|&&(mask, _)| pressed & mask != 0)
+                .map(|&(_, action)| GamepadEvent::Action(action)),
+        );
+        events
+    }
+}
+
+/// The buttons held on any connected controller
+fn buttons_held(states: &[Option<XINPUT_STATE>]) -> WORD {
+    states
+        .iter()
+        .flatten()
+        .fold(0, |held, state| held | state.Gamepad.wButtons)
+}
+
+/// One of the four controller slots XInput reports
+#[derive(Clone, Copy)]
+struct Slot {
+    connected: bool,
+    next_check: Instant,
+}
+
+/// Reads controller `index` if it's connected, or due a check for whether it
+/// has been, logging when it comes and goes
+fn poll_slot(index: DWORD, slot: &mut Slot, now: Instant) -> Option<XINPUT_STATE> {
+    if !slot.connected && now < slot.next_check {
+        return None;
+    }
+
+    let mut state: XINPUT_STATE = unsafe { std::mem::zeroed() };
+    let connected = unsafe { XInputGetState(index, &mut state) } == ERROR_SUCCESS;
+    if connected != slot.connected {
+        if connected {
+            info!(controller = index, "Gamepad connected");
+        } else {
+            info!(controller = index, "Gamepad disconnected");
+        }
+        slot.connected = connected;
+    }
+    if !connected {
+        slot.next_check = now + EMPTY_SLOT_INTERVAL;
+    }
+    connected.then_some(state)
+}
+
+/// Polls the controllers on a background thread for as long as it lives
+pub struct GamepadPoller {
+    config: GamepadConfig,
+    should_stop: Arc<AtomicBool>,
+    poller_thread: Option<thread::JoinHandle<()>>,
+}
+
+impl GamepadPoller {
+    /// Starts polling for `bindings`, made from `config`, calling `on_event`
+    /// from the polling thread. A bypass held when the poller is dropped is
+    /// left for the caller to release.
+    pub fn start<F>(
+        config: GamepadConfig,
+        mut bindings: GamepadBindings,
+        on_event: F,
+    ) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        F: Fn(GamepadEvent) + Send + 'static,
+    {
+        let should_stop = Arc::new(AtomicBool::new(false));
+        let stop = should_stop.clone();
+        let handle = thread::Builder::new()
+            .name("gamepad-poller".to_string())
+            .spawn(move || {
+                let mut slots = [Slot {
+                    connected: false,
+                    next_check: Instant::now(),
+                }; XUSER_MAX_COUNT as usize];
+
+                while !stop.load(Ordering::Relaxed) {
+                    let now = Instant::now();
+                    let mut states = [None; XUSER_MAX_COUNT as usize];
+                    for (index, (slot, state)) in slots.iter_mut().zip(&mut states).enumerate() {
+                        *state = poll_slot(index as DWORD, slot, now);
+                    }
+                    for event in bindings.update(buttons_held(&states)) {
+                        on_event(event);
+                    }
+
+                    thread::sleep(POLL_INTERVAL);
+                }
+
+                info!("Gamepad poller thread stopping");
+            })?;
+
+        Ok(Self {
+            config,
+            should_stop,
+            poller_thread: Some(handle),
+        })
+    }
+
+    /// The config the poller was started with
+    pub fn config(&self) -> &GamepadConfig {
+        &self.config
+    }
+}
+
+impl Drop for GamepadPoller {
+    fn drop(&mut self) {
+        if let Some(handle) = self.poller_thread.take() {
+            self.should_stop.store(true, Ordering::Relaxed);
+            if let Err(e) = handle.join() {
+                error!("Failed to join gamepad poller thread: {:?}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(buttons: WORD) -> Option<XINPUT_STATE> {
+        let mut state: XINPUT_STATE = unsafe { std::mem::zeroed() };
+        state.Gamepad.wButtons = buttons;
+        Some(state)
+    }
+
+    fn bindings() -> GamepadBindings {
+        let config = GamepadConfig {
+            bypass: Some("RightShoulder".to_string()),
+            actions: [
+                ("Back".to_string(), HotkeyAction::ToggleBarrier),
+                ("y".to_string(), HotkeyAction::ToggleHud),
+            ]
+            .into(),
+        };
+        let (bindings, warnings) = GamepadBindings::new(&config);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        bindings
+    }
+
+    #[test]
+    fn test_button_names() {
+        assert_eq!(
+            button_mask("RightShoulder"),
+            Some(XINPUT_GAMEPAD_RIGHT_SHOULDER)
+        );
+        assert_eq!(button_mask("back"), Some(XINPUT_GAMEPAD_BACK));
+        assert_eq!(button_mask("DPadLeft"), Some(XINPUT_GAMEPAD_DPAD_LEFT));
+        assert_eq!(button_mask("A"), Some(XINPUT_GAMEPAD_A));
+        assert_eq!(button_mask("RightTrigger"), None);
+        assert_eq!(button_mask(""), None);
+
+        // Every button has its own bit
+        let all = BUTTONS.iter().fold(0, |all: WORD, &(_, mask)| {
+            assert_eq!(all & mask, 0);
+            all | mask
+        });
+        assert_eq!(all.count_ones() as usize, BUTTONS.len());
+    }
+
+    #[test]
+    fn test_actions_fire_on_press_not_while_held() {
+        let mut bindings = bindings();
+        let back = buttons_held(&[state(XINPUT_GAMEPAD_BACK)]);
+
+        assert_eq!(
+            bindings.update(back),
+            [GamepadEvent::Action(HotkeyAction::ToggleBarrier)]
+        );
+        assert_eq!(bindings.update(back), []);
+        assert_eq!(bindings.update(0), []);
+        assert_eq!(
+            bindings.update(back | XINPUT_GAMEPAD_Y),
+            [
+                GamepadEvent::Action(HotkeyAction::ToggleBarrier),
+                GamepadEvent::Action(HotkeyAction::ToggleHud),
+            ]
+        );
+        // Unbound buttons do nothing
+        assert_eq!(bindings.update(XINPUT_GAMEPAD_A), []);
+    }
+
+    #[test]
+    fn test_bypass_follows_press_and_release() {
+        let mut bindings = bindings();
+        let shoulder = XINPUT_GAMEPAD_RIGHT_SHOULDER;
+
+        assert_eq!(bindings.update(shoulder), [GamepadEvent::Bypass(true)]);
+        assert_eq!(bindings.update(shoulder | XINPUT_GAMEPAD_A), []);
+        assert_eq!(
+            bindings.update(XINPUT_GAMEPAD_A),
+            [GamepadEvent::Bypass(false)]
+        );
+        assert_eq!(bindings.update(0), []);
+    }
+
+    #[test]
+    fn test_buttons_held_across_controllers() {
+        let states = [
+            state(XINPUT_GAMEPAD_BACK),
+            None,
+            state(XINPUT_GAMEPAD_RIGHT_SHOULDER),
+            None,
+        ];
+        assert_eq!(
+            buttons_held(&states),
+            XINPUT_GAMEPAD_BACK | XINPUT_GAMEPAD_RIGHT_SHOULDER
+        );
+
+        // A controller unplugged mid-press releases its buttons
+        let mut bindings = bindings();
+        bindings.update(buttons_held(&states));
+        assert_eq!(
+            bindings.update(buttons_held(&[None, None, None, None])),
+            [GamepadEvent::Bypass(false)]
+        );
+        assert_eq!(buttons_held(&[]), 0);
+    }
+
+    #[test]
+    fn test_unknown_buttons_are_skipped() {
+        let config = GamepadConfig {
+            bypass: Some("Paddle1".to_string()),
+            actions: [
+                ("Start".to_string(), HotkeyAction::ToggleBarrier),
+                ("Turbo".to_string(), HotkeyAction::ToggleHud),
+            ]
+            .into(),
+        };
+        let (mut bindings, warnings) = GamepadBindings::new(&config);
+        assert_eq!(warnings.len(), 2);
+        assert!(
+            warnings.iter().any(|w| w.contains("Paddle1")),
+            "{:?}",
+            warnings
+        );
+        assert!(
+            warnings.iter().any(|w| w.contains("Turbo")),
+            "{:?}",
+            warnings
+        );
+        assert!(!bindings.is_empty());
+        assert_eq!(
+            bindings.update(XINPUT_GAMEPAD_START),
+            [GamepadEvent::Action(HotkeyAction::ToggleBarrier)]
+        );
+
+        let (bindings, _) = GamepadBindings::new(&GamepadConfig::default());
+        assert!(bindings.is_empty());
+    }
+}
@@ -1,6 +1,70 @@
-use crate::config::{vk_code_from_string, HotkeyConfig};
+use crate::config::{
+    vk_code_from_string, ActionHotkeyConfig, ChordConfig, HotkeyAction, HotkeyConfig,
+};
+use mouse_barrier::KeyCombo;
+use std::io;
+use std::ptr;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+use winapi::shared::minwindef::{UINT, WPARAM};
 use winapi::um::winuser::*;
 
+// Registration id of the first hotkey registered with Windows; ids below
+// 0xC000 are the application's own
+const FIRST_REGISTERED_HOTKEY_ID: i32 = 1;
+
+/// A key going down or up, as the keyboard hook saw it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub vk_code: u32,
+    pub is_down: bool,
+    /// When the hook saw it, which may be well before the main loop does
+    pub at: Instant,
+}
+
+/// The keyboard hook callback: sends every key event to the main loop, which
+/// runs the detectors.
+///
+/// Nothing the main loop holds is locked here, so a slow config reload only
+/// delays when a hotkey is acted on, never the hook itself.
+pub fn forward_keys<T: Send + 'static>(
+    events: Sender<T>,
+    wrap: fn(KeyEvent) -> T,
+) -> impl Fn(u32, bool) + Send + Sync + 'static {
+    forward_keys_with(events, wrap, Instant::now)
+}
+
+/// [`forward_keys`], with the time each key is stamped with read from `now`
+fn forward_keys_with<T: Send + 'static>(
+    events: Sender<T>,
+    wrap: fn(KeyEvent) -> T,
+    now: impl Fn() -> Instant + Send + Sync + 'static,
+) -> impl Fn(u32, bool) + Send + Sync + 'static {
+    move |vk_code, is_down| {
+        let _ = events.send(wrap(KeyEvent {
+            vk_code,
+            is_down,
+            at: now(),
+        }));
+    }
+}
+
+enum Modifier {
+    Ctrl,
+    Alt,
+    Shift,
+}
+
+/// Which modifier `vk_code` is, counting the left and right keys as one
+fn modifier(vk_code: u32) -> Option<Modifier> {
+    match vk_code as i32 {
+        VK_CONTROL | VK_LCONTROL | VK_RCONTROL => Some(Modifier::Ctrl),
+        VK_MENU | VK_LMENU | VK_RMENU => Some(Modifier::Alt),
+        VK_SHIFT | VK_LSHIFT | VK_RSHIFT => Some(Modifier::Shift),
+        _ => None,
+    }
+}
+
 pub struct HotkeyDetector {
     config: HotkeyConfig,
     target_vk: u32,
@@ -23,17 +87,11 @@ impl HotkeyDetector {
     }
 
     pub fn handle_key(&mut self, vk_code: u32, is_down: bool) -> bool {
-        match vk_code {
-            x if x == VK_CONTROL as u32 || x == VK_LCONTROL as u32 || x == VK_RCONTROL as u32 => {
-                self.ctrl_pressed = is_down;
-            }
-            x if x == VK_MENU as u32 || x == VK_LMENU as u32 || x == VK_RMENU as u32 => {
-                self.alt_pressed = is_down;
-            }
-            x if x == VK_SHIFT as u32 || x == VK_LSHIFT as u32 || x == VK_RSHIFT as u32 => {
-                self.shift_pressed = is_down;
-            }
-            _ => {
+        match modifier(vk_code) {
+            Some(Modifier::Ctrl) => self.ctrl_pressed = is_down,
+            Some(Modifier::Alt) => self.alt_pressed = is_down,
+            Some(Modifier::Shift) => self.shift_pressed = is_down,
+            None => {
                 if vk_code == self.target_vk && is_down {
                     return self.is_hotkey_pressed();
                 }
@@ -43,18 +101,14 @@ impl HotkeyDetector {
         false
     }
 
-    pub fn update_config(&mut self, new_config: HotkeyConfig) -> Option<()> {
-        let target_vk = vk_code_from_string(&new_config.key)?;
-
-        self.config = new_config;
-        self.target_vk = target_vk;
-
-        // Reset modifier states to avoid confusion
-        self.ctrl_pressed = false;
-        self.alt_pressed = false;
-        self.shift_pressed = false;
-
-        Some(())
+    /// The hotkey as the keyboard hook matches it
+    pub fn key_combo(&self) -> KeyCombo {
+        KeyCombo {
+            vk_code: self.target_vk,
+            ctrl: self.config.ctrl,
+            alt: self.config.alt,
+            shift: self.config.shift,
+        }
     }
 
     fn is_hotkey_pressed(&self) -> bool {
@@ -64,10 +118,263 @@ impl HotkeyDetector {
     }
 }
 
+/// The detector to swap in for a reloaded hotkey, or `Ok(None)` if the
+/// hotkey didn't change.
+///
+/// A key that can't be resolved is an error, so the caller keeps the current
+/// detector rather than losing the toggle to a typo in config.ron.
+pub fn reload_detector(
+    current: &HotkeyConfig,
+    new: &HotkeyConfig,
+) -> Result<Option<HotkeyDetector>, String> {
+    if new == current {
+        return Ok(None);
+    }
+    HotkeyDetector::new(new.clone())
+        .map(Some)
+        .ok_or_else(|| format!("Unknown hotkey key {:?}", new.key))
+}
+
+/// The `action_hotkeys` from config.ron, each with its own detector
+#[derive(Default)]
+pub struct ActionHotkeys {
+    detectors: Vec<(HotkeyAction, HotkeyDetector)>,
+}
+
+impl ActionHotkeys {
+    /// Detectors for `bindings`, plus a warning for each binding skipped
+    /// because its key is unknown; one typo shouldn't cost the others
+    pub fn new(bindings: &[ActionHotkeyConfig]) -> (Self, Vec<String>) {
+        let mut detectors = Vec::new();
+        let mut warnings = Vec::new();
+        for binding in bindings {
+            match HotkeyDetector::new(binding.hotkey.clone()) {
+                Some(detector) => detectors.push((binding.action, detector)),
+                None => warnings.push(format!(
+                    "Unknown hotkey key {:?} for {:?}; binding skipped",
+                    binding.hotkey.key, binding.action
+                )),
+            }
+        }
+        (Self { detectors }, warnings)
+    }
+
+    /// The action whose hotkey this key completes, if any. Every detector
+    /// sees every key, so each keeps its own modifiers current.
+    pub fn handle_key(&mut self, vk_code: u32, is_down: bool) -> Option<HotkeyAction> {
+        let mut pressed = None;
+        for (action, detector) in &mut self.detectors {
+            if detector.handle_key(vk_code, is_down) && pressed.is_none() {
+                pressed = Some(*action);
+            }
+        }
+        pressed
+    }
+}
+
+/// The `EmergencyDisable` hotkeys from `action_hotkeys`, registered with
+/// Windows through `RegisterHotKey` instead of being matched by the keyboard
+/// hook, so they keep working while the hook is stuck, dropped or already
+/// torn down.
+///
+/// Windows posts `WM_HOTKEY` to the thread that registered them, whose
+/// message loop hands it to [`action`](Self::action). Dropping them
+/// unregisters them.
+#[derive(Default)]
+pub struct RegisteredHotkeys {
+    hotkeys: Vec<(i32, HotkeyAction)>, // Registration id and the action it runs
+}
+
+impl RegisteredHotkeys {
+    /// Registers the `EmergencyDisable` bindings among `bindings`. Returns
+    /// them, the bindings left for the keyboard hook, and a warning for each
+    /// one Windows refused, usually because another program has registered
+    /// the same keys; those are left for the keyboard hook too.
+    pub fn register(
+        bindings: &[ActionHotkeyConfig],
+    ) -> (Self, Vec<ActionHotkeyConfig>, Vec<String>) {
+        let mut registered = Self::default();
+        let mut hooked = Vec::new();
+        let mut warnings = Vec::new();
+        for binding in bindings {
+            // Unknown keys are reported along with the keyboard hook's bindings
+            let vk_code = match (binding.action, vk_code_from_string(&binding.hotkey.key)) {
+                (HotkeyAction::EmergencyDisable, Some(vk_code)) => vk_code,
+                _ => {
+                    hooked.push(binding.clone());
+                    continue;
+                }
+            };
+
+            let id = FIRST_REGISTERED_HOTKEY_ID + registered.hotkeys.len() as i32;
+            let modifiers = registered_modifiers(&binding.hotkey);
+            if unsafe { RegisterHotKey(ptr::null_mut(), id, modifiers, vk_code) } != 0 {
+                registered.hotkeys.push((id, binding.action));
+            } else {
+                warnings.push(format!(
+                    "Failed to register {} for {:?} with Windows ({}); the keyboard hook \
+                     watches for it instead",
+                    hotkey_name(&binding.hotkey),
+                    binding.action,
+                    io::Error::last_os_error()
+                ));
+                hooked.push(binding.clone());
+            }
+        }
+        (registered, hooked, warnings)
+    }
+
+    /// The action of the registered hotkey a `WM_HOTKEY` message with this
+    /// `wparam` reports, if it's one of these
+    pub fn action(&self, wparam: WPARAM) -> Option<HotkeyAction> {
+        self.hotkeys
+            .iter()
+            .find(|&&(id, _)| id as WPARAM == wparam)
+            .map(|&(_, action)| action)
+    }
+}
+
+impl Drop for RegisteredHotkeys {
+    fn drop(&mut self) {
+        for &(id, _) in &self.hotkeys {
+            unsafe {
+                UnregisterHotKey(ptr::null_mut(), id);
+            }
+        }
+    }
+}
+
+/// The `RegisterHotKey` modifiers for `config`. Holding the keys down doesn't
+/// repeat the hotkey.
+fn registered_modifiers(config: &HotkeyConfig) -> UINT {
+    let mut modifiers = MOD_NOREPEAT;
+    if config.ctrl {
+        modifiers |= MOD_CONTROL;
+    }
+    if config.alt {
+        modifiers |= MOD_ALT;
+    }
+    if config.shift {
+        modifiers |= MOD_SHIFT;
+    }
+    modifiers as UINT
+}
+
+/// The `chords` from config.ron: the leader hotkey, then one of the bound
+/// keys before the timeout runs out
+pub struct ChordDetector {
+    leader: HotkeyDetector,
+    bindings: Vec<(u32, HotkeyAction)>,
+    timeout: Duration,
+    // When the leader was pressed, while the second key is awaited
+    pending_since: Option<Instant>,
+}
+
+impl ChordDetector {
+    /// The detector for `config`, or `None` if its leader key is unknown,
+    /// plus a warning for each problem. Bindings with unknown keys are
+    /// skipped; the others still work.
+    pub fn new(config: &ChordConfig) -> (Option<Self>, Vec<String>) {
+        let Some(leader) = HotkeyDetector::new(config.leader.clone()) else {
+            let warning = format!("Unknown chord leader key {:?}", config.leader.key);
+            return (None, vec![warning]);
+        };
+
+        let mut bindings = Vec::new();
+        let mut warnings = Vec::new();
+        for (key, &action) in &config.bindings {
+            match vk_code_from_string(key) {
+                Some(vk_code) => bindings.push((vk_code, action)),
+                None => warnings.push(format!(
+                    "Unknown chord key {:?} for {:?}; binding skipped",
+                    key, action
+                )),
+            }
+        }
+
+        let detector = Self {
+            leader,
+            bindings,
+            timeout: Duration::from_millis(u64::from(config.timeout_ms)),
+            pending_since: None,
+        };
+        (Some(detector), warnings)
+    }
+
+    /// The leader as the keyboard hook matches it, for suppressing it
+    pub fn leader_combo(&self) -> KeyCombo {
+        self.leader.key_combo()
+    }
+
+    /// The action whose chord this key completes at `now`, if any.
+    ///
+    /// The leader starts the wait for a second key, and pressing it again
+    /// starts the wait over. The next key other than a modifier ends the wait,
+    /// bound or not, and a key after the timeout does nothing.
+    pub fn handle_key(
+        &mut self,
+        vk_code: u32,
+        is_down: bool,
+        now: Instant,
+    ) -> Option<HotkeyAction> {
+        if self.leader.handle_key(vk_code, is_down) {
+            self.pending_since = Some(now);
+            return None;
+        }
+        if !is_down || modifier(vk_code).is_some() {
+            return None;
+        }
+
+        let since = self.pending_since.take()?;
+        if now.saturating_duration_since(since) > self.timeout {
+            return None;
+        }
+        self.bindings
+            .iter()
+            .find(|&&(key, _)| key == vk_code)
+            .map(|&(_, action)| action)
+    }
+}
+
+/// `config` as it's pressed, e.g. `Ctrl+Alt+F12`
+pub fn hotkey_name(config: &HotkeyConfig) -> String {
+    format!(
+        "{}{}{}{}",
+        if config.ctrl { "Ctrl+" } else { "" },
+        if config.alt { "Alt+" } else { "" },
+        if config.shift { "Shift+" } else { "" },
+        config.key
+    )
+}
+
+/// Ignores toggles that follow the last accepted one too closely.
+///
+/// Each press is a separate key-down, so `HotkeyDetector` reports both halves
+/// of a nervous double-press; this keeps the second from undoing the first.
+#[derive(Debug, Default)]
+pub struct ToggleCooldown {
+    last_toggle: Option<Instant>,
+}
+
+impl ToggleCooldown {
+    /// Whether a toggle at `now` is allowed, remembering it if so
+    pub fn try_toggle(&mut self, now: Instant, cooldown: Duration) -> bool {
+        if let Some(last) = self.last_toggle {
+            if now.saturating_duration_since(last) < cooldown {
+                return false;
+            }
+        }
+        self.last_toggle = Some(now);
+        true
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::HotkeyConfig;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{mpsc, Arc};
 
     fn create_test_config(ctrl: bool, alt: bool, shift: bool, key: &str) -> HotkeyConfig {
         HotkeyConfig {
@@ -75,6 +382,7 @@ mod tests {
             alt,
             shift,
             key: key.to_string(),
+            toggle_cooldown_ms: 150,
         }
     }
 
@@ -100,6 +408,41 @@ mod tests {
         assert!(detector.is_none());
     }
 
+    #[test]
+    fn test_invalid_new_hotkey_keeps_old_binding() {
+        let current = create_test_config(true, false, false, "F12");
+        let typo = create_test_config(true, false, false, "F1Z");
+
+        let error = match reload_detector(&current, &typo) {
+            Err(error) => error,
+            Ok(_) => panic!("accepted an unknown key"),
+        };
+        assert_eq!(error, "Unknown hotkey key \"F1Z\"");
+    }
+
+    #[test]
+    fn test_reload_detector() {
+        let current = create_test_config(true, false, false, "F12");
+        assert!(matches!(reload_detector(&current, &current), Ok(None)));
+
+        let changed = create_test_config(false, true, false, "F11");
+        let mut detector = reload_detector(&current, &changed).unwrap().unwrap();
+        detector.handle_key(VK_MENU as u32, true);
+        assert!(detector.handle_key(VK_F11 as u32, true));
+    }
+
+    #[test]
+    fn test_hotkey_name() {
+        assert_eq!(
+            hotkey_name(&create_test_config(true, true, false, "F12")),
+            "Ctrl+Alt+F12"
+        );
+        assert_eq!(
+            hotkey_name(&create_test_config(false, false, true, "A")),
+            "Shift+A"
+        );
+    }
+
     #[test]
     fn test_handle_key_ctrl_press_release() {
         let config = create_test_config(true, false, false, "F12");
@@ -271,59 +614,6 @@ mod tests {
         assert!(!result);
     }
 
-    #[test]
-    fn test_update_config_valid_key() {
-        let initial_config = create_test_config(true, false, false, "F12");
-        let mut detector = HotkeyDetector::new(initial_config).unwrap();
-
-        let new_config = create_test_config(false, true, false, "F1");
-        let result = detector.update_config(new_config.clone());
-
-        assert!(result.is_some());
-        assert_eq!(detector.config, new_config);
-        assert_eq!(detector.target_vk, VK_F1 as u32);
-
-        // Modifier states should be reset
-        assert!(!detector.ctrl_pressed);
-        assert!(!detector.alt_pressed);
-        assert!(!detector.shift_pressed);
-    }
-
-    #[test]
-    fn test_update_config_invalid_key() {
-        let initial_config = create_test_config(true, false, false, "F12");
-        let mut detector = HotkeyDetector::new(initial_config.clone()).unwrap();
-
-        let new_config = create_test_config(false, true, false, "INVALID_KEY");
-        let result = detector.update_config(new_config);
-
-        assert!(result.is_none());
-        // Config should remain unchanged
-        assert_eq!(detector.config, initial_config);
-        assert_eq!(detector.target_vk, VK_F12 as u32);
-    }
-
-    #[test]
-    fn test_update_config_resets_modifier_states() {
-        let initial_config = create_test_config(true, false, false, "F12");
-        let mut detector = HotkeyDetector::new(initial_config).unwrap();
-
-        // Set some modifier states
-        detector.handle_key(VK_CONTROL as u32, true);
-        detector.handle_key(VK_SHIFT as u32, true);
-        assert!(detector.ctrl_pressed);
-        assert!(detector.shift_pressed);
-
-        // Update config
-        let new_config = create_test_config(false, true, false, "F1");
-        detector.update_config(new_config);
-
-        // Modifier states should be reset
-        assert!(!detector.ctrl_pressed);
-        assert!(!detector.alt_pressed);
-        assert!(!detector.shift_pressed);
-    }
-
     #[test]
     fn test_no_modifier_hotkey() {
         let config = create_test_config(false, false, false, "F12");
@@ -374,4 +664,307 @@ mod tests {
             assert!(result, "Hotkey should trigger for {}", digit);
         }
     }
+
+    #[test]
+    fn test_toggle_cooldown_ignores_rapid_presses() {
+        let cooldown = Duration::from_millis(150);
+        let start = Instant::now();
+        let mut toggles = ToggleCooldown::default();
+
+        assert!(toggles.try_toggle(start, cooldown));
+        assert!(!toggles.try_toggle(start + Duration::from_millis(100), cooldown));
+        // Measured from the last accepted toggle, not the ignored press
+        assert!(toggles.try_toggle(start + Duration::from_millis(150), cooldown));
+        assert!(!toggles.try_toggle(start + Duration::from_millis(299), cooldown));
+    }
+
+    #[test]
+    fn test_zero_toggle_cooldown_accepts_every_press() {
+        let start = Instant::now();
+        let mut toggles = ToggleCooldown::default();
+
+        assert!(toggles.try_toggle(start, Duration::ZERO));
+        assert!(toggles.try_toggle(start, Duration::ZERO));
+    }
+
+    fn binding(action: HotkeyAction, key: &str) -> ActionHotkeyConfig {
+        ActionHotkeyConfig {
+            action,
+            hotkey: create_test_config(true, false, false, key),
+        }
+    }
+
+    #[test]
+    fn test_action_hotkeys_report_their_action() {
+        let (mut hotkeys, warnings) = ActionHotkeys::new(&[
+            binding(HotkeyAction::EnableBarrier, "F10"),
+            binding(HotkeyAction::DisableBarrier, "F11"),
+        ]);
+        assert!(warnings.is_empty());
+
+        assert_eq!(hotkeys.handle_key(VK_CONTROL as u32, true), None);
+        assert_eq!(
+            hotkeys.handle_key(VK_F11 as u32, true),
+            Some(HotkeyAction::DisableBarrier)
+        );
+        assert_eq!(hotkeys.handle_key(VK_F11 as u32, false), None);
+        assert_eq!(
+            hotkeys.handle_key(VK_F10 as u32, true),
+            Some(HotkeyAction::EnableBarrier)
+        );
+
+        // Without Ctrl neither binding fires
+        hotkeys.handle_key(VK_CONTROL as u32, false);
+        assert_eq!(hotkeys.handle_key(VK_F10 as u32, true), None);
+    }
+
+    #[test]
+    fn test_action_hotkeys_skip_unknown_keys() {
+        let (mut hotkeys, warnings) = ActionHotkeys::new(&[
+            binding(HotkeyAction::EnableBarrier, "F1Z"),
+            binding(HotkeyAction::DisableBarrier, "F11"),
+        ]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("F1Z"), "{}", warnings[0]);
+
+        hotkeys.handle_key(VK_CONTROL as u32, true);
+        assert_eq!(
+            hotkeys.handle_key(VK_F11 as u32, true),
+            Some(HotkeyAction::DisableBarrier)
+        );
+    }
+
+    #[test]
+    fn test_registered_hotkeys_leave_other_bindings_to_the_hook() {
+        // Only EmergencyDisable with a known key goes to Windows
+        let bindings = [
+            binding(HotkeyAction::EnableBarrier, "F10"),
+            binding(HotkeyAction::EmergencyDisable, "F1Z"),
+        ];
+        let (registered, hooked, warnings) = RegisteredHotkeys::register(&bindings);
+
+        assert_eq!(hooked, bindings);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(
+            registered.action(FIRST_REGISTERED_HOTKEY_ID as WPARAM),
+            None
+        );
+    }
+
+    #[test]
+    fn test_emergency_disable_is_registered_with_windows() {
+        let emergency = ActionHotkeyConfig {
+            action: HotkeyAction::EmergencyDisable,
+            hotkey: create_test_config(true, true, true, "F9"),
+        };
+        let bindings = [binding(HotkeyAction::EnableBarrier, "F10"), emergency];
+        let (registered, hooked, warnings) = RegisteredHotkeys::register(&bindings);
+
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(hooked, bindings[..1]);
+        assert_eq!(
+            registered.action(FIRST_REGISTERED_HOTKEY_ID as WPARAM),
+            Some(HotkeyAction::EmergencyDisable)
+        );
+        assert_eq!(
+            registered.action(FIRST_REGISTERED_HOTKEY_ID as WPARAM + 1),
+            None
+        );
+
+        // Dropping them unregisters them, so a reload can register them again
+        drop(registered);
+        let (registered, _, warnings) = RegisteredHotkeys::register(&bindings[1..]);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(
+            registered.action(FIRST_REGISTERED_HOTKEY_ID as WPARAM),
+            Some(HotkeyAction::EmergencyDisable)
+        );
+    }
+
+    #[test]
+    fn test_registered_modifiers_never_repeat() {
+        assert_eq!(
+            registered_modifiers(&create_test_config(false, false, false, "F12")),
+            MOD_NOREPEAT as UINT
+        );
+        assert_eq!(
+            registered_modifiers(&create_test_config(true, true, true, "F12")),
+            (MOD_NOREPEAT | MOD_CONTROL | MOD_ALT | MOD_SHIFT) as UINT
+        );
+        assert_eq!(
+            registered_modifiers(&create_test_config(true, false, true, "P")),
+            (MOD_NOREPEAT | MOD_CONTROL | MOD_SHIFT) as UINT
+        );
+    }
+
+    /// Ctrl+M as the leader, with B and H bound
+    fn chords() -> ChordDetector {
+        let config = ChordConfig {
+            leader: create_test_config(true, false, false, "M"),
+            timeout_ms: 1000,
+            suppress_leader: true,
+            bindings: [
+                ("B".to_string(), HotkeyAction::ToggleBarrier),
+                ("H".to_string(), HotkeyAction::ToggleHud),
+            ]
+            .into(),
+        };
+        let (detector, warnings) = ChordDetector::new(&config);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        detector.unwrap()
+    }
+
+    /// Presses Ctrl+M at `at` and lets go of both keys
+    fn press_leader(chords: &mut ChordDetector, at: Instant) {
+        assert_eq!(chords.handle_key(VK_CONTROL as u32, true, at), None);
+        assert_eq!(chords.handle_key('M' as u32, true, at), None);
+        assert_eq!(chords.handle_key('M' as u32, false, at), None);
+        assert_eq!(chords.handle_key(VK_CONTROL as u32, false, at), None);
+    }
+
+    #[test]
+    fn test_chord_sequence_reports_its_action() {
+        let mut chords = chords();
+        let start = Instant::now();
+
+        press_leader(&mut chords, start);
+        let second = start + Duration::from_millis(400);
+        assert_eq!(
+            chords.handle_key('H' as u32, true, second),
+            Some(HotkeyAction::ToggleHud)
+        );
+        assert_eq!(chords.handle_key('H' as u32, false, second), None);
+
+        // The chord is used up: B on its own does nothing
+        assert_eq!(chords.handle_key('B' as u32, true, second), None);
+
+        // Holding Ctrl into the second key is fine too
+        assert_eq!(chords.handle_key(VK_CONTROL as u32, true, second), None);
+        assert_eq!(chords.handle_key('M' as u32, true, second), None);
+        assert_eq!(
+            chords.handle_key('B' as u32, true, second),
+            Some(HotkeyAction::ToggleBarrier)
+        );
+    }
+
+    #[test]
+    fn test_chord_expires_after_timeout() {
+        let mut chords = chords();
+        let start = Instant::now();
+
+        press_leader(&mut chords, start);
+        let late = start + Duration::from_millis(1001);
+        assert_eq!(chords.handle_key('B' as u32, true, late), None);
+        // The late key ended the wait rather than leaving it open
+        assert_eq!(chords.handle_key('B' as u32, true, late), None);
+
+        press_leader(&mut chords, late);
+        assert_eq!(
+            chords.handle_key('B' as u32, true, late + Duration::from_millis(1000)),
+            Some(HotkeyAction::ToggleBarrier)
+        );
+    }
+
+    #[test]
+    fn test_chord_wrong_second_key_cancels() {
+        let mut chords = chords();
+        let start = Instant::now();
+
+        press_leader(&mut chords, start);
+        assert_eq!(chords.handle_key('X' as u32, true, start), None);
+        assert_eq!(chords.handle_key('B' as u32, true, start), None);
+
+        // Modifiers alone don't end the wait
+        press_leader(&mut chords, start);
+        assert_eq!(chords.handle_key(VK_SHIFT as u32, true, start), None);
+        assert_eq!(chords.handle_key(VK_SHIFT as u32, false, start), None);
+        assert_eq!(
+            chords.handle_key('B' as u32, true, start),
+            Some(HotkeyAction::ToggleBarrier)
+        );
+    }
+
+    #[test]
+    fn test_chord_leader_twice_restarts_the_wait() {
+        let mut chords = chords();
+        let start = Instant::now();
+
+        press_leader(&mut chords, start);
+        let again = start + Duration::from_millis(900);
+        press_leader(&mut chords, again);
+
+        // Timed from the second leader, not the first
+        assert_eq!(
+            chords.handle_key('H' as u32, true, again + Duration::from_millis(900)),
+            Some(HotkeyAction::ToggleHud)
+        );
+        // And the leader never completes a chord of its own
+        press_leader(&mut chords, again);
+        press_leader(&mut chords, again);
+        assert_eq!(chords.handle_key('M' as u32, true, again), None);
+    }
+
+    #[test]
+    fn test_keys_queued_behind_a_slow_reload_keep_their_timing() {
+        let start = Instant::now();
+        let elapsed_ms = Arc::new(AtomicU64::new(0));
+        let (tx, rx) = mpsc::channel();
+        let forward = {
+            let elapsed_ms = elapsed_ms.clone();
+            forward_keys_with(
+                tx,
+                |key| key,
+                move || start + Duration::from_millis(elapsed_ms.load(Ordering::Relaxed)),
+            )
+        };
+
+        // The chord is typed within its timeout...
+        forward(VK_CONTROL as u32, true);
+        forward('M' as u32, true);
+        elapsed_ms.store(200, Ordering::Relaxed);
+        forward('B' as u32, true);
+
+        // ...while the main loop spends five seconds on a reload, and only
+        // then gets to the queued keys
+        elapsed_ms.store(5_000, Ordering::Relaxed);
+        let mut chords = chords();
+        let actions: Vec<_> = rx
+            .try_iter()
+            .filter_map(|key| chords.handle_key(key.vk_code, key.is_down, key.at))
+            .collect();
+
+        assert_eq!(actions, [HotkeyAction::ToggleBarrier]);
+    }
+
+    #[test]
+    fn test_chord_skips_unknown_keys() {
+        let mut config = ChordConfig {
+            leader: create_test_config(true, false, false, "M"),
+            timeout_ms: 1000,
+            suppress_leader: true,
+            bindings: [
+                ("B".to_string(), HotkeyAction::ToggleBarrier),
+                ("F1Z".to_string(), HotkeyAction::ToggleHud),
+            ]
+            .into(),
+        };
+        let (detector, warnings) = ChordDetector::new(&config);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("F1Z"), "{}", warnings[0]);
+        let detector = detector.unwrap();
+        assert_eq!(
+            detector.leader_combo(),
+            KeyCombo {
+                vk_code: 'M' as u32,
+                ctrl: true,
+                alt: false,
+                shift: false,
+            }
+        );
+
+        config.leader.key = "F1Z".to_string();
+        let (detector, warnings) = ChordDetector::new(&config);
+        assert!(detector.is_none());
+        assert!(warnings[0].contains("leader"), "{}", warnings[0]);
+    }
 }
@@ -1,17 +1,16 @@
-use crate::config::{HudConfig, HudPosition};
+use crate::config::{CoordinateOrigin, HudConfig, HudCoordinateSpace, HudPosition};
+use crate::uptime::{format_uptime, Uptime};
+use mouse_barrier::{
+    suggested_buffer_zone, BarrierSnapshot, BufferZone, CursorSpeed, EnforcementMode, LastPush,
+    PushEdge, Rect, ScreenPlacement, Zone,
+};
 use std::ffi::OsStr;
-
-pub struct BarrierStateConfig {
-    pub enabled: bool,
-    pub x: i32,
-    pub y: i32,
-    pub width: i32,
-    pub height: i32,
-    pub buffer_zone: i32,
-    pub push_factor: i32,
-}
 use std::os::windows::ffi::OsStrExt;
 use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use tracing::warn;
 use winapi::shared::minwindef::*;
 use winapi::shared::windef::*;
 use winapi::um::libloaderapi::GetModuleHandleW;
@@ -20,11 +19,30 @@ use winapi::um::winuser::*;
 
 // HUD window dimensions and layout constants
 const HUD_WIDTH: i32 = 300;
-const HUD_HEIGHT: i32 = 180;
+const HUD_HEIGHT: i32 = 294;
 const HUD_MARGIN: i32 = 20;
 const HUD_PADDING: i32 = 10;
 const HUD_LINE_HEIGHT: i32 = 18;
 const HUD_TITLE_SPACING: i32 = 5;
+const HUD_FONT_HEIGHT: i32 = 14;
+// Periodic repaint so timers and state changes show while the mouse is idle
+const HUD_REPAINT_TIMER_ID: usize = 1;
+const HUD_REPAINT_INTERVAL_MS: u32 = 100;
+// Characters of the config path that fit beside the "Config: " label
+const HUD_PATH_MAX_CHARS: usize = 32;
+
+// DPI of the monitor the HUD is on; the layout constants above are for 96 DPI
+static HUD_DPI: AtomicU32 = AtomicU32::new(USER_DEFAULT_SCREEN_DPI as u32);
+// Configured background alpha, used unless the system turns transparency off
+static HUD_BACKGROUND_ALPHA: AtomicU8 = AtomicU8::new(255);
+// Inputs to the palette choice; see select_palette
+static HUD_HIGH_CONTRAST: AtomicBool = AtomicBool::new(false);
+static HUD_COLORBLIND_SAFE: AtomicBool = AtomicBool::new(false);
+// Whether the HUD raises itself above the barrier overlays when it moves
+static HUD_ABOVE_OVERLAY: AtomicBool = AtomicBool::new(true);
+
+/// Window class of the HUD, for finding ones left behind by a previous run
+pub const HUD_CLASS_NAME: &str = "AgeOfCrashHUD";
 
 // HUD color constants (COLORREF format: 0x00BBGGRR)
 const COLOR_WHITE: u32 = 0x00FFFFFF;
@@ -34,150 +52,277 @@ const COLOR_RED: u32 = 0x006464FF;
 const COLOR_YELLOW: u32 = 0x0064FFFF;
 const COLOR_DANGER_RED: u32 = 0x000000FF;
 
+/// Colors the HUD draws with, in COLORREF format
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HudPalette {
+    text: u32,
+    background: u32,
+    enabled: u32,
+    disabled: u32,
+    warning: u32,
+    danger: u32,
+}
+
+const STANDARD_PALETTE: HudPalette = HudPalette {
+    text: COLOR_WHITE,
+    background: COLOR_BLACK,
+    enabled: COLOR_GREEN,
+    disabled: COLOR_RED,
+    warning: COLOR_YELLOW,
+    danger: COLOR_DANGER_RED,
+};
+
+// Sky blue, orange, yellow and vermillion from the Okabe-Ito palette, so
+// enabled and disabled never hinge on telling red from green
+const COLORBLIND_SAFE_PALETTE: HudPalette = HudPalette {
+    text: COLOR_WHITE,
+    background: COLOR_BLACK,
+    enabled: 0x00E9B456,
+    disabled: 0x00009FE6,
+    warning: 0x0042E4F0,
+    danger: 0x00005ED5,
+};
+
+/// The palette for the current settings. A high contrast theme's own colors
+/// win over `colorblind_safe`, since the user picked them for a reason.
+fn select_palette(
+    high_contrast: bool,
+    colorblind_safe: bool,
+    system: impl FnOnce() -> HudPalette,
+) -> HudPalette {
+    if high_contrast {
+        system()
+    } else if colorblind_safe {
+        COLORBLIND_SAFE_PALETTE
+    } else {
+        STANDARD_PALETTE
+    }
+}
+
+/// The active theme's colors. High contrast themes only guarantee contrast
+/// for a few of them against the window color, so warnings reuse the text
+/// color and the wording carries the difference.
+fn system_palette() -> HudPalette {
+    unsafe {
+        HudPalette {
+            text: GetSysColor(COLOR_WINDOWTEXT),
+            background: GetSysColor(COLOR_WINDOW),
+            enabled: GetSysColor(COLOR_HOTLIGHT),
+            disabled: GetSysColor(COLOR_GRAYTEXT),
+            warning: GetSysColor(COLOR_WINDOWTEXT),
+            danger: GetSysColor(COLOR_HOTLIGHT),
+        }
+    }
+}
+
+fn current_palette() -> HudPalette {
+    select_palette(
+        HUD_HIGH_CONTRAST.load(Ordering::Relaxed),
+        HUD_COLORBLIND_SAFE.load(Ordering::Relaxed),
+        system_palette,
+    )
+}
+
+/// The HUD overlay window
+///
+/// The window lives on its own UI thread with its own message loop, so it paints
+/// no matter which thread creates the `Hud` or how busy the main loop is. Other
+/// threads talk to it through [`HudCommand`]s.
 pub struct Hud {
-    hwnd: HWND,
     config: HudConfig,
-    enabled: bool,
-    barrier_enabled: bool,
-    barrier_x: i32,
-    barrier_y: i32,
-    barrier_width: i32,
-    barrier_height: i32,
-    buffer_zone: i32,
-    push_factor: i32,
+    ui: Option<HudThread>,
 }
 
 impl Hud {
     pub fn new(config: HudConfig) -> Result<Self, Box<dyn std::error::Error>> {
-        if !config.enabled {
-            return Ok(Self {
-                hwnd: ptr::null_mut(),
-                config,
-                enabled: false,
-                barrier_enabled: false,
-                barrier_x: 0,
-                barrier_y: 0,
-                barrier_width: 0,
-                barrier_height: 0,
-                buffer_zone: 0,
-                push_factor: 0,
-            });
-        }
-
-        let hwnd = create_hud_window(&config)?;
+        HUD_ABOVE_OVERLAY.store(config.hud_above_overlay, Ordering::Relaxed);
+        let ui = if config.enabled {
+            Some(HudThread::spawn(&config)?)
+        } else {
+            None
+        };
 
-        Ok(Self {
-            hwnd,
-            config,
-            enabled: true,
-            barrier_enabled: false,
-            barrier_x: 0,
-            barrier_y: 0,
-            barrier_width: 0,
-            barrier_height: 0,
-            buffer_zone: 0,
-            push_factor: 0,
-        })
+        Ok(Self { config, ui })
     }
 
     pub fn update_config(
         &mut self,
         new_config: HudConfig,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        if new_config.enabled && !self.enabled {
+        HUD_ABOVE_OVERLAY.store(new_config.hud_above_overlay, Ordering::Relaxed);
+        match (new_config.enabled, self.ui.take()) {
             // Create window if it doesn't exist
-            self.hwnd = create_hud_window(&new_config)?;
-            self.enabled = true;
-        } else if !new_config.enabled && self.enabled {
+            (true, None) => self.ui = Some(HudThread::spawn(&new_config)?),
             // Destroy window if it exists
-            if !self.hwnd.is_null() {
-                unsafe {
-                    DestroyWindow(self.hwnd);
+            (false, Some(ui)) => ui.shutdown(),
+            // Update existing window position if needed
+            (true, Some(ui)) => {
+                if new_config.position != self.config.position {
+                    ui.send(HudCommand::Reposition(new_config.position.clone()));
                 }
-                self.hwnd = ptr::null_mut();
+                self.ui = Some(ui);
             }
-            self.enabled = false;
-        } else if self.enabled {
-            // Update existing window position if needed
-            self.update_position(&new_config)?;
+            (false, None) => {}
         }
 
         self.config = new_config;
+        self.refresh()
+    }
 
-        if self.enabled {
-            self.refresh_display()?;
-        }
-
-        Ok(())
+    /// The HUD window's raw `HWND`, while it is shown
+    pub fn window_handle(&self) -> Option<isize> {
+        self.ui.as_ref().map(|ui| ui.hwnd as isize)
     }
 
-    pub fn update_barrier_state(
-        &mut self,
-        config: BarrierStateConfig,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        self.barrier_enabled = config.enabled;
-        self.barrier_x = config.x;
-        self.barrier_y = config.y;
-        self.barrier_width = config.width;
-        self.barrier_height = config.height;
-        self.buffer_zone = config.buffer_zone;
-        self.push_factor = config.push_factor;
-
-        if self.enabled {
-            self.refresh_display()?;
+    /// Repaints the HUD immediately, e.g. after the barrier was toggled
+    pub fn refresh(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(ui) = &self.ui {
+            ui.send(HudCommand::Refresh);
         }
 
         Ok(())
     }
+}
 
-    fn update_position(&self, config: &HudConfig) -> Result<(), Box<dyn std::error::Error>> {
-        if self.hwnd.is_null() {
-            return Ok(());
+impl Drop for Hud {
+    fn drop(&mut self) {
+        if let Some(ui) = self.ui.take() {
+            ui.shutdown();
         }
+    }
+}
 
-        let (x, y) = calculate_hud_position(&config.position)?;
+/// Requests handled on the HUD's UI thread
+enum HudCommand {
+    Reposition(HudPosition),
+    Refresh,
+    Close,
+}
 
-        unsafe {
-            SetWindowPos(
-                self.hwnd,
-                HWND_TOPMOST,
-                x,
-                y,
-                HUD_WIDTH,
-                HUD_HEIGHT,
-                SWP_NOACTIVATE | SWP_NOOWNERZORDER,
-            );
-        }
+/// Posted to the HUD window to wake its thread up to drain the command channel
+const WM_HUD_COMMAND: UINT = WM_APP + 1;
 
-        Ok(())
-    }
+/// The HUD window together with the thread that owns it
+struct HudThread {
+    hwnd: HWND,
+    commands: Sender<HudCommand>,
+    handle: JoinHandle<()>,
+}
 
-    fn refresh_display(&self) -> Result<(), Box<dyn std::error::Error>> {
-        if self.hwnd.is_null() {
-            return Ok(());
+impl HudThread {
+    fn spawn(config: &HudConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<usize, String>>();
+        let (commands, command_rx) = mpsc::channel();
+        let config = config.clone();
+
+        let handle = thread::Builder::new()
+            .name("hud-ui".to_string())
+            .spawn(move || {
+                // The window has to be created here so its messages arrive on this thread
+                let hwnd = match create_hud_window(&config) {
+                    Ok(hwnd) => hwnd,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e.to_string()));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(hwnd as usize));
+                run_hud_message_loop(hwnd, &command_rx);
+            })?;
+
+        match ready_rx.recv() {
+            Ok(Ok(hwnd)) => Ok(Self {
+                hwnd: hwnd as HWND,
+                commands,
+                handle,
+            }),
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                Err(e.into())
+            }
+            Err(_) => {
+                let _ = handle.join();
+                Err("HUD thread exited before creating its window".into())
+            }
         }
+    }
 
-        unsafe {
-            InvalidateRect(self.hwnd, ptr::null(), TRUE);
-            UpdateWindow(self.hwnd);
+    fn send(&self, command: HudCommand) {
+        if self.commands.send(command).is_ok() {
+            unsafe {
+                PostMessageW(self.hwnd, WM_HUD_COMMAND, 0, 0);
+            }
         }
+    }
 
-        Ok(())
+    /// Destroys the window on its own thread, then waits for the thread to exit
+    fn shutdown(self) {
+        self.send(HudCommand::Close);
+        if self.handle.join().is_err() {
+            warn!("HUD thread panicked");
+        }
     }
 }
 
-impl Drop for Hud {
-    fn drop(&mut self) {
-        if !self.hwnd.is_null() {
-            unsafe {
-                DestroyWindow(self.hwnd);
+fn run_hud_message_loop(hwnd: HWND, commands: &Receiver<HudCommand>) {
+    unsafe {
+        let mut msg: MSG = std::mem::zeroed();
+        // Ends once WM_DESTROY posts WM_QUIT
+        while GetMessageW(&mut msg, ptr::null_mut(), 0, 0) > 0 {
+            if msg.message == WM_HUD_COMMAND {
+                for command in commands.try_iter() {
+                    match command {
+                        HudCommand::Reposition(position) => reposition_hud_window(hwnd, &position),
+                        HudCommand::Refresh => {
+                            InvalidateRect(hwnd, ptr::null(), TRUE);
+                            UpdateWindow(hwnd);
+                        }
+                        HudCommand::Close => {
+                            DestroyWindow(hwnd);
+                            break;
+                        }
+                    }
+                }
+                continue;
             }
+
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
         }
     }
 }
 
+fn reposition_hud_window(hwnd: HWND, position: &HudPosition) {
+    let (x, y) = match calculate_hud_position(position) {
+        Ok(pos) => pos,
+        Err(e) => {
+            warn!("Failed to position HUD: {}", e);
+            return;
+        }
+    };
+
+    let (width, height) = hud_size();
+    // Below the overlays, stay put in the z-order rather than jump above them
+    let z_order = if HUD_ABOVE_OVERLAY.load(Ordering::Relaxed) {
+        0
+    } else {
+        SWP_NOZORDER
+    };
+    unsafe {
+        SetWindowPos(
+            hwnd,
+            HWND_TOPMOST,
+            x,
+            y,
+            width,
+            height,
+            SWP_NOACTIVATE | SWP_NOOWNERZORDER | z_order,
+        );
+    }
+}
+
 fn create_hud_window(config: &HudConfig) -> Result<HWND, Box<dyn std::error::Error>> {
-    let class_name: Vec<u16> = OsStr::new("AgeOfCrashHUD")
+    let class_name: Vec<u16> = OsStr::new(HUD_CLASS_NAME)
         .encode_wide()
         .chain(std::iter::once(0))
         .collect();
@@ -206,6 +351,7 @@ fn create_hud_window(config: &HudConfig) -> Result<HWND, Box<dyn std::error::Err
     }
 
     let (x, y) = calculate_hud_position(&config.position)?;
+    let (width, height) = hud_size();
 
     let hwnd = unsafe {
         CreateWindowExW(
@@ -215,8 +361,8 @@ fn create_hud_window(config: &HudConfig) -> Result<HWND, Box<dyn std::error::Err
             WS_POPUP,
             x,
             y,
-            HUD_WIDTH,
-            HUD_HEIGHT,
+            width,
+            height,
             ptr::null_mut(),
             ptr::null_mut(),
             GetModuleHandleW(ptr::null()),
@@ -228,30 +374,67 @@ fn create_hud_window(config: &HudConfig) -> Result<HWND, Box<dyn std::error::Err
         return Err("Failed to create HUD window".into());
     }
 
+    // WM_DPICHANGED only comes when the HUD moves to another monitor, so start
+    // from the scaling of the one it opened on, and resize to match
+    let dpi = unsafe { GetDpiForWindow(hwnd) };
+    if dpi != 0 && dpi != HUD_DPI.swap(dpi, Ordering::Relaxed) {
+        reposition_hud_window(hwnd, &config.position);
+    }
+
     // Set window transparency
+    HUD_BACKGROUND_ALPHA.store(config.background_alpha, Ordering::Relaxed);
     unsafe {
-        SetLayeredWindowAttributes(hwnd, 0, config.background_alpha, LWA_ALPHA);
+        apply_system_settings(hwnd);
 
         ShowWindow(hwnd, SW_SHOWNOACTIVATE);
         UpdateWindow(hwnd);
+
+        SetTimer(hwnd, HUD_REPAINT_TIMER_ID, HUD_REPAINT_INTERVAL_MS, None);
     }
 
     Ok(hwnd)
 }
 
+/// Follows the system's display settings: the theme's colors under high
+/// contrast, and an opaque window while transparency effects are off
+unsafe fn apply_system_settings(hwnd: HWND) {
+    let settings = mouse_barrier::accessibility_settings();
+    HUD_HIGH_CONTRAST.store(settings.high_contrast, Ordering::Relaxed);
+
+    let alpha = if settings.prefers_solid() {
+        255
+    } else {
+        HUD_BACKGROUND_ALPHA.load(Ordering::Relaxed)
+    };
+    SetLayeredWindowAttributes(hwnd, 0, alpha, LWA_ALPHA);
+    InvalidateRect(hwnd, ptr::null(), FALSE);
+}
+
+/// `value`, laid out for 96 DPI, scaled to `dpi`
+fn scale_to_dpi(value: i32, dpi: u32) -> i32 {
+    (value as i64 * dpi as i64 / USER_DEFAULT_SCREEN_DPI as i64) as i32
+}
+
+/// The HUD window size at the DPI of the monitor it is on
+fn hud_size() -> (i32, i32) {
+    let dpi = HUD_DPI.load(Ordering::Relaxed);
+    (scale_to_dpi(HUD_WIDTH, dpi), scale_to_dpi(HUD_HEIGHT, dpi))
+}
+
 fn calculate_hud_position(
     position: &HudPosition,
 ) -> Result<(i32, i32), Box<dyn std::error::Error>> {
     let screen_width = unsafe { GetSystemMetrics(SM_CXSCREEN) };
     let screen_height = unsafe { GetSystemMetrics(SM_CYSCREEN) };
+    let (width, height) = hud_size();
 
     let (x, y) = match position {
         HudPosition::TopLeft => (HUD_MARGIN, HUD_MARGIN),
-        HudPosition::TopRight => (screen_width - HUD_WIDTH - HUD_MARGIN, HUD_MARGIN),
-        HudPosition::BottomLeft => (HUD_MARGIN, screen_height - HUD_HEIGHT - HUD_MARGIN),
+        HudPosition::TopRight => (screen_width - width - HUD_MARGIN, HUD_MARGIN),
+        HudPosition::BottomLeft => (HUD_MARGIN, screen_height - height - HUD_MARGIN),
         HudPosition::BottomRight => (
-            screen_width - HUD_WIDTH - HUD_MARGIN,
-            screen_height - HUD_HEIGHT - HUD_MARGIN,
+            screen_width - width - HUD_MARGIN,
+            screen_height - height - HUD_MARGIN,
         ),
     };
 
@@ -281,7 +464,7 @@ unsafe extern "system" fn hud_window_proc(
 
             // Create fonts and brushes
             let font = CreateFontW(
-                14,
+                scale_to_dpi(HUD_FONT_HEIGHT, HUD_DPI.load(Ordering::Relaxed)),
                 0,
                 0,
                 0,
@@ -300,16 +483,17 @@ unsafe extern "system" fn hud_window_proc(
             let old_font = SelectObject(mem_dc, font as *mut _);
 
             // Set text colors on memory DC
-            SetTextColor(mem_dc, COLOR_WHITE); // White text
+            let palette = current_palette();
+            SetTextColor(mem_dc, palette.text);
             SetBkMode(mem_dc, TRANSPARENT as i32);
 
             // Draw background on memory DC
-            let bg_brush = CreateSolidBrush(COLOR_BLACK); // Black background
+            let bg_brush = CreateSolidBrush(palette.background);
             FillRect(mem_dc, &rect, bg_brush);
             DeleteObject(bg_brush as *mut _);
 
             // Draw HUD content on memory DC
-            draw_hud_content(mem_dc, &rect);
+            draw_hud_content(mem_dc, &rect, &palette);
 
             // Copy from memory DC to screen DC (this reduces flicker)
             BitBlt(
@@ -334,63 +518,170 @@ unsafe extern "system" fn hud_window_proc(
             EndPaint(hwnd, &ps);
             0
         }
-        WM_DESTROY => 0,
+        WM_TIMER if wparam == HUD_REPAINT_TIMER_ID => {
+            // Shares the mouse-driven throttle so the two never paint back to back
+            let due = LAST_REFRESH
+                .lock()
+                .map(|mut last_refresh| claim_refresh(&mut last_refresh, Instant::now()))
+                .unwrap_or(false);
+            if due {
+                InvalidateRect(hwnd, ptr::null(), FALSE);
+            }
+            0
+        }
+        WM_DPICHANGED => {
+            // The new DPI is in the low word; take the size Windows suggests for it
+            HUD_DPI.store(LOWORD(wparam as DWORD) as u32, Ordering::Relaxed);
+            let suggested = &*(lparam as *const RECT);
+            SetWindowPos(
+                hwnd,
+                ptr::null_mut(),
+                suggested.left,
+                suggested.top,
+                suggested.right - suggested.left,
+                suggested.bottom - suggested.top,
+                SWP_NOZORDER | SWP_NOACTIVATE,
+            );
+            InvalidateRect(hwnd, ptr::null(), TRUE);
+            0
+        }
+        WM_SETTINGCHANGE => {
+            // Cheap enough to re-read on every setting rather than pick out
+            // the high contrast and transparency ones
+            apply_system_settings(hwnd);
+            0
+        }
+        WM_DESTROY => {
+            KillTimer(hwnd, HUD_REPAINT_TIMER_ID);
+            // Ends the HUD thread's message loop
+            PostQuitMessage(0);
+            0
+        }
         _ => DefWindowProcW(hwnd, msg, wparam, lparam),
     }
 }
 
-unsafe fn draw_hud_content(hdc: HDC, rect: &RECT) {
-    let state = HUD_STATE.lock().unwrap();
+unsafe fn draw_hud_content(hdc: HDC, rect: &RECT, palette: &HudPalette) {
+    // Painting is slow next to the hook's updates, so draw from a copy
+    let state = snapshot(&HUD_STATE, &HUD_CURSOR);
+    let dpi = HUD_DPI.load(Ordering::Relaxed);
+    let padding = scale_to_dpi(HUD_PADDING, dpi);
+    let line_height = scale_to_dpi(HUD_LINE_HEIGHT, dpi);
 
-    let mut y_pos = rect.top + HUD_PADDING;
+    let mut y_pos = rect.top + padding;
 
     // Title
-    let title_text: Vec<u16> = OsStr::new("Age of Crash - by HousedHorse")
+    let title = format!(
+        "Age of Crash v{} - by HousedHorse",
+        env!("CARGO_PKG_VERSION")
+    );
+    let title_text: Vec<u16> = OsStr::new(&title)
         .encode_wide()
         .chain(std::iter::once(0))
         .collect();
 
     TextOutW(
         hdc,
-        rect.left + HUD_PADDING,
+        rect.left + padding,
         y_pos,
         title_text.as_ptr(),
         title_text.len() as i32 - 1,
     );
-    y_pos += HUD_LINE_HEIGHT + HUD_TITLE_SPACING;
+    y_pos += line_height;
 
-    // Status with color coding
-    let status_text = if state.enabled {
-        "Status: ENABLED"
-    } else {
-        "Status: DISABLED"
-    };
+    // Config file in use, so it's clear which profile is loaded
+    let config_text = format!(
+        "Config: {}",
+        truncate_path(&state.config_path, HUD_PATH_MAX_CHARS)
+    );
+    let config_wide: Vec<u16> = OsStr::new(&config_text)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    TextOutW(
+        hdc,
+        rect.left + padding,
+        y_pos,
+        config_wide.as_ptr(),
+        config_wide.len() as i32 - 1,
+    );
+    y_pos += line_height + scale_to_dpi(HUD_TITLE_SPACING, dpi);
+
+    let barrier_enabled = state.barrier.is_some_and(|barrier| barrier.enabled);
+    let warn_only = barrier_enabled
+        && state
+            .barrier
+            .is_some_and(|barrier| barrier.enforcement_mode == EnforcementMode::WarnOnly);
 
-    let status_wide: Vec<u16> = OsStr::new(status_text)
+    // Status with color coding
+    let status_wide: Vec<u16> = OsStr::new(status_text(state.barrier.as_ref()))
         .encode_wide()
         .chain(std::iter::once(0))
         .collect();
 
     // Color code based on status
-    if state.enabled {
-        SetTextColor(hdc, COLOR_GREEN); // Green for enabled
+    if warn_only {
+        SetTextColor(hdc, palette.warning);
+    } else if barrier_enabled {
+        SetTextColor(hdc, palette.enabled);
     } else {
-        SetTextColor(hdc, COLOR_RED); // Red for disabled
+        SetTextColor(hdc, palette.disabled);
     }
 
     TextOutW(
         hdc,
-        rect.left + HUD_PADDING,
+        rect.left + padding,
         y_pos,
         status_wide.as_ptr(),
         status_wide.len() as i32 - 1,
     );
-    y_pos += HUD_LINE_HEIGHT;
+    y_pos += line_height;
 
-    SetTextColor(hdc, COLOR_WHITE); // Back to white
+    SetTextColor(hdc, palette.text); // Back to the text color
 
-    // Coordinates
-    let coord_text = format!("Position: ({}, {})", state.x, state.y);
+    // Time actually protected, paused while the barrier is off or suspended
+    let uptime_text = format!(
+        "Enabled for {} this session",
+        format_uptime(state.uptime.total(Instant::now()))
+    );
+    let uptime_wide: Vec<u16> = OsStr::new(&uptime_text)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    TextOutW(
+        hdc,
+        rect.left + padding,
+        y_pos,
+        uptime_wide.as_ptr(),
+        uptime_wide.len() as i32 - 1,
+    );
+    y_pos += line_height;
+
+    // Coordinates, labelled with the corner they anchor so they read the same as config.ron
+    let (barrier_origin, mouse_origin) =
+        display_origins(state.coordinate_space, state.window_origin);
+    let (x, y, width, height, buffer_zone, push_factor) = match state.barrier {
+        Some(barrier) => {
+            let (x, y) = anchor_corner(&barrier, state.coordinate_origin, barrier_origin);
+            (
+                x,
+                y,
+                barrier.rect.right - barrier.rect.left,
+                barrier.rect.bottom - barrier.rect.top,
+                barrier.buffer,
+                barrier.push_factor,
+            )
+        }
+        None => (0, 0, 0, 0, BufferZone::default(), 0),
+    };
+    let coord_text = format!(
+        "Position ({}): ({}, {})",
+        origin_label(state.coordinate_origin),
+        x,
+        y
+    );
     let coord_wide: Vec<u16> = OsStr::new(&coord_text)
         .encode_wide()
         .chain(std::iter::once(0))
@@ -398,15 +689,15 @@ unsafe fn draw_hud_content(hdc: HDC, rect: &RECT) {
 
     TextOutW(
         hdc,
-        rect.left + HUD_PADDING,
+        rect.left + padding,
         y_pos,
         coord_wide.as_ptr(),
         coord_wide.len() as i32 - 1,
     );
-    y_pos += HUD_LINE_HEIGHT;
+    y_pos += line_height;
 
     // Size
-    let size_text = format!("Size: {} x {}", state.width, state.height);
+    let size_text = format!("Size: {} x {}", width, height);
     let size_wide: Vec<u16> = OsStr::new(&size_text)
         .encode_wide()
         .chain(std::iter::once(0))
@@ -414,15 +705,15 @@ unsafe fn draw_hud_content(hdc: HDC, rect: &RECT) {
 
     TextOutW(
         hdc,
-        rect.left + HUD_PADDING,
+        rect.left + padding,
         y_pos,
         size_wide.as_ptr(),
         size_wide.len() as i32 - 1,
     );
-    y_pos += HUD_LINE_HEIGHT;
+    y_pos += line_height;
 
     // Buffer zone
-    let buffer_text = format!("Buffer Zone: {}px", state.buffer_zone);
+    let buffer_text = buffer_zone_text(buffer_zone);
     let buffer_wide: Vec<u16> = OsStr::new(&buffer_text)
         .encode_wide()
         .chain(std::iter::once(0))
@@ -430,15 +721,15 @@ unsafe fn draw_hud_content(hdc: HDC, rect: &RECT) {
 
     TextOutW(
         hdc,
-        rect.left + HUD_PADDING,
+        rect.left + padding,
         y_pos,
         buffer_wide.as_ptr(),
         buffer_wide.len() as i32 - 1,
     );
-    y_pos += HUD_LINE_HEIGHT;
+    y_pos += line_height;
 
     // Push factor
-    let push_text = format!("Push Factor: {}px", state.push_factor);
+    let push_text = format!("Push Factor: {}px", push_factor);
     let push_wide: Vec<u16> = OsStr::new(&push_text)
         .encode_wide()
         .chain(std::iter::once(0))
@@ -446,37 +737,61 @@ unsafe fn draw_hud_content(hdc: HDC, rect: &RECT) {
 
     TextOutW(
         hdc,
-        rect.left + HUD_PADDING,
+        rect.left + padding,
         y_pos,
         push_wide.as_ptr(),
         push_wide.len() as i32 - 1,
     );
-    y_pos += HUD_LINE_HEIGHT;
+    y_pos += line_height;
 
     // Mouse position in yellow
-    let mouse_text = format!("Mouse: ({}, {})", state.mouse_x, state.mouse_y);
+    let mouse_text = format!(
+        "Mouse: ({}, {})",
+        state.mouse_x - mouse_origin.0,
+        state.mouse_y - mouse_origin.1
+    );
     let mouse_wide: Vec<u16> = OsStr::new(&mouse_text)
         .encode_wide()
         .chain(std::iter::once(0))
         .collect();
 
-    SetTextColor(hdc, COLOR_YELLOW); // Yellow color
+    SetTextColor(hdc, palette.warning);
     TextOutW(
         hdc,
-        rect.left + HUD_PADDING,
+        rect.left + padding,
         y_pos,
         mouse_wide.as_ptr(),
         mouse_wide.len() as i32 - 1,
     );
-    y_pos += HUD_LINE_HEIGHT;
+    y_pos += line_height;
+
+    // How far the cursor is from the barrier, the number to tune against
+    if let Some(barrier) = &state.barrier {
+        let offset_text = format!(
+            "Cursor: {}",
+            barrier_offset(&barrier.rect, state.mouse_x, state.mouse_y)
+        );
+        let offset_wide: Vec<u16> = OsStr::new(&offset_text)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        SetTextColor(hdc, palette.text);
+        TextOutW(
+            hdc,
+            rect.left + padding,
+            y_pos,
+            offset_wide.as_ptr(),
+            offset_wide.len() as i32 - 1,
+        );
+        y_pos += line_height;
+    }
 
     // Mouse in barrier status
-    let barrier_status_text = if state.mouse_in_barrier {
-        "Mouse Status: IN BARRIER"
-    } else if state.mouse_in_buffer {
-        "Mouse Status: IN BUFFER ZONE"
-    } else {
-        "Mouse Status: Okay"
+    let barrier_status_text = match state.mouse_zone {
+        Zone::Barrier => "Mouse Status: IN BARRIER",
+        Zone::Buffer => "Mouse Status: IN BUFFER ZONE",
+        Zone::Outside => "Mouse Status: Okay",
     };
 
     let barrier_status_wide: Vec<u16> = OsStr::new(barrier_status_text)
@@ -485,126 +800,504 @@ unsafe fn draw_hud_content(hdc: HDC, rect: &RECT) {
         .collect();
 
     // Color based on mouse location
-    if state.mouse_in_barrier {
-        SetTextColor(hdc, COLOR_DANGER_RED); // Red when in inner barrier
-    } else if state.mouse_in_buffer {
-        SetTextColor(hdc, COLOR_YELLOW); // Yellow when in buffer zone
-    } else {
-        SetTextColor(hdc, COLOR_WHITE); // White when okay
-    }
+    match state.mouse_zone {
+        Zone::Barrier => SetTextColor(hdc, palette.danger), // Inner barrier
+        Zone::Buffer => SetTextColor(hdc, palette.warning), // Buffer zone
+        Zone::Outside => SetTextColor(hdc, palette.text),   // Okay
+    };
 
     TextOutW(
         hdc,
-        rect.left + HUD_PADDING,
+        rect.left + padding,
         y_pos,
         barrier_status_wide.as_ptr(),
         barrier_status_wide.len() as i32 - 1,
     );
+
+    // Why the barrier isn't being enforced, e.g. the game window is missing
+    if let Some(notice) = &state.notice {
+        y_pos += line_height;
+
+        let notice_wide: Vec<u16> = OsStr::new(notice)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        SetTextColor(hdc, palette.warning);
+        TextOutW(
+            hdc,
+            rect.left + padding,
+            y_pos,
+            notice_wide.as_ptr(),
+            notice_wide.len() as i32 - 1,
+        );
+    }
+
+    // The barrier is enforced but the user can't see where
+    if state.overlay_warning {
+        y_pos += line_height;
+
+        let warning_wide: Vec<u16> = OsStr::new("Overlay unavailable - barrier is invisible")
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        SetTextColor(hdc, palette.warning);
+        TextOutW(
+            hdc,
+            rect.left + padding,
+            y_pos,
+            warning_wide.as_ptr(),
+            warning_wide.len() as i32 - 1,
+        );
+    }
+
+    // The barrier is partly or entirely outside the screen
+    if let Some(warning) = state.screen_warning {
+        y_pos += line_height;
+
+        let warning_wide: Vec<u16> = OsStr::new(warning)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        SetTextColor(hdc, palette.warning);
+        TextOutW(
+            hdc,
+            rect.left + padding,
+            y_pos,
+            warning_wide.as_ptr(),
+            warning_wide.len() as i32 - 1,
+        );
+    }
+
+    // A hook was dropped and couldn't be reinstalled yet
+    if state.hook_warning {
+        y_pos += line_height;
+
+        let warning_wide: Vec<u16> = OsStr::new("Hooks lost - barrier/hotkey not working")
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        SetTextColor(hdc, palette.danger);
+        TextOutW(
+            hdc,
+            rect.left + padding,
+            y_pos,
+            warning_wide.as_ptr(),
+            warning_wide.len() as i32 - 1,
+        );
+    }
+
+    // Our hooks see no input while an elevated window has focus
+    if state.elevation_warning {
+        y_pos += line_height;
+
+        let warning_wide: Vec<u16> = OsStr::new("Game is elevated - barrier inactive")
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        SetTextColor(hdc, palette.danger);
+        TextOutW(
+            hdc,
+            rect.left + padding,
+            y_pos,
+            warning_wide.as_ptr(),
+            warning_wide.len() as i32 - 1,
+        );
+    }
+
+    // What the hook last did to the cursor, for tuning push_factor
+    if state.debug {
+        y_pos += line_height;
+
+        let push_text = match mouse_barrier::last_push() {
+            Some(push) => format_last_push(&push, Instant::now()),
+            None => "Last push: none".to_string(),
+        };
+        let push_wide: Vec<u16> = OsStr::new(&push_text)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        SetTextColor(hdc, palette.text);
+        TextOutW(
+            hdc,
+            rect.left + padding,
+            y_pos,
+            push_wide.as_ptr(),
+            push_wide.len() as i32 - 1,
+        );
+
+        // How fast the cursor moves, for sizing buffer_zone
+        y_pos += line_height;
+
+        let speed_text = format_cursor_speed(&mouse_barrier::cursor_speed());
+        let speed_wide: Vec<u16> = OsStr::new(&speed_text)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        TextOutW(
+            hdc,
+            rect.left + padding,
+            y_pos,
+            speed_wide.as_ptr(),
+            speed_wide.len() as i32 - 1,
+        );
+    }
 }
 
 // Global HUD state for access from window procedure
-use std::sync::Arc;
-use std::sync::Mutex;
+use std::sync::{Mutex, PoisonError, RwLock};
 use std::time::{Duration, Instant};
 
+const REFRESH_INTERVAL: Duration = Duration::from_millis(33); // ~30 FPS
+
+#[derive(Clone)]
 pub struct HudState {
-    pub enabled: bool,
-    pub x: i32,
-    pub y: i32,
-    pub width: i32,
-    pub height: i32,
-    pub buffer_zone: i32,
-    pub push_factor: i32,
+    pub barrier: Option<BarrierSnapshot>, // Set once the barrier has been created
+    pub coordinate_origin: CoordinateOrigin,
+    pub window_origin: (i32, i32), // Top-left of the window the barrier follows, if any
+    pub coordinate_space: HudCoordinateSpace,
+    pub uptime: Uptime, // How long the barrier has been enabled and not suspended
+    pub config_path: String,
+    // Filled in from HUD_CURSOR by `snapshot`; the copies in HUD_STATE are unused
     pub mouse_x: i32,
     pub mouse_y: i32,
-    pub mouse_in_barrier: bool,
-    pub mouse_in_buffer: bool,
-    pub last_refresh: Instant,
+    pub mouse_zone: Zone,
+    pub notice: Option<String>,
+    pub overlay_warning: bool, // Barrier enforced without its overlay
+    pub screen_warning: Option<&'static str>, // Barrier placed outside the screen
+    pub hook_warning: bool,    // A hook that should be installed isn't
+    pub elevation_warning: bool, // The foreground window is elevated and we aren't
+    pub debug: bool,           // Show push diagnostics
 }
 
 lazy_static::lazy_static! {
-    static ref HUD_STATE: Arc<Mutex<HudState>> = Arc::new(Mutex::new(HudState {
-        enabled: false,
-        x: 0,
-        y: 0,
-        width: 0,
-        height: 0,
-        buffer_zone: 0,
-        push_factor: 0,
+    // Changes rarely; painting only ever takes it for reading
+    static ref HUD_STATE: RwLock<HudState> = RwLock::new(HudState {
+        barrier: None,
+        coordinate_origin: CoordinateOrigin::BottomLeft,
+        window_origin: (0, 0),
+        coordinate_space: HudCoordinateSpace::Mixed,
+        uptime: Uptime::default(),
+        config_path: String::new(),
         mouse_x: 0,
         mouse_y: 0,
-        mouse_in_barrier: false,
-        mouse_in_buffer: false,
-        last_refresh: Instant::now(),
-    }));
-}
-
-pub fn update_global_hud_state(
-    enabled: bool,
-    x: i32,
-    y: i32,
-    width: i32,
-    height: i32,
-    buffer_zone: i32,
-    push_factor: i32,
-) {
-    if let Ok(mut state) = HUD_STATE.lock() {
-        state.enabled = enabled;
-        state.x = x;
-        state.y = y;
-        state.width = width;
-        state.height = height;
-        state.buffer_zone = buffer_zone;
-        state.push_factor = push_factor;
+        mouse_zone: Zone::Outside,
+        notice: None,
+        overlay_warning: false,
+        screen_warning: None,
+        hook_warning: false,
+        elevation_warning: false,
+        debug: false,
+    });
+    // Kept apart from HUD_STATE so throttling repaints never waits on a paint
+    static ref LAST_REFRESH: Mutex<Instant> = Mutex::new(Instant::now());
+}
+
+/// The cursor position the HUD shows, written at mouse-event rate without
+/// taking any lock
+static HUD_CURSOR: CursorSlot = CursorSlot::new();
+
+/// A cursor position packed into one atomic, so a reader always sees an `x`
+/// and `y` that were stored together
+struct CursorSlot {
+    packed: AtomicU64,
+}
+
+impl CursorSlot {
+    const fn new() -> Self {
+        Self {
+            packed: AtomicU64::new(0),
+        }
+    }
+
+    fn store(&self, x: i32, y: i32) {
+        let packed = (u64::from(x as u32) << 32) | u64::from(y as u32);
+        self.packed.store(packed, Ordering::Relaxed);
+    }
+
+    fn load(&self) -> (i32, i32) {
+        let packed = self.packed.load(Ordering::Relaxed);
+        ((packed >> 32) as u32 as i32, packed as u32 as i32)
+    }
+}
+
+/// Copies the HUD state out with the latest cursor position, holding the read
+/// lock only for the copy. A panic in one of the updaters leaves the state
+/// usable, so recover it from the poison.
+fn snapshot(state: &RwLock<HudState>, cursor: &CursorSlot) -> HudState {
+    let mut snapshot = state.read().unwrap_or_else(PoisonError::into_inner).clone();
+    (snapshot.mouse_x, snapshot.mouse_y) = cursor.load();
+    // Classify with the hook's own geometry so edge pixels agree with it
+    snapshot.mouse_zone = mouse_zone(
+        snapshot.barrier.as_ref(),
+        snapshot.mouse_x,
+        snapshot.mouse_y,
+    );
+    snapshot
+}
+
+/// Applies `update` to the HUD state. Like [`snapshot`], it recovers the state
+/// from the poison a panicking updater left, so one failure doesn't freeze
+/// the HUD on what it showed then.
+fn update_state(state: &RwLock<HudState>, update: impl FnOnce(&mut HudState)) {
+    update(&mut state.write().unwrap_or_else(PoisonError::into_inner));
+}
+
+/// Replaces the barrier the HUD reports on with the one the hook is enforcing
+pub fn update_barrier_snapshot(snapshot: BarrierSnapshot) {
+    update_state(&HUD_STATE, |state| state.barrier = Some(snapshot));
+}
+
+pub fn update_window_origin(origin: (i32, i32)) {
+    update_state(&HUD_STATE, |state| state.window_origin = origin);
+}
+
+pub fn update_coordinate_origin(origin: CoordinateOrigin) {
+    update_state(&HUD_STATE, |state| state.coordinate_origin = origin);
+}
+
+/// Replaces the uptime clock; the HUD reads the running total off it as it paints
+pub fn update_uptime(uptime: Uptime) {
+    update_state(&HUD_STATE, |state| state.uptime = uptime);
+}
+
+pub fn update_coordinate_space(space: HudCoordinateSpace) {
+    update_state(&HUD_STATE, |state| state.coordinate_space = space);
+}
+
+/// Shows a warning line at the bottom of the HUD, or clears it with `None`
+pub fn update_notice(notice: Option<&str>) {
+    update_state(&HUD_STATE, |state| {
+        state.notice = notice.map(str::to_string)
+    });
+    refresh_hud_windows();
+}
+
+pub fn update_overlay_warning(warning: bool) {
+    update_state(&HUD_STATE, |state| state.overlay_warning = warning);
+}
+
+/// Warns about a barrier the cursor can't reach, or that was moved to where it can
+pub fn update_screen_placement(placement: ScreenPlacement) {
+    update_state(&HUD_STATE, |state| {
+        state.screen_warning = screen_warning(placement)
+    });
+    refresh_hud_windows();
+}
+
+fn status_text(barrier: Option<&BarrierSnapshot>) -> &'static str {
+    match barrier {
+        Some(barrier) if barrier.enabled => match barrier.enforcement_mode {
+            EnforcementMode::Enforce => "Status: ENABLED",
+            // Armed, but letting the cursor through
+            EnforcementMode::WarnOnly => "Status: WARN ONLY",
+        },
+        _ => "Status: DISABLED",
+    }
+}
+
+fn screen_warning(placement: ScreenPlacement) -> Option<&'static str> {
+    match placement {
+        ScreenPlacement::OnScreen | ScreenPlacement::PartlyOffScreen => None,
+        ScreenPlacement::OffScreen => Some("Barrier off-screen!"),
+        ScreenPlacement::MovedOnScreen => Some("Barrier was off-screen - moved on-screen"),
+    }
+}
+
+pub fn update_hook_warning(warning: bool) {
+    update_state(&HUD_STATE, |state| state.hook_warning = warning);
+}
+
+pub fn update_elevation_warning(warning: bool) {
+    update_state(&HUD_STATE, |state| state.elevation_warning = warning);
+    refresh_hud_windows();
+}
+
+pub fn update_debug(debug: bool) {
+    update_state(&HUD_STATE, |state| state.debug = debug);
+}
+
+/// Switches between the standard and the colorblind-safe palette
+pub fn update_colorblind_safe(colorblind_safe: bool) {
+    HUD_COLORBLIND_SAFE.store(colorblind_safe, Ordering::Relaxed);
+    refresh_hud_windows();
+}
+
+pub fn update_config_path(path: &str) {
+    update_state(&HUD_STATE, |state| state.config_path = path.to_string());
+}
+
+/// The buffer zone line: one width, or each edge's when they differ
+fn buffer_zone_text(buffer_zone: BufferZone) -> String {
+    match buffer_zone.as_uniform() {
+        Some(width) => format!("Buffer Zone: {}px", width),
+        None => format!(
+            "Buffer Zone: T{} R{} B{} L{}px",
+            buffer_zone.top, buffer_zone.right, buffer_zone.bottom, buffer_zone.left
+        ),
+    }
+}
+
+/// Shortens `path` to at most `max_chars` characters by eliding its middle.
+/// The file name is kept whole since it's the part that tells profiles apart,
+/// and the start of the path fills whatever room is left; a file name too long
+/// for that keeps only its end.
+fn truncate_path(path: &str, max_chars: usize) -> String {
+    const ELLIPSIS: &str = "...";
+
+    let len = path.chars().count();
+    if len <= max_chars {
+        return path.to_string();
+    }
+
+    let keep = max_chars.saturating_sub(ELLIPSIS.len());
+    let file_name_len = path
+        .chars()
+        .rev()
+        .position(|c| c == '\\' || c == '/')
+        .map_or(len, |position| position + 1);
+    let tail_len = file_name_len.min(keep);
+    let head: String = path.chars().take(keep - tail_len).collect();
+    let tail: String = path.chars().skip(len - tail_len).collect();
+    format!("{}{}{}", head, ELLIPSIS, tail)
+}
+
+/// Where the cursor is, as the hook sees it; always `Outside` while the barrier is off
+fn mouse_zone(barrier: Option<&BarrierSnapshot>, x: i32, y: i32) -> Zone {
+    match barrier {
+        Some(barrier) if barrier.enabled => barrier.zone_at(x, y),
+        _ => Zone::Outside,
+    }
+}
+
+/// The corner `x`/`y` refer to in config.ron, recovered from the barrier rectangle
+/// and made relative to `window_origin` when the barrier follows a game window
+fn anchor_corner(
+    barrier: &BarrierSnapshot,
+    origin: CoordinateOrigin,
+    window_origin: (i32, i32),
+) -> (i32, i32) {
+    let (x, y) = match origin {
+        CoordinateOrigin::BottomLeft => (barrier.rect.left, barrier.rect.bottom),
+        CoordinateOrigin::TopLeft => (barrier.rect.left, barrier.rect.top),
+    };
+    (x - window_origin.0, y - window_origin.1)
+}
+
+/// Where the barrier position and the cursor are measured from in `space`
+fn display_origins(
+    space: HudCoordinateSpace,
+    window_origin: (i32, i32),
+) -> ((i32, i32), (i32, i32)) {
+    match space {
+        HudCoordinateSpace::Mixed => (window_origin, (0, 0)),
+        HudCoordinateSpace::Screen => ((0, 0), (0, 0)),
+        HudCoordinateSpace::Config => (window_origin, window_origin),
+    }
+}
+
+/// Where `(x, y)` is relative to the nearest edge of `barrier` from inside
+/// it, or the edge it is furthest past from outside, e.g. `12px above top edge`
+fn barrier_offset(barrier: &Rect, x: i32, y: i32) -> String {
+    // Pixels inside each edge; right and bottom are exclusive
+    let edges = [
+        ("top", "above", y - barrier.top),
+        ("bottom", "below", barrier.bottom - 1 - y),
+        ("left", "left of", x - barrier.left),
+        ("right", "right of", barrier.right - 1 - x),
+    ];
+    let (edge, outside, inside) = edges
+        .into_iter()
+        .min_by_key(|&(_, _, inside)| inside)
+        .expect("a rectangle has edges");
+    match inside {
+        0 => format!("on {} edge", edge),
+        inside if inside > 0 => format!("{}px inside {} edge", inside, edge),
+        inside => format!("{}px {} {} edge", -inside, outside, edge),
+    }
+}
+
+/// One-line summary of a push, e.g. `Push (120,950)->(120,900) top x1.6 0.4s`
+fn format_last_push(push: &LastPush, now: Instant) -> String {
+    let edge = match push.edge {
+        Some(PushEdge::Left) => "left",
+        Some(PushEdge::Right) => "right",
+        Some(PushEdge::Top) => "top",
+        Some(PushEdge::Bottom) => "bottom",
+        None => "clamped",
+    };
+    format!(
+        "Push ({},{})->({},{}) {} x{:.1} {:.1}s",
+        push.from.x,
+        push.from.y,
+        push.to.x,
+        push.to.y,
+        edge,
+        push.multiplier,
+        now.saturating_duration_since(push.timestamp).as_secs_f64()
+    )
+}
+
+/// Current and peak cursor speed, e.g. `Speed: 15 px/ev 1200 px/s, peak 92/4800`
+fn format_cursor_speed(speed: &CursorSpeed) -> String {
+    format!(
+        "Speed: {} px/ev {} px/s, peak {}/{}",
+        speed.per_event, speed.per_second, speed.peak_per_event, speed.peak_per_second
+    )
+}
+
+/// A suggestion to widen `buffer` when the fastest approach this session could
+/// cross it in one mouse event, or `None` if it's wide enough
+pub fn buffer_zone_hint(speed: &CursorSpeed, buffer: BufferZone) -> Option<String> {
+    let suggested = suggested_buffer_zone(speed.peak_approach_per_event, buffer)?;
+    Some(format!(
+        "Fastest approach this session: {} px/event - buffer_zone {} may be too small, try {}",
+        speed.peak_approach_per_event,
+        buffer.narrowest(),
+        suggested
+    ))
+}
+
+fn origin_label(origin: CoordinateOrigin) -> &'static str {
+    match origin {
+        CoordinateOrigin::BottomLeft => "bottom-left",
+        CoordinateOrigin::TopLeft => "top-left",
     }
 }
 
 pub fn update_mouse_position(x: i32, y: i32) {
-    const REFRESH_INTERVAL: Duration = Duration::from_millis(33); // ~30 FPS
-
-    if let Ok(mut state) = HUD_STATE.lock() {
-        state.mouse_x = x;
-        state.mouse_y = y;
-
-        // Check if mouse is in barrier zone
-        if state.enabled {
-            // Convert from Windows top-left origin to bottom-left origin for comparison
-            let barrier_bottom = state.y;
-            let barrier_top = state.y - state.height;
-            let barrier_left = state.x;
-            let barrier_right = state.x + state.width;
-
-            // Check if mouse is within inner barrier (without buffer)
-            let in_inner_barrier =
-                x >= barrier_left && x <= barrier_right && y >= barrier_top && y <= barrier_bottom;
-
-            // Check if mouse is within barrier + buffer zone
-            let in_buffer_zone = x >= (barrier_left - state.buffer_zone)
-                && x <= (barrier_right + state.buffer_zone)
-                && y >= (barrier_top - state.buffer_zone)
-                && y <= (barrier_bottom + state.buffer_zone);
-
-            state.mouse_in_barrier = in_inner_barrier;
-            state.mouse_in_buffer = in_buffer_zone && !in_inner_barrier;
-        } else {
-            state.mouse_in_barrier = false;
-            state.mouse_in_buffer = false;
-        }
+    HUD_CURSOR.store(x, y);
+
+    // Only refresh if enough time has passed since last refresh
+    let due = LAST_REFRESH
+        .lock()
+        .map(|mut last_refresh| claim_refresh(&mut last_refresh, Instant::now()))
+        .unwrap_or(false);
+    if due {
+        refresh_hud_windows();
+    }
+}
 
-        // Only refresh if enough time has passed since last refresh
-        let now = Instant::now();
-        if now.duration_since(state.last_refresh) >= REFRESH_INTERVAL {
-            state.last_refresh = now;
-            drop(state); // Release lock before calling refresh
-            refresh_hud_windows();
-        }
+/// Records a repaint at `now` unless one already happened within `REFRESH_INTERVAL`
+fn claim_refresh(last_refresh: &mut Instant, now: Instant) -> bool {
+    if now.duration_since(*last_refresh) >= REFRESH_INTERVAL {
+        *last_refresh = now;
+        true
+    } else {
+        false
     }
 }
 
 fn refresh_hud_windows() {
     unsafe {
         // Find the HUD window by class name and refresh it efficiently
-        let class_name: Vec<u16> = std::ffi::OsStr::new("AgeOfCrashHUD")
+        let class_name: Vec<u16> = std::ffi::OsStr::new(HUD_CLASS_NAME)
             .encode_wide()
             .chain(std::iter::once(0))
             .collect();
@@ -622,45 +1315,31 @@ fn refresh_hud_windows() {
 mod tests {
     use super::*;
     use crate::config::HudPosition;
-
-    fn create_test_barrier_state_config(
-        enabled: bool,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
-        buffer_zone: i32,
-        push_factor: i32,
-    ) -> BarrierStateConfig {
-        BarrierStateConfig {
+    use mouse_barrier::Point;
+    use std::sync::Arc;
+
+    fn test_snapshot(enabled: bool) -> BarrierSnapshot {
+        BarrierSnapshot {
+            // 200x100 barrier with its bottom-left corner at (100, 500)
+            rect: mouse_barrier::barrier_rect(
+                100,
+                500,
+                200,
+                100,
+                CoordinateOrigin::BottomLeft.into(),
+            ),
+            buffer: BufferZone::uniform(25),
             enabled,
-            x,
-            y,
-            width,
-            height,
-            buffer_zone,
-            push_factor,
+            push_factor: 50,
+            enforcement_mode: EnforcementMode::Enforce,
         }
     }
 
-    #[test]
-    fn test_barrier_state_config_creation() {
-        let config = create_test_barrier_state_config(true, 100, 200, 300, 150, 25, 50);
-
-        assert!(config.enabled);
-        assert_eq!(config.x, 100);
-        assert_eq!(config.y, 200);
-        assert_eq!(config.width, 300);
-        assert_eq!(config.height, 150);
-        assert_eq!(config.buffer_zone, 25);
-        assert_eq!(config.push_factor, 50);
-    }
-
     #[test]
     fn test_hud_constants() {
         // Test that HUD constants have expected values (not optimized out since we're testing actual values)
         assert_eq!(HUD_WIDTH, 300);
-        assert_eq!(HUD_HEIGHT, 180);
+        assert_eq!(HUD_HEIGHT, 294);
         assert_eq!(HUD_MARGIN, 20);
         assert_eq!(HUD_PADDING, 10);
         assert_eq!(HUD_LINE_HEIGHT, 18);
@@ -673,6 +1352,14 @@ mod tests {
         assert!(height_check, "HUD height should accommodate padding");
     }
 
+    #[test]
+    fn test_scale_to_dpi() {
+        assert_eq!(scale_to_dpi(HUD_WIDTH, 96), 300);
+        assert_eq!(scale_to_dpi(HUD_WIDTH, 144), 450);
+        assert_eq!(scale_to_dpi(HUD_LINE_HEIGHT, 120), 22);
+        assert_eq!(scale_to_dpi(HUD_FONT_HEIGHT, 192), 28);
+    }
+
     #[test]
     fn test_color_constants() {
         // Test color constants are valid COLORREF values
@@ -690,6 +1377,27 @@ mod tests {
         assert_eq!((COLOR_DANGER_RED >> 16) & 0xFF, 0x00); // Blue component
     }
 
+    #[test]
+    fn test_select_palette() {
+        let system = || HudPalette {
+            text: 0x00FFFFFF,
+            background: 0x00000000,
+            enabled: 0x0000FFFF,
+            disabled: 0x0000FF00,
+            warning: 0x00FFFFFF,
+            danger: 0x0000FFFF,
+        };
+
+        assert_eq!(select_palette(false, false, system), STANDARD_PALETTE);
+        assert_eq!(select_palette(false, true, system), COLORBLIND_SAFE_PALETTE);
+        assert_eq!(select_palette(true, false, system), system());
+        assert_eq!(select_palette(true, true, system), system());
+        assert_ne!(
+            COLORBLIND_SAFE_PALETTE.enabled,
+            COLORBLIND_SAFE_PALETTE.disabled
+        );
+    }
+
     #[test]
     fn test_calculate_hud_position_top_left() {
         let position = HudPosition::TopLeft;
@@ -760,44 +1468,45 @@ mod tests {
     #[test]
     fn test_hud_state_creation() {
         let state = HudState {
-            enabled: true,
-            x: 100,
-            y: 200,
-            width: 300,
-            height: 150,
-            buffer_zone: 25,
-            push_factor: 50,
+            barrier: Some(test_snapshot(true)),
+            coordinate_origin: CoordinateOrigin::TopLeft,
+            window_origin: (0, 0),
+            coordinate_space: HudCoordinateSpace::Mixed,
+            uptime: Uptime::default(),
+            config_path: "C:\\profiles\\config.ron".to_string(),
             mouse_x: 150,
             mouse_y: 250,
-            mouse_in_barrier: false,
-            mouse_in_buffer: true,
-            last_refresh: std::time::Instant::now(),
+            mouse_zone: Zone::Buffer,
+            notice: Some("Game window not found - barrier suspended".to_string()),
+            overlay_warning: true,
+            screen_warning: Some("Barrier off-screen!"),
+            hook_warning: true,
+            elevation_warning: true,
+            debug: true,
         };
 
-        assert!(state.enabled);
-        assert_eq!(state.x, 100);
-        assert_eq!(state.y, 200);
-        assert_eq!(state.width, 300);
-        assert_eq!(state.height, 150);
-        assert_eq!(state.buffer_zone, 25);
-        assert_eq!(state.push_factor, 50);
+        let barrier = state.barrier.unwrap();
+        assert!(barrier.enabled);
+        assert_eq!(barrier.buffer, BufferZone::uniform(25));
+        assert_eq!(barrier.push_factor, 50);
+        assert_eq!(state.coordinate_origin, CoordinateOrigin::TopLeft);
+        assert_eq!(state.config_path, "C:\\profiles\\config.ron");
         assert_eq!(state.mouse_x, 150);
         assert_eq!(state.mouse_y, 250);
-        assert!(!state.mouse_in_barrier);
-        assert!(state.mouse_in_buffer);
+        assert_eq!(state.mouse_zone, Zone::Buffer);
+        assert!(state.notice.is_some());
+        assert!(state.overlay_warning);
+        assert!(state.screen_warning.is_some());
+        assert!(state.hook_warning);
+        assert!(state.elevation_warning);
+        assert!(state.debug);
     }
 
     #[test]
-    fn test_update_global_hud_state() {
-        // Test the global HUD state update function
-        update_global_hud_state(true, 50, 100, 200, 80, 15, 30);
-
-        // Verify the state was updated by checking via update_mouse_position
-        // This is indirect testing since we can't easily access the global state
+    fn test_update_barrier_snapshot() {
+        // Should not panic, and mouse updates should classify against the new snapshot
+        update_barrier_snapshot(test_snapshot(true));
         update_mouse_position(75, 120);
-
-        // The function should not panic and should handle the update correctly
-        // More detailed testing would require accessing the global state directly
     }
 
     #[test]
@@ -817,54 +1526,237 @@ mod tests {
     }
 
     #[test]
-    fn test_barrier_inside_detection_logic() {
-        // Test the coordinate conversion logic that's used in update_mouse_position
-        // We'll test the mathematical logic separately from the global state
-
-        let barrier_x = 100;
-        let barrier_y = 500; // bottom coordinate
-        let barrier_width = 200;
-        let barrier_height = 100;
-        let buffer_zone = 25;
-
-        // Convert to Windows coordinates (top-left origin)
-        let barrier_bottom = barrier_y;
-        let barrier_top = barrier_y - barrier_height; // 500 - 100 = 400
-        let barrier_left = barrier_x; // 100
-        let barrier_right = barrier_x + barrier_width; // 100 + 200 = 300
-
-        // Test point inside inner barrier
-        let mouse_x = 150;
-        let mouse_y = 450;
-        let in_inner_barrier = mouse_x >= barrier_left
-            && mouse_x <= barrier_right
-            && mouse_y >= barrier_top
-            && mouse_y <= barrier_bottom;
-        assert!(in_inner_barrier);
-
-        // Test point in buffer zone but not inner barrier
-        let mouse_x = 80; // barrier_left - 20, within buffer zone (barrier_left - buffer_zone = 75)
-        let mouse_y = 450;
-        let in_buffer_zone = mouse_x >= (barrier_left - buffer_zone)
-            && mouse_x <= (barrier_right + buffer_zone)
-            && mouse_y >= (barrier_top - buffer_zone)
-            && mouse_y <= (barrier_bottom + buffer_zone);
-        let in_inner_barrier = mouse_x >= barrier_left
-            && mouse_x <= barrier_right
-            && mouse_y >= barrier_top
-            && mouse_y <= barrier_bottom;
-
-        assert!(in_buffer_zone);
-        assert!(!in_inner_barrier);
-
-        // Test point outside both
-        let mouse_x = 50; // Too far left
-        let mouse_y = 450;
-        let in_buffer_zone = mouse_x >= (barrier_left - buffer_zone)
-            && mouse_x <= (barrier_right + buffer_zone)
-            && mouse_y >= (barrier_top - buffer_zone)
-            && mouse_y <= (barrier_bottom + buffer_zone);
-        assert!(!in_buffer_zone);
+    fn test_mouse_zone_matches_hook_on_boundary_pixels() {
+        // Barrier spans x 100..300 and y 400..500, right and bottom exclusive
+        let snapshot = test_snapshot(true);
+
+        let cases = [
+            ((100, 400), Zone::Barrier), // Top-left pixel is inside
+            ((299, 499), Zone::Barrier), // Last pixel inside
+            ((300, 450), Zone::Buffer),  // Right edge belongs to the buffer
+            ((150, 500), Zone::Buffer),  // Bottom edge belongs to the buffer
+            ((99, 450), Zone::Buffer),
+            ((75, 450), Zone::Buffer), // Outermost buffer pixel on the left
+            ((74, 450), Zone::Outside),
+            ((324, 450), Zone::Buffer), // Outermost buffer pixel on the right
+            ((325, 450), Zone::Outside),
+            ((150, 524), Zone::Buffer),
+            ((150, 525), Zone::Outside),
+        ];
+
+        for ((x, y), expected) in cases {
+            let zone = mouse_zone(Some(&snapshot), x, y);
+            assert_eq!(zone, expected, "({}, {})", x, y);
+            // The HUD must never disagree with what the hook enforces
+            assert_eq!(zone, snapshot.zone_at(x, y), "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn test_mouse_zone_agrees_at_every_corner() {
+        // A barrier on a monitor left of the primary one, configured top-left
+        let snapshot = BarrierSnapshot {
+            rect: mouse_barrier::barrier_rect(-500, -50, 100, 40, CoordinateOrigin::TopLeft.into()),
+            buffer: BufferZone::uniform(5),
+            enabled: true,
+            push_factor: 50,
+            enforcement_mode: EnforcementMode::Enforce,
+        };
+        let (left, top, right, bottom) = (-500, -50, -400, -10);
+
+        for x in [
+            left - 6,
+            left - 5,
+            left - 1,
+            left,
+            right - 1,
+            right,
+            right + 4,
+            right + 5,
+        ] {
+            for y in [
+                top - 6,
+                top - 5,
+                top - 1,
+                top,
+                bottom - 1,
+                bottom,
+                bottom + 4,
+                bottom + 5,
+            ] {
+                // Left/top edges are inclusive, right/bottom exclusive, like the hook
+                let in_barrier = x >= left && x < right && y >= top && y < bottom;
+                let in_buffer = x >= left - 5 && x < right + 5 && y >= top - 5 && y < bottom + 5;
+                let expected = if in_barrier {
+                    Zone::Barrier
+                } else if in_buffer {
+                    Zone::Buffer
+                } else {
+                    Zone::Outside
+                };
+                assert_eq!(
+                    mouse_zone(Some(&snapshot), x, y),
+                    expected,
+                    "({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_mouse_zone_is_outside_when_barrier_disabled() {
+        assert_eq!(
+            mouse_zone(Some(&test_snapshot(false)), 150, 450),
+            Zone::Outside
+        );
+        assert_eq!(mouse_zone(None, 150, 450), Zone::Outside);
+    }
+
+    #[test]
+    fn test_anchor_corner_matches_configured_origin() {
+        let snapshot = test_snapshot(true);
+
+        assert_eq!(
+            anchor_corner(&snapshot, CoordinateOrigin::BottomLeft, (0, 0)),
+            (100, 500)
+        );
+        assert_eq!(
+            anchor_corner(&snapshot, CoordinateOrigin::TopLeft, (0, 0)),
+            (100, 400)
+        );
+
+        // Anchored to a game window at (40, 30): shows what config.ron says
+        assert_eq!(
+            anchor_corner(&snapshot, CoordinateOrigin::BottomLeft, (40, 30)),
+            (60, 470)
+        );
+    }
+
+    #[test]
+    fn test_barrier_detection_matches_for_both_origins() {
+        // The same 200x100 barrier spanning y 400..500, written in each convention
+        let bottom_left =
+            mouse_barrier::barrier_rect(100, 500, 200, 100, CoordinateOrigin::BottomLeft.into());
+        let top_left =
+            mouse_barrier::barrier_rect(100, 400, 200, 100, CoordinateOrigin::TopLeft.into());
+
+        for rect in [bottom_left, top_left] {
+            assert_eq!(
+                (rect.left, rect.top, rect.right, rect.bottom),
+                (100, 400, 300, 500)
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_last_push() {
+        let timestamp = Instant::now();
+        let mut push = LastPush {
+            from: Point { x: 120, y: 950 },
+            to: Point { x: 120, y: 900 },
+            edge: Some(PushEdge::Top),
+            speed: 40.0,
+            multiplier: 1.6,
+            timestamp,
+        };
+
+        assert_eq!(
+            format_last_push(&push, timestamp + Duration::from_millis(400)),
+            "Push (120,950)->(120,900) top x1.6 0.4s"
+        );
+
+        // A push that couldn't clear the buffer because of the screen edge
+        push.edge = None;
+        push.multiplier = 1.0;
+        assert_eq!(
+            format_last_push(&push, timestamp),
+            "Push (120,950)->(120,900) clamped x1.0 0.0s"
+        );
+    }
+
+    #[test]
+    fn test_format_cursor_speed() {
+        let speed = CursorSpeed {
+            per_event: 15,
+            per_second: 1200,
+            peak_per_event: 92,
+            peak_per_second: 4800,
+            peak_approach_per_event: 40,
+        };
+        assert_eq!(
+            format_cursor_speed(&speed),
+            "Speed: 15 px/ev 1200 px/s, peak 92/4800"
+        );
+    }
+
+    #[test]
+    fn test_buffer_zone_hint() {
+        let mut speed = CursorSpeed {
+            peak_approach_per_event: 92,
+            ..CursorSpeed::default()
+        };
+        assert_eq!(
+            buffer_zone_hint(&speed, BufferZone::uniform(10)).as_deref(),
+            Some("Fastest approach this session: 92 px/event - buffer_zone 10 may be too small, try 95")
+        );
+
+        // The narrowest edge is the one a fast approach can skip
+        let buffer = BufferZone {
+            top: 120,
+            right: 120,
+            bottom: 30,
+            left: 120,
+        };
+        assert_eq!(
+            buffer_zone_hint(&speed, buffer).as_deref(),
+            Some("Fastest approach this session: 92 px/event - buffer_zone 30 may be too small, try 95")
+        );
+
+        speed.peak_approach_per_event = 10;
+        assert_eq!(buffer_zone_hint(&speed, BufferZone::uniform(10)), None);
+    }
+
+    #[test]
+    fn test_truncate_path_keeps_short_paths() {
+        assert_eq!(truncate_path("config.ron", 32), "config.ron");
+        assert_eq!(truncate_path("", 32), "");
+
+        let exact = "C:\\Games\\AoE\\profiles\\ranked.ron";
+        assert_eq!(exact.chars().count(), 32);
+        assert_eq!(truncate_path(exact, 32), exact);
+    }
+
+    #[test]
+    fn test_truncate_path_keeps_file_name() {
+        let path = "C:\\Users\\player\\Documents\\ageofcrash\\profiles\\ranked-1v1.ron";
+        let truncated = truncate_path(path, 32);
+
+        assert_eq!(truncated.chars().count(), 32);
+        assert_eq!(truncated, "C:\\Users\\playe...\\ranked-1v1.ron");
+    }
+
+    #[test]
+    fn test_truncate_path_keeps_the_end_of_a_long_file_name() {
+        let path = "C:\\ageofcrash\\a-very-long-profile-name-for-ranked.ron";
+        let truncated = truncate_path(path, 20);
+
+        assert_eq!(truncated, "...me-for-ranked.ron");
+    }
+
+    #[test]
+    fn test_truncate_path_counts_characters_not_bytes() {
+        let path = "C:\\Users\\player\\Documents\\ageofcrash\\profil-léger.ron";
+        let truncated = truncate_path(path, 20);
+
+        assert_eq!(truncated, "...\\profil-léger.ron");
+    }
+
+    #[test]
+    fn test_origin_labels() {
+        assert_eq!(origin_label(CoordinateOrigin::BottomLeft), "bottom-left");
+        assert_eq!(origin_label(CoordinateOrigin::TopLeft), "top-left");
     }
 
     // Test HUD position enum completeness
@@ -890,11 +1782,7 @@ mod tests {
 
     #[test]
     fn test_refresh_interval_constant() {
-        use std::time::Duration;
-
         // Test that the refresh interval constant exists and is reasonable
-        const REFRESH_INTERVAL: Duration = Duration::from_millis(33); // ~30 FPS
-
         assert!(REFRESH_INTERVAL.as_millis() > 0);
         assert!(REFRESH_INTERVAL.as_millis() <= 100); // Should be faster than 10 FPS
 
@@ -906,4 +1794,193 @@ mod tests {
             fps
         );
     }
+
+    #[test]
+    fn test_repaint_timer_is_slower_than_mouse_throttle() {
+        // The timer only fills in while the mouse is idle
+        assert!(u128::from(HUD_REPAINT_INTERVAL_MS) > REFRESH_INTERVAL.as_millis());
+    }
+
+    #[test]
+    fn test_claim_refresh_throttles_repaints() {
+        let start = Instant::now();
+        let mut last_refresh = start;
+
+        // Too soon after the last repaint
+        assert!(!claim_refresh(
+            &mut last_refresh,
+            start + Duration::from_millis(10)
+        ));
+        assert_eq!(last_refresh, start);
+
+        // Due, and the claim pushes the next one out
+        let due = start + REFRESH_INTERVAL;
+        assert!(claim_refresh(&mut last_refresh, due));
+        assert_eq!(last_refresh, due);
+        assert!(!claim_refresh(
+            &mut last_refresh,
+            due + Duration::from_millis(1)
+        ));
+    }
+
+    #[test]
+    fn test_buffer_zone_text() {
+        assert_eq!(
+            buffer_zone_text(BufferZone::uniform(20)),
+            "Buffer Zone: 20px"
+        );
+        assert_eq!(
+            buffer_zone_text(BufferZone {
+                top: 0,
+                right: 5,
+                bottom: 30,
+                left: 0,
+            }),
+            "Buffer Zone: T0 R5 B30 L0px"
+        );
+    }
+
+    #[test]
+    fn test_display_origins_put_both_in_the_same_space() {
+        let window = (40, 30);
+        assert_eq!(
+            display_origins(HudCoordinateSpace::Mixed, window),
+            (window, (0, 0))
+        );
+        assert_eq!(
+            display_origins(HudCoordinateSpace::Screen, window),
+            ((0, 0), (0, 0))
+        );
+        assert_eq!(
+            display_origins(HudCoordinateSpace::Config, window),
+            (window, window)
+        );
+    }
+
+    #[test]
+    fn test_barrier_offset() {
+        // (100, 400)-(300, 500), right and bottom exclusive
+        let barrier = Rect {
+            left: 100,
+            top: 400,
+            right: 300,
+            bottom: 500,
+        };
+
+        assert_eq!(barrier_offset(&barrier, 150, 388), "12px above top edge");
+        assert_eq!(barrier_offset(&barrier, 150, 400), "on top edge");
+        assert_eq!(barrier_offset(&barrier, 150, 412), "12px inside top edge");
+        assert_eq!(barrier_offset(&barrier, 299, 450), "on right edge");
+        assert_eq!(
+            barrier_offset(&barrier, 300, 450),
+            "1px right of right edge"
+        );
+        assert_eq!(barrier_offset(&barrier, 150, 520), "21px below bottom edge");
+        // Past a corner, the edge it is furthest past
+        assert_eq!(barrier_offset(&barrier, 95, 350), "50px above top edge");
+    }
+
+    #[test]
+    fn test_status_text() {
+        assert_eq!(status_text(None), "Status: DISABLED");
+        assert_eq!(status_text(Some(&test_snapshot(false))), "Status: DISABLED");
+        assert_eq!(status_text(Some(&test_snapshot(true))), "Status: ENABLED");
+
+        let warn_only = BarrierSnapshot {
+            enforcement_mode: EnforcementMode::WarnOnly,
+            ..test_snapshot(true)
+        };
+        assert_eq!(status_text(Some(&warn_only)), "Status: WARN ONLY");
+        // Disabled is disabled, whatever it would do when enabled
+        let disabled = BarrierSnapshot {
+            enabled: false,
+            ..warn_only
+        };
+        assert_eq!(status_text(Some(&disabled)), "Status: DISABLED");
+    }
+
+    #[test]
+    fn test_screen_warning() {
+        assert_eq!(screen_warning(ScreenPlacement::OnScreen), None);
+        // Hanging past an edge is common (a bar along the screen bottom)
+        assert_eq!(screen_warning(ScreenPlacement::PartlyOffScreen), None);
+        assert_eq!(
+            screen_warning(ScreenPlacement::OffScreen),
+            Some("Barrier off-screen!")
+        );
+        assert!(screen_warning(ScreenPlacement::MovedOnScreen).is_some());
+    }
+
+    #[test]
+    fn test_hud_state_survives_a_panicking_updater() {
+        let state = Arc::new(RwLock::new(HudState {
+            barrier: None,
+            coordinate_origin: CoordinateOrigin::BottomLeft,
+            window_origin: (0, 0),
+            coordinate_space: HudCoordinateSpace::Mixed,
+            uptime: Uptime::default(),
+            config_path: String::new(),
+            mouse_x: 0,
+            mouse_y: 0,
+            mouse_zone: Zone::Outside,
+            notice: None,
+            overlay_warning: false,
+            screen_warning: None,
+            hook_warning: false,
+            elevation_warning: false,
+            debug: false,
+        }));
+
+        let updater = {
+            let state = state.clone();
+            std::thread::spawn(move || {
+                let mut state = state.write().unwrap();
+                state.debug = true;
+                panic!("updater failed");
+            })
+        };
+        assert!(updater.join().is_err());
+        assert!(state.is_poisoned());
+
+        assert!(snapshot(&state, &CursorSlot::new()).debug);
+
+        // Later updates still land
+        update_state(&state, |state| state.debug = false);
+        assert!(!snapshot(&state, &CursorSlot::new()).debug);
+    }
+
+    #[test]
+    fn test_snapshot_takes_the_cursor_from_its_slot() {
+        let state = RwLock::new(HudState {
+            barrier: Some(test_snapshot(true)),
+            coordinate_origin: CoordinateOrigin::BottomLeft,
+            window_origin: (0, 0),
+            coordinate_space: HudCoordinateSpace::Mixed,
+            uptime: Uptime::default(),
+            config_path: String::new(),
+            mouse_x: 0,
+            mouse_y: 0,
+            mouse_zone: Zone::Outside,
+            notice: None,
+            overlay_warning: false,
+            screen_warning: None,
+            hook_warning: false,
+            elevation_warning: false,
+            debug: false,
+        });
+        let cursor = CursorSlot::new();
+
+        // Negative coordinates (monitors left of or above the primary) survive packing
+        cursor.store(-1920, -5);
+        assert_eq!(cursor.load(), (-1920, -5));
+
+        // Storing a position doesn't wait on a paint reading the state
+        let painting = state.read().unwrap();
+        cursor.store(150, 450);
+        drop(painting);
+
+        let state = snapshot(&state, &cursor);
+        assert_eq!((state.mouse_x, state.mouse_y), (150, 450));
+        assert_eq!(state.mouse_zone, Zone::Barrier);
+    }
 }
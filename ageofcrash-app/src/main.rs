@@ -1,71 +1,153 @@
+mod bundle;
+mod cli;
 mod config;
+mod config_cache;
 mod config_watcher;
+mod debug_dump;
+mod debug_overlay;
+mod doctor;
+mod elevation;
+mod gamepad;
 mod hotkey;
 mod hud;
+mod measure;
+mod push_marker;
+mod recovery;
+mod shutdown;
+mod simulate;
+mod toast;
+mod trail;
+mod ui_thread;
+mod uptime;
+mod window_anchor;
 
-use config::{AudioOption, Config};
+use cli::{Args, RELAUNCH_ELEVATED_FLAG};
+use config::{
+    with_barrier_bounds, write_atomically, ActionHotkeyConfig, BarrierAnchor, ChordConfig, Config,
+    EnforcementMode, HotkeyAction, HotkeyConfig,
+};
 use config_watcher::{ConfigEvent, ConfigWatcher};
-use hotkey::HotkeyDetector;
-use hud::{BarrierStateConfig, Hud};
+use debug_dump::DebugDump;
+use debug_overlay::DebugOverlay;
+use doctor::{System, Win32System};
+use elevation::ForegroundWatcher;
+use gamepad::{GamepadBindings, GamepadEvent, GamepadPoller};
+use hotkey::{
+    forward_keys, hotkey_name, reload_detector, ActionHotkeys, ChordDetector, HotkeyDetector,
+    KeyEvent, RegisteredHotkeys, ToggleCooldown,
+};
+use hud::Hud;
 use mouse_barrier::{
-    process_hook_requests, set_mouse_position_callback, KeyboardHook, MouseBarrier,
-    MouseBarrierConfig,
+    barrier_coordinates, hooks_healthy, last_push, process_hook_requests, set_decision_callback,
+    set_decision_tracing, set_event_callback, set_mouse_position_callback,
+    set_mouse_position_callback_rate, BarrierEvent, EditMode, KeyboardHook, LastPush, MouseBarrier,
+    MouseBarrierConfig, Rect, SubscriptionId,
 };
+use push_marker::PushMarker;
+use recovery::{SessionMarker, Win32Desktop};
+use shutdown::{SessionWatcher, Shutdown, ShutdownSignal};
+use std::io::Write;
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::{Arc, Mutex};
-use tracing::{error, info, warn, Level};
+use toast::{toggle_message, Toast};
+use tracing::{debug, error, info, warn, Level};
+use trail::Trail;
+use uptime::Uptime;
+use winapi::shared::windef::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2;
 use winapi::um::winuser::*;
+use window_anchor::{AnchorState, WindowTracker};
+
+const CONFIG_PATH: &str = "config.ron";
+// Exists while a session is running; finding it at startup means the last one crashed
+const SESSION_MARKER_PATH: &str = "ageofcrash.running";
 
 enum AppEvent {
-    HotkeyPressed,
-    ConfigReloaded(Config),
+    Key(KeyEvent),
+    ConfigReloaded(Box<Config>),
     ConfigError(String),
+    AnchorChanged(AnchorState),
+    BarrierEdited(Rect),
+    Gamepad(GamepadEvent),
+    Pushed(LastPush),
+    Shutdown,
 }
 
 struct AppState {
     config: Config,
     barrier_enabled: bool,
     mouse_barrier: Option<MouseBarrier>,
-    keyboard_hook: Option<KeyboardHook>,
+    keyboard_subscription: Option<SubscriptionId>, // Forwards key events to the main loop
+    hotkey: Option<HotkeyDetector>,                // Toggles the barrier
+    action_hotkeys: ActionHotkeys,
+    registered_hotkeys: RegisteredHotkeys, // EmergencyDisable, matched by Windows instead of the hook
+    chords: Option<ChordDetector>,         // Leader hotkey followed by a second key
+    gamepad: Option<GamepadPoller>,        // Polls controller buttons, with a gamepad section
+    gamepad_bypass: bool,                  // Holding the gamepad's own bypass
+    toggle_cooldown: ToggleCooldown,
+    debug_dump: Option<Arc<Mutex<DebugDump>>>, // Set by --debug-dump; records every barrier decision
+    session_marker: Option<SessionMarker>,     // Removed on a clean shutdown
     hud: Option<Hud>,
+    trail: Option<Trail>, // Cursor trail overlay, shown with debug and debug_trail
+    debug_overlay: Option<DebugOverlay>, // Hook's-eye view of the barrier, shown with debug and debug_overlay
+    push_marker: Option<PushMarker>, // Flashes where the cursor was pushed, shown with push_marker enabled
+    toast: Option<Toast>,            // Announces enabling and disabling, with notify_on_toggle
+    window_tracker: Option<WindowTracker>,
+    anchor_origin: (i32, i32), // Screen position the barrier coordinates are relative to
+    anchor_suspended: bool,    // Holding a bypass because the anchor window is unusable
+    paused: bool,              // Holding a bypass for the Pause action
+    uptime: Uptime,            // Time enabled and not suspended, shown on the HUD
+    emergency_disabled: bool,  // EmergencyDisable tore everything down; only a restart undoes it
     startup_time: std::time::Instant,
+    startup_grace: std::time::Duration, // Config reloads before this wait for it to pass
+    deferred_reload: Option<Config>,    // The newest reload that arrived during startup_grace
+    shutdown: Shutdown,                 // Run by every way out of main, including early returns
 }
 
 impl AppState {
     fn new(config: Config) -> Self {
+        // Config events this soon after startup are assumed to come from deployment, not edits
+        let startup_grace = std::time::Duration::from_millis(config.startup_grace_ms.into());
         Self {
             config,
             barrier_enabled: false,
             mouse_barrier: None,
-            keyboard_hook: None,
+            keyboard_subscription: None,
+            hotkey: None,
+            action_hotkeys: ActionHotkeys::default(),
+            registered_hotkeys: RegisteredHotkeys::default(),
+            chords: None,
+            gamepad: None,
+            gamepad_bypass: false,
+            toggle_cooldown: ToggleCooldown::default(),
+            debug_dump: None,
+            session_marker: None,
             hud: None,
+            trail: None,
+            debug_overlay: None,
+            push_marker: None,
+            toast: None,
+            window_tracker: None,
+            anchor_origin: (0, 0),
+            anchor_suspended: false,
+            paused: false,
+            uptime: Uptime::default(),
+            emergency_disabled: false,
             startup_time: std::time::Instant::now(),
+            startup_grace,
+            deferred_reload: None,
+            shutdown: Shutdown::default(),
         }
     }
 
+    /// Tears everything down, unless that already happened
+    fn shut_down(&mut self) {
+        let mut shutdown = std::mem::take(&mut self.shutdown);
+        shutdown.run(self);
+        self.shutdown = shutdown;
+    }
+
     fn initialize_barrier(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let config = MouseBarrierConfig {
-            x: self.config.barrier.x,
-            y: self.config.barrier.y,
-            width: self.config.barrier.width,
-            height: self.config.barrier.height,
-            buffer_zone: self.config.barrier.buffer_zone,
-            push_factor: self.config.barrier.push_factor,
-            overlay_color: (
-                self.config.barrier.overlay_color.r,
-                self.config.barrier.overlay_color.g,
-                self.config.barrier.overlay_color.b,
-            ),
-            overlay_alpha: self.config.barrier.overlay_alpha,
-            on_barrier_hit_sound: match &self.config.barrier.audio_feedback.on_barrier_hit {
-                AudioOption::None => None,
-                AudioOption::File(path) => Some(path.clone()),
-            },
-            on_barrier_entry_sound: match &self.config.barrier.audio_feedback.on_barrier_entry {
-                AudioOption::None => None,
-                AudioOption::File(path) => Some(path.clone()),
-            },
-        };
+        let config = mouse_barrier_config(&self.config, self.anchor_origin);
 
         self.mouse_barrier = Some(MouseBarrier::new(config));
 
@@ -80,77 +162,169 @@ impl AppState {
 
     fn initialize_hud(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.hud = Some(Hud::new(self.config.hud.clone())?);
+        self.sync_overlay_stacking();
         self.update_hud_state();
         Ok(())
     }
 
-    fn update_hud_state(&self) {
-        hud::update_global_hud_state(
-            self.barrier_enabled,
-            self.config.barrier.x,
-            self.config.barrier.y,
-            self.config.barrier.width,
-            self.config.barrier.height,
-            self.config.barrier.buffer_zone,
-            self.config.barrier.push_factor,
-        );
+    /// Creates or destroys the cursor trail window to match the config
+    fn sync_trail(&mut self) {
+        let wanted = self.config.debug && self.config.debug_trail;
+        match (wanted, self.trail.is_some()) {
+            (true, false) => match Trail::new() {
+                Ok(trail) => {
+                    info!("Cursor trail shown");
+                    self.trail = Some(trail);
+                }
+                Err(e) => warn!(error = %e, "Failed to create cursor trail window"),
+            },
+            (false, true) => {
+                self.trail = None;
+                info!("Cursor trail hidden");
+            }
+            _ => {}
+        }
     }
 
-    fn cleanup_hooks(&mut self) {
-        // Disable mouse barrier
-        if let Some(mut barrier) = self.mouse_barrier.take() {
-            let _ = barrier.disable();
+    /// Creates, recreates or destroys the push marker window to match the config
+    fn sync_push_marker(&mut self) {
+        let wanted = self.config.push_marker.clone();
+        let current = self.push_marker.as_ref().map(PushMarker::config);
+        if current == Some(&wanted).filter(|wanted| wanted.enabled) {
+            return;
+        }
+
+        // Size and color are baked into the window, so a change means a new one
+        if self.push_marker.take().is_some() && !wanted.enabled {
+            info!("Push marker hidden");
+        }
+        if !wanted.enabled {
+            return;
+        }
+        match PushMarker::new(wanted) {
+            Ok(marker) => {
+                info!("Push marker enabled");
+                self.push_marker = Some(marker);
+            }
+            Err(e) => warn!(error = %e, "Failed to create push marker window"),
+        }
+    }
+
+    /// Creates or destroys the toast window to match `notify_on_toggle`
+    fn sync_toast(&mut self) {
+        match (self.config.notify_on_toggle, self.toast.is_some()) {
+            (true, false) => match Toast::new() {
+                Ok(toast) => self.toast = Some(toast),
+                Err(e) => warn!(error = %e, "Failed to create toggle notification window"),
+            },
+            (false, true) => self.toast = None,
+            _ => {}
+        }
+    }
+
+    /// Creates or destroys the debug overlay window to match the config
+    fn sync_debug_overlay(&mut self) {
+        let wanted = self.config.debug && self.config.debug_overlay;
+        match (wanted, self.debug_overlay.is_some()) {
+            (true, false) => match DebugOverlay::new() {
+                Ok(overlay) => {
+                    info!("Debug overlay shown");
+                    self.debug_overlay = Some(overlay);
+                }
+                Err(e) => warn!(error = %e, "Failed to create debug overlay window"),
+            },
+            (false, true) => {
+                self.debug_overlay = None;
+                info!("Debug overlay hidden");
+            }
+            _ => {}
         }
+    }
+
+    /// Traces barrier decisions while debug is on or a dump is being written
+    fn sync_decision_tracing(&self) {
+        set_decision_tracing(self.config.debug || self.debug_dump.is_some());
+    }
+
+    /// Stacks the overlay windows beneath the HUD, or puts them back above
+    /// it, as `hud.hud_above_overlay` asks
+    fn sync_overlay_stacking(&self) {
+        let Some(barrier) = &self.mouse_barrier else {
+            return;
+        };
+        let hud_window = self.hud.as_ref().and_then(Hud::window_handle);
+        barrier.stack_overlays_below(hud_window.filter(|_| self.config.hud.hud_above_overlay));
+    }
 
-        // Disable keyboard hook
-        if let Some(mut hook) = self.keyboard_hook.take() {
-            let _ = hook.disable();
+    /// Runs the uptime clock while the barrier is enabled and not suspended
+    /// or paused, and hands it to the HUD
+    fn sync_uptime(&mut self) {
+        let protecting = self.barrier_enabled && !self.anchor_suspended && !self.paused;
+        self.uptime
+            .set_running(protecting, std::time::Instant::now());
+        hud::update_uptime(self.uptime.clone());
+    }
+
+    fn update_hud_state(&self) {
+        hud::update_coordinate_origin(self.config.barrier.coordinate_origin);
+        hud::update_window_origin(self.anchor_origin);
+        hud::update_coordinate_space(self.config.hud.coordinate_space);
+        hud::update_debug(self.config.debug);
+        hud::update_colorblind_safe(self.config.colorblind_safe);
+        // The HUD reports what the hook is enforcing rather than a copy of the config
+        if let Some(barrier) = &self.mouse_barrier {
+            if let Some(snapshot) = barrier.snapshot() {
+                hud::update_barrier_snapshot(snapshot);
+            }
+            hud::update_overlay_warning(barrier.overlay_unavailable());
         }
     }
 
-    fn reload_config(&mut self, new_config: Config) -> Result<(), Box<dyn std::error::Error>> {
-        // Skip reloads within first 2 seconds of startup to avoid deployment triggers
-        if self.startup_time.elapsed() < std::time::Duration::from_secs(2) {
-            info!("Skipping config reload during startup grace period");
+    /// Writes a barrier moved or resized with the edit modifiers back to the
+    /// config file, in the configured coordinates, so it stays put
+    fn save_barrier_edit(&mut self, rect: Rect) -> Result<(), Box<dyn std::error::Error>> {
+        let barrier = &mut self.config.barrier;
+        let (x, y, width, height) = barrier_coordinates(rect, barrier.coordinate_origin.into());
+        let (x, y) = (x - self.anchor_origin.0, y - self.anchor_origin.1);
+
+        let text = std::fs::read_to_string(CONFIG_PATH)?;
+        // The watcher reloads the file as soon as it changes, so it must
+        // never see it half written
+        write_atomically(
+            std::path::Path::new(CONFIG_PATH),
+            with_barrier_bounds(&text, (x, y, width, height))?,
+        )?;
+        (barrier.x, barrier.y, barrier.width, barrier.height) = (x, y, width, height);
+        info!(
+            x,
+            y, width, height, "Saved the edited barrier to the config file"
+        );
+
+        self.update_hud_state();
+        Ok(())
+    }
+
+    fn reload_config(&mut self, mut new_config: Config) -> Result<(), Box<dyn std::error::Error>> {
+        // Hold reloads shortly after startup until the deployment that likely
+        // triggered them is done; only the newest is kept
+        if self.in_startup_grace() {
+            info!("Deferring config reload until the startup grace period ends");
+            self.deferred_reload = Some(new_config);
             return Ok(());
         }
 
         info!("Reloading configuration...");
 
-        // Check if barrier is currently enabled before updating
-        let was_enabled = self.barrier_enabled;
+        self.apply_hotkey(&mut new_config.hotkey);
+        self.apply_action_hotkeys(&new_config.action_hotkeys);
+        self.apply_chords(&new_config.chords);
 
         // Update the barrier configuration using the existing global state
         if let Some(barrier) = &mut self.mouse_barrier {
-            let barrier_config = MouseBarrierConfig {
-                x: new_config.barrier.x,
-                y: new_config.barrier.y,
-                width: new_config.barrier.width,
-                height: new_config.barrier.height,
-                buffer_zone: new_config.barrier.buffer_zone,
-                push_factor: new_config.barrier.push_factor,
-                overlay_color: (
-                    new_config.barrier.overlay_color.r,
-                    new_config.barrier.overlay_color.g,
-                    new_config.barrier.overlay_color.b,
-                ),
-                overlay_alpha: new_config.barrier.overlay_alpha,
-                on_barrier_hit_sound: match &new_config.barrier.audio_feedback.on_barrier_hit {
-                    AudioOption::None => None,
-                    AudioOption::File(path) => Some(path.clone()),
-                },
-                on_barrier_entry_sound: match &new_config.barrier.audio_feedback.on_barrier_entry {
-                    AudioOption::None => None,
-                    AudioOption::File(path) => Some(path.clone()),
-                },
-            };
-            barrier.update_barrier(barrier_config);
-
-            // If barrier was enabled, toggle it off and back on to refresh overlay windows
-            if was_enabled {
-                info!("Refreshing overlay windows with new barrier dimensions");
-                barrier.disable()?;
-                barrier.enable()?;
+            let barrier_config = mouse_barrier_config(&new_config, self.anchor_origin);
+            // Moves and resizes the overlay windows in place if the barrier is enabled
+            if let Err(e) = barrier.update_barrier(barrier_config) {
+                warn!("Failed to apply the reloaded barrier config: {}", e);
             }
         }
 
@@ -164,14 +338,26 @@ impl AppState {
         }
 
         // Update HUD if configuration changed
+        set_mouse_position_callback_rate(new_config.hud.position_update_hz);
         if let Some(hud) = &mut self.hud {
             if let Err(e) = hud.update_config(new_config.hud.clone()) {
                 warn!("Failed to update HUD configuration: {}", e);
             }
         }
 
+        if new_config.hud.reset_uptime_on_reload {
+            self.uptime.reset(std::time::Instant::now());
+            self.sync_uptime();
+        }
+
         // Update config
         self.config = new_config;
+        self.sync_trail();
+        self.sync_debug_overlay();
+        self.sync_push_marker();
+        self.sync_toast();
+        self.sync_decision_tracing();
+        self.sync_overlay_stacking();
 
         // Update HUD state with new barrier configuration
         self.update_hud_state();
@@ -182,34 +368,616 @@ impl AppState {
         Ok(())
     }
 
+    fn in_startup_grace(&self) -> bool {
+        self.startup_time.elapsed() < self.startup_grace
+    }
+
+    /// The reload deferred during the startup grace period, once it is over
+    fn take_deferred_reload(&mut self) -> Option<Config> {
+        if self.in_startup_grace() {
+            return None;
+        }
+        self.deferred_reload.take()
+    }
+
+    /// Switches to a reloaded hotkey if it changed. If the new hotkey can't
+    /// be used, the current one stays active and `hotkey` is set back to it,
+    /// so the config keeps describing the binding that works.
+    fn apply_hotkey(&mut self, hotkey: &mut HotkeyConfig) {
+        match reload_detector(&self.config.hotkey, hotkey) {
+            Ok(None) => {}
+            Ok(Some(detector)) => {
+                self.hotkey = Some(detector);
+                info!(hotkey = hotkey_name(hotkey), "Hotkey updated");
+            }
+            Err(e) => {
+                warn!(
+                    error = %e,
+                    "Failed to apply hotkey {}; {} still toggles the barrier",
+                    hotkey_name(hotkey),
+                    hotkey_name(&self.config.hotkey)
+                );
+                *hotkey = self.config.hotkey.clone();
+            }
+        }
+    }
+
+    /// Switches to reloaded action hotkeys if they changed
+    fn apply_action_hotkeys(&mut self, bindings: &[ActionHotkeyConfig]) {
+        if bindings == self.config.action_hotkeys.as_slice() {
+            return;
+        }
+        self.set_action_hotkeys(bindings);
+        info!(count = bindings.len(), "Action hotkeys updated");
+    }
+
+    /// Replaces the action hotkey detectors with ones for `bindings`, with
+    /// `EmergencyDisable` registered with Windows where it can be. Bindings
+    /// with unknown keys are skipped with a warning.
+    fn set_action_hotkeys(&mut self, bindings: &[ActionHotkeyConfig]) {
+        // Unregister the old ones first, so unchanged keys can be registered again
+        self.registered_hotkeys = RegisteredHotkeys::default();
+        let (registered, hooked, mut warnings) = RegisteredHotkeys::register(bindings);
+        let (hotkeys, hook_warnings) = ActionHotkeys::new(&hooked);
+        warnings.extend(hook_warnings);
+        for warning in warnings {
+            warn!("{}", warning);
+        }
+        self.registered_hotkeys = registered;
+        self.action_hotkeys = hotkeys;
+    }
+
+    /// Switches to reloaded chords if they changed
+    fn apply_chords(&mut self, chords: &Option<ChordConfig>) {
+        if *chords == self.config.chords {
+            return;
+        }
+        self.set_chords(chords);
+        info!("Chords updated");
+    }
+
+    /// Replaces the chord detector with one for `chords`, and has the
+    /// keyboard hook swallow its leader if asked to. Problems are logged; a
+    /// leader with an unknown key disables chords.
+    fn set_chords(&mut self, chords: &Option<ChordConfig>) {
+        let (detector, warnings) = match chords {
+            Some(config) => ChordDetector::new(config),
+            None => (None, Vec::new()),
+        };
+        for warning in warnings {
+            warn!("{}", warning);
+        }
+        let suppressed = match (&detector, chords) {
+            (Some(detector), Some(config)) if config.suppress_leader => {
+                vec![detector.leader_combo()]
+            }
+            _ => Vec::new(),
+        };
+        KeyboardHook::set_suppressed(suppressed);
+        self.chords = detector;
+    }
+
+    /// Subscribes to the keyboard hook, which only forwards key events to
+    /// the main loop; the detectors run there, on [`AppEvent::Key`], so a
+    /// slow reload can't hold up the hook.
+    fn subscribe_keys(&mut self, events: &Sender<AppEvent>) -> Result<(), String> {
+        let subscription = KeyboardHook::subscribe(forward_keys(events.clone(), AppEvent::Key))?;
+        self.keyboard_subscription = Some(subscription);
+        Ok(())
+    }
+
+    /// The hotkey actions `key` completes, the toggle hotkey's first
+    fn hotkey_actions(&mut self, key: KeyEvent) -> impl Iterator<Item = HotkeyAction> {
+        let toggled = self
+            .hotkey
+            .as_mut()
+            .is_some_and(|hotkey| hotkey.handle_key(key.vk_code, key.is_down));
+        let action = self.action_hotkeys.handle_key(key.vk_code, key.is_down);
+        // Timed by when the key was pressed, not by when the loop got to it
+        let chord = self
+            .chords
+            .as_mut()
+            .and_then(|chords| chords.handle_key(key.vk_code, key.is_down, key.at));
+        toggled
+            .then_some(HotkeyAction::ToggleBarrier)
+            .into_iter()
+            .chain(action)
+            .chain(chord)
+    }
+
+    fn run_hotkey_action(&mut self, action: HotkeyAction) {
+        // Nothing holds up the safety valve, not even the toggle cooldown
+        if action == HotkeyAction::EmergencyDisable {
+            self.emergency_disable();
+            return;
+        }
+
+        if action == HotkeyAction::ToggleBarrier {
+            let cooldown =
+                std::time::Duration::from_millis(u64::from(self.config.hotkey.toggle_cooldown_ms));
+            if !self
+                .toggle_cooldown
+                .try_toggle(std::time::Instant::now(), cooldown)
+            {
+                info!("Ignored hotkey press within the toggle cooldown");
+                return;
+            }
+
+            match self.toggle_barrier() {
+                Ok(enabled) => {
+                    info!(enabled = enabled, "Mouse barrier toggled");
+                }
+                Err(e) => error!(error = %e, "Failed to toggle barrier"),
+            }
+            return;
+        }
+
+        if action == HotkeyAction::ToggleHud {
+            self.toggle_hud();
+            return;
+        }
+
+        if action == HotkeyAction::Pause {
+            match self.toggle_pause() {
+                Ok(paused) => info!(paused = paused, "Mouse barrier pause toggled"),
+                Err(e) => error!(error = %e, "Failed to pause barrier"),
+            }
+            return;
+        }
+
+        if action == HotkeyAction::ToggleEnforcement {
+            match self.toggle_enforcement() {
+                Ok(mode) => info!(mode = ?mode, "Enforcement mode toggled"),
+                Err(e) => error!(error = %e, "Failed to toggle enforcement mode"),
+            }
+            return;
+        }
+
+        let enable = action == HotkeyAction::EnableBarrier;
+        match self.set_barrier_enabled(enable) {
+            Ok(true) => {
+                info!(enabled = enable, "Mouse barrier set by hotkey");
+                if !enable {
+                    self.log_buffer_zone_hint();
+                }
+            }
+            Ok(false) => info!(enabled = enable, "Mouse barrier already set"),
+            Err(e) => error!(error = %e, "Failed to set barrier"),
+        }
+    }
+
+    /// Tears down the barrier and the keyboard hook at once, whatever state
+    /// they're in, for when the cursor is stuck or a hook misbehaves. Only a
+    /// restart brings them back; config reloads no longer touch them.
+    fn emergency_disable(&mut self) {
+        if self.emergency_disabled {
+            info!("Emergency disable pressed again; everything is already off");
+            return;
+        }
+        self.emergency_disabled = true;
+
+        // Leave the HUD showing a disabled barrier rather than its last state
+        if let Some(barrier) = &mut self.mouse_barrier {
+            if let Err(e) = barrier.disable() {
+                warn!("Failed to disable the barrier: {}", e);
+            }
+            if let Some(snapshot) = barrier.snapshot() {
+                hud::update_barrier_snapshot(snapshot);
+            }
+        }
+        shutdown::Subsystems::cleanup_hooks(self);
+        self.barrier_enabled = false;
+        self.anchor_suspended = false;
+        self.paused = false;
+        self.sync_uptime();
+
+        error!(
+            "EMERGENCY DISABLE: the barrier and keyboard hook are off until ageofcrash is restarted"
+        );
+        hud::update_notice(Some("EMERGENCY DISABLED - restart to re-enable"));
+        if let Some(toast) = &self.toast {
+            toast.show("Emergency disable: everything is off");
+        }
+    }
+
+    /// Starts, restarts or stops window tracking to match `barrier.anchor`.
+    fn sync_window_tracker(
+        &mut self,
+        events: &Sender<AppEvent>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.emergency_disabled {
+            return Ok(());
+        }
+        let anchor = self.config.barrier.anchor.clone();
+        if self.window_tracker.as_ref().map(|t| t.anchor()) == Some(&anchor) {
+            return Ok(());
+        }
+
+        // Dropping the old tracker stops its thread
+        self.window_tracker = None;
+
+        if anchor == BarrierAnchor::Screen {
+            self.set_anchor(Some((0, 0)), None);
+            return Ok(());
+        }
+
+        // Hold the barrier until the tracker has found the window
+        self.set_anchor(None, Some("Looking for game window..."));
+
+        let mut tracker = WindowTracker::new(anchor);
+        let events = events.clone();
+        tracker.start(move |state| {
+            let _ = events.send(AppEvent::AnchorChanged(state));
+        })?;
+        self.window_tracker = Some(tracker);
+
+        Ok(())
+    }
+
+    /// Starts, restarts or stops polling the gamepad to match `gamepad`.
+    /// With no section, or no button the poller recognizes, nothing polls.
+    fn sync_gamepad(
+        &mut self,
+        events: &Sender<AppEvent>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.emergency_disabled {
+            return Ok(());
+        }
+        let config = self.config.gamepad.clone();
+        if self.gamepad.as_ref().map(|poller| poller.config()) == config.as_ref() {
+            return Ok(());
+        }
+
+        // Dropping the old poller stops its thread; a button it saw held
+        // can't be seen coming up any more
+        self.gamepad = None;
+        self.set_gamepad_bypass(false);
+
+        let Some(config) = config else {
+            return Ok(());
+        };
+        let (bindings, warnings) = GamepadBindings::new(&config);
+        for warning in warnings {
+            warn!("{}", warning);
+        }
+        if bindings.is_empty() {
+            info!("No gamepad buttons bound, not polling the gamepad");
+            return Ok(());
+        }
+
+        let events = events.clone();
+        let poller = GamepadPoller::start(config, bindings, move |event| {
+            let _ = events.send(AppEvent::Gamepad(event));
+        })?;
+        self.gamepad = Some(poller);
+        info!("Polling the gamepad");
+
+        Ok(())
+    }
+
+    fn apply_gamepad_event(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Action(action) => self.run_hotkey_action(action),
+            GamepadEvent::Bypass(held) => self.set_gamepad_bypass(held),
+        }
+    }
+
+    /// Lifts the barrier while the gamepad's bypass button is held
+    fn set_gamepad_bypass(&mut self, held: bool) {
+        if held == self.gamepad_bypass {
+            return;
+        }
+        let Some(barrier) = &self.mouse_barrier else {
+            return;
+        };
+        barrier.hold_gamepad_bypass(held);
+        self.gamepad_bypass = held;
+        debug!(held = held, "Gamepad bypass");
+    }
+
+    fn apply_anchor_state(&mut self, state: AnchorState) {
+        // One queued before the tracker was stopped would clear the notice
+        if self.emergency_disabled {
+            return;
+        }
+        self.set_anchor(state.origin(), state.notice());
+    }
+
+    /// Moves the barrier to `origin`, or suspends it with `notice` shown on the
+    /// HUD when there is nothing to anchor to.
+    fn set_anchor(&mut self, origin: Option<(i32, i32)>, notice: Option<&str>) {
+        match origin {
+            Some(origin) => {
+                if self.anchor_origin != origin {
+                    self.anchor_origin = origin;
+                    if let Some(barrier) = &mut self.mouse_barrier {
+                        let config = mouse_barrier_config(&self.config, origin);
+                        if let Err(e) = barrier.update_barrier(config) {
+                            warn!("Failed to move the barrier with the game window: {}", e);
+                        }
+                    }
+                }
+                if self.anchor_suspended {
+                    self.anchor_suspended = false;
+                    if let Some(barrier) = &self.mouse_barrier {
+                        barrier.end_bypass();
+                    }
+                    info!("Anchor window available, barrier resumed");
+                }
+            }
+            None => {
+                if !self.anchor_suspended {
+                    if let Some(barrier) = &self.mouse_barrier {
+                        barrier.begin_bypass();
+                        self.anchor_suspended = true;
+                    }
+                    info!("Anchor window unavailable, barrier suspended");
+                }
+            }
+        }
+
+        hud::update_notice(notice);
+        self.sync_uptime();
+        self.update_hud_state();
+    }
+
     fn toggle_barrier(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let enabled = self.mouse_barrier.as_ref().is_some_and(|b| b.is_enabled());
+        self.set_barrier_enabled(!enabled)?;
+
+        // Switching off is the natural break in a tuning session
+        if enabled {
+            self.log_buffer_zone_hint();
+        }
+        Ok(self.barrier_enabled)
+    }
+
+    /// Logs a wider buffer zone if the fastest approach so far could have
+    /// jumped the current one
+    fn log_buffer_zone_hint(&self) {
+        if let Some(barrier) = &self.mouse_barrier {
+            let hint = barrier.snapshot().and_then(|snapshot| {
+                hud::buffer_zone_hint(&barrier.stats().cursor_speed, snapshot.buffer)
+            });
+            if let Some(hint) = hint {
+                info!("{}", hint);
+            }
+        }
+    }
+
+    /// Enables or disables the barrier, doing nothing if it already is.
+    /// Returns whether that changed anything.
+    fn set_barrier_enabled(&mut self, enabled: bool) -> Result<bool, Box<dyn std::error::Error>> {
         if let Some(barrier) = &mut self.mouse_barrier {
-            self.barrier_enabled = barrier.toggle()?;
+            if barrier.is_enabled() == enabled {
+                return Ok(false);
+            }
+            if enabled {
+                barrier.enable()?;
+            } else {
+                barrier.disable()?;
+            }
+            self.barrier_enabled = enabled;
+            self.sync_uptime();
 
             // Update HUD with new barrier state
             self.update_hud_state();
 
             // Force HUD refresh
-            if let Some(hud) = &mut self.hud {
-                let barrier_state_config = BarrierStateConfig {
-                    enabled: self.barrier_enabled,
-                    x: self.config.barrier.x,
-                    y: self.config.barrier.y,
-                    width: self.config.barrier.width,
-                    height: self.config.barrier.height,
-                    buffer_zone: self.config.barrier.buffer_zone,
-                    push_factor: self.config.barrier.push_factor,
-                };
-                if let Err(e) = hud.update_barrier_state(barrier_state_config) {
-                    warn!("Failed to update HUD barrier state: {}", e);
+            if let Some(hud) = &self.hud {
+                if let Err(e) = hud.refresh() {
+                    warn!("Failed to refresh HUD: {}", e);
                 }
             }
+            if let Some(toast) = &self.toast {
+                toast.show(toggle_message(enabled));
+            }
 
-            Ok(self.barrier_enabled)
+            Ok(true)
         } else {
             Err("Mouse barrier not initialized".into())
         }
     }
+
+    /// Shows the HUD if it's hidden and hides it if it's shown, until the
+    /// config is next reloaded
+    fn toggle_hud(&mut self) {
+        self.config.hud.enabled = !self.config.hud.enabled;
+        if let Some(hud) = &mut self.hud {
+            if let Err(e) = hud.update_config(self.config.hud.clone()) {
+                // Still showing what it was, so don't claim otherwise
+                self.config.hud.enabled = !self.config.hud.enabled;
+                warn!("Failed to toggle the HUD: {}", e);
+                return;
+            }
+        }
+        self.sync_overlay_stacking();
+        info!(shown = self.config.hud.enabled, "HUD toggled");
+    }
+
+    /// Suspends the barrier, or lifts the suspension, leaving it enabled.
+    /// Returns whether it is now paused.
+    fn toggle_pause(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let Some(barrier) = &self.mouse_barrier else {
+            return Err("Mouse barrier not initialized".into());
+        };
+        if self.paused {
+            barrier.end_bypass();
+        } else {
+            barrier.begin_bypass();
+        }
+        self.paused = !self.paused;
+        self.sync_uptime();
+        if let Some(toast) = &self.toast {
+            toast.show(if self.paused {
+                "Barrier paused"
+            } else {
+                "Barrier resumed"
+            });
+        }
+        Ok(self.paused)
+    }
+
+    /// Switches between enforcing the barrier and only warning, leaving it
+    /// enabled or disabled as it is. Lasts until the config is next reloaded.
+    fn toggle_enforcement(&mut self) -> Result<EnforcementMode, Box<dyn std::error::Error>> {
+        let Some(barrier) = &mut self.mouse_barrier else {
+            return Err("Mouse barrier not initialized".into());
+        };
+        let mode = match self.config.barrier.enforcement_mode {
+            EnforcementMode::Enforce => EnforcementMode::WarnOnly,
+            EnforcementMode::WarnOnly => EnforcementMode::Enforce,
+        };
+        let mut config = mouse_barrier_config(&self.config, self.anchor_origin);
+        config.enforcement_mode = mode.into();
+        barrier.update_barrier(config)?;
+        self.config.barrier.enforcement_mode = mode;
+
+        self.update_hud_state();
+        if let Some(hud) = &self.hud {
+            if let Err(e) = hud.refresh() {
+                warn!("Failed to refresh HUD: {}", e);
+            }
+        }
+        Ok(mode)
+    }
+}
+
+impl Drop for AppState {
+    fn drop(&mut self) {
+        self.shut_down();
+    }
+}
+
+impl shutdown::Subsystems for AppState {
+    fn cleanup_hooks(&mut self) {
+        // Last chance to report on this session's speeds
+        self.log_buffer_zone_hint();
+
+        // Stop following the game window so it can't re-arm the barrier
+        self.window_tracker = None;
+        self.gamepad = None;
+
+        // Disable mouse barrier
+        if let Some(mut barrier) = self.mouse_barrier.take() {
+            let stats = barrier.stats();
+            info!(
+                cursor_move_failures = stats.cursor_move_failures,
+                would_have_pushed = stats.would_have_pushed,
+                keyboard_hook_reinstalls = stats.keyboard_hook_reinstalls,
+                mouse_hook_reinstalls = stats.mouse_hook_reinstalls,
+                peak_px_per_event = stats.cursor_speed.peak_per_event,
+                peak_px_per_second = stats.cursor_speed.peak_per_second,
+                "Mouse barrier stats"
+            );
+            let _ = barrier.disable();
+        }
+
+        // The last subscription leaving uninstalls the keyboard hook
+        if let Some(subscription) = self.keyboard_subscription.take() {
+            let _ = KeyboardHook::unsubscribe(subscription);
+        }
+    }
+
+    fn close_windows(&mut self) {
+        // Dropping each one closes its window and joins its UI thread
+        self.trail = None;
+        self.debug_overlay = None;
+        self.push_marker = None;
+        self.toast = None;
+        self.hud = None;
+    }
+
+    fn flush(&mut self) {
+        if let Some(dump) = &self.debug_dump {
+            if let Ok(mut dump) = dump.lock() {
+                if let Err(e) = dump.flush() {
+                    warn!(error = %e, "Failed to flush debug dump");
+                }
+            }
+        }
+        let _ = std::io::stdout().flush();
+    }
+
+    fn release_session(&mut self) {
+        if let Some(marker) = self.session_marker.take() {
+            if let Err(e) = marker.release() {
+                warn!(error = %e, "Failed to remove session marker");
+            }
+        }
+    }
+}
+
+/// Builds the library configuration, offsetting the barrier by `origin`
+fn mouse_barrier_config(config: &Config, origin: (i32, i32)) -> MouseBarrierConfig {
+    let barrier = &config.barrier;
+    let (overlay_color, proximity_far_color) = barrier.overlay_colors(config.colorblind_safe);
+    MouseBarrierConfig {
+        bounds: barrier.bounds(origin),
+        buffer_zone: (&barrier.buffer_zone).into(),
+        buffer_blocks: barrier.buffer_blocks,
+        push_factor: barrier.push_factor,
+        dynamic_push: barrier.dynamic_push,
+        offscreen_push: barrier.offscreen_push.into(),
+        screen_margin: barrier.screen_margin,
+        approach_zone: barrier.approach_zone.as_ref().map(Into::into),
+        overlay_enabled: barrier.overlay_enabled,
+        overlay_color: (overlay_color.r, overlay_color.g, overlay_color.b),
+        proximity_coloring: barrier.proximity_coloring,
+        proximity_far_color: (
+            proximity_far_color.r,
+            proximity_far_color.g,
+            proximity_far_color.b,
+        ),
+        proximity_range: barrier.proximity_range,
+        buffer_alpha: barrier.buffer_alpha(),
+        overlay_coverage: barrier.overlay_coverage.into(),
+        overlay_failure: barrier.overlay_failure.into(),
+        interior_alpha: barrier.interior_alpha,
+        flash_alpha: barrier.flash_alpha(),
+        overlay_animation: barrier.overlay_animation.into(),
+        overlay_tick_ms: barrier.overlay_tick_ms,
+        on_barrier_hit_sound: barrier.audio_feedback.on_barrier_hit.sound(),
+        on_barrier_entry_sound: barrier.audio_feedback.on_barrier_entry.sound(),
+        entry_cue: barrier.audio_feedback.entry_cue.into(),
+        interrupt_previous_sound: barrier.audio_feedback.interrupt_previous,
+        coordinate_origin: barrier.coordinate_origin.into(),
+        snap_grid: barrier.snap_grid(),
+        consume_blocked_events: barrier.consume_blocked_events,
+        resync_after_block: barrier.resync_after_block,
+        enforcement_mode: barrier.enforcement_mode.into(),
+        bypass_hold_ms: barrier.bypass_hold_ms,
+        bypass_modifier: barrier.bypass_modifier.map(Into::into),
+        clamp_to_screen: barrier.clamp_to_screen,
+        edit_mode: barrier.edit_mode.as_ref().map(|edit| EditMode {
+            ctrl: edit.ctrl,
+            alt: edit.alt,
+            shift: edit.shift,
+            resize_step: edit.resize_step,
+        }),
+        high_contrast: (&barrier.high_contrast).into(),
+    }
+}
+
+/// Drops every queued config reload but the newest, which supersedes them
+fn coalesce_config_reloads(events: &mut Vec<AppEvent>) {
+    let is_reload = |event: &AppEvent| matches!(event, AppEvent::ConfigReloaded(_));
+    let Some(newest) = events.iter().rposition(is_reload) else {
+        return;
+    };
+    let before = events.len();
+    let mut index = 0;
+    events.retain(|event| {
+        let keep = index >= newest || !is_reload(event);
+        index += 1;
+        keep
+    });
+    if events.len() < before {
+        debug!(
+            skipped = before - events.len(),
+            "Coalesced queued config reloads"
+        );
+    }
 }
 
 fn log_config(config: &Config) {
@@ -218,31 +986,65 @@ fn log_config(config: &Config) {
         barrier.height = config.barrier.height,
         barrier.x = config.barrier.x,
         barrier.y = config.barrier.y,
-        barrier.buffer_zone = config.barrier.buffer_zone,
+        barrier.corners = ?config.barrier.corners,
+        barrier.buffer_zone = ?config.barrier.buffer_zone,
         "Barrier area configured"
     );
     info!(
         push_factor = config.barrier.push_factor,
         "Push factor configured"
     );
-    info!(
-        hotkey = format!(
-            "{}{}{}{}",
-            if config.hotkey.ctrl { "Ctrl+" } else { "" },
-            if config.hotkey.alt { "Alt+" } else { "" },
-            if config.hotkey.shift { "Shift+" } else { "" },
-            config.hotkey.key
-        ),
-        "Hotkey configured"
-    );
+    info!(hotkey = hotkey_name(&config.hotkey), "Hotkey configured");
     info!(debug = config.debug, "Debug mode");
 }
 
+/// Absolute form of `path` for display, falling back to it as given
+fn resolve_config_path(path: &str) -> String {
+    std::path::absolute(path)
+        .map(|resolved| resolved.display().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("Age of Crash Mouse Barrier v0.1.0");
+    println!("Age of Crash Mouse Barrier v{}", env!("CARGO_PKG_VERSION"));
+    // Before any window exists. The HUD and overlays follow each monitor's
+    // scaling themselves through WM_DPICHANGED, which only per-monitor aware
+    // processes get; if this fails, the DPI awareness warning below says so
+    unsafe {
+        SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2);
+    }
+    let raw_args: Vec<String> = std::env::args().skip(1).collect();
+    let args = Args::parse(raw_args.clone())?;
+
+    if args.relaunch_elevated && elevation::process_elevated(None) != Some(true) {
+        // The elevated copy must not try to relaunch again
+        let forwarded: Vec<String> = raw_args
+            .into_iter()
+            .filter(|arg| arg != RELAUNCH_ELEVATED_FLAG)
+            .collect();
+        elevation::relaunch_elevated(&forwarded)?;
+        println!("Restarted as administrator");
+        return Ok(());
+    }
+
+    // Before loading, since the import replaces the config
+    if let Some(path) = &args.import {
+        return bundle::run_import(path, std::path::Path::new(CONFIG_PATH));
+    }
+
+    // Before loading, so a config that doesn't load is one of the things it
+    // reports, and before recovery, which would close a running copy's windows
+    if args.doctor {
+        return doctor::run(Config::load_or_create(CONFIG_PATH));
+    }
+
     println!("Loading configuration...");
 
-    let config = Config::load_or_create("config.ron")?;
+    let config = if args.config_cache {
+        config_cache::load_or_create_cached(CONFIG_PATH)?
+    } else {
+        Config::load_or_create(CONFIG_PATH)?
+    };
 
     // Initialize tracing based on debug flag
     let level = if config.debug {
@@ -260,21 +1062,110 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     log_config(&config);
 
+    if args.measure {
+        return measure::run(&config, CONFIG_PATH);
+    }
+
+    if let Some(path) = &args.export {
+        return bundle::run_export(&config, path);
+    }
+
+    // Replaying a script needs none of the hooks or windows below
+    if let Some(path) = &args.simulate {
+        return simulate::run(path, &mouse_barrier_config(&config, (0, 0)), &config.hotkey);
+    }
+
+    if let Some(warning) = doctor::dpi_awareness_warning(Win32System.dpi_awareness()) {
+        warn!("{}", warning);
+    }
+
+    // Clean up after a previous session that crashed or was killed
+    let (recovery, session_marker) =
+        recovery::recover(std::path::Path::new(SESSION_MARKER_PATH), &mut Win32Desktop);
+
     // Create app state
     let mut state = AppState::new(config.clone());
+    state.session_marker = session_marker;
+    if recovery.previous_still_running {
+        warn!(
+            previous_pid = ?recovery.previous_pid,
+            "Another instance is still running; its windows and session marker were left alone"
+        );
+    }
+    if recovery.unclean_shutdown {
+        warn!(
+            previous_pid = ?recovery.previous_pid,
+            "Previous session did not shut down cleanly"
+        );
+        // Whoever restarted us is likely fixing config.ron; apply their edits at once
+        state.startup_grace = std::time::Duration::ZERO;
+    }
+    // Create event channel for hotkey and config events
+    let (tx, rx): (Sender<AppEvent>, Receiver<AppEvent>) = mpsc::channel();
+
+    // Registered first so the HUD hears whether the new barrier is on-screen
+    let event_tx = tx.clone();
+    set_event_callback(move |event| match event {
+        BarrierEvent::PlacementChanged(placement) => hud::update_screen_placement(placement),
+        BarrierEvent::Edited(rect) => {
+            let _ = event_tx.send(AppEvent::BarrierEdited(rect));
+        }
+        BarrierEvent::Pushed => {
+            if let Some(push) = last_push() {
+                let _ = event_tx.send(AppEvent::Pushed(push));
+            }
+        }
+        BarrierEvent::BypassStarted | BarrierEvent::BypassEnded => {}
+    });
     state.initialize_barrier()?;
     state.initialize_hud()?;
+    state.sync_trail();
+    state.sync_debug_overlay();
+    state.sync_push_marker();
+    state.sync_toast();
+    hud::update_config_path(&resolve_config_path(CONFIG_PATH));
+
+    // Barrier decisions are logged in debug mode; mirror them to the dump
+    if let Some(path) = &args.debug_dump {
+        let dump = DebugDump::create(path)
+            .map_err(|e| format!("Failed to create debug dump {}: {}", path.display(), e))?;
+        info!(path = %path.display(), "Writing barrier decisions to debug dump");
+        state.debug_dump = Some(Arc::new(Mutex::new(dump)));
+    }
+    let decision_dump = state.debug_dump.clone();
+    set_decision_callback(move |decision| {
+        if let Some(dump) = &decision_dump {
+            if let Ok(mut dump) = dump.lock() {
+                if let Err(e) = dump.record(&decision) {
+                    warn!(error = %e, "Failed to write debug dump; stopped recording decisions");
+                }
+            }
+        }
+    });
+    state.sync_decision_tracing();
 
     // Set up mouse position callback for HUD updates
-    set_mouse_position_callback(|x, y| {
+    set_mouse_position_callback_rate(config.hud.position_update_hz);
+    set_mouse_position_callback(|x, y, _source| {
         hud::update_mouse_position(x, y);
+        trail::record_position(x, y);
     });
 
-    // Create event channel for hotkey and config events
-    let (tx, rx): (Sender<AppEvent>, Receiver<AppEvent>) = mpsc::channel();
+    // Follow the game window if the barrier is anchored to one
+    state.sync_window_tracker(&tx)?;
+
+    // Poll the gamepad if any of its buttons are bound
+    if let Err(e) = state.sync_gamepad(&tx) {
+        warn!(error = %e, "Failed to start polling the gamepad");
+    }
 
-    // Set up config watcher
-    let (mut config_watcher, config_rx) = ConfigWatcher::new("config.ron")?;
+    // Set up config watcher; with the cache the config wasn't parsed, and
+    // parsing it here would give the startup time back
+    let (mut config_watcher, config_rx) = if args.config_cache {
+        ConfigWatcher::watching(CONFIG_PATH)
+    } else {
+        ConfigWatcher::new(CONFIG_PATH)?
+    };
     config_watcher.start()?;
 
     // Keep config_watcher alive
@@ -304,61 +1195,121 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     });
 
     // Set up keyboard hook
-    let hotkey_detector = Arc::new(Mutex::new(
-        HotkeyDetector::new(config.hotkey.clone()).ok_or("Failed to create hotkey detector")?,
-    ));
-
-    let hotkey_tx = tx.clone();
-    let hotkey_detector_clone = hotkey_detector.clone();
-    let mut keyboard_hook = KeyboardHook::new(move |vk_code, is_down| {
-        if let Ok(mut detector) = hotkey_detector_clone.lock() {
-            if detector.handle_key(vk_code, is_down) {
-                let _ = hotkey_tx.send(AppEvent::HotkeyPressed);
-            }
-        }
-    });
-
-    keyboard_hook.enable()?;
-    state.keyboard_hook = Some(keyboard_hook);
+    let detector =
+        HotkeyDetector::new(config.hotkey.clone()).ok_or("Failed to create hotkey detector")?;
+    state.hotkey = Some(detector);
+    state.set_action_hotkeys(&config.action_hotkeys);
+    state.set_chords(&config.chords);
+    state.subscribe_keys(&tx)?;
 
     info!("Keyboard hook enabled. Press the hotkey to toggle the mouse barrier.");
     info!("Config file monitoring enabled. Changes will be applied automatically.");
     info!("Press Ctrl+C to exit.");
 
+    // Logoff and system shutdown end the session through the same shutdown path
+    let shutdown_finished = Arc::new(ShutdownSignal::default());
+    state
+        .shutdown
+        .signal_when_finished(shutdown_finished.clone());
+    let session_tx = tx.clone();
+    let _session_watcher = match SessionWatcher::new(
+        move || {
+            let _ = session_tx.send(AppEvent::Shutdown);
+        },
+        shutdown_finished.clone(),
+    ) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            warn!(error = %e, "Failed to watch for the session ending");
+            None
+        }
+    };
+
+    // Our hooks go deaf while an elevated game has focus; say so instead of
+    // failing silently
+    let _foreground_watcher = match ForegroundWatcher::start(|outranked| {
+        if outranked {
+            warn!(
+                "The foreground window runs as administrator; the barrier and hotkey \
+                 can't see input until it loses focus. Run with --relaunch-elevated to fix this"
+            );
+        } else {
+            info!("The foreground window is no longer elevated; the barrier is active again");
+        }
+        hud::update_elevation_warning(outranked);
+    }) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            warn!(error = %e, "Failed to watch the foreground window's elevation");
+            None
+        }
+    };
+
     // Windows message loop with integrated event processing
+    let mut hooks_were_healthy = true;
     unsafe {
-        loop {
+        'event_loop: loop {
             // Process hook requests from middle mouse monitoring thread
             process_hook_requests();
 
-            // Process all pending application events first
-            while let Ok(event) = rx.try_recv() {
+            // Warn on the HUD while a dropped hook couldn't be reinstalled
+            let hooks_are_healthy = hooks_healthy();
+            if hooks_are_healthy != hooks_were_healthy {
+                hud::update_hook_warning(!hooks_are_healthy);
+                hooks_were_healthy = hooks_are_healthy;
+            }
+
+            // Process all pending application events first. An editor that
+            // saves in several writes queues a reload for each, and applying
+            // every one would rebuild the overlay for nothing
+            let mut events: Vec<AppEvent> = rx.try_iter().collect();
+            // Ahead of the queued events, so a newer reload still supersedes it
+            if let Some(config) = state.take_deferred_reload() {
+                events.insert(0, AppEvent::ConfigReloaded(Box::new(config)));
+            }
+            coalesce_config_reloads(&mut events);
+            for event in events {
                 match event {
-                    AppEvent::HotkeyPressed => match state.toggle_barrier() {
-                        Ok(enabled) => {
-                            info!(enabled = enabled, "Mouse barrier toggled");
+                    AppEvent::Key(key) => {
+                        let actions: Vec<_> = state.hotkey_actions(key).collect();
+                        for action in actions {
+                            state.run_hotkey_action(action);
                         }
-                        Err(e) => error!(error = %e, "Failed to toggle barrier"),
-                    },
+                    }
                     AppEvent::ConfigReloaded(new_config) => {
-                        // Update hotkey detector if hotkey changed
-                        if new_config.hotkey != state.config.hotkey {
-                            if let Ok(mut detector) = hotkey_detector.lock() {
-                                if detector.update_config(new_config.hotkey.clone()).is_some() {
-                                    info!("Hotkey updated successfully");
-                                } else {
-                                    warn!("Failed to update hotkey - invalid key specified");
-                                }
-                            }
-                        }
-
-                        if let Err(e) = state.reload_config(new_config) {
+                        if let Err(e) = state.reload_config(*new_config) {
                             error!(error = %e, "Failed to reload configuration");
                         }
+                        if let Err(e) = state.sync_window_tracker(&tx) {
+                            error!(error = %e, "Failed to start window tracking");
+                        }
+                        if let Err(e) = state.sync_gamepad(&tx) {
+                            error!(error = %e, "Failed to start polling the gamepad");
+                        }
+                    }
+                    AppEvent::AnchorChanged(anchor_state) => {
+                        state.apply_anchor_state(anchor_state);
+                    }
+                    AppEvent::Gamepad(gamepad_event) => {
+                        state.apply_gamepad_event(gamepad_event);
+                    }
+                    AppEvent::Pushed(push) => {
+                        if let Some(marker) = &state.push_marker {
+                            marker.show(push);
+                        }
+                    }
+                    AppEvent::BarrierEdited(rect) => {
+                        if let Err(e) = state.save_barrier_edit(rect) {
+                            error!(error = %e, "Failed to save the edited barrier");
+                        }
                     }
                     AppEvent::ConfigError(err) => {
                         warn!(error = %err, "Config file error");
                     }
+                    AppEvent::Shutdown => {
+                        info!("Shutdown requested");
+                        break 'event_loop;
+                    }
                 }
             }
 
@@ -367,7 +1318,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let result = PeekMessageW(&mut msg, std::ptr::null_mut(), 0, 0, PM_REMOVE);
             if result > 0 {
                 if msg.message == WM_QUIT {
-                    break;
+                    break 'event_loop;
+                }
+                // Registered hotkeys arrive here even without the keyboard hook
+                if msg.message == WM_HOTKEY {
+                    if let Some(action) = state.registered_hotkeys.action(msg.wParam) {
+                        state.run_hotkey_action(action);
+                    }
                 }
                 TranslateMessage(&msg);
                 DispatchMessageW(&msg);
@@ -378,8 +1335,136 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    // Cleanup hooks
-    state.cleanup_hooks();
+    // Also lets a session that is ending go ahead
+    state.shut_down();
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::GamepadConfig;
+
+    /// App state with a barrier that was never enabled, so no hooks go in
+    fn app_state() -> AppState {
+        let mut state = AppState::new(Config::default());
+        state.startup_grace = std::time::Duration::ZERO;
+        state.initialize_barrier().unwrap();
+        state
+    }
+
+    #[test]
+    fn test_emergency_disable_skips_the_toggle_cooldown() {
+        let mut state = app_state();
+        state.config.hotkey.toggle_cooldown_ms = 60_000;
+
+        // As if the barrier had just been toggled; another toggle is ignored
+        let cooldown = std::time::Duration::from_secs(60);
+        assert!(state
+            .toggle_cooldown
+            .try_toggle(std::time::Instant::now(), cooldown));
+        state.run_hotkey_action(HotkeyAction::ToggleBarrier);
+        assert!(!state.barrier_enabled);
+
+        state.run_hotkey_action(HotkeyAction::EmergencyDisable);
+
+        assert!(state.emergency_disabled);
+        assert!(state.mouse_barrier.is_none());
+        assert!(!state.barrier_enabled);
+    }
+
+    #[test]
+    fn test_hotkey_reloaded_during_startup_grace_is_applied_afterwards() {
+        let mut state = app_state();
+        state.startup_grace = std::time::Duration::from_secs(60);
+
+        let mut config = Config::default();
+        config.hotkey.key = "F11".to_string();
+        state.reload_config(config.clone()).unwrap();
+        assert_ne!(state.config.hotkey, config.hotkey);
+        assert!(state.take_deferred_reload().is_none());
+
+        state.startup_grace = std::time::Duration::ZERO;
+        let deferred = state.take_deferred_reload().unwrap();
+        state.reload_config(deferred).unwrap();
+        assert_eq!(state.config.hotkey, config.hotkey);
+        assert!(state.take_deferred_reload().is_none());
+    }
+
+    #[test]
+    fn test_keys_sent_during_a_reload_are_acted_on_afterwards() {
+        let mut state = app_state();
+        let (tx, rx) = mpsc::channel();
+        let forward = forward_keys(tx, AppEvent::Key);
+
+        // The hook keeps forwarding keys while the main loop reloads; the old
+        // hotkey is pressed, then the new one
+        let mut config = Config::default();
+        config.hotkey.key = "F11".to_string();
+        let typing = std::thread::spawn(move || {
+            for (vk_code, is_down) in [
+                (VK_CONTROL, true),
+                (VK_F12, true),
+                (VK_F12, false),
+                (VK_F11, true),
+                (VK_F11, false),
+                (VK_CONTROL, false),
+            ] {
+                forward(vk_code as u32, is_down);
+            }
+        });
+        state.reload_config(config).unwrap();
+        typing.join().unwrap();
+
+        // Once the loop gets to them, they're matched against the new config
+        let mut actions = Vec::new();
+        for event in rx.try_iter() {
+            if let AppEvent::Key(key) = event {
+                actions.extend(state.hotkey_actions(key));
+            }
+        }
+        assert_eq!(actions, [HotkeyAction::ToggleBarrier]);
+    }
+
+    #[test]
+    fn test_gamepad_bypass_outlives_other_bypasses() {
+        let mut state = app_state();
+        state.apply_gamepad_event(GamepadEvent::Bypass(true));
+
+        // Pausing and resuming takes and releases a counted bypass
+        state.toggle_pause().unwrap();
+        state.toggle_pause().unwrap();
+        let barrier = state.mouse_barrier.as_ref().unwrap();
+        assert!(barrier.is_bypassed());
+        // An unbalanced release can't end it either
+        barrier.end_bypass();
+        assert!(barrier.is_bypassed());
+
+        state.apply_gamepad_event(GamepadEvent::Bypass(false));
+        assert!(!state.mouse_barrier.as_ref().unwrap().is_bypassed());
+    }
+
+    #[test]
+    fn test_reloads_after_emergency_disable_leave_everything_off() {
+        let mut state = app_state();
+        state.run_hotkey_action(HotkeyAction::EmergencyDisable);
+
+        let mut config = Config::default();
+        config.barrier.x += 10;
+        config.gamepad = Some(GamepadConfig {
+            bypass: Some("RightShoulder".to_string()),
+            ..GamepadConfig::default()
+        });
+        state.reload_config(config).unwrap();
+        let (tx, _rx) = mpsc::channel();
+        state.sync_window_tracker(&tx).unwrap();
+        state.sync_gamepad(&tx).unwrap();
+        state.run_hotkey_action(HotkeyAction::EnableBarrier);
+
+        assert!(state.mouse_barrier.is_none());
+        assert!(state.keyboard_subscription.is_none());
+        assert!(state.gamepad.is_none());
+        assert!(!state.barrier_enabled);
+    }
+}
@@ -0,0 +1,326 @@
+use crate::config::{
+    with_barrier_bounds, write_atomically, BarrierAnchor, Config, CoordinateOrigin,
+};
+use crate::hotkey::{forward_keys, hotkey_name, HotkeyDetector, KeyEvent};
+use crate::window_anchor::query_anchor_state;
+use mouse_barrier::{barrier_coordinates, KeyboardHook, Point, Rect};
+use std::fmt::Write as _;
+use std::sync::mpsc;
+use std::time::Duration;
+use winapi::shared::windef::POINT;
+use winapi::um::winuser::*;
+
+/// Pairs up clicks: the second click of each pair proposes the barrier
+/// spanning both, and the click after that starts a new pair
+#[derive(Debug, Default)]
+pub struct Measurement {
+    first: Option<Point>,
+}
+
+impl Measurement {
+    pub fn click(&mut self, at: Point) -> Option<Rect> {
+        match self.first.take() {
+            None => {
+                self.first = Some(at);
+                None
+            }
+            Some(first) => Some(span(first, at)),
+        }
+    }
+}
+
+/// The smallest barrier covering both clicked pixels, whichever corners
+/// they were
+pub fn span(a: Point, b: Point) -> Rect {
+    Rect {
+        left: a.x.min(b.x),
+        top: a.y.min(b.y),
+        right: a.x.max(b.x) + 1,
+        bottom: a.y.max(b.y) + 1,
+    }
+}
+
+/// `rect` as config.ron's `(x, y, width, height)` for `origin`, relative to
+/// the anchor at `anchor_origin`
+pub fn config_bounds(
+    rect: Rect,
+    origin: CoordinateOrigin,
+    anchor_origin: (i32, i32),
+) -> (i32, i32, i32, i32) {
+    let (x, y, width, height) = barrier_coordinates(rect, origin.into());
+    (x - anchor_origin.0, y - anchor_origin.1, width, height)
+}
+
+/// The proposed barrier in both coordinate origins, marking the one
+/// config.ron uses
+pub fn proposal(rect: Rect, configured: CoordinateOrigin, anchor_origin: (i32, i32)) -> String {
+    let mut text = String::from("Proposed barrier:\n");
+    for (name, origin) in [
+        ("TopLeft", CoordinateOrigin::TopLeft),
+        ("BottomLeft", CoordinateOrigin::BottomLeft),
+    ] {
+        let (x, y, width, height) = config_bounds(rect, origin, anchor_origin);
+        let _ = write!(
+            text,
+            "  {:<11} x: {}, y: {}, width: {}, height: {}",
+            format!("{}:", name),
+            x,
+            y,
+            width,
+            height
+        );
+        if origin == configured {
+            text.push_str("  <- coordinate_origin in config.ron");
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// What the keyboard asks of the measuring loop
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Save,
+    Quit,
+}
+
+/// The command `key` gives, if any. Every key goes through `hotkey`, so it
+/// keeps track of the modifiers held.
+///
+/// The hook sees keys typed into any window, so Y only saves while a
+/// proposal is waiting for it; otherwise it is left to whatever it was
+/// typed into.
+fn key_command(
+    hotkey: Option<&mut HotkeyDetector>,
+    key: KeyEvent,
+    proposing: bool,
+) -> Option<Command> {
+    let toggled = hotkey.is_some_and(|hotkey| hotkey.handle_key(key.vk_code, key.is_down));
+    if toggled || (key.is_down && key.vk_code == VK_ESCAPE as u32) {
+        Some(Command::Quit)
+    } else if proposing && key.is_down && key.vk_code == 'Y' as u32 {
+        Some(Command::Save)
+    } else {
+        None
+    }
+}
+
+/// Runs `measure`: prints the cursor position at every left click, proposes a
+/// barrier for each pair of clicks, and writes the last proposal to
+/// `config_path` when Y is pressed. Escape or the toggle hotkey quits without
+/// writing anything.
+pub fn run(config: &Config, config_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // Proposals are relative to the game window when the barrier follows one,
+    // so they can be pasted into config.ron as they are
+    let anchor_origin = match &config.barrier.anchor {
+        BarrierAnchor::Screen => (0, 0),
+        anchor => query_anchor_state(anchor)
+            .origin()
+            .ok_or("The game window must be on screen to measure a barrier anchored to it")?,
+    };
+    let origin = config.barrier.coordinate_origin;
+
+    // The hook only forwards keys; the detector runs on this loop
+    let mut detector = HotkeyDetector::new(config.hotkey.clone());
+    let (keys, received) = mpsc::channel();
+    let subscription = KeyboardHook::subscribe(forward_keys(keys, |key| key))?;
+
+    println!(
+        "Measuring: left-click two opposite corners of the barrier. Once a barrier is \
+         proposed, press Y to save it to {}, or Escape or {} to quit without changes.",
+        config_path,
+        hotkey_name(&config.hotkey)
+    );
+
+    let mut measurement = Measurement::default();
+    let mut proposed = None;
+    let mut button_was_down = false;
+    let result = loop {
+        // Keys after a command wait for the next pass
+        let command = received
+            .try_iter()
+            .find_map(|key| key_command(detector.as_mut(), key, proposed.is_some()));
+        match command {
+            Some(Command::Quit) => {
+                println!("Left {} unchanged", config_path);
+                break Ok(());
+            }
+            Some(Command::Save) => {
+                if let Some(rect) = proposed {
+                    break save(rect, origin, anchor_origin, config_path);
+                }
+            }
+            None => {}
+        }
+
+        // Sampled rather than hooked: the barrier's mouse hook only runs while
+        // the barrier is up, and this only needs to see presses, not block them
+        let button_down = unsafe { GetAsyncKeyState(VK_LBUTTON) } & 0x8000u16 as i16 != 0;
+        if button_down && !button_was_down {
+            let mut pos = POINT { x: 0, y: 0 };
+            // Physical pixels, the same ones the barrier hook sees
+            if unsafe { GetPhysicalCursorPos(&mut pos) } != 0 {
+                let at = Point { x: pos.x, y: pos.y };
+                println!(
+                    "Click at x: {}, y: {}",
+                    at.x - anchor_origin.0,
+                    at.y - anchor_origin.1
+                );
+                if let Some(rect) = measurement.click(at) {
+                    print!("{}", proposal(rect, origin, anchor_origin));
+                    proposed = Some(rect);
+                }
+            }
+        }
+        button_was_down = button_down;
+
+        unsafe {
+            let mut msg = std::mem::zeroed();
+            if PeekMessageW(&mut msg, std::ptr::null_mut(), 0, 0, PM_REMOVE) > 0 {
+                if msg.message == WM_QUIT {
+                    break Ok(());
+                }
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            } else {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        }
+    };
+
+    KeyboardHook::unsubscribe(subscription)?;
+    result
+}
+
+fn save(
+    rect: Rect,
+    origin: CoordinateOrigin,
+    anchor_origin: (i32, i32),
+    config_path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bounds = config_bounds(rect, origin, anchor_origin);
+    let text = std::fs::read_to_string(config_path)?;
+    write_atomically(
+        std::path::Path::new(config_path),
+        with_barrier_bounds(&text, bounds)?,
+    )?;
+    println!("Saved the barrier to {}", config_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn test_measurement_proposes_every_second_click() {
+        let mut measurement = Measurement::default();
+        assert_eq!(measurement.click(point(100, 400)), None);
+        assert_eq!(
+            measurement.click(point(299, 499)),
+            Some(Rect {
+                left: 100,
+                top: 400,
+                right: 300,
+                bottom: 500,
+            })
+        );
+
+        // The next click starts over instead of pairing with the last one
+        assert_eq!(measurement.click(point(0, 0)), None);
+        assert!(measurement.click(point(9, 9)).is_some());
+    }
+
+    #[test]
+    fn test_span_takes_corners_in_any_order() {
+        let rect = span(point(100, 400), point(299, 499));
+        assert_eq!(span(point(299, 499), point(100, 400)), rect);
+        assert_eq!(span(point(100, 499), point(299, 400)), rect);
+        // Two clicks on the same pixel still cover it
+        assert_eq!(
+            span(point(5, 5), point(5, 5)),
+            Rect {
+                left: 5,
+                top: 5,
+                right: 6,
+                bottom: 6,
+            }
+        );
+    }
+
+    #[test]
+    fn test_config_bounds_follow_origin_and_anchor() {
+        let rect = span(point(100, 400), point(299, 499));
+        assert_eq!(
+            config_bounds(rect, CoordinateOrigin::TopLeft, (0, 0)),
+            (100, 400, 200, 100)
+        );
+        assert_eq!(
+            config_bounds(rect, CoordinateOrigin::BottomLeft, (0, 0)),
+            (100, 500, 200, 100)
+        );
+        // Relative to a game window whose corner is at (50, 20)
+        assert_eq!(
+            config_bounds(rect, CoordinateOrigin::BottomLeft, (50, 20)),
+            (50, 480, 200, 100)
+        );
+    }
+
+    #[test]
+    fn test_proposal_shows_both_origins() {
+        let rect = span(point(100, 400), point(299, 499));
+        assert_eq!(
+            proposal(rect, CoordinateOrigin::BottomLeft, (0, 0)),
+            "Proposed barrier:\n\
+             \x20 TopLeft:    x: 100, y: 400, width: 200, height: 100\n\
+             \x20 BottomLeft: x: 100, y: 500, width: 200, height: 100  \
+             <- coordinate_origin in config.ron\n"
+        );
+    }
+
+    #[test]
+    fn test_key_commands() {
+        let key = |vk_code: u32, is_down| KeyEvent {
+            vk_code,
+            is_down,
+            at: std::time::Instant::now(),
+        };
+        let mut hotkey = HotkeyDetector::new(crate::config::HotkeyConfig {
+            ctrl: true,
+            alt: false,
+            shift: false,
+            key: "B".to_string(),
+            toggle_cooldown_ms: 0,
+        });
+
+        assert_eq!(
+            key_command(None, key('Y' as u32, true), true),
+            Some(Command::Save)
+        );
+        assert_eq!(key_command(None, key('Y' as u32, false), true), None);
+        // Nothing proposed yet, so Y was meant for some other window
+        assert_eq!(key_command(None, key('Y' as u32, true), false), None);
+        assert_eq!(
+            key_command(None, key(VK_ESCAPE as u32, true), false),
+            Some(Command::Quit)
+        );
+
+        // The detector follows the modifiers through every key
+        assert_eq!(
+            key_command(hotkey.as_mut(), key('B' as u32, true), false),
+            None
+        );
+        assert_eq!(
+            key_command(hotkey.as_mut(), key(VK_CONTROL as u32, true), false),
+            None
+        );
+        assert_eq!(
+            key_command(hotkey.as_mut(), key('B' as u32, true), false),
+            Some(Command::Quit)
+        );
+    }
+}
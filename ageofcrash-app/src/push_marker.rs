@@ -0,0 +1,454 @@
+use crate::config::{OverlayColor, PushMarkerConfig};
+use crate::ui_thread::{register_class, wide, UiThread};
+use mouse_barrier::{LastPush, Point};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use winapi::shared::minwindef::*;
+use winapi::shared::windef::*;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::wingdi::*;
+use winapi::um::winuser::*;
+
+/// Window class of the marker, for finding ones left behind by a previous run
+pub const PUSH_MARKER_CLASS_NAME: &str = "AgeOfCrashPushMarker";
+
+// More pushes than this within STROBE_WINDOW and the marker stays hidden
+const MAX_FLASHES_PER_WINDOW: usize = 3;
+const STROBE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Posted to the marker window when the barrier has pushed the cursor
+const WM_MARKER_PUSH: UINT = WM_APP + 2;
+
+const MARKER_TIMER_ID: usize = 1;
+const MARKER_TIMER_INTERVAL_MS: u32 = 16; // ~60 Hz while fading, so the fade looks smooth
+const RING_THICKNESS: f64 = 2.0;
+
+/// Which push the marker shows and whether pushes are coming too fast to
+/// show any
+#[derive(Debug, Default)]
+struct MarkerFlash {
+    /// Times of the pushes seen within the last `STROBE_WINDOW`, oldest first
+    recent: VecDeque<Instant>,
+    /// Destination and time of the push being shown
+    shown: Option<(Point, Instant)>,
+}
+
+impl MarkerFlash {
+    /// Takes in a push from [`mouse_barrier::last_push`], ignoring the one
+    /// already seen
+    fn observe(&mut self, push: &LastPush) {
+        if self.recent.back() == Some(&push.timestamp) {
+            return;
+        }
+        while self
+            .recent
+            .front()
+            .is_some_and(|&at| push.timestamp.saturating_duration_since(at) >= STROBE_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(push.timestamp);
+
+        // A burst of pushes would strobe; hide the marker until it calms down
+        self.shown = if self.recent.len() > MAX_FLASHES_PER_WINDOW {
+            None
+        } else {
+            Some((push.to, push.timestamp))
+        };
+    }
+
+    /// Where to show the marker at `now` and how opaque, fading out over
+    /// `duration`; `None` once it has faded
+    fn visible_at(&self, now: Instant, duration: Duration) -> Option<(Point, u8)> {
+        let (at, since) = self.shown?;
+        fade_alpha(now.saturating_duration_since(since), duration).map(|alpha| (at, alpha))
+    }
+}
+
+/// Opacity `elapsed` into a fade lasting `duration`, or `None` once it's over
+fn fade_alpha(elapsed: Duration, duration: Duration) -> Option<u8> {
+    if elapsed >= duration {
+        return None;
+    }
+    let remaining = 1.0 - elapsed.as_secs_f64() / duration.as_secs_f64();
+    Some((255.0 * remaining).round() as u8)
+}
+
+/// The marker as `size` by `size` premultiplied BGRA pixels, top row first:
+/// a ring with a crosshair through it, open in the middle so the cursor
+/// itself stays visible
+fn marker_pixels(size: i32, color: &OverlayColor) -> Vec<u32> {
+    let opaque = 0xFF00_0000 | (color.r as u32) << 16 | (color.g as u32) << 8 | color.b as u32;
+    let center = (size - 1) as f64 / 2.0;
+    let radius = size as f64 / 2.0;
+    let gap = radius / 3.0;
+
+    let mut pixels = Vec::with_capacity((size * size) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let (dx, dy) = (x as f64 - center, y as f64 - center);
+            let distance = dx.hypot(dy);
+            let ring = distance <= radius && distance > radius - RING_THICKNESS;
+            let cross = (dx.abs() < 1.0 || dy.abs() < 1.0) && distance > gap && distance <= radius;
+            pixels.push(if ring || cross { opaque } else { 0 });
+        }
+    }
+    pixels
+}
+
+/// A small click-through marker flashed where the barrier pushed the cursor,
+/// fading out over `duration_ms`
+///
+/// The window and its bitmap are created once on their own UI thread, which
+/// moves and fades the window when [`PushMarker::show`] hands it a push, so
+/// nothing here runs on the hook thread. The thread only wakes for pushes
+/// and the fades that follow them.
+pub struct PushMarker {
+    config: PushMarkerConfig,
+    latest: Arc<Mutex<Option<LastPush>>>,
+    ui: UiThread,
+}
+
+impl PushMarker {
+    pub fn new(config: PushMarkerConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let latest = Arc::new(Mutex::new(None));
+        let window_config = config.clone();
+        let window_latest = latest.clone();
+        let ui = UiThread::spawn("push-marker-ui", move || {
+            create_marker_window(&window_config, window_latest)
+        })?;
+        Ok(Self { config, latest, ui })
+    }
+
+    /// The settings the marker was created with
+    pub fn config(&self) -> &PushMarkerConfig {
+        &self.config
+    }
+
+    /// Flashes the marker at `push`, unless pushes are coming too fast
+    pub fn show(&self, push: LastPush) {
+        if let Ok(mut latest) = self.latest.lock() {
+            *latest = Some(push);
+        }
+        self.ui.post(WM_MARKER_PUSH);
+    }
+}
+
+/// What the marker's UI thread draws from, owned by that thread
+struct MarkerWindow {
+    size: i32,
+    duration: Duration,
+    flash: MarkerFlash,
+    /// Handed over by [`PushMarker::show`]
+    latest: Arc<Mutex<Option<LastPush>>>,
+    visible: bool,
+    // Memory DC holding the marker bitmap, and what it held before
+    mem_dc: HDC,
+    bitmap: HBITMAP,
+    old_bitmap: HGDIOBJ,
+}
+
+impl Drop for MarkerWindow {
+    fn drop(&mut self) {
+        unsafe {
+            SelectObject(self.mem_dc, self.old_bitmap);
+            DeleteObject(self.bitmap as *mut _);
+            DeleteDC(self.mem_dc);
+        }
+    }
+}
+
+thread_local! {
+    // Set once the marker window is created on its UI thread
+    static MARKER: RefCell<Option<MarkerWindow>> = const { RefCell::new(None) };
+}
+
+/// A 32-bit top-down DIB holding the marker, selected into a memory DC
+unsafe fn create_marker_bitmap(
+    config: &PushMarkerConfig,
+) -> Result<(HDC, HBITMAP, HGDIOBJ), Box<dyn std::error::Error>> {
+    let size = config.size;
+    let mut info: BITMAPINFO = std::mem::zeroed();
+    info.bmiHeader.biSize = std::mem::size_of::<BITMAPINFOHEADER>() as u32;
+    info.bmiHeader.biWidth = size;
+    info.bmiHeader.biHeight = -size; // Negative for top-down rows
+    info.bmiHeader.biPlanes = 1;
+    info.bmiHeader.biBitCount = 32;
+    info.bmiHeader.biCompression = BI_RGB;
+
+    let mem_dc = CreateCompatibleDC(ptr::null_mut());
+    if mem_dc.is_null() {
+        return Err("Failed to create push marker DC".into());
+    }
+    let mut bits: *mut winapi::ctypes::c_void = ptr::null_mut();
+    let bitmap = CreateDIBSection(mem_dc, &info, DIB_RGB_COLORS, &mut bits, ptr::null_mut(), 0);
+    if bitmap.is_null() || bits.is_null() {
+        DeleteDC(mem_dc);
+        return Err("Failed to create push marker bitmap".into());
+    }
+
+    let pixels = marker_pixels(size, &config.color);
+    ptr::copy_nonoverlapping(pixels.as_ptr(), bits as *mut u32, pixels.len());
+    let old_bitmap = SelectObject(mem_dc, bitmap as *mut _);
+    Ok((mem_dc, bitmap, old_bitmap))
+}
+
+fn create_marker_window(
+    config: &PushMarkerConfig,
+    latest: Arc<Mutex<Option<LastPush>>>,
+) -> Result<HWND, Box<dyn std::error::Error>> {
+    let class_name = register_class(PUSH_MARKER_CLASS_NAME, marker_window_proc);
+    let window_title = wide("Mouse Barrier Push Marker");
+
+    // Created hidden; the first push shows it
+    let hwnd = unsafe {
+        CreateWindowExW(
+            WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_NOACTIVATE | WS_EX_TOOLWINDOW,
+            class_name.as_ptr(),
+            window_title.as_ptr(),
+            WS_POPUP,
+            0,
+            0,
+            config.size,
+            config.size,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            GetModuleHandleW(ptr::null()),
+            ptr::null_mut(),
+        )
+    };
+
+    if hwnd.is_null() {
+        return Err("Failed to create push marker window".into());
+    }
+
+    let (mem_dc, bitmap, old_bitmap) = match unsafe { create_marker_bitmap(config) } {
+        Ok(surface) => surface,
+        Err(e) => {
+            unsafe {
+                DestroyWindow(hwnd);
+            }
+            return Err(e);
+        }
+    };
+    MARKER.with(|marker| {
+        *marker.borrow_mut() = Some(MarkerWindow {
+            size: config.size,
+            duration: Duration::from_millis(config.duration_ms.into()),
+            flash: MarkerFlash::default(),
+            latest,
+            visible: false,
+            mem_dc,
+            bitmap,
+            old_bitmap,
+        });
+    });
+
+    Ok(hwnd)
+}
+
+/// Takes in the push [`PushMarker::show`] handed over and starts fading the
+/// marker from it
+unsafe fn observe_push(hwnd: HWND, marker: &mut MarkerWindow) {
+    let push = marker
+        .latest
+        .lock()
+        .ok()
+        .and_then(|mut latest| latest.take());
+    if let Some(push) = push {
+        marker.flash.observe(&push);
+    }
+    if update_marker(hwnd, marker) {
+        SetTimer(hwnd, MARKER_TIMER_ID, MARKER_TIMER_INTERVAL_MS, None);
+    }
+}
+
+/// Moves and fades the window to match the latest push, hiding it once the
+/// flash is over. Returns whether it is still showing.
+unsafe fn update_marker(hwnd: HWND, marker: &mut MarkerWindow) -> bool {
+    let Some((at, alpha)) = marker.flash.visible_at(Instant::now(), marker.duration) else {
+        if marker.visible {
+            ShowWindow(hwnd, SW_HIDE);
+            marker.visible = false;
+        }
+        return false;
+    };
+
+    // Pushes land in cursor coordinates, the same ones windows are placed in
+    let mut destination = POINT {
+        x: at.x - marker.size / 2,
+        y: at.y - marker.size / 2,
+    };
+    let mut size = SIZE {
+        cx: marker.size,
+        cy: marker.size,
+    };
+    let mut source = POINT { x: 0, y: 0 };
+    let mut blend = BLENDFUNCTION {
+        BlendOp: AC_SRC_OVER,
+        BlendFlags: 0,
+        SourceConstantAlpha: alpha,
+        AlphaFormat: AC_SRC_ALPHA,
+    };
+    UpdateLayeredWindow(
+        hwnd,
+        ptr::null_mut(),
+        &mut destination,
+        &mut size,
+        marker.mem_dc,
+        &mut source,
+        0,
+        &mut blend,
+        ULW_ALPHA,
+    );
+    if !marker.visible {
+        ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+        marker.visible = true;
+    }
+    true
+}
+
+unsafe extern "system" fn marker_window_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_MARKER_PUSH => {
+            MARKER.with(|marker| {
+                if let Some(marker) = marker.borrow_mut().as_mut() {
+                    observe_push(hwnd, marker);
+                }
+            });
+            0
+        }
+        WM_TIMER if wparam == MARKER_TIMER_ID => {
+            MARKER.with(|marker| {
+                if let Some(marker) = marker.borrow_mut().as_mut() {
+                    // Nothing to draw until the next push
+                    if !update_marker(hwnd, marker) {
+                        KillTimer(hwnd, MARKER_TIMER_ID);
+                    }
+                }
+            });
+            0
+        }
+        WM_MOUSEACTIVATE => MA_NOACTIVATE as LRESULT,
+        WM_DESTROY => {
+            KillTimer(hwnd, MARKER_TIMER_ID);
+            // Frees the bitmap and its DC
+            MARKER.with(|marker| marker.borrow_mut().take());
+            // Ends the marker thread's message loop
+            PostQuitMessage(0);
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DURATION: Duration = Duration::from_millis(200);
+
+    fn push_to(x: i32, timestamp: Instant) -> LastPush {
+        LastPush {
+            from: Point { x: 95, y: 150 },
+            to: Point { x, y: 150 },
+            edge: None,
+            speed: 5.0,
+            multiplier: 1.0,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_marker_fades_out_after_a_push() {
+        let start = Instant::now();
+        let mut flash = MarkerFlash::default();
+        assert_eq!(flash.visible_at(start, DURATION), None);
+
+        flash.observe(&push_to(70, start));
+        assert_eq!(
+            flash.visible_at(start, DURATION),
+            Some((Point { x: 70, y: 150 }, 255))
+        );
+        assert_eq!(
+            flash.visible_at(start + DURATION / 2, DURATION),
+            Some((Point { x: 70, y: 150 }, 128))
+        );
+        assert_eq!(flash.visible_at(start + DURATION, DURATION), None);
+
+        // Polling the same push again doesn't restart the flash
+        flash.observe(&push_to(70, start));
+        assert_eq!(flash.visible_at(start + DURATION, DURATION), None);
+    }
+
+    #[test]
+    fn test_marker_moves_to_the_latest_push() {
+        let start = Instant::now();
+        let mut flash = MarkerFlash::default();
+        flash.observe(&push_to(70, start));
+        flash.observe(&push_to(40, start + Duration::from_millis(100)));
+
+        assert_eq!(
+            flash.visible_at(start + Duration::from_millis(100), DURATION),
+            Some((Point { x: 40, y: 150 }, 255))
+        );
+    }
+
+    #[test]
+    fn test_rapid_pushes_suppress_the_marker() {
+        let start = Instant::now();
+        let mut flash = MarkerFlash::default();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        for ms in [0, 100, 200] {
+            flash.observe(&push_to(70, at(ms)));
+            assert!(flash.visible_at(at(ms), DURATION).is_some());
+        }
+        // A fourth push within the second would strobe
+        flash.observe(&push_to(70, at(300)));
+        assert_eq!(flash.visible_at(at(300), DURATION), None);
+
+        // Once the burst is over a second back, pushes show again
+        flash.observe(&push_to(70, at(1250)));
+        assert!(flash.visible_at(at(1250), DURATION).is_some());
+    }
+
+    #[test]
+    fn test_marker_pixels_draw_ring_and_crosshair_around_a_clear_center() {
+        let color = OverlayColor {
+            r: 255,
+            g: 128,
+            b: 0,
+        };
+        let size = 24;
+        let pixels = marker_pixels(size, &color);
+        let pixel = |x: i32, y: i32| pixels[(y * size + x) as usize];
+
+        assert_eq!(pixels.len(), (size * size) as usize);
+        // Premultiplied BGRA, fully opaque
+        assert_eq!(pixel(12, 0), 0xFFFF_8000);
+        assert_eq!(pixel(0, 12), 0xFFFF_8000);
+        // Crosshair arm between the center and the ring
+        assert_eq!(pixel(12, 4), 0xFFFF_8000);
+        // The cursor's own spot and the corners stay see-through
+        assert_eq!(pixel(12, 12), 0);
+        assert_eq!(pixel(0, 0), 0);
+        assert_eq!(pixel(size - 1, size - 1), 0);
+    }
+
+    #[test]
+    fn test_fade_alpha() {
+        assert_eq!(fade_alpha(Duration::ZERO, DURATION), Some(255));
+        assert_eq!(fade_alpha(Duration::from_millis(150), DURATION), Some(64));
+        assert_eq!(fade_alpha(DURATION, DURATION), None);
+        assert_eq!(fade_alpha(Duration::ZERO, Duration::ZERO), None);
+    }
+}
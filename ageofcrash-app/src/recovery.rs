@@ -0,0 +1,456 @@
+use crate::debug_overlay::DEBUG_OVERLAY_CLASS_NAME;
+use crate::hud::HUD_CLASS_NAME;
+use crate::push_marker::PUSH_MARKER_CLASS_NAME;
+use crate::toast::TOAST_CLASS_NAME;
+use crate::trail::TRAIL_CLASS_NAME;
+use mouse_barrier::OVERLAY_CLASS_NAME;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::os::windows::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use tracing::{info, warn};
+use winapi::shared::minwindef::{DWORD, FALSE};
+use winapi::shared::winerror::ERROR_INVALID_PARAMETER;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::minwinbase::STILL_ACTIVE;
+use winapi::um::processthreadsapi::{GetCurrentProcessId, GetExitCodeProcess, OpenProcess};
+use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+use winapi::um::winuser::*;
+
+/// Window classes only a running instance owns; any of them still belonging to
+/// a previous session that is gone were left behind by it
+const LEFTOVER_WINDOW_CLASSES: [&str; 6] = [
+    OVERLAY_CLASS_NAME,
+    HUD_CLASS_NAME,
+    TRAIL_CLASS_NAME,
+    DEBUG_OVERLAY_CLASS_NAME,
+    PUSH_MARKER_CLASS_NAME,
+    TOAST_CLASS_NAME,
+];
+
+/// What startup recovery found and cleaned up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RecoveryReport {
+    /// The previous session left its running marker behind
+    pub unclean_shutdown: bool,
+    /// Process id recorded in that marker, if it could be read
+    pub previous_pid: Option<u32>,
+    /// Leftover overlay, HUD and trail windows that were asked to close
+    pub windows_closed: usize,
+    /// The previous session's process is still running, so its windows were
+    /// left alone
+    pub previous_still_running: bool,
+}
+
+/// The Win32 calls recovery makes, kept behind a trait so the recovery logic
+/// can be tested without a desktop
+pub trait Desktop {
+    /// Windows of `class` owned by process `pid`
+    fn process_windows(&self, class: &str, pid: u32) -> Vec<usize>;
+    /// Whether process `pid` is still running; a process that can't be
+    /// inspected is assumed to be
+    fn process_running(&self, pid: u32) -> bool;
+    /// Asks `window` to close; returns false if the request couldn't be sent
+    fn close_window(&mut self, window: usize) -> bool;
+    /// Lifts any cursor clip rectangle, whoever set it
+    fn release_cursor_clip(&mut self);
+}
+
+/// The real desktop
+pub struct Win32Desktop;
+
+impl Win32Desktop {
+    /// Windows of `class` owned by processes other than this one
+    pub fn foreign_windows(&self, class: &str) -> Vec<usize> {
+        let own_process = unsafe { GetCurrentProcessId() };
+        class_windows(class)
+            .into_iter()
+            .filter(|&(_, pid)| pid != own_process)
+            .map(|(hwnd, _)| hwnd)
+            .collect()
+    }
+}
+
+/// Every top-level window of `class`, with the id of the process that owns it
+fn class_windows(class: &str) -> Vec<(usize, u32)> {
+    let class_name: Vec<u16> = OsStr::new(class)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    let mut windows = Vec::new();
+    let mut after = ptr::null_mut();
+    loop {
+        let hwnd =
+            unsafe { FindWindowExW(ptr::null_mut(), after, class_name.as_ptr(), ptr::null()) };
+        if hwnd.is_null() {
+            break;
+        }
+
+        let mut process_id: DWORD = 0;
+        unsafe { GetWindowThreadProcessId(hwnd, &mut process_id) };
+        windows.push((hwnd as usize, process_id));
+        after = hwnd;
+    }
+    windows
+}
+
+impl Desktop for Win32Desktop {
+    fn process_windows(&self, class: &str, pid: u32) -> Vec<usize> {
+        class_windows(class)
+            .into_iter()
+            .filter(|&(_, owner)| owner == pid)
+            .map(|(hwnd, _)| hwnd)
+            .collect()
+    }
+
+    fn process_running(&self, pid: u32) -> bool {
+        unsafe {
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+            if process.is_null() {
+                // Any other failure, like being denied access, means it exists
+                return GetLastError() != ERROR_INVALID_PARAMETER;
+            }
+            let mut exit_code: DWORD = 0;
+            let ok = GetExitCodeProcess(process, &mut exit_code) != 0;
+            CloseHandle(process);
+            !ok || exit_code == STILL_ACTIVE
+        }
+    }
+
+    fn close_window(&mut self, window: usize) -> bool {
+        // DestroyWindow only works on windows of the calling thread, so ask the
+        // owner to close it instead
+        unsafe { PostMessageW(window as _, WM_CLOSE, 0, 0) != 0 }
+    }
+
+    fn release_cursor_clip(&mut self) {
+        unsafe {
+            ClipCursor(ptr::null());
+        }
+    }
+}
+
+/// A file that exists for as long as a session is running.
+///
+/// Finding it at startup means the previous session never released it, i.e.
+/// it crashed or was killed. Dropping the marker releases it, so every way out
+/// of `main` does, except a panic, which counts as a crash.
+pub struct SessionMarker {
+    path: PathBuf,
+    released: bool,
+}
+
+impl SessionMarker {
+    /// The contents of the marker a previous session left at `path`, if any
+    pub fn previous(path: &Path) -> io::Result<Option<String>> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the marker for this process, replacing any left at `path`
+    pub fn acquire(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        fs::write(&path, std::process::id().to_string())?;
+        Ok(Self {
+            path,
+            released: false,
+        })
+    }
+
+    /// Removes the marker, recording that this session shut down cleanly
+    pub fn release(mut self) -> io::Result<()> {
+        self.released = true;
+        fs::remove_file(&self.path)
+    }
+}
+
+impl Drop for SessionMarker {
+    fn drop(&mut self) {
+        if !self.released && !std::thread::panicking() {
+            if let Err(e) = fs::remove_file(&self.path) {
+                warn!(path = %self.path.display(), error = %e, "Failed to remove session marker");
+            }
+        }
+    }
+}
+
+/// Undoes whatever a previous session that didn't shut down cleanly may have
+/// left behind, then marks this session as running.
+///
+/// While the session that wrote the marker is still running, the marker is
+/// its own and this session runs without one: overwriting it would let
+/// whichever instance exits first remove it for both, hiding a later crash
+/// of the other. Failing to read or write the marker isn't fatal either.
+pub fn recover(
+    marker_path: &Path,
+    desktop: &mut impl Desktop,
+) -> (RecoveryReport, Option<SessionMarker>) {
+    let mut report = RecoveryReport::default();
+
+    // The hooks die with their process, but a cursor clip outlives it
+    desktop.release_cursor_clip();
+
+    let previous = match SessionMarker::previous(marker_path) {
+        Ok(previous) => previous,
+        Err(e) => {
+            warn!(path = %marker_path.display(), error = %e, "Failed to read session marker");
+            return (report, None);
+        }
+    };
+    if let Some(contents) = previous {
+        report.unclean_shutdown = true;
+        report.previous_pid = contents.trim().parse().ok();
+    }
+
+    // Only the windows of the session that left the marker are ours to close,
+    // and only once it's gone; another instance still running keeps its own
+    let own_pid = std::process::id();
+    match report.previous_pid.filter(|&pid| pid != own_pid) {
+        // Its marker is still there because it is, not because it crashed
+        Some(pid) if desktop.process_running(pid) => {
+            report.unclean_shutdown = false;
+            report.previous_still_running = true;
+            return (report, None);
+        }
+        Some(pid) => {
+            for class in LEFTOVER_WINDOW_CLASSES {
+                for window in desktop.process_windows(class, pid) {
+                    if desktop.close_window(window) {
+                        report.windows_closed += 1;
+                    }
+                }
+            }
+        }
+        None => {}
+    }
+    if report.windows_closed > 0 {
+        info!(
+            windows = report.windows_closed,
+            "Closed overlay/HUD/trail windows left by the previous session"
+        );
+    }
+
+    let marker = match SessionMarker::acquire(marker_path) {
+        Ok(marker) => Some(marker),
+        Err(e) => {
+            warn!(path = %marker_path.display(), error = %e, "Failed to write session marker");
+            None
+        }
+    };
+    (report, marker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[derive(Default)]
+    struct FakeDesktop {
+        /// Class, owning process and handle of each window
+        windows: Vec<(&'static str, u32, usize)>,
+        running: Vec<u32>,
+        closed: Vec<usize>,
+        unclosable: Vec<usize>,
+        clip_released: bool,
+    }
+
+    impl Desktop for FakeDesktop {
+        fn process_windows(&self, class: &str, pid: u32) -> Vec<usize> {
+            self.windows
+                .iter()
+                .filter(|&&(window_class, owner, _)| window_class == class && owner == pid)
+                .map(|&(_, _, window)| window)
+                .collect()
+        }
+
+        fn process_running(&self, pid: u32) -> bool {
+            self.running.contains(&pid)
+        }
+
+        fn close_window(&mut self, window: usize) -> bool {
+            if self.unclosable.contains(&window) {
+                return false;
+            }
+            self.closed.push(window);
+            true
+        }
+
+        fn release_cursor_clip(&mut self) {
+            self.clip_released = true;
+        }
+    }
+
+    #[test]
+    fn test_clean_start() {
+        let dir = TempDir::new().unwrap();
+        let marker_path = dir.path().join("ageofcrash.running");
+        let mut desktop = FakeDesktop::default();
+
+        let (report, marker) = recover(&marker_path, &mut desktop);
+
+        assert_eq!(report, RecoveryReport::default());
+        assert!(desktop.clip_released);
+        assert!(marker.is_some());
+        assert_eq!(
+            fs::read_to_string(&marker_path).unwrap(),
+            std::process::id().to_string()
+        );
+    }
+
+    #[test]
+    fn test_release_marks_clean_shutdown() {
+        let dir = TempDir::new().unwrap();
+        let marker_path = dir.path().join("ageofcrash.running");
+
+        let (_, marker) = recover(&marker_path, &mut FakeDesktop::default());
+        marker.unwrap().release().unwrap();
+        assert!(!marker_path.exists());
+
+        let (report, _) = recover(&marker_path, &mut FakeDesktop::default());
+        assert!(!report.unclean_shutdown);
+    }
+
+    #[test]
+    fn test_leftover_marker_reports_unclean_shutdown() {
+        let dir = TempDir::new().unwrap();
+        let marker_path = dir.path().join("ageofcrash.running");
+        fs::write(&marker_path, "4242").unwrap();
+
+        let (report, marker) = recover(&marker_path, &mut FakeDesktop::default());
+
+        assert!(report.unclean_shutdown);
+        assert_eq!(report.previous_pid, Some(4242));
+        // The marker now belongs to this session
+        assert!(marker.is_some());
+        assert_eq!(
+            fs::read_to_string(&marker_path).unwrap(),
+            std::process::id().to_string()
+        );
+    }
+
+    #[test]
+    fn test_unreadable_marker_still_counts_as_unclean() {
+        let dir = TempDir::new().unwrap();
+        let marker_path = dir.path().join("ageofcrash.running");
+        fs::write(&marker_path, "").unwrap();
+
+        let (report, _) = recover(&marker_path, &mut FakeDesktop::default());
+
+        assert!(report.unclean_shutdown);
+        assert_eq!(report.previous_pid, None);
+    }
+
+    #[test]
+    fn test_closes_leftover_windows_of_the_dead_previous_session() {
+        let dir = TempDir::new().unwrap();
+        let marker_path = dir.path().join("ageofcrash.running");
+        fs::write(&marker_path, "4242").unwrap();
+        let mut desktop = FakeDesktop {
+            windows: vec![
+                (OVERLAY_CLASS_NAME, 4242, 1),
+                (OVERLAY_CLASS_NAME, 4242, 2),
+                (HUD_CLASS_NAME, 4242, 3),
+                ("Notepad", 4242, 4),
+                // Another instance, running alongside
+                (OVERLAY_CLASS_NAME, 5000, 5),
+            ],
+            running: vec![5000],
+            unclosable: vec![2],
+            ..FakeDesktop::default()
+        };
+
+        let (report, _) = recover(&marker_path, &mut desktop);
+
+        // Only our own classes are touched, and failed closes aren't counted
+        assert_eq!(desktop.closed, vec![1, 3]);
+        assert_eq!(report.windows_closed, 2);
+        assert!(!report.previous_still_running);
+    }
+
+    #[test]
+    fn test_leaves_a_running_instance_alone() {
+        let dir = TempDir::new().unwrap();
+        let marker_path = dir.path().join("ageofcrash.running");
+        fs::write(&marker_path, "4242").unwrap();
+        let mut desktop = FakeDesktop {
+            windows: vec![(OVERLAY_CLASS_NAME, 4242, 1), (HUD_CLASS_NAME, 4242, 2)],
+            running: vec![4242],
+            ..FakeDesktop::default()
+        };
+
+        let (report, _) = recover(&marker_path, &mut desktop);
+
+        assert!(desktop.closed.is_empty());
+        assert_eq!(report.windows_closed, 0);
+        assert!(report.previous_still_running);
+        assert!(!report.unclean_shutdown);
+    }
+
+    #[test]
+    fn test_a_running_instance_keeps_its_marker() {
+        let dir = TempDir::new().unwrap();
+        let marker_path = dir.path().join("ageofcrash.running");
+        fs::write(&marker_path, "4242").unwrap();
+        let mut desktop = FakeDesktop {
+            running: vec![4242],
+            ..FakeDesktop::default()
+        };
+
+        let (_, marker) = recover(&marker_path, &mut desktop);
+
+        // This session runs without one, so exiting can't remove the other's
+        assert!(marker.is_none());
+        assert_eq!(fs::read_to_string(&marker_path).unwrap(), "4242");
+
+        // If the other instance then crashes, the next start still notices
+        let (report, _) = recover(&marker_path, &mut FakeDesktop::default());
+        assert!(report.unclean_shutdown);
+        assert_eq!(report.previous_pid, Some(4242));
+    }
+
+    #[test]
+    fn test_without_a_marker_no_windows_are_closed() {
+        let dir = TempDir::new().unwrap();
+        let mut desktop = FakeDesktop {
+            windows: vec![(OVERLAY_CLASS_NAME, 4242, 1)],
+            ..FakeDesktop::default()
+        };
+
+        let (report, _) = recover(&dir.path().join("ageofcrash.running"), &mut desktop);
+
+        assert!(desktop.closed.is_empty());
+        assert_eq!(report.windows_closed, 0);
+    }
+
+    #[test]
+    fn test_dropping_the_marker_releases_it() {
+        let dir = TempDir::new().unwrap();
+        let marker_path = dir.path().join("ageofcrash.running");
+
+        let (_, marker) = recover(&marker_path, &mut FakeDesktop::default());
+        assert!(marker_path.exists());
+        drop(marker);
+        assert!(!marker_path.exists());
+    }
+
+    #[test]
+    fn test_unwritable_marker_is_not_fatal() {
+        let dir = TempDir::new().unwrap();
+        let mut desktop = FakeDesktop::default();
+
+        // A directory can't be read or overwritten as a file
+        let (report, marker) = recover(dir.path(), &mut desktop);
+
+        assert!(marker.is_none());
+        assert!(!report.unclean_shutdown);
+        assert!(desktop.clip_released);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::ui_thread::{register_class, wide, UiThread};
+use std::cell::RefCell;
+use std::ptr;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+use winapi::shared::minwindef::*;
+use winapi::shared::windef::*;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::winuser::*;
+
+/// Window class of the hidden window that listens for the session ending
+pub const SESSION_CLASS_NAME: &str = "AgeOfCrashSession";
+
+// Windows offers to kill apps that take longer than about five seconds to end
+// their session, so give up waiting on the main loop a little before that
+const SESSION_END_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// The parts of the app shutdown tears down, kept behind a trait so the order
+/// can be tested without a desktop
+pub trait Subsystems {
+    /// Stops window tracking and removes the barrier and keyboard hooks, so
+    /// nothing can re-arm the barrier from here on
+    fn cleanup_hooks(&mut self);
+    /// Closes the HUD and trail windows and waits for their UI threads to exit
+    fn close_windows(&mut self);
+    /// Writes out buffered logs and diagnostics
+    fn flush(&mut self);
+    /// Records that this session shut down cleanly
+    fn release_session(&mut self);
+}
+
+/// Runs the shutdown sequence once, however many ways shutdown is requested
+/// (an event, `WM_QUIT`, the session ending, an error return from `main`).
+#[derive(Debug, Default)]
+pub struct Shutdown {
+    done: bool,
+    finished: Option<Arc<ShutdownSignal>>,
+}
+
+impl Shutdown {
+    /// Finishes `finished` once the sequence has run, so a session that is
+    /// ending waits for it
+    pub fn signal_when_finished(&mut self, finished: Arc<ShutdownSignal>) {
+        self.finished = Some(finished);
+    }
+
+    /// Tears `subsystems` down; returns false if that already happened.
+    ///
+    /// Hooks go first so the cursor is free even if a UI thread hangs, and
+    /// the session marker goes last so a crash part-way through still reads
+    /// as an unclean shutdown at the next start.
+    pub fn run(&mut self, subsystems: &mut impl Subsystems) -> bool {
+        if self.done {
+            return false;
+        }
+        self.done = true;
+
+        subsystems.cleanup_hooks();
+        subsystems.close_windows();
+        subsystems.flush();
+        subsystems.release_session();
+        if let Some(finished) = &self.finished {
+            finished.finish();
+        }
+        true
+    }
+}
+
+/// Set by the main loop once shutdown has finished, for threads that must not
+/// return before then
+#[derive(Debug, Default)]
+pub struct ShutdownSignal {
+    finished: Mutex<bool>,
+    changed: Condvar,
+}
+
+impl ShutdownSignal {
+    pub fn finish(&self) {
+        if let Ok(mut finished) = self.finished.lock() {
+            *finished = true;
+            self.changed.notify_all();
+        }
+    }
+
+    /// Waits up to `timeout` for [`ShutdownSignal::finish`]; returns whether
+    /// it was called
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let Ok(finished) = self.finished.lock() else {
+            return false;
+        };
+        match self
+            .changed
+            .wait_timeout_while(finished, timeout, |finished| !*finished)
+        {
+            Ok((finished, _)) => *finished,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Turns logoff and system shutdown into a shutdown request.
+///
+/// Only top-level windows hear about the session ending, so this keeps a
+/// hidden one on its own UI thread. Windows may end the process as soon as
+/// `WM_ENDSESSION` has been handled, so the window holds on to it until the
+/// main loop signals that shutdown finished.
+pub struct SessionWatcher {
+    _ui: UiThread,
+}
+
+impl SessionWatcher {
+    pub fn new<F>(
+        request_shutdown: F,
+        finished: Arc<ShutdownSignal>,
+    ) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        F: Fn() + Send + 'static,
+    {
+        let ui = UiThread::spawn("session-ui", move || {
+            SESSION_HANDLER.with(|handler| {
+                *handler.borrow_mut() = Some(SessionHandler {
+                    request_shutdown: Box::new(request_shutdown),
+                    finished,
+                });
+            });
+            create_session_window()
+        })?;
+        Ok(Self { _ui: ui })
+    }
+}
+
+struct SessionHandler {
+    request_shutdown: Box<dyn Fn() + Send>,
+    finished: Arc<ShutdownSignal>,
+}
+
+thread_local! {
+    // Set on the session thread before its window can receive messages
+    static SESSION_HANDLER: RefCell<Option<SessionHandler>> = const { RefCell::new(None) };
+}
+
+fn create_session_window() -> Result<HWND, Box<dyn std::error::Error>> {
+    let class_name = register_class(SESSION_CLASS_NAME, session_window_proc);
+    let window_title = wide("Mouse Barrier Session");
+
+    // Never shown; a message-only window wouldn't receive the session broadcasts
+    let hwnd = unsafe {
+        CreateWindowExW(
+            WS_EX_TOOLWINDOW,
+            class_name.as_ptr(),
+            window_title.as_ptr(),
+            WS_POPUP,
+            0,
+            0,
+            0,
+            0,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            GetModuleHandleW(ptr::null()),
+            ptr::null_mut(),
+        )
+    };
+
+    if hwnd.is_null() {
+        return Err("Failed to create session window".into());
+    }
+
+    Ok(hwnd)
+}
+
+/// Requests shutdown and holds the session open until it has finished
+fn end_session() {
+    SESSION_HANDLER.with(|handler| {
+        if let Some(handler) = handler.borrow().as_ref() {
+            info!("Session is ending; shutting down");
+            (handler.request_shutdown)();
+            if !handler.finished.wait(SESSION_END_TIMEOUT) {
+                warn!(
+                    "Shutdown didn't finish within {:?} of the session ending",
+                    SESSION_END_TIMEOUT
+                );
+            }
+        }
+    });
+}
+
+unsafe extern "system" fn session_window_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        // Never stand in the way of logoff or shutdown
+        WM_QUERYENDSESSION => TRUE as LRESULT,
+        WM_ENDSESSION => {
+            // FALSE means another app cancelled it
+            if wparam != 0 {
+                end_session();
+            }
+            0
+        }
+        WM_DESTROY => {
+            PostQuitMessage(0);
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[derive(Default)]
+    struct FakeSubsystems {
+        steps: Vec<&'static str>,
+    }
+
+    impl Subsystems for FakeSubsystems {
+        fn cleanup_hooks(&mut self) {
+            self.steps.push("cleanup_hooks");
+        }
+
+        fn close_windows(&mut self) {
+            self.steps.push("close_windows");
+        }
+
+        fn flush(&mut self) {
+            self.steps.push("flush");
+        }
+
+        fn release_session(&mut self) {
+            self.steps.push("release_session");
+        }
+    }
+
+    #[test]
+    fn test_shutdown_order() {
+        let mut subsystems = FakeSubsystems::default();
+        assert!(Shutdown::default().run(&mut subsystems));
+
+        assert_eq!(
+            subsystems.steps,
+            ["cleanup_hooks", "close_windows", "flush", "release_session"]
+        );
+    }
+
+    #[test]
+    fn test_shutdown_runs_once() {
+        let mut subsystems = FakeSubsystems::default();
+        let mut shutdown = Shutdown::default();
+
+        assert!(shutdown.run(&mut subsystems));
+        // e.g. the session ended, then WM_QUIT arrived
+        assert!(!shutdown.run(&mut subsystems));
+        assert_eq!(subsystems.steps.len(), 4);
+    }
+
+    #[test]
+    fn test_shutdown_finishes_its_signal() {
+        let finished = Arc::new(ShutdownSignal::default());
+        let mut shutdown = Shutdown::default();
+        shutdown.signal_when_finished(finished.clone());
+        assert!(!finished.wait(Duration::ZERO));
+
+        shutdown.run(&mut FakeSubsystems::default());
+        assert!(finished.wait(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_signal_wakes_a_waiting_thread() {
+        let signal = Arc::new(ShutdownSignal::default());
+        assert!(!signal.wait(Duration::from_millis(10)));
+
+        let waiter = {
+            let signal = signal.clone();
+            thread::spawn(move || signal.wait(Duration::from_secs(10)))
+        };
+        signal.finish();
+
+        assert!(waiter.join().unwrap());
+        // Finishing is sticky, so late waiters don't block
+        assert!(signal.wait(Duration::ZERO));
+    }
+}
@@ -0,0 +1,283 @@
+use crate::config::{vk_code_from_string, HotkeyConfig};
+use crate::debug_dump::{kind_name, CSV_HEADER};
+use crate::hotkey::HotkeyDetector;
+use mouse_barrier::{
+    MouseBarrierConfig, SimulatedAction, SimulatedMove, Simulation, SoundKind, Zone,
+};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::sync::{mpsc, Mutex};
+use winapi::um::winuser::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN};
+
+/// One line of a simulation script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// `move,<x>,<y>`: the cursor moves to (`x`, `y`)
+    Move { x: i32, y: i32 },
+    /// `key,<key>,<down|up>`: a key is pressed or released; `key` is a name
+    /// from config.ron (`F12`, `A`, `7`) or a hex virtual-key code (`0x11`)
+    Key { vk_code: u32, is_down: bool },
+}
+
+/// Reads a simulation script.
+///
+/// Blank lines and lines starting with `#` are skipped. A `--debug-dump` CSV
+/// works as a script too: its header is recognized, and each row replays the
+/// move it recorded.
+pub fn parse_script(script: &str) -> Result<Vec<Step>, String> {
+    let mut lines = script
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .peekable();
+
+    let is_dump = lines.next_if(|(_, line)| *line == CSV_HEADER).is_some();
+    lines
+        .map(|(number, line)| {
+            let step = if is_dump {
+                parse_dump_row(line)
+            } else {
+                parse_step(line)
+            };
+            step.ok_or_else(|| format!("Line {}: can't read {:?}", number, line))
+        })
+        .collect()
+}
+
+fn parse_step(line: &str) -> Option<Step> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    match fields.as_slice() {
+        ["move", x, y] => Some(Step::Move {
+            x: x.parse().ok()?,
+            y: y.parse().ok()?,
+        }),
+        ["key", key, state] => Some(Step::Key {
+            vk_code: parse_key(key)?,
+            is_down: match *state {
+                "down" => true,
+                "up" => false,
+                _ => return None,
+            },
+        }),
+        _ => None,
+    }
+}
+
+/// The move a debug dump row recorded, from its `input_x`/`input_y` columns
+fn parse_dump_row(line: &str) -> Option<Step> {
+    let mut fields = line.split(',').skip(2);
+    Some(Step::Move {
+        x: fields.next()?.parse().ok()?,
+        y: fields.next()?.parse().ok()?,
+    })
+}
+
+fn parse_key(key: &str) -> Option<u32> {
+    match key.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => vk_code_from_string(key),
+    }
+}
+
+/// Replays the script at `path` against a simulated barrier and prints what
+/// the barrier did at every step.
+///
+/// The barrier starts enabled; key steps that make up the configured hotkey
+/// toggle it. Nothing is hooked and the real cursor never moves.
+pub fn run(
+    path: &Path,
+    barrier: &MouseBarrierConfig,
+    hotkey: &HotkeyConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let script = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read script {}: {}", path.display(), e))?;
+    let steps = parse_script(&script)?;
+
+    let (width, height) = unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+    let mut simulation = Simulation::new(barrier, width, height);
+    simulation.enable();
+
+    // Wired like the real hotkey subscription, minus the toggle cooldown:
+    // script steps carry no timing
+    let detector =
+        Mutex::new(HotkeyDetector::new(hotkey.clone()).ok_or("Failed to create hotkey detector")?);
+    let (hotkey_tx, hotkey_rx) = mpsc::channel();
+    simulation.subscribe_keys(move |vk_code, is_down| {
+        if let Ok(mut detector) = detector.lock() {
+            if detector.handle_key(vk_code, is_down) {
+                let _ = hotkey_tx.send(());
+            }
+        }
+    });
+
+    println!("Simulating {} steps from {}", steps.len(), path.display());
+    for step in steps {
+        match step {
+            Step::Move { x, y } => {
+                let outcome = simulation.simulate_mouse_move(x, y);
+                println!("{}", describe_move(x, y, &outcome));
+            }
+            Step::Key { vk_code, is_down } => {
+                simulation.simulate_key(vk_code, is_down);
+                for () in hotkey_rx.try_iter() {
+                    let enabled = simulation.toggle();
+                    println!(
+                        "hotkey -> barrier {}",
+                        if enabled { "enabled" } else { "disabled" }
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// One output line, e.g.
+/// `move (80,275) -> buffer_push (49,275), consumed, zone outside->buffer, sound barrier_hit`
+fn describe_move(x: i32, y: i32, outcome: &SimulatedMove) -> String {
+    let mut line = format!("move ({},{}) -> ", x, y);
+    let Some(decision) = outcome.decision else {
+        line.push_str("disabled");
+        return line;
+    };
+
+    let _ = write!(
+        line,
+        "{} ({},{})",
+        kind_name(decision.kind),
+        outcome.cursor.x,
+        outcome.cursor.y
+    );
+    if outcome.consumed {
+        line.push_str(", consumed");
+    }
+    for action in &outcome.actions {
+        match action {
+            SimulatedAction::CursorMoved(_) | SimulatedAction::CursorSlowed(_) => {}
+            SimulatedAction::ZoneChanged { from, to } => {
+                let _ = write!(line, ", zone {}->{}", zone_name(*from), zone_name(*to));
+            }
+            SimulatedAction::SoundRequested(kind) => {
+                let _ = write!(line, ", sound {}", sound_name(*kind));
+            }
+            SimulatedAction::WouldHavePushed(push) => {
+                let _ = write!(line, ", would push to ({},{})", push.to.x, push.to.y);
+            }
+        }
+    }
+    line
+}
+
+fn zone_name(zone: Zone) -> &'static str {
+    match zone {
+        Zone::Outside => "outside",
+        Zone::Buffer => "buffer",
+        Zone::Barrier => "barrier",
+    }
+}
+
+fn sound_name(kind: SoundKind) -> &'static str {
+    match kind {
+        SoundKind::BarrierHit => "barrier_hit",
+        SoundKind::BarrierEntry => "barrier_entry",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mouse_barrier::{BarrierDecision, DecisionKind, Point};
+    use std::time::Instant;
+
+    #[test]
+    fn test_parse_script() {
+        let script = "\
+# approach from the left, then toggle off
+move,20,275
+move, 600, 275
+
+key,0x11,down
+key,F12,down
+key,7,up
+";
+        assert_eq!(
+            parse_script(script),
+            Ok(vec![
+                Step::Move { x: 20, y: 275 },
+                Step::Move { x: 600, y: 275 },
+                Step::Key {
+                    vk_code: 0x11,
+                    is_down: true
+                },
+                Step::Key {
+                    vk_code: 0x7B,
+                    is_down: true
+                },
+                Step::Key {
+                    vk_code: 0x37,
+                    is_down: false
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_script_reports_the_bad_line() {
+        let error = parse_script("move,1,2\n\nmove,1\n").unwrap_err();
+        assert!(error.starts_with("Line 3:"), "{}", error);
+        assert!(parse_script("key,F12,sideways").is_err());
+        assert!(parse_script("jump,1,2").is_err());
+    }
+
+    #[test]
+    fn test_debug_dump_replays_its_inputs() {
+        let dump = format!(
+            "{}\n1500,pass,10,20,,,10,20\n2000,trajectory_block,150,105,-5,105,89,105\n",
+            CSV_HEADER
+        );
+        assert_eq!(
+            parse_script(&dump),
+            Ok(vec![
+                Step::Move { x: 10, y: 20 },
+                Step::Move { x: 150, y: 105 },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_describe_move() {
+        let disabled = SimulatedMove {
+            decision: None,
+            actions: Vec::new(),
+            consumed: false,
+            cursor: Point { x: 5, y: 6 },
+        };
+        assert_eq!(describe_move(5, 6, &disabled), "move (5,6) -> disabled");
+
+        let pushed = SimulatedMove {
+            decision: Some(BarrierDecision {
+                input: Point { x: 80, y: 275 },
+                last: Some(Point { x: 50, y: 275 }),
+                kind: DecisionKind::BufferPush,
+                output: Point { x: 49, y: 275 },
+                timestamp: Instant::now(),
+            }),
+            actions: vec![
+                SimulatedAction::ZoneChanged {
+                    from: Zone::Outside,
+                    to: Zone::Buffer,
+                },
+                SimulatedAction::SoundRequested(SoundKind::BarrierHit),
+            ],
+            consumed: true,
+            cursor: Point { x: 49, y: 275 },
+        };
+        assert_eq!(
+            describe_move(80, 275, &pushed),
+            "move (80,275) -> buffer_push (49,275), consumed, zone outside->buffer, sound barrier_hit"
+        );
+    }
+}
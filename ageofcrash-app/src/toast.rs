@@ -0,0 +1,257 @@
+use crate::ui_thread::{register_class, wide, UiThread};
+use std::cell::Cell;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use winapi::shared::minwindef::*;
+use winapi::shared::windef::*;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::wingdi::*;
+use winapi::um::winuser::*;
+
+/// Window class of the toast, for finding ones left behind by a previous run
+pub const TOAST_CLASS_NAME: &str = "AgeOfCrashToast";
+
+const TOAST_WIDTH: i32 = 220;
+const TOAST_HEIGHT: i32 = 44;
+const TOAST_MARGIN: i32 = 16; // From the corner of the work area
+const TOAST_FONT_HEIGHT: i32 = 18;
+
+// Shown at TOAST_ALPHA for TOAST_HOLD, then faded out over TOAST_FADE
+const TOAST_ALPHA: u8 = 230;
+const TOAST_HOLD: Duration = Duration::from_millis(1200);
+const TOAST_FADE: Duration = Duration::from_millis(300);
+
+const TOAST_FADE_TIMER_ID: usize = 1;
+const TOAST_FADE_INTERVAL_MS: u32 = 33; // ~30 Hz
+
+const COLOR_BACKGROUND: u32 = 0x00202020;
+const COLOR_TEXT: u32 = 0x00FFFFFF;
+
+/// What the toast says after the barrier was enabled or disabled
+pub fn toggle_message(enabled: bool) -> &'static str {
+    if enabled {
+        "Barrier enabled"
+    } else {
+        "Barrier disabled"
+    }
+}
+
+/// The toast's opacity `elapsed` after it was shown, or `None` once it has
+/// faded out
+fn toast_alpha(elapsed: Duration) -> Option<u8> {
+    let Some(fading) = elapsed.checked_sub(TOAST_HOLD) else {
+        return Some(TOAST_ALPHA);
+    };
+    if fading >= TOAST_FADE {
+        return None;
+    }
+    let remaining = 1.0 - fading.as_secs_f64() / TOAST_FADE.as_secs_f64();
+    Some((TOAST_ALPHA as f64 * remaining).round() as u8)
+}
+
+/// Text of the toast being shown, handed from [`Toast::show`] to the UI thread
+static TOAST_TEXT: Mutex<String> = Mutex::new(String::new());
+
+thread_local! {
+    // When the toast on this UI thread was last shown
+    static SHOWN_AT: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// A brief notification in the corner of the screen, shown with
+/// `notify_on_toggle`
+///
+/// Like the HUD, the window lives on its own UI thread. [`Toast::show`] only
+/// posts it a message, so toggling never waits on drawing.
+pub struct Toast {
+    ui: UiThread,
+}
+
+impl Toast {
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            ui: UiThread::spawn("toast-ui", create_toast_window)?,
+        })
+    }
+
+    /// Shows `text`, replacing any toast still on screen
+    pub fn show(&self, text: &str) {
+        if let Ok(mut shown) = TOAST_TEXT.lock() {
+            text.clone_into(&mut shown);
+        }
+        self.ui.post(WM_TOAST_SHOW);
+    }
+}
+
+/// Posted to the toast window to show the text in `TOAST_TEXT`
+const WM_TOAST_SHOW: UINT = WM_APP + 2;
+
+fn create_toast_window() -> Result<HWND, Box<dyn std::error::Error>> {
+    let class_name = register_class(TOAST_CLASS_NAME, toast_window_proc);
+    let window_title = wide("Mouse Barrier Toast");
+
+    // Created hidden; WM_TOAST_SHOW places and shows it
+    let hwnd = unsafe {
+        CreateWindowExW(
+            WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_NOACTIVATE | WS_EX_TOOLWINDOW,
+            class_name.as_ptr(),
+            window_title.as_ptr(),
+            WS_POPUP,
+            0,
+            0,
+            TOAST_WIDTH,
+            TOAST_HEIGHT,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            GetModuleHandleW(ptr::null()),
+            ptr::null_mut(),
+        )
+    };
+
+    if hwnd.is_null() {
+        return Err("Failed to create toast window".into());
+    }
+
+    Ok(hwnd)
+}
+
+/// Moves the toast to the bottom-right corner of the work area, above the
+/// taskbar, and shows it at full opacity
+unsafe fn show_toast(hwnd: HWND) {
+    let mut work_area: RECT = std::mem::zeroed();
+    if SystemParametersInfoW(SPI_GETWORKAREA, 0, &mut work_area as *mut _ as *mut _, 0) == 0 {
+        work_area = RECT {
+            left: 0,
+            top: 0,
+            right: GetSystemMetrics(SM_CXSCREEN),
+            bottom: GetSystemMetrics(SM_CYSCREEN),
+        };
+    }
+    SetWindowPos(
+        hwnd,
+        HWND_TOPMOST,
+        work_area.right - TOAST_WIDTH - TOAST_MARGIN,
+        work_area.bottom - TOAST_HEIGHT - TOAST_MARGIN,
+        0,
+        0,
+        SWP_NOSIZE | SWP_NOACTIVATE,
+    );
+    SetLayeredWindowAttributes(hwnd, 0, TOAST_ALPHA, LWA_ALPHA);
+    ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+    InvalidateRect(hwnd, ptr::null(), TRUE);
+
+    SHOWN_AT.with(|shown_at| shown_at.set(Some(Instant::now())));
+    SetTimer(hwnd, TOAST_FADE_TIMER_ID, TOAST_FADE_INTERVAL_MS, None);
+}
+
+unsafe fn paint_toast(hwnd: HWND) {
+    let mut ps: PAINTSTRUCT = std::mem::zeroed();
+    let hdc = BeginPaint(hwnd, &mut ps);
+
+    let mut rect: RECT = std::mem::zeroed();
+    GetClientRect(hwnd, &mut rect);
+
+    let background = CreateSolidBrush(COLOR_BACKGROUND);
+    FillRect(hdc, &rect, background);
+    DeleteObject(background as *mut _);
+
+    let font = CreateFontW(
+        TOAST_FONT_HEIGHT,
+        0,
+        0,
+        0,
+        FW_SEMIBOLD,
+        0,
+        0,
+        0,
+        DEFAULT_CHARSET,
+        OUT_DEFAULT_PRECIS,
+        CLIP_DEFAULT_PRECIS,
+        DEFAULT_QUALITY,
+        DEFAULT_PITCH | FF_DONTCARE,
+        ptr::null(),
+    );
+    let old_font = SelectObject(hdc, font as *mut _);
+    SetTextColor(hdc, COLOR_TEXT);
+    SetBkMode(hdc, TRANSPARENT as i32);
+
+    let text: Vec<u16> = TOAST_TEXT
+        .lock()
+        .map(|text| OsStr::new(text.as_str()).encode_wide().collect())
+        .unwrap_or_default();
+    DrawTextW(
+        hdc,
+        text.as_ptr(),
+        text.len() as i32,
+        &mut rect,
+        DT_CENTER | DT_VCENTER | DT_SINGLELINE,
+    );
+
+    SelectObject(hdc, old_font);
+    DeleteObject(font as *mut _);
+    EndPaint(hwnd, &ps);
+}
+
+unsafe extern "system" fn toast_window_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_TOAST_SHOW => {
+            show_toast(hwnd);
+            0
+        }
+        WM_PAINT => {
+            paint_toast(hwnd);
+            0
+        }
+        WM_TIMER if wparam == TOAST_FADE_TIMER_ID => {
+            let elapsed = SHOWN_AT
+                .with(Cell::get)
+                .map(|shown_at| shown_at.elapsed())
+                .unwrap_or(Duration::MAX);
+            match toast_alpha(elapsed) {
+                Some(alpha) => {
+                    SetLayeredWindowAttributes(hwnd, 0, alpha, LWA_ALPHA);
+                }
+                None => {
+                    KillTimer(hwnd, TOAST_FADE_TIMER_ID);
+                    ShowWindow(hwnd, SW_HIDE);
+                }
+            }
+            0
+        }
+        WM_MOUSEACTIVATE => MA_NOACTIVATE as LRESULT,
+        WM_DESTROY => {
+            KillTimer(hwnd, TOAST_FADE_TIMER_ID);
+            // Ends the toast thread's message loop
+            PostQuitMessage(0);
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toast_holds_then_fades_out() {
+        assert_eq!(toast_alpha(Duration::ZERO), Some(TOAST_ALPHA));
+        assert_eq!(toast_alpha(TOAST_HOLD), Some(TOAST_ALPHA));
+        assert_eq!(toast_alpha(TOAST_HOLD + TOAST_FADE / 2), Some(115));
+        assert_eq!(toast_alpha(TOAST_HOLD + TOAST_FADE), None);
+        assert_eq!(toast_alpha(Duration::MAX), None);
+    }
+
+    #[test]
+    fn test_toggle_message() {
+        assert_eq!(toggle_message(true), "Barrier enabled");
+        assert_eq!(toggle_message(false), "Barrier disabled");
+    }
+}
@@ -0,0 +1,406 @@
+use crate::ui_thread::{register_class, wide, UiThread};
+use mouse_barrier::{LastPush, Point};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::ptr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use winapi::shared::minwindef::*;
+use winapi::shared::windef::*;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::wingdi::*;
+use winapi::um::winuser::*;
+
+/// Window class of the trail, for finding ones left behind by a previous run
+pub const TRAIL_CLASS_NAME: &str = "AgeOfCrashTrail";
+
+// Positions kept; at the default 60 Hz position rate this is about two seconds
+const TRAIL_CAPACITY: usize = 120;
+// Pushes kept, each drawn as a marker
+const PUSH_CAPACITY: usize = 16;
+// How long a position or push stays on screen
+const TRAIL_WINDOW: Duration = Duration::from_secs(1);
+
+const TRAIL_REPAINT_TIMER_ID: usize = 1;
+const TRAIL_REPAINT_INTERVAL_MS: u32 = 33; // ~30 Hz
+const DOT_RADIUS: i32 = 2;
+const CROSS_ARM: i32 = 6;
+
+// Painted black is see-through: the window uses it as its color key
+const COLOR_TRANSPARENT: u32 = 0x00000000;
+const COLOR_DOT: u32 = 0x0000FFFF; // Yellow
+const COLOR_PUSH_FROM: u32 = 0x000000FF; // Red
+const COLOR_PUSH_TO: u32 = 0x0000FF00; // Green
+
+/// Recent cursor positions and pushes, newest last
+#[derive(Debug, Default)]
+struct CursorTrail {
+    positions: VecDeque<(Point, Instant)>,
+    pushes: VecDeque<LastPush>,
+}
+
+impl CursorTrail {
+    fn record_position(&mut self, pos: Point, now: Instant) {
+        if self.positions.len() == TRAIL_CAPACITY {
+            self.positions.pop_front();
+        }
+        self.positions.push_back((pos, now));
+    }
+
+    /// Adds `push` unless it is the one already recorded last
+    fn record_push(&mut self, push: LastPush) {
+        if self
+            .pushes
+            .back()
+            .is_some_and(|last| last.timestamp == push.timestamp)
+        {
+            return;
+        }
+        if self.pushes.len() == PUSH_CAPACITY {
+            self.pushes.pop_front();
+        }
+        self.pushes.push_back(push);
+    }
+
+    /// Drops everything older than `TRAIL_WINDOW`
+    fn expire(&mut self, now: Instant) {
+        let fresh = |at: Instant| now.saturating_duration_since(at) < TRAIL_WINDOW;
+        while self.positions.front().is_some_and(|&(_, at)| !fresh(at)) {
+            self.positions.pop_front();
+        }
+        while self
+            .pushes
+            .front()
+            .is_some_and(|push| !fresh(push.timestamp))
+        {
+            self.pushes.pop_front();
+        }
+    }
+}
+
+/// The trail being recorded, present while a [`Trail`] window exists
+static TRAIL: Mutex<Option<CursorTrail>> = Mutex::new(None);
+
+thread_local! {
+    // Measured once when the trail window is created on its UI thread
+    static WINDOW_SCALE: Cell<Option<ScreenScale>> = const { Cell::new(None) };
+}
+
+/// Adds a position from the mouse position callback (physical pixels) to the
+/// trail, if one is shown
+pub fn record_position(x: i32, y: i32) {
+    if let Ok(mut trail) = TRAIL.lock() {
+        if let Some(trail) = trail.as_mut() {
+            trail.record_position(Point { x, y }, Instant::now());
+        }
+    }
+}
+
+/// Logical and physical screen size, for drawing hook positions (physical
+/// pixels) on a window laid out in logical ones
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenScale {
+    pub logical: (i32, i32),
+    pub physical: (i32, i32),
+}
+
+impl ScreenScale {
+    pub fn current() -> Self {
+        let logical = unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+
+        let mut dev_mode: DEVMODEW = unsafe { std::mem::zeroed() };
+        dev_mode.dmSize = std::mem::size_of::<DEVMODEW>() as u16;
+        let physical =
+            if unsafe { EnumDisplaySettingsW(ptr::null(), ENUM_CURRENT_SETTINGS, &mut dev_mode) }
+                != 0
+            {
+                (dev_mode.dmPelsWidth as i32, dev_mode.dmPelsHeight as i32)
+            } else {
+                logical
+            };
+
+        Self { logical, physical }
+    }
+
+    pub fn to_window(self, pos: Point) -> Point {
+        let scale = |value: i32, logical: i32, physical: i32| {
+            if physical <= 0 {
+                value
+            } else {
+                (value as f64 * logical as f64 / physical as f64).round() as i32
+            }
+        };
+        Point {
+            x: scale(pos.x, self.logical.0, self.physical.0),
+            y: scale(pos.y, self.logical.1, self.physical.1),
+        }
+    }
+}
+
+/// A click-through, full-screen window drawing where the cursor went in the
+/// last second and where the barrier pushed it, for tuning `buffer_zone`
+///
+/// Like the HUD, the window lives on its own UI thread. Positions come from
+/// the mouse position callback and pushes are polled from
+/// [`mouse_barrier::last_push`], so nothing here runs on the hook thread.
+pub struct Trail {
+    _ui: UiThread,
+}
+
+impl Trail {
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        if let Ok(mut trail) = TRAIL.lock() {
+            *trail = Some(CursorTrail::default());
+        }
+
+        match UiThread::spawn("trail-ui", create_trail_window) {
+            Ok(ui) => Ok(Self { _ui: ui }),
+            Err(e) => {
+                stop_recording();
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Drop for Trail {
+    fn drop(&mut self) {
+        // The window closes once this returns, when `_ui` is dropped
+        stop_recording();
+    }
+}
+
+fn stop_recording() {
+    if let Ok(mut trail) = TRAIL.lock() {
+        *trail = None;
+    }
+}
+
+fn create_trail_window() -> Result<HWND, Box<dyn std::error::Error>> {
+    let class_name = register_class(TRAIL_CLASS_NAME, trail_window_proc);
+    let window_title = wide("Mouse Barrier Trail");
+
+    let scale = ScreenScale::current();
+    WINDOW_SCALE.with(|cell| cell.set(Some(scale)));
+    let (width, height) = scale.logical;
+    let hwnd = unsafe {
+        CreateWindowExW(
+            WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_NOACTIVATE | WS_EX_TOOLWINDOW,
+            class_name.as_ptr(),
+            window_title.as_ptr(),
+            WS_POPUP,
+            0,
+            0,
+            width,
+            height,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            GetModuleHandleW(ptr::null()),
+            ptr::null_mut(),
+        )
+    };
+
+    if hwnd.is_null() {
+        return Err("Failed to create trail window".into());
+    }
+
+    unsafe {
+        SetLayeredWindowAttributes(hwnd, COLOR_TRANSPARENT, 0, LWA_COLORKEY);
+
+        ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+        SetTimer(
+            hwnd,
+            TRAIL_REPAINT_TIMER_ID,
+            TRAIL_REPAINT_INTERVAL_MS,
+            None,
+        );
+    }
+
+    Ok(hwnd)
+}
+
+unsafe extern "system" fn trail_window_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            let mut ps: PAINTSTRUCT = std::mem::zeroed();
+            let hdc = BeginPaint(hwnd, &mut ps);
+
+            let mut rect: RECT = std::mem::zeroed();
+            GetClientRect(hwnd, &mut rect);
+
+            // Draw off-screen so the full-screen clear never flickers
+            let mem_dc = CreateCompatibleDC(hdc);
+            let bitmap = CreateCompatibleBitmap(hdc, rect.right, rect.bottom);
+            let old_bitmap = SelectObject(mem_dc, bitmap as *mut _);
+
+            let background = CreateSolidBrush(COLOR_TRANSPARENT);
+            FillRect(mem_dc, &rect, background);
+            DeleteObject(background as *mut _);
+
+            if let Some(scale) = WINDOW_SCALE.with(Cell::get) {
+                draw_trail(mem_dc, scale);
+            }
+
+            BitBlt(hdc, 0, 0, rect.right, rect.bottom, mem_dc, 0, 0, SRCCOPY);
+
+            SelectObject(mem_dc, old_bitmap);
+            DeleteObject(bitmap as *mut _);
+            DeleteDC(mem_dc);
+
+            EndPaint(hwnd, &ps);
+            0
+        }
+        WM_TIMER if wparam == TRAIL_REPAINT_TIMER_ID => {
+            if let Ok(mut trail) = TRAIL.lock() {
+                if let Some(trail) = trail.as_mut() {
+                    if let Some(push) = mouse_barrier::last_push() {
+                        trail.record_push(push);
+                    }
+                    trail.expire(Instant::now());
+                }
+            }
+            InvalidateRect(hwnd, ptr::null(), FALSE);
+            0
+        }
+        WM_MOUSEACTIVATE => MA_NOACTIVATE as LRESULT,
+        WM_DESTROY => {
+            KillTimer(hwnd, TRAIL_REPAINT_TIMER_ID);
+            // Ends the trail thread's message loop
+            PostQuitMessage(0);
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+unsafe fn draw_trail(hdc: HDC, scale: ScreenScale) {
+    let Ok(trail) = TRAIL.lock() else {
+        return;
+    };
+    let Some(trail) = trail.as_ref() else {
+        return;
+    };
+
+    let dot_brush = CreateSolidBrush(COLOR_DOT);
+    for &(pos, _) in &trail.positions {
+        let pos = scale.to_window(pos);
+        let dot = RECT {
+            left: pos.x - DOT_RADIUS,
+            top: pos.y - DOT_RADIUS,
+            right: pos.x + DOT_RADIUS + 1,
+            bottom: pos.y + DOT_RADIUS + 1,
+        };
+        FillRect(hdc, &dot, dot_brush);
+    }
+    DeleteObject(dot_brush as *mut _);
+
+    // Where each push caught the cursor (hook coordinates) and where it sent
+    // it (already in cursor coordinates)
+    for push in &trail.pushes {
+        draw_cross(hdc, scale.to_window(push.from), COLOR_PUSH_FROM);
+        draw_cross(hdc, push.to, COLOR_PUSH_TO);
+    }
+}
+
+unsafe fn draw_cross(hdc: HDC, at: Point, color: u32) {
+    let pen = CreatePen(PS_SOLID as i32, 2, color);
+    let old_pen = SelectObject(hdc, pen as *mut _);
+
+    MoveToEx(hdc, at.x - CROSS_ARM, at.y - CROSS_ARM, ptr::null_mut());
+    LineTo(hdc, at.x + CROSS_ARM + 1, at.y + CROSS_ARM + 1);
+    MoveToEx(hdc, at.x + CROSS_ARM, at.y - CROSS_ARM, ptr::null_mut());
+    LineTo(hdc, at.x - CROSS_ARM - 1, at.y + CROSS_ARM + 1);
+
+    SelectObject(hdc, old_pen);
+    DeleteObject(pen as *mut _);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_at(timestamp: Instant) -> LastPush {
+        LastPush {
+            from: Point { x: 95, y: 150 },
+            to: Point { x: 70, y: 150 },
+            edge: None,
+            speed: 5.0,
+            multiplier: 1.0,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_trail_keeps_only_the_newest_positions() {
+        let mut trail = CursorTrail::default();
+        let now = Instant::now();
+
+        for x in 0..(TRAIL_CAPACITY as i32 + 10) {
+            trail.record_position(Point { x, y: 0 }, now);
+        }
+
+        assert_eq!(trail.positions.len(), TRAIL_CAPACITY);
+        assert_eq!(trail.positions.front().unwrap().0.x, 10);
+        assert_eq!(
+            trail.positions.back().unwrap().0.x,
+            TRAIL_CAPACITY as i32 + 9
+        );
+    }
+
+    #[test]
+    fn test_trail_expires_old_entries() {
+        let mut trail = CursorTrail::default();
+        let start = Instant::now();
+
+        trail.record_position(Point { x: 1, y: 1 }, start);
+        trail.record_push(push_at(start));
+        trail.record_position(Point { x: 2, y: 2 }, start + Duration::from_millis(500));
+
+        trail.expire(start + Duration::from_millis(999));
+        assert_eq!(trail.positions.len(), 2);
+        assert_eq!(trail.pushes.len(), 1);
+
+        trail.expire(start + TRAIL_WINDOW);
+        assert_eq!(trail.positions.len(), 1);
+        assert!(trail.pushes.is_empty());
+    }
+
+    #[test]
+    fn test_the_same_push_is_recorded_once() {
+        let mut trail = CursorTrail::default();
+        let start = Instant::now();
+
+        // The timer polls last_push() repeatedly between pushes
+        trail.record_push(push_at(start));
+        trail.record_push(push_at(start));
+        trail.record_push(push_at(start + Duration::from_millis(40)));
+
+        assert_eq!(trail.pushes.len(), 2);
+    }
+
+    #[test]
+    fn test_positions_are_scaled_to_window_coordinates() {
+        let scaled = ScreenScale {
+            logical: (1920, 1080),
+            physical: (3840, 2160),
+        };
+        assert_eq!(
+            scaled.to_window(Point { x: 1001, y: 400 }),
+            Point { x: 501, y: 200 }
+        );
+
+        let unscaled = ScreenScale {
+            logical: (1920, 1080),
+            physical: (1920, 1080),
+        };
+        assert_eq!(
+            unscaled.to_window(Point { x: -5, y: 1079 }),
+            Point { x: -5, y: 1079 }
+        );
+    }
+}
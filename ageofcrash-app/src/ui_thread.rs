@@ -0,0 +1,198 @@
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tracing::warn;
+use winapi::shared::minwindef::*;
+use winapi::shared::windef::*;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::winuser::*;
+
+/// Posted to a [`UiThread`]'s window to make its thread destroy it. Windows
+/// with messages of their own number them from `WM_APP + 2`.
+const WM_UI_THREAD_CLOSE: UINT = WM_APP + 1;
+
+/// A window together with the UI thread that owns it, for the trail, the
+/// overlays, the toast and the session window.
+///
+/// The thread creates the window, so its messages arrive there, then runs
+/// its message loop. Dropping the `UiThread` destroys the window on its own
+/// thread and waits for the thread to exit.
+pub struct UiThread {
+    name: &'static str,
+    hwnd: HWND,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl UiThread {
+    /// Starts thread `name`, which creates its window with `create_window`.
+    /// Returns once the window exists, or with the error that stopped it.
+    pub fn spawn<F>(
+        name: &'static str,
+        create_window: F,
+    ) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        F: FnOnce() -> Result<HWND, Box<dyn std::error::Error>> + Send + 'static,
+    {
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<usize, String>>();
+
+        let handle = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                let hwnd = match create_window() {
+                    Ok(hwnd) => hwnd,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e.to_string()));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(hwnd as usize));
+                run_message_loop(hwnd);
+            })?;
+
+        match ready_rx.recv() {
+            Ok(Ok(hwnd)) => Ok(Self {
+                name,
+                hwnd: hwnd as HWND,
+                handle: Some(handle),
+            }),
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                Err(e.into())
+            }
+            Err(_) => {
+                let _ = handle.join();
+                Err(format!("Thread {} exited before creating its window", name).into())
+            }
+        }
+    }
+
+    /// Posts `msg` to the window without waiting for it to be handled
+    pub fn post(&self, msg: UINT) {
+        unsafe {
+            PostMessageW(self.hwnd, msg, 0, 0);
+        }
+    }
+}
+
+impl Drop for UiThread {
+    fn drop(&mut self) {
+        self.post(WM_UI_THREAD_CLOSE);
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                warn!(thread = self.name, "UI thread panicked");
+            }
+        }
+    }
+}
+
+fn run_message_loop(hwnd: HWND) {
+    unsafe {
+        let mut msg: MSG = std::mem::zeroed();
+        // Also ends if the window is destroyed some other way and its
+        // WM_DESTROY posts WM_QUIT
+        while GetMessageW(&mut msg, ptr::null_mut(), 0, 0) > 0 {
+            if msg.message == WM_UI_THREAD_CLOSE {
+                DestroyWindow(hwnd);
+                break;
+            }
+
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+}
+
+/// `text` as a null-terminated UTF-16 string
+pub fn wide(text: &str) -> Vec<u16> {
+    OsStr::new(text)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect()
+}
+
+/// Registers window class `class_name` with `window_proc`, returning the name
+/// as a wide string for `CreateWindowExW`. Registering a class again, as a
+/// window created a second time does, fails harmlessly.
+pub fn register_class(
+    class_name: &str,
+    window_proc: unsafe extern "system" fn(HWND, UINT, WPARAM, LPARAM) -> LRESULT,
+) -> Vec<u16> {
+    let class_name = wide(class_name);
+    let wc = WNDCLASSW {
+        style: 0,
+        lpfnWndProc: Some(window_proc),
+        cbClsExtra: 0,
+        cbWndExtra: 0,
+        hInstance: unsafe { GetModuleHandleW(ptr::null()) },
+        hIcon: ptr::null_mut(),
+        hCursor: ptr::null_mut(),
+        hbrBackground: ptr::null_mut(),
+        lpszMenuName: ptr::null(),
+        lpszClassName: class_name.as_ptr(),
+    };
+
+    unsafe {
+        RegisterClassW(&wc);
+    }
+    class_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe extern "system" fn test_window_proc(
+        hwnd: HWND,
+        msg: UINT,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        DefWindowProcW(hwnd, msg, wparam, lparam)
+    }
+
+    #[test]
+    fn test_window_creation_errors_are_returned() {
+        let result = UiThread::spawn("test-failing-ui", || Err("no window for you".into()));
+        assert_eq!(
+            result.err().map(|e| e.to_string()).as_deref(),
+            Some("no window for you")
+        );
+    }
+
+    #[test]
+    fn test_drop_destroys_the_window_and_joins_the_thread() {
+        let ui = UiThread::spawn("test-ui", || {
+            let class_name = register_class("AgeOfCrashUiThreadTest", test_window_proc);
+            let hwnd = unsafe {
+                CreateWindowExW(
+                    0,
+                    class_name.as_ptr(),
+                    ptr::null(),
+                    WS_POPUP,
+                    0,
+                    0,
+                    10,
+                    10,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    GetModuleHandleW(ptr::null()),
+                    ptr::null_mut(),
+                )
+            };
+            if hwnd.is_null() {
+                return Err("Failed to create test window".into());
+            }
+            Ok(hwnd)
+        })
+        .unwrap();
+        let hwnd = ui.hwnd;
+        assert_ne!(unsafe { IsWindow(hwnd) }, 0);
+
+        // The window's procedure never posts WM_QUIT, so only the close
+        // message ends the loop
+        drop(ui);
+        assert_eq!(unsafe { IsWindow(hwnd) }, 0);
+    }
+}
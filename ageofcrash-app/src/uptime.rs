@@ -0,0 +1,119 @@
+use std::time::{Duration, Instant};
+
+/// How long the barrier has protected the cursor: every stretch it was
+/// enabled and not suspended, added up.
+///
+/// Time is passed in rather than read, so the HUD can ask for the total at
+/// paint time and tests can drive the clock.
+#[derive(Debug, Clone, Default)]
+pub struct Uptime {
+    /// Stretches that have ended
+    finished: Duration,
+    /// Start of the stretch in progress, if the clock is running
+    running_since: Option<Instant>,
+}
+
+impl Uptime {
+    /// Starts or stops the clock at `now`. Asking for the state it is already
+    /// in changes nothing, so callers can sync it after every state change.
+    pub fn set_running(&mut self, running: bool, now: Instant) {
+        match (running, self.running_since) {
+            (true, None) => self.running_since = Some(now),
+            (false, Some(since)) => {
+                self.finished += now.saturating_duration_since(since);
+                self.running_since = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// The total as of `now`, including the stretch in progress
+    pub fn total(&self, now: Instant) -> Duration {
+        let running = self
+            .running_since
+            .map_or(Duration::ZERO, |since| now.saturating_duration_since(since));
+        self.finished + running
+    }
+
+    /// Starts counting from zero again, keeping the clock running if it was
+    pub fn reset(&mut self, now: Instant) {
+        self.finished = Duration::ZERO;
+        self.running_since = self.running_since.map(|_| now);
+    }
+}
+
+/// `mm:ss`, or `h:mm:ss` from an hour on
+pub fn format_uptime(uptime: Duration) -> String {
+    let seconds = uptime.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_uptime_counts_only_running_stretches() {
+        let start = Instant::now();
+        let mut uptime = Uptime::default();
+        assert_eq!(uptime.total(start + 5 * SECOND), Duration::ZERO);
+
+        uptime.set_running(true, start);
+        assert_eq!(uptime.total(start + 10 * SECOND), 10 * SECOND);
+
+        // Suspended: the total holds still
+        uptime.set_running(false, start + 10 * SECOND);
+        assert_eq!(uptime.total(start + 60 * SECOND), 10 * SECOND);
+
+        // Resumed: picks up where it left off
+        uptime.set_running(true, start + 60 * SECOND);
+        assert_eq!(uptime.total(start + 65 * SECOND), 15 * SECOND);
+    }
+
+    #[test]
+    fn test_uptime_ignores_repeated_states() {
+        let start = Instant::now();
+        let mut uptime = Uptime::default();
+
+        uptime.set_running(true, start);
+        // A second start doesn't restart the stretch
+        uptime.set_running(true, start + 10 * SECOND);
+        assert_eq!(uptime.total(start + 20 * SECOND), 20 * SECOND);
+
+        uptime.set_running(false, start + 20 * SECOND);
+        // Nor does a second stop count anything twice
+        uptime.set_running(false, start + 30 * SECOND);
+        assert_eq!(uptime.total(start + 40 * SECOND), 20 * SECOND);
+    }
+
+    #[test]
+    fn test_uptime_reset_keeps_the_clock_running() {
+        let start = Instant::now();
+        let mut uptime = Uptime::default();
+        uptime.set_running(true, start);
+
+        uptime.reset(start + 30 * SECOND);
+        assert_eq!(uptime.total(start + 40 * SECOND), 10 * SECOND);
+
+        uptime.set_running(false, start + 40 * SECOND);
+        uptime.reset(start + 50 * SECOND);
+        assert_eq!(uptime.total(start + 60 * SECOND), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(Duration::ZERO), "00:00");
+        assert_eq!(format_uptime(Duration::from_millis(59_999)), "00:59");
+        assert_eq!(format_uptime(42 * 60 * SECOND + 10 * SECOND), "42:10");
+        assert_eq!(format_uptime(3599 * SECOND), "59:59");
+        assert_eq!(format_uptime(3600 * SECOND), "1:00:00");
+        assert_eq!(format_uptime(26 * 3600 * SECOND + 5 * SECOND), "26:00:05");
+    }
+}
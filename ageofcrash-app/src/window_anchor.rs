@@ -0,0 +1,385 @@
+use crate::config::BarrierAnchor;
+use std::ffi::OsString;
+use std::os::windows::ffi::OsStringExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info};
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, LPARAM, TRUE};
+use winapi::shared::windef::{HWND, RECT};
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::processthreadsapi::{GetCurrentProcessId, OpenProcess};
+use winapi::um::winbase::QueryFullProcessImageNameW;
+use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+use winapi::um::winuser::*;
+
+/// Where the game window is, as far as the barrier is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnchorState {
+    /// The window is at least partly on the desktop; its outer rectangle
+    Visible {
+        left: i32,
+        top: i32,
+        right: i32,
+        bottom: i32,
+    },
+    /// No window matches the configured anchor
+    Missing,
+    /// The window is minimized, so its rectangle is meaningless
+    Minimized,
+    /// The window lies entirely outside the desktop
+    OffScreen,
+}
+
+impl AnchorState {
+    /// Screen position the barrier coordinates are relative to, if the barrier
+    /// can be enforced at all
+    pub fn origin(&self) -> Option<(i32, i32)> {
+        match *self {
+            AnchorState::Visible { left, top, .. } => Some((left, top)),
+            _ => None,
+        }
+    }
+
+    /// Why the barrier is suspended, for the HUD
+    pub fn notice(&self) -> Option<&'static str> {
+        match self {
+            AnchorState::Visible { .. } => None,
+            AnchorState::Missing => Some("Game window not found - barrier suspended"),
+            AnchorState::Minimized => Some("Game window minimized - barrier suspended"),
+            AnchorState::OffScreen => Some("Game window off-screen - barrier suspended"),
+        }
+    }
+}
+
+/// Polls the window the barrier is anchored to and reports every change
+pub struct WindowTracker {
+    anchor: BarrierAnchor,
+    tracker_thread: Option<thread::JoinHandle<()>>,
+    should_stop: Arc<AtomicBool>,
+    poll_interval: Duration,
+}
+
+impl WindowTracker {
+    pub fn new(anchor: BarrierAnchor) -> Self {
+        WindowTracker {
+            anchor,
+            tracker_thread: None,
+            should_stop: Arc::new(AtomicBool::new(false)),
+            poll_interval: Duration::from_millis(100),
+        }
+    }
+
+    pub fn anchor(&self) -> &BarrierAnchor {
+        &self.anchor
+    }
+
+    /// Starts polling; `on_change` runs on the tracker thread with the first
+    /// state found and again whenever it changes.
+    pub fn start<F>(&mut self, on_change: F) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: Fn(AnchorState) + Send + 'static,
+    {
+        let anchor = self.anchor.clone();
+        let should_stop = self.should_stop.clone();
+        let poll_interval = self.poll_interval;
+
+        let handle = thread::Builder::new()
+            .name("window-tracker".to_string())
+            .spawn(move || {
+                let mut last_state = None;
+
+                while !should_stop.load(Ordering::Relaxed) {
+                    let state = query_anchor_state(&anchor);
+                    if last_state != Some(state) {
+                        info!(?state, "Anchor window changed");
+                        last_state = Some(state);
+                        on_change(state);
+                    }
+
+                    thread::sleep(poll_interval);
+                }
+
+                info!("Window tracker thread stopping");
+            })?;
+
+        self.tracker_thread = Some(handle);
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(handle) = self.tracker_thread.take() {
+            self.should_stop.store(true, Ordering::Relaxed);
+            if let Err(e) = handle.join() {
+                error!("Failed to join window tracker thread: {:?}", e);
+            }
+        }
+    }
+}
+
+impl Drop for WindowTracker {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Where the anchor window is right now
+pub fn query_anchor_state(anchor: &BarrierAnchor) -> AnchorState {
+    let Some(hwnd) = find_anchor_window(anchor) else {
+        return AnchorState::Missing;
+    };
+
+    unsafe {
+        let minimized = IsIconic(hwnd) != 0;
+        let mut rect: RECT = std::mem::zeroed();
+        if GetWindowRect(hwnd, &mut rect) == 0 {
+            // The window went away between finding it and asking for its rect
+            return AnchorState::Missing;
+        }
+
+        let desktop = RECT {
+            left: GetSystemMetrics(SM_XVIRTUALSCREEN),
+            top: GetSystemMetrics(SM_YVIRTUALSCREEN),
+            right: GetSystemMetrics(SM_XVIRTUALSCREEN) + GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            bottom: GetSystemMetrics(SM_YVIRTUALSCREEN) + GetSystemMetrics(SM_CYVIRTUALSCREEN),
+        };
+
+        classify_window(&rect, minimized, &desktop)
+    }
+}
+
+fn classify_window(rect: &RECT, minimized: bool, desktop: &RECT) -> AnchorState {
+    if minimized {
+        return AnchorState::Minimized;
+    }
+
+    let on_desktop = rect.left < desktop.right
+        && rect.right > desktop.left
+        && rect.top < desktop.bottom
+        && rect.bottom > desktop.top;
+    if !on_desktop {
+        return AnchorState::OffScreen;
+    }
+
+    AnchorState::Visible {
+        left: rect.left,
+        top: rect.top,
+        right: rect.right,
+        bottom: rect.bottom,
+    }
+}
+
+struct WindowSearch<'a> {
+    anchor: &'a BarrierAnchor,
+    own_process: DWORD,
+    found: HWND,
+}
+
+fn find_anchor_window(anchor: &BarrierAnchor) -> Option<HWND> {
+    if *anchor == BarrierAnchor::Screen {
+        return None;
+    }
+
+    let mut search = WindowSearch {
+        anchor,
+        own_process: unsafe { GetCurrentProcessId() },
+        found: std::ptr::null_mut(),
+    };
+
+    unsafe {
+        EnumWindows(
+            Some(enum_windows_proc),
+            &mut search as *mut WindowSearch as LPARAM,
+        );
+    }
+
+    (!search.found.is_null()).then_some(search.found)
+}
+
+/// Id of the process owning the window `anchor` currently matches
+pub fn find_anchor_process(anchor: &BarrierAnchor) -> Option<u32> {
+    let hwnd = find_anchor_window(anchor)?;
+    let mut process_id: DWORD = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, &mut process_id) };
+    (process_id != 0).then_some(process_id)
+}
+
+unsafe extern "system" fn enum_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let search = &mut *(lparam as *mut WindowSearch);
+
+    if IsWindowVisible(hwnd) == 0 {
+        return TRUE;
+    }
+
+    let mut process_id: DWORD = 0;
+    GetWindowThreadProcessId(hwnd, &mut process_id);
+    // Never anchor to our own HUD or overlay windows
+    if process_id == search.own_process {
+        return TRUE;
+    }
+
+    let matches = match search.anchor {
+        BarrierAnchor::Screen => false,
+        BarrierAnchor::WindowTitle(pattern) => title_matches(&window_title(hwnd), pattern),
+        BarrierAnchor::Process(name) => {
+            process_image_path(process_id).is_some_and(|path| process_matches(&path, name))
+        }
+    };
+
+    if matches {
+        search.found = hwnd;
+        return FALSE; // Stop enumerating
+    }
+    TRUE
+}
+
+unsafe fn window_title(hwnd: HWND) -> String {
+    let mut buffer = [0u16; 512];
+    let len = GetWindowTextW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32);
+    String::from_utf16_lossy(&buffer[..len.max(0) as usize])
+}
+
+unsafe fn process_image_path(process_id: DWORD) -> Option<String> {
+    let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, process_id);
+    if process.is_null() {
+        return None;
+    }
+
+    let mut buffer = [0u16; 1024];
+    let mut len = buffer.len() as DWORD;
+    let ok = QueryFullProcessImageNameW(process, 0, buffer.as_mut_ptr(), &mut len) != 0;
+    CloseHandle(process);
+
+    ok.then(|| {
+        OsString::from_wide(&buffer[..len as usize])
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+/// Case-insensitive substring match; an empty pattern matches nothing
+fn title_matches(title: &str, pattern: &str) -> bool {
+    !pattern.is_empty() && title.to_lowercase().contains(&pattern.to_lowercase())
+}
+
+/// Matches an executable by file name, with or without the `.exe` extension
+fn process_matches(image_path: &str, name: &str) -> bool {
+    let path = Path::new(image_path);
+    let file_name = path.file_name().and_then(|n| n.to_str());
+    let file_stem = path.file_stem().and_then(|n| n.to_str());
+
+    [file_name, file_stem]
+        .into_iter()
+        .flatten()
+        .any(|candidate| candidate.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESKTOP: RECT = RECT {
+        left: 0,
+        top: 0,
+        right: 1920,
+        bottom: 1080,
+    };
+
+    fn rect(left: i32, top: i32, right: i32, bottom: i32) -> RECT {
+        RECT {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    #[test]
+    fn test_classify_window_visible() {
+        let state = classify_window(&rect(100, 50, 1380, 770), false, &DESKTOP);
+
+        assert_eq!(
+            state,
+            AnchorState::Visible {
+                left: 100,
+                top: 50,
+                right: 1380,
+                bottom: 770
+            }
+        );
+        assert_eq!(state.origin(), Some((100, 50)));
+        assert_eq!(state.notice(), None);
+    }
+
+    #[test]
+    fn test_classify_window_partly_off_screen_is_still_visible() {
+        let state = classify_window(&rect(-200, 900, 1080, 1620), false, &DESKTOP);
+        assert_eq!(state.origin(), Some((-200, 900)));
+    }
+
+    #[test]
+    fn test_classify_window_minimized() {
+        // Minimized windows report a parking rect far off-screen
+        let state = classify_window(&rect(-32000, -32000, -31840, -31972), true, &DESKTOP);
+
+        assert_eq!(state, AnchorState::Minimized);
+        assert_eq!(state.origin(), None);
+        assert!(state.notice().is_some());
+    }
+
+    #[test]
+    fn test_classify_window_off_screen() {
+        for off_screen in [
+            rect(1920, 0, 3200, 720), // Touching the right edge only
+            rect(-1280, 0, 0, 720),   // Touching the left edge only
+            rect(0, 1080, 1280, 1800),
+            rect(5000, 5000, 6280, 5720),
+        ] {
+            let state = classify_window(&off_screen, false, &DESKTOP);
+            assert_eq!(state, AnchorState::OffScreen);
+            assert_eq!(state.origin(), None);
+        }
+    }
+
+    #[test]
+    fn test_missing_window_suspends() {
+        assert_eq!(AnchorState::Missing.origin(), None);
+        assert!(AnchorState::Missing.notice().is_some());
+    }
+
+    #[test]
+    fn test_title_matches() {
+        assert!(title_matches(
+            "Age of Empires II: Definitive Edition",
+            "age of empires"
+        ));
+        assert!(title_matches("Age of Empires II", "Empires II"));
+        assert!(!title_matches("Age of Mythology", "Empires"));
+        assert!(!title_matches("Anything", ""));
+    }
+
+    #[test]
+    fn test_process_matches() {
+        let path = "C:\\Program Files\\Steam\\steamapps\\common\\AoE2DE\\AoE2DE_s.exe";
+
+        assert!(process_matches(path, "AoE2DE_s.exe"));
+        assert!(process_matches(path, "aoe2de_s.EXE"));
+        assert!(process_matches(path, "AoE2DE_s"));
+        assert!(!process_matches(path, "AoE2DE"));
+        assert!(!process_matches(path, "steamapps"));
+    }
+
+    #[test]
+    fn test_window_tracker_defaults() {
+        let tracker = WindowTracker::new(BarrierAnchor::WindowTitle("Age".to_string()));
+
+        assert_eq!(
+            tracker.anchor(),
+            &BarrierAnchor::WindowTitle("Age".to_string())
+        );
+        assert_eq!(tracker.poll_interval, Duration::from_millis(100));
+        assert!(tracker.tracker_thread.is_none());
+    }
+}
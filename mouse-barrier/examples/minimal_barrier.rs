@@ -0,0 +1,112 @@
+//! The smallest program that puts up a barrier: a 400x40 strip along the
+//! bottom-left of the primary monitor, kept until Escape is pressed.
+//!
+//! The hooks and overlay windows live in global state owned by the thread
+//! that created them, and Windows only delivers low-level hook events to a
+//! thread that pumps messages. So the barrier is created, enabled and dropped
+//! on this one thread, and the loop below keeps pumping messages and calling
+//! `process_hook_requests` for as long as it should stay up.
+//!
+//! Run it with `cargo run -p mouse-barrier --example minimal_barrier`.
+
+#[cfg(windows)]
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use mouse_barrier::{
+        BarrierBounds, BufferZone, BuiltinSound, CoordinateOrigin, EnforcementMode, EntryCue,
+        HighContrastStyle, KeyboardHook, MouseBarrier, MouseBarrierConfig, OffscreenPush,
+        OverlayAnimation, OverlayCoverage, OverlayFailurePolicy, Sound,
+    };
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use winapi::um::winuser::{
+        DispatchMessageW, PeekMessageW, TranslateMessage, PM_REMOVE, VK_ESCAPE, WM_QUIT,
+    };
+
+    let config = MouseBarrierConfig {
+        // Bottom-left corner at (0, 1080), growing right and up
+        bounds: BarrierBounds::CornerAndSize {
+            x: 0,
+            y: 1080,
+            width: 400,
+            height: 40,
+        },
+        buffer_zone: BufferZone::uniform(20),
+        buffer_blocks: true,
+        push_factor: 50,
+        dynamic_push: true,
+        offscreen_push: OffscreenPush::ClampToEdge,
+        screen_margin: 0,
+        approach_zone: None,
+        overlay_enabled: true,
+        overlay_color: (255, 0, 0),
+        proximity_coloring: false,
+        proximity_far_color: (0, 255, 0),
+        proximity_range: 200,
+        buffer_alpha: 200,
+        overlay_coverage: OverlayCoverage::BufferOnly,
+        overlay_failure: OverlayFailurePolicy::ContinueInvisible,
+        interior_alpha: 100,
+        flash_alpha: 255,
+        overlay_animation: OverlayAnimation::Steady,
+        overlay_tick_ms: 50,
+        on_barrier_hit_sound: Some(Sound::Builtin(BuiltinSound::Tick)),
+        on_barrier_entry_sound: None,
+        entry_cue: EntryCue::Barrier,
+        interrupt_previous_sound: true,
+        coordinate_origin: CoordinateOrigin::BottomLeft,
+        snap_grid: None,
+        consume_blocked_events: true,
+        resync_after_block: false,
+        enforcement_mode: EnforcementMode::Enforce,
+        bypass_hold_ms: 0,
+        bypass_modifier: None,
+        clamp_to_screen: true,
+        edit_mode: None,
+        high_contrast: HighContrastStyle::default(),
+    };
+
+    let mut barrier = MouseBarrier::new(config);
+    barrier.enable()?;
+    println!("Barrier up; press Escape to quit");
+
+    // The keyboard hook calls back on this thread, from inside the message
+    // loop; it only flags the request and the loop acts on it
+    let quit = Arc::new(AtomicBool::new(false));
+    let escape_pressed = Arc::clone(&quit);
+    let subscription = KeyboardHook::subscribe(move |vk, is_down| {
+        if is_down && vk == VK_ESCAPE as u32 {
+            escape_pressed.store(true, Ordering::Relaxed);
+        }
+    })?;
+
+    while !quit.load(Ordering::Relaxed) {
+        // Reinstalls hooks the watchdog found dropped, among other requests
+        // that must be handled on this thread
+        mouse_barrier::process_hook_requests();
+
+        unsafe {
+            let mut msg = std::mem::zeroed();
+            if PeekMessageW(&mut msg, std::ptr::null_mut(), 0, 0, PM_REMOVE) > 0 {
+                if msg.message == WM_QUIT {
+                    break;
+                }
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            } else {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+
+    KeyboardHook::unsubscribe(subscription)?;
+    // Dropping the barrier removes its hook and overlay windows
+    drop(barrier);
+    println!("Barrier down");
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("minimal_barrier needs Windows: the barrier is built on its mouse hooks");
+}
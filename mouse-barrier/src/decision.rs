@@ -0,0 +1,130 @@
+use crate::engine::Point;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+// Decisions the main loop can fall behind by before the oldest are dropped;
+// a few seconds of 1000Hz input
+const DECISION_QUEUE_CAPACITY: usize = 4096;
+
+/// Which branch of the enforcement pipeline handled a cursor move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecisionKind {
+    /// The move was delivered untouched
+    PassThrough,
+    /// The path from the last position crossed the barrier, so the cursor was
+    /// stopped before the buffer zone
+    TrajectoryBlock,
+    /// The cursor landed in the buffer zone or barrier and was pushed out
+    BufferPush,
+    /// The move headed into the approach zone and was slowed down
+    ApproachSlowdown,
+    /// An earlier failed cursor move left the cursor inside the barrier, so
+    /// it was pushed out on this move
+    CorrectivePush,
+}
+
+/// One enforcement decision of the mouse hook, for reconstructing what the
+/// barrier did with a cursor move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BarrierDecision {
+    /// Where the move was heading
+    pub input: Point,
+    /// The previous position the hook saw, if any
+    pub last: Option<Point>,
+    pub kind: DecisionKind,
+    /// Where the hook put the cursor; `input` for a pass-through
+    pub output: Point,
+    pub timestamp: Instant,
+}
+
+/// Decisions recorded by the hook and waiting to be delivered from the main
+/// loop. When full, the oldest are dropped and counted.
+pub(crate) struct DecisionQueue {
+    decisions: VecDeque<BarrierDecision>,
+    dropped: u64,
+}
+
+impl DecisionQueue {
+    pub(crate) const fn new() -> Self {
+        Self {
+            decisions: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, decision: BarrierDecision) {
+        if self.decisions.len() >= DECISION_QUEUE_CAPACITY {
+            self.decisions.pop_front();
+            self.dropped += 1;
+        }
+        self.decisions.push_back(decision);
+    }
+
+    /// Takes the queued decisions, oldest first, along with how many were
+    /// dropped since the last call
+    pub(crate) fn drain(&mut self) -> (Vec<BarrierDecision>, u64) {
+        let decisions = self.decisions.drain(..).collect();
+        (decisions, std::mem::take(&mut self.dropped))
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.decisions.clear();
+        self.dropped = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decision(x: i32) -> BarrierDecision {
+        BarrierDecision {
+            input: Point { x, y: 0 },
+            last: None,
+            kind: DecisionKind::PassThrough,
+            output: Point { x, y: 0 },
+            timestamp: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_queue_drains_in_order() {
+        let mut queue = DecisionQueue::new();
+        queue.push(decision(1));
+        queue.push(decision(2));
+
+        let (decisions, dropped) = queue.drain();
+        let inputs: Vec<i32> = decisions.iter().map(|d| d.input.x).collect();
+        assert_eq!(inputs, vec![1, 2]);
+        assert_eq!(dropped, 0);
+        assert!(queue.drain().0.is_empty());
+    }
+
+    #[test]
+    fn test_full_queue_drops_the_oldest() {
+        let mut queue = DecisionQueue::new();
+        for x in 0..DECISION_QUEUE_CAPACITY as i32 + 3 {
+            queue.push(decision(x));
+        }
+
+        let (decisions, dropped) = queue.drain();
+        assert_eq!(decisions.len(), DECISION_QUEUE_CAPACITY);
+        assert_eq!(decisions[0].input.x, 3);
+        assert_eq!(dropped, 3);
+
+        // The count restarts once reported
+        queue.push(decision(0));
+        assert_eq!(queue.drain().1, 0);
+    }
+
+    #[test]
+    fn test_clear_forgets_drops() {
+        let mut queue = DecisionQueue::new();
+        for x in 0..DECISION_QUEUE_CAPACITY as i32 + 1 {
+            queue.push(decision(x));
+        }
+        queue.clear();
+
+        assert_eq!(queue.drain(), (Vec::new(), 0));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::engine::{buffer_rect, point_in_rect, Point, Rect};
+use crate::BufferZone;
+
+/// Wheel delta of one notch on a standard mouse wheel
+const WHEEL_NOTCH: i32 = 120;
+
+/// Moving and resizing the barrier with the mouse while the edit modifiers
+/// are held.
+///
+/// The hook feeds it wheel, button and move events; every change lands in
+/// `pending` for the hook thread to apply outside the hook, and the gesture
+/// remembers where the barrier ended up until the modifiers are released.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EditGesture {
+    /// Where the cursor grabbed the barrier, and where the barrier was then
+    drag: Option<(Point, Rect)>,
+    /// The button press that grabbed it was swallowed and its release is
+    /// still to come; outlives the drag when the modifiers go first
+    pressed: bool,
+    /// A change not yet applied to the barrier
+    pending: Option<Rect>,
+    /// Where this gesture last put the barrier
+    edited: Option<Rect>,
+}
+
+impl EditGesture {
+    /// Grows the barrier upward by `step` pixels per wheel notch, or shrinks
+    /// it for a negative `wheel_delta`, keeping its bottom edge and at least
+    /// one pixel of height. Only applies over the barrier or its buffer zone;
+    /// returns whether it did.
+    pub(crate) fn wheel(
+        &mut self,
+        current: Rect,
+        buffer_zone: BufferZone,
+        pos: Point,
+        wheel_delta: i32,
+        step: i32,
+    ) -> bool {
+        let rect = self.latest(current);
+        if !point_in_rect(&pos, &buffer_rect(&rect, buffer_zone)) {
+            return false;
+        }
+
+        let change = wheel_delta * step / WHEEL_NOTCH;
+        let height = (rect.bottom - rect.top + change).max(1);
+        self.set(Rect {
+            top: rect.bottom - height,
+            ..rect
+        });
+        true
+    }
+
+    /// Grabs the barrier if `pos` is on it or its buffer zone; returns whether
+    /// it did
+    pub(crate) fn press(&mut self, current: Rect, buffer_zone: BufferZone, pos: Point) -> bool {
+        let rect = self.latest(current);
+        if !point_in_rect(&pos, &buffer_rect(&rect, buffer_zone)) {
+            return false;
+        }
+        self.drag = Some((pos, rect));
+        self.pressed = true;
+        true
+    }
+
+    /// Moves a grabbed barrier along with the cursor; returns whether it moved
+    pub(crate) fn drag_to(&mut self, pos: Point) -> bool {
+        let Some((grab, start)) = self.drag else {
+            return false;
+        };
+        let (dx, dy) = (pos.x - grab.x, pos.y - grab.y);
+        self.set(Rect {
+            left: start.left + dx,
+            top: start.top + dy,
+            right: start.right + dx,
+            bottom: start.bottom + dy,
+        });
+        true
+    }
+
+    /// Lets go of a grabbed barrier; returns whether the press being released
+    /// was the one that grabbed it, even if the gesture has ended since, so
+    /// the release is swallowed along with its press
+    pub(crate) fn release(&mut self) -> bool {
+        self.drag = None;
+        std::mem::take(&mut self.pressed)
+    }
+
+    /// Whether there is anything left to apply or report
+    pub(crate) fn is_active(&self) -> bool {
+        self.drag.is_some() || self.pending.is_some() || self.edited.is_some()
+    }
+
+    /// The change made since the last call, if any
+    pub(crate) fn take_pending(&mut self) -> Option<Rect> {
+        self.pending.take()
+    }
+
+    /// Ends the gesture once the modifiers are released; returns where the
+    /// barrier ended up if the gesture changed it
+    pub(crate) fn finish(&mut self) -> Option<Rect> {
+        self.drag = None;
+        self.edited.take()
+    }
+
+    fn latest(&self, current: Rect) -> Rect {
+        self.edited.unwrap_or(current)
+    }
+
+    fn set(&mut self, rect: Rect) {
+        self.pending = Some(rect);
+        self.edited = Some(rect);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Barrier (100,200)-(400,350) with a 20px buffer zone
+    const BARRIER: Rect = Rect {
+        left: 100,
+        top: 200,
+        right: 400,
+        bottom: 350,
+    };
+    const BUFFER: BufferZone = BufferZone::uniform(20);
+
+    #[test]
+    fn test_wheel_resizes_upward_over_the_barrier_only() {
+        let mut gesture = EditGesture::default();
+
+        assert!(!gesture.wheel(BARRIER, BUFFER, Point { x: 50, y: 275 }, 120, 10));
+        assert!(!gesture.is_active());
+
+        // Over the buffer zone, two notches up
+        assert!(gesture.wheel(BARRIER, BUFFER, Point { x: 90, y: 275 }, 240, 10));
+        let grown = Rect {
+            top: 180,
+            ..BARRIER
+        };
+        assert_eq!(gesture.take_pending(), Some(grown));
+        assert_eq!(gesture.take_pending(), None);
+
+        // Builds on the unapplied change, and never collapses the barrier
+        assert!(gesture.wheel(BARRIER, BUFFER, Point { x: 200, y: 275 }, -120 * 50, 10));
+        let shrunk = Rect {
+            top: 349,
+            ..BARRIER
+        };
+        assert_eq!(gesture.finish(), Some(shrunk));
+        // Still applied after the gesture ends
+        assert!(gesture.is_active());
+        assert_eq!(gesture.take_pending(), Some(shrunk));
+        assert!(!gesture.is_active());
+    }
+
+    #[test]
+    fn test_drag_moves_the_grabbed_barrier() {
+        let mut gesture = EditGesture::default();
+        assert!(!gesture.drag_to(Point { x: 10, y: 10 }));
+        assert!(!gesture.press(BARRIER, BUFFER, Point { x: 500, y: 275 }));
+
+        assert!(gesture.press(BARRIER, BUFFER, Point { x: 150, y: 250 }));
+        assert!(gesture.drag_to(Point { x: 160, y: 230 }));
+        assert!(gesture.drag_to(Point { x: 170, y: 240 }));
+        let moved = Rect {
+            left: 120,
+            top: 190,
+            right: 420,
+            bottom: 340,
+        };
+        assert_eq!(gesture.take_pending(), Some(moved));
+
+        assert!(gesture.release());
+        assert!(!gesture.release());
+        assert!(!gesture.drag_to(Point { x: 0, y: 0 }));
+        assert_eq!(gesture.finish(), Some(moved));
+    }
+
+    #[test]
+    fn test_release_matches_only_the_grabbing_press() {
+        let mut gesture = EditGesture::default();
+        // Pressed before the gesture began, so its release isn't ours
+        assert!(!gesture.release());
+
+        // The modifiers came up before the button
+        assert!(gesture.press(BARRIER, BUFFER, Point { x: 150, y: 250 }));
+        assert_eq!(gesture.finish(), None);
+        assert!(gesture.release());
+        assert!(!gesture.release());
+    }
+
+    #[test]
+    fn test_finish_without_changes_reports_nothing() {
+        let mut gesture = EditGesture::default();
+        assert!(gesture.press(BARRIER, BUFFER, Point { x: 150, y: 250 }));
+        assert!(gesture.is_active());
+
+        assert_eq!(gesture.finish(), None);
+        assert!(!gesture.is_active());
+    }
+}
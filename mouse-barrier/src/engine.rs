@@ -0,0 +1,2085 @@
+use crate::decision::{BarrierDecision, DecisionKind};
+use crate::overlay::distance_to_rect;
+use crate::speed::{CursorSpeed, SpeedTracker};
+use crate::{ApproachZone, BufferZone, EnforcementMode, EntryCue, OffscreenPush};
+use std::time::Instant;
+
+/// A screen position in the coordinates the mouse hook reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A screen rectangle; `right` and `bottom` are exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+/// Logical (DPI-scaled) and physical screen size, for placing overlays and
+/// converting push targets to `SetCursorPos` coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct ScreenMetrics {
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+    pub(crate) physical_width: i32,
+    pub(crate) physical_height: i32,
+    /// Pixels pushed positions keep clear of every screen edge
+    pub(crate) margin: i32,
+}
+
+/// Where the cursor is relative to the barrier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Zone {
+    /// Clear of the barrier and its buffer zone
+    #[default]
+    Outside,
+    /// In the buffer zone surrounding the barrier
+    Buffer,
+    /// Inside the barrier itself
+    Barrier,
+}
+
+/// Side of the buffer zone a push moved the cursor out through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushEdge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// The most recent cursor move issued by the mouse hook, for debugging pushes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LastPush {
+    /// Where the cursor was heading, in screen coordinates
+    pub from: Point,
+    /// Where the hook put it instead
+    pub to: Point,
+    /// The side the cursor ended up on, or `None` if the screen edge kept it
+    /// from clearing the buffer zone
+    pub edge: Option<PushEdge>,
+    /// Pixels travelled since the previous mouse event
+    pub speed: f64,
+    /// Factor the push distance was scaled by for that speed
+    pub multiplier: f64,
+    pub timestamp: Instant,
+}
+
+/// A sound cue the hook should play, if one is configured for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundKind {
+    /// The cursor reached the buffer zone from outside it
+    BarrierHit,
+    /// The cursor made the crossing the `EntryCue` names
+    BarrierEntry,
+}
+
+/// One step the mouse hook carries out in response to a cursor move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum EngineAction {
+    /// Deliver the event untouched
+    PassThrough,
+    /// Move the cursor to `push.to`, recording `push` once the move succeeds
+    MoveCursorTo(LastPush),
+    /// Move the cursor to `to` instead of `from`, slowing a move through the
+    /// approach zone; not a push, so nothing is recorded
+    SlowCursorTo {
+        from: Point,
+        to: Point,
+    },
+    /// The cursor moved into a different zone
+    ZoneChanged {
+        from: Zone,
+        to: Zone,
+    },
+    PlaySound(SoundKind),
+    /// Warn-only mode: the cursor would have been moved as `push` describes,
+    /// but the event is delivered untouched
+    WouldPush(LastPush),
+}
+
+/// The geometry behind one cursor move, as the engine sees it before handling
+/// the move; lets the hook log why a move was redirected
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct MoveContext {
+    pub(crate) barrier: Rect,
+    pub(crate) buffer: Rect,
+    pub(crate) last: Option<Point>,
+    pub(crate) current: Point,
+    pub(crate) zone: Zone,
+    /// Push distance scaled for the speed of the move
+    pub(crate) push_factor: i32,
+}
+
+/// The mouse hook's enforcement pipeline, kept apart from the hook ABI so it
+/// can be driven with synthetic movement traces.
+///
+/// It owns everything the hook remembers between events: the previous cursor
+/// position for the trajectory check, the zone for sound cues, whether a
+/// failed move left a corrective push pending, and what it decided last.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BarrierEngine {
+    barrier: Rect,
+    buffer_zone: BufferZone,
+    push_factor: i32,
+    offscreen_push: OffscreenPush,
+    screen: ScreenMetrics,
+    entry_cue: EntryCue,
+    /// The buffer zone only warns; the cursor is kept out of the barrier itself
+    warning_buffer: bool,
+    /// Pushes always cover `push_factor`, however fast the cursor moved
+    constant_push: bool,
+    /// Pushes are reported as `WouldPush` and the cursor is never moved
+    warn_only: bool,
+    approach_zone: Option<ApproachZone>,
+    /// The fractions of a pixel slowed moves have lost to rounding, carried
+    /// into the next one so a crawl still gets somewhere
+    approach_carry: (f64, f64),
+    last_pos: Option<Point>,
+    zone: Zone,
+    corrective_push_pending: bool,
+    last_decision: Option<BarrierDecision>,
+    speed: SpeedTracker,
+}
+
+impl BarrierEngine {
+    /// Applies new geometry without forgetting where the cursor was.
+    pub(crate) fn configure(
+        &mut self,
+        barrier: Rect,
+        buffer_zone: BufferZone,
+        push_factor: i32,
+        offscreen_push: OffscreenPush,
+        screen: ScreenMetrics,
+    ) {
+        self.barrier = barrier;
+        self.buffer_zone = buffer_zone;
+        self.push_factor = push_factor;
+        self.offscreen_push = offscreen_push;
+        self.screen = screen;
+    }
+
+    /// Chooses which crossing plays the entry sound.
+    pub(crate) fn set_entry_cue(&mut self, entry_cue: EntryCue) {
+        self.entry_cue = entry_cue;
+    }
+
+    /// Chooses whether the cursor is kept out of the buffer zone (`true`) or
+    /// only out of the barrier, with the buffer zone left to sounds and color.
+    pub(crate) fn set_buffer_blocks(&mut self, buffer_blocks: bool) {
+        self.warning_buffer = !buffer_blocks;
+    }
+
+    /// Chooses whether pushes grow with the speed of the move (`true`) or are
+    /// always `push_factor` long.
+    pub(crate) fn set_dynamic_push(&mut self, dynamic_push: bool) {
+        self.constant_push = !dynamic_push;
+    }
+
+    /// Chooses whether pushes move the cursor or are only reported.
+    pub(crate) fn set_enforcement_mode(&mut self, mode: EnforcementMode) {
+        self.warn_only = mode == EnforcementMode::WarnOnly;
+    }
+
+    /// Chooses whether moves towards the blocked area slow down in front of it.
+    pub(crate) fn set_approach_zone(&mut self, approach_zone: Option<ApproachZone>) {
+        self.approach_zone = approach_zone;
+    }
+
+    /// Forgets the cursor history, so a re-enabled barrier starts fresh.
+    pub(crate) fn reset(&mut self) {
+        self.last_pos = None;
+        self.approach_carry = (0.0, 0.0);
+        self.zone = Zone::Outside;
+        self.corrective_push_pending = false;
+        self.last_decision = None;
+        self.speed.pause();
+    }
+
+    /// How fast the moves handed to [`Self::on_mouse_move`] were
+    pub(crate) fn cursor_speed(&self) -> CursorSpeed {
+        self.speed.speed()
+    }
+
+    /// What the last call to [`Self::on_mouse_move`] decided
+    pub(crate) fn last_decision(&self) -> Option<BarrierDecision> {
+        self.last_decision
+    }
+
+    /// Describes a move to `pos` without handling it; call before
+    /// [`Self::on_mouse_move`].
+    pub(crate) fn move_context(&self, pos: Point) -> MoveContext {
+        let buffer = buffer_rect(&self.barrier, self.buffer_zone);
+        MoveContext {
+            barrier: self.barrier,
+            buffer,
+            last: self.last_pos,
+            current: pos,
+            zone: classify_zone(&pos, &self.barrier, &buffer),
+            push_factor: self.push_factor_for(self.last_pos.as_ref(), &pos),
+        }
+    }
+
+    /// Where a move from `last` to `pos` would put the cursor, or `None` if it
+    /// would be let through.
+    ///
+    /// Runs the same decision as [`Self::on_mouse_move`] against the current
+    /// geometry, but ignores and changes none of the engine's history, so it
+    /// also skips a corrective push left pending by a failed move and the
+    /// approach zone slowdown.
+    pub(crate) fn preview(&self, pos: Point, last: Option<Point>) -> Option<Point> {
+        let blocked = self.blocked_rect(&buffer_rect(&self.barrier, self.buffer_zone));
+        match decide_move(
+            last.as_ref(),
+            &pos,
+            &self.barrier,
+            &blocked,
+            self.push_factor_for(last.as_ref(), &pos),
+            &self.screen,
+            self.offscreen_push,
+        ) {
+            MoveDecision::Allow => None,
+            MoveDecision::StopBeforeBarrier(target) | MoveDecision::PushOut(target) => Some(target),
+        }
+    }
+
+    /// How far a move from `last` to `current` is pushed: faster movement gets
+    /// pushed further out, unless pushes are constant
+    fn push_factor_for(&self, last: Option<&Point>, current: &Point) -> i32 {
+        match last {
+            Some(last) if !self.constant_push => {
+                calculate_dynamic_push_factor(self.push_factor, last, current)
+            }
+            _ => self.push_factor,
+        }
+    }
+
+    /// Where a move from `last` to `pos` that the barrier lets through ends up
+    /// once slowed by the approach zone, or `None` if it isn't slowed.
+    fn slow_approach(&mut self, last: Point, pos: Point, blocked: &Rect) -> Option<Point> {
+        let slowed = self.approach_zone.and_then(|zone| {
+            let distance = distance_to_rect(pos, blocked);
+            // Only moves closing in on the blocked area are slowed
+            if distance >= zone.distance || distance >= distance_to_rect(last, blocked) {
+                return None;
+            }
+            let scale = approach_scale(zone, distance);
+            let (carry_x, carry_y) = self.approach_carry;
+            let dx = (pos.x - last.x) as f64 * scale + carry_x;
+            let dy = (pos.y - last.y) as f64 * scale + carry_y;
+            self.approach_carry = (dx.fract(), dy.fract());
+            Some(Point {
+                x: last.x + dx.trunc() as i32,
+                y: last.y + dy.trunc() as i32,
+            })
+        });
+        if slowed.is_none() {
+            self.approach_carry = (0.0, 0.0);
+        }
+        slowed
+    }
+
+    /// Turns one cursor move into the actions the hook must carry out, in
+    /// order. The last action is always `PassThrough`, `MoveCursorTo`,
+    /// `SlowCursorTo` or, in warn-only mode, `WouldPush`.
+    pub(crate) fn on_mouse_move(&mut self, pos: Point, now: Instant) -> Vec<EngineAction> {
+        let last = self.last_pos.replace(pos);
+        let buffer = buffer_rect(&self.barrier, self.buffer_zone);
+        let blocked = self.blocked_rect(&buffer);
+        let speed = last.map_or(0.0, |last| movement_speed(&last, &pos));
+
+        // A previous push failed while the cursor was inside the barrier, so
+        // push out now instead of trusting the buffer transition state
+        if std::mem::take(&mut self.corrective_push_pending)
+            && !self.warn_only
+            && point_in_rect(&pos, &blocked)
+        {
+            let target = push_point_out_of_rect(
+                &pos,
+                &self.barrier,
+                &blocked,
+                self.push_factor,
+                &self.screen,
+                self.offscreen_push,
+            );
+            self.record_decision(pos, last, DecisionKind::CorrectivePush, target, now);
+            return vec![move_cursor_action(pos, target, &blocked, speed, 1.0, now)];
+        }
+
+        let decision = decide_move(
+            last.as_ref(),
+            &pos,
+            &self.barrier,
+            &blocked,
+            self.push_factor_for(last.as_ref(), &pos),
+            &self.screen,
+            self.offscreen_push,
+        );
+        if last.is_some() {
+            // Moves the barrier redirects reached it even if they'd end beyond it
+            let approaching = !matches!(decision, MoveDecision::Allow)
+                || classify_zone(&pos, &self.barrier, &buffer) != Zone::Outside;
+            self.speed.record(speed, approaching, now);
+        }
+        // Only a move the barrier lets through is slowed; one crossing or
+        // landing in the blocked area was handled at full speed above. Warn-only
+        // mode never changes where the cursor goes
+        let slowed = match (decision, last) {
+            (MoveDecision::Allow, Some(last)) if !self.warn_only => {
+                self.slow_approach(last, pos, &blocked)
+            }
+            _ => None,
+        };
+        let landing = slowed.unwrap_or(pos);
+        let mut actions = Vec::new();
+
+        // A move stopped short of the barrier never lands where it was
+        // heading, so it doesn't count as a zone transition, unless warn-only
+        // mode lets it land after all
+        if self.warn_only || !matches!(decision, MoveDecision::StopBeforeBarrier(_)) {
+            // Zones and sounds follow the buffer zone even when it doesn't block
+            let zone = classify_zone(&landing, &self.barrier, &buffer);
+            let previous = std::mem::replace(&mut self.zone, zone);
+            if previous != zone {
+                actions.push(EngineAction::ZoneChanged {
+                    from: previous,
+                    to: zone,
+                });
+
+                // Play barrier entry sound on the configured crossing
+                let entered = match self.entry_cue {
+                    EntryCue::Barrier => zone == Zone::Barrier,
+                    EntryCue::BufferZone => previous == Zone::Outside,
+                };
+                if entered {
+                    actions.push(EngineAction::PlaySound(SoundKind::BarrierEntry));
+                }
+
+                // Play barrier hit sound when arriving from outside the buffer zone
+                if previous == Zone::Outside {
+                    actions.push(EngineAction::PlaySound(SoundKind::BarrierHit));
+                }
+            }
+        }
+
+        let action = match decision {
+            MoveDecision::Allow => match slowed {
+                Some(target) => {
+                    // The next move starts from where the cursor really is
+                    self.last_pos = Some(target);
+                    self.record_decision(pos, last, DecisionKind::ApproachSlowdown, target, now);
+                    EngineAction::SlowCursorTo {
+                        from: pos,
+                        to: target,
+                    }
+                }
+                None => {
+                    self.record_decision(pos, last, DecisionKind::PassThrough, pos, now);
+                    EngineAction::PassThrough
+                }
+            },
+            MoveDecision::StopBeforeBarrier(target) => {
+                self.record_decision(pos, last, DecisionKind::TrajectoryBlock, target, now);
+                move_cursor_action(pos, target, &blocked, speed, 1.0, now)
+            }
+            MoveDecision::PushOut(target) => {
+                self.record_decision(pos, last, DecisionKind::BufferPush, target, now);
+                // Only a move with a previous position had its push scaled
+                let multiplier = if last.is_some() && !self.constant_push {
+                    push_multiplier(speed)
+                } else {
+                    1.0
+                };
+                move_cursor_action(pos, target, &blocked, speed, multiplier, now)
+            }
+        };
+        actions.push(match action {
+            EngineAction::MoveCursorTo(push) if self.warn_only => EngineAction::WouldPush(push),
+            action => action,
+        });
+        actions
+    }
+
+    /// The area the cursor is kept out of: `buffer`, or just the barrier when
+    /// the buffer zone only warns
+    fn blocked_rect(&self, buffer: &Rect) -> Rect {
+        if self.warning_buffer {
+            self.barrier
+        } else {
+            *buffer
+        }
+    }
+
+    fn record_decision(
+        &mut self,
+        input: Point,
+        last: Option<Point>,
+        kind: DecisionKind,
+        output: Point,
+        timestamp: Instant,
+    ) {
+        self.last_decision = Some(BarrierDecision {
+            input,
+            last,
+            kind,
+            output,
+            timestamp,
+        });
+    }
+
+    /// Takes back a slowed move whose cursor ended up at `pos` after all, so
+    /// the next move is measured from there.
+    pub(crate) fn on_slowdown_undone(&mut self, pos: Point) {
+        self.last_pos = Some(pos);
+        self.approach_carry = (0.0, 0.0);
+    }
+
+    /// Arms a push on the next move if a failed `SetCursorPos` left the cursor
+    /// inside the barrier.
+    pub(crate) fn on_move_failed(&mut self, pos: Point) {
+        if point_in_rect(&pos, &self.barrier) {
+            self.corrective_push_pending = true;
+        }
+    }
+}
+
+/// Where engine actions take effect: the real hook, or a simulation
+pub(crate) trait ActionSink {
+    /// Moves the cursor to `push.to`; returns false if the move failed
+    fn move_cursor(&mut self, push: &LastPush) -> bool;
+    /// Moves the cursor to `to` for a slowed move; returns false if the move
+    /// failed
+    fn slow_cursor(&mut self, to: Point) -> bool;
+    fn zone_changed(&mut self, from: Zone, to: Zone);
+    fn play_sound(&mut self, kind: SoundKind);
+    /// Counts a push that warn-only mode held back
+    fn would_push(&mut self, push: &LastPush);
+}
+
+/// Carries out the actions [`BarrierEngine::on_mouse_move`] returned, in order.
+/// Returns whether the hook should swallow the original event.
+///
+/// A push always swallows the event once the cursor has moved: a delivered
+/// event would put the cursor back where it was heading, on the far side of
+/// the barrier. `consume_blocked_events` only decides whether slowed moves are
+/// swallowed.
+pub(crate) fn carry_out(
+    engine: &mut BarrierEngine,
+    actions: Vec<EngineAction>,
+    consume_blocked_events: bool,
+    sink: &mut impl ActionSink,
+) -> bool {
+    let mut consume = false;
+    for action in actions {
+        match action {
+            EngineAction::PassThrough => {}
+            EngineAction::ZoneChanged { from, to } => sink.zone_changed(from, to),
+            EngineAction::PlaySound(kind) => sink.play_sound(kind),
+            EngineAction::WouldPush(push) => sink.would_push(&push),
+            EngineAction::MoveCursorTo(push) => {
+                let moved = sink.move_cursor(&push);
+                if !moved {
+                    engine.on_move_failed(push.from);
+                }
+                consume = should_consume(moved, true);
+            }
+            EngineAction::SlowCursorTo { from, to } => {
+                let moved = sink.slow_cursor(to);
+                consume = should_consume(moved, consume_blocked_events);
+                // A delivered event puts the cursor where it was heading
+                if !consume {
+                    engine.on_slowdown_undone(from);
+                }
+            }
+        }
+    }
+    consume
+}
+
+/// Whether the hook swallows an event whose cursor move was redirected.
+///
+/// The event is only consumed once the cursor has really been repositioned;
+/// if `SetCursorPos` failed, swallowing it would leave the cursor frozen.
+fn should_consume(moved: bool, consume_blocked_events: bool) -> bool {
+    moved && consume_blocked_events
+}
+
+fn move_cursor_action(
+    from: Point,
+    to: Point,
+    buffer: &Rect,
+    speed: f64,
+    multiplier: f64,
+    timestamp: Instant,
+) -> EngineAction {
+    EngineAction::MoveCursorTo(LastPush {
+        from,
+        to,
+        edge: push_edge(&to, buffer),
+        speed,
+        multiplier,
+        timestamp,
+    })
+}
+
+/// The barrier grown by `buffer_zone` on each side
+pub(crate) fn buffer_rect(barrier: &Rect, buffer_zone: BufferZone) -> Rect {
+    Rect {
+        left: barrier.left - buffer_zone.left,
+        top: barrier.top - buffer_zone.top,
+        right: barrier.right + buffer_zone.right,
+        bottom: barrier.bottom + buffer_zone.bottom,
+    }
+}
+
+pub(crate) fn classify_zone(point: &Point, barrier: &Rect, buffer: &Rect) -> Zone {
+    if point_in_rect(point, barrier) {
+        Zone::Barrier
+    } else if point_in_rect(point, buffer) {
+        Zone::Buffer
+    } else {
+        Zone::Outside
+    }
+}
+
+pub(crate) fn point_in_rect(point: &Point, rect: &Rect) -> bool {
+    point.x >= rect.left && point.x < rect.right && point.y >= rect.top && point.y < rect.bottom
+}
+
+fn check_movement_path(start: &Point, end: &Point, barrier: &Rect, buffer: &Rect) -> Option<Point> {
+    let samples = trajectory_samples(*start, *end);
+    for (i, check_point) in samples.iter().enumerate() {
+        // Check if this intermediate point hits the barrier
+        if point_in_rect(check_point, barrier) {
+            // Find the last safe point outside the buffer zone
+            let earlier = std::iter::once(start).chain(&samples[..i]);
+            return Some(
+                *earlier
+                    .rev()
+                    .find(|safe_point| !point_in_rect(safe_point, buffer))
+                    // If no safe point found, return start position
+                    .unwrap_or(start),
+            );
+        }
+    }
+    None
+}
+
+/// How many points along a move the trajectory check tests
+const PATH_STEPS: i32 = 10;
+
+/// The points the trajectory check tests a move from `start` to `end` at,
+/// from just past `start` to `end`; empty for a move too small to check
+pub fn trajectory_samples(start: Point, end: Point) -> Vec<Point> {
+    // Skip if movement is too small
+    let dx = end.x - start.x;
+    let dy = end.y - start.y;
+    if dx.abs() < 2 && dy.abs() < 2 {
+        return Vec::new();
+    }
+
+    (1..=PATH_STEPS)
+        .map(|i| {
+            let t = i as f32 / PATH_STEPS as f32;
+            Point {
+                x: (start.x as f32 + dx as f32 * t) as i32,
+                y: (start.y as f32 + dy as f32 * t) as i32,
+            }
+        })
+        .collect()
+}
+
+/// What the mouse hook does with a single cursor move
+#[derive(Clone, Copy)]
+enum MoveDecision {
+    /// The move is legal and is delivered untouched
+    Allow,
+    /// The path from the previous position crosses the barrier; stop at the
+    /// last point before the buffer zone
+    StopBeforeBarrier(Point),
+    /// The cursor landed inside the buffer zone or barrier; push it back out
+    PushOut(Point),
+}
+
+/// Decides how to enforce the barrier for a move from `last` to `current`.
+///
+/// Only moves that actually cross or land in the protected area are redirected.
+/// A move that is merely heading towards the barrier is let through: a
+/// low-level hook can't rewrite the event's coordinates, and the trajectory
+/// check on the next move still stops the cursor before it crosses.
+///
+/// `buffer` is the area the cursor is kept out of, which is just `barrier`
+/// when the buffer zone only warns. `push_factor` is the push distance, already
+/// scaled for the speed of the move.
+fn decide_move(
+    last: Option<&Point>,
+    current: &Point,
+    barrier: &Rect,
+    buffer: &Rect,
+    push_factor: i32,
+    screen: &ScreenMetrics,
+    offscreen: OffscreenPush,
+) -> MoveDecision {
+    if let Some(last) = last {
+        if let Some(safe_pos) = check_movement_path(last, current, barrier, buffer) {
+            return MoveDecision::StopBeforeBarrier(safe_pos);
+        }
+    }
+
+    if classify_zone(current, barrier, buffer) == Zone::Outside {
+        return MoveDecision::Allow;
+    }
+
+    MoveDecision::PushOut(push_point_out_of_rect(
+        current,
+        barrier,
+        buffer,
+        push_factor,
+        screen,
+        offscreen,
+    ))
+}
+
+/// The fraction of a move kept `distance` pixels from the blocked area:
+/// `min_scale` at its edge, rising linearly to 1 at the outer edge of `zone`
+fn approach_scale(zone: ApproachZone, distance: i32) -> f64 {
+    let min_scale = zone.min_scale as f64;
+    let t = (distance as f64 / zone.distance as f64).clamp(0.0, 1.0);
+    min_scale + (1.0 - min_scale) * t
+}
+
+fn calculate_dynamic_push_factor(base_factor: i32, last_pos: &Point, current_pos: &Point) -> i32 {
+    let multiplier = push_multiplier(movement_speed(last_pos, current_pos));
+    (base_factor as f64 * multiplier) as i32
+}
+
+fn movement_speed(last_pos: &Point, current_pos: &Point) -> f64 {
+    let dx = (current_pos.x - last_pos.x) as f64;
+    let dy = (current_pos.y - last_pos.y) as f64;
+    (dx * dx + dy * dy).sqrt()
+}
+
+fn push_multiplier(speed: f64) -> f64 {
+    // Scale push factor: faster movement = larger push
+    // Speed 10 = 1x, Speed 50 = 2x, Speed 100+ = 3x
+    (speed / 25.0).clamp(1.0, 3.0)
+}
+
+/// Which side of the buffer zone `target` lies on, if it's outside it at all.
+fn push_edge(target: &Point, buffer: &Rect) -> Option<PushEdge> {
+    if target.x < buffer.left {
+        Some(PushEdge::Left)
+    } else if target.x >= buffer.right {
+        Some(PushEdge::Right)
+    } else if target.y < buffer.top {
+        Some(PushEdge::Top)
+    } else if target.y >= buffer.bottom {
+        Some(PushEdge::Bottom)
+    } else {
+        None
+    }
+}
+
+/// Where a push straight out through `side` of `buffer` lands
+fn push_through(point: &Point, buffer: &Rect, side: PushEdge, push_factor: i32) -> Point {
+    match side {
+        PushEdge::Left => Point {
+            x: buffer.left - push_factor,
+            y: point.y,
+        },
+        PushEdge::Right => Point {
+            x: buffer.right + push_factor,
+            y: point.y,
+        },
+        PushEdge::Top => Point {
+            x: point.x,
+            y: buffer.top - push_factor,
+        },
+        PushEdge::Bottom => Point {
+            x: point.x,
+            y: buffer.bottom + push_factor,
+        },
+    }
+}
+
+/// Where a push out through `side` lands if it's sent out the opposite side instead
+fn flipped_push(point: &Point, buffer: &Rect, side: PushEdge, push_factor: i32) -> Point {
+    match side {
+        PushEdge::Left => push_through(point, buffer, PushEdge::Right, push_factor),
+        PushEdge::Right => Point {
+            x: (buffer.left - push_factor).max(0),
+            y: point.y,
+        },
+        PushEdge::Top => push_through(point, buffer, PushEdge::Bottom, push_factor),
+        PushEdge::Bottom => Point {
+            x: point.x,
+            y: (buffer.top - push_factor).max(0),
+        },
+    }
+}
+
+/// How far past the buffer a thin barrier's push lands
+const THIN_BARRIER_CLEARANCE: i32 = 2;
+
+/// How far past the buffer a push through `side` lands: `push_factor`, unless
+/// the barrier is thinner than that across `side`. Such a push would put the
+/// cursor further from the barrier than its far edge is, so it only clears
+/// the buffer instead.
+fn push_distance(barrier: &Rect, side: PushEdge, push_factor: i32) -> i32 {
+    let thickness = if is_horizontal(side) {
+        barrier.right - barrier.left
+    } else {
+        barrier.bottom - barrier.top
+    };
+    if thickness < push_factor {
+        THIN_BARRIER_CLEARANCE.min(push_factor)
+    } else {
+        push_factor
+    }
+}
+
+fn is_horizontal(side: PushEdge) -> bool {
+    matches!(side, PushEdge::Left | PushEdge::Right)
+}
+
+/// The screen margin, capped so some of the screen is always left
+fn safe_margin(screen: &ScreenMetrics) -> i32 {
+    let limit = (screen.width.min(screen.height) - 1).max(0) / 2;
+    screen.margin.clamp(0, limit)
+}
+
+/// Whether `point` lies on the screen, clear of its margin
+fn on_screen(point: &Point, screen: &ScreenMetrics) -> bool {
+    let margin = safe_margin(screen);
+    point.x >= margin
+        && point.x < screen.width - margin
+        && point.y >= margin
+        && point.y < screen.height - margin
+}
+
+fn clamp_to_screen(point: Point, screen: &ScreenMetrics) -> Point {
+    let margin = safe_margin(screen);
+    Point {
+        x: point.x.clamp(margin, screen.width - 1 - margin),
+        y: point.y.clamp(margin, screen.height - 1 - margin),
+    }
+}
+
+/// Pushes `point` out of the `buffer` around `barrier` through its nearest
+/// side, falling back to `offscreen` when that push would leave the screen.
+/// Barriers thinner than `push_factor` are only just cleared; see
+/// [`push_distance`].
+///
+/// With a screen margin, the cursor is first pulled clear of it, so a push
+/// along the edge doesn't keep the part of the position that lies in the
+/// margin, and wherever a push lands it must also clear the buffer: parked in
+/// the buffer, the next event would push again and the cursor would jump. A
+/// flip or slide that can't land clear falls back to clamping.
+fn push_point_out_of_rect(
+    point: &Point,
+    barrier: &Rect,
+    buffer: &Rect,
+    push_factor: i32,
+    screen: &ScreenMetrics,
+    offscreen: OffscreenPush,
+) -> Point {
+    let screen_width = screen.width;
+    let screen_height = screen.height;
+    let margin = safe_margin(screen);
+    // Without a margin the cursor may be on another monitor, off this screen
+    let point = &if margin > 0 {
+        clamp_to_screen(*point, screen)
+    } else {
+        *point
+    };
+    let keep_out = if margin > 0 { buffer } else { barrier };
+
+    // Sides of the buffer ordered by how close the mouse is to them; ties go
+    // left, right, top, bottom
+    let mut sides = [
+        (point.x - buffer.left, PushEdge::Left),
+        (buffer.right - point.x, PushEdge::Right),
+        (point.y - buffer.top, PushEdge::Top),
+        (buffer.bottom - point.y, PushEdge::Bottom),
+    ];
+    sides.sort_by_key(|&(distance, _)| distance);
+    let nearest = sides[0].1;
+    let push = |side| push_distance(barrier, side, push_factor);
+    let target = push_through(point, buffer, nearest, push(nearest));
+
+    // Where every policy may land: on the screen and out of `keep_out`
+    let lands =
+        |candidate: &Point| on_screen(candidate, screen) && !point_in_rect(candidate, keep_out);
+    // Stop at the screen boundary, unless the barrier runs right up to it;
+    // then use the nearest side that does leave it
+    let clamped = || {
+        sides
+            .iter()
+            .map(|&(_, side)| {
+                clamp_to_screen(push_through(point, buffer, side, push(side)), screen)
+            })
+            .find(|candidate| !point_in_rect(candidate, keep_out))
+    };
+
+    let new_point = if on_screen(&target, screen) {
+        target
+    } else {
+        // A flip or slide that can't land falls back to clamping
+        match offscreen {
+            OffscreenPush::FlipDirection => {
+                Some(flipped_push(point, buffer, nearest, push(nearest)))
+                    .filter(lands)
+                    .or_else(clamped)
+            }
+            OffscreenPush::ClampToEdge => clamped(),
+            // Leave along the blocked edge, through whichever end is closer
+            OffscreenPush::SlideAlongEdge => sides
+                .iter()
+                .filter(|&&(_, side)| is_horizontal(side) != is_horizontal(nearest))
+                .map(|&(_, side)| push_through(point, buffer, side, push(side)))
+                .find(lands)
+                .or_else(clamped),
+        }
+        .unwrap_or_else(|| clamp_to_screen(target, screen))
+    };
+
+    // Convert from physical coordinates to logical coordinates for SetCursorPos
+    // Get actual physical screen resolution instead of using hardcoded values
+    let physical_width = screen.physical_width as f64;
+    let physical_height = screen.physical_height as f64;
+    let scale_x = screen_width as f64 / physical_width;
+    let scale_y = screen_height as f64 / physical_height;
+
+    let logical_x = (new_point.x as f64 * scale_x).round() as i32;
+    let logical_y = (new_point.y as f64 * scale_y).round() as i32;
+
+    Point {
+        x: logical_x.clamp(margin, screen_width - 1 - margin),
+        y: logical_y.clamp(margin, screen_height - 1 - margin),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: ScreenMetrics = ScreenMetrics {
+        width: 1920,
+        height: 1080,
+        physical_width: 1920,
+        physical_height: 1080,
+        margin: 0,
+    };
+
+    #[test]
+    fn test_should_consume_matrix() {
+        // (cursor moved, consume_blocked_events) -> consume
+        let cases = [
+            (true, true, true),
+            (true, false, false),
+            (false, true, false),
+            (false, false, false),
+        ];
+        for (moved, consume_blocked_events, expected) in cases {
+            assert_eq!(
+                should_consume(moved, consume_blocked_events),
+                expected,
+                "moved={} consume_blocked_events={}",
+                moved,
+                consume_blocked_events
+            );
+        }
+    }
+
+    #[test]
+    fn test_classify_zone() {
+        let barrier = Rect {
+            left: 100,
+            top: 400,
+            right: 300,
+            bottom: 500,
+        };
+        let buffer = Rect {
+            left: 80,
+            top: 380,
+            right: 320,
+            bottom: 520,
+        };
+
+        assert_eq!(
+            classify_zone(&Point { x: 150, y: 450 }, &barrier, &buffer),
+            Zone::Barrier
+        );
+        assert_eq!(
+            classify_zone(&Point { x: 90, y: 450 }, &barrier, &buffer),
+            Zone::Buffer
+        );
+        assert_eq!(
+            classify_zone(&Point { x: 310, y: 510 }, &barrier, &buffer),
+            Zone::Buffer
+        );
+        assert_eq!(
+            classify_zone(&Point { x: 50, y: 450 }, &barrier, &buffer),
+            Zone::Outside
+        );
+    }
+
+    #[test]
+    fn test_point_in_rect() {
+        let rect = Rect {
+            left: 10,
+            top: 20,
+            right: 100,
+            bottom: 80,
+        };
+
+        // Point inside
+        let inside_point = Point { x: 50, y: 40 };
+        assert!(point_in_rect(&inside_point, &rect));
+
+        // Point on boundary (excluded)
+        let boundary_point = Point { x: 100, y: 40 };
+        assert!(!point_in_rect(&boundary_point, &rect));
+
+        // Point outside
+        let outside_point = Point { x: 150, y: 40 };
+        assert!(!point_in_rect(&outside_point, &rect));
+
+        // Corner cases
+        let left_edge = Point { x: 10, y: 40 };
+        assert!(point_in_rect(&left_edge, &rect));
+
+        let top_edge = Point { x: 50, y: 20 };
+        assert!(point_in_rect(&top_edge, &rect));
+    }
+
+    #[test]
+    fn test_calculate_dynamic_push_factor() {
+        let last_pos = Point { x: 0, y: 0 };
+        let base_factor = 50;
+
+        // No movement
+        let current_pos = Point { x: 0, y: 0 };
+        let result = calculate_dynamic_push_factor(base_factor, &last_pos, &current_pos);
+        assert_eq!(result, base_factor); // Should be 1x multiplier
+
+        // Slow movement (speed < 25)
+        let current_pos = Point { x: 10, y: 0 };
+        let result = calculate_dynamic_push_factor(base_factor, &last_pos, &current_pos);
+        assert_eq!(result, base_factor); // Should be 1x multiplier
+
+        // Medium movement (speed = 25)
+        let current_pos = Point { x: 25, y: 0 };
+        let result = calculate_dynamic_push_factor(base_factor, &last_pos, &current_pos);
+        assert_eq!(result, base_factor); // Should be 1x multiplier
+
+        // Fast movement (speed = 50)
+        let current_pos = Point { x: 50, y: 0 };
+        let result = calculate_dynamic_push_factor(base_factor, &last_pos, &current_pos);
+        assert_eq!(result, 100); // Should be 2x multiplier
+
+        // Very fast movement (speed = 75, should clamp to 3x)
+        let current_pos = Point { x: 75, y: 0 };
+        let result = calculate_dynamic_push_factor(base_factor, &last_pos, &current_pos);
+        assert_eq!(result, 150); // Should be 3x multiplier
+
+        // Extremely fast movement (should clamp to 3x max)
+        let current_pos = Point { x: 1000, y: 0 };
+        let result = calculate_dynamic_push_factor(base_factor, &last_pos, &current_pos);
+        assert_eq!(result, 150); // Should be clamped to 3x multiplier
+    }
+
+    #[test]
+    fn test_push_edge_reports_side_of_buffer() {
+        let buffer = Rect {
+            left: 100,
+            top: 100,
+            right: 200,
+            bottom: 200,
+        };
+        let edge = |x, y| push_edge(&Point { x, y }, &buffer);
+
+        assert_eq!(edge(99, 150), Some(PushEdge::Left));
+        assert_eq!(edge(200, 150), Some(PushEdge::Right));
+        assert_eq!(edge(150, 99), Some(PushEdge::Top));
+        assert_eq!(edge(150, 200), Some(PushEdge::Bottom));
+
+        // A push clamped at the screen edge can leave the cursor in the buffer
+        assert_eq!(edge(100, 100), None);
+        assert_eq!(edge(199, 199), None);
+    }
+
+    #[test]
+    fn test_push_multiplier_matches_dynamic_push_factor() {
+        let last_pos = Point { x: 0, y: 0 };
+        for x in [0, 10, 25, 50, 75, 1000] {
+            let current_pos = Point { x, y: 0 };
+            let multiplier = push_multiplier(movement_speed(&last_pos, &current_pos));
+            assert_eq!(
+                calculate_dynamic_push_factor(50, &last_pos, &current_pos),
+                (50.0 * multiplier) as i32
+            );
+        }
+        assert_eq!(movement_speed(&last_pos, &Point { x: 3, y: 4 }), 5.0);
+    }
+
+    #[test]
+    fn test_push_point_out_of_rect_basic() {
+        let rect = Rect {
+            left: 100,
+            top: 100,
+            right: 200,
+            bottom: 200,
+        };
+        let push_factor = 20;
+
+        // Point inside rect - should be pushed out
+        let point = Point { x: 150, y: 150 };
+        let pushed = push_point_out_of_rect(
+            &point,
+            &rect,
+            &rect,
+            push_factor,
+            &SCREEN,
+            OffscreenPush::ClampToEdge,
+        );
+
+        // The point should be moved outside the rect
+        assert!(!point_in_rect(&pushed, &rect));
+    }
+
+    /// A 5px-tall, 400px-wide strip, like a resource bar along the screen
+    const THIN_BARRIER: Rect = Rect {
+        left: 700,
+        top: 1000,
+        right: 1100,
+        bottom: 1005,
+    };
+
+    #[test]
+    fn test_thin_barrier_is_only_just_cleared() {
+        let push = |point: (i32, i32), buffer_zone: i32| {
+            let pushed = push_point_out_of_rect(
+                &Point {
+                    x: point.0,
+                    y: point.1,
+                },
+                &THIN_BARRIER,
+                &buffer_rect(&THIN_BARRIER, BufferZone::uniform(buffer_zone)),
+                50,
+                &SCREEN,
+                OffscreenPush::ClampToEdge,
+            );
+            (pushed.x, pushed.y)
+        };
+
+        // Placed just above or below, whichever is nearer, not 50px away
+        assert_eq!(push((900, 1001), 0), (900, 998));
+        assert_eq!(push((900, 1004), 0), (900, 1007));
+        assert_eq!(push((900, 1001), 10), (900, 988));
+        assert_eq!(push((900, 1004), 10), (900, 1017));
+
+        // The strip is wide, so pushes out of its ends keep their full length
+        assert_eq!(push((701, 1002), 0), (650, 1002));
+    }
+
+    #[test]
+    fn test_thin_barrier_holds_a_cursor_pressing_into_it() {
+        let mut engine = BarrierEngine::default();
+        engine.configure(
+            THIN_BARRIER,
+            BufferZone::uniform(0),
+            50,
+            OffscreenPush::ClampToEdge,
+            SCREEN,
+        );
+
+        // Keep nudging the cursor down into the strip from wherever it was put
+        let mut pos = Point { x: 900, y: 990 };
+        for _ in 0..20 {
+            pos.y += 3;
+            let now = Instant::now();
+            for action in engine.on_mouse_move(pos, now) {
+                if let EngineAction::MoveCursorTo(push) = action {
+                    pos = push.to;
+                }
+            }
+            assert!(
+                (990..THIN_BARRIER.top).contains(&pos.y),
+                "cursor left at {:?}",
+                pos
+            );
+        }
+    }
+
+    /// Pushes `point` out of a 10px buffer around `barrier` with a 20px push
+    fn offscreen_push(point: (i32, i32), barrier: Rect, offscreen: OffscreenPush) -> (i32, i32) {
+        let pushed = push_point_out_of_rect(
+            &Point {
+                x: point.0,
+                y: point.1,
+            },
+            &barrier,
+            &buffer_rect(&barrier, BufferZone::uniform(10)),
+            20,
+            &SCREEN,
+            offscreen,
+        );
+        (pushed.x, pushed.y)
+    }
+
+    #[test]
+    fn test_offscreen_push_for_barriers_flush_with_the_screen() {
+        let rect = |left, top| Rect {
+            left,
+            top,
+            right: left + 100,
+            bottom: top + 100,
+        };
+        // (placement, barrier, cursor just inside the flush side,
+        //  FlipDirection, ClampToEdge, SlideAlongEdge)
+        let cases = [
+            (
+                "left",
+                rect(0, 490),
+                (5, 540),
+                (130, 540),
+                (5, 460),
+                (5, 460),
+            ),
+            (
+                "right",
+                rect(1820, 490),
+                (1914, 540),
+                (1790, 540),
+                (1914, 460),
+                (1914, 460),
+            ),
+            (
+                "top",
+                rect(910, 0),
+                (960, 5),
+                (960, 130),
+                (880, 5),
+                (880, 5),
+            ),
+            (
+                "bottom",
+                rect(910, 980),
+                (960, 1075),
+                (960, 950),
+                (880, 1075),
+                (880, 1075),
+            ),
+            ("top-left", rect(0, 0), (5, 8), (130, 8), (5, 130), (5, 130)),
+            (
+                "top-right",
+                rect(1820, 0),
+                (1914, 8),
+                (1790, 8),
+                (1914, 130),
+                (1914, 130),
+            ),
+            (
+                "bottom-left",
+                rect(0, 980),
+                (5, 1072),
+                (130, 1072),
+                (5, 950),
+                (5, 950),
+            ),
+            (
+                "bottom-right",
+                rect(1820, 980),
+                (1914, 1072),
+                (1790, 1072),
+                (1914, 950),
+                (1914, 950),
+            ),
+        ];
+
+        for (placement, barrier, cursor, flip, clamp, slide) in cases {
+            let expected = [
+                (OffscreenPush::FlipDirection, flip),
+                (OffscreenPush::ClampToEdge, clamp),
+                (OffscreenPush::SlideAlongEdge, slide),
+            ];
+            for (offscreen, expected) in expected {
+                let pushed = offscreen_push(cursor, barrier, offscreen);
+                assert_eq!(pushed, expected, "{} {:?}", placement, offscreen);
+
+                let pushed = Point {
+                    x: pushed.0,
+                    y: pushed.1,
+                };
+                assert!(on_screen(&pushed, &SCREEN), "{} {:?}", placement, offscreen);
+                assert!(
+                    !point_in_rect(&pushed, &barrier),
+                    "{} {:?}",
+                    placement,
+                    offscreen
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_offscreen_push_when_only_the_buffer_leaves_the_screen() {
+        // The barrier stops 5px short of the left edge, so its buffer runs off it
+        let barrier = Rect {
+            left: 5,
+            top: 490,
+            right: 105,
+            bottom: 590,
+        };
+
+        assert_eq!(
+            offscreen_push((8, 540), barrier, OffscreenPush::FlipDirection),
+            (135, 540)
+        );
+        // Parked on the screen edge, still in the buffer but clear of the barrier
+        assert_eq!(
+            offscreen_push((8, 540), barrier, OffscreenPush::ClampToEdge),
+            (0, 540)
+        );
+        assert_eq!(
+            offscreen_push((8, 540), barrier, OffscreenPush::SlideAlongEdge),
+            (8, 460)
+        );
+
+        // Pushes that stay on screen ignore the setting
+        for offscreen in [
+            OffscreenPush::FlipDirection,
+            OffscreenPush::ClampToEdge,
+            OffscreenPush::SlideAlongEdge,
+        ] {
+            assert_eq!(offscreen_push((100, 540), barrier, offscreen), (135, 540));
+        }
+    }
+
+    #[test]
+    fn test_screen_margin_keeps_pushes_clear_of_edge_hugging_barriers() {
+        let screen = ScreenMetrics {
+            margin: 10,
+            ..SCREEN
+        };
+        let rect = |left, top| Rect {
+            left,
+            top,
+            right: left + 100,
+            bottom: top + 100,
+        };
+        // Flush with each edge and corner, and 5px short of the left edge
+        let barriers = [
+            rect(0, 490),
+            rect(1820, 490),
+            rect(910, 0),
+            rect(910, 980),
+            rect(0, 0),
+            rect(1820, 0),
+            rect(0, 980),
+            rect(1820, 980),
+            rect(5, 490),
+        ];
+        let policies = [
+            OffscreenPush::FlipDirection,
+            OffscreenPush::ClampToEdge,
+            OffscreenPush::SlideAlongEdge,
+        ];
+
+        for barrier in barriers {
+            let buffer = buffer_rect(&barrier, BufferZone::uniform(10));
+            for x in (buffer.left.max(0)..buffer.right.min(1920)).step_by(3) {
+                for y in (buffer.top.max(0)..buffer.bottom.min(1080)).step_by(3) {
+                    for offscreen in policies {
+                        let point = Point { x, y };
+                        let pushed = push_point_out_of_rect(
+                            &point, &barrier, &buffer, 20, &screen, offscreen,
+                        );
+                        let context = (barrier, point, offscreen);
+                        assert!(on_screen(&pushed, &screen), "{:?} -> {:?}", context, pushed);
+                        // Parked in the buffer, the next event would push again
+                        assert!(
+                            !point_in_rect(&pushed, &buffer),
+                            "{:?} -> {:?}",
+                            context,
+                            pushed
+                        );
+                    }
+                }
+            }
+        }
+
+        // Parking on the margin would leave the cursor in the buffer, so it
+        // goes out the top instead
+        let barrier = rect(5, 490);
+        let pushed = push_point_out_of_rect(
+            &Point { x: 8, y: 540 },
+            &barrier,
+            &buffer_rect(&barrier, BufferZone::uniform(10)),
+            20,
+            &screen,
+            OffscreenPush::ClampToEdge,
+        );
+        assert_eq!(pushed, Point { x: 10, y: 460 });
+    }
+
+    #[test]
+    fn test_flips_and_slides_that_cant_land_clear_fall_back_to_clamping() {
+        let screen = ScreenMetrics {
+            margin: 10,
+            ..SCREEN
+        };
+        // Flipping out of a band across the screen goes off the far edge, and
+        // sliding along a strip down the screen goes off the top and bottom;
+        // both would park the cursor on the margin, in the buffer
+        let band = Rect {
+            left: 0,
+            top: 490,
+            right: 1920,
+            bottom: 590,
+        };
+        let strip = Rect {
+            left: 0,
+            top: 0,
+            right: 100,
+            bottom: 1080,
+        };
+        let cases = [
+            (band, OffscreenPush::FlipDirection, Point { x: 12, y: 460 }),
+            (
+                strip,
+                OffscreenPush::SlideAlongEdge,
+                Point { x: 130, y: 540 },
+            ),
+        ];
+
+        for (barrier, offscreen, expected) in cases {
+            let pushed = push_point_out_of_rect(
+                &Point { x: 12, y: 540 },
+                &barrier,
+                &buffer_rect(&barrier, BufferZone::uniform(10)),
+                20,
+                &screen,
+                offscreen,
+            );
+            assert_eq!(pushed, expected, "{:?}", offscreen);
+        }
+    }
+
+    #[test]
+    fn test_check_movement_path_no_collision() {
+        let start = Point { x: 50, y: 50 };
+        let end = Point { x: 60, y: 50 };
+        let barrier = Rect {
+            left: 100,
+            top: 100,
+            right: 200,
+            bottom: 200,
+        };
+        let buffer = Rect {
+            left: 90,
+            top: 90,
+            right: 210,
+            bottom: 210,
+        };
+
+        let result = check_movement_path(&start, &end, &barrier, &buffer);
+        assert!(result.is_none()); // No collision, should return None
+    }
+
+    #[test]
+    fn test_check_movement_path_small_movement() {
+        let start = Point { x: 50, y: 50 };
+        let end = Point { x: 51, y: 50 }; // Very small movement
+        let barrier = Rect {
+            left: 100,
+            top: 100,
+            right: 200,
+            bottom: 200,
+        };
+        let buffer = Rect {
+            left: 90,
+            top: 90,
+            right: 210,
+            bottom: 210,
+        };
+
+        let result = check_movement_path(&start, &end, &barrier, &buffer);
+        assert!(result.is_none()); // Should skip small movements
+    }
+
+    #[test]
+    fn test_trajectory_samples() {
+        let samples = trajectory_samples(Point { x: 0, y: 0 }, Point { x: 100, y: -50 });
+        assert_eq!(samples.len(), PATH_STEPS as usize);
+        assert_eq!(samples[0], Point { x: 10, y: -5 });
+        assert_eq!(samples[4], Point { x: 50, y: -25 });
+        assert_eq!(samples.last(), Some(&Point { x: 100, y: -50 }));
+
+        // Too small to check
+        assert!(trajectory_samples(Point { x: 5, y: 5 }, Point { x: 6, y: 4 }).is_empty());
+    }
+
+    #[test]
+    fn test_check_movement_path_collision() {
+        let start = Point { x: 50, y: 150 };
+        let end = Point { x: 250, y: 150 }; // Path goes through barrier
+        let barrier = Rect {
+            left: 100,
+            top: 100,
+            right: 200,
+            bottom: 200,
+        };
+        let buffer = Rect {
+            left: 90,
+            top: 90,
+            right: 210,
+            bottom: 210,
+        };
+
+        let result = check_movement_path(&start, &end, &barrier, &buffer);
+        assert!(result.is_some()); // Should detect collision and return safe point
+
+        let safe_point = result.unwrap();
+        assert!(!point_in_rect(&safe_point, &buffer)); // Safe point should be outside buffer
+    }
+
+    const DECISION_BARRIER: Rect = Rect {
+        left: 100,
+        top: 100,
+        right: 200,
+        bottom: 200,
+    };
+    const DECISION_BUFFER: Rect = Rect {
+        left: 90,
+        top: 90,
+        right: 210,
+        bottom: 210,
+    };
+
+    fn decide(last: Option<Point>, current: Point) -> MoveDecision {
+        decide_move(
+            last.as_ref(),
+            &current,
+            &DECISION_BARRIER,
+            &DECISION_BUFFER,
+            20,
+            &SCREEN,
+            OffscreenPush::ClampToEdge,
+        )
+    }
+
+    #[test]
+    fn test_decide_move_allows_moves_outside_buffer() {
+        let decision = decide(Some(Point { x: 40, y: 150 }), Point { x: 50, y: 150 });
+        assert!(matches!(decision, MoveDecision::Allow));
+
+        // First event after enabling has no trajectory to check
+        assert!(matches!(
+            decide(None, Point { x: 50, y: 150 }),
+            MoveDecision::Allow
+        ));
+    }
+
+    #[test]
+    fn test_decide_move_lets_prediction_only_moves_through() {
+        // Heading straight for the barrier, and the next step of the same size
+        // would land inside it, but the cursor itself is still legal
+        let decision = decide(Some(Point { x: 60, y: 150 }), Point { x: 85, y: 150 });
+        assert!(matches!(decision, MoveDecision::Allow));
+    }
+
+    #[test]
+    fn test_decide_move_stops_paths_crossing_barrier() {
+        let decision = decide(Some(Point { x: 50, y: 150 }), Point { x: 250, y: 150 });
+        match decision {
+            MoveDecision::StopBeforeBarrier(safe) => {
+                assert!(!point_in_rect(&safe, &DECISION_BUFFER));
+            }
+            _ => panic!("expected StopBeforeBarrier"),
+        }
+    }
+
+    #[test]
+    fn test_decide_move_pushes_out_of_buffer_and_barrier() {
+        // Landed in the buffer zone with a tiny move
+        let decision = decide(Some(Point { x: 94, y: 150 }), Point { x: 95, y: 150 });
+        match decision {
+            MoveDecision::PushOut(target) => {
+                assert!(!point_in_rect(&target, &DECISION_BUFFER));
+            }
+            _ => panic!("expected PushOut"),
+        }
+
+        // Already inside the barrier with no previous position
+        assert!(matches!(
+            decide(None, Point { x: 150, y: 105 }),
+            MoveDecision::PushOut(_)
+        ));
+    }
+
+    #[test]
+    fn test_asymmetric_buffer_corners() {
+        // Flush against the screen bottom: a tall buffer above, narrower ones
+        // at the sides and none below
+        let buffer = buffer_rect(
+            &DECISION_BARRIER,
+            BufferZone {
+                top: 30,
+                right: 15,
+                bottom: 0,
+                left: 15,
+            },
+        );
+        let zone = |x, y| classify_zone(&Point { x, y }, &DECISION_BARRIER, &buffer);
+        let push = |x, y| match decide_move(
+            None,
+            &Point { x, y },
+            &DECISION_BARRIER,
+            &buffer,
+            20,
+            &SCREEN,
+            OffscreenPush::ClampToEdge,
+        ) {
+            MoveDecision::PushOut(target) => (target.x, target.y),
+            _ => panic!("expected PushOut at ({}, {})", x, y),
+        };
+
+        // Where the top and side buffers meet, the corner is as wide as the
+        // side buffer and as tall as the top one
+        assert_eq!(zone(85, 70), Zone::Buffer);
+        assert_eq!(zone(84, 70), Zone::Outside);
+        assert_eq!(zone(85, 69), Zone::Outside);
+        assert_eq!(zone(214, 70), Zone::Buffer);
+        assert_eq!(zone(215, 70), Zone::Outside);
+
+        // Pushed out through whichever of the two buffers is nearer
+        assert_eq!(push(86, 95), (65, 95));
+        assert_eq!(push(95, 71), (95, 50));
+        assert_eq!(push(213, 95), (235, 95));
+
+        // Where a side buffer meets the missing bottom one, the side buffer
+        // stops level with the barrier's bottom edge
+        assert_eq!(zone(85, 199), Zone::Buffer);
+        assert_eq!(zone(85, 200), Zone::Outside);
+        assert_eq!(zone(150, 200), Zone::Outside);
+        assert_eq!(push(95, 199), (95, 220));
+        assert_eq!(push(86, 190), (65, 190));
+    }
+
+    /// An engine guarding `DECISION_BARRIER` with a 10px buffer and 20px pushes
+    fn test_engine() -> BarrierEngine {
+        let mut engine = BarrierEngine::default();
+        engine.configure(
+            DECISION_BARRIER,
+            BufferZone::uniform(10),
+            20,
+            OffscreenPush::ClampToEdge,
+            SCREEN,
+        );
+        engine
+    }
+
+    #[test]
+    fn test_preview_matches_on_mouse_move_without_side_effects() {
+        let moves = [
+            (Some(Point { x: 40, y: 150 }), Point { x: 50, y: 150 }),
+            (Some(Point { x: 50, y: 150 }), Point { x: 250, y: 150 }),
+            (Some(Point { x: 94, y: 150 }), Point { x: 95, y: 150 }),
+            (None, Point { x: 150, y: 105 }),
+        ];
+
+        for (last, pos) in moves {
+            let preview_engine = test_engine();
+            let preview = preview_engine.preview(pos, last);
+            assert_eq!(preview_engine.last_decision(), None);
+            assert_eq!(preview_engine.last_pos, None);
+
+            let mut engine = test_engine();
+            if let Some(last) = last {
+                engine.on_mouse_move(last, Instant::now());
+            }
+            engine.on_mouse_move(pos, Instant::now());
+            let decision = engine.last_decision().unwrap();
+            let moved = (decision.kind != DecisionKind::PassThrough).then_some(decision.output);
+            assert_eq!(preview, moved, "{:?} -> {:?}", last, pos);
+        }
+    }
+
+    /// The parts of an `EngineAction` a movement trace cares about
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Seen {
+        Pass,
+        MoveTo(i32, i32),
+        Slow(i32, i32),
+        Changed(Zone, Zone),
+        Play(SoundKind),
+        WouldPush(i32, i32),
+    }
+
+    fn seen(actions: &[EngineAction]) -> Vec<Seen> {
+        actions
+            .iter()
+            .map(|action| match *action {
+                EngineAction::PassThrough => Seen::Pass,
+                EngineAction::MoveCursorTo(push) => Seen::MoveTo(push.to.x, push.to.y),
+                EngineAction::SlowCursorTo { to, .. } => Seen::Slow(to.x, to.y),
+                EngineAction::ZoneChanged { from, to } => Seen::Changed(from, to),
+                EngineAction::PlaySound(kind) => Seen::Play(kind),
+                EngineAction::WouldPush(push) => Seen::WouldPush(push.to.x, push.to.y),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_movement_traces() {
+        use Seen::*;
+        const HIT: Seen = Play(SoundKind::BarrierHit);
+        const ENTRY: Seen = Play(SoundKind::BarrierEntry);
+        const INTO_BUFFER: Seen = Changed(Zone::Outside, Zone::Buffer);
+
+        type Trace = &'static [((i32, i32), &'static [Seen])];
+        let cases: [(&str, Trace); 6] = [
+            (
+                "slow approach",
+                &[
+                    ((70, 150), &[Pass]),
+                    ((80, 150), &[Pass]),
+                    ((89, 150), &[Pass]),
+                    // The first buffer pixel is enforced, and a crawl isn't scaled up
+                    ((90, 150), &[INTO_BUFFER, HIT, MoveTo(70, 150)]),
+                ],
+            ),
+            (
+                "decelerating to click just outside the buffer",
+                &[
+                    ((20, 150), &[Pass]),
+                    ((60, 150), &[Pass]),
+                    // Another step this size would land in the barrier, but
+                    // nothing is pushed for where the cursor might go next
+                    ((80, 150), &[Pass]),
+                    ((87, 150), &[Pass]),
+                    ((89, 150), &[Pass]),
+                ],
+            ),
+            (
+                "fast flick",
+                &[
+                    ((50, 150), &[Pass]),
+                    // Straight through the barrier: stopped at the last point
+                    // before the buffer, and never counted as entering it
+                    ((250, 150), &[MoveTo(70, 150)]),
+                ],
+            ),
+            (
+                "diagonal corner hit",
+                &[
+                    ((80, 130), &[Pass]),
+                    // Both ends are outside, but the path clips the top-left corner
+                    ((130, 80), &[MoveTo(85, 125)]),
+                ],
+            ),
+            (
+                "enter then leave",
+                &[
+                    ((85, 150), &[Pass]),
+                    ((95, 150), &[INTO_BUFFER, HIT, MoveTo(70, 150)]),
+                    ((68, 150), &[Changed(Zone::Buffer, Zone::Outside), Pass]),
+                    ((80, 150), &[Pass]),
+                    // Coming back plays the hit sound again
+                    ((92, 150), &[INTO_BUFFER, HIT, MoveTo(70, 150)]),
+                ],
+            ),
+            (
+                "enabled inside the barrier",
+                &[(
+                    (150, 105),
+                    &[
+                        Changed(Zone::Outside, Zone::Barrier),
+                        ENTRY,
+                        HIT,
+                        MoveTo(150, 70),
+                    ],
+                )],
+            ),
+        ];
+
+        for (name, trace) in cases {
+            let mut engine = test_engine();
+            for (step, &((x, y), expected)) in trace.iter().enumerate() {
+                let actions = engine.on_mouse_move(Point { x, y }, Instant::now());
+                assert_eq!(seen(&actions), expected, "{} step {}", name, step);
+            }
+        }
+    }
+
+    #[test]
+    fn test_entry_cue_modes() {
+        let entries = |cue: EntryCue, trace: &[(i32, i32)]| {
+            let mut engine = test_engine();
+            engine.set_entry_cue(cue);
+            trace
+                .iter()
+                .filter(|&&(x, y)| {
+                    engine
+                        .on_mouse_move(Point { x, y }, Instant::now())
+                        .contains(&EngineAction::PlaySound(SoundKind::BarrierEntry))
+                })
+                .count()
+        };
+
+        // Reaching the buffer zone, where the push stops the cursor, twice
+        let approach = [(85, 150), (95, 150), (68, 150), (92, 150)];
+        assert_eq!(entries(EntryCue::Barrier, &approach), 0);
+        assert_eq!(entries(EntryCue::BufferZone, &approach), 2);
+
+        // Moving deeper while still inside only counts once per visit
+        let deeper = [(85, 150), (91, 150), (93, 150)];
+        assert_eq!(entries(EntryCue::BufferZone, &deeper), 1);
+
+        // Landing in the barrier itself counts in both modes
+        let teleport = [(150, 105)];
+        assert_eq!(entries(EntryCue::Barrier, &teleport), 1);
+        assert_eq!(entries(EntryCue::BufferZone, &teleport), 1);
+    }
+
+    #[test]
+    fn test_buffer_blocks_modes() {
+        use Seen::*;
+        const INTO_BUFFER: Seen = Changed(Zone::Outside, Zone::Buffer);
+        const HIT: Seen = Play(SoundKind::BarrierHit);
+        let trace = |buffer_blocks: bool, moves: &[(i32, i32)]| {
+            let mut engine = test_engine();
+            engine.set_buffer_blocks(buffer_blocks);
+            moves
+                .iter()
+                .map(|&(x, y)| seen(&engine.on_mouse_move(Point { x, y }, Instant::now())))
+                .collect::<Vec<_>>()
+        };
+
+        // Creeping up to the barrier: a blocking buffer zone pushes at its
+        // edge, a warning one only plays the hit sound there
+        let creep = [(85, 150), (95, 150), (99, 150), (100, 150)];
+        assert_eq!(trace(true, &creep)[1], [INTO_BUFFER, HIT, MoveTo(70, 150)]);
+        assert_eq!(
+            trace(false, &creep),
+            [
+                vec![Pass],
+                vec![INTO_BUFFER, HIT, Pass],
+                vec![Pass],
+                // Pushed out of the barrier itself
+                vec![
+                    Changed(Zone::Buffer, Zone::Barrier),
+                    Play(SoundKind::BarrierEntry),
+                    MoveTo(80, 150)
+                ],
+            ]
+        );
+
+        // A flick through the barrier stops before the barrier, in the buffer zone
+        let flick = [(50, 150), (250, 150)];
+        assert_eq!(trace(true, &flick)[1], [MoveTo(70, 150)]);
+        assert_eq!(trace(false, &flick)[1], [MoveTo(90, 150)]);
+
+        // Preview agrees
+        let mut engine = test_engine();
+        engine.set_buffer_blocks(false);
+        assert_eq!(
+            engine.preview(Point { x: 250, y: 150 }, Some(Point { x: 50, y: 150 })),
+            Some(Point { x: 90, y: 150 })
+        );
+        assert_eq!(engine.preview(Point { x: 95, y: 150 }, None), None);
+    }
+
+    #[test]
+    fn test_warn_only_reports_pushes_without_moving_the_cursor() {
+        use Seen::*;
+        let trace = |moves: &[(i32, i32)]| {
+            let mut engine = test_engine();
+            engine.set_dynamic_push(false);
+            engine.set_approach_zone(Some(ApproachZone {
+                distance: 40,
+                min_scale: 0.5,
+            }));
+            engine.set_enforcement_mode(EnforcementMode::WarnOnly);
+            moves
+                .iter()
+                .map(|&(x, y)| seen(&engine.on_mouse_move(Point { x, y }, Instant::now())))
+                .collect::<Vec<_>>()
+        };
+
+        // Never slowed; the same zones and sounds as enforcing, with each push
+        // reported from where the cursor really is
+        assert_eq!(
+            trace(&[(40, 150), (60, 150), (95, 150), (99, 150), (120, 150)]),
+            [
+                vec![Pass],
+                vec![Pass],
+                vec![
+                    Changed(Zone::Outside, Zone::Buffer),
+                    Play(SoundKind::BarrierHit),
+                    WouldPush(70, 150)
+                ],
+                vec![WouldPush(70, 150)],
+                // Would have been stopped where it left off
+                vec![
+                    Changed(Zone::Buffer, Zone::Barrier),
+                    Play(SoundKind::BarrierEntry),
+                    WouldPush(99, 150)
+                ],
+            ]
+        );
+
+        // A flick through the barrier would have been stopped, but lands
+        assert_eq!(trace(&[(50, 150), (250, 150)])[1], [WouldPush(70, 150)]);
+    }
+
+    #[test]
+    fn test_constant_push_ignores_speed() {
+        let (from, fast) = (Point { x: 50, y: 150 }, Point { x: 95, y: 150 });
+        let push = |dynamic_push: bool| {
+            let mut engine = test_engine();
+            engine.set_dynamic_push(dynamic_push);
+            engine.on_mouse_move(from, Instant::now());
+            let context = engine.move_context(fast);
+            let actions = engine.on_mouse_move(fast, Instant::now());
+            let multiplier = match actions.last() {
+                Some(EngineAction::MoveCursorTo(push)) => push.multiplier,
+                _ => panic!("expected a push"),
+            };
+            (context.push_factor, seen(&actions), multiplier)
+        };
+
+        // 45px at once: scaled 1.8x past the buffer edge at 90
+        assert_eq!(push(true).0, 36);
+        assert_eq!(push(true).1.last(), Some(&Seen::MoveTo(54, 150)));
+
+        // The same move with dynamic push off gets the configured 20px
+        let (push_factor, actions, multiplier) = push(false);
+        assert_eq!(push_factor, 20);
+        assert_eq!(actions.last(), Some(&Seen::MoveTo(70, 150)));
+        assert_eq!(multiplier, 1.0);
+
+        // Preview agrees
+        let mut engine = test_engine();
+        engine.set_dynamic_push(false);
+        assert_eq!(
+            engine.preview(fast, Some(from)),
+            Some(Point { x: 70, y: 150 })
+        );
+    }
+
+    #[test]
+    fn test_approach_zone_traces() {
+        use Seen::*;
+        let trace = |moves: &[(i32, i32)]| {
+            let mut engine = test_engine();
+            engine.set_approach_zone(Some(ApproachZone {
+                distance: 40,
+                min_scale: 0.5,
+            }));
+            moves
+                .iter()
+                .map(|&(x, y)| seen(&engine.on_mouse_move(Point { x, y }, Instant::now())))
+                .collect::<Vec<_>>()
+        };
+
+        // The buffer zone starts at x = 90, so the approach zone at x = 50.
+        // Each event is 8px from where the cursor really is, and keeps less of
+        // it the closer it gets
+        assert_eq!(
+            trace(&[(40, 150), (48, 150), (56, 150), (63, 150), (70, 150)]),
+            [
+                vec![Pass],
+                vec![Pass],
+                vec![Slow(55, 150)],
+                vec![Slow(62, 150)],
+                vec![Slow(68, 150)],
+            ]
+        );
+
+        // A flick through the barrier is stopped as without the approach zone
+        assert_eq!(trace(&[(40, 150), (250, 150)])[1], [MoveTo(82, 150)]);
+
+        // Moving away or alongside is never slowed
+        assert_eq!(
+            trace(&[(80, 150), (60, 150), (60, 170), (85, 170)])[1..3],
+            [vec![Pass], vec![Pass]]
+        );
+    }
+
+    #[test]
+    fn test_approach_zone_carries_fractions_of_a_pixel() {
+        let mut engine = test_engine();
+        engine.set_approach_zone(Some(ApproachZone {
+            distance: 40,
+            min_scale: 0.5,
+        }));
+        let mut cursor = Point { x: 80, y: 150 };
+        engine.on_mouse_move(cursor, Instant::now());
+
+        // Crawling a pixel at a time at about half speed still gets there
+        let mut events = 0;
+        while cursor.x < 89 {
+            let target = Point {
+                x: cursor.x + 1,
+                ..cursor
+            };
+            for action in engine.on_mouse_move(target, Instant::now()) {
+                if let EngineAction::SlowCursorTo { to, .. } = action {
+                    cursor = to;
+                }
+            }
+            let decision = engine.last_decision().unwrap();
+            assert_eq!(decision.kind, DecisionKind::ApproachSlowdown);
+            assert_eq!(decision.output, cursor);
+            events += 1;
+        }
+        assert!((16..=19).contains(&events), "{} events", events);
+    }
+
+    #[test]
+    fn test_push_records_speed_and_edge() {
+        let mut engine = test_engine();
+        let now = Instant::now();
+        engine.on_mouse_move(Point { x: 40, y: 150 }, now);
+
+        // A 55px flick into the buffer is pushed 2.2x further
+        let actions = engine.on_mouse_move(Point { x: 95, y: 150 }, now);
+        assert_eq!(
+            actions.last(),
+            Some(&EngineAction::MoveCursorTo(LastPush {
+                from: Point { x: 95, y: 150 },
+                to: Point { x: 46, y: 150 },
+                edge: Some(PushEdge::Left),
+                speed: 55.0,
+                multiplier: 55.0 / 25.0,
+                timestamp: now,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_cursor_speed_tells_approaches_apart() {
+        let mut engine = test_engine();
+        let now = Instant::now();
+        engine.on_mouse_move(Point { x: 10, y: 150 }, now);
+
+        // A 70px flick that stops short of the buffer zone, then a 15px move into it
+        engine.on_mouse_move(Point { x: 80, y: 150 }, now);
+        engine.on_mouse_move(Point { x: 95, y: 150 }, now);
+        let speed = engine.cursor_speed();
+        assert_eq!(speed.per_event, 15);
+        assert_eq!(speed.peak_per_event, 70);
+        assert_eq!(speed.peak_approach_per_event, 15);
+
+        // A move jumping clean over the barrier is stopped, so it approached too
+        engine.reset();
+        engine.on_mouse_move(Point { x: 50, y: 150 }, now);
+        engine.on_mouse_move(Point { x: 250, y: 150 }, now);
+        assert_eq!(engine.cursor_speed().peak_approach_per_event, 200);
+    }
+
+    #[test]
+    fn test_failed_push_is_corrected_on_next_move() {
+        let mut engine = test_engine();
+        let now = Instant::now();
+        engine.on_mouse_move(Point { x: 150, y: 105 }, now);
+
+        // The push out failed and the cursor is still in the barrier
+        engine.on_move_failed(Point { x: 150, y: 105 });
+        let actions = engine.on_mouse_move(Point { x: 150, y: 106 }, now);
+        assert_eq!(seen(&actions), [Seen::MoveTo(150, 70)]);
+        assert!(!engine.corrective_push_pending);
+
+        // A failure outside the barrier doesn't arm it, and reset disarms it
+        engine.on_move_failed(Point { x: 95, y: 150 });
+        assert!(!engine.corrective_push_pending);
+        engine.on_move_failed(Point { x: 150, y: 105 });
+        engine.reset();
+        assert!(!engine.corrective_push_pending);
+        assert_eq!(engine.zone, Zone::Outside);
+    }
+
+    #[test]
+    fn test_last_decision_names_the_branch() {
+        let mut engine = test_engine();
+        let now = Instant::now();
+        assert_eq!(engine.last_decision(), None);
+
+        // The decision's output is where the final action sends the cursor
+        let mut decide = |pos: Point| {
+            let actions = engine.on_mouse_move(pos, now);
+            let decision = engine.last_decision().unwrap();
+            let output = match actions.last() {
+                Some(EngineAction::MoveCursorTo(push)) => push.to,
+                _ => pos,
+            };
+            assert_eq!(decision.input, pos);
+            assert_eq!(decision.output, output);
+            assert_eq!(decision.timestamp, now);
+            (decision.kind, decision.last)
+        };
+
+        let start = Point { x: 50, y: 150 };
+        assert_eq!(decide(start), (DecisionKind::PassThrough, None));
+        assert_eq!(
+            decide(Point { x: 150, y: 150 }),
+            (DecisionKind::TrajectoryBlock, Some(start))
+        );
+
+        engine.reset();
+        assert_eq!(engine.last_decision(), None);
+        engine.on_mouse_move(Point { x: 150, y: 105 }, now);
+        assert_eq!(
+            engine.last_decision().map(|decision| decision.kind),
+            Some(DecisionKind::BufferPush)
+        );
+
+        engine.on_move_failed(Point { x: 150, y: 105 });
+        let actions = engine.on_mouse_move(Point { x: 150, y: 106 }, now);
+        let decision = engine.last_decision().unwrap();
+        assert_eq!(decision.kind, DecisionKind::CorrectivePush);
+        assert_eq!(decision.last, Some(Point { x: 150, y: 105 }));
+        assert_eq!(
+            seen(&actions),
+            [Seen::MoveTo(decision.output.x, decision.output.y)]
+        );
+    }
+
+    #[test]
+    fn test_move_context() {
+        let mut engine = test_engine();
+        let first = Point { x: 50, y: 150 };
+
+        let context = engine.move_context(first);
+        assert_eq!(context.barrier, DECISION_BARRIER);
+        assert_eq!(
+            context.buffer,
+            buffer_rect(&DECISION_BARRIER, BufferZone::uniform(10))
+        );
+        assert_eq!(context.last, None);
+        assert_eq!(context.zone, Zone::Outside);
+        assert_eq!(context.push_factor, 20);
+
+        // Describing a move doesn't handle it
+        let fast = Point { x: 95, y: 150 };
+        assert_eq!(engine.move_context(fast).last, None);
+
+        engine.on_mouse_move(first, Instant::now());
+        let context = engine.move_context(fast);
+        assert_eq!(context.last, Some(first));
+        assert_eq!(context.current, fast);
+        assert_eq!(context.zone, Zone::Buffer);
+        assert_eq!(
+            context.push_factor,
+            calculate_dynamic_push_factor(20, &first, &fast)
+        );
+    }
+}
@@ -0,0 +1,476 @@
+/// A change the thread that owns the mouse hook must make to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HookAction {
+    Install,
+    Uninstall,
+}
+
+/// A button that holds a bypass of its own while it is down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BypassButton {
+    Middle,
+    Gamepad,
+}
+
+/// Whether the mouse hook should be installed: the barrier's enabled flag and
+/// the bypass holders, changed only through these transitions.
+///
+/// Bypasses begin and end on other threads (the middle-button monitor, the
+/// gamepad, programmatic callers), which can only flag that a change is pending. The
+/// thread owning the hook applies it with [`Self::take_action`], which decides
+/// from the state at that moment rather than when the change was flagged, so
+/// a bypass released just as the barrier is disabled can't reinstall the hook
+/// behind `disable`.
+#[derive(Debug, Default)]
+pub(crate) struct HookControl {
+    enabled: bool,
+    bypass_holders: u32,
+    /// The buttons' bypasses, each held apart from the counted ones and from
+    /// each other so a release can always end its own without ending anyone
+    /// else's
+    middle_button: bool,
+    gamepad_button: bool,
+    change_pending: bool,
+}
+
+impl HookControl {
+    pub(crate) const fn new() -> Self {
+        Self {
+            enabled: false,
+            bypass_holders: 0,
+            middle_button: false,
+            gamepad_button: false,
+            change_pending: false,
+        }
+    }
+
+    /// The barrier was enabled; the caller installs the hook itself if
+    /// [`Self::hook_wanted`]
+    pub(crate) fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    /// The barrier was disabled; the caller removes the hook itself, so any
+    /// pending change is dropped
+    pub(crate) fn disable(&mut self) {
+        self.enabled = false;
+        self.change_pending = false;
+    }
+
+    /// Adds a bypass holder; returns true if this started the bypass.
+    pub(crate) fn begin_bypass(&mut self) -> bool {
+        let was_bypassed = self.is_bypassed();
+        self.bypass_holders += 1;
+        self.bypass_changed(was_bypassed)
+    }
+
+    /// Removes a bypass holder; returns true if this ended the bypass.
+    /// Unbalanced calls are ignored rather than wrapping the count.
+    pub(crate) fn end_bypass(&mut self) -> bool {
+        let was_bypassed = self.is_bypassed();
+        self.bypass_holders = self.bypass_holders.saturating_sub(1);
+        self.bypass_changed(was_bypassed)
+    }
+
+    /// Takes or drops `button`'s bypass; returns true if this started or
+    /// ended the bypass. Dropping it when not held does nothing.
+    pub(crate) fn hold_button(&mut self, button: BypassButton, held: bool) -> bool {
+        let was_bypassed = self.is_bypassed();
+        match button {
+            BypassButton::Middle => self.middle_button = held,
+            BypassButton::Gamepad => self.gamepad_button = held,
+        }
+        self.bypass_changed(was_bypassed)
+    }
+
+    pub(crate) fn is_bypassed(&self) -> bool {
+        self.bypass_holders > 0 || self.middle_button || self.gamepad_button
+    }
+
+    /// Flags the hook change a bypass starting or ending calls for; returns
+    /// whether it did either
+    fn bypass_changed(&mut self, was_bypassed: bool) -> bool {
+        let changed = self.is_bypassed() != was_bypassed;
+        // Only touch the hook while the barrier is meant to be active
+        if changed && self.enabled {
+            self.change_pending = true;
+        }
+        changed
+    }
+
+    #[cfg(test)]
+    pub(crate) fn change_pending(&self) -> bool {
+        self.change_pending
+    }
+
+    /// The barrier is enabled and no bypass holds the hook off
+    pub(crate) fn hook_wanted(&self) -> bool {
+        self.enabled && !self.is_bypassed()
+    }
+
+    /// Takes the pending change, if any, as what to do to a hook that is
+    /// currently `installed` or not
+    pub(crate) fn take_action(&mut self, installed: bool) -> Option<HookAction> {
+        if !std::mem::take(&mut self.change_pending) {
+            return None;
+        }
+        match (self.hook_wanted(), installed) {
+            (true, false) => Some(HookAction::Install),
+            (false, true) => Some(HookAction::Uninstall),
+            _ => None,
+        }
+    }
+}
+
+/// A bypass the middle button starts or stops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BypassChange {
+    Begin,
+    End,
+}
+
+/// When a held middle button turns into a bypass.
+///
+/// The monitor samples the button and the clock together; the bypass begins
+/// once the button has been down for the hold threshold, so a click released
+/// sooner never starts one. Every release ends the button's bypass, however
+/// long the threshold was and whether or not this hold began it, so one left
+/// by an earlier monitor is never stuck.
+#[derive(Debug, Default)]
+pub(crate) struct MiddleButtonHold {
+    pressed_at_ms: Option<u64>,
+    engaged: bool,
+}
+
+impl MiddleButtonHold {
+    /// Records the button as `pressed` or not at `now_ms`, returning the
+    /// bypass change that calls for, if any
+    pub(crate) fn sample(
+        &mut self,
+        pressed: bool,
+        now_ms: u64,
+        hold_ms: u64,
+    ) -> Option<BypassChange> {
+        if !pressed {
+            self.engaged = false;
+            return self
+                .pressed_at_ms
+                .take()
+                .is_some()
+                .then_some(BypassChange::End);
+        }
+        let pressed_at = *self.pressed_at_ms.get_or_insert(now_ms);
+        if !self.engaged && now_ms.saturating_sub(pressed_at) >= hold_ms {
+            self.engaged = true;
+            return Some(BypassChange::Begin);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One step of an interleaving: a transition, or the hook thread
+    /// servicing requests
+    #[derive(Debug, Clone, Copy)]
+    enum Step {
+        Enable,
+        Disable,
+        Bypass,
+        Release,
+        Process,
+    }
+    use Step::*;
+
+    /// Runs `steps` the way `MouseBarrier` drives the control: enable installs
+    /// the hook unless bypassed, disable removes it, and processing applies
+    /// the pending change. Returns whether the hook was installed after each
+    /// step.
+    fn run(steps: &[Step]) -> Vec<bool> {
+        let mut control = HookControl::new();
+        let mut installed = false;
+        steps
+            .iter()
+            .map(|step| {
+                match step {
+                    Enable => {
+                        control.enable();
+                        installed |= control.hook_wanted();
+                    }
+                    Disable => {
+                        control.disable();
+                        installed = false;
+                    }
+                    Bypass => {
+                        control.begin_bypass();
+                    }
+                    Release => {
+                        control.end_bypass();
+                    }
+                    Process => match control.take_action(installed) {
+                        Some(HookAction::Install) => installed = true,
+                        Some(HookAction::Uninstall) => installed = false,
+                        None => {}
+                    },
+                }
+                installed
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bypass_suspends_and_restores_the_hook() {
+        assert_eq!(
+            run(&[Enable, Bypass, Process, Release, Process]),
+            [true, true, false, false, true]
+        );
+    }
+
+    #[test]
+    fn test_bypass_released_after_disable_leaves_the_hook_out() {
+        // enable -> bypass -> disable -> release
+        assert_eq!(
+            run(&[Enable, Bypass, Process, Disable, Release, Process]),
+            [true, true, false, false, false, false]
+        );
+        // The bypass was never serviced before the barrier went down
+        assert_eq!(
+            run(&[Enable, Bypass, Disable, Release, Process]),
+            [true, true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn test_disable_drops_a_pending_install() {
+        // The release queued an install, but the barrier was disabled before
+        // the hook thread got to it
+        assert_eq!(
+            run(&[Enable, Bypass, Process, Release, Disable, Process]),
+            [true, true, false, false, false, false]
+        );
+
+        let mut control = HookControl::new();
+        control.enable();
+        control.begin_bypass();
+        control.end_bypass();
+        assert!(control.change_pending());
+        control.disable();
+        assert!(!control.change_pending());
+        assert_eq!(control.take_action(false), None);
+    }
+
+    #[test]
+    fn test_enable_while_bypassed_waits_for_the_release() {
+        assert_eq!(
+            run(&[Bypass, Enable, Process, Release, Process]),
+            [false, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn test_pending_change_is_decided_when_serviced() {
+        // A bypass that begins and ends before the hook thread looks leaves
+        // the hook where it is
+        assert_eq!(
+            run(&[Enable, Bypass, Release, Process]),
+            [true, true, true, true]
+        );
+        // Released and taken again: still suspended when serviced
+        assert_eq!(
+            run(&[Enable, Bypass, Process, Release, Bypass, Process]),
+            [true, true, false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn test_re_enable_after_disable_installs_again() {
+        assert_eq!(
+            run(&[Enable, Disable, Enable, Process]),
+            [true, false, true, true]
+        );
+        // A bypass still held across disable and enable keeps the hook out
+        assert_eq!(
+            run(&[Enable, Bypass, Disable, Enable, Process, Release, Process]),
+            [true, true, false, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn test_bypass_holders_are_reference_counted() {
+        let mut control = HookControl::new();
+        assert!(!control.is_bypassed());
+
+        // Middle button and a programmatic caller overlap
+        assert!(control.begin_bypass());
+        assert!(!control.begin_bypass());
+        assert!(control.is_bypassed());
+
+        assert!(!control.end_bypass());
+        assert!(control.is_bypassed());
+        assert!(control.end_bypass());
+        assert!(!control.is_bypassed());
+    }
+
+    #[test]
+    fn test_unbalanced_release_is_ignored() {
+        let mut control = HookControl::new();
+        control.enable();
+
+        assert!(!control.end_bypass());
+        assert!(!control.is_bypassed());
+        assert!(!control.change_pending());
+
+        // A stray end must not swallow the next begin
+        assert!(control.begin_bypass());
+        assert!(control.is_bypassed());
+    }
+
+    /// Feeds `samples` of `(pressed, now_ms)` through a fresh hold and
+    /// returns the change each one produced
+    fn hold(hold_ms: u64, samples: &[(bool, u64)]) -> Vec<Option<BypassChange>> {
+        let mut hold = MiddleButtonHold::default();
+        samples
+            .iter()
+            .map(|&(pressed, now_ms)| hold.sample(pressed, now_ms, hold_ms))
+            .collect()
+    }
+
+    #[test]
+    fn test_zero_hold_bypasses_on_press() {
+        assert_eq!(
+            hold(0, &[(false, 0), (true, 5), (true, 10), (false, 15)]),
+            [
+                None,
+                Some(BypassChange::Begin),
+                None,
+                Some(BypassChange::End)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_click_shorter_than_hold_never_bypasses() {
+        // The release still ends whatever bypass the button holds
+        assert_eq!(
+            hold(200, &[(true, 0), (true, 100), (true, 199), (false, 205)]),
+            [None, None, None, Some(BypassChange::End)]
+        );
+    }
+
+    #[test]
+    fn test_release_ends_a_bypass_begun_elsewhere() {
+        // An earlier monitor began the button's bypass and stopped without
+        // ending it; this one only sees the press from partway through
+        let mut control = HookControl::new();
+        control.enable();
+        assert!(control.hold_button(BypassButton::Middle, true));
+        // A programmatic caller holds one too
+        control.begin_bypass();
+
+        let mut hold = MiddleButtonHold::default();
+        assert_eq!(hold.sample(true, 0, 200), None);
+        assert_eq!(hold.sample(false, 50, 200), Some(BypassChange::End));
+        assert!(!control.hold_button(BypassButton::Middle, false));
+        // The caller's bypass outlives the button's
+        assert!(control.is_bypassed());
+        assert!(control.end_bypass());
+        assert!(control.hook_wanted());
+
+        // Idle samples don't end anything twice
+        assert_eq!(hold.sample(false, 60, 200), None);
+    }
+
+    #[test]
+    fn test_middle_button_and_callers_share_the_bypass() {
+        let mut control = HookControl::new();
+        control.enable();
+
+        assert!(control.hold_button(BypassButton::Middle, true));
+        assert!(control.change_pending());
+        assert_eq!(control.take_action(true), Some(HookAction::Uninstall));
+        // Already bypassed: the caller joins without another hook change
+        assert!(!control.begin_bypass());
+        assert!(!control.hold_button(BypassButton::Middle, false));
+        assert!(!control.change_pending());
+        assert!(control.end_bypass());
+        assert_eq!(control.take_action(false), Some(HookAction::Install));
+
+        // Dropping a middle-button bypass that isn't held changes nothing
+        assert!(!control.hold_button(BypassButton::Middle, false));
+        assert!(!control.change_pending());
+    }
+
+    #[test]
+    fn test_buttons_hold_separate_bypasses() {
+        let mut control = HookControl::new();
+        control.enable();
+
+        assert!(control.hold_button(BypassButton::Gamepad, true));
+        assert!(!control.hold_button(BypassButton::Middle, true));
+        // The middle button coming up leaves the gamepad's bypass in place
+        assert!(!control.hold_button(BypassButton::Middle, false));
+        assert!(control.is_bypassed());
+        // And so does a stray release from a programmatic caller
+        assert!(!control.end_bypass());
+        assert!(control.is_bypassed());
+
+        assert!(control.hold_button(BypassButton::Gamepad, false));
+        assert!(!control.is_bypassed());
+        assert!(control.hook_wanted());
+
+        // The other way round
+        control.hold_button(BypassButton::Middle, true);
+        control.hold_button(BypassButton::Gamepad, true);
+        assert!(!control.hold_button(BypassButton::Gamepad, false));
+        assert!(control.hold_button(BypassButton::Middle, false));
+    }
+
+    #[test]
+    fn test_long_hold_bypasses_at_threshold_until_release() {
+        assert_eq!(
+            hold(
+                200,
+                &[
+                    (true, 1000),
+                    (true, 1150),
+                    (true, 1200),
+                    (true, 1500),
+                    (false, 1505)
+                ]
+            ),
+            [
+                None,
+                None,
+                Some(BypassChange::Begin),
+                None,
+                Some(BypassChange::End)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_early_release_restarts_the_hold() {
+        // The second press is timed from when it began, not the first
+        assert_eq!(
+            hold(
+                200,
+                &[
+                    (true, 0),
+                    (false, 150),
+                    (true, 160),
+                    (true, 300),
+                    (true, 360)
+                ]
+            ),
+            [
+                None,
+                Some(BypassChange::End),
+                None,
+                None,
+                Some(BypassChange::Begin)
+            ]
+        );
+    }
+}
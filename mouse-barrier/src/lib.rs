@@ -1,1147 +1,1490 @@
-use std::mem;
-use std::ptr;
-use std::sync::atomic::{AtomicBool, AtomicI32, AtomicPtr, Ordering};
-use std::sync::{Arc, Mutex, OnceLock};
-use std::thread;
-use std::time::Duration;
-use tracing::{info, warn};
-use winapi::shared::minwindef::{HMODULE, LPARAM, LRESULT, TRUE, UINT, WPARAM};
-use winapi::shared::windef::{HWND, POINT, RECT};
-use winapi::um::errhandlingapi::GetLastError;
-use winapi::um::libloaderapi::{GetModuleHandleW, GetProcAddress, LoadLibraryW};
-use winapi::um::wingdi::*;
-use winapi::um::winuser::*;
-
-type KeyboardCallback = Arc<Mutex<Option<Box<dyn Fn(u32, bool) + Send + Sync>>>>;
-type MousePositionCallback = Arc<Mutex<Option<Box<dyn Fn(i32, i32) + Send + Sync>>>>;
-
-static MOUSE_BARRIER_STATE: OnceLock<Arc<Mutex<Option<MouseBarrierState>>>> = OnceLock::new();
-static KEYBOARD_CALLBACK: OnceLock<KeyboardCallback> = OnceLock::new();
-static MOUSE_POSITION_CALLBACK: OnceLock<MousePositionCallback> = OnceLock::new();
-static KEYBOARD_HOOK_HANDLE: AtomicPtr<winapi::shared::windef::HHOOK__> =
-    AtomicPtr::new(std::ptr::null_mut());
-static MOUSE_HOOK_HANDLE: AtomicPtr<winapi::shared::windef::HHOOK__> =
-    AtomicPtr::new(std::ptr::null_mut());
-static LAST_IN_BARRIER: AtomicBool = AtomicBool::new(false);
-static MIDDLE_BUTTON_MONITORING: AtomicBool = AtomicBool::new(false);
-static MIDDLE_MOUSE_DOWN: AtomicBool = AtomicBool::new(false);
-static HOOK_INSTALL_REQUESTED: AtomicBool = AtomicBool::new(false);
-static HOOK_UNINSTALL_REQUESTED: AtomicBool = AtomicBool::new(false);
-static LAST_MOUSE_POS: Mutex<Option<POINT>> = Mutex::new(None);
-static HAS_ENTERED_BARRIER: AtomicBool = AtomicBool::new(false);
-static OVERLAY_WINDOWS: [AtomicPtr<winapi::shared::windef::HWND__>; 4] = [
-    AtomicPtr::new(std::ptr::null_mut()),
-    AtomicPtr::new(std::ptr::null_mut()),
-    AtomicPtr::new(std::ptr::null_mut()),
-    AtomicPtr::new(std::ptr::null_mut()),
-];
-
-// Cached screen metrics to avoid repeated API calls
-static SCREEN_WIDTH: AtomicI32 = AtomicI32::new(0);
-static SCREEN_HEIGHT: AtomicI32 = AtomicI32::new(0);
-
-// Physical screen resolution for coordinate scaling
-static PHYSICAL_SCREEN_WIDTH: AtomicI32 = AtomicI32::new(0);
-static PHYSICAL_SCREEN_HEIGHT: AtomicI32 = AtomicI32::new(0);
-
-// Current overlay color for window painting
-static CURRENT_OVERLAY_COLOR: std::sync::atomic::AtomicU32 =
-    std::sync::atomic::AtomicU32::new(0x00FF0000); // Default red
-
-#[derive(Clone)]
-struct MouseBarrierState {
-    barrier_rect: RECT,
-    buffer_zone: i32,
-    push_factor: i32,
-    enabled: bool,
-    overlay_color: u32, // RGB color as 0x00RRGGBB
-    overlay_alpha: u8,  // Alpha transparency (0-255)
-    on_barrier_hit_sound: Option<String>,
-    on_barrier_entry_sound: Option<String>,
-}
+// Without the Win32 runtime nothing drives the engine or lays out overlays, but
+// they still build so their tests run on any platform
+#![cfg_attr(not(windows), allow(dead_code))]
+
+mod decision;
+mod edit;
+mod engine;
+mod hook_control;
+// The overlay layout is only used by the overlay windows
+#[cfg_attr(all(windows, not(feature = "overlay")), allow(dead_code))]
+mod overlay;
+mod position;
+// Drives the engine from synthetic input, for replaying traces without hooks
+#[cfg(any(feature = "simulation", test))]
+mod simulation;
+mod speed;
+mod subscription;
+
+// The OS boundary, and the Win32 runtime behind it: hooks, overlay windows,
+// cursor moves and sounds
+mod platform;
+
+pub use decision::{BarrierDecision, DecisionKind};
+use engine::{buffer_rect, classify_zone};
+pub use engine::{trajectory_samples, LastPush, Point, PushEdge, Rect, SoundKind, Zone};
+#[cfg(all(windows, feature = "overlay"))]
+pub use platform::paint::{OverlayHandle, PaintContext};
+#[cfg(all(windows, feature = "overlay"))]
+pub use platform::win32::OVERLAY_CLASS_NAME;
+#[cfg(windows)]
+pub use platform::win32::{
+    accessibility_settings, cursor_speed, hook_view, hooks_healthy, last_push,
+    process_hook_requests, set_decision_callback, set_decision_tracing, set_event_callback,
+    set_mouse_position_callback, set_mouse_position_callback_rate, set_on_zone_change_callback,
+    simulate, KeyboardHook, MouseBarrier,
+};
+pub use position::DEFAULT_POSITION_CALLBACK_HZ;
+#[cfg(any(feature = "simulation", test))]
+pub use simulation::{SimulatedAction, SimulatedMove, Simulation};
+pub use speed::{suggested_buffer_zone, CursorSpeed};
+pub use subscription::SubscriptionId;
 
 pub struct MouseBarrierConfig {
-    pub x: i32,
-    pub y: i32,
-    pub width: i32,
-    pub height: i32,
-    pub buffer_zone: i32,
+    pub bounds: BarrierBounds,
+    pub buffer_zone: BufferZone,
+    /// Keep the cursor out of the buffer zone; when false the buffer zone only
+    /// plays sounds and shows color, and the cursor is kept out of the barrier
+    pub buffer_blocks: bool,
     pub push_factor: i32,
+    /// Scale pushes 1x-3x with the speed of the move; when false every push
+    /// is `push_factor` long
+    pub dynamic_push: bool,
+    pub offscreen_push: OffscreenPush,
+    /// Pixels pushes keep the cursor clear of every screen edge
+    pub screen_margin: i32,
+    /// Slow the cursor down as it closes in on the buffer zone; `None` lets it
+    /// move at full speed right up to the edge
+    pub approach_zone: Option<ApproachZone>,
+    /// Draw the overlay windows; when false the barrier is enforced with
+    /// nothing on screen
+    pub overlay_enabled: bool,
     pub overlay_color: (u8, u8, u8),
-    pub overlay_alpha: u8,
-    pub on_barrier_hit_sound: Option<String>,
-    pub on_barrier_entry_sound: Option<String>,
+    /// Shade the overlay from `proximity_far_color` to `overlay_color` as
+    /// the cursor closes in on the barrier
+    pub proximity_coloring: bool,
+    pub proximity_far_color: (u8, u8, u8),
+    /// Distance from the barrier, in pixels, at which the overlay shows
+    /// `proximity_far_color`
+    pub proximity_range: i32,
+    /// Alpha of the buffer zone frame (0-255)
+    pub buffer_alpha: u8,
+    pub overlay_coverage: OverlayCoverage,
+    pub overlay_failure: OverlayFailurePolicy,
+    /// Alpha of the barrier interior tint (0-255)
+    pub interior_alpha: u8,
+    /// Alpha the buffer zone frame flashes to when the cursor hits it; the
+    /// flash is drawn by the overlay timer, so needs `overlay_tick_ms` > 0
+    pub flash_alpha: u8,
+    pub overlay_animation: OverlayAnimation,
+    /// How often each overlay window re-asserts its z-order and advances its
+    /// animation, in milliseconds; 0 disables the timer
+    pub overlay_tick_ms: u32,
+    pub on_barrier_hit_sound: Option<Sound>,
+    pub on_barrier_entry_sound: Option<Sound>,
+    /// Which crossing plays `on_barrier_entry_sound`
+    pub entry_cue: EntryCue,
+    /// Stop a sound that is still playing when a new one is triggered
+    pub interrupt_previous_sound: bool,
+    pub coordinate_origin: CoordinateOrigin,
+    /// Round every barrier edge to the nearest line of this grid; `None` uses
+    /// the coordinates as given
+    pub snap_grid: Option<SnapGrid>,
+    /// Swallow mouse moves that the approach zone slowed; when false the
+    /// original event is delivered and moves the cursor the full distance.
+    /// Pushes always swallow the event, since delivering it would carry the
+    /// cursor straight past the barrier
+    pub consume_blocked_events: bool,
+    /// After a push, inject a zero-length relative move so apps that track
+    /// the cursor through raw input resync with where it was put
+    pub resync_after_block: bool,
+    /// Whether the barrier pushes the cursor, or only warns where it would have
+    pub enforcement_mode: EnforcementMode,
+    /// How long, in milliseconds, the middle button must be held before it
+    /// bypasses the barrier; 0 bypasses as soon as it is pressed
+    pub bypass_hold_ms: u32,
+    /// A modifier that lets the cursor through the barrier while held. Seen
+    /// through the keyboard hook, so it only works while something is
+    /// subscribed to `KeyboardHook`
+    pub bypass_modifier: Option<BypassModifier>,
+    /// Move a barrier that lies entirely off-screen onto the screen, keeping
+    /// its size; when false it is only reported
+    pub clamp_to_screen: bool,
+    /// Modifiers that turn the mouse into a barrier editor while held; `None`
+    /// disables editing. Seen through the keyboard hook like
+    /// `bypass_modifier`, and AltGr doesn't count as Ctrl+Alt
+    pub edit_mode: Option<EditMode>,
+    /// How the overlay is drawn while Windows asks for high contrast or no
+    /// transparency
+    pub high_contrast: HighContrastStyle,
 }
 
-pub struct MouseBarrier;
-
-pub struct KeyboardHook;
-
-impl MouseBarrier {
-    pub fn new(config: MouseBarrierConfig) -> Self {
-        // Convert from bottom-left origin to Windows top-left origin
-        let barrier_rect = RECT {
-            left: config.x,
-            top: config.y - config.height, // y is bottom, so top = y - height
-            right: config.x + config.width, // right extends from left
-            bottom: config.y,              // bottom is the y coordinate
-        };
+/// Moving and resizing the barrier with the mouse
+///
+/// While the modifiers are held the barrier isn't enforced; the wheel over it
+/// grows or shrinks its height, and dragging it moves it. Releasing them
+/// reports the result as [`BarrierEvent::Edited`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EditMode {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// Pixels one wheel notch grows or shrinks the barrier's height by
+    pub resize_step: i32,
+}
 
-        let state = MouseBarrierState {
-            barrier_rect,
-            buffer_zone: config.buffer_zone,
-            push_factor: config.push_factor,
-            enabled: false,
-            overlay_color: ((config.overlay_color.0 as u32) << 16)
-                | ((config.overlay_color.1 as u32) << 8)
-                | (config.overlay_color.2 as u32),
-            overlay_alpha: config.overlay_alpha,
-            on_barrier_hit_sound: config.on_barrier_hit_sound,
-            on_barrier_entry_sound: config.on_barrier_entry_sound,
-        };
+/// A key pressed with exactly these modifiers held, e.g. Ctrl+M
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyCombo {
+    /// The key's virtual-key code
+    pub vk_code: u32,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
 
-        let state_lock = MOUSE_BARRIER_STATE.get_or_init(|| Arc::new(Mutex::new(None)));
-        *state_lock.lock().unwrap() = Some(state.clone());
-
-        // Cache screen metrics on first initialization
-        unsafe {
-            let width = GetSystemMetrics(SM_CXSCREEN);
-            let height = GetSystemMetrics(SM_CYSCREEN);
-            SCREEN_WIDTH.store(width, Ordering::Relaxed);
-            SCREEN_HEIGHT.store(height, Ordering::Relaxed);
-
-            // Cache physical screen resolution for coordinate scaling using EnumDisplaySettings
-            let mut dev_mode: DEVMODEW = std::mem::zeroed();
-            dev_mode.dmSize = std::mem::size_of::<DEVMODEW>() as u16;
-
-            let physical_width;
-            let physical_height;
-
-            if EnumDisplaySettingsW(std::ptr::null(), ENUM_CURRENT_SETTINGS, &mut dev_mode) != 0 {
-                physical_width = dev_mode.dmPelsWidth as i32;
-                physical_height = dev_mode.dmPelsHeight as i32;
-            } else {
-                // Fallback to logical screen size if EnumDisplaySettings fails
-                physical_width = width;
-                physical_height = height;
-            }
+/// Progressive resistance in front of the buffer zone.
+///
+/// Within `distance` pixels of the buffer zone, moves towards it are scaled
+/// down linearly from full speed at the outer edge to `min_scale` at the
+/// buffer zone itself. Moves away from it are never slowed, and a move fast
+/// enough to cross the barrier is still stopped outright.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ApproachZone {
+    /// How far out from the buffer zone the slowdown starts, in pixels
+    pub distance: i32,
+    /// Fraction of a move kept right at the buffer zone, in (0, 1]
+    pub min_scale: f32,
+}
 
-            PHYSICAL_SCREEN_WIDTH.store(physical_width, Ordering::Relaxed);
-            PHYSICAL_SCREEN_HEIGHT.store(physical_height, Ordering::Relaxed);
+/// How far the buffer zone reaches out from each edge of the barrier, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BufferZone {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
 
-            info!(
-                "Screen metrics initialized - Logical: {}x{}, Physical: {}x{}",
-                width, height, physical_width, physical_height
-            );
+impl BufferZone {
+    /// The same width on every edge
+    pub const fn uniform(width: i32) -> Self {
+        Self {
+            top: width,
+            right: width,
+            bottom: width,
+            left: width,
         }
+    }
 
-        // Update the global overlay color
-        CURRENT_OVERLAY_COLOR.store(state.overlay_color, Ordering::Relaxed);
+    /// The width shared by every edge, or `None` if the edges differ
+    pub fn as_uniform(&self) -> Option<i32> {
+        (self.right == self.top && self.bottom == self.top && self.left == self.top)
+            .then_some(self.top)
+    }
 
-        Self
+    /// The width of the narrowest edge
+    pub fn narrowest(&self) -> i32 {
+        self.top.min(self.right).min(self.bottom).min(self.left)
     }
+}
 
-    pub fn enable(&mut self) -> Result<(), String> {
-        let current_hook = MOUSE_HOOK_HANDLE.load(Ordering::Acquire);
-        if !current_hook.is_null() {
-            return Ok(());
-        }
+impl From<i32> for BufferZone {
+    fn from(width: i32) -> Self {
+        Self::uniform(width)
+    }
+}
 
-        let state_lock = MOUSE_BARRIER_STATE.get().unwrap();
-        if let Some(ref mut state) = *state_lock.lock().unwrap() {
-            state.enabled = true;
-        }
+/// The overlay style used instead of translucent tinting while Windows high
+/// contrast is on or transparency effects are turned off: an opaque border
+/// and hatch pattern, with the gaps in the pattern left see-through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighContrastStyle {
+    /// Switch to this style when the system settings ask for it; false keeps
+    /// the normal overlay regardless
+    pub follow_system: bool,
+    /// Border and pattern color; `None` uses the theme's highlight color
+    pub color: Option<(u8, u8, u8)>,
+    pub pattern: HatchPattern,
+}
 
-        // Create overlay windows (4 rectangles)
-        match create_overlay_windows() {
-            Ok(windows) => {
-                for (i, hwnd) in windows.into_iter().enumerate() {
-                    if i < 4 {
-                        OVERLAY_WINDOWS[i].store(hwnd, Ordering::Release);
-                    }
-                }
-                info!("Created overlay windows");
-            }
-            Err(e) => {
-                warn!("Failed to create overlay windows: {}", e);
-            }
+impl Default for HighContrastStyle {
+    fn default() -> Self {
+        Self {
+            follow_system: true,
+            color: None,
+            pattern: HatchPattern::default(),
         }
-
-        // Start middle button monitoring that controls hook installation
-        MIDDLE_BUTTON_MONITORING.store(true, Ordering::Release);
-        thread::spawn(move || {
-            monitor_middle_button_and_control_hook();
-        });
-
-        // Install main mouse hook initially
-        install_mouse_hook()?;
-
-        Ok(())
     }
+}
 
-    pub fn disable(&mut self) -> Result<(), String> {
-        // Stop middle button monitoring
-        MIDDLE_BUTTON_MONITORING.store(false, Ordering::Release);
-
-        let state_lock = MOUSE_BARRIER_STATE.get().unwrap();
-        if let Some(ref mut state) = *state_lock.lock().unwrap() {
-            state.enabled = false;
-        }
+/// Fill pattern of the high-contrast overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HatchPattern {
+    Horizontal,
+    Vertical,
+    Diagonal,
+    Cross,
+    #[default]
+    DiagonalCross,
+}
 
-        uninstall_mouse_hook()?;
+/// The system display settings the overlay and HUD adapt to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessibilitySettings {
+    /// A high contrast theme is active
+    pub high_contrast: bool,
+    /// "Transparency effects" is on in the personalization settings
+    pub transparency_effects: bool,
+}
 
-        // Destroy overlay windows
-        for atomic_ptr in &OVERLAY_WINDOWS {
-            let hwnd = atomic_ptr.swap(ptr::null_mut(), Ordering::AcqRel);
-            if !hwnd.is_null() {
-                unsafe {
-                    DestroyWindow(hwnd);
-                }
-            }
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        Self {
+            high_contrast: false,
+            transparency_effects: true,
         }
-        info!("Destroyed overlay windows");
-
-        Ok(())
     }
+}
 
-    pub fn toggle(&mut self) -> Result<bool, String> {
-        let is_enabled = self.is_enabled();
-        if is_enabled {
-            self.disable()?;
-            Ok(false)
-        } else {
-            self.enable()?;
-            Ok(true)
-        }
+impl AccessibilitySettings {
+    /// Whether translucent and animated drawing should give way to solid colors
+    pub fn prefers_solid(&self) -> bool {
+        self.high_contrast || !self.transparency_effects
     }
+}
 
-    pub fn is_enabled(&self) -> bool {
-        let state_lock = MOUSE_BARRIER_STATE.get().unwrap();
-        if let Some(ref state) = *state_lock.lock().unwrap() {
-            state.enabled
-        } else {
-            false
-        }
-    }
+/// Which corner of the barrier the configured `x`/`y` pair refers to
+///
+/// Both conventions use ordinary screen coordinates (origin at the top-left of
+/// the primary monitor, y growing downward); only the anchored corner differs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoordinateOrigin {
+    /// `y` is the bottom edge and `height` extends upward from it
+    #[default]
+    BottomLeft,
+    /// `y` is the top edge and `height` extends downward from it, matching what
+    /// Windows tools such as screenshot rulers report
+    TopLeft,
+}
 
-    pub fn update_barrier(&mut self, config: MouseBarrierConfig) {
-        let state_lock = MOUSE_BARRIER_STATE.get().unwrap();
-        if let Some(ref mut state) = *state_lock.lock().unwrap() {
-            // Convert from bottom-left origin to Windows top-left origin
-            state.barrier_rect = RECT {
-                left: config.x,
-                top: config.y - config.height, // y is bottom, so top = y - height
-                right: config.x + config.width, // right extends from left
-                bottom: config.y,              // bottom is the y coordinate
-            };
-            state.buffer_zone = config.buffer_zone;
-            state.push_factor = config.push_factor;
-            state.overlay_color = ((config.overlay_color.0 as u32) << 16)
-                | ((config.overlay_color.1 as u32) << 8)
-                | (config.overlay_color.2 as u32);
-            state.overlay_alpha = config.overlay_alpha;
-            state.on_barrier_hit_sound = config.on_barrier_hit_sound;
-            state.on_barrier_entry_sound = config.on_barrier_entry_sound;
-
-            // Update the global overlay color
-            CURRENT_OVERLAY_COLOR.store(state.overlay_color, Ordering::Relaxed);
-        }
+/// Where the barrier is, in screen coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarrierBounds {
+    /// `(x, y)` is the corner `coordinate_origin` names, and the barrier
+    /// extends `width` right and `height` away from it
+    CornerAndSize {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    /// Any two opposite corners, in either order; `coordinate_origin` doesn't
+    /// apply
+    Corners(Point, Point),
+}
 
-        // Update the overlay windows if they exist
-        for atomic_ptr in &OVERLAY_WINDOWS {
-            let hwnd = atomic_ptr.load(Ordering::Acquire);
-            if !hwnd.is_null() {
-                unsafe {
-                    InvalidateRect(hwnd, ptr::null(), TRUE);
-                }
-            }
+impl BarrierBounds {
+    /// The rectangle these bounds describe, reading `CornerAndSize` with `origin`
+    pub fn rect(&self, origin: CoordinateOrigin) -> Rect {
+        match *self {
+            BarrierBounds::CornerAndSize {
+                x,
+                y,
+                width,
+                height,
+            } => barrier_rect(x, y, width, height, origin),
+            BarrierBounds::Corners(a, b) => Rect {
+                left: a.x.min(b.x),
+                top: a.y.min(b.y),
+                right: a.x.max(b.x),
+                bottom: a.y.max(b.y),
+            },
         }
     }
 }
 
-impl Drop for MouseBarrier {
-    fn drop(&mut self) {
-        let _ = self.disable();
+/// Converts configured barrier coordinates into a screen rectangle
+///
+/// This and its inverse [`barrier_coordinates`] are the only places where the
+/// configured coordinate convention is interpreted; the hook, the overlays and
+/// the HUD all go through them.
+pub fn barrier_rect(x: i32, y: i32, width: i32, height: i32, origin: CoordinateOrigin) -> Rect {
+    match origin {
+        CoordinateOrigin::BottomLeft => Rect {
+            left: x,
+            top: y - height, // y is bottom, so top = y - height
+            right: x + width,
+            bottom: y,
+        },
+        CoordinateOrigin::TopLeft => Rect {
+            left: x,
+            top: y, // y is top, so bottom = y + height
+            right: x + width,
+            bottom: y + height,
+        },
     }
 }
 
-impl KeyboardHook {
-    pub fn new<F>(callback: F) -> Self
-    where
-        F: Fn(u32, bool) + Send + Sync + 'static,
-    {
-        let callback_lock = KEYBOARD_CALLBACK.get_or_init(|| Arc::new(Mutex::new(None)));
-        *callback_lock.lock().unwrap() = Some(Box::new(callback));
-
-        // Hook handle will be managed globally via atomic pointer
-
-        Self
-    }
-
-    pub fn enable(&mut self) -> Result<(), String> {
-        let current_hook = KEYBOARD_HOOK_HANDLE.load(Ordering::Acquire);
-        if !current_hook.is_null() {
-            return Ok(());
-        }
-
-        unsafe {
-            let hook = SetWindowsHookExW(
-                WH_KEYBOARD_LL,
-                Some(keyboard_proc),
-                GetModuleHandleW(std::ptr::null()),
-                0,
-            );
+/// The configured `(x, y, width, height)` that describe `rect`, the inverse
+/// of [`barrier_rect`]
+pub fn barrier_coordinates(rect: Rect, origin: CoordinateOrigin) -> (i32, i32, i32, i32) {
+    let y = match origin {
+        CoordinateOrigin::BottomLeft => rect.bottom,
+        CoordinateOrigin::TopLeft => rect.top,
+    };
+    (rect.left, y, rect.right - rect.left, rect.bottom - rect.top)
+}
 
-            if hook.is_null() {
-                return Err(format!("Failed to set keyboard hook: {}", GetLastError()));
-            }
+/// The lines [`MouseBarrierConfig::snap_grid`] rounds barrier edges to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapGrid {
+    /// Multiples of this many pixels
+    Pixels(i32),
+    /// The lines dividing the virtual screen into this many equal columns
+    /// and rows, e.g. 8 for eighths, so a barrier lands on the same fraction
+    /// of the screen at any resolution. The screen is the one the barrier
+    /// was configured on; a later layout change doesn't snap it again.
+    ScreenFraction(i32),
+}
 
-            KEYBOARD_HOOK_HANDLE.store(hook, Ordering::Release);
+impl SnapGrid {
+    /// `rect` with each edge rounded to this grid, laid over `screen`
+    pub fn snap(self, rect: Rect, screen: &Rect) -> Rect {
+        match self {
+            SnapGrid::Pixels(grid) => snap_rect(rect, grid),
+            SnapGrid::ScreenFraction(parts) => snap_rect_to_screen(rect, screen, parts),
         }
-
-        Ok(())
     }
+}
 
-    pub fn disable(&mut self) -> Result<(), String> {
-        let hook = KEYBOARD_HOOK_HANDLE.swap(std::ptr::null_mut(), Ordering::AcqRel);
+/// Rounds each edge of `rect` to the nearest multiple of `grid`, halves
+/// rounding toward the bottom-right
+///
+/// A barrier narrower than one grid cell would collapse to nothing, so it keeps
+/// at least one cell in each direction. Grids below 2 leave the rectangle alone.
+pub fn snap_rect(rect: Rect, grid: i32) -> Rect {
+    if grid < 2 {
+        return rect;
+    }
 
-        if !hook.is_null() {
-            unsafe {
-                if UnhookWindowsHookEx(hook) == 0 {
-                    return Err(format!("Failed to unhook keyboard: {}", GetLastError()));
-                }
-            }
-        }
+    let axis = GridAxis {
+        origin: 0,
+        spacing: grid.into(),
+        parts: 1,
+    };
+    snap_rect_on(rect, &axis, &axis)
+}
 
-        Ok(())
+/// Rounds each edge of `rect` to the nearest line dividing `screen` into
+/// `parts` columns and rows, as [`snap_rect`] does for a pixel grid. Lines
+/// continue past the screen at the same spacing; a fraction below 1 or an
+/// empty screen leaves the rectangle alone.
+pub fn snap_rect_to_screen(rect: Rect, screen: &Rect, parts: i32) -> Rect {
+    let (width, height) = (screen.right - screen.left, screen.bottom - screen.top);
+    if parts < 1 || width <= 0 || height <= 0 {
+        return rect;
     }
+
+    let columns = GridAxis {
+        origin: screen.left.into(),
+        spacing: width.into(),
+        parts: parts.into(),
+    };
+    let rows = GridAxis {
+        origin: screen.top.into(),
+        spacing: height.into(),
+        parts: parts.into(),
+    };
+    snap_rect_on(rect, &columns, &rows)
 }
 
-impl Drop for KeyboardHook {
-    fn drop(&mut self) {
-        let _ = self.disable();
+fn snap_rect_on(rect: Rect, columns: &GridAxis, rows: &GridAxis) -> Rect {
+    let (left, right) = columns.snap(rect.left, rect.right);
+    let (top, bottom) = rows.snap(rect.top, rect.bottom);
+    Rect {
+        left,
+        top,
+        right,
+        bottom,
     }
 }
 
-pub fn set_mouse_position_callback<F>(callback: F)
-where
-    F: Fn(i32, i32) + Send + Sync + 'static,
-{
-    let callback_lock = MOUSE_POSITION_CALLBACK.get_or_init(|| Arc::new(Mutex::new(None)));
-    if let Ok(mut guard) = callback_lock.lock() {
-        *guard = Some(Box::new(callback));
-    }
+/// Grid lines along one axis, `spacing / parts` apart starting at `origin`.
+/// Wide enough that scaling any pair of `i32`s by `parts` can't overflow.
+struct GridAxis {
+    origin: i128,
+    spacing: i128,
+    parts: i128,
 }
 
-unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
-    if code >= 0 && wparam == WM_MOUSEMOVE as WPARAM {
-        let mouse_data = *(lparam as *const MSLLHOOKSTRUCT);
-        let current_pos = mouse_data.pt;
+impl GridAxis {
+    /// Snaps the span `start..end`, keeping at least one cell of it
+    fn snap(&self, start: i32, end: i32) -> (i32, i32) {
+        let first = self.nearest(start);
+        let last = self.nearest(end).max(first + 1);
+        (self.line(first), self.line(last))
+    }
 
-        // Update HUD with current mouse position
-        if let Some(callback_lock) = MOUSE_POSITION_CALLBACK.get() {
-            if let Ok(callback_guard) = callback_lock.lock() {
-                if let Some(ref callback) = *callback_guard {
-                    callback(current_pos.x, current_pos.y);
-                }
-            }
-        }
+    /// The index of the line nearest `value`, halves rounding up
+    fn nearest(&self, value: i32) -> i128 {
+        let offset = i128::from(value) - self.origin;
+        (offset * 2 * self.parts + self.spacing).div_euclid(2 * self.spacing)
+    }
 
-        if let Some(state_lock) = MOUSE_BARRIER_STATE.get() {
-            if let Ok(state_guard) = state_lock.lock() {
-                if let Some(ref state) = *state_guard {
-                    if state.enabled {
-                        // Get last mouse position for trajectory checking
-                        let last_pos = if let Ok(mut last_pos_guard) = LAST_MOUSE_POS.lock() {
-                            let last = *last_pos_guard;
-                            *last_pos_guard = Some(current_pos);
-                            last
-                        } else {
-                            None
-                        };
-
-                        // Create buffer zone rect
-                        let buffer_rect = RECT {
-                            left: state.barrier_rect.left - state.buffer_zone,
-                            top: state.barrier_rect.top - state.buffer_zone,
-                            right: state.barrier_rect.right + state.buffer_zone,
-                            bottom: state.barrier_rect.bottom + state.buffer_zone,
-                        };
-
-                        // First, check trajectory for fast movements
-                        if let Some(last) = last_pos {
-                            if let Some(safe_pos) = check_movement_path(
-                                &last,
-                                &current_pos,
-                                &state.barrier_rect,
-                                &buffer_rect,
-                            ) {
-                                // Movement would pass through barrier, stop at safe position
-                                SetCursorPos(safe_pos.x, safe_pos.y);
-                                return 1;
-                            }
-
-                            // Predictive positioning - check where cursor is heading
-                            let dx = current_pos.x - last.x;
-                            let dy = current_pos.y - last.y;
-                            let predicted_pos = POINT {
-                                x: current_pos.x + dx,
-                                y: current_pos.y + dy,
-                            };
-
-                            // If predicted position would be in barrier, stop now
-                            if point_in_rect(&predicted_pos, &state.barrier_rect) {
-                                // Find a safe position just outside the buffer
-                                let push_factor = calculate_dynamic_push_factor(
-                                    state.push_factor,
-                                    &last,
-                                    &current_pos,
-                                );
-                                let safe_pos =
-                                    push_point_out_of_rect(&current_pos, &buffer_rect, push_factor);
-                                SetCursorPos(safe_pos.x, safe_pos.y);
-                                return 1;
-                            }
-                        }
-
-                        if point_in_rect(&current_pos, &state.barrier_rect) {
-                            warn!(x = current_pos.x, y = current_pos.y, "Cursor in barrier!");
-
-                            // Play barrier entry sound if this is the first time
-                            if !HAS_ENTERED_BARRIER.load(Ordering::Acquire) {
-                                HAS_ENTERED_BARRIER.store(true, Ordering::Release);
-                                if let Some(ref sound_path) = state.on_barrier_entry_sound {
-                                    play_sound_async(sound_path);
-                                }
-                            }
-                        } else {
-                            // Reset the flag when cursor leaves barrier
-                            HAS_ENTERED_BARRIER.store(false, Ordering::Release);
-                        }
-
-                        let in_buffer = point_in_rect(&current_pos, &buffer_rect);
-                        let was_in_buffer = LAST_IN_BARRIER.load(Ordering::Acquire);
-
-                        if in_buffer != was_in_buffer {
-                            LAST_IN_BARRIER.store(in_buffer, Ordering::Release);
-
-                            // Play barrier hit sound when entering buffer zone
-                            if in_buffer {
-                                if let Some(ref sound_path) = state.on_barrier_hit_sound {
-                                    play_sound_async(sound_path);
-                                }
-                            }
-                        }
-
-                        if in_buffer {
-                            // Calculate dynamic push factor based on movement speed
-                            let push_factor = if let Some(last) = last_pos {
-                                calculate_dynamic_push_factor(
-                                    state.push_factor,
-                                    &last,
-                                    &current_pos,
-                                )
-                            } else {
-                                state.push_factor
-                            };
-
-                            let new_pos =
-                                push_point_out_of_rect(&current_pos, &buffer_rect, push_factor);
-
-                            SetCursorPos(new_pos.x, new_pos.y);
-
-                            return 1;
-                        }
-                    }
-                }
-            }
-        }
+    fn line(&self, index: i128) -> i32 {
+        let line = self.origin + (index * self.spacing).div_euclid(self.parts);
+        line.clamp(i128::from(i32::MIN), i128::from(i32::MAX)) as i32
     }
+}
 
-    CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+/// How a barrier sits on the virtual screen (all monitors together)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScreenPlacement {
+    #[default]
+    OnScreen,
+    /// Part of the barrier hangs past the edge of the screen
+    PartlyOffScreen,
+    /// None of the barrier is on screen, so the cursor can never reach it
+    OffScreen,
+    /// The configured barrier lay entirely off-screen and was moved onto the
+    /// screen, keeping its size
+    MovedOnScreen,
 }
 
-unsafe extern "system" fn keyboard_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
-    if code >= 0 {
-        if let Some(callback_lock) = KEYBOARD_CALLBACK.get() {
-            if let Ok(callback_guard) = callback_lock.lock() {
-                if let Some(ref callback) = *callback_guard {
-                    let kbd_data = *(lparam as *const KBDLLHOOKSTRUCT);
-                    let is_key_down =
-                        wparam == WM_KEYDOWN as WPARAM || wparam == WM_SYSKEYDOWN as WPARAM;
-                    callback(kbd_data.vkCode, is_key_down);
-                }
-            }
-        }
+/// Where a barrier configured at `rect` ends up on `screen`.
+///
+/// Only a barrier lying entirely off-screen is moved, and only with `clamp`;
+/// one hanging past an edge may well be meant that way.
+pub(crate) fn place_on_screen(rect: Rect, screen: &Rect, clamp: bool) -> (Rect, ScreenPlacement) {
+    let overlaps = rect.left < screen.right
+        && rect.right > screen.left
+        && rect.top < screen.bottom
+        && rect.bottom > screen.top;
+    if !overlaps {
+        return if clamp {
+            (
+                move_onto_screen(rect, screen),
+                ScreenPlacement::MovedOnScreen,
+            )
+        } else {
+            (rect, ScreenPlacement::OffScreen)
+        };
     }
 
-    CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+    let inside = rect.left >= screen.left
+        && rect.right <= screen.right
+        && rect.top >= screen.top
+        && rect.bottom <= screen.bottom;
+    let placement = if inside {
+        ScreenPlacement::OnScreen
+    } else {
+        ScreenPlacement::PartlyOffScreen
+    };
+    (rect, placement)
 }
 
-fn install_mouse_hook() -> Result<(), String> {
-    let current_hook = MOUSE_HOOK_HANDLE.load(Ordering::Acquire);
-    if !current_hook.is_null() {
-        return Ok(());
+/// `rect` moved the shortest distance that puts it on `screen`; a rectangle
+/// larger than the screen is aligned to its top-left corner
+fn move_onto_screen(rect: Rect, screen: &Rect) -> Rect {
+    let dx = shift_into(rect.left, rect.right, screen.left, screen.right);
+    let dy = shift_into(rect.top, rect.bottom, screen.top, screen.bottom);
+    Rect {
+        left: rect.left + dx,
+        top: rect.top + dy,
+        right: rect.right + dx,
+        bottom: rect.bottom + dy,
     }
+}
 
-    unsafe {
-        let hook = SetWindowsHookExW(
-            WH_MOUSE_LL,
-            Some(mouse_proc),
-            GetModuleHandleW(std::ptr::null()),
-            0,
-        );
+/// How far to move the span `start..end` so it lies within `min..max`
+fn shift_into(start: i32, end: i32, min: i32, max: i32) -> i32 {
+    if start < min || end - start > max - min {
+        min - start
+    } else if end > max {
+        max - end
+    } else {
+        0
+    }
+}
 
-        if hook.is_null() {
-            return Err(format!("Failed to set mouse hook: {}", GetLastError()));
+impl MouseBarrierConfig {
+    /// The barrier rectangle this configuration describes on `screen`,
+    /// snapped to `snap_grid` when one is set
+    pub(crate) fn rect(&self, screen: &Rect) -> Rect {
+        let rect = self.bounds.rect(self.coordinate_origin);
+        match self.snap_grid {
+            Some(grid) => grid.snap(rect, screen),
+            None => rect,
         }
-
-        MOUSE_HOOK_HANDLE.store(hook, Ordering::Release);
     }
-    Ok(())
 }
 
-fn uninstall_mouse_hook() -> Result<(), String> {
-    let hook = MOUSE_HOOK_HANDLE.swap(std::ptr::null_mut(), Ordering::AcqRel);
+/// A sound cue the barrier can play
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sound {
+    /// Path to a WAV file; a relative path is resolved against the working
+    /// directory when it plays
+    File(String),
+    Builtin(BuiltinSound),
+}
 
-    if !hook.is_null() {
-        unsafe {
-            if UnhookWindowsHookEx(hook) == 0 {
-                return Err(format!("Failed to unhook mouse: {}", GetLastError()));
-            }
-        }
-    }
-    Ok(())
+/// Sounds embedded in the library, so cues work without shipping any files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinSound {
+    /// A short tick
+    Click,
+    /// Two rising notes
+    Chime,
+    /// A quiet, very short tick; the default for buffer zone hits
+    Tick,
+    /// A low thump; the default for reaching the barrier itself
+    Thunk,
 }
 
-pub fn process_hook_requests() {
-    // Check for uninstall requests
-    if HOOK_UNINSTALL_REQUESTED.swap(false, Ordering::AcqRel) {
-        if let Err(e) = uninstall_mouse_hook() {
-            warn!("Failed to uninstall mouse hook: {}", e);
-        } else {
-            info!("Uninstalled mouse hook due to middle button press");
+impl BuiltinSound {
+    pub const ALL: [BuiltinSound; 4] = [
+        BuiltinSound::Click,
+        BuiltinSound::Chime,
+        BuiltinSound::Tick,
+        BuiltinSound::Thunk,
+    ];
+
+    /// The name configs refer to this sound by, e.g. `click`
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinSound::Click => "click",
+            BuiltinSound::Chime => "chime",
+            BuiltinSound::Tick => "tick",
+            BuiltinSound::Thunk => "thunk",
         }
     }
 
-    // Check for install requests
-    if HOOK_INSTALL_REQUESTED.swap(false, Ordering::AcqRel) {
-        if let Err(e) = install_mouse_hook() {
-            warn!("Failed to reinstall mouse hook: {}", e);
-        } else {
-            info!("Reinstalled mouse hook after middle button release");
-        }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|sound| sound.name().eq_ignore_ascii_case(name))
     }
-}
 
-fn monitor_middle_button_and_control_hook() {
-    let mut last_middle_state = false;
-
-    while MIDDLE_BUTTON_MONITORING.load(Ordering::Acquire) {
-        unsafe {
-            let middle_pressed = GetAsyncKeyState(VK_MBUTTON) & 0x8000u16 as i16 != 0;
-
-            // Detect state changes
-            if middle_pressed != last_middle_state {
-                if middle_pressed {
-                    // Middle button pressed - request hook uninstall
-                    HOOK_UNINSTALL_REQUESTED.store(true, Ordering::Release);
-                    info!("Requested mouse hook uninstall due to middle button press");
-                } else {
-                    // Middle button released - request hook reinstall if barrier is enabled
-                    if let Some(state_lock) = MOUSE_BARRIER_STATE.get() {
-                        if let Ok(state_guard) = state_lock.lock() {
-                            if let Some(ref state) = *state_guard {
-                                if state.enabled {
-                                    HOOK_INSTALL_REQUESTED.store(true, Ordering::Release);
-                                    info!("Requested mouse hook reinstall after middle button release");
-                                }
-                            }
-                        }
-                    }
-                }
-                last_middle_state = middle_pressed;
-            }
-
-            MIDDLE_MOUSE_DOWN.store(middle_pressed, Ordering::Relaxed);
+    /// The embedded WAV file
+    #[cfg_attr(all(windows, not(feature = "audio")), allow(dead_code))]
+    pub(crate) fn wav(self) -> &'static [u8] {
+        match self {
+            BuiltinSound::Click => include_bytes!("../assets/click.wav"),
+            BuiltinSound::Chime => include_bytes!("../assets/chime.wav"),
+            BuiltinSound::Tick => include_bytes!("../assets/tick.wav"),
+            BuiltinSound::Thunk => include_bytes!("../assets/thunk.wav"),
         }
-        thread::sleep(Duration::from_millis(5)); // 200Hz polling for responsiveness
     }
 }
 
-fn point_in_rect(point: &POINT, rect: &RECT) -> bool {
-    point.x >= rect.left && point.x < rect.right && point.y >= rect.top && point.y < rect.bottom
+/// Where a position passed to the mouse position callback came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionSource {
+    /// Reported by the low-level mouse hook for a WM_MOUSEMOVE
+    Hook,
+    /// Polled at ~30 Hz while the middle-button bypass has the hook uninstalled
+    Polled,
 }
 
-fn play_sound_async(sound_path: &str) {
-    let path = sound_path.to_string();
-    thread::spawn(move || {
-        unsafe {
-            // Load winmm.dll dynamically
-            let winmm_name: Vec<u16> = "winmm\0".encode_utf16().collect();
-            let winmm = LoadLibraryW(winmm_name.as_ptr());
-            if winmm.is_null() {
-                warn!("Failed to load winmm.dll for audio playback");
-                return;
-            }
-
-            // Get PlaySoundW function
-            let playsound_name = b"PlaySoundW\0";
-            let playsound_proc = GetProcAddress(winmm, playsound_name.as_ptr() as *const i8);
-            if playsound_proc.is_null() {
-                warn!("Failed to find PlaySoundW function");
-                return;
-            }
+/// Where a push goes when sending the cursor straight out of the nearest side
+/// of the buffer zone would put it off the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OffscreenPush {
+    /// Push out the opposite side instead, across the barrier
+    FlipDirection,
+    /// Stop at the screen boundary just outside the barrier
+    #[default]
+    ClampToEdge,
+    /// Move along the blocked edge out of whichever end of the buffer is closer
+    SlideAlongEdge,
+}
 
-            // Cast to function pointer and call
-            type PlaySoundWFn = unsafe extern "system" fn(*const u16, HMODULE, u32) -> i32;
-            let playsound_fn: PlaySoundWFn = std::mem::transmute(playsound_proc);
-
-            let wide_path: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
-            // SND_FILENAME = 0x00020000, SND_ASYNC = 0x0001, SND_NODEFAULT = 0x0002
-            playsound_fn(
-                wide_path.as_ptr(),
-                std::ptr::null_mut(),
-                0x00020000 | 0x0001 | 0x0002,
-            );
-        }
-    });
+/// Which crossing counts as entering the barrier for the entry sound
+///
+/// The hit sound always plays when the cursor reaches the buffer zone from
+/// outside it; this only changes when the entry sound plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntryCue {
+    /// The cursor reached the barrier itself. Pushes keep it out of there, so
+    /// in practice this means a teleport or a flick too fast to stop
+    #[default]
+    Barrier,
+    /// The cursor crossed into the buffer zone from outside it, at the same
+    /// moment as the hit sound
+    BufferZone,
 }
 
-fn check_movement_path(start: &POINT, end: &POINT, barrier: &RECT, buffer: &RECT) -> Option<POINT> {
-    // Skip if movement is too small
-    let dx = end.x - start.x;
-    let dy = end.y - start.y;
-    if dx.abs() < 2 && dy.abs() < 2 {
-        return None;
-    }
+/// Which parts of the barrier the overlay windows tint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlayCoverage {
+    /// Frame the buffer zone around the barrier
+    #[default]
+    BufferOnly,
+    /// Tint only the barrier interior
+    BarrierOnly,
+    /// Frame the buffer zone and tint the interior
+    Both,
+}
 
-    // Check multiple points along the movement path
-    let steps = 10; // More steps for better accuracy
-    for i in 1..=steps {
-        let t = i as f32 / steps as f32;
-        let check_point = POINT {
-            x: (start.x as f32 + dx as f32 * t) as i32,
-            y: (start.y as f32 + dy as f32 * t) as i32,
-        };
+/// How the overlay's opacity changes over time, advanced by the overlay timer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlayAnimation {
+    /// Hold the configured alpha
+    #[default]
+    Steady,
+    /// Fade each strip down to `min_alpha` and back to its configured alpha
+    /// once every `period_ms`
+    Pulse { period_ms: u32, min_alpha: u8 },
+}
 
-        // Check if this intermediate point hits the barrier
-        if point_in_rect(&check_point, barrier) {
-            // Find the last safe point outside the buffer zone
-            for j in (0..i).rev() {
-                let safe_t = j as f32 / steps as f32;
-                let safe_point = POINT {
-                    x: (start.x as f32 + dx as f32 * safe_t) as i32,
-                    y: (start.y as f32 + dy as f32 * safe_t) as i32,
-                };
+/// What [`MouseBarrier::enable`] does when the overlay windows can't be created
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlayFailurePolicy {
+    /// Enforce the barrier without an overlay and report it through
+    /// [`MouseBarrier::overlay_unavailable`]
+    #[default]
+    ContinueInvisible,
+    /// Leave the barrier disabled and return the error
+    FailEnable,
+}
 
-                if !point_in_rect(&safe_point, buffer) {
-                    return Some(safe_point);
-                }
-            }
-            // If no safe point found, return start position
-            return Some(*start);
-        }
-    }
-    None
+/// The part of the overlay one overlay window draws
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverlayRegion {
+    /// The buffer zone above the barrier
+    Top,
+    /// The buffer zone below the barrier
+    Bottom,
+    /// The buffer zone left of the barrier, between `Top` and `Bottom`
+    Left,
+    /// The buffer zone right of the barrier, between `Top` and `Bottom`
+    Right,
+    /// The barrier itself
+    Interior,
 }
 
-fn calculate_dynamic_push_factor(base_factor: i32, last_pos: &POINT, current_pos: &POINT) -> i32 {
-    let dx = (current_pos.x - last_pos.x) as f64;
-    let dy = (current_pos.y - last_pos.y) as f64;
-    let speed = (dx * dx + dy * dy).sqrt();
+/// Whether the barrier acts on the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnforcementMode {
+    /// Push the cursor out of the barrier
+    #[default]
+    Enforce,
+    /// A dry run: overlays, zones, sounds and events work as usual, but the
+    /// cursor is never moved; each push held back is counted in
+    /// [`BarrierStats::would_have_pushed`]
+    WarnOnly,
+}
 
-    // Scale push factor: faster movement = larger push
-    // Speed 10 = 1x, Speed 50 = 2x, Speed 100+ = 3x
-    let multiplier = (speed / 25.0).clamp(1.0, 3.0);
-    (base_factor as f64 * multiplier) as i32
+/// A modifier key that lifts the barrier while held; either the left or the
+/// right key will do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BypassModifier {
+    Ctrl,
+    Alt,
+    Shift,
+    Win,
 }
 
-fn push_point_out_of_rect(point: &POINT, rect: &RECT, push_factor: i32) -> POINT {
-    // Use cached screen metrics
-    let screen_width = SCREEN_WIDTH.load(Ordering::Relaxed);
-    let screen_height = SCREEN_HEIGHT.load(Ordering::Relaxed);
-
-    // Determine which edge the mouse is closest to and push away from that edge
-    let dist_to_left = point.x - rect.left;
-    let dist_to_right = rect.right - point.x;
-    let dist_to_top = point.y - rect.top;
-    let dist_to_bottom = rect.bottom - point.y;
-
-    // Find the minimum distance to determine which edge to push from
-    let min_dist = dist_to_left
-        .min(dist_to_right)
-        .min(dist_to_top)
-        .min(dist_to_bottom);
-
-    let new_point = if min_dist == dist_to_left {
-        // Push left, but ensure we don't go below 0
-        let target_x = rect.left - push_factor;
-        POINT {
-            x: if target_x < 0 {
-                // If pushing left would go off-screen, push right instead
-                rect.right + push_factor
-            } else {
-                target_x
-            },
-            y: point.y,
-        }
-    } else if min_dist == dist_to_right {
-        // Push right, but ensure we don't exceed screen width
-        let target_x = rect.right + push_factor;
-        POINT {
-            x: if target_x >= screen_width {
-                // If pushing right would go off-screen, push left instead
-                (rect.left - push_factor).max(0)
-            } else {
-                target_x
-            },
-            y: point.y,
-        }
-    } else if min_dist == dist_to_top {
-        // Push up, but ensure we don't go below 0
-        let target_y = rect.top - push_factor;
-        POINT {
-            x: point.x,
-            y: if target_y < 0 {
-                // If pushing up would go off-screen, push down instead
-                rect.bottom + push_factor
-            } else {
-                target_y
-            },
-        }
-    } else {
-        // Push down, but ensure we don't exceed screen height
-        let target_y = rect.bottom + push_factor;
-        POINT {
-            x: point.x,
-            y: if target_y >= screen_height {
-                // If pushing down would go off-screen, push up instead
-                (rect.top - push_factor).max(0)
-            } else {
-                target_y
-            },
-        }
-    };
+/// Runtime counters collected by the hooks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BarrierStats {
+    /// Pushes abandoned because `SetCursorPos` failed twice in a row
+    pub cursor_move_failures: u64,
+    /// Times the keyboard hook was reinstalled after Windows silently dropped it
+    pub keyboard_hook_reinstalls: u64,
+    /// Times the mouse hook was reinstalled after Windows silently dropped it
+    pub mouse_hook_reinstalls: u64,
+    /// Pushes [`EnforcementMode::WarnOnly`] held back
+    pub would_have_pushed: u64,
+    /// How fast the cursor has been moving, now and at its fastest
+    pub cursor_speed: CursorSpeed,
+}
 
-    // Convert from physical coordinates to logical coordinates for SetCursorPos
-    // Get actual physical screen resolution instead of using hardcoded values
-    let physical_width = PHYSICAL_SCREEN_WIDTH.load(Ordering::Relaxed) as f64;
-    let physical_height = PHYSICAL_SCREEN_HEIGHT.load(Ordering::Relaxed) as f64;
-    let scale_x = screen_width as f64 / physical_width;
-    let scale_y = screen_height as f64 / physical_height;
+/// A consistent copy of the barrier the mouse hook is enforcing
+///
+/// Anything that reports on the barrier, like the HUD, should read it from here
+/// and classify positions with [`BarrierSnapshot::zone_at`] so it agrees with the
+/// hook on every pixel, edges included.
+#[derive(Debug, Clone, Copy)]
+pub struct BarrierSnapshot {
+    /// Barrier rectangle in screen coordinates; `right` and `bottom` are exclusive
+    pub rect: Rect,
+    /// Width of the buffer zone around each edge of `rect`, in pixels
+    pub buffer: BufferZone,
+    pub enabled: bool,
+    pub push_factor: i32,
+    pub enforcement_mode: EnforcementMode,
+}
 
-    let logical_x = (new_point.x as f64 * scale_x).round() as i32;
-    let logical_y = (new_point.y as f64 * scale_y).round() as i32;
+impl BarrierSnapshot {
+    /// The barrier grown by the buffer zone on each side
+    pub fn buffer_rect(&self) -> Rect {
+        buffer_rect(&self.rect, self.buffer)
+    }
 
-    POINT {
-        x: logical_x.clamp(0, screen_width - 1),
-        y: logical_y.clamp(0, screen_height - 1),
+    /// Classifies a screen position exactly as the mouse hook does.
+    ///
+    /// This is pure geometry; it doesn't look at `enabled`.
+    pub fn zone_at(&self, x: i32, y: i32) -> Zone {
+        classify_zone(&Point { x, y }, &self.rect, &self.buffer_rect())
     }
 }
 
-unsafe extern "system" fn window_proc(
-    hwnd: HWND,
-    msg: UINT,
-    wparam: WPARAM,
-    lparam: LPARAM,
-) -> LRESULT {
-    match msg {
-        WM_PAINT => {
-            let mut ps: PAINTSTRUCT = mem::zeroed();
-            let hdc = BeginPaint(hwnd, &mut ps);
-
-            // Draw overlay rectangle with configured color
-            let color = CURRENT_OVERLAY_COLOR.load(Ordering::Relaxed);
-            let r = ((color >> 16) & 0xFF) as u8;
-            let g = ((color >> 8) & 0xFF) as u8;
-            let b = (color & 0xFF) as u8;
-
-            let brush = CreateSolidBrush(RGB(r, g, b));
-            let mut client_rect = RECT {
-                left: 0,
-                top: 0,
-                right: 0,
-                bottom: 0,
-            };
-            GetClientRect(hwnd, &mut client_rect);
-            FillRect(hdc, &client_rect, brush);
-            DeleteObject(brush as *mut _);
+/// The barrier and the last cursor move as the mouse hook saw them, for
+/// drawing what the enforcement works with while tuning it
+#[derive(Debug, Clone, Copy)]
+pub struct HookView {
+    pub snapshot: BarrierSnapshot,
+    /// What the hook decided for the last cursor move it handled, if any
+    pub decision: Option<BarrierDecision>,
+}
 
-            EndPaint(hwnd, &ps);
-            0
-        }
-        WM_ERASEBKGND => {
-            1 // Return non-zero to indicate we handled it
-        }
-        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
-    }
+/// Where a barrier is on screen and how its overlay is drawn, as returned by
+/// `MouseBarrier::current_geometry`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BarrierGeometry {
+    /// Barrier rectangle in screen coordinates; `right` and `bottom` are exclusive
+    pub rect: Rect,
+    /// Width of the buffer zone around each edge of `rect`, in pixels
+    pub buffer: BufferZone,
+    /// `rect` grown by `buffer` on each side
+    pub buffer_rect: Rect,
+    pub overlay_color: (u8, u8, u8),
+    pub buffer_alpha: u8,
+    /// Alpha of the interior tint, drawn when `overlay_coverage` includes it
+    pub interior_alpha: u8,
+    /// Alpha the buffer frame flashes to on a barrier hit
+    pub flash_alpha: u8,
+    pub overlay_coverage: OverlayCoverage,
 }
 
-fn create_overlay_windows() -> Result<Vec<HWND>, String> {
-    let state_lock = MOUSE_BARRIER_STATE.get().unwrap();
-    let mut windows = Vec::new();
-
-    if let Ok(state_guard) = state_lock.lock() {
-        if let Some(ref state) = *state_guard {
-            // Calculate positions for 4 windows
-            let screen_width = unsafe { GetSystemMetrics(SM_CXSCREEN) };
-            let screen_height = unsafe { GetSystemMetrics(SM_CYSCREEN) };
-            let physical_width = PHYSICAL_SCREEN_WIDTH.load(Ordering::Relaxed) as f64;
-            let physical_height = PHYSICAL_SCREEN_HEIGHT.load(Ordering::Relaxed) as f64;
-            let scale_x = screen_width as f64 / physical_width;
-            let scale_y = screen_height as f64 / physical_height;
-
-            let barrier_left = (state.barrier_rect.left as f64 * scale_x).round() as i32;
-            let barrier_top = (state.barrier_rect.top as f64 * scale_y).round() as i32;
-            let barrier_right = (state.barrier_rect.right as f64 * scale_x).round() as i32;
-            let barrier_bottom = (state.barrier_rect.bottom as f64 * scale_y).round() as i32;
-
-            let scaled_buffer = (state.buffer_zone as f64 * scale_x).round() as i32;
-            let buffer_left = barrier_left - scaled_buffer;
-            let buffer_top = barrier_top - scaled_buffer;
-            let buffer_right = barrier_right + scaled_buffer;
-            let buffer_bottom = barrier_bottom + scaled_buffer;
-
-            // Create 4 windows - top, bottom, left, right
-            let clamped_buffer_bottom = buffer_bottom.min(screen_height);
-            let clamped_buffer_top = buffer_top.max(0);
-            let clamped_buffer_left = buffer_left.max(0);
-            let clamped_buffer_right = buffer_right.min(screen_width);
-
-            let window_configs = [
-                (
-                    "top",
-                    clamped_buffer_left,
-                    clamped_buffer_top,
-                    clamped_buffer_right - clamped_buffer_left,
-                    barrier_top - clamped_buffer_top,
-                ),
-                (
-                    "bottom",
-                    clamped_buffer_left,
-                    barrier_bottom,
-                    clamped_buffer_right - clamped_buffer_left,
-                    clamped_buffer_bottom - barrier_bottom,
-                ),
-                (
-                    "left",
-                    clamped_buffer_left,
-                    barrier_top,
-                    barrier_left - clamped_buffer_left,
-                    barrier_bottom - barrier_top,
-                ),
-                (
-                    "right",
-                    barrier_right,
-                    barrier_top,
-                    clamped_buffer_right - barrier_right,
-                    barrier_bottom - barrier_top,
-                ),
-            ];
-
-            for (name, x, y, width, height) in window_configs.iter() {
-                if *width > 0 && *height > 0 {
-                    match create_single_overlay_window(
-                        *x,
-                        *y,
-                        *width,
-                        *height,
-                        state.overlay_color,
-                        state.overlay_alpha,
-                    ) {
-                        Ok(hwnd) => windows.push(hwnd),
-                        Err(e) => return Err(format!("Failed to create {} window: {}", name, e)),
-                    }
-                }
-            }
+impl BarrierGeometry {
+    pub(crate) fn new(
+        rect: Rect,
+        buffer: BufferZone,
+        overlay_color: (u8, u8, u8),
+        buffer_alpha: u8,
+        interior_alpha: u8,
+        flash_alpha: u8,
+        overlay_coverage: OverlayCoverage,
+    ) -> Self {
+        Self {
+            rect,
+            buffer,
+            buffer_rect: buffer_rect(&rect, buffer),
+            overlay_color,
+            buffer_alpha,
+            interior_alpha,
+            flash_alpha,
+            overlay_coverage,
         }
     }
+}
 
-    Ok(windows)
+/// Notable barrier state changes, delivered to the callback registered with
+/// [`set_event_callback`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarrierEvent {
+    /// The first bypass holder arrived and the mouse hook is being removed
+    BypassStarted,
+    /// The last bypass holder left and the mouse hook is being restored
+    BypassEnded,
+    /// The barrier was placed on the screen differently than before, because
+    /// its configuration or the screen layout changed
+    PlacementChanged(ScreenPlacement),
+    /// The edit modifiers were released after moving or resizing the barrier
+    /// to this rectangle, in screen coordinates
+    Edited(Rect),
+    /// The barrier pushed the cursor; [`last_push`] has the details. Sent
+    /// from `process_hook_requests` rather than the hook, once for all the
+    /// pushes since it last ran.
+    Pushed,
 }
 
-fn create_single_overlay_window(
-    x: i32,
-    y: i32,
-    width: i32,
-    height: i32,
-    _color: u32,
-    alpha: u8,
-) -> Result<HWND, String> {
-    unsafe {
-        let instance = GetModuleHandleW(ptr::null());
-        let class_name: Vec<u16> = "MouseBarrierOverlay\0".encode_utf16().collect();
-
-        // Check if class is already registered
-        let mut wc_existing: WNDCLASSEXW = mem::zeroed();
-        wc_existing.cbSize = mem::size_of::<WNDCLASSEXW>() as u32;
-
-        if GetClassInfoExW(instance, class_name.as_ptr(), &mut wc_existing) == 0 {
-            // Class not registered, so register it
-            let wc = WNDCLASSEXW {
-                cbSize: mem::size_of::<WNDCLASSEXW>() as u32,
-                style: CS_HREDRAW | CS_VREDRAW,
-                lpfnWndProc: Some(window_proc),
-                cbClsExtra: 0,
-                cbWndExtra: 0,
-                hInstance: instance,
-                hIcon: ptr::null_mut(),
-                hCursor: ptr::null_mut(),
-                hbrBackground: ptr::null_mut(), // No background brush
-                lpszMenuName: ptr::null(),
-                lpszClassName: class_name.as_ptr(),
-                hIconSm: ptr::null_mut(),
-            };
+/// Why a [`MouseBarrier`] call failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BarrierError {
+    /// There is no barrier state yet; `MouseBarrier::new` sets it up
+    NotInitialized,
+    /// Installing a hook or creating the overlay windows failed
+    Win32(String),
+}
 
-            if RegisterClassExW(&wc) == 0 {
-                return Err(format!(
-                    "Failed to register window class: {}",
-                    GetLastError()
-                ));
+impl std::fmt::Display for BarrierError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BarrierError::NotInitialized => {
+                write!(f, "Mouse barrier used before MouseBarrier::new")
             }
+            BarrierError::Win32(message) => write!(f, "{}", message),
         }
+    }
+}
 
-        // Use the provided window dimensions
-
-        let hwnd = CreateWindowExW(
-            WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
-            class_name.as_ptr(),
-            class_name.as_ptr(),
-            WS_POPUP,
-            x,
-            y,
-            width,
-            height,
-            ptr::null_mut(),
-            ptr::null_mut(),
-            instance,
-            ptr::null_mut(),
-        );
-
-        if hwnd.is_null() {
-            return Err(format!("Failed to create window: {}", GetLastError()));
-        }
-
-        // Use configurable alpha transparency
-        SetLayeredWindowAttributes(hwnd, 0, alpha, LWA_ALPHA);
-
-        ShowWindow(hwnd, SW_SHOW);
-        UpdateWindow(hwnd);
+impl std::error::Error for BarrierError {}
 
-        Ok(hwnd)
+impl From<String> for BarrierError {
+    fn from(message: String) -> Self {
+        BarrierError::Win32(message)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use engine::{BarrierEngine, EngineAction, ScreenMetrics};
+    use std::time::Instant;
 
     #[test]
     fn test_mouse_barrier_config_creation() {
         let config = MouseBarrierConfig {
-            x: 100,
-            y: 200,
-            width: 300,
-            height: 150,
-            buffer_zone: 25,
+            bounds: BarrierBounds::CornerAndSize {
+                x: 100,
+                y: 200,
+                width: 300,
+                height: 150,
+            },
+            buffer_zone: BufferZone::uniform(25),
+            buffer_blocks: false,
             push_factor: 50,
+            dynamic_push: false,
+            offscreen_push: OffscreenPush::SlideAlongEdge,
+            screen_margin: 0,
+            approach_zone: None,
+            overlay_enabled: true,
             overlay_color: (255, 128, 64),
-            overlay_alpha: 200,
-            on_barrier_hit_sound: Some("hit.wav".to_string()),
+            proximity_coloring: true,
+            proximity_far_color: (0, 255, 0),
+            proximity_range: 250,
+            buffer_alpha: 200,
+            overlay_coverage: OverlayCoverage::Both,
+            overlay_failure: OverlayFailurePolicy::FailEnable,
+            interior_alpha: 100,
+            flash_alpha: 240,
+            overlay_animation: OverlayAnimation::Pulse {
+                period_ms: 1000,
+                min_alpha: 40,
+            },
+            overlay_tick_ms: 0,
+            on_barrier_hit_sound: Some(Sound::File("hit.wav".to_string())),
             on_barrier_entry_sound: None,
+            entry_cue: EntryCue::BufferZone,
+            interrupt_previous_sound: false,
+            coordinate_origin: CoordinateOrigin::TopLeft,
+            snap_grid: Some(SnapGrid::Pixels(10)),
+            consume_blocked_events: false,
+            resync_after_block: true,
+            enforcement_mode: EnforcementMode::WarnOnly,
+            bypass_hold_ms: 250,
+            bypass_modifier: Some(BypassModifier::Alt),
+            clamp_to_screen: true,
+            edit_mode: None,
+            high_contrast: HighContrastStyle {
+                follow_system: false,
+                color: Some((255, 255, 0)),
+                pattern: HatchPattern::Cross,
+            },
         };
 
-        assert_eq!(config.x, 100);
-        assert_eq!(config.y, 200);
-        assert_eq!(config.width, 300);
-        assert_eq!(config.height, 150);
-        assert_eq!(config.buffer_zone, 25);
+        assert_eq!(
+            config.bounds,
+            BarrierBounds::CornerAndSize {
+                x: 100,
+                y: 200,
+                width: 300,
+                height: 150
+            }
+        );
+        assert_eq!(config.buffer_zone, BufferZone::uniform(25));
+        assert!(!config.buffer_blocks);
         assert_eq!(config.push_factor, 50);
+        assert!(!config.dynamic_push);
+        assert_eq!(config.offscreen_push, OffscreenPush::SlideAlongEdge);
         assert_eq!(config.overlay_color, (255, 128, 64));
-        assert_eq!(config.overlay_alpha, 200);
-        assert_eq!(config.on_barrier_hit_sound, Some("hit.wav".to_string()));
+        assert!(config.proximity_coloring);
+        assert_eq!(config.proximity_far_color, (0, 255, 0));
+        assert_eq!(config.proximity_range, 250);
+        assert_eq!(config.buffer_alpha, 200);
+        assert_eq!(config.overlay_coverage, OverlayCoverage::Both);
+        assert_eq!(config.overlay_failure, OverlayFailurePolicy::FailEnable);
+        assert_eq!(config.interior_alpha, 100);
+        assert_eq!(config.flash_alpha, 240);
+        assert_eq!(
+            config.overlay_animation,
+            OverlayAnimation::Pulse {
+                period_ms: 1000,
+                min_alpha: 40
+            }
+        );
+        assert_eq!(config.overlay_tick_ms, 0);
+        assert_eq!(
+            config.on_barrier_hit_sound,
+            Some(Sound::File("hit.wav".to_string()))
+        );
         assert_eq!(config.on_barrier_entry_sound, None);
+        assert_eq!(config.entry_cue, EntryCue::BufferZone);
+        assert!(!config.interrupt_previous_sound);
+        assert_eq!(config.coordinate_origin, CoordinateOrigin::TopLeft);
+        assert_eq!(config.snap_grid, Some(SnapGrid::Pixels(10)));
+        assert!(!config.consume_blocked_events);
+        assert_eq!(config.enforcement_mode, EnforcementMode::WarnOnly);
+        assert_eq!(config.bypass_hold_ms, 250);
+        assert_eq!(config.bypass_modifier, Some(BypassModifier::Alt));
+        assert!(!config.high_contrast.follow_system);
+        assert_eq!(config.high_contrast.pattern, HatchPattern::Cross);
+    }
+
+    #[test]
+    fn test_accessibility_settings_prefer_solid_drawing() {
+        assert!(!AccessibilitySettings::default().prefers_solid());
+        assert!(AccessibilitySettings {
+            high_contrast: true,
+            transparency_effects: true,
+        }
+        .prefers_solid());
+        assert!(AccessibilitySettings {
+            high_contrast: false,
+            transparency_effects: false,
+        }
+        .prefers_solid());
+        assert!(HighContrastStyle::default().follow_system);
+    }
+
+    #[test]
+    fn test_overlay_failure_policy_defaults_to_continue() {
+        assert_eq!(
+            OverlayFailurePolicy::default(),
+            OverlayFailurePolicy::ContinueInvisible
+        );
     }
 
     #[test]
-    fn test_point_in_rect() {
-        let rect = RECT {
-            left: 10,
-            top: 20,
-            right: 100,
-            bottom: 80,
+    fn test_barrier_rect_bottom_left_origin() {
+        let rect = barrier_rect(100, 500, 200, 100, CoordinateOrigin::BottomLeft);
+
+        assert_eq!(rect.left, 100);
+        assert_eq!(rect.top, 400);
+        assert_eq!(rect.right, 300);
+        assert_eq!(rect.bottom, 500);
+    }
+
+    #[test]
+    fn test_barrier_rect_top_left_origin() {
+        let rect = barrier_rect(100, 400, 200, 100, CoordinateOrigin::TopLeft);
+
+        assert_eq!(rect.left, 100);
+        assert_eq!(rect.top, 400);
+        assert_eq!(rect.right, 300);
+        assert_eq!(rect.bottom, 500);
+    }
+
+    #[test]
+    fn test_barrier_coordinates_invert_barrier_rect() {
+        for origin in [CoordinateOrigin::BottomLeft, CoordinateOrigin::TopLeft] {
+            let rect = barrier_rect(100, 500, 200, 100, origin);
+            assert_eq!(barrier_coordinates(rect, origin), (100, 500, 200, 100));
+        }
+    }
+
+    #[test]
+    fn test_corners_describe_the_same_rect_as_corner_and_size() {
+        let (top_left, bottom_right) = (Point { x: 100, y: 400 }, Point { x: 300, y: 500 });
+        let (bottom_left, top_right) = (Point { x: 100, y: 500 }, Point { x: 300, y: 400 });
+        let expected = Rect {
+            left: 100,
+            top: 400,
+            right: 300,
+            bottom: 500,
         };
 
-        // Point inside
-        let inside_point = POINT { x: 50, y: 40 };
-        assert!(point_in_rect(&inside_point, &rect));
+        // Either diagonal, in either order, whatever the coordinate origin
+        for (a, b) in [
+            (top_left, bottom_right),
+            (bottom_right, top_left),
+            (bottom_left, top_right),
+            (top_right, bottom_left),
+        ] {
+            for origin in [CoordinateOrigin::BottomLeft, CoordinateOrigin::TopLeft] {
+                assert_eq!(BarrierBounds::Corners(a, b).rect(origin), expected);
+            }
+        }
 
-        // Point on boundary (excluded)
-        let boundary_point = POINT { x: 100, y: 40 };
-        assert!(!point_in_rect(&boundary_point, &rect));
+        let bottom_left_sized = BarrierBounds::CornerAndSize {
+            x: 100,
+            y: 500,
+            width: 200,
+            height: 100,
+        };
+        assert_eq!(
+            bottom_left_sized.rect(CoordinateOrigin::BottomLeft),
+            expected
+        );
+        let top_left_sized = BarrierBounds::CornerAndSize {
+            x: 100,
+            y: 400,
+            width: 200,
+            height: 100,
+        };
+        assert_eq!(top_left_sized.rect(CoordinateOrigin::TopLeft), expected);
+    }
 
-        // Point outside
-        let outside_point = POINT { x: 150, y: 40 };
-        assert!(!point_in_rect(&outside_point, &rect));
+    #[test]
+    fn test_coordinate_origin_defaults_to_bottom_left() {
+        assert_eq!(CoordinateOrigin::default(), CoordinateOrigin::BottomLeft);
+    }
 
-        // Corner cases
-        let left_edge = POINT { x: 10, y: 40 };
-        assert!(point_in_rect(&left_edge, &rect));
+    #[test]
+    fn test_snap_rect_rounds_edges_to_the_grid() {
+        let rect = Rect {
+            left: 13,
+            top: 1044,
+            right: 217,
+            bottom: 1085,
+        };
+        assert_eq!(
+            snap_rect(rect, 10),
+            Rect {
+                left: 10,
+                top: 1040,
+                right: 220,
+                bottom: 1090,
+            }
+        );
+        // Below 2 there is nothing to round to
+        assert_eq!(snap_rect(rect, 1), rect);
+        assert_eq!(snap_rect(rect, 0), rect);
+        assert_eq!(snap_rect(rect, -8), rect);
+    }
 
-        let top_edge = POINT { x: 50, y: 20 };
-        assert!(point_in_rect(&top_edge, &rect));
+    #[test]
+    fn test_snap_rect_on_arbitrary_inputs() {
+        // Deterministic LCG so the inputs cover negatives, odd offsets and
+        // values near the i32 limits without pulling in a dependency
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            seed = seed
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (seed >> 32) as i32
+        };
+
+        for grid in [2, 3, 8, 10, 135, 240] {
+            for _ in 0..2000 {
+                let (left, top) = (next() / 4, next() / 4);
+                // Mostly small sizes so some collapse below one grid cell
+                let (width, height) = (next().rem_euclid(600), next().rem_euclid(600));
+                let rect = Rect {
+                    left,
+                    top,
+                    right: left + width,
+                    bottom: top + height,
+                };
+                let snapped = snap_rect(rect, grid);
+                let near = |edge: i32, original: i32| (edge - original).abs() <= grid / 2;
+
+                for edge in [snapped.left, snapped.top, snapped.right, snapped.bottom] {
+                    assert_eq!(edge.rem_euclid(grid), 0, "{rect:?} on grid {grid}");
+                }
+                assert!(near(snapped.left, rect.left), "{rect:?} -> {snapped:?}");
+                assert!(near(snapped.top, rect.top), "{rect:?} -> {snapped:?}");
+                // Far edges round too, unless that would leave the barrier empty
+                assert!(
+                    near(snapped.right, rect.right) || snapped.right == snapped.left + grid,
+                    "{rect:?} -> {snapped:?}"
+                );
+                assert!(
+                    near(snapped.bottom, rect.bottom) || snapped.bottom == snapped.top + grid,
+                    "{rect:?} -> {snapped:?}"
+                );
+                assert!(snapped.right > snapped.left && snapped.bottom > snapped.top);
+                // Snapping an already snapped rectangle changes nothing
+                assert_eq!(snap_rect(snapped, grid), snapped);
+            }
+        }
     }
 
     #[test]
-    fn test_calculate_dynamic_push_factor() {
-        let last_pos = POINT { x: 0, y: 0 };
-        let base_factor = 50;
-
-        // No movement
-        let current_pos = POINT { x: 0, y: 0 };
-        let result = calculate_dynamic_push_factor(base_factor, &last_pos, &current_pos);
-        assert_eq!(result, base_factor); // Should be 1x multiplier
-
-        // Slow movement (speed < 25)
-        let current_pos = POINT { x: 10, y: 0 };
-        let result = calculate_dynamic_push_factor(base_factor, &last_pos, &current_pos);
-        assert_eq!(result, base_factor); // Should be 1x multiplier
-
-        // Medium movement (speed = 25)
-        let current_pos = POINT { x: 25, y: 0 };
-        let result = calculate_dynamic_push_factor(base_factor, &last_pos, &current_pos);
-        assert_eq!(result, base_factor); // Should be 1x multiplier
-
-        // Fast movement (speed = 50)
-        let current_pos = POINT { x: 50, y: 0 };
-        let result = calculate_dynamic_push_factor(base_factor, &last_pos, &current_pos);
-        assert_eq!(result, 100); // Should be 2x multiplier
-
-        // Very fast movement (speed = 75, should clamp to 3x)
-        let current_pos = POINT { x: 75, y: 0 };
-        let result = calculate_dynamic_push_factor(base_factor, &last_pos, &current_pos);
-        assert_eq!(result, 150); // Should be 3x multiplier
-
-        // Extremely fast movement (should clamp to 3x max)
-        let current_pos = POINT { x: 1000, y: 0 };
-        let result = calculate_dynamic_push_factor(base_factor, &last_pos, &current_pos);
-        assert_eq!(result, 150); // Should be clamped to 3x multiplier
+    fn test_builtin_sound_names() {
+        for sound in BuiltinSound::ALL {
+            assert_eq!(BuiltinSound::from_name(sound.name()), Some(sound));
+        }
+        assert_eq!(BuiltinSound::from_name("Click"), Some(BuiltinSound::Click));
+        assert_eq!(BuiltinSound::from_name("beep"), None);
     }
 
     #[test]
-    fn test_push_point_out_of_rect_basic() {
-        // Simple test case - mock screen size
-        SCREEN_WIDTH.store(1920, Ordering::Relaxed);
-        SCREEN_HEIGHT.store(1080, Ordering::Relaxed);
+    fn test_builtin_sounds_are_wav_files() {
+        for sound in BuiltinSound::ALL {
+            let wav = sound.wav();
+            assert_eq!(&wav[..4], b"RIFF", "{}", sound.name());
+            assert_eq!(&wav[8..12], b"WAVE", "{}", sound.name());
+        }
+    }
 
-        let rect = RECT {
-            left: 100,
-            top: 100,
-            right: 200,
-            bottom: 200,
+    #[test]
+    fn test_place_on_screen() {
+        // A 1080p screen
+        let screen = Rect {
+            left: 0,
+            top: 0,
+            right: 1920,
+            bottom: 1080,
+        };
+        let rect = |left, top| Rect {
+            left,
+            top,
+            right: left + 200,
+            bottom: top + 100,
         };
-        let push_factor = 20;
 
-        // Point inside rect - should be pushed out
-        let point = POINT { x: 150, y: 150 };
-        let pushed = push_point_out_of_rect(&point, &rect, push_factor);
+        assert_eq!(
+            place_on_screen(rect(100, 900), &screen, true),
+            (rect(100, 900), ScreenPlacement::OnScreen)
+        );
+        // Hanging past an edge is reported but never moved
+        assert_eq!(
+            place_on_screen(rect(-50, 1000), &screen, true),
+            (rect(-50, 1000), ScreenPlacement::PartlyOffScreen)
+        );
+
+        // Written for a 4K screen
+        assert_eq!(
+            place_on_screen(rect(3000, 2000), &screen, false),
+            (rect(3000, 2000), ScreenPlacement::OffScreen)
+        );
+        assert_eq!(
+            place_on_screen(rect(3000, 2000), &screen, true),
+            (rect(1720, 980), ScreenPlacement::MovedOnScreen)
+        );
+        assert_eq!(
+            place_on_screen(rect(500, -400), &screen, true),
+            (rect(500, 0), ScreenPlacement::MovedOnScreen)
+        );
 
-        // The point should be moved outside the rect
-        assert!(!point_in_rect(&pushed, &rect));
+        // Touching the edge from outside doesn't count as on screen
+        assert_eq!(
+            place_on_screen(rect(1920, 500), &screen, false).1,
+            ScreenPlacement::OffScreen
+        );
     }
 
     #[test]
-    fn test_check_movement_path_no_collision() {
-        let start = POINT { x: 50, y: 50 };
-        let end = POINT { x: 60, y: 50 };
-        let barrier = RECT {
-            left: 100,
-            top: 100,
-            right: 200,
-            bottom: 200,
+    fn test_moving_onto_screen_keeps_the_size() {
+        // Secondary monitor left of the primary one
+        let screen = Rect {
+            left: -1280,
+            top: 0,
+            right: 1920,
+            bottom: 1080,
         };
-        let buffer = RECT {
-            left: 90,
-            top: 90,
-            right: 210,
-            bottom: 210,
+        let wide = Rect {
+            left: 5000,
+            top: 2000,
+            right: 9000,
+            bottom: 2100,
         };
 
-        let result = check_movement_path(&start, &end, &barrier, &buffer);
-        assert!(result.is_none()); // No collision, should return None
+        let (moved, placement) = place_on_screen(wide, &screen, true);
+        assert_eq!(placement, ScreenPlacement::MovedOnScreen);
+        assert_eq!(moved.left, screen.left);
+        assert_eq!(moved.bottom, screen.bottom);
+        assert_eq!(moved.right - moved.left, wide.right - wide.left);
+        assert_eq!(moved.bottom - moved.top, wide.bottom - wide.top);
     }
 
     #[test]
-    fn test_check_movement_path_small_movement() {
-        let start = POINT { x: 50, y: 50 };
-        let end = POINT { x: 51, y: 50 }; // Very small movement
-        let barrier = RECT {
-            left: 100,
-            top: 100,
-            right: 200,
-            bottom: 200,
+    fn test_config_rect_applies_snap_grid() {
+        let mut config = MouseBarrierConfig {
+            bounds: BarrierBounds::CornerAndSize {
+                x: 3,
+                y: 1077,
+                width: 197,
+                height: 41,
+            },
+            buffer_zone: BufferZone::uniform(20),
+            buffer_blocks: true,
+            push_factor: 50,
+            dynamic_push: true,
+            offscreen_push: OffscreenPush::default(),
+            screen_margin: 0,
+            approach_zone: None,
+            overlay_enabled: true,
+            overlay_color: (255, 0, 0),
+            proximity_coloring: false,
+            proximity_far_color: (0, 255, 0),
+            proximity_range: 200,
+            buffer_alpha: 200,
+            overlay_coverage: OverlayCoverage::default(),
+            overlay_failure: OverlayFailurePolicy::default(),
+            interior_alpha: 100,
+            flash_alpha: 200,
+            overlay_animation: OverlayAnimation::default(),
+            overlay_tick_ms: 50,
+            on_barrier_hit_sound: None,
+            on_barrier_entry_sound: None,
+            entry_cue: EntryCue::default(),
+            interrupt_previous_sound: true,
+            coordinate_origin: CoordinateOrigin::BottomLeft,
+            snap_grid: None,
+            consume_blocked_events: true,
+            resync_after_block: false,
+            enforcement_mode: EnforcementMode::Enforce,
+            bypass_hold_ms: 0,
+            bypass_modifier: None,
+            clamp_to_screen: false,
+            edit_mode: None,
+            high_contrast: HighContrastStyle::default(),
         };
-        let buffer = RECT {
-            left: 90,
-            top: 90,
-            right: 210,
-            bottom: 210,
+        let screen = Rect {
+            left: 0,
+            top: 0,
+            right: 1920,
+            bottom: 1080,
         };
+        assert_eq!(
+            config.rect(&screen),
+            barrier_rect(3, 1077, 197, 41, CoordinateOrigin::BottomLeft)
+        );
 
-        let result = check_movement_path(&start, &end, &barrier, &buffer);
-        assert!(result.is_none()); // Should skip small movements
+        config.snap_grid = Some(SnapGrid::Pixels(10));
+        assert_eq!(
+            config.rect(&screen),
+            Rect {
+                left: 0,
+                top: 1040,
+                right: 200,
+                bottom: 1080,
+            }
+        );
+
+        // Eighths of 1920x1080 are 240 wide and 135 high
+        config.bounds = BarrierBounds::CornerAndSize {
+            x: 3,
+            y: 1077,
+            width: 197,
+            height: 100,
+        };
+        config.snap_grid = Some(SnapGrid::ScreenFraction(8));
+        assert_eq!(
+            config.rect(&screen),
+            Rect {
+                left: 0,
+                top: 945,
+                right: 240,
+                bottom: 1080,
+            }
+        );
     }
 
     #[test]
-    fn test_check_movement_path_collision() {
-        let start = POINT { x: 50, y: 150 };
-        let end = POINT { x: 250, y: 150 }; // Path goes through barrier
-        let barrier = RECT {
+    fn test_snap_rect_to_screen_fractions() {
+        // A second monitor to the left puts the virtual screen at negative x
+        let screen = Rect {
+            left: -1920,
+            top: 0,
+            right: 1920,
+            bottom: 1080,
+        };
+        let rect = Rect {
+            left: -10,
+            top: 500,
+            right: 500,
+            bottom: 530,
+        };
+        // Columns are 480 wide from -1920, rows 135 high from 0; the thin
+        // barrier keeps a whole row
+        assert_eq!(
+            snap_rect_to_screen(rect, &screen, 8),
+            Rect {
+                left: 0,
+                top: 540,
+                right: 480,
+                bottom: 675,
+            }
+        );
+        // Thirds are 1280 wide and 360 high
+        assert_eq!(
+            snap_rect_to_screen(rect, &screen, 3),
+            Rect {
+                left: -640,
+                top: 360,
+                right: 640,
+                bottom: 720,
+            }
+        );
+        assert_eq!(snap_rect_to_screen(rect, &screen, 0), rect);
+        let empty = Rect {
+            right: -1920,
+            ..screen
+        };
+        assert_eq!(snap_rect_to_screen(rect, &empty, 8), rect);
+
+        // The same barrier scaled to a screen a third larger snaps to the same
+        // fractions of it
+        let scaled = Rect {
+            left: -2560,
+            top: 0,
+            right: 2560,
+            bottom: 1440,
+        };
+        let on_scaled = snap_rect_to_screen(
+            Rect {
+                left: -13,
+                top: 667,
+                right: 667,
+                bottom: 707,
+            },
+            &scaled,
+            8,
+        );
+        assert_eq!(
+            on_scaled,
+            Rect {
+                left: 0,
+                top: 720,
+                right: 640,
+                bottom: 900,
+            }
+        );
+    }
+
+    #[test]
+    fn test_geometry_resolves_the_buffer_rect() {
+        let rect = Rect {
             left: 100,
             top: 100,
             right: 200,
             bottom: 200,
         };
-        let buffer = RECT {
-            left: 90,
-            top: 90,
-            right: 210,
-            bottom: 210,
-        };
-
-        let result = check_movement_path(&start, &end, &barrier, &buffer);
-        assert!(result.is_some()); // Should detect collision and return safe point
+        let geometry = BarrierGeometry::new(
+            rect,
+            BufferZone::uniform(10),
+            (255, 0, 0),
+            200,
+            100,
+            240,
+            OverlayCoverage::Both,
+        );
 
-        let safe_point = result.unwrap();
-        assert!(!point_in_rect(&safe_point, &buffer)); // Safe point should be outside buffer
+        assert_eq!(geometry.rect, rect);
+        assert_eq!(
+            geometry.buffer_rect,
+            Rect {
+                left: 90,
+                top: 90,
+                right: 210,
+                bottom: 210
+            }
+        );
+        assert_eq!(geometry.overlay_color, (255, 0, 0));
+        assert_eq!(geometry.overlay_coverage, OverlayCoverage::Both);
+        assert_eq!(geometry.flash_alpha, 240);
     }
 
     #[test]
-    fn test_mouse_barrier_state_creation() {
-        let state = MouseBarrierState {
-            barrier_rect: RECT {
-                left: 0,
+    fn test_buffer_zone_per_edge() {
+        assert_eq!(BufferZone::from(15).as_uniform(), Some(15));
+
+        // Only warns from below: nothing to the sides or above
+        let snapshot = BarrierSnapshot {
+            rect: Rect {
+                left: 100,
+                top: 100,
+                right: 200,
+                bottom: 200,
+            },
+            buffer: BufferZone {
                 top: 0,
-                right: 100,
-                bottom: 100,
+                right: 0,
+                bottom: 30,
+                left: 0,
             },
-            buffer_zone: 10,
-            push_factor: 30,
-            enabled: false,
-            overlay_color: 0xFF0000,
-            overlay_alpha: 128,
-            on_barrier_hit_sound: Some("sound.wav".to_string()),
-            on_barrier_entry_sound: None,
+            enabled: true,
+            push_factor: 20,
+            enforcement_mode: EnforcementMode::Enforce,
         };
+        assert_eq!(snapshot.buffer.as_uniform(), None);
+        assert_eq!(snapshot.zone_at(150, 99), Zone::Outside);
+        assert_eq!(snapshot.zone_at(99, 150), Zone::Outside);
+        assert_eq!(snapshot.zone_at(200, 150), Zone::Outside);
+        assert_eq!(snapshot.zone_at(150, 229), Zone::Buffer);
+        assert_eq!(snapshot.zone_at(150, 230), Zone::Outside);
+    }
+
+    #[test]
+    fn test_snapshot_zone_at_matches_hook_on_boundary_pixels() {
+        let snapshot = BarrierSnapshot {
+            rect: Rect {
+                left: 100,
+                top: 100,
+                right: 200,
+                bottom: 200,
+            },
+            buffer: BufferZone::uniform(10),
+            enabled: true,
+            push_factor: 20,
+            enforcement_mode: EnforcementMode::Enforce,
+        };
+        let buffer = snapshot.buffer_rect();
+        assert_eq!(
+            (buffer.left, buffer.top, buffer.right, buffer.bottom),
+            (90, 90, 210, 210)
+        );
+
+        // Every pixel on and around the barrier and buffer edges, with the
+        // buffer the same on every edge and with edges of their own
+        let per_edge = BarrierSnapshot {
+            buffer: BufferZone {
+                top: 10,
+                right: 0,
+                bottom: 25,
+                left: 5,
+            },
+            ..snapshot
+        };
+        for snapshot in [snapshot, per_edge] {
+            let buffer = snapshot.buffer_rect();
+            let around = |edges: [i32; 4]| -> Vec<i32> {
+                edges
+                    .iter()
+                    .flat_map(|&edge| [edge - 1, edge, edge + 1])
+                    .collect()
+            };
+            let xs = around([buffer.left, 100, 200, buffer.right]);
+            let ys = around([buffer.top, 100, 200, buffer.bottom]);
+            for &x in &xs {
+                for &y in &ys {
+                    let zone = snapshot.zone_at(x, y);
+                    let mut engine = BarrierEngine::default();
+                    engine.configure(
+                        snapshot.rect,
+                        snapshot.buffer,
+                        snapshot.push_factor,
+                        OffscreenPush::ClampToEdge,
+                        ScreenMetrics {
+                            width: 1920,
+                            height: 1080,
+                            physical_width: 1920,
+                            physical_height: 1080,
+                            margin: 0,
+                        },
+                    );
+                    let actions = engine.on_mouse_move(Point { x, y }, Instant::now());
+                    let hook_pushes = matches!(actions.last(), Some(EngineAction::MoveCursorTo(_)));
+                    assert_eq!(zone != Zone::Outside, hook_pushes, "({}, {})", x, y);
+                }
+            }
+        }
 
-        assert_eq!(state.buffer_zone, 10);
-        assert_eq!(state.push_factor, 30);
-        assert!(!state.enabled);
-        assert_eq!(state.overlay_color, 0xFF0000);
-        assert_eq!(state.overlay_alpha, 128);
-        assert_eq!(state.on_barrier_hit_sound, Some("sound.wav".to_string()));
-        assert_eq!(state.on_barrier_entry_sound, None);
+        // Right and bottom edges are exclusive
+        assert_eq!(snapshot.zone_at(100, 100), Zone::Barrier);
+        assert_eq!(snapshot.zone_at(199, 199), Zone::Barrier);
+        assert_eq!(snapshot.zone_at(200, 150), Zone::Buffer);
+        assert_eq!(snapshot.zone_at(150, 200), Zone::Buffer);
+        assert_eq!(snapshot.zone_at(209, 150), Zone::Buffer);
+        assert_eq!(snapshot.zone_at(210, 150), Zone::Outside);
+        assert_eq!(snapshot.zone_at(89, 150), Zone::Outside);
     }
 
     // Test helper functions
@@ -1153,7 +1496,7 @@ mod tests {
         let width = 200;
         let height = 100;
 
-        let expected_rect = RECT {
+        let expected_rect = Rect {
             left: x,
             top: y - height,  // top = 500 - 100 = 400
             right: x + width, // right = 100 + 200 = 300
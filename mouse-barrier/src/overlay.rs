@@ -0,0 +1,1108 @@
+use crate::engine::{Point, Rect, ScreenMetrics};
+use crate::{
+    AccessibilitySettings, BufferZone, HighContrastStyle, OverlayAnimation, OverlayCoverage,
+    OverlayRegion,
+};
+use std::time::{Duration, Instant};
+
+// Overlay windows: four buffer frame strips plus one tinting the barrier interior
+pub(crate) const OVERLAY_STRIP_COUNT: usize = 5;
+pub(crate) const INTERIOR_STRIP: usize = 4;
+/// How long the buffer frame shows `flash_alpha` after a barrier hit
+pub(crate) const HIT_FLASH_DURATION: Duration = Duration::from_millis(150);
+
+impl OverlayCoverage {
+    pub(crate) fn covers_buffer(self) -> bool {
+        matches!(self, OverlayCoverage::BufferOnly | OverlayCoverage::Both)
+    }
+
+    pub(crate) fn covers_interior(self) -> bool {
+        matches!(self, OverlayCoverage::BarrierOnly | OverlayCoverage::Both)
+    }
+}
+
+impl OverlayRegion {
+    /// The region of each overlay strip, in the order of `OVERLAY_WINDOWS`
+    pub(crate) const STRIPS: [OverlayRegion; OVERLAY_STRIP_COUNT] = [
+        OverlayRegion::Top,
+        OverlayRegion::Bottom,
+        OverlayRegion::Left,
+        OverlayRegion::Right,
+        OverlayRegion::Interior,
+    ];
+}
+
+/// Packs an `(r, g, b)` triple into the 0x00RRGGBB form kept in the barrier state
+pub(crate) fn pack_overlay_color((r, g, b): (u8, u8, u8)) -> u32 {
+    ((r as u32) << 16) | ((g as u32) << 8) | (b as u32)
+}
+
+pub(crate) fn unpack_overlay_color(color: u32) -> (u8, u8, u8) {
+    ((color >> 16) as u8, (color >> 8) as u8, color as u8)
+}
+
+/// The overlay color for a cursor `distance` pixels from the barrier: `near`
+/// at the barrier, fading linearly to `far` at `range` pixels and beyond
+pub(crate) fn proximity_color(
+    near: (u8, u8, u8),
+    far: (u8, u8, u8),
+    distance: i32,
+    range: i32,
+) -> (u8, u8, u8) {
+    if range <= 0 {
+        return near;
+    }
+    let t = (distance.max(0) as f64 / range as f64).min(1.0);
+    let mix = |near: u8, far: u8| (near as f64 + (far as f64 - near as f64) * t).round() as u8;
+    (mix(near.0, far.0), mix(near.1, far.1), mix(near.2, far.2))
+}
+
+/// Straight-line distance in pixels from `point` to the nearest part of
+/// `rect`; 0 inside it
+pub(crate) fn distance_to_rect(point: Point, rect: &Rect) -> i32 {
+    let dx = (rect.left - point.x).max(point.x - (rect.right - 1)).max(0);
+    let dy = (rect.top - point.y).max(point.y - (rect.bottom - 1)).max(0);
+    (dx as f64).hypot(dy as f64).round() as i32
+}
+
+/// The alpha a strip configured with `alpha` shows `elapsed` into its animation
+pub(crate) fn animated_alpha(alpha: u8, animation: OverlayAnimation, elapsed: Duration) -> u8 {
+    match animation {
+        OverlayAnimation::Steady | OverlayAnimation::Pulse { period_ms: 0, .. } => alpha,
+        OverlayAnimation::Pulse {
+            period_ms,
+            min_alpha,
+        } => {
+            let min_alpha = min_alpha.min(alpha);
+            let phase = (elapsed.as_millis() % period_ms as u128) as f64 / period_ms as f64;
+            // Configured alpha at the start of each period, min_alpha halfway through
+            let level = (1.0 + (2.0 * std::f64::consts::PI * phase).cos()) / 2.0;
+            min_alpha + ((alpha - min_alpha) as f64 * level).round() as u8
+        }
+    }
+}
+
+/// Screen-space placement of one overlay strip, in logical coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OverlayStrip {
+    pub(crate) name: &'static str,
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+}
+
+impl OverlayStrip {
+    pub(crate) fn is_visible(&self) -> bool {
+        self.width > 0 && self.height > 0
+    }
+}
+
+/// Computes the overlay strips: four (top, bottom, left, right) framing the
+/// barrier plus one covering its interior. The strips are stored in
+/// `OVERLAY_WINDOWS` in this same order. Strips that `coverage` excludes come
+/// back empty so their windows are hidden.
+pub(crate) fn compute_overlay_strips(
+    barrier_rect: &Rect,
+    buffer_zone: BufferZone,
+    screen: &ScreenMetrics,
+    coverage: OverlayCoverage,
+) -> [OverlayStrip; OVERLAY_STRIP_COUNT] {
+    let scale_x = screen.width as f64 / screen.physical_width as f64;
+    let scale_y = screen.height as f64 / screen.physical_height as f64;
+
+    let barrier_left = (barrier_rect.left as f64 * scale_x).round() as i32;
+    let barrier_top = (barrier_rect.top as f64 * scale_y).round() as i32;
+    let barrier_right = (barrier_rect.right as f64 * scale_x).round() as i32;
+    let barrier_bottom = (barrier_rect.bottom as f64 * scale_y).round() as i32;
+
+    let scaled = |width: i32, scale: f64| (width as f64 * scale).round() as i32;
+    let buffer = Rect {
+        left: barrier_left - scaled(buffer_zone.left, scale_x),
+        top: barrier_top - scaled(buffer_zone.top, scale_y),
+        right: barrier_right + scaled(buffer_zone.right, scale_x),
+        bottom: barrier_bottom + scaled(buffer_zone.bottom, scale_y),
+    };
+    let monitor = Rect {
+        left: 0,
+        top: 0,
+        right: screen.width,
+        bottom: screen.height,
+    };
+
+    // Only the on-screen part of the buffer frame is drawn. The barrier is
+    // clamped into that visible area so every strip below has a non-negative
+    // size and together they exactly cover the visible frame around it.
+    let visible = intersect_rect(&buffer, &monitor);
+    let inner = Rect {
+        left: barrier_left.clamp(visible.left, visible.right),
+        top: barrier_top.clamp(visible.top, visible.bottom),
+        right: barrier_right.clamp(visible.left, visible.right),
+        bottom: barrier_bottom.clamp(visible.top, visible.bottom),
+    };
+
+    let mut strips = [
+        OverlayStrip {
+            name: "top",
+            x: visible.left,
+            y: visible.top,
+            width: visible.right - visible.left,
+            height: inner.top - visible.top,
+        },
+        OverlayStrip {
+            name: "bottom",
+            x: visible.left,
+            y: inner.bottom,
+            width: visible.right - visible.left,
+            height: visible.bottom - inner.bottom,
+        },
+        OverlayStrip {
+            name: "left",
+            x: visible.left,
+            y: inner.top,
+            width: inner.left - visible.left,
+            height: inner.bottom - inner.top,
+        },
+        OverlayStrip {
+            name: "right",
+            x: inner.right,
+            y: inner.top,
+            width: visible.right - inner.right,
+            height: inner.bottom - inner.top,
+        },
+        OverlayStrip {
+            name: "interior",
+            x: inner.left,
+            y: inner.top,
+            width: inner.right - inner.left,
+            height: inner.bottom - inner.top,
+        },
+    ];
+
+    for (index, strip) in strips.iter_mut().enumerate() {
+        let covered = if index == INTERIOR_STRIP {
+            coverage.covers_interior()
+        } else {
+            coverage.covers_buffer()
+        };
+        if !covered {
+            strip.width = 0;
+            strip.height = 0;
+        }
+    }
+
+    strips
+}
+
+/// Intersection of two rectangles; an empty result has zero width or height,
+/// never a negative one.
+fn intersect_rect(a: &Rect, b: &Rect) -> Rect {
+    let left = a.left.max(b.left);
+    let top = a.top.max(b.top);
+    Rect {
+        left,
+        top,
+        right: a.right.min(b.right).max(left),
+        bottom: a.bottom.min(b.bottom).max(top),
+    }
+}
+
+pub(crate) fn strip_alpha(index: usize, buffer_alpha: u8, interior_alpha: u8) -> u8 {
+    if index == INTERIOR_STRIP {
+        interior_alpha
+    } else {
+        buffer_alpha
+    }
+}
+
+/// The alpha the strip at `index` shows `since_hit` after the last barrier
+/// hit: `flash_alpha` on the buffer frame while the flash lasts, `alpha`
+/// otherwise. The interior never flashes.
+pub(crate) fn flashed_alpha(
+    index: usize,
+    alpha: u8,
+    flash_alpha: u8,
+    since_hit: Option<Duration>,
+) -> u8 {
+    match since_hit {
+        Some(since_hit) if index != INTERIOR_STRIP && since_hit < HIT_FLASH_DURATION => flash_alpha,
+        _ => alpha,
+    }
+}
+
+/// The color to draw the high-contrast overlay in, or `None` while the normal
+/// overlay applies: the configured color, else the theme's `highlight` color
+pub(crate) fn high_contrast_color(
+    style: &HighContrastStyle,
+    settings: AccessibilitySettings,
+    highlight: (u8, u8, u8),
+) -> Option<(u8, u8, u8)> {
+    (style.follow_system && settings.prefers_solid()).then(|| style.color.unwrap_or(highlight))
+}
+
+/// Everything about the overlay windows a layout round applies: where each
+/// strip goes and how the windows are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OverlayLayout {
+    pub(crate) strips: [OverlayStrip; OVERLAY_STRIP_COUNT],
+    pub(crate) color: u32,
+    pub(crate) buffer_alpha: u8,
+    pub(crate) interior_alpha: u8,
+    pub(crate) tick_ms: u32,
+    /// Color and hatch pattern while the high-contrast style is drawn
+    pub(crate) high_contrast: Option<(u32, u32)>,
+}
+
+impl OverlayLayout {
+    /// Which strips need their window moved and repainted to go from
+    /// `previous` to this layout; all of them when nothing was applied yet or
+    /// the windows are drawn differently
+    pub(crate) fn changed_strips(
+        &self,
+        previous: Option<&OverlayLayout>,
+    ) -> [bool; OVERLAY_STRIP_COUNT] {
+        match previous {
+            Some(previous)
+                if previous.color == self.color
+                    && previous.buffer_alpha == self.buffer_alpha
+                    && previous.interior_alpha == self.interior_alpha
+                    && previous.tick_ms == self.tick_ms
+                    && previous.high_contrast == self.high_contrast =>
+            {
+                std::array::from_fn(|index| previous.strips[index] != self.strips[index])
+            }
+            _ => [true; OVERLAY_STRIP_COUNT],
+        }
+    }
+}
+
+/// Shortest time between two layout rounds, about one frame at 60 Hz
+pub(crate) const OVERLAY_LAYOUT_INTERVAL: Duration = Duration::from_millis(16);
+/// How often the number of coalesced layout requests is logged
+pub(crate) const OVERLAY_LAYOUT_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Coalesces requests to lay the overlay windows out again.
+///
+/// Every barrier change only marks the layout dirty; one place then applies
+/// the newest layout at most every `OVERLAY_LAYOUT_INTERVAL`, and only the
+/// strips that moved since the layout applied last. A resize gesture that
+/// changes the barrier hundreds of times a second repaints the windows at
+/// most once a frame.
+#[derive(Debug)]
+pub(crate) struct OverlayLayoutQueue {
+    dirty: bool,
+    applied: Option<OverlayLayout>,
+    last_round: Option<Instant>,
+    // Requests and rounds since `report_start`, for the debug log
+    requests: u32,
+    rounds: u32,
+    report_start: Option<Instant>,
+}
+
+/// Layout requests and the rounds that applied them over one report interval
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LayoutReport {
+    pub(crate) requests: u32,
+    pub(crate) rounds: u32,
+}
+
+impl OverlayLayoutQueue {
+    pub(crate) const fn new() -> Self {
+        Self {
+            dirty: false,
+            applied: None,
+            last_round: None,
+            requests: 0,
+            rounds: 0,
+            report_start: None,
+        }
+    }
+
+    /// Marks the layout out of date
+    pub(crate) fn request(&mut self, now: Instant) {
+        self.dirty = true;
+        self.requests += 1;
+        self.report_start.get_or_insert(now);
+    }
+
+    /// Whether a layout round is due: something changed and the last round
+    /// was at least `OVERLAY_LAYOUT_INTERVAL` ago
+    pub(crate) fn due(&self, now: Instant) -> bool {
+        self.dirty
+            && self
+                .last_round
+                .is_none_or(|last| now.saturating_duration_since(last) >= OVERLAY_LAYOUT_INTERVAL)
+    }
+
+    /// Starts a round for `layout`, returning the strips that changed since
+    /// the last one; none when it matches the layout applied last
+    pub(crate) fn begin_round(
+        &mut self,
+        now: Instant,
+        layout: OverlayLayout,
+    ) -> [bool; OVERLAY_STRIP_COUNT] {
+        self.dirty = false;
+        self.last_round = Some(now);
+        self.rounds += 1;
+        let changed = layout.changed_strips(self.applied.as_ref());
+        self.applied = Some(layout);
+        changed
+    }
+
+    /// Drops the pending request and the applied layout, for when the windows
+    /// are destroyed, or recreated from scratch, outside a round
+    pub(crate) fn forget(&mut self) {
+        self.dirty = false;
+        self.applied = None;
+    }
+
+    /// The requests and rounds of the last `OVERLAY_LAYOUT_REPORT_INTERVAL`,
+    /// once it has passed; nothing while the barrier isn't changing
+    pub(crate) fn report(&mut self, now: Instant) -> Option<LayoutReport> {
+        let start = self.report_start?;
+        if now.saturating_duration_since(start) < OVERLAY_LAYOUT_REPORT_INTERVAL {
+            return None;
+        }
+        let report = LayoutReport {
+            requests: self.requests,
+            rounds: self.rounds,
+        };
+        self.requests = 0;
+        self.rounds = 0;
+        self.report_start = None;
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::buffer_rect;
+
+    #[test]
+    fn test_regions_follow_the_strip_order() {
+        let strips = compute_overlay_strips(
+            &Rect {
+                left: 100,
+                top: 100,
+                right: 200,
+                bottom: 200,
+            },
+            BufferZone::uniform(10),
+            &unscaled_screen(),
+            OverlayCoverage::Both,
+        );
+        for (region, strip) in OverlayRegion::STRIPS.iter().zip(strips) {
+            assert_eq!(format!("{:?}", region).to_lowercase(), strip.name);
+        }
+        assert_eq!(
+            OverlayRegion::STRIPS[INTERIOR_STRIP],
+            OverlayRegion::Interior
+        );
+    }
+
+    #[test]
+    fn test_overlay_color_packing_roundtrip() {
+        assert_eq!(pack_overlay_color((255, 128, 64)), 0x00FF8040);
+        assert_eq!(unpack_overlay_color(0x00FF8040), (255, 128, 64));
+
+        for color in [(0, 0, 0), (255, 255, 255), (1, 2, 3)] {
+            assert_eq!(unpack_overlay_color(pack_overlay_color(color)), color);
+        }
+    }
+
+    #[test]
+    fn test_proximity_color_fades_from_near_to_far() {
+        let (red, green) = ((255, 0, 0), (0, 255, 0));
+        assert_eq!(proximity_color(red, green, 0, 200), red);
+        assert_eq!(proximity_color(red, green, 100, 200), (128, 128, 0));
+        assert_eq!(proximity_color(red, green, 200, 200), green);
+        assert_eq!(proximity_color(red, green, 5000, 200), green);
+        assert_eq!(proximity_color(red, green, 50, 0), red);
+    }
+
+    #[test]
+    fn test_distance_to_rect() {
+        let rect = Rect {
+            left: 100,
+            top: 100,
+            right: 200,
+            bottom: 200,
+        };
+        assert_eq!(distance_to_rect(Point { x: 150, y: 150 }, &rect), 0);
+        assert_eq!(distance_to_rect(Point { x: 199, y: 199 }, &rect), 0);
+        assert_eq!(distance_to_rect(Point { x: 90, y: 150 }, &rect), 10);
+        assert_eq!(distance_to_rect(Point { x: 150, y: 219 }, &rect), 20);
+        // Diagonally off a corner
+        assert_eq!(distance_to_rect(Point { x: 96, y: 97 }, &rect), 5);
+    }
+
+    #[test]
+    fn test_animated_alpha_steady_holds_configured_alpha() {
+        for ms in [0, 250, 1000, 12_345] {
+            assert_eq!(
+                animated_alpha(200, OverlayAnimation::Steady, Duration::from_millis(ms)),
+                200
+            );
+        }
+    }
+
+    #[test]
+    fn test_animated_alpha_pulse_cycle() {
+        let pulse = OverlayAnimation::Pulse {
+            period_ms: 1000,
+            min_alpha: 40,
+        };
+        let at = |ms| animated_alpha(200, pulse, Duration::from_millis(ms));
+
+        assert_eq!(at(0), 200);
+        // Quarter period: halfway between 40 and 200
+        assert_eq!(at(250), 120);
+        assert_eq!(at(500), 40);
+        assert_eq!(at(750), 120);
+        // Wraps around every period
+        assert_eq!(at(1000), 200);
+        assert_eq!(at(2500), 40);
+    }
+
+    #[test]
+    fn test_animated_alpha_pulse_edge_cases() {
+        // A zero period can't pulse
+        let no_period = OverlayAnimation::Pulse {
+            period_ms: 0,
+            min_alpha: 40,
+        };
+        assert_eq!(
+            animated_alpha(200, no_period, Duration::from_millis(500)),
+            200
+        );
+
+        // A strip dimmer than min_alpha (e.g. the interior) never brightens
+        let pulse = OverlayAnimation::Pulse {
+            period_ms: 1000,
+            min_alpha: 150,
+        };
+        for ms in [0, 250, 500] {
+            assert_eq!(animated_alpha(100, pulse, Duration::from_millis(ms)), 100);
+        }
+    }
+
+    fn unscaled_screen() -> ScreenMetrics {
+        ScreenMetrics {
+            width: 1920,
+            height: 1080,
+            physical_width: 1920,
+            physical_height: 1080,
+            margin: 0,
+        }
+    }
+
+    #[test]
+    fn test_compute_overlay_strips_frames_interior_barrier() {
+        let barrier = Rect {
+            left: 100,
+            top: 400,
+            right: 300,
+            bottom: 500,
+        };
+
+        let [top, bottom, left, right, _] = compute_overlay_strips(
+            &barrier,
+            BufferZone::uniform(20),
+            &unscaled_screen(),
+            OverlayCoverage::BufferOnly,
+        );
+
+        assert_eq!((top.x, top.y, top.width, top.height), (80, 380, 240, 20));
+        assert_eq!(
+            (bottom.x, bottom.y, bottom.width, bottom.height),
+            (80, 500, 240, 20)
+        );
+        assert_eq!(
+            (left.x, left.y, left.width, left.height),
+            (80, 400, 20, 100)
+        );
+        assert_eq!(
+            (right.x, right.y, right.width, right.height),
+            (300, 400, 20, 100)
+        );
+    }
+
+    #[test]
+    fn test_compute_overlay_strips_per_edge_buffer() {
+        let barrier = Rect {
+            left: 100,
+            top: 400,
+            right: 300,
+            bottom: 500,
+        };
+        let buffer_zone = BufferZone {
+            top: 30,
+            right: 0,
+            bottom: 10,
+            left: 20,
+        };
+
+        let [top, bottom, left, right, _] = compute_overlay_strips(
+            &barrier,
+            buffer_zone,
+            &unscaled_screen(),
+            OverlayCoverage::BufferOnly,
+        );
+
+        assert_eq!((top.x, top.y, top.width, top.height), (80, 370, 220, 30));
+        assert_eq!(
+            (bottom.x, bottom.y, bottom.width, bottom.height),
+            (80, 500, 220, 10)
+        );
+        assert_eq!(
+            (left.x, left.y, left.width, left.height),
+            (80, 400, 20, 100)
+        );
+        assert!(!right.is_visible());
+    }
+
+    #[test]
+    fn test_compute_overlay_strips_follows_updated_geometry() {
+        let screen = unscaled_screen();
+        let before = Rect {
+            left: 100,
+            top: 400,
+            right: 300,
+            bottom: 500,
+        };
+        let after = Rect {
+            left: 500,
+            top: 600,
+            right: 900,
+            bottom: 650,
+        };
+
+        let old_strips = compute_overlay_strips(
+            &before,
+            BufferZone::uniform(20),
+            &screen,
+            OverlayCoverage::BufferOnly,
+        );
+        let new_strips = compute_overlay_strips(
+            &after,
+            BufferZone::uniform(10),
+            &screen,
+            OverlayCoverage::BufferOnly,
+        );
+        assert_ne!(old_strips, new_strips);
+
+        let [top, bottom, left, right, _] = new_strips;
+        assert_eq!((top.x, top.y, top.width, top.height), (490, 590, 420, 10));
+        assert_eq!(
+            (bottom.x, bottom.y, bottom.width, bottom.height),
+            (490, 650, 420, 10)
+        );
+        assert_eq!(
+            (left.x, left.y, left.width, left.height),
+            (490, 600, 10, 50)
+        );
+        assert_eq!(
+            (right.x, right.y, right.width, right.height),
+            (900, 600, 10, 50)
+        );
+    }
+
+    #[test]
+    fn test_compute_overlay_strips_scales_physical_to_logical() {
+        let screen = ScreenMetrics {
+            width: 1920,
+            height: 1080,
+            physical_width: 3840,
+            physical_height: 2160,
+            margin: 0,
+        };
+        let barrier = Rect {
+            left: 200,
+            top: 800,
+            right: 600,
+            bottom: 1000,
+        };
+
+        let [top, _, left, _, _] = compute_overlay_strips(
+            &barrier,
+            BufferZone::uniform(40),
+            &screen,
+            OverlayCoverage::BufferOnly,
+        );
+
+        assert_eq!((top.x, top.y, top.width, top.height), (80, 380, 240, 20));
+        assert_eq!(
+            (left.x, left.y, left.width, left.height),
+            (80, 400, 20, 100)
+        );
+    }
+
+    #[test]
+    fn test_compute_overlay_strips_hides_offscreen_strips() {
+        // Barrier anchored in the bottom-left corner: left and bottom strips are clamped away
+        let barrier = Rect {
+            left: 0,
+            top: 1040,
+            right: 200,
+            bottom: 1080,
+        };
+
+        let [top, bottom, left, right, _] = compute_overlay_strips(
+            &barrier,
+            BufferZone::uniform(20),
+            &unscaled_screen(),
+            OverlayCoverage::BufferOnly,
+        );
+
+        assert!(top.is_visible());
+        assert!(!bottom.is_visible());
+        assert!(!left.is_visible());
+        assert!(right.is_visible());
+    }
+
+    /// Checks that the strips have non-negative sizes, do not overlap and
+    /// together cover exactly the on-screen part of the buffer frame
+    fn assert_strips_cover_visible_frame(barrier: &Rect, buffer_zone: BufferZone) {
+        let screen = unscaled_screen();
+        let strips = compute_overlay_strips(barrier, buffer_zone, &screen, OverlayCoverage::Both);
+
+        for strip in &strips {
+            assert!(strip.width >= 0 && strip.height >= 0, "{:?}", strip);
+        }
+
+        let area = |strip: &OverlayStrip| strip.width as i64 * strip.height as i64;
+        let monitor = Rect {
+            left: 0,
+            top: 0,
+            right: screen.width,
+            bottom: screen.height,
+        };
+        let buffer = buffer_rect(barrier, buffer_zone);
+        let visible_buffer = intersect_rect(&buffer, &monitor);
+        let visible_barrier = intersect_rect(barrier, &monitor);
+        let rect_area =
+            |rect: &Rect| (rect.right - rect.left) as i64 * (rect.bottom - rect.top) as i64;
+
+        let frame_area: i64 = strips[..INTERIOR_STRIP].iter().map(area).sum();
+        assert_eq!(
+            frame_area,
+            rect_area(&visible_buffer) - rect_area(&visible_barrier)
+        );
+        assert_eq!(area(&strips[INTERIOR_STRIP]), rect_area(&visible_barrier));
+
+        for (i, a) in strips.iter().enumerate() {
+            for b in &strips[i + 1..] {
+                let overlap = intersect_rect(
+                    &Rect {
+                        left: a.x,
+                        top: a.y,
+                        right: a.x + a.width,
+                        bottom: a.y + a.height,
+                    },
+                    &Rect {
+                        left: b.x,
+                        top: b.y,
+                        right: b.x + b.width,
+                        bottom: b.y + b.height,
+                    },
+                );
+                assert_eq!(rect_area(&overlap), 0, "{} overlaps {}", a.name, b.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compute_overlay_strips_corner_anchored() {
+        // Default config: barrier in the bottom-left corner of a 1080p screen
+        let barrier = Rect {
+            left: 0,
+            top: 1040,
+            right: 200,
+            bottom: 1080,
+        };
+
+        let [top, bottom, left, right, _] = compute_overlay_strips(
+            &barrier,
+            BufferZone::uniform(20),
+            &unscaled_screen(),
+            OverlayCoverage::BufferOnly,
+        );
+
+        assert_eq!((top.x, top.y, top.width, top.height), (0, 1020, 220, 20));
+        assert_eq!((bottom.width, bottom.height), (220, 0));
+        assert_eq!((left.width, left.height), (0, 40));
+        assert_eq!(
+            (right.x, right.y, right.width, right.height),
+            (200, 1040, 20, 40)
+        );
+        assert_strips_cover_visible_frame(&barrier, BufferZone::uniform(20));
+    }
+
+    #[test]
+    fn test_compute_overlay_strips_edge_anchored() {
+        // Barrier hugging the top edge of the screen
+        let barrier = Rect {
+            left: 500,
+            top: 0,
+            right: 900,
+            bottom: 40,
+        };
+
+        let [top, bottom, left, right, _] = compute_overlay_strips(
+            &barrier,
+            BufferZone::uniform(20),
+            &unscaled_screen(),
+            OverlayCoverage::BufferOnly,
+        );
+
+        assert!(!top.is_visible());
+        assert_eq!(
+            (bottom.x, bottom.y, bottom.width, bottom.height),
+            (480, 40, 440, 20)
+        );
+        assert_eq!((left.x, left.y, left.width, left.height), (480, 0, 20, 40));
+        assert_eq!(
+            (right.x, right.y, right.width, right.height),
+            (900, 0, 20, 40)
+        );
+        assert_strips_cover_visible_frame(&barrier, BufferZone::uniform(20));
+    }
+
+    #[test]
+    fn test_compute_overlay_strips_barrier_partly_offscreen() {
+        // Barrier top above the screen used to produce a negative-height top strip
+        let barrier = Rect {
+            left: 100,
+            top: -50,
+            right: 300,
+            bottom: 30,
+        };
+
+        let [top, _, left, right, interior] = compute_overlay_strips(
+            &barrier,
+            BufferZone::uniform(20),
+            &unscaled_screen(),
+            OverlayCoverage::Both,
+        );
+
+        assert_eq!((top.width, top.height), (240, 0));
+        assert_eq!((left.x, left.y, left.width, left.height), (80, 0, 20, 30));
+        assert_eq!(
+            (right.x, right.y, right.width, right.height),
+            (300, 0, 20, 30)
+        );
+        assert_eq!(
+            (interior.x, interior.y, interior.width, interior.height),
+            (100, 0, 200, 30)
+        );
+        assert_strips_cover_visible_frame(&barrier, BufferZone::uniform(20));
+    }
+
+    #[test]
+    fn test_compute_overlay_strips_cover_visible_frame() {
+        let placements = [
+            (100, 400, 300, 500),     // fully interior
+            (0, 1040, 200, 1080),     // bottom-left corner
+            (1720, 0, 1920, 40),      // top-right corner
+            (0, 500, 40, 700),        // left edge
+            (1900, 500, 1950, 700),   // hanging off the right edge
+            (-300, -300, -100, -100), // entirely off screen
+            (-10, -10, 1930, 1090),   // larger than the screen
+        ];
+
+        for (left, top, right, bottom) in placements {
+            let barrier = Rect {
+                left,
+                top,
+                right,
+                bottom,
+            };
+            for buffer_zone in [0, 20, 150] {
+                assert_strips_cover_visible_frame(&barrier, BufferZone::uniform(buffer_zone));
+            }
+            assert_strips_cover_visible_frame(
+                &barrier,
+                BufferZone {
+                    top: 40,
+                    right: 0,
+                    bottom: 5,
+                    left: 150,
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_compute_overlay_strips_coverage_modes() {
+        let barrier = Rect {
+            left: 100,
+            top: 400,
+            right: 300,
+            bottom: 500,
+        };
+        let screen = unscaled_screen();
+
+        let buffer_only = compute_overlay_strips(
+            &barrier,
+            BufferZone::uniform(20),
+            &screen,
+            OverlayCoverage::BufferOnly,
+        );
+        assert!(buffer_only[..INTERIOR_STRIP]
+            .iter()
+            .all(OverlayStrip::is_visible));
+        assert!(!buffer_only[INTERIOR_STRIP].is_visible());
+
+        let barrier_only = compute_overlay_strips(
+            &barrier,
+            BufferZone::uniform(20),
+            &screen,
+            OverlayCoverage::BarrierOnly,
+        );
+        assert!(!barrier_only[..INTERIOR_STRIP]
+            .iter()
+            .any(OverlayStrip::is_visible));
+        let interior = barrier_only[INTERIOR_STRIP];
+        assert_eq!(
+            (interior.x, interior.y, interior.width, interior.height),
+            (100, 400, 200, 100)
+        );
+
+        let both = compute_overlay_strips(
+            &barrier,
+            BufferZone::uniform(20),
+            &screen,
+            OverlayCoverage::Both,
+        );
+        assert!(both.iter().all(OverlayStrip::is_visible));
+        assert_eq!(both[..INTERIOR_STRIP], buffer_only[..INTERIOR_STRIP]);
+        assert_eq!(both[INTERIOR_STRIP], interior);
+    }
+
+    #[test]
+    fn test_compute_overlay_strips_clamps_interior_to_screen() {
+        // Barrier hanging off the bottom-right corner of the screen
+        let barrier = Rect {
+            left: 1800,
+            top: 1000,
+            right: 2000,
+            bottom: 1200,
+        };
+
+        let strips = compute_overlay_strips(
+            &barrier,
+            BufferZone::uniform(20),
+            &unscaled_screen(),
+            OverlayCoverage::BarrierOnly,
+        );
+        let interior = strips[INTERIOR_STRIP];
+
+        assert_eq!(
+            (interior.x, interior.y, interior.width, interior.height),
+            (1800, 1000, 120, 80)
+        );
+    }
+
+    #[test]
+    fn test_strip_alpha_uses_interior_alpha_for_interior() {
+        for index in 0..INTERIOR_STRIP {
+            assert_eq!(strip_alpha(index, 200, 60), 200);
+        }
+        assert_eq!(strip_alpha(INTERIOR_STRIP, 200, 60), 60);
+    }
+
+    #[test]
+    fn test_flashed_alpha_only_flashes_the_buffer_frame_briefly() {
+        let during = Some(Duration::from_millis(10));
+        for index in 0..INTERIOR_STRIP {
+            assert_eq!(flashed_alpha(index, 100, 240, during), 240);
+            assert_eq!(flashed_alpha(index, 100, 240, None), 100);
+            assert_eq!(
+                flashed_alpha(index, 100, 240, Some(HIT_FLASH_DURATION)),
+                100
+            );
+        }
+        assert_eq!(flashed_alpha(INTERIOR_STRIP, 60, 240, during), 60);
+    }
+
+    #[test]
+    fn test_high_contrast_color_follows_the_system() {
+        let highlight = (0, 120, 215);
+        let high_contrast = AccessibilitySettings {
+            high_contrast: true,
+            transparency_effects: true,
+        };
+        let no_transparency = AccessibilitySettings {
+            high_contrast: false,
+            transparency_effects: false,
+        };
+        let style = HighContrastStyle::default();
+
+        assert_eq!(
+            high_contrast_color(&style, AccessibilitySettings::default(), highlight),
+            None
+        );
+        assert_eq!(
+            high_contrast_color(&style, high_contrast, highlight),
+            Some(highlight)
+        );
+        assert_eq!(
+            high_contrast_color(&style, no_transparency, highlight),
+            Some(highlight)
+        );
+
+        let yellow = HighContrastStyle {
+            color: Some((255, 255, 0)),
+            ..style
+        };
+        assert_eq!(
+            high_contrast_color(&yellow, high_contrast, highlight),
+            Some((255, 255, 0))
+        );
+        let ignored = HighContrastStyle {
+            follow_system: false,
+            ..yellow
+        };
+        assert_eq!(
+            high_contrast_color(&ignored, high_contrast, highlight),
+            None
+        );
+    }
+
+    fn layout_around(barrier: Rect) -> OverlayLayout {
+        OverlayLayout {
+            strips: compute_overlay_strips(
+                &barrier,
+                BufferZone::uniform(10),
+                &unscaled_screen(),
+                OverlayCoverage::Both,
+            ),
+            color: 0x00FF0000,
+            buffer_alpha: 200,
+            interior_alpha: 100,
+            tick_ms: 50,
+            high_contrast: None,
+        }
+    }
+
+    #[test]
+    fn test_layout_changes_only_the_strips_that_moved() {
+        let layout = layout_around(Rect {
+            left: 100,
+            top: 100,
+            right: 200,
+            bottom: 200,
+        });
+        assert_eq!(layout.changed_strips(None), [true; OVERLAY_STRIP_COUNT]);
+        assert_eq!(
+            layout.changed_strips(Some(&layout)),
+            [false; OVERLAY_STRIP_COUNT]
+        );
+
+        // Growing the barrier downwards leaves the top strip where it was
+        let taller = layout_around(Rect {
+            left: 100,
+            top: 100,
+            right: 200,
+            bottom: 250,
+        });
+        assert_eq!(
+            taller.changed_strips(Some(&layout)),
+            [false, true, true, true, true]
+        );
+
+        // A new color repaints every strip, moved or not
+        let recolored = OverlayLayout {
+            color: 0x0000FF00,
+            ..layout
+        };
+        assert_eq!(
+            recolored.changed_strips(Some(&layout)),
+            [true; OVERLAY_STRIP_COUNT]
+        );
+        let high_contrast = OverlayLayout {
+            high_contrast: Some((0x00FFFF00, 0)),
+            ..layout
+        };
+        assert_eq!(
+            high_contrast.changed_strips(Some(&layout)),
+            [true; OVERLAY_STRIP_COUNT]
+        );
+    }
+
+    #[test]
+    fn test_layout_queue_coalesces_requests_into_rounds() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let layout = layout_around(Rect {
+            left: 100,
+            top: 100,
+            right: 200,
+            bottom: 200,
+        });
+        let mut queue = OverlayLayoutQueue::new();
+        assert!(!queue.due(start));
+
+        // A burst of requests makes one round, then waits out the interval
+        for ms in 0..5 {
+            queue.request(at(ms));
+        }
+        assert!(queue.due(at(4)));
+        assert_eq!(
+            queue.begin_round(at(4), layout),
+            [true; OVERLAY_STRIP_COUNT]
+        );
+        assert!(!queue.due(at(5)));
+
+        queue.request(at(10));
+        assert!(!queue.due(at(19)));
+        assert!(queue.due(at(20)));
+
+        // Nothing moved since the last round, so nothing is redrawn
+        assert_eq!(
+            queue.begin_round(at(20), layout),
+            [false; OVERLAY_STRIP_COUNT]
+        );
+
+        // Windows recreated outside a round get every strip again
+        queue.request(at(40));
+        queue.forget();
+        assert!(!queue.due(at(40)));
+        queue.request(at(41));
+        assert_eq!(
+            queue.begin_round(at(41), layout),
+            [true; OVERLAY_STRIP_COUNT]
+        );
+    }
+
+    #[test]
+    fn test_layout_queue_reports_once_per_interval() {
+        let start = Instant::now();
+        let layout = layout_around(Rect {
+            left: 100,
+            top: 100,
+            right: 200,
+            bottom: 200,
+        });
+        let mut queue = OverlayLayoutQueue::new();
+        assert_eq!(queue.report(start), None);
+
+        for ms in 0..100 {
+            queue.request(start + Duration::from_millis(ms));
+        }
+        queue.begin_round(start, layout);
+        queue.begin_round(start + Duration::from_millis(16), layout);
+        assert_eq!(queue.report(start + Duration::from_millis(999)), None);
+        assert_eq!(
+            queue.report(start + OVERLAY_LAYOUT_REPORT_INTERVAL),
+            Some(LayoutReport {
+                requests: 100,
+                rounds: 2
+            })
+        );
+
+        // Quiet again until the next request
+        assert_eq!(queue.report(start + Duration::from_secs(5)), None);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::Sound;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::OnceLock;
+use std::thread;
+use tracing::{debug, warn};
+use winapi::shared::minwindef::HMODULE;
+use winapi::um::libloaderapi::{GetProcAddress, LoadLibraryW};
+
+type PlaySoundWFn = unsafe extern "system" fn(*const u16, HMODULE, u32) -> i32;
+
+// SND_ASYNC = 0x0001, SND_NODEFAULT = 0x0002
+const PLAY_SOUND_FLAGS: u32 = 0x0001 | 0x0002;
+
+// Where the sound comes from: a file name, or a WAV image in memory
+const SND_FILENAME: u32 = 0x00020000;
+const SND_MEMORY: u32 = 0x0004;
+
+// SND_NOSTOP: leave a sound that is already playing alone instead of replacing it
+const SND_NOSTOP: u32 = 0x0010;
+
+struct SoundRequest {
+    sound: Sound,
+    interrupt_previous: bool,
+}
+
+// Sending half of the audio worker's queue; the worker lives for the rest of the process
+static AUDIO_WORKER: OnceLock<Sender<SoundRequest>> = OnceLock::new();
+
+// PlaySoundW resolved from winmm.dll on first use; None if loading failed
+static PLAY_SOUND: OnceLock<Option<PlaySoundWFn>> = OnceLock::new();
+
+/// Starts the audio worker thread if it is not already running.
+pub(crate) fn start_audio_worker() {
+    audio_worker();
+}
+
+/// Queues a sound for playback without blocking the caller.
+///
+/// With `interrupt_previous` the worker stops whatever is playing before starting
+/// the new sound, so only the latest cue is heard. Without it a sound that is still
+/// playing is left to finish and the new one is skipped, since winmm plays a single
+/// sound at a time.
+///
+/// Safe to call from the low-level mouse hook: the only work done here is a channel send.
+pub(crate) fn play_sound_async(sound: &Sound, interrupt_previous: bool) {
+    let request = SoundRequest {
+        sound: sound.clone(),
+        interrupt_previous,
+    };
+    if audio_worker().send(request).is_err() {
+        warn!("Audio worker is not running, dropping sound");
+    }
+}
+
+fn audio_worker() -> &'static Sender<SoundRequest> {
+    AUDIO_WORKER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        if let Err(e) = thread::Builder::new()
+            .name("audio-worker".to_string())
+            .spawn(move || run_audio_worker(rx))
+        {
+            warn!("Failed to spawn audio worker thread: {}", e);
+        }
+        tx
+    })
+}
+
+fn run_audio_worker(requests: Receiver<SoundRequest>) {
+    // Resolve winmm up front so the first sound does not pay for the load
+    if play_sound_fn().is_none() {
+        warn!("Audio playback unavailable, sounds will be ignored");
+    }
+
+    // Requests are handled one at a time, so playback is serialized on this thread
+    while let Ok(request) = requests.recv() {
+        let Some(playsound_fn) = play_sound_fn() else {
+            continue;
+        };
+
+        debug!(sound = ?request.sound, "Playing sound");
+        let flags = play_flags(request.interrupt_previous);
+        unsafe {
+            if request.interrupt_previous {
+                // A null sound name stops any sound currently playing
+                playsound_fn(std::ptr::null(), std::ptr::null_mut(), 0);
+            }
+            match &request.sound {
+                Sound::File(path) => {
+                    let wide_path: Vec<u16> =
+                        path.encode_utf16().chain(std::iter::once(0)).collect();
+                    playsound_fn(
+                        wide_path.as_ptr(),
+                        std::ptr::null_mut(),
+                        flags | SND_FILENAME,
+                    );
+                }
+                // The embedded WAV is 'static, so it outlives the async playback
+                Sound::Builtin(builtin) => {
+                    playsound_fn(
+                        builtin.wav().as_ptr().cast(),
+                        std::ptr::null_mut(),
+                        flags | SND_MEMORY,
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn play_flags(interrupt_previous: bool) -> u32 {
+    if interrupt_previous {
+        PLAY_SOUND_FLAGS
+    } else {
+        PLAY_SOUND_FLAGS | SND_NOSTOP
+    }
+}
+
+/// Returns the cached PlaySoundW pointer, loading winmm.dll the first time it is needed.
+///
+/// A failed load is cached too, so a missing winmm.dll is reported once rather than per sound.
+fn play_sound_fn() -> Option<PlaySoundWFn> {
+    *PLAY_SOUND.get_or_init(|| unsafe { load_play_sound() })
+}
+
+unsafe fn load_play_sound() -> Option<PlaySoundWFn> {
+    // Load winmm.dll dynamically
+    let winmm_name: Vec<u16> = "winmm\0".encode_utf16().collect();
+    let winmm = LoadLibraryW(winmm_name.as_ptr());
+    if winmm.is_null() {
+        warn!("Failed to load winmm.dll for audio playback");
+        return None;
+    }
+
+    // Get PlaySoundW function
+    let playsound_name = b"PlaySoundW\0";
+    let playsound_proc = GetProcAddress(winmm, playsound_name.as_ptr() as *const i8);
+    if playsound_proc.is_null() {
+        warn!("Failed to find PlaySoundW function");
+        return None;
+    }
+
+    // Cast to function pointer
+    let playsound_fn: PlaySoundWFn = std::mem::transmute(playsound_proc);
+    Some(playsound_fn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_play_flags_keep_base_flags() {
+        for interrupt_previous in [true, false] {
+            let flags = play_flags(interrupt_previous);
+            assert_eq!(flags & PLAY_SOUND_FLAGS, PLAY_SOUND_FLAGS);
+        }
+    }
+
+    #[test]
+    fn test_play_flags_only_yield_when_not_interrupting() {
+        assert_eq!(play_flags(true) & SND_NOSTOP, 0);
+        assert_eq!(play_flags(false) & SND_NOSTOP, SND_NOSTOP);
+    }
+}
@@ -0,0 +1,93 @@
+use super::Platform;
+use crate::engine::ScreenMetrics;
+use crate::{Point, Sound};
+
+/// A call the runtime made on [`MockPlatform`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Call {
+    InstallMouseHook,
+    UninstallMouseHook,
+    CreateOverlays,
+    DestroyOverlays,
+    StartBypassMonitor,
+    StopBypassMonitor,
+    SetCursorPos(Point),
+    ResyncCursor,
+    PlaySound(Sound),
+}
+
+/// A platform with no OS behind it: it records every call, keeps track of
+/// what would be up, and fails the calls it was told to
+#[derive(Debug, Default)]
+pub(crate) struct MockPlatform {
+    pub(crate) calls: Vec<Call>,
+    pub(crate) mouse_hook: bool,
+    pub(crate) overlays: bool,
+    pub(crate) monitoring: bool,
+    pub(crate) screen: ScreenMetrics,
+    pub(crate) fail_overlays: bool,
+    pub(crate) fail_mouse_hook: bool,
+    pub(crate) fail_cursor_moves: bool,
+}
+
+impl Platform for MockPlatform {
+    fn install_mouse_hook(&mut self) -> Result<(), String> {
+        self.calls.push(Call::InstallMouseHook);
+        if self.fail_mouse_hook {
+            return Err("mock mouse hook failure".to_string());
+        }
+        self.mouse_hook = true;
+        Ok(())
+    }
+
+    fn uninstall_mouse_hook(&mut self) -> Result<(), String> {
+        self.calls.push(Call::UninstallMouseHook);
+        self.mouse_hook = false;
+        Ok(())
+    }
+
+    fn mouse_hook_installed(&self) -> bool {
+        self.mouse_hook
+    }
+
+    fn create_overlays(&mut self) -> Result<(), String> {
+        self.calls.push(Call::CreateOverlays);
+        if self.fail_overlays {
+            return Err("mock overlay failure".to_string());
+        }
+        self.overlays = true;
+        Ok(())
+    }
+
+    fn destroy_overlays(&mut self) {
+        self.calls.push(Call::DestroyOverlays);
+        self.overlays = false;
+    }
+
+    fn start_bypass_monitor(&mut self) {
+        self.calls.push(Call::StartBypassMonitor);
+        self.monitoring = true;
+    }
+
+    fn stop_bypass_monitor(&mut self) {
+        self.calls.push(Call::StopBypassMonitor);
+        self.monitoring = false;
+    }
+
+    fn set_cursor_pos(&mut self, to: Point) -> bool {
+        self.calls.push(Call::SetCursorPos(to));
+        !self.fail_cursor_moves
+    }
+
+    fn resync_cursor(&mut self) {
+        self.calls.push(Call::ResyncCursor);
+    }
+
+    fn play_sound(&mut self, sound: &Sound, _interrupt_previous: bool) {
+        self.calls.push(Call::PlaySound(sound.clone()));
+    }
+
+    fn screen_metrics(&self) -> ScreenMetrics {
+        self.screen
+    }
+}
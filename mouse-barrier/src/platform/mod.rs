@@ -0,0 +1,461 @@
+//! The boundary between the barrier runtime and the operating system.
+//!
+//! [`Platform`] is everything the runtime asks of the OS: the mouse hook,
+//! the overlay windows, the middle-button bypass monitor, cursor moves,
+//! sounds and screen metrics. The sequencing built on top of it (what comes
+//! up, in which order, and what is torn down when) lives here, free of any
+//! OS calls, so it is tested on every platform against [`mock::MockPlatform`].
+//!
+//! `win32` is the Windows implementation and the rest of the runtime that is
+//! still Windows-only: the hook procedures, overlay painting and the public
+//! `MouseBarrier` and `KeyboardHook` types. `paint` lets embedders draw on the
+//! overlay windows. The overlay windows and `paint` are only built with the
+//! `overlay` feature, and `audio` with the `audio` feature.
+
+use crate::engine::ScreenMetrics;
+use crate::hook_control::{HookAction, HookControl};
+use crate::{OverlayFailurePolicy, Point, Sound};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+#[cfg(all(windows, feature = "audio"))]
+pub(crate) mod audio;
+#[cfg(any(test, not(windows)))]
+pub(crate) mod mock;
+#[cfg(all(windows, feature = "overlay"))]
+pub(crate) mod paint;
+#[cfg(windows)]
+pub(crate) mod watchdog;
+#[cfg(windows)]
+pub(crate) mod win32;
+
+/// What the barrier runtime needs from the OS
+pub(crate) trait Platform {
+    fn install_mouse_hook(&mut self) -> Result<(), String>;
+    fn uninstall_mouse_hook(&mut self) -> Result<(), String>;
+    fn mouse_hook_installed(&self) -> bool;
+
+    /// Creates the overlay windows for the current barrier
+    fn create_overlays(&mut self) -> Result<(), String>;
+    fn destroy_overlays(&mut self);
+
+    /// Starts watching the middle button, which holds a bypass while down
+    fn start_bypass_monitor(&mut self);
+    /// Stops the monitor; once this returns it can't begin or end a bypass
+    fn stop_bypass_monitor(&mut self);
+
+    /// Moves the cursor; false if it didn't move
+    fn set_cursor_pos(&mut self, to: Point) -> bool;
+    /// Sends a zero-length move so apps tracking raw input see where the
+    /// cursor is now; the mouse hook must ignore it
+    fn resync_cursor(&mut self);
+    /// Starts `sound` without waiting for it to finish
+    fn play_sound(&mut self, sound: &Sound, interrupt_previous: bool);
+    /// Only the overlay windows lay themselves out against the screen
+    #[cfg_attr(all(windows, not(feature = "overlay")), allow(dead_code))]
+    fn screen_metrics(&self) -> ScreenMetrics;
+}
+
+/// How the overlay came up when the barrier was enabled
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum OverlayOutcome {
+    Shown,
+    /// The overlay is turned off, so no windows were created
+    Disabled,
+    /// The windows couldn't be created and the barrier runs without them
+    Unavailable(String),
+}
+
+/// Why [`start_runtime`] failed
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum StartError {
+    /// The overlay couldn't be created and the policy is `FailEnable`; the
+    /// hook control has been disabled again and nothing was started
+    Overlay(String),
+    /// The mouse hook couldn't be installed; the overlay and bypass monitor
+    /// are up, as they would be during a bypass
+    MouseHook(String),
+}
+
+/// Runs `f` on `control`. A panic while it was locked leaves the counts
+/// intact, so a poisoned lock is used anyway rather than stranding the hook.
+pub(crate) fn with_control<R>(
+    control: &Mutex<HookControl>,
+    f: impl FnOnce(&mut HookControl) -> R,
+) -> R {
+    f(&mut control.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// Brings up the overlay, bypass monitor and mouse hook of a barrier whose
+/// `control` was just enabled. The hook is left out while a bypass is held;
+/// [`apply_hook_change`] installs it when the bypass ends. Without
+/// `overlay_enabled` no overlay windows are created at all.
+pub(crate) fn start_runtime(
+    platform: &mut impl Platform,
+    control: &Mutex<HookControl>,
+    overlay_enabled: bool,
+    overlay_failure: OverlayFailurePolicy,
+) -> Result<OverlayOutcome, StartError> {
+    let overlay = if !overlay_enabled {
+        OverlayOutcome::Disabled
+    } else {
+        match platform.create_overlays() {
+            Ok(()) => OverlayOutcome::Shown,
+            Err(e) if overlay_failure == OverlayFailurePolicy::FailEnable => {
+                with_control(control, HookControl::disable);
+                return Err(StartError::Overlay(e));
+            }
+            Err(e) => OverlayOutcome::Unavailable(e),
+        }
+    };
+
+    platform.start_bypass_monitor();
+
+    if with_control(control, |control| control.hook_wanted()) {
+        platform
+            .install_mouse_hook()
+            .map_err(StartError::MouseHook)?;
+    }
+    Ok(overlay)
+}
+
+/// Marks the hook unwanted, stops the bypass monitor, uninstalls the mouse
+/// hook and destroys the overlay windows, in that order.
+///
+/// Marking the hook unwanted first drops any pending change and keeps a bypass
+/// released from now on, by the monitor or anyone else, from requesting a
+/// reinstall that [`apply_hook_change`] would carry out behind our back.
+pub(crate) fn stop_runtime(
+    platform: &mut impl Platform,
+    control: &Mutex<HookControl>,
+) -> Result<(), String> {
+    with_control(control, HookControl::disable);
+    platform.stop_bypass_monitor();
+
+    let unhook_result = platform.uninstall_mouse_hook();
+    platform.destroy_overlays();
+
+    unhook_result
+}
+
+/// `dwExtraInfo` of the moves [`Platform::resync_cursor`] injects
+pub(crate) const RESYNC_EXTRA_INFO: usize = 0x4147_4352;
+
+/// Whether a hooked event is one of our own resync moves, which the mouse
+/// hook passes on untouched: acting on one could push again and send another
+pub(crate) fn is_resync_move(extra_info: usize) -> bool {
+    extra_info == RESYNC_EXTRA_INFO
+}
+
+/// A resync move a push asked for.
+///
+/// Injecting input from inside the mouse hook would have to wait on that
+/// very hook, so the hook only flags the move and the thread servicing hook
+/// requests sends it. Pushes flagged before then share one move.
+#[derive(Debug, Default)]
+pub(crate) struct PendingResync(AtomicBool);
+
+impl PendingResync {
+    pub(crate) const fn new() -> Self {
+        Self(AtomicBool::new(false))
+    }
+
+    /// Sends the move if one was asked for; returns whether it was
+    pub(crate) fn send(&self, platform: &mut impl Platform) -> bool {
+        let pending = self.0.swap(false, Ordering::AcqRel);
+        if pending {
+            platform.resync_cursor();
+        }
+        pending
+    }
+}
+
+/// Moves the cursor for a push and, given `resync`, asks for a resync move
+/// once it has moved. Returns false if it didn't move.
+pub(crate) fn push_cursor(
+    platform: &mut impl Platform,
+    to: Point,
+    resync: Option<&PendingResync>,
+) -> bool {
+    let moved = platform.set_cursor_pos(to);
+    if let (true, Some(resync)) = (moved, resync) {
+        resync.0.store(true, Ordering::Release);
+    }
+    moved
+}
+
+/// Carries out the hook change a bypass asked for, if one is pending, and
+/// returns what was done and how it went.
+///
+/// Decided now, not when the bypass changed: the barrier may have been
+/// disabled or bypassed again since.
+pub(crate) fn apply_hook_change(
+    platform: &mut impl Platform,
+    control: &Mutex<HookControl>,
+) -> Option<(HookAction, Result<(), String>)> {
+    let installed = platform.mouse_hook_installed();
+    let action = with_control(control, |control| control.take_action(installed))?;
+    let result = match action {
+        HookAction::Install => platform.install_mouse_hook(),
+        HookAction::Uninstall => platform.uninstall_mouse_hook(),
+    };
+    Some((action, result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::{Call, MockPlatform};
+    use super::*;
+
+    fn enabled_control() -> Mutex<HookControl> {
+        let control = Mutex::new(HookControl::new());
+        with_control(&control, HookControl::enable);
+        control
+    }
+
+    #[test]
+    fn test_start_brings_up_overlay_monitor_and_hook_in_order() {
+        let mut platform = MockPlatform::default();
+        let control = enabled_control();
+
+        assert_eq!(
+            start_runtime(
+                &mut platform,
+                &control,
+                true,
+                OverlayFailurePolicy::FailEnable
+            ),
+            Ok(OverlayOutcome::Shown)
+        );
+        assert_eq!(
+            platform.calls,
+            [
+                Call::CreateOverlays,
+                Call::StartBypassMonitor,
+                Call::InstallMouseHook
+            ]
+        );
+        assert!(platform.mouse_hook_installed());
+    }
+
+    #[test]
+    fn test_overlay_failure_follows_the_policy() {
+        let mut platform = MockPlatform {
+            fail_overlays: true,
+            ..MockPlatform::default()
+        };
+        let control = enabled_control();
+
+        // FailEnable: nothing else starts and the hook is no longer wanted
+        assert!(matches!(
+            start_runtime(
+                &mut platform,
+                &control,
+                true,
+                OverlayFailurePolicy::FailEnable
+            ),
+            Err(StartError::Overlay(_))
+        ));
+        assert_eq!(platform.calls, [Call::CreateOverlays]);
+        assert!(!with_control(&control, |control| control.hook_wanted()));
+
+        // ContinueInvisible: the barrier is enforced anyway
+        let mut platform = MockPlatform {
+            fail_overlays: true,
+            ..MockPlatform::default()
+        };
+        let control = enabled_control();
+        assert!(matches!(
+            start_runtime(
+                &mut platform,
+                &control,
+                true,
+                OverlayFailurePolicy::ContinueInvisible
+            ),
+            Ok(OverlayOutcome::Unavailable(_))
+        ));
+        assert!(platform.mouse_hook_installed());
+    }
+
+    #[test]
+    fn test_disabled_overlay_creates_no_windows() {
+        // Even a failing overlay is never tried, so FailEnable can't refuse
+        let mut platform = MockPlatform {
+            fail_overlays: true,
+            ..MockPlatform::default()
+        };
+        let control = enabled_control();
+
+        assert_eq!(
+            start_runtime(
+                &mut platform,
+                &control,
+                false,
+                OverlayFailurePolicy::FailEnable
+            ),
+            Ok(OverlayOutcome::Disabled)
+        );
+        assert_eq!(
+            platform.calls,
+            [Call::StartBypassMonitor, Call::InstallMouseHook]
+        );
+
+        // Tearing down without windows is the usual sequence
+        platform.calls.clear();
+        assert_eq!(stop_runtime(&mut platform, &control), Ok(()));
+        assert_eq!(
+            platform.calls,
+            [
+                Call::StopBypassMonitor,
+                Call::UninstallMouseHook,
+                Call::DestroyOverlays
+            ]
+        );
+        assert!(!platform.overlays);
+    }
+
+    #[test]
+    fn test_hook_failure_leaves_the_rest_running() {
+        let mut platform = MockPlatform {
+            fail_mouse_hook: true,
+            ..MockPlatform::default()
+        };
+        let control = enabled_control();
+
+        assert!(matches!(
+            start_runtime(
+                &mut platform,
+                &control,
+                true,
+                OverlayFailurePolicy::FailEnable
+            ),
+            Err(StartError::MouseHook(_))
+        ));
+        assert!(platform.overlays);
+        assert!(platform.monitoring);
+    }
+
+    #[test]
+    fn test_only_our_own_moves_count_as_resyncs() {
+        assert!(is_resync_move(RESYNC_EXTRA_INFO));
+        // Hardware moves, and moves other software injects with its own tag
+        assert!(!is_resync_move(0));
+        assert!(!is_resync_move(0xFF51_5700));
+    }
+
+    #[test]
+    fn test_resync_is_sent_after_the_push_not_from_it() {
+        let mut platform = MockPlatform::default();
+        let resync = PendingResync::new();
+        let to = Point { x: 120, y: 40 };
+
+        assert!(push_cursor(&mut platform, to, Some(&resync)));
+        // Nothing injected while the hook is still running
+        assert_eq!(platform.calls, [Call::SetCursorPos(to)]);
+
+        assert!(resync.send(&mut platform));
+        assert_eq!(platform.calls, [Call::SetCursorPos(to), Call::ResyncCursor]);
+        // Sent once per request
+        assert!(!resync.send(&mut platform));
+        assert_eq!(platform.calls.len(), 2);
+    }
+
+    #[test]
+    fn test_pushes_before_the_resync_share_one_move() {
+        let mut platform = MockPlatform::default();
+        let resync = PendingResync::new();
+
+        push_cursor(&mut platform, Point { x: 120, y: 40 }, Some(&resync));
+        push_cursor(&mut platform, Point { x: 121, y: 40 }, Some(&resync));
+        assert!(resync.send(&mut platform));
+        assert_eq!(
+            platform
+                .calls
+                .iter()
+                .filter(|call| **call == Call::ResyncCursor)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_no_resync_without_the_option_or_a_move() {
+        let mut platform = MockPlatform::default();
+        let resync = PendingResync::new();
+
+        // resync_after_block off
+        assert!(push_cursor(&mut platform, Point { x: 120, y: 40 }, None));
+        assert!(!resync.send(&mut platform));
+
+        // The cursor didn't move, so there is nothing to resync with
+        platform.fail_cursor_moves = true;
+        assert!(!push_cursor(
+            &mut platform,
+            Point { x: 120, y: 40 },
+            Some(&resync)
+        ));
+        assert!(!resync.send(&mut platform));
+        assert!(!platform.calls.contains(&Call::ResyncCursor));
+    }
+
+    #[test]
+    fn test_start_while_bypassed_installs_the_hook_on_release() {
+        let mut platform = MockPlatform::default();
+        let control = Mutex::new(HookControl::new());
+        with_control(&control, HookControl::begin_bypass);
+        with_control(&control, HookControl::enable);
+
+        start_runtime(
+            &mut platform,
+            &control,
+            true,
+            OverlayFailurePolicy::FailEnable,
+        )
+        .unwrap();
+        assert!(!platform.mouse_hook_installed());
+        assert!(apply_hook_change(&mut platform, &control).is_none());
+
+        with_control(&control, HookControl::end_bypass);
+        assert_eq!(
+            apply_hook_change(&mut platform, &control),
+            Some((HookAction::Install, Ok(())))
+        );
+        assert!(platform.mouse_hook_installed());
+    }
+
+    #[test]
+    fn test_stop_tears_down_in_order_and_ignores_late_releases() {
+        let mut platform = MockPlatform::default();
+        let control = enabled_control();
+        start_runtime(
+            &mut platform,
+            &control,
+            true,
+            OverlayFailurePolicy::FailEnable,
+        )
+        .unwrap();
+
+        // The monitor takes a bypass and the hook thread services it
+        with_control(&control, HookControl::begin_bypass);
+        assert_eq!(
+            apply_hook_change(&mut platform, &control),
+            Some((HookAction::Uninstall, Ok(())))
+        );
+        platform.calls.clear();
+
+        assert_eq!(stop_runtime(&mut platform, &control), Ok(()));
+        assert_eq!(
+            platform.calls,
+            [
+                Call::StopBypassMonitor,
+                Call::UninstallMouseHook,
+                Call::DestroyOverlays
+            ]
+        );
+
+        // Released after the barrier went down: the hook stays out
+        with_control(&control, HookControl::end_bypass);
+        assert!(apply_hook_change(&mut platform, &control).is_none());
+        assert!(!platform.mouse_hook_installed());
+    }
+}
@@ -0,0 +1,164 @@
+//! Drawing on the overlay windows from outside the crate: handles to the
+//! windows, and the painter that runs in their `WM_PAINT`.
+
+use crate::{OverlayRegion, Rect};
+use std::ffi::OsStr;
+use std::marker::PhantomData;
+use std::os::windows::ffi::OsStrExt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use tracing::error;
+use winapi::shared::windef::{HDC, HWND, RECT};
+use winapi::um::wingdi::{
+    CreateSolidBrush, DeleteObject, SetBkMode, SetTextColor, TextOutW, RGB, TRANSPARENT,
+};
+use winapi::um::winuser::{FillRect, FrameRect};
+
+type OverlayPainter = dyn Fn(&mut PaintContext, OverlayRegion) + Send + Sync;
+
+static OVERLAY_PAINTER: Mutex<Option<Arc<OverlayPainter>>> = Mutex::new(None);
+// Bumped whenever the overlay windows are destroyed, so handles to them go stale
+static OVERLAY_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// One overlay window, as returned by
+/// [`MouseBarrier::overlay_windows`](crate::MouseBarrier::overlay_windows)
+///
+/// The window belongs to the thread that enabled the barrier: its messages,
+/// and so the overlay painter, run there. Once the barrier is disabled the
+/// window is destroyed and [`hwnd`](Self::hwnd) returns `None`; never destroy
+/// it yourself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverlayHandle {
+    hwnd: HWND,
+    region: OverlayRegion,
+    barrier_index: usize,
+    generation: u64,
+}
+
+impl OverlayHandle {
+    pub(super) fn new(hwnd: HWND, region: OverlayRegion) -> Self {
+        Self {
+            hwnd,
+            region,
+            barrier_index: 0,
+            generation: OVERLAY_GENERATION.load(Ordering::Acquire),
+        }
+    }
+
+    /// The window, or `None` once it has been destroyed
+    pub fn hwnd(&self) -> Option<HWND> {
+        (self.generation == OVERLAY_GENERATION.load(Ordering::Acquire)).then_some(self.hwnd)
+    }
+
+    pub fn region(&self) -> OverlayRegion {
+        self.region
+    }
+
+    /// Which barrier the window draws; a [`MouseBarrier`](crate::MouseBarrier)
+    /// has one, so this is always 0
+    pub fn barrier_index(&self) -> usize {
+        self.barrier_index
+    }
+}
+
+/// Makes every [`OverlayHandle`] handed out so far return `None`. Called
+/// before the windows are destroyed.
+pub(super) fn invalidate_handles() {
+    OVERLAY_GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Drawing on one overlay window while it paints
+///
+/// Coordinates are relative to the window, with `(0, 0)` at its top-left
+/// corner. Colors are `(r, g, b)`.
+pub struct PaintContext<'a> {
+    hdc: HDC,
+    size: (i32, i32),
+    // Only lives for the WM_PAINT it was made for
+    _paint: PhantomData<&'a mut ()>,
+}
+
+impl PaintContext<'_> {
+    /// The window's width and height
+    pub fn size(&self) -> (i32, i32) {
+        self.size
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, (r, g, b): (u8, u8, u8)) {
+        unsafe {
+            let brush = CreateSolidBrush(RGB(r, g, b));
+            FillRect(self.hdc, &win32_rect(rect), brush);
+            DeleteObject(brush as *mut _);
+        }
+    }
+
+    /// Outlines `rect` with a one pixel border
+    pub fn frame_rect(&mut self, rect: Rect, (r, g, b): (u8, u8, u8)) {
+        unsafe {
+            let brush = CreateSolidBrush(RGB(r, g, b));
+            FrameRect(self.hdc, &win32_rect(rect), brush);
+            DeleteObject(brush as *mut _);
+        }
+    }
+
+    /// Draws `text` in the default font with its top-left corner at `(x, y)`
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, (r, g, b): (u8, u8, u8)) {
+        let wide: Vec<u16> = OsStr::new(text).encode_wide().collect();
+        unsafe {
+            SetTextColor(self.hdc, RGB(r, g, b));
+            SetBkMode(self.hdc, TRANSPARENT as i32);
+            TextOutW(self.hdc, x, y, wide.as_ptr(), wide.len() as i32);
+        }
+    }
+
+    /// The device context, for drawing the methods above don't cover. Only
+    /// valid until the painter returns, and anything selected into it must be
+    /// selected back out.
+    pub fn hdc(&self) -> HDC {
+        self.hdc
+    }
+}
+
+fn win32_rect(rect: Rect) -> RECT {
+    RECT {
+        left: rect.left,
+        top: rect.top,
+        right: rect.right,
+        bottom: rect.bottom,
+    }
+}
+
+pub(super) fn set_painter(painter: Arc<OverlayPainter>) {
+    *OVERLAY_PAINTER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = Some(painter);
+}
+
+pub(super) fn clear_painter() {
+    *OVERLAY_PAINTER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Runs the overlay painter, if one is set, on a window of `region` that has
+/// just been filled. A painter that panics is unregistered rather than taking
+/// the window procedure down with it.
+pub(super) unsafe fn run_painter(hdc: HDC, client_rect: &RECT, region: OverlayRegion) {
+    // Not called with the lock held, so the painter may replace itself
+    let Some(painter) = OVERLAY_PAINTER.lock().ok().and_then(|p| p.clone()) else {
+        return;
+    };
+    let mut context = PaintContext {
+        hdc,
+        size: (
+            client_rect.right - client_rect.left,
+            client_rect.bottom - client_rect.top,
+        ),
+        _paint: PhantomData,
+    };
+    if panic::catch_unwind(AssertUnwindSafe(|| painter(&mut context, region))).is_err() {
+        error!(?region, "Overlay painter panicked and was unregistered");
+        clear_painter();
+    }
+}
@@ -0,0 +1,221 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+// Sentinel for "no activity pending"; real timestamps start at 1
+const NO_ACTIVITY: u64 = 0;
+
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+/// Milliseconds since the first call, offset so that 0 never names a real instant.
+pub(crate) fn now_ms() -> u64 {
+    EPOCH.get_or_init(Instant::now).elapsed().as_millis() as u64 + 1
+}
+
+/// Liveness tracking for a low-level hook.
+///
+/// Windows silently removes a low-level hook whose callback takes too long, which
+/// happens under heavy system load. The hook procedure calls `record_event` on every
+/// invocation, and a sampling thread reports input it observed independently (e.g.
+/// via `GetAsyncKeyState`) with `observe_activity`. When observed input goes unseen
+/// by the hook for longer than the stall threshold, `check` raises a reinstall
+/// request for the main thread to act on.
+pub(crate) struct HookWatchdog {
+    stall_threshold_ms: u64,
+    last_event_ms: AtomicU64,
+    unseen_activity_since_ms: AtomicU64,
+    reinstall_requested: AtomicBool,
+    reinstalls: AtomicU64,
+}
+
+impl HookWatchdog {
+    pub(crate) const fn new(stall_threshold: Duration) -> Self {
+        Self {
+            stall_threshold_ms: stall_threshold.as_millis() as u64,
+            last_event_ms: AtomicU64::new(NO_ACTIVITY),
+            unseen_activity_since_ms: AtomicU64::new(NO_ACTIVITY),
+            reinstall_requested: AtomicBool::new(false),
+            reinstalls: AtomicU64::new(0),
+        }
+    }
+
+    /// Called from the hook procedure; must stay cheap.
+    pub(crate) fn record_event(&self, now: u64) {
+        self.last_event_ms.store(now, Ordering::Release);
+    }
+
+    /// Notes input that the hook should have seen. Only the first unseen input is
+    /// remembered so a stall is measured from when it started.
+    pub(crate) fn observe_activity(&self, now: u64) {
+        let _ = self.unseen_activity_since_ms.compare_exchange(
+            NO_ACTIVITY,
+            now,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
+    }
+
+    /// Returns true (and raises a reinstall request) if observed input has gone
+    /// unseen by the hook for longer than the stall threshold.
+    pub(crate) fn check(&self, now: u64) -> bool {
+        let since = self.unseen_activity_since_ms.load(Ordering::Acquire);
+        if since == NO_ACTIVITY {
+            return false;
+        }
+
+        if self.last_event_ms.load(Ordering::Acquire) >= since {
+            // The hook caught up with the input, so it is alive
+            self.unseen_activity_since_ms
+                .store(NO_ACTIVITY, Ordering::Release);
+            return false;
+        }
+
+        if now.saturating_sub(since) < self.stall_threshold_ms {
+            return false;
+        }
+
+        self.unseen_activity_since_ms
+            .store(NO_ACTIVITY, Ordering::Release);
+        self.reinstall_requested.store(true, Ordering::Release);
+        true
+    }
+
+    /// Consumes a pending reinstall request.
+    pub(crate) fn take_reinstall_request(&self) -> bool {
+        self.reinstall_requested.swap(false, Ordering::AcqRel)
+    }
+
+    /// Counts a completed reinstall and returns the new total.
+    pub(crate) fn record_reinstall(&self) -> u64 {
+        self.reinstalls.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub(crate) fn reinstalls(&self) -> u64 {
+        self.reinstalls.load(Ordering::Relaxed)
+    }
+
+    /// Forgets pending activity and requests, e.g. when the hook is removed on purpose.
+    pub(crate) fn reset(&self) {
+        self.unseen_activity_since_ms
+            .store(NO_ACTIVITY, Ordering::Release);
+        self.reinstall_requested.store(false, Ordering::Release);
+    }
+}
+
+/// Waits up to `timeout` for a background thread to exit, joining it if it did.
+///
+/// A thread that does not finish in time is abandoned with a warning instead of
+/// blocking the caller (usually the main thread running the message loop).
+pub(crate) fn join_with_timeout(handle: thread::JoinHandle<()>, timeout: Duration, name: &str) {
+    let deadline = Instant::now() + timeout;
+    while !handle.is_finished() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    if handle.is_finished() {
+        if handle.join().is_err() {
+            warn!("{} thread panicked", name);
+        }
+    } else {
+        warn!(
+            "{} thread did not exit within {:?}; abandoning it",
+            name, timeout
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: Duration = Duration::from_secs(3);
+
+    #[test]
+    fn test_now_ms_is_never_the_sentinel() {
+        assert_ne!(now_ms(), NO_ACTIVITY);
+    }
+
+    #[test]
+    fn test_idle_hook_is_healthy() {
+        let watchdog = HookWatchdog::new(THRESHOLD);
+
+        // No input observed at all: silence is expected
+        assert!(!watchdog.check(100_000));
+        assert!(!watchdog.take_reinstall_request());
+    }
+
+    #[test]
+    fn test_hook_seeing_activity_is_healthy() {
+        let watchdog = HookWatchdog::new(THRESHOLD);
+
+        watchdog.observe_activity(1_000);
+        watchdog.record_event(1_020);
+
+        assert!(!watchdog.check(10_000));
+        assert!(!watchdog.take_reinstall_request());
+    }
+
+    #[test]
+    fn test_unseen_activity_requests_reinstall_after_threshold() {
+        let watchdog = HookWatchdog::new(THRESHOLD);
+        watchdog.record_event(500);
+
+        watchdog.observe_activity(1_000);
+        assert!(!watchdog.check(3_999));
+
+        // Later observations must not push the stall start forward
+        watchdog.observe_activity(3_500);
+        assert!(watchdog.check(4_000));
+        assert!(watchdog.take_reinstall_request());
+        assert!(!watchdog.take_reinstall_request());
+
+        // The request is raised once per stall
+        assert!(!watchdog.check(10_000));
+    }
+
+    #[test]
+    fn test_activity_dated_to_the_previous_sample() {
+        // The mouse watchdog dates movement to the sample before it was noticed,
+        // so a hook event anywhere in between (e.g. the hook's own push) counts
+        let watchdog = HookWatchdog::new(THRESHOLD);
+
+        watchdog.record_event(1_010);
+        watchdog.observe_activity(1_000);
+        assert!(!watchdog.check(5_000));
+
+        watchdog.observe_activity(5_000);
+        assert!(!watchdog.check(7_999));
+        assert!(watchdog.check(8_000));
+    }
+
+    #[test]
+    fn test_reset_clears_pending_state() {
+        let watchdog = HookWatchdog::new(THRESHOLD);
+
+        watchdog.observe_activity(1_000);
+        assert!(watchdog.check(5_000));
+        watchdog.observe_activity(6_000);
+        watchdog.reset();
+
+        assert!(!watchdog.take_reinstall_request());
+        assert!(!watchdog.check(20_000));
+    }
+
+    #[test]
+    fn test_reinstall_count() {
+        let watchdog = HookWatchdog::new(THRESHOLD);
+
+        assert_eq!(watchdog.reinstalls(), 0);
+        assert_eq!(watchdog.record_reinstall(), 1);
+        assert_eq!(watchdog.record_reinstall(), 2);
+        assert_eq!(watchdog.reinstalls(), 2);
+    }
+
+    #[test]
+    fn test_join_with_timeout_joins_finished_thread() {
+        let handle = thread::spawn(|| {});
+        join_with_timeout(handle, Duration::from_millis(250), "Test");
+    }
+}
@@ -0,0 +1,2633 @@
+#[cfg(feature = "audio")]
+use super::audio::{self, play_sound_async};
+use super::watchdog::{self, join_with_timeout, HookWatchdog};
+use super::{
+    apply_hook_change, is_resync_move, push_cursor, start_runtime, stop_runtime, with_control,
+    OverlayOutcome, PendingResync, Platform, StartError, RESYNC_EXTRA_INFO,
+};
+use crate::decision::DecisionQueue;
+use crate::edit::EditGesture;
+use crate::engine::{
+    carry_out, ActionSink, BarrierEngine, EngineAction, MoveContext, ScreenMetrics, SoundKind,
+};
+use crate::hook_control::{BypassButton, BypassChange, HookAction, HookControl, MiddleButtonHold};
+use crate::overlay::{pack_overlay_color, unpack_overlay_color};
+use crate::place_on_screen;
+use crate::position::{DeliveryThrottle, PositionSlot, DEFAULT_POSITION_CALLBACK_HZ};
+use crate::subscription::{Subscribers, SubscriptionId};
+use crate::{
+    AccessibilitySettings, ApproachZone, BarrierDecision, BarrierError, BarrierEvent,
+    BarrierGeometry, BarrierSnapshot, BarrierStats, BufferZone, BypassModifier, CursorSpeed,
+    EditMode, EnforcementMode, EntryCue, HighContrastStyle, HookView, KeyCombo, LastPush,
+    MouseBarrierConfig, OffscreenPush, OverlayAnimation, OverlayCoverage, OverlayFailurePolicy,
+    Point, PositionSource, Rect, ScreenPlacement, Sound, Zone,
+};
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicPtr, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, debug_span, info, warn, Level};
+use winapi::shared::minwindef::{DWORD, LPARAM, LPVOID, LRESULT, UINT, WPARAM};
+use winapi::shared::windef::{POINT, RECT};
+use winapi::shared::winerror::ERROR_SUCCESS;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::wingdi::*;
+use winapi::um::winreg::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+use winapi::um::winuser::*;
+
+// The overlay windows, compiled in with the `overlay` feature
+#[cfg(feature = "overlay")]
+mod overlay_windows;
+#[cfg(feature = "overlay")]
+pub use overlay_windows::OVERLAY_CLASS_NAME;
+
+type KeyboardCallback = dyn Fn(u32, bool) + Send + Sync;
+type MousePositionCallback =
+    Arc<Mutex<Option<Arc<dyn Fn(i32, i32, PositionSource) + Send + Sync>>>>;
+type ZoneChangeCallback = Arc<Mutex<Option<Box<dyn Fn(Zone, Zone) + Send + Sync>>>>;
+type EventCallback = Arc<Mutex<Option<Box<dyn Fn(BarrierEvent) + Send + Sync>>>>;
+type DecisionCallback = Arc<Mutex<Option<Box<dyn Fn(BarrierDecision) + Send + Sync>>>>;
+
+static MOUSE_BARRIER_STATE: OnceLock<Arc<Mutex<Option<MouseBarrierState>>>> = OnceLock::new();
+static KEYBOARD_SUBSCRIBERS: Mutex<Subscribers<KeyboardCallback>> = Mutex::new(Subscribers::new());
+// Key presses the keyboard hook swallows after its subscribers have seen them
+static SUPPRESSED_COMBOS: Mutex<Vec<KeyCombo>> = Mutex::new(Vec::new());
+// The key whose press was swallowed, so its release is too; 0 for none
+static SUPPRESSED_KEY_DOWN: AtomicU32 = AtomicU32::new(0);
+// Modifier keys the keyboard hook last saw go down, one bit per key
+static HELD_MODIFIERS: AtomicU32 = AtomicU32::new(0);
+static MOUSE_POSITION_CALLBACK: OnceLock<MousePositionCallback> = OnceLock::new();
+static ZONE_CHANGE_CALLBACK: OnceLock<ZoneChangeCallback> = OnceLock::new();
+static EVENT_CALLBACK: OnceLock<EventCallback> = OnceLock::new();
+static DECISION_CALLBACK: OnceLock<DecisionCallback> = OnceLock::new();
+static DECISION_TRACING: AtomicBool = AtomicBool::new(false);
+static DECISIONS: Mutex<DecisionQueue> = Mutex::new(DecisionQueue::new());
+// Decisions the hook dropped rather than wait for the queue's lock
+static DECISIONS_CONTENDED: AtomicU64 = AtomicU64::new(0);
+static DECISION_LOG_THROTTLE: Mutex<DeliveryThrottle> =
+    Mutex::new(DeliveryThrottle::new(MOVE_LOG_MAX_HZ));
+static DECISION_LOGS_SUPPRESSED: AtomicU64 = AtomicU64::new(0);
+static HOOK_CONTROL: Mutex<HookControl> = Mutex::new(HookControl::new());
+static KEYBOARD_HOOK_HANDLE: AtomicPtr<winapi::shared::windef::HHOOK__> =
+    AtomicPtr::new(std::ptr::null_mut());
+static MOUSE_HOOK_HANDLE: AtomicPtr<winapi::shared::windef::HHOOK__> =
+    AtomicPtr::new(std::ptr::null_mut());
+static MIDDLE_BUTTON_MONITORING: AtomicBool = AtomicBool::new(false);
+static MIDDLE_MOUSE_DOWN: AtomicBool = AtomicBool::new(false);
+// A push is waiting to be reported as BarrierEvent::Pushed
+static PUSH_PENDING: AtomicBool = AtomicBool::new(false);
+// A push asked for a resync move, sent by the next process_hook_requests
+static RESYNC_PENDING: PendingResync = PendingResync::new();
+static LAST_PUSH: Mutex<Option<LastPush>> = Mutex::new(None);
+// What cursor_speed last read, for when the hook holds the barrier state
+static LAST_CURSOR_SPEED: Mutex<CursorSpeed> = Mutex::new(CursorSpeed {
+    per_event: 0,
+    per_second: 0,
+    peak_per_event: 0,
+    peak_per_second: 0,
+    peak_approach_per_event: 0,
+});
+static LATEST_POSITION: PositionSlot = PositionSlot::new();
+static POSITION_THROTTLE: Mutex<DeliveryThrottle> =
+    Mutex::new(DeliveryThrottle::new(DEFAULT_POSITION_CALLBACK_HZ));
+static MOVE_LOG_THROTTLE: Mutex<DeliveryThrottle> =
+    Mutex::new(DeliveryThrottle::new(MOVE_LOG_MAX_HZ));
+static MOVE_LOGS_SUPPRESSED: AtomicU64 = AtomicU64::new(0);
+static CURSOR_MOVE_FAILURES: AtomicU64 = AtomicU64::new(0);
+static WOULD_HAVE_PUSHED: AtomicU64 = AtomicU64::new(0);
+static OVERLAY_UNAVAILABLE: AtomicBool = AtomicBool::new(false);
+static BYPASS_MONITOR_THREAD: Mutex<Option<thread::JoinHandle<()>>> = Mutex::new(None);
+static KEYBOARD_WATCHDOG: HookWatchdog = HookWatchdog::new(KEYBOARD_STALL_THRESHOLD);
+static KEYBOARD_WATCHDOG_RUNNING: AtomicBool = AtomicBool::new(false);
+static KEYBOARD_WATCHDOG_THREAD: Mutex<Option<thread::JoinHandle<()>>> = Mutex::new(None);
+static MOUSE_WATCHDOG: HookWatchdog = HookWatchdog::new(MOUSE_STALL_THRESHOLD);
+static LAST_HOOK_HEALTH_CHECK_MS: AtomicU64 = AtomicU64::new(0);
+static LAST_SCREEN_CHECK_MS: AtomicU64 = AtomicU64::new(0);
+// When the cursor last hit the barrier, for the overlay flash; 0 means never
+static LAST_HIT_MS: AtomicU64 = AtomicU64::new(0);
+
+// The hook can see 1000 moves a second, so per-move and per-decision debug
+// logs are capped
+const MOVE_LOG_MAX_HZ: u32 = 10;
+
+// How long disable() waits for a monitor thread (bypass, keyboard watchdog) to exit
+const MONITOR_JOIN_TIMEOUT: Duration = Duration::from_millis(250);
+
+// Keys pressed without keyboard_proc running for this long mean the hook was dropped
+const KEYBOARD_STALL_THRESHOLD: Duration = Duration::from_secs(3);
+
+// How often the keyboard watchdog samples key state
+const KEYBOARD_WATCHDOG_INTERVAL: Duration = Duration::from_millis(100);
+
+// Cursor movement without mouse_proc running for this long means the hook was dropped
+const MOUSE_STALL_THRESHOLD: Duration = Duration::from_secs(3);
+
+// How often process_hook_requests looks for hooks that should be installed but aren't
+const HOOK_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// How often process_hook_requests looks for a changed screen layout
+const SCREEN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Cursor polling rate used while the bypass has the mouse hook uninstalled, and
+// for the mouse watchdog while it is installed
+const POSITION_POLL_INTERVAL: Duration = Duration::from_millis(33); // ~30 Hz
+
+// Cached screen metrics to avoid repeated API calls
+static SCREEN_WIDTH: AtomicI32 = AtomicI32::new(0);
+static SCREEN_HEIGHT: AtomicI32 = AtomicI32::new(0);
+
+// Physical screen resolution for coordinate scaling
+static PHYSICAL_SCREEN_WIDTH: AtomicI32 = AtomicI32::new(0);
+static PHYSICAL_SCREEN_HEIGHT: AtomicI32 = AtomicI32::new(0);
+
+// How long the middle button must be held before it bypasses the barrier
+static BYPASS_HOLD_MS: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Clone)]
+struct MouseBarrierState {
+    /// Where the barrier is enforced; `configured_rect` unless it had to be
+    /// moved on-screen
+    barrier_rect: Rect,
+    configured_rect: Rect,
+    clamp_to_screen: bool,
+    placement: ScreenPlacement,
+    /// The virtual screen the barrier was last placed on
+    screen: Rect,
+    buffer_zone: BufferZone,
+    buffer_blocks: bool,
+    push_factor: i32,
+    dynamic_push: bool,
+    offscreen_push: OffscreenPush,
+    screen_margin: i32,
+    approach_zone: Option<ApproachZone>,
+    enabled: bool,
+    overlay_enabled: bool,
+    overlay_color: u32, // RGB color as 0x00RRGGBB
+    proximity_coloring: bool,
+    proximity_far_color: u32, // RGB color as 0x00RRGGBB
+    proximity_range: i32,
+    buffer_alpha: u8, // Alpha of the buffer frame (0-255)
+    overlay_coverage: OverlayCoverage,
+    overlay_failure: OverlayFailurePolicy,
+    interior_alpha: u8, // Alpha of the interior overlay (0-255)
+    flash_alpha: u8,    // Alpha of the buffer frame while a hit flashes (0-255)
+    overlay_animation: OverlayAnimation,
+    overlay_tick_ms: u32, // Overlay timer interval; 0 disables the timer
+    on_barrier_hit_sound: Option<Sound>,
+    on_barrier_entry_sound: Option<Sound>,
+    entry_cue: EntryCue,
+    interrupt_previous_sound: bool,
+    consume_blocked_events: bool, // Only slowed moves; pushes are always swallowed
+    resync_after_block: bool,
+    enforcement_mode: EnforcementMode,
+    bypass_modifier: Option<BypassModifier>,
+    edit_mode: Option<EditMode>,
+    edit: EditGesture,
+    high_contrast: HighContrastStyle,
+    engine: BarrierEngine,
+}
+
+impl MouseBarrierState {
+    /// Places the configured barrier on the current screen; returns the new
+    /// placement if it changed
+    fn place(&mut self) -> Option<ScreenPlacement> {
+        let (rect, placement) =
+            place_on_screen(self.configured_rect, &self.screen, self.clamp_to_screen);
+        self.barrier_rect = rect;
+        if placement == self.placement {
+            return None;
+        }
+        self.placement = placement;
+
+        let r = self.configured_rect;
+        let s = self.screen;
+        match placement {
+            ScreenPlacement::OnScreen => info!("Barrier is on-screen again"),
+            ScreenPlacement::PartlyOffScreen => warn!(
+                "Barrier ({},{})-({},{}) extends past the screen ({},{})-({},{})",
+                r.left, r.top, r.right, r.bottom, s.left, s.top, s.right, s.bottom
+            ),
+            ScreenPlacement::OffScreen => warn!(
+                "BARRIER IS OFF-SCREEN: ({},{})-({},{}) lies entirely outside the screen \
+                 ({},{})-({},{}), so the cursor can never reach it; set clamp_to_screen \
+                 to move it on-screen",
+                r.left, r.top, r.right, r.bottom, s.left, s.top, s.right, s.bottom
+            ),
+            ScreenPlacement::MovedOnScreen => warn!(
+                "Barrier ({},{})-({},{}) lies entirely outside the screen ({},{})-({},{}); \
+                 moved it to ({},{})-({},{})",
+                r.left,
+                r.top,
+                r.right,
+                r.bottom,
+                s.left,
+                s.top,
+                s.right,
+                s.bottom,
+                rect.left,
+                rect.top,
+                rect.right,
+                rect.bottom
+            ),
+        }
+        Some(placement)
+    }
+
+    /// Hands the current geometry to the engine, keeping its cursor history
+    fn sync_engine(&mut self) {
+        self.engine.configure(
+            self.barrier_rect,
+            self.buffer_zone,
+            self.push_factor,
+            self.offscreen_push,
+            ScreenMetrics {
+                margin: self.screen_margin,
+                ..ScreenMetrics::cached()
+            },
+        );
+        self.engine.set_entry_cue(self.entry_cue);
+        self.engine.set_buffer_blocks(self.buffer_blocks);
+        self.engine.set_dynamic_push(self.dynamic_push);
+        self.engine.set_approach_zone(self.approach_zone);
+        self.engine.set_enforcement_mode(self.enforcement_mode);
+    }
+
+    fn snapshot(&self) -> BarrierSnapshot {
+        BarrierSnapshot {
+            rect: self.barrier_rect,
+            buffer: self.buffer_zone,
+            enabled: self.enabled,
+            push_factor: self.push_factor,
+            enforcement_mode: self.enforcement_mode,
+        }
+    }
+
+    fn geometry(&self) -> BarrierGeometry {
+        BarrierGeometry::new(
+            self.barrier_rect,
+            self.buffer_zone,
+            unpack_overlay_color(self.overlay_color),
+            self.buffer_alpha,
+            self.interior_alpha,
+            self.flash_alpha,
+            self.overlay_coverage,
+        )
+    }
+}
+
+pub struct MouseBarrier;
+
+/// The process-wide low-level keyboard hook, shared by all subscribers.
+///
+/// The hook is installed on the thread making the first subscription, which
+/// must run a message loop (and call [`process_hook_requests`]) for callbacks
+/// to arrive.
+pub struct KeyboardHook;
+
+impl MouseBarrier {
+    pub fn new(config: MouseBarrierConfig) -> Self {
+        // Cache screen metrics on first initialization
+        cache_screen_metrics();
+        let screen = virtual_screen();
+        let barrier_rect = config.rect(&screen);
+
+        let mut state = MouseBarrierState {
+            barrier_rect,
+            configured_rect: barrier_rect,
+            clamp_to_screen: config.clamp_to_screen,
+            placement: ScreenPlacement::OnScreen,
+            screen,
+            buffer_zone: config.buffer_zone,
+            buffer_blocks: config.buffer_blocks,
+            push_factor: config.push_factor,
+            dynamic_push: config.dynamic_push,
+            offscreen_push: config.offscreen_push,
+            screen_margin: config.screen_margin,
+            approach_zone: config.approach_zone,
+            enabled: false,
+            overlay_enabled: config.overlay_enabled,
+            overlay_color: pack_overlay_color(config.overlay_color),
+            proximity_coloring: config.proximity_coloring,
+            proximity_far_color: pack_overlay_color(config.proximity_far_color),
+            proximity_range: config.proximity_range,
+            buffer_alpha: config.buffer_alpha,
+            overlay_coverage: config.overlay_coverage,
+            overlay_failure: config.overlay_failure,
+            interior_alpha: config.interior_alpha,
+            flash_alpha: config.flash_alpha,
+            overlay_animation: config.overlay_animation,
+            overlay_tick_ms: config.overlay_tick_ms,
+            on_barrier_hit_sound: config.on_barrier_hit_sound,
+            on_barrier_entry_sound: config.on_barrier_entry_sound,
+            entry_cue: config.entry_cue,
+            interrupt_previous_sound: config.interrupt_previous_sound,
+            consume_blocked_events: config.consume_blocked_events,
+            resync_after_block: config.resync_after_block,
+            enforcement_mode: config.enforcement_mode,
+            bypass_modifier: config.bypass_modifier,
+            edit_mode: config.edit_mode,
+            edit: EditGesture::default(),
+            high_contrast: config.high_contrast,
+            engine: BarrierEngine::default(),
+        };
+        let placement = state.place();
+
+        let state_lock = MOUSE_BARRIER_STATE.get_or_init(|| Arc::new(Mutex::new(None)));
+        *state_lock.lock().unwrap() = Some(state.clone());
+
+        if let Some(placement) = placement {
+            emit_event(BarrierEvent::PlacementChanged(placement));
+        }
+
+        // Spin up the audio worker now so the first barrier hit does not pay for it
+        #[cfg(feature = "audio")]
+        audio::start_audio_worker();
+
+        #[cfg(feature = "overlay")]
+        {
+            overlay_windows::refresh_system_style();
+            overlay_windows::restyle(&state);
+        }
+        BYPASS_HOLD_MS.store(config.bypass_hold_ms, Ordering::Relaxed);
+
+        Self
+    }
+
+    pub fn enable(&mut self) -> Result<(), BarrierError> {
+        // Already enabled, possibly with the hook out for a bypass; setting up
+        // again would leak the overlay windows
+        let current_hook = MOUSE_HOOK_HANDLE.load(Ordering::Acquire);
+        if !current_hook.is_null() || self.is_enabled() {
+            return Ok(());
+        }
+
+        let (overlay_enabled, overlay_failure) = with_state(|state| {
+            state.enabled = true;
+            state.sync_engine();
+            (
+                cfg!(feature = "overlay") && state.overlay_enabled,
+                state.overlay_failure,
+            )
+        })?;
+        with_hook_control(HookControl::enable);
+
+        // Overlay windows (frame strips plus interior) unless they're turned
+        // off, then the middle button monitor, then the mouse hook unless a
+        // bypass is already held
+        match start_runtime(&mut Win32, &HOOK_CONTROL, overlay_enabled, overlay_failure) {
+            Ok(OverlayOutcome::Shown) => {
+                OVERLAY_UNAVAILABLE.store(false, Ordering::Release);
+                info!("Created overlay windows");
+                #[cfg(feature = "overlay")]
+                overlay_windows::check_overlay_click_through();
+            }
+            Ok(OverlayOutcome::Disabled) => {
+                OVERLAY_UNAVAILABLE.store(false, Ordering::Release);
+                info!("Overlay is turned off, barrier is active without one");
+            }
+            Ok(OverlayOutcome::Unavailable(e)) => {
+                OVERLAY_UNAVAILABLE.store(true, Ordering::Release);
+                warn!(
+                    "Failed to create overlay windows, barrier is active without an overlay: {}",
+                    e
+                );
+            }
+            Err(StartError::Overlay(e)) => {
+                let _ = with_state(|state| state.enabled = false);
+                return Err(format!("Failed to create overlay windows: {}", e).into());
+            }
+            Err(StartError::MouseHook(e)) => return Err(e.into()),
+        }
+
+        Ok(())
+    }
+
+    /// Disables the barrier and tears down everything `enable` set up.
+    ///
+    /// When this returns the bypass monitor thread has exited (or been
+    /// abandoned after a timeout), no hook change is pending, none can be
+    /// requested until the next `enable`, the mouse hook is uninstalled and no
+    /// overlay window exists.
+    pub fn disable(&mut self) -> Result<(), BarrierError> {
+        with_state(|state| {
+            state.enabled = false;
+            // A push armed while enabled must not fire the next time the barrier is enabled
+            state.engine.reset();
+        })?;
+
+        Ok(shutdown_barrier_runtime()?)
+    }
+
+    pub fn toggle(&mut self) -> Result<bool, BarrierError> {
+        let is_enabled = self.is_enabled();
+        if is_enabled {
+            self.disable()?;
+            Ok(false)
+        } else {
+            self.enable()?;
+            Ok(true)
+        }
+    }
+
+    /// False as well when there is no barrier state
+    pub fn is_enabled(&self) -> bool {
+        self.read_state(|state| state.enabled).unwrap_or(false)
+    }
+
+    /// Suspends the barrier until a matching [`end_bypass`](Self::end_bypass).
+    ///
+    /// Bypasses are reference counted, and the middle button and the gamepad
+    /// hold one each of their own, so the barrier only comes back once every
+    /// holder has ended theirs.
+    /// The hook change itself happens in [`process_hook_requests`].
+    pub fn begin_bypass(&self) {
+        begin_bypass();
+    }
+
+    /// Releases one bypass taken with [`begin_bypass`](Self::begin_bypass).
+    pub fn end_bypass(&self) {
+        end_bypass();
+    }
+
+    /// Holds the barrier off while the gamepad's bypass button is down.
+    ///
+    /// The gamepad's bypass is its own, like the middle button's, so neither
+    /// button coming up nor an unbalanced [`end_bypass`](Self::end_bypass)
+    /// can end it. Holding or dropping it twice does nothing.
+    pub fn hold_gamepad_bypass(&self, held: bool) {
+        hold_button_bypass(BypassButton::Gamepad, held);
+    }
+
+    /// Whether any bypass (either button or programmatic) is currently held.
+    pub fn is_bypassed(&self) -> bool {
+        with_hook_control(|control| control.is_bypassed())
+    }
+
+    /// Whether the barrier is being enforced without (part of) its overlay
+    /// because a window couldn't be created.
+    pub fn overlay_unavailable(&self) -> bool {
+        OVERLAY_UNAVAILABLE.load(Ordering::Acquire)
+    }
+
+    /// Returns the most recent cursor push, if the hook has made one.
+    pub fn last_push(&self) -> Option<LastPush> {
+        last_push()
+    }
+
+    /// Returns a snapshot of the hook's runtime counters.
+    pub fn stats(&self) -> BarrierStats {
+        BarrierStats {
+            cursor_move_failures: CURSOR_MOVE_FAILURES.load(Ordering::Relaxed),
+            keyboard_hook_reinstalls: KEYBOARD_WATCHDOG.reinstalls(),
+            mouse_hook_reinstalls: MOUSE_WATCHDOG.reinstalls(),
+            would_have_pushed: WOULD_HAVE_PUSHED.load(Ordering::Relaxed),
+            cursor_speed: cursor_speed(),
+        }
+    }
+
+    /// Applies a new barrier configuration, moving, resizing and recoloring
+    /// the overlay windows in place when the barrier is enabled.
+    pub fn update_barrier(&mut self, config: MouseBarrierConfig) -> Result<(), BarrierError> {
+        BYPASS_HOLD_MS.store(config.bypass_hold_ms, Ordering::Relaxed);
+        self.update_state(|state| {
+            state.configured_rect = config.rect(&state.screen);
+            state.clamp_to_screen = config.clamp_to_screen;
+            state.buffer_zone = config.buffer_zone;
+            state.buffer_blocks = config.buffer_blocks;
+            state.push_factor = config.push_factor;
+            state.dynamic_push = config.dynamic_push;
+            state.offscreen_push = config.offscreen_push;
+            state.screen_margin = config.screen_margin;
+            state.approach_zone = config.approach_zone;
+            state.overlay_enabled = config.overlay_enabled;
+            state.overlay_color = pack_overlay_color(config.overlay_color);
+            state.proximity_coloring = config.proximity_coloring;
+            state.proximity_far_color = pack_overlay_color(config.proximity_far_color);
+            state.proximity_range = config.proximity_range;
+            state.buffer_alpha = config.buffer_alpha;
+            state.overlay_coverage = config.overlay_coverage;
+            state.overlay_failure = config.overlay_failure;
+            state.interior_alpha = config.interior_alpha;
+            state.flash_alpha = config.flash_alpha;
+            state.overlay_animation = config.overlay_animation;
+            state.overlay_tick_ms = config.overlay_tick_ms;
+            state.on_barrier_hit_sound = config.on_barrier_hit_sound;
+            state.on_barrier_entry_sound = config.on_barrier_entry_sound;
+            state.entry_cue = config.entry_cue;
+            state.interrupt_previous_sound = config.interrupt_previous_sound;
+            state.consume_blocked_events = config.consume_blocked_events;
+            state.resync_after_block = config.resync_after_block;
+            state.enforcement_mode = config.enforcement_mode;
+            state.bypass_modifier = config.bypass_modifier;
+            state.edit_mode = config.edit_mode;
+            state.high_contrast = config.high_contrast;
+        })
+    }
+
+    /// Returns a copy of the geometry and state the hook is currently enforcing.
+    pub fn snapshot(&self) -> Option<BarrierSnapshot> {
+        self.read_state(MouseBarrierState::snapshot)
+    }
+
+    /// The barriers the hook is enforcing, in screen coordinates, with their
+    /// buffer zones and overlay colors.
+    ///
+    /// Empty if there is no barrier state. There is a single barrier for now;
+    /// the list leaves room for more.
+    pub fn current_geometry(&self) -> Vec<BarrierGeometry> {
+        self.read_state(|state| vec![state.geometry()])
+            .unwrap_or_default()
+    }
+
+    pub fn buffer_zone(&self) -> BufferZone {
+        self.read_state(|state| state.buffer_zone)
+            .unwrap_or_default()
+    }
+
+    /// Sets the buffer zone width, one for every edge or one per edge, with
+    /// each edge clamped to be non-negative, and resizes the overlays.
+    pub fn set_buffer_zone(
+        &mut self,
+        buffer_zone: impl Into<BufferZone>,
+    ) -> Result<(), BarrierError> {
+        let buffer_zone = buffer_zone.into();
+        self.update_state(|state| {
+            state.buffer_zone = BufferZone {
+                top: buffer_zone.top.max(0),
+                right: buffer_zone.right.max(0),
+                bottom: buffer_zone.bottom.max(0),
+                left: buffer_zone.left.max(0),
+            }
+        })
+    }
+
+    pub fn push_factor(&self) -> i32 {
+        self.read_state(|state| state.push_factor).unwrap_or(0)
+    }
+
+    /// Sets the push distance, clamped to at least one pixel.
+    pub fn set_push_factor(&mut self, push_factor: i32) -> Result<(), BarrierError> {
+        self.update_state(|state| state.push_factor = push_factor.max(1))
+    }
+
+    pub fn overlay_color(&self) -> (u8, u8, u8) {
+        self.read_state(|state| unpack_overlay_color(state.overlay_color))
+            .unwrap_or_default()
+    }
+
+    /// Sets the overlay color and repaints the overlays.
+    pub fn set_overlay_color(&mut self, r: u8, g: u8, b: u8) -> Result<(), BarrierError> {
+        self.update_state(|state| state.overlay_color = pack_overlay_color((r, g, b)))
+    }
+
+    fn read_state<R>(&self, read: impl FnOnce(&MouseBarrierState) -> R) -> Option<R> {
+        let state_lock = MOUSE_BARRIER_STATE.get()?;
+        let state_guard = state_lock.lock().ok()?;
+        state_guard.as_ref().map(read)
+    }
+
+    fn update_state(
+        &mut self,
+        change: impl FnOnce(&mut MouseBarrierState),
+    ) -> Result<(), BarrierError> {
+        update_barrier_state(change)
+    }
+}
+
+/// Applies `change` to the barrier state, places the barrier on the screen
+/// again, then asks for the overlay windows to be brought in line with it if
+/// the barrier is enabled. The windows follow on the next
+/// [`process_hook_requests`], which coalesces a burst of changes.
+fn update_barrier_state(change: impl FnOnce(&mut MouseBarrierState)) -> Result<(), BarrierError> {
+    let (enabled, overlay_enabled, placement) = with_state(|state| {
+        change(state);
+        let placement = state.place();
+        state.sync_engine();
+        #[cfg(feature = "overlay")]
+        overlay_windows::restyle(state);
+
+        (state.enabled, state.overlay_enabled, placement)
+    })?;
+
+    if let Some(placement) = placement {
+        emit_event(BarrierEvent::PlacementChanged(placement));
+    }
+
+    // Reposition the overlay windows to the new geometry, creating them if
+    // the overlay was turned on, or destroy them if it was turned off. An
+    // overlay that's off can't be missing windows
+    #[cfg(feature = "overlay")]
+    if enabled && overlay_enabled {
+        overlay_windows::request_layout();
+    } else if enabled {
+        overlay_windows::destroy_overlay_windows();
+        OVERLAY_UNAVAILABLE.store(false, Ordering::Release);
+    }
+    #[cfg(not(feature = "overlay"))]
+    let _ = (enabled, overlay_enabled);
+    Ok(())
+}
+
+/// Applies `change` to the barrier state, or fails if `MouseBarrier::new`
+/// hasn't set it up. A hook that panicked mid-update leaves the state usable,
+/// so a poisoned lock is recovered rather than propagated.
+fn with_state<R>(change: impl FnOnce(&mut MouseBarrierState) -> R) -> Result<R, BarrierError> {
+    let state_lock = MOUSE_BARRIER_STATE
+        .get()
+        .ok_or(BarrierError::NotInitialized)?;
+    let mut state_guard = state_lock.lock().unwrap_or_else(PoisonError::into_inner);
+    state_guard
+        .as_mut()
+        .map(change)
+        .ok_or(BarrierError::NotInitialized)
+}
+
+impl Drop for MouseBarrier {
+    fn drop(&mut self) {
+        if let Err(e) = self.disable() {
+            warn!("Failed to disable mouse barrier on drop: {}", e);
+        }
+    }
+}
+
+impl KeyboardHook {
+    /// Adds `callback` to the subscribers receiving every key event as
+    /// `(virtual key code, is_down)`.
+    ///
+    /// The first subscriber installs the hook and starts its watchdog, which
+    /// reinstalls the hook (via `process_hook_requests`) if Windows silently
+    /// drops it. If installing fails, the callback is not subscribed.
+    pub fn subscribe<F>(callback: F) -> Result<SubscriptionId, String>
+    where
+        F: Fn(u32, bool) + Send + Sync + 'static,
+    {
+        let mut subscribers = lock_keyboard_subscribers();
+        if subscribers.is_empty() {
+            install_keyboard_hook()?;
+            start_keyboard_watchdog();
+        }
+
+        Ok(subscribers.add(Arc::new(callback)))
+    }
+
+    /// Keeps presses of `combos` from reaching the focused app: subscribers
+    /// still see them, then the hook swallows the key-down and its matching
+    /// key-up. Replaces the combos set before; an empty list lets every key
+    /// through.
+    pub fn set_suppressed(combos: Vec<KeyCombo>) {
+        *SUPPRESSED_COMBOS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = combos;
+    }
+
+    /// Removes a subscriber; unknown or already removed ids are ignored. The
+    /// last subscriber leaving uninstalls the hook.
+    pub fn unsubscribe(id: SubscriptionId) -> Result<(), String> {
+        let mut subscribers = lock_keyboard_subscribers();
+        if !subscribers.remove(id) || !subscribers.is_empty() {
+            return Ok(());
+        }
+
+        stop_keyboard_watchdog();
+        uninstall_keyboard_hook()
+    }
+}
+
+fn lock_keyboard_subscribers() -> std::sync::MutexGuard<'static, Subscribers<KeyboardCallback>> {
+    match KEYBOARD_SUBSCRIBERS.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Registers the callback receiving cursor positions (physical pixels).
+///
+/// Positions normally come from the mouse hook. While the middle-button
+/// bypass has the hook uninstalled and the barrier is enabled, the cursor is
+/// polled instead and reported with [`PositionSource::Polled`].
+///
+/// The hook only records the latest position; the callback runs from
+/// [`process_hook_requests`], on that caller's thread, with only the newest
+/// position since the previous call. It is called at most
+/// [`set_mouse_position_callback_rate`] times per second (60 by default) and
+/// not at all while the cursor is still.
+pub fn set_mouse_position_callback<F>(callback: F)
+where
+    F: Fn(i32, i32, PositionSource) + Send + Sync + 'static,
+{
+    let callback_lock = MOUSE_POSITION_CALLBACK.get_or_init(|| Arc::new(Mutex::new(None)));
+    if let Ok(mut guard) = callback_lock.lock() {
+        *guard = Some(Arc::new(callback));
+    }
+}
+
+/// Caps how often the mouse position callback is called; 0 removes the cap
+/// and delivers every time [`process_hook_requests`] sees a new position.
+pub fn set_mouse_position_callback_rate(max_hz: u32) {
+    if let Ok(mut throttle) = POSITION_THROTTLE.lock() {
+        throttle.set_max_hz(max_hz);
+    }
+}
+
+/// Registers a callback invoked with `(previous, current)` whenever the cursor
+/// moves between [`Zone`]s while the barrier is enabled.
+///
+/// The callback runs on the hook thread with the barrier state locked, so it
+/// must be quick and must not call back into [`MouseBarrier`].
+pub fn set_on_zone_change_callback<F>(callback: F)
+where
+    F: Fn(Zone, Zone) + Send + Sync + 'static,
+{
+    let callback_lock = ZONE_CHANGE_CALLBACK.get_or_init(|| Arc::new(Mutex::new(None)));
+    if let Ok(mut guard) = callback_lock.lock() {
+        *guard = Some(Box::new(callback));
+    }
+}
+
+fn notify_zone_change(previous: Zone, current: Zone) {
+    if let Some(callback_lock) = ZONE_CHANGE_CALLBACK.get() {
+        if let Ok(callback_guard) = callback_lock.lock() {
+            if let Some(ref callback) = *callback_guard {
+                callback(previous, current);
+            }
+        }
+    }
+}
+
+/// Registers a callback for [`BarrierEvent`]s.
+///
+/// Events are delivered on whichever thread caused them (for the middle-button
+/// bypass, the monitor thread; for pushes, the one calling
+/// [`process_hook_requests`]), so the callback should hand work off rather
+/// than block.
+pub fn set_event_callback<F>(callback: F)
+where
+    F: Fn(BarrierEvent) + Send + Sync + 'static,
+{
+    let callback_lock = EVENT_CALLBACK.get_or_init(|| Arc::new(Mutex::new(None)));
+    if let Ok(mut guard) = callback_lock.lock() {
+        *guard = Some(Box::new(callback));
+    }
+}
+
+fn emit_event(event: BarrierEvent) {
+    if let Some(callback_lock) = EVENT_CALLBACK.get() {
+        if let Ok(callback_guard) = callback_lock.lock() {
+            if let Some(ref callback) = *callback_guard {
+                callback(event);
+            }
+        }
+    }
+}
+
+/// Registers the callback receiving every [`BarrierDecision`] the mouse hook
+/// makes while decision tracing is on (see [`set_decision_tracing`]).
+///
+/// The hook only queues decisions; the callback runs from
+/// [`process_hook_requests`] on the thread calling it, so slow work such as
+/// writing to disk never delays input. Each decision is also logged at debug
+/// level, at most 10 times a second.
+pub fn set_decision_callback<F>(callback: F)
+where
+    F: Fn(BarrierDecision) + Send + Sync + 'static,
+{
+    let callback_lock = DECISION_CALLBACK.get_or_init(|| Arc::new(Mutex::new(None)));
+    if let Ok(mut guard) = callback_lock.lock() {
+        *guard = Some(Box::new(callback));
+    }
+}
+
+/// Turns decision tracing on or off. While off (the default) the hook records
+/// nothing, so it costs a single atomic load per move.
+pub fn set_decision_tracing(enabled: bool) {
+    DECISION_TRACING.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        if let Ok(mut decisions) = DECISIONS.lock() {
+            decisions.clear();
+        }
+    }
+}
+
+/// Called from the hook, so it never waits on the main loop draining the queue
+fn queue_decision(decision: BarrierDecision) {
+    match DECISIONS.try_lock() {
+        Ok(mut decisions) => decisions.push(decision),
+        Err(_) => {
+            DECISIONS_CONTENDED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Hands queued decisions to the decision callback, oldest first
+fn deliver_decisions() {
+    let Ok((decisions, dropped)) = DECISIONS.lock().map(|mut queue| queue.drain()) else {
+        return;
+    };
+    let dropped = dropped + DECISIONS_CONTENDED.swap(0, Ordering::Relaxed);
+    if dropped > 0 {
+        warn!(
+            dropped = dropped,
+            "Dropped barrier decisions the main loop fell behind on"
+        );
+    }
+    if decisions.is_empty() {
+        return;
+    }
+
+    if tracing::enabled!(Level::DEBUG) {
+        for decision in &decisions {
+            log_decision(decision);
+        }
+    }
+    if let Some(callback_lock) = DECISION_CALLBACK.get() {
+        if let Ok(callback_guard) = callback_lock.lock() {
+            if let Some(ref callback) = *callback_guard {
+                for decision in decisions {
+                    callback(decision);
+                }
+            }
+        }
+    }
+}
+
+/// Logs `decision` at most `MOVE_LOG_MAX_HZ` times a second, reporting how
+/// many were skipped since the previous log
+fn log_decision(decision: &BarrierDecision) {
+    let now = Instant::now();
+    let Ok(mut throttle) = DECISION_LOG_THROTTLE.lock() else {
+        return;
+    };
+    if !throttle.ready(now) {
+        DECISION_LOGS_SUPPRESSED.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+    throttle.record(now);
+    drop(throttle);
+
+    debug!(
+        kind = ?decision.kind,
+        input = ?decision.input,
+        last = ?decision.last,
+        output = ?decision.output,
+        suppressed = DECISION_LOGS_SUPPRESSED.swap(0, Ordering::Relaxed),
+        "Barrier decision"
+    );
+}
+
+/// Returns the most recent cursor push, if the hook has made one.
+///
+/// Unlike [`MouseBarrier::last_push`] this needs no barrier handle, so
+/// diagnostics running on other threads can poll it.
+pub fn last_push() -> Option<LastPush> {
+    LAST_PUSH.lock().ok().and_then(|guard| *guard)
+}
+
+/// How fast the cursor has been moving while the barrier was enabled, now and
+/// at its fastest this session; all zero without barrier state.
+///
+/// Unlike [`MouseBarrier::stats`] this needs no barrier handle, so the HUD can
+/// poll it as it paints. Uses `try_lock` so painting never waits on the mouse
+/// hook; while the hook holds the state, the last reading is returned.
+pub fn cursor_speed() -> CursorSpeed {
+    let Some(state_lock) = MOUSE_BARRIER_STATE.get() else {
+        return CursorSpeed::default();
+    };
+    let mut last = LAST_CURSOR_SPEED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let state_guard = match state_lock.try_lock() {
+        Ok(guard) => guard,
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        Err(TryLockError::WouldBlock) => return *last,
+    };
+    *last = state_guard
+        .as_ref()
+        .map(|state| state.engine.cursor_speed())
+        .unwrap_or_default();
+    *last
+}
+
+/// Where the barrier would put the cursor if it moved from `last_point` to
+/// `point`, or `None` if the move would be let through.
+///
+/// Runs the mouse hook's decision against the current barrier without moving
+/// the cursor or touching the hook's history, so a config UI can preview the
+/// barrier. The answer is the same whether or not the barrier is enabled;
+/// without barrier state nothing is blocked.
+pub fn simulate(point: Point, last_point: Option<Point>) -> Option<(i32, i32)> {
+    let state_lock = MOUSE_BARRIER_STATE.get()?;
+    let state_guard = state_lock.lock().unwrap_or_else(PoisonError::into_inner);
+    let target = state_guard.as_ref()?.engine.preview(point, last_point)?;
+    Some((target.x, target.y))
+}
+
+/// The barrier the hook is enforcing and its last decision, or `None` without
+/// barrier state.
+///
+/// Reads the hook's own state rather than a copy, so a debug overlay polling
+/// it shows exactly what the trajectory check works with.
+pub fn hook_view() -> Option<HookView> {
+    let state_lock = MOUSE_BARRIER_STATE.get()?;
+    let state_guard = state_lock.lock().unwrap_or_else(PoisonError::into_inner);
+    let state = state_guard.as_ref()?;
+    Some(HookView {
+        snapshot: state.snapshot(),
+        decision: state.engine.last_decision(),
+    })
+}
+
+fn record_push(push: LastPush) {
+    if let Ok(mut guard) = LAST_PUSH.lock() {
+        *guard = Some(push);
+    }
+    PUSH_PENDING.store(true, Ordering::Release);
+}
+
+/// Reports the pushes made since the last call as one
+/// [`BarrierEvent::Pushed`], off the hook thread
+fn deliver_push() {
+    if PUSH_PENDING.swap(false, Ordering::AcqRel) {
+        emit_event(BarrierEvent::Pushed);
+    }
+}
+
+fn with_hook_control<R>(f: impl FnOnce(&mut HookControl) -> R) -> R {
+    with_control(&HOOK_CONTROL, f)
+}
+
+fn begin_bypass() {
+    if with_hook_control(HookControl::begin_bypass) {
+        info!("Bypass started, requested mouse hook uninstall");
+        emit_event(BarrierEvent::BypassStarted);
+    }
+}
+
+fn end_bypass() {
+    if with_hook_control(HookControl::end_bypass) {
+        info!("Bypass ended, requested mouse hook reinstall if still enabled");
+        emit_event(BarrierEvent::BypassEnded);
+    }
+}
+
+/// Takes or drops `button`'s own bypass
+fn hold_button_bypass(button: BypassButton, held: bool) {
+    if !with_hook_control(|control| control.hold_button(button, held)) {
+        return;
+    }
+    if held {
+        info!("Bypass started, requested mouse hook uninstall");
+        emit_event(BarrierEvent::BypassStarted);
+    } else {
+        info!("Bypass ended, requested mouse hook reinstall if still enabled");
+        emit_event(BarrierEvent::BypassEnded);
+    }
+}
+
+/// Hands the latest published position to the position callback, if one
+/// arrived and the rate limit allows it
+fn deliver_mouse_position() {
+    let now = Instant::now();
+    let Ok(mut throttle) = POSITION_THROTTLE.lock() else {
+        return;
+    };
+    if !throttle.ready(now) {
+        return;
+    }
+    let Some((pos, source)) = LATEST_POSITION.take() else {
+        return;
+    };
+    throttle.record(now);
+    drop(throttle);
+
+    // Called after the lock is released, so a slow callback never holds up
+    // one being replaced
+    let callback = MOUSE_POSITION_CALLBACK
+        .get()
+        .and_then(|callback_lock| callback_lock.lock().ok()?.clone());
+    if let Some(callback) = callback {
+        callback(pos.x, pos.y, source);
+    }
+}
+
+unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    MOUSE_WATCHDOG.record_event(watchdog::now_ms());
+
+    if code >= 0 {
+        let mouse_data = *(lparam as *const MSLLHOOKSTRUCT);
+        // Our own resync move; acting on it could push again and send another
+        if is_resync_move(mouse_data.dwExtraInfo) {
+            return CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam);
+        }
+        let is_move = wparam == WM_MOUSEMOVE as WPARAM;
+        if is_move {
+            // Delivered to the position callback later, off the hook thread
+            LATEST_POSITION.publish(Point::from(mouse_data.pt), PositionSource::Hook);
+        }
+
+        // One lock per event, so editing and enforcement see the same state
+        if let Some(state_lock) = MOUSE_BARRIER_STATE.get() {
+            if let Ok(mut state_guard) = state_lock.lock() {
+                if let Some(ref mut state) = *state_guard {
+                    match edit_barrier(state, wparam, &mouse_data) {
+                        Some(true) => return 1,
+                        Some(false) => {
+                            return CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+                        }
+                        None => {}
+                    }
+                    if is_move && enforce_barrier(state, Point::from(mouse_data.pt)) {
+                        return 1;
+                    }
+                }
+            }
+        }
+    }
+
+    CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+}
+
+/// Runs a mouse move past the barrier; returns whether to swallow it
+fn enforce_barrier(state: &mut MouseBarrierState, current_pos: Point) -> bool {
+    if !state.enabled {
+        return false;
+    }
+    if bypass_modifier_held(state.bypass_modifier) {
+        // Let the cursor through, and don't judge the first move after the
+        // key is released by where it was before
+        state.engine.reset();
+        return false;
+    }
+
+    // Geometry for the debug logs, skipped unless they're on
+    let context = tracing::enabled!(Level::DEBUG).then(|| state.engine.move_context(current_pos));
+    let _move_span = context.as_ref().map(|context| {
+        debug_span!(
+            "mouse_move",
+            barrier = ?context.barrier,
+            buffer = ?context.buffer,
+            last = ?context.last,
+            current = ?context.current,
+            push_factor = context.push_factor,
+        )
+        .entered()
+    });
+
+    #[cfg(feature = "overlay")]
+    if state.overlay_enabled && state.proximity_coloring {
+        overlay_windows::update_proximity_color(state, current_pos);
+    }
+    let actions = state.engine.on_mouse_move(current_pos, Instant::now());
+    if let Some(context) = &context {
+        log_barrier_move(context, &actions);
+    }
+    if DECISION_TRACING.load(Ordering::Relaxed) {
+        if let Some(decision) = state.engine.last_decision() {
+            queue_decision(decision);
+        }
+    }
+    let mut sink = HookSink {
+        hit_sound: state.on_barrier_hit_sound.as_ref(),
+        entry_sound: state.on_barrier_entry_sound.as_ref(),
+        interrupt_previous_sound: state.interrupt_previous_sound,
+        resync_after_block: state.resync_after_block,
+    };
+    carry_out(
+        &mut state.engine,
+        actions,
+        state.consume_blocked_events,
+        &mut sink,
+    )
+}
+
+/// Feeds mouse input to the edit gesture while the edit modifiers are held.
+///
+/// Returns `None` when not editing, so the event goes through the barrier as
+/// usual; otherwise whether to swallow it. The barrier isn't enforced while
+/// editing, or it would fight the drag; the moved barrier is applied later by
+/// `process_hook_requests`, outside the hook.
+fn edit_barrier(
+    state: &mut MouseBarrierState,
+    wparam: WPARAM,
+    mouse_data: &MSLLHOOKSTRUCT,
+) -> Option<bool> {
+    // A press the gesture swallowed has its release swallowed too, even once
+    // the modifiers are up, so the app never sees a release without a press.
+    // One whose press went through goes through as well.
+    if wparam as UINT == WM_LBUTTONUP && state.edit.release() {
+        return Some(true);
+    }
+    let mode = state.edit_mode?;
+    if !state.enabled || !edit_modifiers_held(&mode) {
+        return None;
+    }
+
+    let pos = Point::from(mouse_data.pt);
+    let consumed = match wparam as UINT {
+        WM_MOUSEWHEEL => {
+            let wheel_delta = GET_WHEEL_DELTA_WPARAM(mouse_data.mouseData as WPARAM) as i32;
+            state.edit.wheel(
+                state.barrier_rect,
+                state.buffer_zone,
+                pos,
+                wheel_delta,
+                mode.resize_step,
+            )
+        }
+        WM_LBUTTONDOWN => state.edit.press(state.barrier_rect, state.buffer_zone, pos),
+        WM_MOUSEMOVE => {
+            state.edit.drag_to(pos);
+            // The cursor history is stale once editing ends
+            state.engine.reset();
+            false
+        }
+        _ => false,
+    };
+    Some(consumed)
+}
+
+/// Whether every modifier `mode` asks for is held down, as the keyboard hook
+/// saw them and `GetAsyncKeyState` confirms
+fn edit_modifiers_held(mode: &EditMode) -> bool {
+    edit_modifiers_held_with(mode, |vk| unsafe {
+        GetAsyncKeyState(vk) & 0x8000u16 as i16 != 0
+    })
+}
+
+/// [`edit_modifiers_held`] with `key_down` standing in for `GetAsyncKeyState`.
+///
+/// Called for every mouse event, so it goes by what the keyboard hook
+/// recorded and only asks the keyboard while that says the modifiers are
+/// down, as [`bypass_modifier_held_with`] does.
+fn edit_modifiers_held_with(mode: &EditMode, key_down: impl Fn(i32) -> bool) -> bool {
+    [
+        (mode.ctrl, BypassModifier::Ctrl),
+        (mode.alt, BypassModifier::Alt),
+        (mode.shift, BypassModifier::Shift),
+    ]
+    .into_iter()
+    .all(|(wanted, modifier)| !wanted || bypass_modifier_held_with(Some(modifier), &key_down))
+}
+
+/// Applies the barrier moves and resizes the edit gesture made, and reports
+/// the result once the edit modifiers are released
+fn apply_barrier_edit() {
+    let Ok((pending, finished)) = with_state(|state| {
+        if !state.edit.is_active() {
+            return (None, None);
+        }
+        let editing = state.enabled && state.edit_mode.as_ref().is_some_and(edit_modifiers_held);
+        let finished = if editing { None } else { state.edit.finish() };
+        (state.edit.take_pending(), finished)
+    }) else {
+        return;
+    };
+
+    if let Some(rect) = pending {
+        if let Err(e) = update_barrier_state(|state| state.configured_rect = rect) {
+            warn!("Failed to apply the edited barrier: {}", e);
+        }
+    }
+    if let Some(rect) = finished {
+        info!(
+            "Barrier edited to ({},{})-({},{})",
+            rect.left, rect.top, rect.right, rect.bottom
+        );
+        emit_event(BarrierEvent::Edited(rect));
+    }
+}
+
+/// Carries out engine actions for real: moves the cursor, plays the
+/// configured sounds and notifies the zone change callback
+struct HookSink<'a> {
+    hit_sound: Option<&'a Sound>,
+    entry_sound: Option<&'a Sound>,
+    interrupt_previous_sound: bool,
+    resync_after_block: bool,
+}
+
+impl ActionSink for HookSink<'_> {
+    fn move_cursor(&mut self, push: &LastPush) -> bool {
+        let resync = self.resync_after_block.then_some(&RESYNC_PENDING);
+        let moved = push_cursor(&mut Win32, push.to, resync);
+        if moved {
+            record_push(*push);
+        }
+        moved
+    }
+
+    fn slow_cursor(&mut self, to: Point) -> bool {
+        Win32.set_cursor_pos(to)
+    }
+
+    fn zone_changed(&mut self, from: Zone, to: Zone) {
+        notify_zone_change(from, to);
+    }
+
+    fn would_push(&mut self, _push: &LastPush) {
+        WOULD_HAVE_PUSHED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn play_sound(&mut self, kind: SoundKind) {
+        if kind == SoundKind::BarrierHit {
+            LAST_HIT_MS.store(watchdog::now_ms(), Ordering::Relaxed);
+        }
+        let sound = match kind {
+            SoundKind::BarrierHit => self.hit_sound,
+            SoundKind::BarrierEntry => self.entry_sound,
+        };
+        if let Some(sound) = sound {
+            Win32.play_sound(sound, self.interrupt_previous_sound);
+        }
+    }
+}
+
+/// Logs a move the barrier acted on from inside its `mouse_move` span, at most
+/// `MOVE_LOG_MAX_HZ` times a second. Each log reports how many were skipped
+/// since the previous one.
+fn log_barrier_move(context: &MoveContext, actions: &[EngineAction]) {
+    let push = actions.iter().find_map(|action| match action {
+        EngineAction::MoveCursorTo(push) => Some(push),
+        _ => None,
+    });
+    if push.is_none() && context.zone != Zone::Barrier {
+        return;
+    }
+
+    let now = Instant::now();
+    let Ok(mut throttle) = MOVE_LOG_THROTTLE.lock() else {
+        return;
+    };
+    if !throttle.ready(now) {
+        MOVE_LOGS_SUPPRESSED.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+    throttle.record(now);
+    drop(throttle);
+
+    let suppressed = MOVE_LOGS_SUPPRESSED.swap(0, Ordering::Relaxed);
+    match push {
+        Some(push) => debug!(
+            zone = ?context.zone,
+            to = ?push.to,
+            edge = ?push.edge,
+            speed = push.speed,
+            multiplier = push.multiplier,
+            suppressed = suppressed,
+            "Redirecting cursor"
+        ),
+        None => debug!(suppressed = suppressed, "Cursor in barrier!"),
+    }
+}
+
+unsafe extern "system" fn keyboard_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    KEYBOARD_WATCHDOG.record_event(watchdog::now_ms());
+
+    if code >= 0 {
+        let kbd_data = *(lparam as *const KBDLLHOOKSTRUCT);
+        let is_key_down = wparam == WM_KEYDOWN as WPARAM || wparam == WM_SYSKEYDOWN as WPARAM;
+        track_modifier(kbd_data.vkCode, kbd_data.scanCode, is_key_down);
+        // Run the callbacks outside the lock so they may (un)subscribe
+        let callbacks = lock_keyboard_subscribers().snapshot();
+        for callback in callbacks {
+            callback(kbd_data.vkCode, is_key_down);
+        }
+        if is_suppressed_key(kbd_data.vkCode, is_key_down) {
+            return 1;
+        }
+    }
+
+    CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+}
+
+/// The `HELD_MODIFIERS` bit for `vk_code`, if it's a modifier; the generic
+/// codes share a bit with the left keys
+fn modifier_bit(vk_code: u32) -> Option<u32> {
+    let index = match vk_code as i32 {
+        VK_CONTROL | VK_LCONTROL => 0,
+        VK_RCONTROL => 1,
+        VK_MENU | VK_LMENU => 2,
+        VK_RMENU => 3,
+        VK_SHIFT | VK_LSHIFT => 4,
+        VK_RSHIFT => 5,
+        VK_LWIN => 6,
+        VK_RWIN => 7,
+        _ => return None,
+    };
+    Some(1 << index)
+}
+
+/// The `HELD_MODIFIERS` bits for either key of `modifier`
+fn modifier_mask(modifier: BypassModifier) -> u32 {
+    match modifier {
+        BypassModifier::Ctrl => 0b11,
+        BypassModifier::Alt => 0b1100,
+        BypassModifier::Shift => 0b11_0000,
+        BypassModifier::Win => 0b1100_0000,
+    }
+}
+
+/// Scan code the keyboard hook sees on the Left Ctrl that Windows sends
+/// along with AltGr
+const ALTGR_CTRL_SCAN_CODE: u32 = 0x21D;
+/// The `HELD_MODIFIERS` bit for that Ctrl, which no modifier mask includes
+const ALTGR_CTRL_BIT: u32 = 1 << 8;
+
+/// Records a modifier going down or up in `HELD_MODIFIERS`. The Ctrl that
+/// comes with AltGr gets a bit of its own, so typing with AltGr never counts
+/// as holding Ctrl+Alt.
+fn track_modifier(vk_code: u32, scan_code: u32, is_down: bool) {
+    let bit = if vk_code == VK_LCONTROL as u32 && scan_code == ALTGR_CTRL_SCAN_CODE {
+        Some(ALTGR_CTRL_BIT)
+    } else {
+        modifier_bit(vk_code)
+    };
+    if let Some(bit) = bit {
+        if is_down {
+            HELD_MODIFIERS.fetch_or(bit, Ordering::Relaxed);
+        } else {
+            HELD_MODIFIERS.fetch_and(!bit, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Whether the barrier's bypass modifier is held, as the keyboard hook saw it
+/// and `GetAsyncKeyState` confirms
+fn bypass_modifier_held(modifier: Option<BypassModifier>) -> bool {
+    bypass_modifier_held_with(modifier, |vk| unsafe {
+        GetAsyncKeyState(vk) & 0x8000u16 as i16 != 0
+    })
+}
+
+/// [`bypass_modifier_held`] with `key_down` standing in for
+/// `GetAsyncKeyState`.
+///
+/// The keyboard hook can miss a release, e.g. one made while a secure desktop
+/// had the input, which would leave the barrier bypassed until the key is
+/// pressed again. So a modifier it saw go down is only honoured while the
+/// key still reads as down, and forgotten otherwise. That is only asked
+/// while the hook thinks the key is held, so ordinary moves never call it.
+fn bypass_modifier_held_with(
+    modifier: Option<BypassModifier>,
+    key_down: impl Fn(i32) -> bool,
+) -> bool {
+    let Some(modifier) = modifier else {
+        return false;
+    };
+    let mask = modifier_mask(modifier);
+    if HELD_MODIFIERS.load(Ordering::Relaxed) & mask == 0 {
+        return false;
+    }
+    let down = match modifier {
+        BypassModifier::Ctrl => key_down(VK_CONTROL),
+        BypassModifier::Alt => key_down(VK_MENU),
+        BypassModifier::Shift => key_down(VK_SHIFT),
+        BypassModifier::Win => key_down(VK_LWIN) || key_down(VK_RWIN),
+    };
+    if !down {
+        HELD_MODIFIERS.fetch_and(!mask, Ordering::Relaxed);
+    }
+    down
+}
+
+/// Whether the keyboard hook swallows this key event: a press of one of the
+/// suppressed combos, or the release of a press it swallowed
+fn is_suppressed_key(vk_code: u32, is_down: bool) -> bool {
+    is_suppressed_key_with(vk_code, is_down, |vk| unsafe {
+        GetAsyncKeyState(vk) & 0x8000u16 as i16 != 0
+    })
+}
+
+/// [`is_suppressed_key`] with `key_down` standing in for `GetAsyncKeyState`
+/// when reading the modifiers
+fn is_suppressed_key_with(vk_code: u32, is_down: bool, key_down: impl Fn(i32) -> bool) -> bool {
+    if !is_down {
+        return SUPPRESSED_KEY_DOWN
+            .compare_exchange(vk_code, 0, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok();
+    }
+    let Ok(combos) = SUPPRESSED_COMBOS.try_lock() else {
+        return false;
+    };
+    if combos.is_empty() {
+        return false;
+    }
+    let (ctrl, alt, shift) = (key_down(VK_CONTROL), key_down(VK_MENU), key_down(VK_SHIFT));
+    let suppressed = combos.iter().any(|combo| {
+        combo.vk_code == vk_code && combo.ctrl == ctrl && combo.alt == alt && combo.shift == shift
+    });
+    if suppressed {
+        SUPPRESSED_KEY_DOWN.store(vk_code, Ordering::Release);
+    }
+    suppressed
+}
+
+fn install_keyboard_hook() -> Result<(), String> {
+    unsafe {
+        let hook = SetWindowsHookExW(
+            WH_KEYBOARD_LL,
+            Some(keyboard_proc),
+            GetModuleHandleW(std::ptr::null()),
+            0,
+        );
+
+        if hook.is_null() {
+            return Err(format!("Failed to set keyboard hook: {}", GetLastError()));
+        }
+
+        KEYBOARD_HOOK_HANDLE.store(hook, Ordering::Release);
+    }
+
+    Ok(())
+}
+
+fn uninstall_keyboard_hook() -> Result<(), String> {
+    let hook = KEYBOARD_HOOK_HANDLE.swap(std::ptr::null_mut(), Ordering::AcqRel);
+
+    if !hook.is_null() {
+        unsafe {
+            if UnhookWindowsHookEx(hook) == 0 {
+                return Err(format!("Failed to unhook keyboard: {}", GetLastError()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces a keyboard hook that has stopped receiving events. Windows may have
+/// already removed the old one, so failing to unhook it is not an error.
+fn reinstall_keyboard_hook() -> Result<(), String> {
+    if let Err(e) = uninstall_keyboard_hook() {
+        info!("Stale keyboard hook was already gone: {}", e);
+    }
+    // The dead hook may have missed modifier releases
+    HELD_MODIFIERS.store(0, Ordering::Relaxed);
+    install_keyboard_hook()
+}
+
+fn install_mouse_hook() -> Result<(), String> {
+    let current_hook = MOUSE_HOOK_HANDLE.load(Ordering::Acquire);
+    if !current_hook.is_null() {
+        return Ok(());
+    }
+
+    unsafe {
+        let hook = SetWindowsHookExW(
+            WH_MOUSE_LL,
+            Some(mouse_proc),
+            GetModuleHandleW(std::ptr::null()),
+            0,
+        );
+
+        if hook.is_null() {
+            return Err(format!("Failed to set mouse hook: {}", GetLastError()));
+        }
+
+        MOUSE_HOOK_HANDLE.store(hook, Ordering::Release);
+    }
+    Ok(())
+}
+
+fn uninstall_mouse_hook() -> Result<(), String> {
+    let hook = MOUSE_HOOK_HANDLE.swap(std::ptr::null_mut(), Ordering::AcqRel);
+    // Movement the removed hook missed says nothing about the next one
+    MOUSE_WATCHDOG.reset();
+
+    if !hook.is_null() {
+        unsafe {
+            if UnhookWindowsHookEx(hook) == 0 {
+                return Err(format!("Failed to unhook mouse: {}", GetLastError()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replaces a mouse hook that has stopped receiving events. Windows may have
+/// already removed the old one, so failing to unhook it is not an error.
+fn reinstall_mouse_hook() -> Result<(), String> {
+    if let Err(e) = uninstall_mouse_hook() {
+        info!("Stale mouse hook was already gone: {}", e);
+    }
+    install_mouse_hook()
+}
+
+/// The mouse hook should be installed: the barrier is enabled and no bypass
+/// holds it off
+fn mouse_hook_expected() -> bool {
+    with_hook_control(|control| control.hook_wanted())
+}
+
+/// The keyboard hook should be installed: [`KeyboardHook`] has subscribers,
+/// whose first subscription started the watchdog
+fn keyboard_hook_expected() -> bool {
+    KEYBOARD_WATCHDOG_RUNNING.load(Ordering::Acquire)
+}
+
+/// Whether every hook that should be installed is.
+///
+/// False while the barrier is enabled but its mouse hook is gone, or while
+/// the [`KeyboardHook`] has subscribers but its hook is gone, e.g. because
+/// Windows dropped it and reinstalling failed. The barrier or hotkey doesn't
+/// work until [`process_hook_requests`] manages to reinstall it.
+pub fn hooks_healthy() -> bool {
+    let mouse_ok = !mouse_hook_expected() || !MOUSE_HOOK_HANDLE.load(Ordering::Acquire).is_null();
+    let keyboard_ok =
+        !keyboard_hook_expected() || !KEYBOARD_HOOK_HANDLE.load(Ordering::Acquire).is_null();
+    mouse_ok && keyboard_ok
+}
+
+/// Reinstalls hooks the watchdogs consider dead, and at most once per
+/// `HOOK_HEALTH_CHECK_INTERVAL` any hook that should be installed but isn't.
+fn check_hook_health() {
+    // Check for a keyboard hook the watchdog considers dead
+    if KEYBOARD_WATCHDOG.take_reinstall_request()
+        && !KEYBOARD_HOOK_HANDLE.load(Ordering::Acquire).is_null()
+    {
+        match reinstall_keyboard_hook() {
+            Ok(()) => {
+                let reinstalls = KEYBOARD_WATCHDOG.record_reinstall();
+                info!(
+                    reinstalls = reinstalls,
+                    "Reinstalled keyboard hook after it stopped receiving events"
+                );
+            }
+            Err(e) => warn!("Failed to reinstall keyboard hook: {}", e),
+        }
+    }
+
+    // Check for a mouse hook the watchdog considers dead
+    if MOUSE_WATCHDOG.take_reinstall_request()
+        && !MOUSE_HOOK_HANDLE.load(Ordering::Acquire).is_null()
+    {
+        match reinstall_mouse_hook() {
+            Ok(()) => {
+                let reinstalls = MOUSE_WATCHDOG.record_reinstall();
+                info!(
+                    reinstalls = reinstalls,
+                    "Reinstalled mouse hook after it stopped receiving events"
+                );
+            }
+            Err(e) => warn!("Failed to reinstall mouse hook: {}", e),
+        }
+    }
+
+    let now = watchdog::now_ms();
+    let last_check = LAST_HOOK_HEALTH_CHECK_MS.load(Ordering::Relaxed);
+    if now.saturating_sub(last_check) < HOOK_HEALTH_CHECK_INTERVAL.as_millis() as u64 {
+        return;
+    }
+    LAST_HOOK_HEALTH_CHECK_MS.store(now, Ordering::Relaxed);
+
+    // A failed reinstall leaves the handle empty; keep retrying while it's needed
+    if mouse_hook_expected() && MOUSE_HOOK_HANDLE.load(Ordering::Acquire).is_null() {
+        match install_mouse_hook() {
+            Ok(()) => {
+                let reinstalls = MOUSE_WATCHDOG.record_reinstall();
+                warn!(
+                    reinstalls = reinstalls,
+                    "Mouse hook was missing while the barrier is enabled; reinstalled it"
+                );
+            }
+            Err(e) => warn!("Mouse hook is missing and reinstalling failed: {}", e),
+        }
+    }
+
+    if keyboard_hook_expected() && KEYBOARD_HOOK_HANDLE.load(Ordering::Acquire).is_null() {
+        match install_keyboard_hook() {
+            Ok(()) => {
+                let reinstalls = KEYBOARD_WATCHDOG.record_reinstall();
+                warn!(
+                    reinstalls = reinstalls,
+                    "Keyboard hook was missing while enabled; reinstalled it"
+                );
+            }
+            Err(e) => warn!("Keyboard hook is missing and reinstalling failed: {}", e),
+        }
+    }
+}
+
+/// Services hook install/uninstall requests, reinstalls hooks that were
+/// dropped, and delivers the latest cursor position to the position callback.
+/// Call it regularly from the thread that owns the hooks.
+pub fn process_hook_requests() {
+    match apply_hook_change(&mut Win32, &HOOK_CONTROL) {
+        Some((HookAction::Uninstall, Ok(()))) => info!("Uninstalled mouse hook for a bypass"),
+        Some((HookAction::Uninstall, Err(e))) => warn!("Failed to uninstall mouse hook: {}", e),
+        Some((HookAction::Install, Ok(()))) => {
+            info!("Reinstalled mouse hook after the bypass ended")
+        }
+        Some((HookAction::Install, Err(e))) => warn!("Failed to reinstall mouse hook: {}", e),
+        None => {}
+    }
+
+    RESYNC_PENDING.send(&mut Win32);
+    deliver_push();
+
+    apply_barrier_edit();
+    #[cfg(feature = "overlay")]
+    overlay_windows::apply_pending_layout();
+    deliver_mouse_position();
+    deliver_decisions();
+    check_hook_health();
+    check_virtual_screen();
+}
+
+/// Places the barrier again when the screen layout changed (a resolution
+/// change, a monitor plugged in or out), so an off-screen barrier is reported
+/// or moved just as it would be at startup
+fn check_virtual_screen() {
+    let now = watchdog::now_ms();
+    let last_check = LAST_SCREEN_CHECK_MS.load(Ordering::Relaxed);
+    if now.saturating_sub(last_check) < SCREEN_CHECK_INTERVAL.as_millis() as u64 {
+        return;
+    }
+    LAST_SCREEN_CHECK_MS.store(now, Ordering::Relaxed);
+
+    let screen = virtual_screen();
+    let Ok(previous) = with_state(|state| state.screen) else {
+        return;
+    };
+    if screen == previous {
+        return;
+    }
+
+    info!(
+        "Screen layout changed from ({},{})-({},{}) to ({},{})-({},{})",
+        previous.left,
+        previous.top,
+        previous.right,
+        previous.bottom,
+        screen.left,
+        screen.top,
+        screen.right,
+        screen.bottom
+    );
+    cache_screen_metrics();
+    if let Err(e) = update_barrier_state(|state| state.screen = screen) {
+        warn!("Failed to place the barrier on the new screen: {}", e);
+    }
+}
+
+/// Tears down everything `enable` set up; see [`stop_runtime`] for the order
+fn shutdown_barrier_runtime() -> Result<(), String> {
+    let unhook_result = stop_runtime(&mut Win32, &HOOK_CONTROL);
+    OVERLAY_UNAVAILABLE.store(false, Ordering::Release);
+    // The windows it drew on are gone
+    #[cfg(feature = "overlay")]
+    overlay_windows::clear_painter();
+    unhook_result
+}
+
+/// The [`Platform`] the barrier runtime runs on under Windows
+struct Win32;
+
+impl Platform for Win32 {
+    fn install_mouse_hook(&mut self) -> Result<(), String> {
+        install_mouse_hook()
+    }
+
+    fn uninstall_mouse_hook(&mut self) -> Result<(), String> {
+        uninstall_mouse_hook()
+    }
+
+    fn mouse_hook_installed(&self) -> bool {
+        !MOUSE_HOOK_HANDLE.load(Ordering::Acquire).is_null()
+    }
+
+    #[cfg(feature = "overlay")]
+    fn create_overlays(&mut self) -> Result<(), String> {
+        overlay_windows::create_overlays()
+    }
+
+    /// Built without overlay windows; `enable` never asks for them
+    #[cfg(not(feature = "overlay"))]
+    fn create_overlays(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn destroy_overlays(&mut self) {
+        #[cfg(feature = "overlay")]
+        overlay_windows::destroy_overlay_windows();
+    }
+
+    fn start_bypass_monitor(&mut self) {
+        start_bypass_monitor(middle_button_pressed);
+    }
+
+    fn stop_bypass_monitor(&mut self) {
+        stop_bypass_monitor();
+    }
+
+    fn set_cursor_pos(&mut self, to: Point) -> bool {
+        move_cursor(&to)
+    }
+
+    fn resync_cursor(&mut self) {
+        send_resync_move();
+    }
+
+    #[cfg(feature = "audio")]
+    fn play_sound(&mut self, sound: &Sound, interrupt_previous: bool) {
+        play_sound_async(sound, interrupt_previous);
+    }
+
+    /// Built without audio, so sounds are skipped
+    #[cfg(not(feature = "audio"))]
+    fn play_sound(&mut self, _sound: &Sound, _interrupt_previous: bool) {}
+
+    fn screen_metrics(&self) -> ScreenMetrics {
+        ScreenMetrics::current()
+    }
+}
+
+fn start_bypass_monitor(read_middle_button: fn() -> bool) {
+    // Never run two monitors at once; a leftover thread would keep raising requests
+    stop_bypass_monitor();
+
+    MIDDLE_BUTTON_MONITORING.store(true, Ordering::Release);
+    let handle = thread::spawn(move || {
+        monitor_middle_button_and_control_hook(read_middle_button);
+    });
+
+    if let Ok(mut guard) = BYPASS_MONITOR_THREAD.lock() {
+        *guard = Some(handle);
+    }
+}
+
+fn stop_bypass_monitor() {
+    MIDDLE_BUTTON_MONITORING.store(false, Ordering::Release);
+
+    let handle = match BYPASS_MONITOR_THREAD.lock() {
+        Ok(mut guard) => guard.take(),
+        Err(poisoned) => poisoned.into_inner().take(),
+    };
+
+    if let Some(handle) = handle {
+        join_with_timeout(handle, MONITOR_JOIN_TIMEOUT, "Bypass monitor");
+    }
+
+    MIDDLE_MOUSE_DOWN.store(false, Ordering::Relaxed);
+}
+
+fn start_keyboard_watchdog() {
+    stop_keyboard_watchdog();
+
+    KEYBOARD_WATCHDOG_RUNNING.store(true, Ordering::Release);
+    let handle = thread::spawn(monitor_keyboard_hook_health);
+
+    if let Ok(mut guard) = KEYBOARD_WATCHDOG_THREAD.lock() {
+        *guard = Some(handle);
+    }
+}
+
+fn stop_keyboard_watchdog() {
+    KEYBOARD_WATCHDOG_RUNNING.store(false, Ordering::Release);
+
+    let handle = match KEYBOARD_WATCHDOG_THREAD.lock() {
+        Ok(mut guard) => guard.take(),
+        Err(poisoned) => poisoned.into_inner().take(),
+    };
+
+    if let Some(handle) = handle {
+        join_with_timeout(handle, MONITOR_JOIN_TIMEOUT, "Keyboard watchdog");
+    }
+
+    KEYBOARD_WATCHDOG.reset();
+}
+
+/// Samples the keyboard with `GetAsyncKeyState` and reports fresh key presses to
+/// the watchdog, which requests a reinstall if `keyboard_proc` never saw them.
+fn monitor_keyboard_hook_health() {
+    let mut last_pressed = sample_pressed_keys();
+
+    while KEYBOARD_WATCHDOG_RUNNING.load(Ordering::Acquire) {
+        thread::sleep(KEYBOARD_WATCHDOG_INTERVAL);
+
+        let pressed = sample_pressed_keys();
+        let now = watchdog::now_ms();
+        if has_new_key_press(&last_pressed, &pressed) {
+            KEYBOARD_WATCHDOG.observe_activity(now);
+        }
+        last_pressed = pressed;
+
+        if KEYBOARD_WATCHDOG.check(now) {
+            warn!(
+                "Keyboard hook missed key presses for {:?}; requesting reinstall",
+                KEYBOARD_STALL_THRESHOLD
+            );
+        }
+    }
+}
+
+/// Bitset of the virtual keys currently held down. Mouse buttons are skipped
+/// since they never reach the keyboard hook.
+fn sample_pressed_keys() -> [u64; 4] {
+    let mut pressed = [0u64; 4];
+    for vk in 0x08..=0xFEi32 {
+        let down = unsafe { GetAsyncKeyState(vk) } & 0x8000u16 as i16 != 0;
+        if down {
+            pressed[vk as usize / 64] |= 1 << (vk as usize % 64);
+        }
+    }
+    pressed
+}
+
+fn has_new_key_press(previous: &[u64; 4], current: &[u64; 4]) -> bool {
+    previous
+        .iter()
+        .zip(current)
+        .any(|(previous, current)| current & !previous != 0)
+}
+
+fn middle_button_pressed() -> bool {
+    unsafe { GetAsyncKeyState(VK_MBUTTON) & 0x8000u16 as i16 != 0 }
+}
+
+fn barrier_state_enabled() -> bool {
+    MOUSE_BARRIER_STATE
+        .get()
+        .and_then(|state_lock| state_lock.lock().ok())
+        .is_some_and(|state_guard| state_guard.as_ref().is_some_and(|state| state.enabled))
+}
+
+/// Feeds the position callback from `GetPhysicalCursorPos` while the hook is
+/// uninstalled but the barrier is logically enabled, so consumers don't freeze
+/// on the last hooked position during a bypass.
+fn poll_cursor_position_while_unhooked() {
+    if !MOUSE_HOOK_HANDLE.load(Ordering::Acquire).is_null() || !barrier_state_enabled() {
+        return;
+    }
+
+    let mut pos = POINT { x: 0, y: 0 };
+    // Physical coordinates, matching what the low-level hook reports
+    if unsafe { GetPhysicalCursorPos(&mut pos) } != 0 {
+        LATEST_POSITION.publish(Point::from(pos), PositionSource::Polled);
+    }
+}
+
+/// Where the cursor was at a mouse watchdog sample, and when
+struct CursorSample {
+    pos: Point,
+    at_ms: u64,
+}
+
+/// Reports cursor movement to the mouse watchdog while the hook is installed.
+///
+/// Movement counts as seen if `mouse_proc` ran at any point since the previous
+/// sample, which also covers the cursor moves the hook makes itself.
+fn sample_mouse_hook_health(previous: &mut Option<CursorSample>) {
+    if MOUSE_HOOK_HANDLE.load(Ordering::Acquire).is_null() {
+        *previous = None;
+        return;
+    }
+
+    let mut pos = POINT { x: 0, y: 0 };
+    if unsafe { GetPhysicalCursorPos(&mut pos) } == 0 {
+        return;
+    }
+    let sample = CursorSample {
+        pos: Point::from(pos),
+        at_ms: watchdog::now_ms(),
+    };
+
+    if let Some(previous) = previous.as_ref() {
+        if previous.pos != sample.pos {
+            MOUSE_WATCHDOG.observe_activity(previous.at_ms);
+        }
+    }
+    if MOUSE_WATCHDOG.check(sample.at_ms) {
+        warn!(
+            "Mouse hook missed cursor movement for {:?}; requesting reinstall",
+            MOUSE_STALL_THRESHOLD
+        );
+    }
+    *previous = Some(sample);
+}
+
+fn monitor_middle_button_and_control_hook(read_middle_button: fn() -> bool) {
+    let mut hold = MiddleButtonHold::default();
+    let mut last_position_poll = std::time::Instant::now();
+    let mut last_cursor_sample = None;
+
+    while MIDDLE_BUTTON_MONITORING.load(Ordering::Acquire) {
+        let middle_pressed = read_middle_button();
+
+        // The middle button holds a bypass from when it has been down for
+        // BYPASS_HOLD_MS until it is released
+        let hold_ms = u64::from(BYPASS_HOLD_MS.load(Ordering::Relaxed));
+        match hold.sample(middle_pressed, watchdog::now_ms(), hold_ms) {
+            Some(BypassChange::Begin) => hold_button_bypass(BypassButton::Middle, true),
+            Some(BypassChange::End) => hold_button_bypass(BypassButton::Middle, false),
+            None => {}
+        }
+
+        MIDDLE_MOUSE_DOWN.store(middle_pressed, Ordering::Relaxed);
+
+        if last_position_poll.elapsed() >= POSITION_POLL_INTERVAL {
+            last_position_poll = std::time::Instant::now();
+            poll_cursor_position_while_unhooked();
+            sample_mouse_hook_health(&mut last_cursor_sample);
+        }
+
+        thread::sleep(Duration::from_millis(5)); // 200Hz polling for responsiveness
+    }
+
+    // Don't leave the button's bypass held if monitoring stopped mid-press
+    hold_button_bypass(BypassButton::Middle, false);
+}
+
+/// Moves the cursor, retrying once if `SetCursorPos` fails.
+///
+/// Returns false when both attempts fail; the hook then lets the original event
+/// through rather than swallowing it and leaving the cursor stalled.
+fn move_cursor(pos: &Point) -> bool {
+    move_cursor_with(pos, |x, y| unsafe { SetCursorPos(x, y) != 0 })
+}
+
+/// Injects a zero-length relative move tagged with `RESYNC_EXTRA_INFO`, so
+/// apps reading raw input see the cursor where the barrier put it
+fn send_resync_move() {
+    unsafe {
+        let mut input: INPUT = mem::zeroed();
+        input.type_ = INPUT_MOUSE;
+        *input.u.mi_mut() = MOUSEINPUT {
+            dx: 0,
+            dy: 0,
+            mouseData: 0,
+            dwFlags: MOUSEEVENTF_MOVE,
+            time: 0,
+            dwExtraInfo: RESYNC_EXTRA_INFO,
+        };
+        if SendInput(1, &mut input, mem::size_of::<INPUT>() as i32) != 1 {
+            debug!("Failed to send a cursor resync move: {}", GetLastError());
+        }
+    }
+}
+
+fn move_cursor_with(pos: &Point, set_cursor_pos: impl Fn(i32, i32) -> bool) -> bool {
+    if set_cursor_pos(pos.x, pos.y) || set_cursor_pos(pos.x, pos.y) {
+        return true;
+    }
+
+    let failures = CURSOR_MOVE_FAILURES.fetch_add(1, Ordering::Relaxed) + 1;
+    warn!(
+        x = pos.x,
+        y = pos.y,
+        failures = failures,
+        "SetCursorPos failed"
+    );
+    false
+}
+
+/// Reads whether a high contrast theme is active and whether transparency
+/// effects are turned on.
+///
+/// Settings that can't be read count as the Windows defaults.
+pub fn accessibility_settings() -> AccessibilitySettings {
+    let mut contrast = HIGHCONTRASTW {
+        cbSize: mem::size_of::<HIGHCONTRASTW>() as UINT,
+        dwFlags: 0,
+        lpszDefaultScheme: ptr::null_mut(),
+    };
+    let read = unsafe {
+        SystemParametersInfoW(
+            SPI_GETHIGHCONTRAST,
+            contrast.cbSize,
+            &mut contrast as *mut HIGHCONTRASTW as LPVOID,
+            0,
+        )
+    } != 0;
+
+    AccessibilitySettings {
+        high_contrast: read && contrast.dwFlags & HCF_HIGHCONTRASTON != 0,
+        transparency_effects: transparency_effects_enabled().unwrap_or(true),
+    }
+}
+
+/// The "Transparency effects" switch under Personalization > Colors; `None`
+/// on Windows versions without it
+fn transparency_effects_enabled() -> Option<bool> {
+    let wide = |text: &str| -> Vec<u16> { text.encode_utf16().chain(std::iter::once(0)).collect() };
+    let key = wide(r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize");
+    let value = wide("EnableTransparency");
+    let mut data: DWORD = 0;
+    let mut size = mem::size_of::<DWORD>() as DWORD;
+
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            key.as_ptr(),
+            value.as_ptr(),
+            RRF_RT_REG_DWORD,
+            ptr::null_mut(),
+            &mut data as *mut DWORD as LPVOID,
+            &mut size,
+        )
+    };
+    (status == ERROR_SUCCESS as i32).then_some(data != 0)
+}
+
+impl From<POINT> for Point {
+    fn from(point: POINT) -> Self {
+        Self {
+            x: point.x,
+            y: point.y,
+        }
+    }
+}
+
+impl From<RECT> for Rect {
+    fn from(rect: RECT) -> Self {
+        Self {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        }
+    }
+}
+
+impl From<Rect> for RECT {
+    fn from(rect: Rect) -> Self {
+        Self {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        }
+    }
+}
+
+impl ScreenMetrics {
+    /// The metrics cached when the barrier was created, so the mouse hook
+    /// never queries the system while handling an event
+    fn cached() -> Self {
+        Self {
+            width: SCREEN_WIDTH.load(Ordering::Relaxed),
+            height: SCREEN_HEIGHT.load(Ordering::Relaxed),
+            physical_width: PHYSICAL_SCREEN_WIDTH.load(Ordering::Relaxed),
+            physical_height: PHYSICAL_SCREEN_HEIGHT.load(Ordering::Relaxed),
+            margin: 0,
+        }
+    }
+
+    #[cfg_attr(not(feature = "overlay"), allow(dead_code))]
+    fn current() -> Self {
+        Self {
+            width: unsafe { GetSystemMetrics(SM_CXSCREEN) },
+            height: unsafe { GetSystemMetrics(SM_CYSCREEN) },
+            physical_width: PHYSICAL_SCREEN_WIDTH.load(Ordering::Relaxed),
+            physical_height: PHYSICAL_SCREEN_HEIGHT.load(Ordering::Relaxed),
+            margin: 0,
+        }
+    }
+}
+
+/// Caches the primary screen's logical and physical size for the mouse hook
+fn cache_screen_metrics() {
+    unsafe {
+        let width = GetSystemMetrics(SM_CXSCREEN);
+        let height = GetSystemMetrics(SM_CYSCREEN);
+        SCREEN_WIDTH.store(width, Ordering::Relaxed);
+        SCREEN_HEIGHT.store(height, Ordering::Relaxed);
+
+        // Cache physical screen resolution for coordinate scaling using EnumDisplaySettings
+        let mut dev_mode: DEVMODEW = std::mem::zeroed();
+        dev_mode.dmSize = std::mem::size_of::<DEVMODEW>() as u16;
+
+        let (physical_width, physical_height) =
+            if EnumDisplaySettingsW(std::ptr::null(), ENUM_CURRENT_SETTINGS, &mut dev_mode) != 0 {
+                (dev_mode.dmPelsWidth as i32, dev_mode.dmPelsHeight as i32)
+            } else {
+                // Fallback to logical screen size if EnumDisplaySettings fails
+                (width, height)
+            };
+
+        PHYSICAL_SCREEN_WIDTH.store(physical_width, Ordering::Relaxed);
+        PHYSICAL_SCREEN_HEIGHT.store(physical_height, Ordering::Relaxed);
+
+        info!(
+            "Screen metrics initialized - Logical: {}x{}, Physical: {}x{}",
+            width, height, physical_width, physical_height
+        );
+    }
+}
+
+/// The bounding rectangle of all monitors, in screen coordinates
+fn virtual_screen() -> Rect {
+    unsafe {
+        let left = GetSystemMetrics(SM_XVIRTUALSCREEN);
+        let top = GetSystemMetrics(SM_YVIRTUALSCREEN);
+        Rect {
+            left,
+            top,
+            right: left + GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            bottom: top + GetSystemMetrics(SM_CYVIRTUALSCREEN),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BuiltinSound;
+
+    #[test]
+    fn test_has_new_key_press() {
+        let none = [0u64; 4];
+        let mut ctrl = [0u64; 4];
+        ctrl[0] |= 1 << 0x11;
+        let mut ctrl_f12 = ctrl;
+        ctrl_f12[VK_F12 as usize / 64] |= 1 << (VK_F12 as usize % 64);
+
+        assert!(has_new_key_press(&none, &ctrl));
+        assert!(has_new_key_press(&ctrl, &ctrl_f12));
+        // Holding or releasing keys is not new input
+        assert!(!has_new_key_press(&ctrl, &ctrl));
+        assert!(!has_new_key_press(&ctrl_f12, &ctrl));
+        assert!(!has_new_key_press(&ctrl, &none));
+    }
+
+    #[test]
+    fn test_move_cursor_retries_once_before_failing() {
+        let target = Point { x: 10, y: 20 };
+
+        // First attempt fails, retry succeeds: no failure recorded
+        let attempts = std::cell::Cell::new(0);
+        let before = CURSOR_MOVE_FAILURES.load(Ordering::Relaxed);
+        assert!(move_cursor_with(&target, |x, y| {
+            assert_eq!((x, y), (10, 20));
+            attempts.set(attempts.get() + 1);
+            attempts.get() > 1
+        }));
+        assert_eq!(attempts.get(), 2);
+        assert_eq!(CURSOR_MOVE_FAILURES.load(Ordering::Relaxed), before);
+
+        // Both attempts fail: the move is reported as failed and counted
+        let attempts = std::cell::Cell::new(0);
+        assert!(!move_cursor_with(&target, |_, _| {
+            attempts.set(attempts.get() + 1);
+            false
+        }));
+        assert_eq!(attempts.get(), 2);
+        assert!(CURSOR_MOVE_FAILURES.load(Ordering::Relaxed) > before);
+    }
+
+    #[test]
+    fn test_bypass_modifier_tracks_either_key() {
+        let _guard = GLOBAL_STATE_TEST_LOCK.lock().unwrap();
+        let held = |modifier| bypass_modifier_held_with(modifier, |_| true);
+        HELD_MODIFIERS.store(0, Ordering::Relaxed);
+        assert!(!held(Some(BypassModifier::Alt)));
+
+        track_modifier(VK_RMENU as u32, 0, true);
+        assert!(held(Some(BypassModifier::Alt)));
+        assert!(!held(Some(BypassModifier::Ctrl)));
+        assert!(!held(None));
+
+        // Still held on the left after the right one is released
+        track_modifier(VK_LMENU as u32, 0, true);
+        track_modifier(VK_RMENU as u32, 0, false);
+        assert!(held(Some(BypassModifier::Alt)));
+        track_modifier(VK_LMENU as u32, 0, false);
+        assert!(!held(Some(BypassModifier::Alt)));
+
+        track_modifier(VK_LWIN as u32, 0, true);
+        track_modifier('A' as u32, 0, true);
+        assert!(held(Some(BypassModifier::Win)));
+        track_modifier(VK_LWIN as u32, 0, false);
+        assert_eq!(HELD_MODIFIERS.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_suppressed_combo_swallows_its_press_and_release() {
+        let _guard = GLOBAL_STATE_TEST_LOCK.lock().unwrap();
+        let ctrl_m = KeyCombo {
+            vk_code: 'M' as u32,
+            ctrl: true,
+            alt: false,
+            shift: false,
+        };
+        KeyboardHook::set_suppressed(vec![ctrl_m]);
+        let ctrl = |vk| vk == VK_CONTROL;
+        let nothing = |_| false;
+
+        // Without Ctrl, M goes through, and so does its release
+        assert!(!is_suppressed_key_with('M' as u32, true, nothing));
+        assert!(!is_suppressed_key_with('M' as u32, false, nothing));
+
+        assert!(is_suppressed_key_with('M' as u32, true, ctrl));
+        // Other keys' releases aren't the one that was swallowed
+        assert!(!is_suppressed_key_with('B' as u32, false, ctrl));
+        // Swallowed even after Ctrl comes up first, but only once
+        assert!(is_suppressed_key_with('M' as u32, false, nothing));
+        assert!(!is_suppressed_key_with('M' as u32, false, nothing));
+
+        // An extra modifier makes it a different combo
+        let ctrl_shift = |vk| vk == VK_CONTROL || vk == VK_SHIFT;
+        assert!(!is_suppressed_key_with('M' as u32, true, ctrl_shift));
+
+        // Clearing the list lets everything through
+        KeyboardHook::set_suppressed(Vec::new());
+        assert!(!is_suppressed_key_with('M' as u32, true, ctrl));
+        SUPPRESSED_KEY_DOWN.store(0, Ordering::Release);
+    }
+
+    #[test]
+    fn test_altgr_is_not_ctrl_alt_for_editing() {
+        let _guard = GLOBAL_STATE_TEST_LOCK.lock().unwrap();
+        HELD_MODIFIERS.store(0, Ordering::Relaxed);
+        let mode = EditMode {
+            ctrl: true,
+            alt: true,
+            shift: false,
+            resize_step: 10,
+        };
+        // GetAsyncKeyState reads Ctrl and Alt as down while AltGr is
+        let all_down = |_| true;
+
+        track_modifier(VK_LCONTROL as u32, ALTGR_CTRL_SCAN_CODE, true);
+        track_modifier(VK_RMENU as u32, 0x38, true);
+        assert!(!edit_modifiers_held_with(&mode, all_down));
+
+        // With a Ctrl of its own it is Ctrl+Alt
+        track_modifier(VK_RCONTROL as u32, 0x1D, true);
+        assert!(edit_modifiers_held_with(&mode, all_down));
+
+        track_modifier(VK_RCONTROL as u32, 0x1D, false);
+        track_modifier(VK_RMENU as u32, 0x38, false);
+        track_modifier(VK_LCONTROL as u32, ALTGR_CTRL_SCAN_CODE, false);
+        assert_eq!(HELD_MODIFIERS.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_edit_modifiers_ask_the_keyboard_only_while_held() {
+        let _guard = GLOBAL_STATE_TEST_LOCK.lock().unwrap();
+        HELD_MODIFIERS.store(0, Ordering::Relaxed);
+        let mode = EditMode {
+            ctrl: true,
+            alt: false,
+            shift: true,
+            resize_step: 10,
+        };
+        let asked = std::cell::Cell::new(0);
+        let all_down = |_| {
+            asked.set(asked.get() + 1);
+            true
+        };
+
+        // Ordinary moves read nothing but the hook's record
+        assert!(!edit_modifiers_held_with(&mode, all_down));
+        assert_eq!(asked.get(), 0);
+
+        track_modifier(VK_LCONTROL as u32, 0x1D, true);
+        track_modifier(VK_LSHIFT as u32, 0x2A, true);
+        assert!(edit_modifiers_held_with(&mode, all_down));
+        assert_eq!(asked.get(), 2);
+        HELD_MODIFIERS.store(0, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn test_bypass_modifier_forgets_a_missed_release() {
+        let _guard = GLOBAL_STATE_TEST_LOCK.lock().unwrap();
+        HELD_MODIFIERS.store(0, Ordering::Relaxed);
+        track_modifier(VK_LCONTROL as u32, 0, true);
+        track_modifier(VK_LSHIFT as u32, 0, true);
+
+        // The hook never saw Ctrl come back up, but the keyboard says it's up
+        let asked = std::cell::Cell::new(0);
+        let ctrl_up = |vk| {
+            asked.set(vk);
+            vk != VK_CONTROL
+        };
+        assert!(!bypass_modifier_held_with(
+            Some(BypassModifier::Ctrl),
+            ctrl_up
+        ));
+        assert_eq!(asked.get(), VK_CONTROL);
+        assert_eq!(HELD_MODIFIERS.load(Ordering::Relaxed), 0b1_0000);
+
+        // Not held as far as the hook knows, so the keyboard isn't asked
+        asked.set(0);
+        assert!(!bypass_modifier_held_with(
+            Some(BypassModifier::Ctrl),
+            ctrl_up
+        ));
+        assert_eq!(asked.get(), 0);
+
+        // Shift is still down, and stays recorded
+        assert!(bypass_modifier_held_with(
+            Some(BypassModifier::Shift),
+            ctrl_up
+        ));
+        HELD_MODIFIERS.store(0, Ordering::Relaxed);
+    }
+
+    static FAKE_MIDDLE_BUTTON_POLLS: std::sync::atomic::AtomicU32 =
+        std::sync::atomic::AtomicU32::new(0);
+
+    fn flapping_middle_button() -> bool {
+        // Alternate pressed/released on every poll so the monitor raises as many
+        // install/uninstall requests as it possibly can
+        FAKE_MIDDLE_BUTTON_POLLS
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(2)
+    }
+
+    // Serializes the tests that swap out the global barrier state or the
+    // held modifiers
+    static GLOBAL_STATE_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    fn set_test_barrier_enabled(enabled: bool) {
+        if enabled {
+            with_hook_control(HookControl::enable);
+        } else {
+            with_hook_control(HookControl::disable);
+        }
+        let state_lock = MOUSE_BARRIER_STATE.get_or_init(|| Arc::new(Mutex::new(None)));
+        *state_lock.lock().unwrap() = Some(MouseBarrierState {
+            barrier_rect: Rect {
+                left: 0,
+                top: 0,
+                right: 100,
+                bottom: 100,
+            },
+            configured_rect: Rect {
+                left: 0,
+                top: 0,
+                right: 100,
+                bottom: 100,
+            },
+            clamp_to_screen: false,
+            placement: ScreenPlacement::OnScreen,
+            screen: Rect {
+                left: 0,
+                top: 0,
+                right: 1920,
+                bottom: 1080,
+            },
+            buffer_zone: BufferZone::uniform(10),
+            buffer_blocks: true,
+            push_factor: 30,
+            dynamic_push: true,
+            offscreen_push: OffscreenPush::ClampToEdge,
+            screen_margin: 0,
+            approach_zone: None,
+            enabled,
+            overlay_enabled: true,
+            overlay_color: 0xFF0000,
+            proximity_coloring: false,
+            proximity_far_color: 0x00FF00,
+            proximity_range: 200,
+            buffer_alpha: 128,
+            overlay_coverage: OverlayCoverage::BufferOnly,
+            overlay_failure: OverlayFailurePolicy::ContinueInvisible,
+            interior_alpha: 64,
+            flash_alpha: 200,
+            overlay_animation: OverlayAnimation::Steady,
+            overlay_tick_ms: 50,
+            on_barrier_hit_sound: None,
+            on_barrier_entry_sound: None,
+            entry_cue: EntryCue::Barrier,
+            interrupt_previous_sound: true,
+            consume_blocked_events: true,
+            resync_after_block: false,
+            enforcement_mode: EnforcementMode::Enforce,
+            bypass_modifier: None,
+            edit_mode: None,
+            edit: EditGesture::default(),
+            high_contrast: HighContrastStyle::default(),
+            engine: BarrierEngine::default(),
+        });
+    }
+
+    fn assert_shutdown_invariants() {
+        assert!(!with_hook_control(|control| control.change_pending()));
+        assert!(!MIDDLE_BUTTON_MONITORING.load(Ordering::Acquire));
+        assert!(BYPASS_MONITOR_THREAD.lock().unwrap().is_none());
+        assert!(!with_hook_control(|control| control.is_bypassed()));
+        assert!(MOUSE_HOOK_HANDLE.load(Ordering::Acquire).is_null());
+        #[cfg(feature = "overlay")]
+        assert!(overlay_windows::OVERLAY_WINDOWS
+            .iter()
+            .all(|hwnd| hwnd.load(Ordering::Acquire).is_null()));
+    }
+
+    #[test]
+    fn test_shutdown_is_deterministic_under_rapid_toggling() {
+        let _guard = GLOBAL_STATE_TEST_LOCK.lock().unwrap();
+        for i in 0..50u32 {
+            // "enable": barrier logically on with the bypass monitor flapping
+            set_test_barrier_enabled(true);
+            start_bypass_monitor(flapping_middle_button);
+            thread::sleep(Duration::from_millis(if i.is_multiple_of(5) {
+                12
+            } else {
+                1
+            }));
+
+            // "disable": must leave nothing behind, no matter where the monitor was
+            set_test_barrier_enabled(false);
+            shutdown_barrier_runtime().unwrap();
+            assert_shutdown_invariants();
+
+            // A late monitor iteration must not be able to raise a request afterwards
+            thread::sleep(Duration::from_millis(6));
+            assert_shutdown_invariants();
+        }
+
+        // Restarting the monitor while one is running must not leak the old thread
+        set_test_barrier_enabled(true);
+        start_bypass_monitor(flapping_middle_button);
+        start_bypass_monitor(flapping_middle_button);
+        set_test_barrier_enabled(false);
+        shutdown_barrier_runtime().unwrap();
+        assert_shutdown_invariants();
+
+        *MOUSE_BARRIER_STATE.get().unwrap().lock().unwrap() = None;
+    }
+
+    #[test]
+    fn test_cursor_speed_keeps_its_last_reading_while_the_state_is_busy() {
+        let _guard = GLOBAL_STATE_TEST_LOCK.lock().unwrap();
+        set_test_barrier_enabled(true);
+        let state_lock = MOUSE_BARRIER_STATE.get().unwrap();
+        {
+            let mut state = state_lock.lock().unwrap();
+            let engine = &mut state.as_mut().unwrap().engine;
+            let now = Instant::now();
+            engine.on_mouse_move(Point { x: 500, y: 500 }, now);
+            engine.on_mouse_move(Point { x: 540, y: 500 }, now);
+        }
+        assert_eq!(cursor_speed().per_event, 40);
+
+        // As if the hook were handling a move while the HUD paints
+        let mut state = state_lock.lock().unwrap();
+        state.as_mut().unwrap().engine.reset();
+        assert_eq!(cursor_speed().per_event, 40);
+        drop(state);
+
+        *state_lock.lock().unwrap() = None;
+        assert_eq!(cursor_speed(), CursorSpeed::default());
+    }
+
+    #[test]
+    fn test_pushes_are_reported_once_off_the_hook() {
+        let _guard = GLOBAL_STATE_TEST_LOCK.lock().unwrap();
+        let reported = Arc::new(AtomicU32::new(0));
+        let counter = reported.clone();
+        set_event_callback(move |event| {
+            if event == BarrierEvent::Pushed {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        let push = LastPush {
+            from: Point { x: 95, y: 50 },
+            to: Point { x: 70, y: 50 },
+            edge: None,
+            speed: 5.0,
+            multiplier: 1.0,
+            timestamp: Instant::now(),
+        };
+
+        // Recording happens in the hook; nothing is reported from there
+        record_push(push);
+        record_push(push);
+        assert_eq!(reported.load(Ordering::Relaxed), 0);
+
+        deliver_push();
+        assert_eq!(reported.load(Ordering::Relaxed), 1);
+        deliver_push();
+        assert_eq!(reported.load(Ordering::Relaxed), 1);
+        set_event_callback(|_| {});
+    }
+
+    #[cfg(feature = "overlay")]
+    #[test]
+    fn test_reload_creates_and_destroys_overlay_windows() {
+        let _guard = GLOBAL_STATE_TEST_LOCK.lock().unwrap();
+        cache_screen_metrics();
+        set_test_barrier_enabled(true);
+        let any_window = || {
+            overlay_windows::OVERLAY_WINDOWS
+                .iter()
+                .any(|hwnd| !hwnd.load(Ordering::Acquire).is_null())
+        };
+
+        // Turning the overlay on lays it out, creating the missing windows
+        update_barrier_state(|state| state.overlay_enabled = true).unwrap();
+        thread::sleep(crate::overlay::OVERLAY_LAYOUT_INTERVAL);
+        overlay_windows::apply_pending_layout();
+        assert!(any_window());
+        assert!(!OVERLAY_UNAVAILABLE.load(Ordering::Acquire));
+
+        // Turning it off destroys them, and nothing is missing any more
+        OVERLAY_UNAVAILABLE.store(true, Ordering::Release);
+        update_barrier_state(|state| state.overlay_enabled = false).unwrap();
+        assert!(!any_window());
+        assert!(!OVERLAY_UNAVAILABLE.load(Ordering::Acquire));
+
+        set_test_barrier_enabled(false);
+        *MOUSE_BARRIER_STATE.get().unwrap().lock().unwrap() = None;
+    }
+
+    #[test]
+    fn test_calls_without_barrier_state_fail_gracefully() {
+        let _guard = GLOBAL_STATE_TEST_LOCK.lock().unwrap();
+        if let Some(state_lock) = MOUSE_BARRIER_STATE.get() {
+            *state_lock.lock().unwrap() = None;
+        }
+
+        // Only `new` sets up the state, but nothing stops skipping it
+        let mut barrier = MouseBarrier;
+        assert_eq!(barrier.enable(), Err(BarrierError::NotInitialized));
+        assert_eq!(barrier.disable(), Err(BarrierError::NotInitialized));
+        assert_eq!(barrier.toggle(), Err(BarrierError::NotInitialized));
+        assert!(!barrier.is_enabled());
+        assert_eq!(
+            barrier.set_buffer_zone(10),
+            Err(BarrierError::NotInitialized)
+        );
+        assert!(MOUSE_HOOK_HANDLE.load(Ordering::Acquire).is_null());
+    }
+
+    #[test]
+    fn test_mouse_barrier_state_creation() {
+        let state = MouseBarrierState {
+            barrier_rect: Rect {
+                left: 0,
+                top: 0,
+                right: 100,
+                bottom: 100,
+            },
+            configured_rect: Rect {
+                left: 0,
+                top: 0,
+                right: 100,
+                bottom: 100,
+            },
+            clamp_to_screen: false,
+            placement: ScreenPlacement::OnScreen,
+            screen: Rect {
+                left: 0,
+                top: 0,
+                right: 1920,
+                bottom: 1080,
+            },
+            buffer_zone: BufferZone::uniform(10),
+            buffer_blocks: true,
+            push_factor: 30,
+            dynamic_push: true,
+            offscreen_push: OffscreenPush::ClampToEdge,
+            screen_margin: 0,
+            approach_zone: None,
+            enabled: false,
+            overlay_enabled: true,
+            overlay_color: 0xFF0000,
+            proximity_coloring: false,
+            proximity_far_color: 0x00FF00,
+            proximity_range: 200,
+            buffer_alpha: 128,
+            overlay_coverage: OverlayCoverage::BufferOnly,
+            overlay_failure: OverlayFailurePolicy::ContinueInvisible,
+            interior_alpha: 64,
+            flash_alpha: 200,
+            overlay_animation: OverlayAnimation::Steady,
+            overlay_tick_ms: 50,
+            on_barrier_hit_sound: Some(Sound::Builtin(BuiltinSound::Click)),
+            on_barrier_entry_sound: None,
+            entry_cue: EntryCue::Barrier,
+            interrupt_previous_sound: true,
+            consume_blocked_events: true,
+            resync_after_block: false,
+            enforcement_mode: EnforcementMode::Enforce,
+            bypass_modifier: None,
+            edit_mode: None,
+            edit: EditGesture::default(),
+            high_contrast: HighContrastStyle::default(),
+            engine: BarrierEngine::default(),
+        };
+
+        assert_eq!(state.buffer_zone, BufferZone::uniform(10));
+        assert_eq!(state.push_factor, 30);
+        assert!(!state.enabled);
+        assert_eq!(state.overlay_color, 0xFF0000);
+        assert_eq!(state.buffer_alpha, 128);
+        assert_eq!(
+            state.on_barrier_hit_sound,
+            Some(Sound::Builtin(BuiltinSound::Click))
+        );
+        assert_eq!(state.on_barrier_entry_sound, None);
+
+        // Already on the 1080p screen, so placing it changes nothing
+        let mut state = state;
+        assert_eq!(state.place(), None);
+        state.screen.right = 50;
+        state.screen.bottom = 50;
+        assert_eq!(state.place(), Some(ScreenPlacement::PartlyOffScreen));
+    }
+}
@@ -0,0 +1,889 @@
+//! The overlay windows drawn over the barrier and its buffer zone: creating,
+//! moving, restyling and painting them. Compiled in with the `overlay`
+//! feature; without it the barrier is enforced with nothing on screen.
+
+use super::{
+    accessibility_settings, cache_screen_metrics, update_barrier_state, with_state, MouseBarrier,
+    MouseBarrierState, Win32, LAST_HIT_MS, MOUSE_BARRIER_STATE, OVERLAY_UNAVAILABLE,
+};
+use crate::overlay::{
+    animated_alpha, compute_overlay_strips, distance_to_rect, flashed_alpha, high_contrast_color,
+    pack_overlay_color, proximity_color, strip_alpha, unpack_overlay_color, OverlayLayout,
+    OverlayLayoutQueue, OVERLAY_STRIP_COUNT,
+};
+use crate::platform::paint::{self, OverlayHandle, PaintContext};
+use crate::platform::watchdog;
+use crate::platform::Platform;
+use crate::{
+    AccessibilitySettings, BarrierError, HatchPattern, HighContrastStyle, OverlayAnimation,
+    OverlayRegion, Point,
+};
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+use winapi::shared::minwindef::{DWORD, LPARAM, LRESULT, TRUE, UINT, WPARAM};
+use winapi::shared::windef::{COLORREF, HDC, HWND, HWND__, RECT};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::wingdi::*;
+use winapi::um::winuser::*;
+
+// Shared start of the overlay animation, so every strip pulses in step
+static OVERLAY_EPOCH: OnceLock<Instant> = OnceLock::new();
+static LAST_PROXIMITY_REPAINT_MS: AtomicU64 = AtomicU64::new(0);
+pub(super) static OVERLAY_WINDOWS: [AtomicPtr<HWND__>; OVERLAY_STRIP_COUNT] = [
+    AtomicPtr::new(std::ptr::null_mut()),
+    AtomicPtr::new(std::ptr::null_mut()),
+    AtomicPtr::new(std::ptr::null_mut()),
+    AtomicPtr::new(std::ptr::null_mut()),
+    AtomicPtr::new(std::ptr::null_mut()),
+];
+// A window of the embedder the overlays are kept directly beneath; null keeps
+// them at the top of the topmost band
+static OVERLAYS_BELOW: AtomicPtr<HWND__> = AtomicPtr::new(std::ptr::null_mut());
+// Barrier changes waiting for the windows to be laid out again, and the
+// layout they were last given
+static LAYOUT_QUEUE: Mutex<OverlayLayoutQueue> = Mutex::new(OverlayLayoutQueue::new());
+// The system settings the overlay style follows, read when the barrier is
+// created and on WM_SETTINGCHANGE rather than by every restyle, which runs
+// under the barrier state lock
+static SYSTEM_STYLE: Mutex<Option<SystemStyle>> = Mutex::new(None);
+
+/// Window class of the overlay strips, for finding ones left behind by a
+/// previous run
+pub const OVERLAY_CLASS_NAME: &str = "MouseBarrierOverlay";
+// Per-window timer driving overlay animation and z-order re-assertion
+const OVERLAY_TIMER_ID: usize = 1;
+
+// Shortest time between overlay repaints for proximity coloring
+const PROXIMITY_REPAINT_INTERVAL: Duration = Duration::from_millis(33); // ~30 fps
+
+// Current overlay color for window painting
+static CURRENT_OVERLAY_COLOR: std::sync::atomic::AtomicU32 =
+    std::sync::atomic::AtomicU32::new(0x00FF0000); // Default red
+
+// Whether the overlay is drawn in its high-contrast style, and that style's
+// color (0x00RRGGBB) and hatch pattern (HS_*)
+static HIGH_CONTRAST_OVERLAY: AtomicBool = AtomicBool::new(false);
+static HIGH_CONTRAST_COLOR: AtomicU32 = AtomicU32::new(0);
+static HIGH_CONTRAST_PATTERN: AtomicU32 = AtomicU32::new(HS_DIAGCROSS);
+
+/// Painted into the gaps of the high-contrast pattern and keyed out, so the
+/// game shows through them; a color no theme or config is likely to pick
+const HIGH_CONTRAST_KEY: COLORREF = 0x00030201;
+/// Width of the high-contrast overlay's border, in pixels
+const HIGH_CONTRAST_BORDER: i32 = 2;
+
+impl MouseBarrier {
+    /// The overlay windows that exist right now, one per region. There are
+    /// none while the barrier is disabled, or when its overlay is turned off
+    /// or couldn't be created; a region `overlay_coverage` leaves out, or one entirely
+    /// off-screen, has a hidden window.
+    pub fn overlay_windows(&self) -> Vec<OverlayHandle> {
+        OVERLAY_WINDOWS
+            .iter()
+            .zip(OverlayRegion::STRIPS)
+            .filter_map(|(slot, region)| {
+                let hwnd = slot.load(Ordering::Acquire);
+                (!hwnd.is_null()).then(|| OverlayHandle::new(hwnd, region))
+            })
+            .collect()
+    }
+
+    /// The raw `HWND`s of the overlay windows that exist right now, as
+    /// integers, for tools that capture or composite them; empty while the
+    /// barrier is disabled.
+    ///
+    /// A handle is only valid while the barrier stays enabled: disabling it
+    /// destroys the windows, and Windows may reuse the values. Prefer
+    /// [`overlay_windows`](Self::overlay_windows) when the region matters or
+    /// the handles are kept around.
+    pub fn overlay_window_handles(&self) -> Vec<isize> {
+        OVERLAY_WINDOWS
+            .iter()
+            .map(|slot| slot.load(Ordering::Acquire))
+            .filter(|hwnd| !hwnd.is_null())
+            .map(|hwnd| hwnd as isize)
+            .collect()
+    }
+
+    /// Keeps the overlay windows directly beneath `window`, a topmost window
+    /// of the caller's such as a HUD, rather than above every other topmost
+    /// window. `None` puts them back on top. Restacks the windows that exist
+    /// now, and holds for ones created later; once `window` is destroyed the
+    /// overlays go back on top.
+    pub fn stack_overlays_below(&self, window: Option<isize>) {
+        let window = window.map_or(ptr::null_mut(), |hwnd| hwnd as HWND);
+        OVERLAYS_BELOW.store(window, Ordering::Release);
+        for slot in OVERLAY_WINDOWS.iter() {
+            let hwnd = slot.load(Ordering::Acquire);
+            if !hwnd.is_null() {
+                unsafe { restack_overlay(hwnd) };
+            }
+        }
+    }
+
+    /// Makes every overlay window repaint, running the overlay painter again.
+    /// Safe to call from any thread; the painting happens on the thread that
+    /// enabled the barrier, the next time it pumps messages.
+    pub fn request_repaint(&self) {
+        invalidate_overlay_windows();
+    }
+
+    /// Sets the painter that draws over each overlay window after its base
+    /// fill, given the region the window draws, and repaints them with it.
+    /// Replaces any painter set before.
+    ///
+    /// The painter runs inside `WM_PAINT` on the thread that enabled the
+    /// barrier, so it holds up that thread's message loop and should be quick.
+    /// Disabling the barrier unregisters it.
+    pub fn set_overlay_painter<F>(&self, painter: F)
+    where
+        F: Fn(&mut PaintContext, OverlayRegion) + Send + Sync + 'static,
+    {
+        paint::set_painter(Arc::new(painter));
+        invalidate_overlay_windows();
+    }
+}
+
+/// Creates the overlay windows for the current barrier
+pub(super) fn create_overlays() -> Result<(), String> {
+    let windows = create_overlay_windows()?;
+    for (slot, hwnd) in OVERLAY_WINDOWS.iter().zip(windows) {
+        slot.store(hwnd, Ordering::Release);
+    }
+    forget_layout();
+    Ok(())
+}
+
+/// Takes up the color and high-contrast style of `state` for the next paint
+pub(super) fn restyle(state: &MouseBarrierState) {
+    CURRENT_OVERLAY_COLOR.store(state.overlay_color, Ordering::Relaxed);
+    apply_overlay_style(&state.high_contrast, system_style());
+}
+
+/// What the overlay style reads from the system
+#[derive(Debug, Clone, Copy)]
+pub(super) struct SystemStyle {
+    accessibility: AccessibilitySettings,
+    /// The theme's highlight color
+    highlight: (u8, u8, u8),
+}
+
+/// Reads the system settings the overlay style follows again, including the
+/// registry, and caches them for [`restyle`]. Call it without the barrier
+/// state locked.
+pub(super) fn refresh_system_style() -> SystemStyle {
+    let highlight = unsafe { GetSysColor(COLOR_HIGHLIGHT) };
+    let style = SystemStyle {
+        accessibility: accessibility_settings(),
+        // COLORREF is 0x00BBGGRR
+        highlight: (
+            highlight as u8,
+            (highlight >> 8) as u8,
+            (highlight >> 16) as u8,
+        ),
+    };
+    *SYSTEM_STYLE.lock().unwrap_or_else(PoisonError::into_inner) = Some(style);
+    style
+}
+
+/// The cached system settings, read now only if they never were
+fn system_style() -> SystemStyle {
+    let cached = *SYSTEM_STYLE.lock().unwrap_or_else(PoisonError::into_inner);
+    cached.unwrap_or_else(refresh_system_style)
+}
+
+/// Asks for the overlay windows to be moved, resized and recolored to match
+/// the barrier state. Only marks the layout out of date, so a burst of
+/// changes costs nothing until [`apply_pending_layout`] runs.
+pub(super) fn request_layout() {
+    if let Ok(mut queue) = LAYOUT_QUEUE.lock() {
+        queue.request(Instant::now());
+    }
+}
+
+/// Lays the overlay windows out for the current barrier state if a change is
+/// waiting and the last layout round was long enough ago, moving and
+/// repainting only the strips that changed. Runs from `process_hook_requests`,
+/// on the thread that owns the windows.
+pub(super) fn apply_pending_layout() {
+    let now = Instant::now();
+    {
+        let Ok(mut queue) = LAYOUT_QUEUE.lock() else {
+            return;
+        };
+        if let Some(report) = queue.report(now) {
+            debug!(
+                requests = report.requests,
+                rounds = report.rounds,
+                "Coalesced overlay layout requests"
+            );
+        }
+        if !queue.due(now) {
+            return;
+        }
+    }
+
+    // Read without the queue locked; a barrier disabled meanwhile has no
+    // windows to lay out
+    let layout =
+        with_state(|state| (state.enabled && state.overlay_enabled).then(|| current_layout(state)));
+    let Ok(mut queue) = LAYOUT_QUEUE.lock() else {
+        return;
+    };
+    let Ok(Some(layout)) = layout else {
+        queue.forget();
+        return;
+    };
+    let changed = queue.begin_round(now, layout);
+    if !changed.contains(&true) {
+        return;
+    }
+    drop(queue);
+
+    let complete = apply_overlay_strips(&layout, &changed);
+    OVERLAY_UNAVAILABLE.store(!complete, Ordering::Release);
+    if !complete {
+        // Try the missing windows again on the next change
+        forget_layout();
+    }
+}
+
+/// Where the overlay windows of `state` go and how they're drawn
+fn current_layout(state: &MouseBarrierState) -> OverlayLayout {
+    OverlayLayout {
+        strips: compute_overlay_strips(
+            &state.barrier_rect,
+            state.buffer_zone,
+            &Win32.screen_metrics(),
+            state.overlay_coverage,
+        ),
+        color: state.overlay_color,
+        buffer_alpha: state.buffer_alpha,
+        interior_alpha: state.interior_alpha,
+        tick_ms: state.overlay_tick_ms,
+        high_contrast: HIGH_CONTRAST_OVERLAY.load(Ordering::Relaxed).then(|| {
+            (
+                HIGH_CONTRAST_COLOR.load(Ordering::Relaxed),
+                HIGH_CONTRAST_PATTERN.load(Ordering::Relaxed),
+            )
+        }),
+    }
+}
+
+/// Drops any waiting layout request and the layout applied last, once the
+/// windows were created or destroyed outside a layout round
+fn forget_layout() {
+    if let Ok(mut queue) = LAYOUT_QUEUE.lock() {
+        queue.forget();
+    }
+}
+
+/// Forgets the painter set with `MouseBarrier::set_overlay_painter`
+pub(super) fn clear_painter() {
+    paint::clear_painter();
+}
+
+/// Recolors the overlay for how close `pos` is to the barrier, at most every
+/// `PROXIMITY_REPAINT_INTERVAL`.
+///
+/// Goes through the same color store and repaint as a configured color
+/// change; the windows only repaint when the color actually moved.
+pub(super) fn update_proximity_color(state: &MouseBarrierState, pos: Point) {
+    let now = watchdog::now_ms();
+    let last = LAST_PROXIMITY_REPAINT_MS.load(Ordering::Relaxed);
+    if now.saturating_sub(last) < PROXIMITY_REPAINT_INTERVAL.as_millis() as u64 {
+        return;
+    }
+    LAST_PROXIMITY_REPAINT_MS.store(now, Ordering::Relaxed);
+
+    let color = pack_overlay_color(proximity_color(
+        unpack_overlay_color(state.overlay_color),
+        unpack_overlay_color(state.proximity_far_color),
+        distance_to_rect(pos, &state.barrier_rect),
+        state.proximity_range,
+    ));
+    if CURRENT_OVERLAY_COLOR.swap(color, Ordering::Relaxed) != color {
+        invalidate_overlay_windows();
+    }
+}
+
+fn destroy_windows(windows: &[HWND]) {
+    for &hwnd in windows.iter().filter(|hwnd| !hwnd.is_null()) {
+        unsafe {
+            DestroyWindow(hwnd);
+        }
+    }
+}
+
+/// Makes every live overlay window repaint with the current color
+fn invalidate_overlay_windows() {
+    for slot in OVERLAY_WINDOWS.iter() {
+        let hwnd = slot.load(Ordering::Acquire);
+        if !hwnd.is_null() {
+            unsafe {
+                InvalidateRect(hwnd, ptr::null(), TRUE);
+            }
+        }
+    }
+}
+
+pub(super) fn destroy_overlay_windows() {
+    paint::invalidate_handles();
+    forget_layout();
+    let mut destroyed = 0;
+    for atomic_ptr in &OVERLAY_WINDOWS {
+        let hwnd = atomic_ptr.swap(ptr::null_mut(), Ordering::AcqRel);
+        if !hwnd.is_null() {
+            unsafe {
+                DestroyWindow(hwnd);
+            }
+            destroyed += 1;
+        }
+    }
+
+    if destroyed > 0 {
+        info!("Destroyed overlay windows");
+    }
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            let mut ps: PAINTSTRUCT = mem::zeroed();
+            let hdc = BeginPaint(hwnd, &mut ps);
+
+            let mut client_rect = RECT {
+                left: 0,
+                top: 0,
+                right: 0,
+                bottom: 0,
+            };
+            GetClientRect(hwnd, &mut client_rect);
+
+            if HIGH_CONTRAST_OVERLAY.load(Ordering::Relaxed) {
+                paint_high_contrast(hdc, &client_rect);
+            } else {
+                // Draw overlay rectangle with configured color
+                let color = CURRENT_OVERLAY_COLOR.load(Ordering::Relaxed);
+                let r = ((color >> 16) & 0xFF) as u8;
+                let g = ((color >> 8) & 0xFF) as u8;
+                let b = (color & 0xFF) as u8;
+
+                let brush = CreateSolidBrush(RGB(r, g, b));
+                FillRect(hdc, &client_rect, brush);
+                DeleteObject(brush as *mut _);
+            }
+
+            if let Some(region) = overlay_region(hwnd) {
+                paint::run_painter(hdc, &client_rect, region);
+            }
+
+            EndPaint(hwnd, &ps);
+            0
+        }
+        WM_ERASEBKGND => {
+            1 // Return non-zero to indicate we handled it
+        }
+        WM_TIMER if wparam == OVERLAY_TIMER_ID => {
+            overlay_tick(hwnd);
+            0
+        }
+        WM_DPICHANGED => {
+            // The strips have to line up with the barrier rather than take the
+            // rect Windows suggests, so lay them all out again at the new scale
+            cache_screen_metrics();
+            if let Err(e) = update_barrier_state(|_| {}) {
+                warn!("Failed to lay out the overlay after a DPI change: {}", e);
+            }
+            0
+        }
+        WM_SETTINGCHANGE => {
+            // Every strip gets this for every setting; only the first to see
+            // the overlay style change applies it
+            let system = refresh_system_style();
+            let changed = with_state(|state| apply_overlay_style(&state.high_contrast, system));
+            if changed.unwrap_or(false) {
+                if let Err(e) = update_barrier_state(|_| {}) {
+                    warn!(
+                        "Failed to restyle the overlay after a settings change: {}",
+                        e
+                    );
+                }
+            }
+            0
+        }
+        WM_DESTROY => {
+            KillTimer(hwnd, OVERLAY_TIMER_ID);
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+/// The region the overlay window `hwnd` draws, if it is one of ours
+fn overlay_region(hwnd: HWND) -> Option<OverlayRegion> {
+    OVERLAY_WINDOWS
+        .iter()
+        .position(|slot| slot.load(Ordering::Acquire) == hwnd)
+        .map(|index| OverlayRegion::STRIPS[index])
+}
+
+/// One overlay timer tick: keeps the strip above other topmost windows,
+/// advances its animation and flashes the buffer frame after a barrier hit.
+unsafe fn overlay_tick(hwnd: HWND) {
+    let Some(index) = OVERLAY_WINDOWS
+        .iter()
+        .position(|slot| slot.load(Ordering::Acquire) == hwnd)
+    else {
+        return;
+    };
+    let Some((alpha, flash_alpha, animation)) = overlay_tick_params(index) else {
+        return;
+    };
+
+    let since_hit = match LAST_HIT_MS.load(Ordering::Relaxed) {
+        0 => None,
+        at_ms => Some(Duration::from_millis(
+            watchdog::now_ms().saturating_sub(at_ms),
+        )),
+    };
+    // Set even for steady strips, which have to come back from a flash
+    let elapsed = OVERLAY_EPOCH.get_or_init(Instant::now).elapsed();
+    set_overlay_alpha(
+        hwnd,
+        flashed_alpha(
+            index,
+            animated_alpha(alpha, animation, elapsed),
+            flash_alpha,
+            since_hit,
+        ),
+    );
+
+    // Games that go fullscreen or other topmost windows can end up above us,
+    // and above the window we stack beneath, so bring that back up first
+    raise_overlays_below();
+    restack_overlay(hwnd);
+}
+
+/// Puts the window set with `stack_overlays_below` back at the very top.
+///
+/// Its owner only makes it topmost when it moves, so without this the
+/// overlays would follow it down beneath whatever went above it.
+/// `SWP_ASYNCWINDOWPOS` keeps the overlay thread from waiting on the thread
+/// that owns it.
+unsafe fn raise_overlays_below() {
+    let below = OVERLAYS_BELOW.load(Ordering::Acquire);
+    if below.is_null() || IsWindow(below) == 0 {
+        return;
+    }
+    SetWindowPos(
+        below,
+        HWND_TOPMOST,
+        0,
+        0,
+        0,
+        0,
+        SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE | SWP_NOOWNERZORDER | SWP_ASYNCWINDOWPOS,
+    );
+}
+
+/// Where overlay windows go in the z-order: beneath the window set with
+/// `stack_overlays_below` while it exists, otherwise at the very top
+unsafe fn overlay_insert_after() -> HWND {
+    let below = OVERLAYS_BELOW.load(Ordering::Acquire);
+    if !below.is_null() && IsWindow(below) != 0 {
+        below
+    } else {
+        HWND_TOPMOST
+    }
+}
+
+/// Puts `hwnd` back where overlays belong in the z-order
+unsafe fn restack_overlay(hwnd: HWND) {
+    SetWindowPos(
+        hwnd,
+        overlay_insert_after(),
+        0,
+        0,
+        0,
+        0,
+        SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+    );
+}
+
+/// The configured alpha, flash alpha and animation for the strip at `index`.
+///
+/// Uses `try_lock` so the message loop never waits on the barrier state;
+/// skipping a tick is harmless.
+fn overlay_tick_params(index: usize) -> Option<(u8, u8, OverlayAnimation)> {
+    let state_guard = MOUSE_BARRIER_STATE.get()?.try_lock().ok()?;
+    let state = state_guard.as_ref()?;
+    Some((
+        strip_alpha(index, state.buffer_alpha, state.interior_alpha),
+        state.flash_alpha,
+        state.overlay_animation,
+    ))
+}
+
+/// Sets a strip's opacity. The high-contrast style ignores `alpha` and is
+/// opaque, with the gaps in its pattern keyed out instead.
+unsafe fn set_overlay_alpha(hwnd: HWND, alpha: u8) {
+    if HIGH_CONTRAST_OVERLAY.load(Ordering::Relaxed) {
+        SetLayeredWindowAttributes(hwnd, HIGH_CONTRAST_KEY, 255, LWA_COLORKEY | LWA_ALPHA);
+    } else {
+        SetLayeredWindowAttributes(hwnd, 0, alpha, LWA_ALPHA);
+    }
+}
+
+/// Paints a strip in the high-contrast style: a hatch pattern over the color
+/// key, framed by a solid border
+unsafe fn paint_high_contrast(hdc: HDC, rect: &RECT) {
+    let (r, g, b) = unpack_overlay_color(HIGH_CONTRAST_COLOR.load(Ordering::Relaxed));
+    let color = RGB(r, g, b);
+
+    let key = CreateSolidBrush(HIGH_CONTRAST_KEY);
+    FillRect(hdc, rect, key);
+    DeleteObject(key as *mut _);
+
+    let hatch = CreateHatchBrush(HIGH_CONTRAST_PATTERN.load(Ordering::Relaxed) as i32, color);
+    SetBkMode(hdc, TRANSPARENT as i32);
+    FillRect(hdc, rect, hatch);
+    DeleteObject(hatch as *mut _);
+
+    let border = CreateSolidBrush(color);
+    for inset in 0..HIGH_CONTRAST_BORDER {
+        let frame = RECT {
+            left: rect.left + inset,
+            top: rect.top + inset,
+            right: rect.right - inset,
+            bottom: rect.bottom - inset,
+        };
+        FrameRect(hdc, &frame, border);
+    }
+    DeleteObject(border as *mut _);
+}
+
+/// Picks the overlay's drawing style from `style` and the `system` settings;
+/// returns whether that changed how the overlay is drawn
+fn apply_overlay_style(style: &HighContrastStyle, system: SystemStyle) -> bool {
+    let color = high_contrast_color(style, system.accessibility, system.highlight);
+
+    let enabled = color.is_some();
+    let color = color.map(pack_overlay_color).unwrap_or(0);
+    let pattern = hatch_style(style.pattern);
+    let was_enabled = HIGH_CONTRAST_OVERLAY.swap(enabled, Ordering::Relaxed);
+    let old_color = HIGH_CONTRAST_COLOR.swap(color, Ordering::Relaxed);
+    let old_pattern = HIGH_CONTRAST_PATTERN.swap(pattern, Ordering::Relaxed);
+
+    if was_enabled != enabled {
+        info!(high_contrast = enabled, "Overlay style changed");
+    }
+    was_enabled != enabled || old_color != color || old_pattern != pattern
+}
+
+fn hatch_style(pattern: HatchPattern) -> u32 {
+    match pattern {
+        HatchPattern::Horizontal => HS_HORIZONTAL,
+        HatchPattern::Vertical => HS_VERTICAL,
+        HatchPattern::Diagonal => HS_BDIAGONAL,
+        HatchPattern::Cross => HS_CROSS,
+        HatchPattern::DiagonalCross => HS_DIAGCROSS,
+    }
+}
+
+/// Starts, retimes or (for a zero interval) stops an overlay window's timer.
+unsafe fn set_overlay_timer(hwnd: HWND, tick_ms: u32) {
+    if tick_ms == 0 {
+        KillTimer(hwnd, OVERLAY_TIMER_ID);
+    } else {
+        SetTimer(hwnd, OVERLAY_TIMER_ID, tick_ms, None);
+    }
+}
+
+fn create_overlay_windows() -> Result<[HWND; OVERLAY_STRIP_COUNT], String> {
+    let state_lock = MOUSE_BARRIER_STATE
+        .get()
+        .ok_or_else(|| BarrierError::NotInitialized.to_string())?;
+    let mut windows = [ptr::null_mut(); OVERLAY_STRIP_COUNT];
+
+    if let Ok(state_guard) = state_lock.lock() {
+        if let Some(ref state) = *state_guard {
+            let strips = compute_overlay_strips(
+                &state.barrier_rect,
+                state.buffer_zone,
+                &Win32.screen_metrics(),
+                state.overlay_coverage,
+            );
+
+            for (index, (slot, strip)) in windows.iter_mut().zip(strips.iter()).enumerate() {
+                if strip.is_visible() {
+                    match create_single_overlay_window(
+                        strip.x,
+                        strip.y,
+                        strip.width,
+                        strip.height,
+                        state.overlay_color,
+                        strip_alpha(index, state.buffer_alpha, state.interior_alpha),
+                        state.overlay_tick_ms,
+                    ) {
+                        Ok(hwnd) => *slot = hwnd,
+                        Err(e) => {
+                            // Don't leave the strips created so far on screen
+                            destroy_windows(&windows);
+                            return Err(format!("Failed to create {} window: {}", strip.name, e));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(windows)
+}
+
+/// Moves and resizes the live overlay windows of the `changed` strips to
+/// match `layout` without recreating them. Strips that collapse to nothing
+/// are hidden; strips that become visible and have no window yet get one
+/// created.
+///
+/// Returns false if a visible strip is left without a window.
+fn apply_overlay_strips(layout: &OverlayLayout, changed: &[bool; OVERLAY_STRIP_COUNT]) -> bool {
+    let mut complete = true;
+
+    for (index, (slot, strip)) in OVERLAY_WINDOWS.iter().zip(layout.strips.iter()).enumerate() {
+        if !changed[index] {
+            continue;
+        }
+        let hwnd = slot.load(Ordering::Acquire);
+        let alpha = strip_alpha(index, layout.buffer_alpha, layout.interior_alpha);
+
+        if hwnd.is_null() {
+            if strip.is_visible() {
+                match create_single_overlay_window(
+                    strip.x,
+                    strip.y,
+                    strip.width,
+                    strip.height,
+                    layout.color,
+                    alpha,
+                    layout.tick_ms,
+                ) {
+                    Ok(hwnd) => slot.store(hwnd, Ordering::Release),
+                    Err(e) => {
+                        warn!("Failed to create {} overlay window: {}", strip.name, e);
+                        complete = false;
+                    }
+                }
+            }
+            continue;
+        }
+
+        unsafe {
+            if strip.is_visible() {
+                SetWindowPos(
+                    hwnd,
+                    overlay_insert_after(),
+                    strip.x,
+                    strip.y,
+                    strip.width,
+                    strip.height,
+                    SWP_NOACTIVATE | SWP_SHOWWINDOW,
+                );
+                set_overlay_alpha(hwnd, alpha);
+                set_overlay_timer(hwnd, layout.tick_ms);
+                InvalidateRect(hwnd, ptr::null(), TRUE);
+            } else {
+                ShowWindow(hwnd, SW_HIDE);
+            }
+        }
+    }
+
+    complete
+}
+
+/// Extended styles for the overlay strips.
+///
+/// They're click-through (`WS_EX_TRANSPARENT`) and never activate
+/// (`WS_EX_NOACTIVATE`), so creating, showing or clicking near one leaves the
+/// game as the foreground window, as the HUD already does.
+const OVERLAY_EX_STYLE: DWORD =
+    WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE;
+
+/// The extended styles that make an overlay click-through; without them the
+/// barrier region would swallow clicks meant for the game
+const CLICK_THROUGH_EX_STYLES: [(DWORD, &str); 2] = [
+    (WS_EX_TRANSPARENT, "WS_EX_TRANSPARENT"),
+    (WS_EX_LAYERED, "WS_EX_LAYERED"),
+];
+
+/// Names of the click-through styles `ex_style` lacks
+fn missing_click_through_styles(ex_style: DWORD) -> Vec<&'static str> {
+    CLICK_THROUGH_EX_STYLES
+        .iter()
+        .filter(|&&(style, _)| ex_style & style != style)
+        .map(|&(_, name)| name)
+        .collect()
+}
+
+/// Reads back the extended styles of the live overlay windows and logs an
+/// error for any that ended up without a click-through style
+pub(super) fn check_overlay_click_through() {
+    for slot in OVERLAY_WINDOWS.iter() {
+        let hwnd = slot.load(Ordering::Acquire);
+        if hwnd.is_null() {
+            continue;
+        }
+
+        let ex_style = unsafe { GetWindowLongPtrW(hwnd, GWL_EXSTYLE) } as DWORD;
+        let missing = missing_click_through_styles(ex_style);
+        if !missing.is_empty() {
+            error!(
+                hwnd = ?hwnd,
+                ex_style = format_args!("{:#x}", ex_style),
+                "Overlay window is missing {}; it will intercept clicks",
+                missing.join(", ")
+            );
+        }
+    }
+}
+
+fn create_single_overlay_window(
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    _color: u32,
+    alpha: u8,
+    tick_ms: u32,
+) -> Result<HWND, String> {
+    unsafe {
+        let instance = GetModuleHandleW(ptr::null());
+        let class_name: Vec<u16> = OVERLAY_CLASS_NAME
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+
+        // Check if class is already registered
+        let mut wc_existing: WNDCLASSEXW = mem::zeroed();
+        wc_existing.cbSize = mem::size_of::<WNDCLASSEXW>() as u32;
+
+        if GetClassInfoExW(instance, class_name.as_ptr(), &mut wc_existing) == 0 {
+            // Class not registered, so register it
+            let wc = WNDCLASSEXW {
+                cbSize: mem::size_of::<WNDCLASSEXW>() as u32,
+                style: CS_HREDRAW | CS_VREDRAW,
+                lpfnWndProc: Some(window_proc),
+                cbClsExtra: 0,
+                cbWndExtra: 0,
+                hInstance: instance,
+                hIcon: ptr::null_mut(),
+                hCursor: ptr::null_mut(),
+                hbrBackground: ptr::null_mut(), // No background brush
+                lpszMenuName: ptr::null(),
+                lpszClassName: class_name.as_ptr(),
+                hIconSm: ptr::null_mut(),
+            };
+
+            if RegisterClassExW(&wc) == 0 {
+                return Err(format!(
+                    "Failed to register window class: {}",
+                    GetLastError()
+                ));
+            }
+        }
+
+        // Use the provided window dimensions
+
+        let hwnd = CreateWindowExW(
+            OVERLAY_EX_STYLE,
+            class_name.as_ptr(),
+            class_name.as_ptr(),
+            WS_POPUP,
+            x,
+            y,
+            width,
+            height,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            instance,
+            ptr::null_mut(),
+        );
+
+        if hwnd.is_null() {
+            return Err(format!("Failed to create window: {}", GetLastError()));
+        }
+
+        // Use configurable alpha transparency
+        set_overlay_alpha(hwnd, alpha);
+
+        // SW_SHOW would activate the overlay and take focus from the game
+        ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+        restack_overlay(hwnd);
+        UpdateWindow(hwnd);
+        set_overlay_timer(hwnd, tick_ms);
+
+        Ok(hwnd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_windows_never_take_focus() {
+        // Enabling the barrier while the game is focused must leave it in the
+        // foreground, and the overlays must never intercept clicks
+        assert_eq!(OVERLAY_EX_STYLE & WS_EX_NOACTIVATE, WS_EX_NOACTIVATE);
+        assert_eq!(OVERLAY_EX_STYLE & WS_EX_TRANSPARENT, WS_EX_TRANSPARENT);
+        assert_eq!(OVERLAY_EX_STYLE & WS_EX_TOPMOST, WS_EX_TOPMOST);
+        // Keeps the overlays out of the taskbar and Alt+Tab
+        assert_eq!(OVERLAY_EX_STYLE & WS_EX_TOOLWINDOW, WS_EX_TOOLWINDOW);
+    }
+
+    #[test]
+    fn test_restyle_uses_the_cached_system_settings() {
+        // As if WM_SETTINGCHANGE had read a high contrast theme
+        let system = SystemStyle {
+            accessibility: AccessibilitySettings {
+                high_contrast: true,
+                transparency_effects: true,
+            },
+            highlight: (1, 2, 3),
+        };
+        *SYSTEM_STYLE.lock().unwrap() = Some(system);
+
+        apply_overlay_style(&HighContrastStyle::default(), system_style());
+        assert!(HIGH_CONTRAST_OVERLAY.load(Ordering::Relaxed));
+        assert_eq!(
+            HIGH_CONTRAST_COLOR.load(Ordering::Relaxed),
+            pack_overlay_color((1, 2, 3))
+        );
+
+        let system = refresh_system_style();
+        apply_overlay_style(&HighContrastStyle::default(), system);
+    }
+
+    #[test]
+    fn test_missing_click_through_styles() {
+        assert!(missing_click_through_styles(OVERLAY_EX_STYLE).is_empty());
+        assert_eq!(
+            missing_click_through_styles(OVERLAY_EX_STYLE & !WS_EX_TRANSPARENT),
+            ["WS_EX_TRANSPARENT"]
+        );
+        assert_eq!(
+            missing_click_through_styles(WS_EX_TOPMOST),
+            ["WS_EX_TRANSPARENT", "WS_EX_LAYERED"]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use crate::engine::Point;
+use crate::PositionSource;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+
+/// Rate the mouse position callback is limited to until configured otherwise
+pub const DEFAULT_POSITION_CALLBACK_HZ: u32 = 60;
+
+/// Latest cursor position, written by the hook and read by whoever delivers it
+/// to the position callback.
+///
+/// Writers never block and only the newest position survives; positions
+/// published between two reads are dropped. The hook and the bypass poller
+/// never publish at the same time, so the position and its source can't be
+/// mixed up between writers.
+pub(crate) struct PositionSlot {
+    packed: AtomicU64,
+    source: AtomicU8,
+    fresh: AtomicBool,
+}
+
+impl PositionSlot {
+    pub(crate) const fn new() -> Self {
+        Self {
+            packed: AtomicU64::new(0),
+            source: AtomicU8::new(0),
+            fresh: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn publish(&self, pos: Point, source: PositionSource) {
+        let packed = (u64::from(pos.x as u32) << 32) | u64::from(pos.y as u32);
+        self.packed.store(packed, Ordering::Relaxed);
+        self.source.store(source as u8, Ordering::Relaxed);
+        self.fresh.store(true, Ordering::Release);
+    }
+
+    /// Takes the position published since the last call, if any
+    pub(crate) fn take(&self) -> Option<(Point, PositionSource)> {
+        if !self.fresh.swap(false, Ordering::Acquire) {
+            return None;
+        }
+
+        let packed = self.packed.load(Ordering::Relaxed);
+        let pos = Point {
+            x: (packed >> 32) as u32 as i32,
+            y: packed as u32 as i32,
+        };
+        let source = if self.source.load(Ordering::Relaxed) == PositionSource::Polled as u8 {
+            PositionSource::Polled
+        } else {
+            PositionSource::Hook
+        };
+        Some((pos, source))
+    }
+}
+
+/// Spaces out callback deliveries to at most `max_hz` per second
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DeliveryThrottle {
+    min_interval: Duration,
+    last: Option<Instant>,
+}
+
+impl DeliveryThrottle {
+    /// `max_hz` of 0 removes the limit
+    pub(crate) const fn new(max_hz: u32) -> Self {
+        Self {
+            min_interval: interval_for(max_hz),
+            last: None,
+        }
+    }
+
+    pub(crate) fn set_max_hz(&mut self, max_hz: u32) {
+        self.min_interval = interval_for(max_hz);
+    }
+
+    pub(crate) fn ready(&self, now: Instant) -> bool {
+        self.last
+            .is_none_or(|last| now.saturating_duration_since(last) >= self.min_interval)
+    }
+
+    pub(crate) fn record(&mut self, now: Instant) {
+        self.last = Some(now);
+    }
+}
+
+const fn interval_for(max_hz: u32) -> Duration {
+    if max_hz == 0 {
+        Duration::ZERO
+    } else {
+        Duration::from_nanos(1_000_000_000 / max_hz as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_hands_out_only_the_latest_position_once() {
+        let slot = PositionSlot::new();
+        assert_eq!(slot.take(), None);
+
+        slot.publish(Point { x: 10, y: 20 }, PositionSource::Hook);
+        slot.publish(Point { x: -1920, y: -5 }, PositionSource::Polled);
+        assert_eq!(
+            slot.take(),
+            Some((Point { x: -1920, y: -5 }, PositionSource::Polled))
+        );
+        assert_eq!(slot.take(), None);
+
+        slot.publish(
+            Point {
+                x: i32::MAX,
+                y: i32::MIN,
+            },
+            PositionSource::Hook,
+        );
+        assert_eq!(
+            slot.take(),
+            Some((
+                Point {
+                    x: i32::MAX,
+                    y: i32::MIN
+                },
+                PositionSource::Hook
+            ))
+        );
+    }
+
+    #[test]
+    fn test_throttle_spaces_deliveries() {
+        let start = Instant::now();
+        let mut throttle = DeliveryThrottle::new(50); // 20 ms apart
+        assert!(throttle.ready(start));
+
+        throttle.record(start);
+        assert!(!throttle.ready(start + Duration::from_millis(19)));
+        assert!(throttle.ready(start + Duration::from_millis(20)));
+
+        throttle.set_max_hz(10);
+        assert!(!throttle.ready(start + Duration::from_millis(99)));
+        assert!(throttle.ready(start + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_throttle_without_limit_is_always_ready() {
+        let start = Instant::now();
+        let mut throttle = DeliveryThrottle::new(0);
+        throttle.record(start);
+        assert!(throttle.ready(start));
+    }
+}
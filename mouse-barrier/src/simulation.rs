@@ -0,0 +1,472 @@
+use crate::engine::{carry_out, ActionSink, BarrierEngine, ScreenMetrics, SoundKind};
+use crate::subscription::{Subscribers, SubscriptionId};
+use crate::{place_on_screen, BarrierDecision, LastPush, MouseBarrierConfig, Point, Rect, Zone};
+use std::sync::Arc;
+use std::time::Instant;
+
+type KeyCallback = dyn Fn(u32, bool) + Send + Sync;
+
+/// One thing the barrier did in response to a simulated cursor move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimulatedAction {
+    /// The hook moved the cursor to `push.to`
+    CursorMoved(LastPush),
+    /// The approach zone slowed the move down, leaving the cursor here
+    CursorSlowed(Point),
+    ZoneChanged {
+        from: Zone,
+        to: Zone,
+    },
+    /// A sound cue fired; the hook only plays it if a sound is configured for it
+    SoundRequested(SoundKind),
+    /// Warn-only mode held back this push; the cursor stayed put
+    WouldHavePushed(LastPush),
+}
+
+/// What the barrier did with one simulated cursor move
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedMove {
+    /// What the engine decided; `None` while the barrier is disabled
+    pub decision: Option<BarrierDecision>,
+    /// The actions the hook carried out, in order
+    pub actions: Vec<SimulatedAction>,
+    /// Whether the hook would have swallowed the original event
+    pub consumed: bool,
+    /// Where the cursor ended up
+    pub cursor: Point,
+}
+
+/// Collects the actions the real hook would have carried out
+#[derive(Default)]
+struct RecordingSink {
+    actions: Vec<SimulatedAction>,
+}
+
+impl ActionSink for RecordingSink {
+    fn move_cursor(&mut self, push: &LastPush) -> bool {
+        self.actions.push(SimulatedAction::CursorMoved(*push));
+        true
+    }
+
+    fn slow_cursor(&mut self, to: Point) -> bool {
+        self.actions.push(SimulatedAction::CursorSlowed(to));
+        true
+    }
+
+    fn zone_changed(&mut self, from: Zone, to: Zone) {
+        self.actions.push(SimulatedAction::ZoneChanged { from, to });
+    }
+
+    fn play_sound(&mut self, kind: SoundKind) {
+        self.actions.push(SimulatedAction::SoundRequested(kind));
+    }
+
+    fn would_push(&mut self, push: &LastPush) {
+        self.actions.push(SimulatedAction::WouldHavePushed(*push));
+    }
+}
+
+/// The barrier without hooks, windows or a desktop.
+///
+/// Cursor moves and key events are fed in by hand and run through the same
+/// engine and action handling as the mouse hook, so recorded movement traces
+/// can be replayed on any platform.
+pub struct Simulation {
+    engine: BarrierEngine,
+    screen: ScreenMetrics,
+    enabled: bool,
+    consume_blocked_events: bool,
+    cursor: Point,
+    key_subscribers: Subscribers<KeyCallback>,
+}
+
+impl Simulation {
+    /// A disabled barrier on a `screen_width` x `screen_height` screen, with
+    /// the cursor at the origin
+    pub fn new(config: &MouseBarrierConfig, screen_width: i32, screen_height: i32) -> Self {
+        let mut simulation = Self {
+            engine: BarrierEngine::default(),
+            screen: ScreenMetrics {
+                width: screen_width,
+                height: screen_height,
+                physical_width: screen_width,
+                physical_height: screen_height,
+                margin: 0,
+            },
+            enabled: false,
+            consume_blocked_events: config.consume_blocked_events,
+            cursor: Point::default(),
+            key_subscribers: Subscribers::new(),
+        };
+        simulation.update_barrier(config);
+        simulation
+    }
+
+    /// Applies a new barrier configuration without forgetting the cursor history.
+    ///
+    /// An off-screen barrier is moved on-screen like the real one when
+    /// `clamp_to_screen` is set.
+    pub fn update_barrier(&mut self, config: &MouseBarrierConfig) {
+        let screen = Rect {
+            left: 0,
+            top: 0,
+            right: self.screen.width,
+            bottom: self.screen.height,
+        };
+        let (rect, _) = place_on_screen(config.rect(&screen), &screen, config.clamp_to_screen);
+        self.engine.configure(
+            rect,
+            config.buffer_zone,
+            config.push_factor,
+            config.offscreen_push,
+            ScreenMetrics {
+                margin: config.screen_margin,
+                ..self.screen
+            },
+        );
+        self.engine.set_entry_cue(config.entry_cue);
+        self.engine.set_buffer_blocks(config.buffer_blocks);
+        self.engine.set_dynamic_push(config.dynamic_push);
+        self.engine.set_approach_zone(config.approach_zone);
+        self.engine.set_enforcement_mode(config.enforcement_mode);
+        self.consume_blocked_events = config.consume_blocked_events;
+    }
+
+    pub fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    pub fn disable(&mut self) {
+        self.enabled = false;
+        // Like the hook, a re-enabled barrier starts without cursor history
+        self.engine.reset();
+    }
+
+    /// Flips the barrier on or off; returns whether it is now enabled
+    pub fn toggle(&mut self) -> bool {
+        if self.enabled {
+            self.disable();
+        } else {
+            self.enable();
+        }
+        self.enabled
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Where the cursor is after the last simulated move
+    pub fn cursor(&self) -> Point {
+        self.cursor
+    }
+
+    /// Moves the cursor to (`x`, `y`) as if the mouse hook had seen it
+    pub fn simulate_mouse_move(&mut self, x: i32, y: i32) -> SimulatedMove {
+        let pos = Point { x, y };
+        self.cursor = pos;
+        if !self.enabled {
+            return SimulatedMove {
+                decision: None,
+                actions: Vec::new(),
+                consumed: false,
+                cursor: pos,
+            };
+        }
+
+        let actions = self.engine.on_mouse_move(pos, Instant::now());
+        let mut sink = RecordingSink::default();
+        let consumed = carry_out(
+            &mut self.engine,
+            actions,
+            self.consume_blocked_events,
+            &mut sink,
+        );
+
+        match sink.actions.last() {
+            Some(SimulatedAction::CursorMoved(push)) => self.cursor = push.to,
+            // A delivered event moves the cursor all the way after all
+            Some(&SimulatedAction::CursorSlowed(to)) if consumed => self.cursor = to,
+            _ => {}
+        }
+        SimulatedMove {
+            decision: self.engine.last_decision(),
+            actions: sink.actions,
+            consumed,
+            cursor: self.cursor,
+        }
+    }
+
+    /// Registers a callback for simulated key events, like
+    /// `KeyboardHook::subscribe` does for real ones
+    pub fn subscribe_keys<F>(&mut self, callback: F) -> SubscriptionId
+    where
+        F: Fn(u32, bool) + Send + Sync + 'static,
+    {
+        self.key_subscribers.add(Arc::new(callback))
+    }
+
+    /// Returns false if `id` wasn't subscribed
+    pub fn unsubscribe_keys(&mut self, id: SubscriptionId) -> bool {
+        self.key_subscribers.remove(id)
+    }
+
+    /// Delivers a key press or release to every key subscriber
+    pub fn simulate_key(&self, vk_code: u32, is_down: bool) {
+        for callback in self.key_subscribers.snapshot() {
+            callback(vk_code, is_down);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ApproachZone, BarrierBounds, BufferZone, CoordinateOrigin, DecisionKind, EnforcementMode,
+        EntryCue, HighContrastStyle, OffscreenPush, OverlayAnimation, OverlayCoverage,
+        OverlayFailurePolicy,
+    };
+    use std::sync::Mutex;
+
+    // Barrier (100,200)-(400,350) with a 25px buffer zone
+    fn config() -> MouseBarrierConfig {
+        MouseBarrierConfig {
+            bounds: BarrierBounds::CornerAndSize {
+                x: 100,
+                y: 200,
+                width: 300,
+                height: 150,
+            },
+            buffer_zone: BufferZone::uniform(25),
+            buffer_blocks: true,
+            push_factor: 50,
+            dynamic_push: true,
+            offscreen_push: OffscreenPush::ClampToEdge,
+            screen_margin: 0,
+            approach_zone: None,
+            overlay_enabled: true,
+            overlay_color: (255, 0, 0),
+            proximity_coloring: false,
+            proximity_far_color: (0, 255, 0),
+            proximity_range: 200,
+            buffer_alpha: 128,
+            overlay_coverage: OverlayCoverage::BufferOnly,
+            overlay_failure: OverlayFailurePolicy::ContinueInvisible,
+            interior_alpha: 64,
+            flash_alpha: 200,
+            overlay_animation: OverlayAnimation::Steady,
+            overlay_tick_ms: 50,
+            on_barrier_hit_sound: None,
+            on_barrier_entry_sound: None,
+            entry_cue: EntryCue::Barrier,
+            interrupt_previous_sound: true,
+            coordinate_origin: CoordinateOrigin::TopLeft,
+            snap_grid: None,
+            consume_blocked_events: true,
+            resync_after_block: false,
+            enforcement_mode: EnforcementMode::Enforce,
+            bypass_hold_ms: 0,
+            bypass_modifier: None,
+            clamp_to_screen: false,
+            edit_mode: None,
+            high_contrast: HighContrastStyle::default(),
+        }
+    }
+
+    #[test]
+    fn test_disabled_barrier_passes_everything_through() {
+        let mut simulation = Simulation::new(&config(), 1920, 1080);
+
+        let outcome = simulation.simulate_mouse_move(200, 275);
+
+        assert_eq!(outcome.decision, None);
+        assert!(outcome.actions.is_empty());
+        assert!(!outcome.consumed);
+        assert_eq!(simulation.cursor(), Point { x: 200, y: 275 });
+    }
+
+    #[test]
+    fn test_fast_move_across_the_barrier_is_blocked() {
+        let mut simulation = Simulation::new(&config(), 1920, 1080);
+        simulation.enable();
+        simulation.simulate_mouse_move(20, 275);
+
+        // One event that would tunnel straight through
+        let outcome = simulation.simulate_mouse_move(600, 275);
+
+        assert_eq!(
+            outcome.decision.map(|decision| decision.kind),
+            Some(DecisionKind::TrajectoryBlock)
+        );
+        assert!(outcome.consumed);
+        assert!(outcome.cursor.x < 75, "stopped at {:?}", outcome.cursor);
+        assert_eq!(simulation.cursor(), outcome.cursor);
+    }
+
+    #[test]
+    fn test_warn_only_lets_the_cursor_through() {
+        let mut simulation = Simulation::new(
+            &MouseBarrierConfig {
+                enforcement_mode: EnforcementMode::WarnOnly,
+                ..config()
+            },
+            1920,
+            1080,
+        );
+        simulation.enable();
+        simulation.simulate_mouse_move(50, 275);
+
+        let outcome = simulation.simulate_mouse_move(80, 275);
+
+        assert!(outcome
+            .actions
+            .contains(&SimulatedAction::SoundRequested(SoundKind::BarrierHit)));
+        assert!(matches!(
+            outcome.actions.last(),
+            Some(SimulatedAction::WouldHavePushed(push)) if push.to.x < 75
+        ));
+        assert!(!outcome.consumed);
+        assert_eq!(outcome.cursor, Point { x: 80, y: 275 });
+
+        // Switching modes takes effect on the next move, without re-enabling
+        simulation.update_barrier(&config());
+        let outcome = simulation.simulate_mouse_move(85, 275);
+        assert!(outcome.consumed);
+        assert!(outcome.cursor.x < 75, "pushed to {:?}", outcome.cursor);
+    }
+
+    #[test]
+    fn test_entering_the_buffer_reports_zone_and_sound() {
+        let mut simulation = Simulation::new(&config(), 1920, 1080);
+        simulation.enable();
+        simulation.simulate_mouse_move(50, 275);
+
+        let outcome = simulation.simulate_mouse_move(80, 275);
+
+        assert!(outcome.actions.contains(&SimulatedAction::ZoneChanged {
+            from: Zone::Outside,
+            to: Zone::Buffer,
+        }));
+        assert!(outcome
+            .actions
+            .contains(&SimulatedAction::SoundRequested(SoundKind::BarrierHit)));
+        assert!(matches!(
+            outcome.actions.last(),
+            Some(SimulatedAction::CursorMoved(_))
+        ));
+        assert!(outcome.cursor.x < 75, "pushed to {:?}", outcome.cursor);
+    }
+
+    #[test]
+    fn test_approach_zone_slows_only_swallowed_moves() {
+        let approach = MouseBarrierConfig {
+            approach_zone: Some(ApproachZone {
+                distance: 50,
+                min_scale: 0.2,
+            }),
+            ..config()
+        };
+        let mut simulation = Simulation::new(&approach, 1920, 1080);
+        simulation.enable();
+        simulation.simulate_mouse_move(30, 275);
+
+        let outcome = simulation.simulate_mouse_move(50, 275);
+        assert_eq!(
+            outcome.decision.map(|decision| decision.kind),
+            Some(DecisionKind::ApproachSlowdown)
+        );
+        assert!(outcome.consumed);
+        assert!((30..50).contains(&outcome.cursor.x), "{:?}", outcome.cursor);
+
+        // A delivered event would move the cursor all the way anyway
+        let delivered = MouseBarrierConfig {
+            consume_blocked_events: false,
+            resync_after_block: false,
+            enforcement_mode: EnforcementMode::Enforce,
+            ..approach
+        };
+        let mut simulation = Simulation::new(&delivered, 1920, 1080);
+        simulation.enable();
+        simulation.simulate_mouse_move(30, 275);
+        let outcome = simulation.simulate_mouse_move(50, 275);
+        assert!(!outcome.consumed);
+        assert_eq!(outcome.cursor, Point { x: 50, y: 275 });
+    }
+
+    #[test]
+    fn test_pushes_are_swallowed_even_when_events_are_delivered() {
+        let delivered = MouseBarrierConfig {
+            consume_blocked_events: false,
+            ..config()
+        };
+        let mut simulation = Simulation::new(&delivered, 1920, 1080);
+        simulation.enable();
+        simulation.simulate_mouse_move(20, 275);
+
+        // Delivering this event would land the cursor inside the barrier
+        let outcome = simulation.simulate_mouse_move(600, 275);
+        assert_eq!(
+            outcome.decision.map(|decision| decision.kind),
+            Some(DecisionKind::TrajectoryBlock)
+        );
+        assert!(outcome.consumed);
+        assert!(outcome.cursor.x < 100, "{:?}", outcome.cursor);
+    }
+
+    #[test]
+    fn test_toggle_forgets_cursor_history() {
+        let mut simulation = Simulation::new(&config(), 1920, 1080);
+        simulation.enable();
+        simulation.simulate_mouse_move(20, 275);
+        assert!(!simulation.toggle());
+        assert!(simulation.toggle());
+
+        // Without a previous position there is no path to block
+        let outcome = simulation.simulate_mouse_move(600, 275);
+        assert_eq!(
+            outcome.decision.map(|decision| decision.kind),
+            Some(DecisionKind::PassThrough)
+        );
+    }
+
+    #[test]
+    fn test_off_screen_barrier_is_moved_on_screen() {
+        // Written for a 4K screen, replayed on 1080p
+        let config = MouseBarrierConfig {
+            bounds: BarrierBounds::CornerAndSize {
+                x: 3000,
+                y: 200,
+                width: 300,
+                height: 150,
+            },
+            clamp_to_screen: true,
+            ..config()
+        };
+        let mut simulation = Simulation::new(&config, 1920, 1080);
+        simulation.enable();
+        simulation.simulate_mouse_move(1500, 275);
+
+        // Now at (1620,200)-(1920,350)
+        let outcome = simulation.simulate_mouse_move(1700, 275);
+        assert!(outcome.consumed);
+        assert!(outcome.cursor.x < 1595, "pushed to {:?}", outcome.cursor);
+    }
+
+    #[test]
+    fn test_keys_reach_every_subscriber() {
+        let mut simulation = Simulation::new(&config(), 1920, 1080);
+        let keys = Arc::new(Mutex::new(Vec::new()));
+        let id = {
+            let keys = keys.clone();
+            simulation.subscribe_keys(move |vk, down| keys.lock().unwrap().push((vk, down)))
+        };
+
+        simulation.simulate_key(0x70, true);
+        simulation.simulate_key(0x70, false);
+        assert!(simulation.unsubscribe_keys(id));
+        simulation.simulate_key(0x71, true);
+
+        assert_eq!(*keys.lock().unwrap(), vec![(0x70, true), (0x70, false)]);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::BufferZone;
+use std::time::{Duration, Instant};
+
+/// Moves are totalled over this long for the pixels-per-second reading, since
+/// the gap between two hook events is too short to time on its own
+const PER_SECOND_WINDOW: Duration = Duration::from_millis(100);
+
+/// Suggested buffer zone widths are rounded up to a multiple of this
+const SUGGESTION_STEP: u32 = 5;
+
+/// How fast the cursor moved while the barrier was enabled, as the mouse hook
+/// measured it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CursorSpeed {
+    /// Pixels travelled since the previous mouse event
+    pub per_event: u32,
+    /// Pixels per second over the last tenth of a second of movement
+    pub per_second: u32,
+    /// The largest `per_event` this session
+    pub peak_per_event: u32,
+    /// The largest `per_second` this session
+    pub peak_per_second: u32,
+    /// The largest `per_event` this session among moves that reached the buffer
+    /// zone or the barrier; see [`suggested_buffer_zone`]
+    pub peak_approach_per_event: u32,
+}
+
+/// Keeps [`CursorSpeed`] up to date as the engine handles moves
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SpeedTracker {
+    speed: CursorSpeed,
+    /// When the current per-second window opened, and the pixels moved in it
+    window: Option<(Instant, f64)>,
+    /// When the last move was recorded
+    last_move: Option<Instant>,
+}
+
+impl SpeedTracker {
+    pub(crate) fn speed(&self) -> CursorSpeed {
+        self.speed
+    }
+
+    /// Records a move `distance` pixels long that ended at `now`;
+    /// `approaching` if it reached the buffer zone or the barrier.
+    pub(crate) fn record(&mut self, distance: f64, approaching: bool, now: Instant) {
+        let per_event = distance.round() as u32;
+        let speed = &mut self.speed;
+        speed.per_event = per_event;
+        speed.peak_per_event = speed.peak_per_event.max(per_event);
+        if approaching {
+            speed.peak_approach_per_event = speed.peak_approach_per_event.max(per_event);
+        }
+
+        // A cursor left still isn't moving slowly, so a pause starts a new window
+        let paused = self
+            .last_move
+            .replace(now)
+            .is_none_or(|last| now.saturating_duration_since(last) >= PER_SECOND_WINDOW);
+        let (start, moved) = match self.window {
+            Some((start, moved)) if !paused => (start, moved + distance),
+            _ => (now, 0.0),
+        };
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed >= PER_SECOND_WINDOW {
+            let per_second = (moved / elapsed.as_secs_f64()).round() as u32;
+            speed.per_second = per_second;
+            speed.peak_per_second = speed.peak_per_second.max(per_second);
+            self.window = Some((now, 0.0));
+        } else {
+            self.window = Some((start, moved));
+        }
+    }
+
+    /// Zeroes the current speed and starts timing afresh, for when the engine
+    /// stops seeing moves; the session's peaks are kept.
+    pub(crate) fn pause(&mut self) {
+        self.speed.per_event = 0;
+        self.speed.per_second = 0;
+        self.window = None;
+        self.last_move = None;
+    }
+}
+
+/// A `buffer_zone` width that would have caught the fastest approach seen, or
+/// `None` if the narrowest edge of `buffer` already does.
+///
+/// A move longer than the buffer zone is wide can jump straight across it in
+/// one event, so the barrier catches the cursor with the trajectory check
+/// rather than turning it back in the buffer zone, and the hit cue comes late.
+/// The suggestion is `peak_approach_per_event` rounded up to a multiple of
+/// 5 px.
+pub fn suggested_buffer_zone(peak_approach_per_event: u32, buffer: BufferZone) -> Option<i32> {
+    if i64::from(peak_approach_per_event) <= i64::from(buffer.narrowest()) {
+        return None;
+    }
+    let suggested = peak_approach_per_event
+        .div_ceil(SUGGESTION_STEP)
+        .saturating_mul(SUGGESTION_STEP);
+    Some(i32::try_from(suggested).unwrap_or(i32::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_keeps_the_session_peaks() {
+        let start = Instant::now();
+        let mut tracker = SpeedTracker::default();
+        tracker.record(12.4, false, start);
+        tracker.record(92.0, false, start + Duration::from_millis(1));
+        tracker.record(40.0, true, start + Duration::from_millis(2));
+        tracker.record(7.6, false, start + Duration::from_millis(3));
+
+        let speed = tracker.speed();
+        assert_eq!(speed.per_event, 8);
+        assert_eq!(speed.peak_per_event, 92);
+        // Only the move that reached the buffer zone counts as an approach
+        assert_eq!(speed.peak_approach_per_event, 40);
+
+        tracker.pause();
+        let speed = tracker.speed();
+        assert_eq!((speed.per_event, speed.per_second), (0, 0));
+        assert_eq!(speed.peak_per_event, 92);
+        assert_eq!(speed.peak_approach_per_event, 40);
+    }
+
+    #[test]
+    fn test_per_second_is_measured_over_a_window() {
+        let start = Instant::now();
+        let mut tracker = SpeedTracker::default();
+        // 10 px every 10 ms is 1000 px/s
+        for tick in 0..=10 {
+            tracker.record(10.0, false, start + Duration::from_millis(tick * 10));
+        }
+        assert_eq!(tracker.speed().per_second, 1000);
+        assert_eq!(tracker.speed().peak_per_second, 1000);
+
+        // Half as fast for the next window
+        for tick in 11..=20 {
+            tracker.record(5.0, false, start + Duration::from_millis(tick * 10));
+        }
+        assert_eq!(tracker.speed().per_second, 500);
+        assert_eq!(tracker.speed().peak_per_second, 1000);
+    }
+
+    #[test]
+    fn test_a_pause_does_not_count_as_slow_movement() {
+        let start = Instant::now();
+        let mut tracker = SpeedTracker::default();
+        tracker.record(10.0, false, start);
+        tracker.record(10.0, false, start + Duration::from_millis(50));
+
+        // Two seconds idle, then 20 px every 10 ms
+        let resumed = start + Duration::from_secs(2);
+        for tick in 0..=10 {
+            tracker.record(20.0, false, resumed + Duration::from_millis(tick * 10));
+        }
+        assert_eq!(tracker.speed().per_second, 2000);
+    }
+
+    #[test]
+    fn test_suggested_buffer_zone() {
+        let buffer = BufferZone::uniform(10);
+        assert_eq!(suggested_buffer_zone(92, buffer), Some(95));
+        assert_eq!(suggested_buffer_zone(100, buffer), Some(100));
+        assert_eq!(suggested_buffer_zone(11, buffer), Some(15));
+        // Moves no longer than the buffer zone is wide can't skip it
+        assert_eq!(suggested_buffer_zone(10, buffer), None);
+        assert_eq!(suggested_buffer_zone(0, buffer), None);
+        assert_eq!(suggested_buffer_zone(u32::MAX, buffer), Some(i32::MAX));
+    }
+
+    #[test]
+    fn test_suggested_buffer_zone_checks_the_narrowest_edge() {
+        let buffer = BufferZone {
+            top: 60,
+            right: 60,
+            bottom: 20,
+            left: 60,
+        };
+        assert_eq!(suggested_buffer_zone(40, buffer), Some(40));
+        assert_eq!(suggested_buffer_zone(20, buffer), None);
+    }
+}
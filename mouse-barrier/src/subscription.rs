@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+/// Identifies one subscription to a shared hook, for unsubscribing it later
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+/// Callbacks sharing a single underlying hook.
+///
+/// Ids are never reused, so unsubscribing twice (or with an id from before the
+/// list last emptied) can't remove someone else's callback.
+pub(crate) struct Subscribers<F: ?Sized> {
+    next_id: u64,
+    entries: Vec<(SubscriptionId, Arc<F>)>,
+}
+
+impl<F: ?Sized> Subscribers<F> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            next_id: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    pub(crate) fn add(&mut self, callback: Arc<F>) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.entries.push((id, callback));
+        id
+    }
+
+    /// Returns false if `id` wasn't subscribed
+    pub(crate) fn remove(&mut self, id: SubscriptionId) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|(entry, _)| *entry != id);
+        self.entries.len() != before
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The live callbacks in subscription order. Callers run them after
+    /// releasing the list, so a callback may itself (un)subscribe.
+    pub(crate) fn snapshot(&self) -> Vec<Arc<F>> {
+        self.entries
+            .iter()
+            .map(|(_, callback)| callback.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    type Callback = dyn Fn(u32) + Send + Sync;
+
+    fn recorder(log: &Arc<Mutex<Vec<(char, u32)>>>, name: char) -> Arc<Callback> {
+        let log = log.clone();
+        Arc::new(move |value| log.lock().unwrap().push((name, value)))
+    }
+
+    #[test]
+    fn test_fans_out_to_every_live_subscriber() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut subscribers: Subscribers<Callback> = Subscribers::new();
+        assert!(subscribers.is_empty());
+
+        let a = subscribers.add(recorder(&log, 'a'));
+        let b = subscribers.add(recorder(&log, 'b'));
+        assert_ne!(a, b);
+
+        for callback in subscribers.snapshot() {
+            callback(1);
+        }
+        assert!(subscribers.remove(a));
+        for callback in subscribers.snapshot() {
+            callback(2);
+        }
+
+        assert_eq!(*log.lock().unwrap(), vec![('a', 1), ('b', 1), ('b', 2)]);
+        assert!(subscribers.remove(b));
+        assert!(subscribers.is_empty());
+    }
+
+    #[test]
+    fn test_stale_ids_are_not_reused() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut subscribers: Subscribers<Callback> = Subscribers::new();
+
+        let old = subscribers.add(recorder(&log, 'a'));
+        assert!(subscribers.remove(old));
+        assert!(!subscribers.remove(old));
+
+        // Once emptied, new subscribers still get fresh ids
+        let new = subscribers.add(recorder(&log, 'b'));
+        assert_ne!(old, new);
+        assert!(!subscribers.remove(old));
+        assert!(!subscribers.is_empty());
+    }
+}